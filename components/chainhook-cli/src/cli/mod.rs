@@ -1,9 +1,11 @@
 use crate::config::generator::generate_config;
 use crate::config::Config;
 use crate::scan::bitcoin::scan_bitcoin_chainstate_via_rpc_using_predicate;
+use crate::scan::common::DeliveredOccurrences;
 use crate::scan::stacks::{
     consolidate_local_stacks_chainstate_using_csv, scan_stacks_chainstate_via_csv_using_predicate,
 };
+use crate::scan::synthetic::send_test_occurrence;
 use crate::service::http_api::document_predicate_api_server;
 use crate::service::Service;
 use crate::storage::{
@@ -11,12 +13,17 @@ use crate::storage::{
     open_readonly_stacks_db_conn,
 };
 
+use chainhook_sdk::bitcoin::{address::AddressType, Address};
+use chainhook_sdk::chainhooks::stacks::check_stacks_predicate_contracts;
 use chainhook_sdk::chainhooks::types::{
-    BitcoinChainhookFullSpecification, BitcoinChainhookNetworkSpecification, BitcoinPredicateType,
-    ChainhookFullSpecification, FileHook, HookAction, OrdinalOperations,
-    StacksChainhookFullSpecification, StacksChainhookNetworkSpecification, StacksPredicate,
+    get_bitcoin_network, BitcoinChainhookFullSpecification, BitcoinChainhookNetworkSpecification,
+    BitcoinPredicateType, ChainhookFullSpecification, ChainhookSpecification, ExactMatchingRule,
+    FileHook, HookAction, OrdinalOperations, OutputPredicate, StacksChainhookFullSpecification,
+    StacksChainhookNetworkSpecification, StacksContractCallBasedPredicate, StacksPredicate,
     StacksPrintEventBasedPredicate,
 };
+use chainhook_sdk::indexer::bitcoin::BitcoinBlockCache;
+use chainhook_sdk::indexer::stacks::StacksBlockCache;
 use chainhook_sdk::types::{BitcoinNetwork, BlockIdentifier, StacksNetwork};
 use chainhook_sdk::utils::Context;
 use clap::{Parser, Subcommand};
@@ -25,6 +32,10 @@ use std::collections::BTreeMap;
 use std::io::{BufReader, Read};
 use std::path::PathBuf;
 use std::process;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -64,6 +75,9 @@ enum PredicatesCommand {
     /// Check given predicate
     #[clap(name = "check", bin_name = "check")]
     Check(CheckPredicate),
+    /// Send a synthetic occurrence of given predicate to its action
+    #[clap(name = "test", bin_name = "test")]
+    Test(TestPredicate),
 }
 
 #[derive(Subcommand, PartialEq, Clone, Debug)]
@@ -114,7 +128,28 @@ struct NewPredicate {
 #[derive(Parser, PartialEq, Clone, Debug)]
 struct ScanPredicate {
     /// Chainhook spec file to scan (json format)
-    pub predicate_path: String,
+    #[clap(required_unless_present_any = &["stacks_contract_call", "bitcoin_address"])]
+    pub predicate_path: Option<String>,
+    /// Scan for calls to a contract method (<contract_identifier>::<method>) instead of a spec file
+    #[clap(
+        long = "stacks-contract-call",
+        conflicts_with = "predicate_path",
+        conflicts_with = "bitcoin_address"
+    )]
+    pub stacks_contract_call: Option<String>,
+    /// Scan for outputs paying a Bitcoin address instead of a spec file
+    #[clap(
+        long = "bitcoin-address",
+        conflicts_with = "predicate_path",
+        conflicts_with = "stacks_contract_call"
+    )]
+    pub bitcoin_address: Option<String>,
+    /// Write the heights and txids of the matched blocks to a manifest file (newline delimited json)
+    #[clap(long = "manifest-path")]
+    pub manifest_path: Option<String>,
+    /// Log the occurrences of a Bitcoin predicate instead of executing its action
+    #[clap(long = "dry-run")]
+    pub dry_run: bool,
     /// Target Testnet network
     #[clap(long = "testnet", conflicts_with = "mainnet")]
     pub testnet: bool,
@@ -147,6 +182,28 @@ struct CheckPredicate {
         conflicts_with = "testnet"
     )]
     pub config_path: Option<String>,
+    /// Check that the contracts referenced by a Stacks predicate exist on the given Stacks node
+    #[clap(long = "stacks-node-rpc-url")]
+    pub stacks_node_rpc_url: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct TestPredicate {
+    /// Chainhook spec file to test (json format)
+    pub predicate_path: String,
+    /// Target Testnet network
+    #[clap(long = "testnet", conflicts_with = "mainnet")]
+    pub testnet: bool,
+    /// Target Mainnet network
+    #[clap(long = "mainnet", conflicts_with = "testnet")]
+    pub mainnet: bool,
+    /// Load config file path
+    #[clap(
+        long = "config-path",
+        conflicts_with = "mainnet",
+        conflicts_with = "testnet"
+    )]
+    pub config_path: Option<String>,
 }
 
 #[derive(Subcommand, PartialEq, Clone, Debug)]
@@ -190,6 +247,9 @@ struct StartCommand {
     /// Specify relative path of the chainhooks (yaml format) to evaluate
     #[clap(long = "predicate-path")]
     pub predicates_paths: Vec<String>,
+    /// Load every predicate (json format) found in a directory
+    #[clap(long = "predicates-dir")]
+    pub predicates_dir: Option<String>,
     /// Start REST API for managing predicates
     #[clap(long = "start-http-api")]
     pub start_http_api: bool,
@@ -301,12 +361,24 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
                     config.monitoring.prometheus_monitoring_port = cmd.prometheus_monitoring_port;
                 }
 
-                let predicates = cmd
+                if cmd.predicates_dir.is_some() {
+                    config.predicates.predicates_dir = cmd.predicates_dir.clone();
+                }
+
+                let mut predicates = cmd
                     .predicates_paths
                     .iter()
                     .map(|p| load_predicate_from_path(p))
                     .collect::<Result<Vec<ChainhookFullSpecification>, _>>()?;
 
+                if let Some(ref predicates_dir) = config.predicates.predicates_dir {
+                    let (predicates_from_dir, errors) = load_predicates_from_dir(predicates_dir)?;
+                    for e in errors.iter() {
+                        error!(ctx.expect_logger(), "Skipping predicate: {e}");
+                    }
+                    predicates.extend(predicates_from_dir);
+                }
+
                 info!(ctx.expect_logger(), "Starting service...",);
 
                 let mut service = Service::new(config, ctx);
@@ -330,8 +402,6 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
         },
         Command::Predicates(subcmd) => match subcmd {
             PredicatesCommand::New(cmd) => {
-                use uuid::Uuid;
-
                 let id = Uuid::new_v4();
 
                 let predicate = match (cmd.stacks, cmd.bitcoin) {
@@ -345,13 +415,25 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
                             predicate: StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
                                 contract_identifier: "ST1SVA0SST0EDT4MFYGWGP6GNSXMMQJDVP1G8QTTC.arkadiko-freddie-v1-1".into(),
                                 contains: "vault".into(),
+                                emitting_method: None,
                             }),
                             expire_after_occurrence: None,
                             capture_all_events: None,
                             decode_clarity_values: None,
                             include_contract_abi: None,
+                            projection: None,
+                            include_mempool_timestamps: None,
+                            identifier_format: None,
+                            derived_fields: None,
+                            max_clarity_value_length: None,
+                            trigger_on: None,
+                            min_confirmations: None,
                             action:  HookAction::FileAppend(FileHook {
-                                path: "arkadiko.txt".into()
+                                path: "arkadiko.txt".into(),
+                                format: None,
+                                max_size_bytes: None,
+                                max_lines: None,
+                                include_raw_tx: None,
                             })
                         });
 
@@ -362,13 +444,25 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
                             predicate: StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
                                 contract_identifier: "SP2C2YFP12AJZB4MABJBAJ55XECVS7E4PMMZ89YZR.arkadiko-freddie-v1-1".into(),
                                 contains: "vault".into(),
+                                emitting_method: None,
                             }),
                             expire_after_occurrence: None,
                             capture_all_events: None,
                             decode_clarity_values: None,
                             include_contract_abi: None,
+                            projection: None,
+                            include_mempool_timestamps: None,
+                            identifier_format: None,
+                            derived_fields: None,
+                            max_clarity_value_length: None,
+                            trigger_on: None,
+                            min_confirmations: None,
                             action:  HookAction::FileAppend(FileHook {
-                                path: "arkadiko.txt".into()
+                                path: "arkadiko.txt".into(),
+                                format: None,
+                                max_size_bytes: None,
+                                max_lines: None,
+                                include_raw_tx: None,
                             })
                         });
 
@@ -387,6 +481,7 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
                             BitcoinNetwork::Mainnet,
                             BitcoinChainhookNetworkSpecification {
                                 start_block: Some(767430),
+                                start_block_offset_from_tip: None,
                                 end_block: Some(767430),
                                 blocks: None,
                                 predicate: BitcoinPredicateType::OrdinalsProtocol(
@@ -395,11 +490,19 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
                                 expire_after_occurrence: None,
                                 action: HookAction::FileAppend(FileHook {
                                     path: "ordinals.txt".into(),
+                                    format: None,
+                                    max_size_bytes: None,
+                                    max_lines: None,
+                                    include_raw_tx: None,
                                 }),
                                 include_inputs: None,
                                 include_outputs: None,
                                 include_proof: None,
                                 include_witness: None,
+                                identifier_format: None,
+                                stop_on_match: None,
+                                trigger_on: None,
+                                min_confirmations: None,
                             },
                         );
 
@@ -450,9 +553,23 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
             PredicatesCommand::Scan(cmd) => {
                 let mut config =
                     Config::default(false, cmd.testnet, cmd.mainnet, &cmd.config_path)?;
-                let predicate = load_predicate_from_path(&cmd.predicate_path)?;
+                let predicate = match cmd.predicate_path {
+                    Some(ref predicate_path) => load_predicate_from_path(predicate_path)?,
+                    None => build_predicate_from_cli_flags(
+                        &cmd.stacks_contract_call,
+                        &cmd.bitcoin_address,
+                        &config,
+                    )?,
+                };
                 match predicate {
                     ChainhookFullSpecification::Bitcoin(predicate) => {
+                        predicate.validate().map_err(|errors| {
+                            format!(
+                                "Invalid predicate {}: {}",
+                                predicate.uuid,
+                                errors.join(", ")
+                            )
+                        })?;
                         let predicate_spec = match predicate
                             .into_selected_network_specification(&config.network.bitcoin_network)
                         {
@@ -465,14 +582,39 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
                             }
                         };
 
+                        let block_cache = BitcoinBlockCache::new(
+                            config.limits.max_number_of_cached_bitcoin_blocks,
+                        );
+                        let deliveries = DeliveredOccurrences::new(
+                            config.limits.max_number_of_tracked_deliveries,
+                        );
+                        // Interrupting the scan lets it checkpoint the blocks already delivered.
+                        let cancellation = Arc::new(AtomicBool::new(false));
+                        let moved_cancellation = cancellation.clone();
+                        if let Err(e) = ctrlc::set_handler(move || {
+                            moved_cancellation.store(true, Ordering::SeqCst);
+                        }) {
+                            warn!(
+                                ctx.expect_logger(),
+                                "Unable to handle interruptions of the scan: {e}"
+                            );
+                        }
                         scan_bitcoin_chainstate_via_rpc_using_predicate(
                             &predicate_spec,
                             None,
+                            cmd.manifest_path.as_deref(),
+                            cmd.dry_run,
+                            &block_cache,
+                            &deliveries,
+                            Some(&cancellation),
                             &config,
                             &ctx,
                         )
                         .await?;
                     }
+                    ChainhookFullSpecification::Stacks(_) if cmd.dry_run => {
+                        return Err("--dry-run is only supported for Bitcoin predicates".into());
+                    }
                     ChainhookFullSpecification::Stacks(predicate) => {
                         let predicate_spec = match predicate
                             .into_selected_network_specification(&config.network.stacks_network)
@@ -487,9 +629,13 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
                         };
                         // TODO: if a stacks.rocksdb is present, use it.
                         // TODO: update Stacks archive file if required.
+                        let block_cache =
+                            StacksBlockCache::new(config.limits.max_number_of_cached_stacks_blocks);
                         scan_stacks_chainstate_via_csv_using_predicate(
                             &predicate_spec,
+                            cmd.manifest_path.as_deref(),
                             &mut config,
+                            &block_cache,
                             &ctx,
                         )
                         .await?;
@@ -501,9 +647,9 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
                 let predicate: ChainhookFullSpecification =
                     load_predicate_from_path(&cmd.predicate_path)?;
 
-                match predicate {
+                let description = match predicate {
                     ChainhookFullSpecification::Bitcoin(predicate) => {
-                        let _ = match predicate
+                        let predicate = match predicate
                             .into_selected_network_specification(&config.network.bitcoin_network)
                         {
                             Ok(predicate) => predicate,
@@ -514,9 +660,10 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
                                 ));
                             }
                         };
+                        predicate.predicate.describe()
                     }
                     ChainhookFullSpecification::Stacks(predicate) => {
-                        let _ = match predicate
+                        let predicate = match predicate
                             .into_selected_network_specification(&config.network.stacks_network)
                         {
                             Ok(predicate) => predicate,
@@ -527,9 +674,58 @@ async fn handle_command(opts: Opts, ctx: Context) -> Result<(), String> {
                                 ));
                             }
                         };
+                        if let Some(ref stacks_node_rpc_url) = cmd.stacks_node_rpc_url {
+                            check_stacks_predicate_contracts(
+                                &predicate.predicate,
+                                stacks_node_rpc_url,
+                            )
+                            .await?;
+                        }
+                        predicate.predicate.describe()
                     }
-                }
-                println!("✔️ Predicate {} successfully checked", cmd.predicate_path);
+                };
+                println!(
+                    "✔️ Predicate {} successfully checked: {}",
+                    cmd.predicate_path, description
+                );
+            }
+            PredicatesCommand::Test(cmd) => {
+                let config = Config::default(false, cmd.testnet, cmd.mainnet, &cmd.config_path)?;
+                let predicate: ChainhookFullSpecification =
+                    load_predicate_from_path(&cmd.predicate_path)?;
+                let predicate = match predicate {
+                    ChainhookFullSpecification::Bitcoin(predicate) => {
+                        match predicate
+                            .into_selected_network_specification(&config.network.bitcoin_network)
+                        {
+                            Ok(predicate) => ChainhookSpecification::Bitcoin(predicate),
+                            Err(e) => {
+                                return Err(format!(
+                                    "Specification missing for network {:?}: {e}",
+                                    config.network.bitcoin_network
+                                ));
+                            }
+                        }
+                    }
+                    ChainhookFullSpecification::Stacks(predicate) => {
+                        match predicate
+                            .into_selected_network_specification(&config.network.stacks_network)
+                        {
+                            Ok(predicate) => ChainhookSpecification::Stacks(predicate),
+                            Err(e) => {
+                                return Err(format!(
+                                    "Specification missing for network {:?}: {e}",
+                                    config.network.stacks_network
+                                ));
+                            }
+                        }
+                    }
+                };
+                send_test_occurrence(&predicate, &config, &ctx).await?;
+                println!(
+                    "✔️ Synthetic occurrence of predicate {} delivered",
+                    cmd.predicate_path
+                );
             }
         },
         Command::Stacks(subcmd) => match subcmd {
@@ -631,3 +827,188 @@ pub fn load_predicate_from_path(
         .map_err(|e| format!("unable to parse json file {}\n{:?}", predicate_path, e))?;
     Ok(predicate)
 }
+
+/// Parses every `.json` file found in `predicates_dir`, in file name order. A file that can't
+/// be parsed doesn't prevent the others from loading: its error is returned alongside the
+/// predicates successfully loaded.
+pub fn load_predicates_from_dir(
+    predicates_dir: &str,
+) -> Result<(Vec<ChainhookFullSpecification>, Vec<String>), String> {
+    let mut predicates_paths = list_predicates_paths_in_dir(predicates_dir)?;
+    predicates_paths.sort();
+    let mut predicates = vec![];
+    let mut errors = vec![];
+    for predicate_path in predicates_paths.iter() {
+        match load_predicate_from_path(&predicate_path.display().to_string()) {
+            Ok(predicate) => predicates.push(predicate),
+            Err(e) => errors.push(e),
+        }
+    }
+    Ok((predicates, errors))
+}
+
+pub fn list_predicates_paths_in_dir(predicates_dir: &str) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(predicates_dir)
+        .map_err(|e| format!("unable to read directory {}\n{:?}", predicates_dir, e))?;
+    let predicates_paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .map_or(false, |extension| extension == "json")
+        })
+        .collect();
+    Ok(predicates_paths)
+}
+
+pub const INLINE_PREDICATE_OUTPUT_PATH: &str = "occurrences.json";
+
+fn build_predicate_from_cli_flags(
+    stacks_contract_call: &Option<String>,
+    bitcoin_address: &Option<String>,
+    config: &Config,
+) -> Result<ChainhookFullSpecification, String> {
+    match (stacks_contract_call, bitcoin_address) {
+        (Some(contract_call), None) => {
+            build_stacks_contract_call_predicate(contract_call, &config.network.stacks_network)
+        }
+        (None, Some(address)) => {
+            build_bitcoin_address_predicate(address, &config.network.bitcoin_network)
+        }
+        _ => Err("command `predicates scan` should either provide a predicate path, or one of the flags --stacks-contract-call or --bitcoin-address".into()),
+    }
+}
+
+pub fn build_stacks_contract_call_predicate(
+    contract_call: &str,
+    network: &StacksNetwork,
+) -> Result<ChainhookFullSpecification, String> {
+    let (contract_identifier, method) = contract_call
+        .split_once("::")
+        .filter(|(contract_identifier, method)| {
+            contract_identifier.contains('.') && !method.is_empty()
+        })
+        .ok_or(format!(
+            "unable to parse contract call {}: expected <contract_identifier>::<method>",
+            contract_call
+        ))?;
+
+    let mut networks = BTreeMap::new();
+    networks.insert(
+        network.clone(),
+        StacksChainhookNetworkSpecification {
+            start_block: None,
+            end_block: None,
+            blocks: None,
+            predicate: StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+                contract_identifier: contract_identifier.into(),
+                method: method.to_string(),
+                case_insensitive: None,
+                args: None,
+            }),
+            expire_after_occurrence: None,
+            capture_all_events: None,
+            decode_clarity_values: None,
+            include_contract_abi: None,
+            projection: None,
+            include_mempool_timestamps: None,
+            identifier_format: None,
+            derived_fields: None,
+            max_clarity_value_length: None,
+            trigger_on: None,
+            min_confirmations: None,
+            action: HookAction::FileAppend(FileHook {
+                path: INLINE_PREDICATE_OUTPUT_PATH.into(),
+                format: None,
+                max_size_bytes: None,
+                max_lines: None,
+                include_raw_tx: None,
+            }),
+        },
+    );
+
+    Ok(ChainhookFullSpecification::Stacks(
+        StacksChainhookFullSpecification {
+            uuid: Uuid::new_v4().to_string(),
+            owner_uuid: None,
+            name: contract_call.to_string(),
+            version: 1,
+            networks,
+        },
+    ))
+}
+
+pub fn build_bitcoin_address_predicate(
+    address: &str,
+    network: &BitcoinNetwork,
+) -> Result<ChainhookFullSpecification, String> {
+    let address_type = Address::from_str(address)
+        .map_err(|e| {
+            format!(
+                "unable to parse bitcoin address {}: {}",
+                address,
+                e.to_string()
+            )
+        })?
+        .require_network(get_bitcoin_network(network))
+        .map_err(|e| {
+            format!(
+                "bitcoin address {} is not a {:?} address: {}",
+                address,
+                network,
+                e.to_string()
+            )
+        })?
+        .address_type();
+    let rule = ExactMatchingRule::Equals(address.to_string());
+    let output_predicate = match address_type {
+        Some(AddressType::P2pkh) => OutputPredicate::P2pkh(rule),
+        Some(AddressType::P2sh) => OutputPredicate::P2sh(rule),
+        Some(AddressType::P2wpkh) => OutputPredicate::P2wpkh(rule),
+        Some(AddressType::P2wsh) => OutputPredicate::P2wsh(rule),
+        Some(AddressType::P2tr) => OutputPredicate::P2tr(rule),
+        _ => return Err(format!("bitcoin address {} type not supported", address)),
+    };
+
+    let mut networks = BTreeMap::new();
+    networks.insert(
+        network.clone(),
+        BitcoinChainhookNetworkSpecification {
+            start_block: None,
+            start_block_offset_from_tip: None,
+            end_block: None,
+            blocks: None,
+            predicate: BitcoinPredicateType::Outputs(output_predicate),
+            expire_after_occurrence: None,
+            action: HookAction::FileAppend(FileHook {
+                path: INLINE_PREDICATE_OUTPUT_PATH.into(),
+                format: None,
+                max_size_bytes: None,
+                max_lines: None,
+                include_raw_tx: None,
+            }),
+            include_inputs: None,
+            include_outputs: None,
+            include_proof: None,
+            include_witness: None,
+            identifier_format: None,
+            stop_on_match: None,
+            trigger_on: None,
+            min_confirmations: None,
+        },
+    );
+
+    Ok(ChainhookFullSpecification::Bitcoin(
+        BitcoinChainhookFullSpecification {
+            uuid: Uuid::new_v4().to_string(),
+            owner_uuid: None,
+            name: address.to_string(),
+            version: 1,
+            networks,
+        },
+    ))
+}
+
+#[cfg(test)]
+pub mod tests;
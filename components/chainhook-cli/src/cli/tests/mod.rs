@@ -0,0 +1,206 @@
+use chainhook_sdk::chainhooks::stacks::check_stacks_predicate_contracts;
+use chainhook_sdk::chainhooks::types::{
+    BitcoinPredicateType, ChainhookFullSpecification, ExactMatchingRule, FileHook, HookAction,
+    OutputPredicate, StacksContractCallBasedPredicate, StacksPredicate,
+};
+use chainhook_sdk::types::{BitcoinNetwork, StacksNetwork};
+use clap::Parser;
+use std::time::Duration;
+use test_case::test_case;
+
+use crate::service::tests::helpers::build_predicates::{
+    build_bitcoin_payload, build_stacks_payload,
+};
+use crate::service::tests::helpers::get_free_port;
+use crate::service::tests::helpers::mock_stacks_node::create_tmp_working_dir;
+use crate::service::tests::helpers::mock_stacks_node::{
+    mock_stacks_node_contracts, MOCK_CONTRACT_IDENTIFIER,
+};
+
+use super::{
+    build_bitcoin_address_predicate, build_stacks_contract_call_predicate,
+    load_predicates_from_dir, Command, Opts, PredicatesCommand, INLINE_PREDICATE_OUTPUT_PATH,
+};
+
+#[test]
+fn it_builds_stacks_contract_call_predicate_from_flags() {
+    let opts = Opts::try_parse_from([
+        "chainhook",
+        "predicates",
+        "scan",
+        "--stacks-contract-call",
+        "SP000000000000000000002Q6VF78.pox::stack-stx",
+        "--mainnet",
+    ])
+    .unwrap();
+    let cmd = match opts.command {
+        Command::Predicates(PredicatesCommand::Scan(cmd)) => cmd,
+        _ => panic!("expected predicates scan command"),
+    };
+    assert_eq!(cmd.predicate_path, None);
+
+    let spec = build_stacks_contract_call_predicate(
+        &cmd.stacks_contract_call.unwrap(),
+        &StacksNetwork::Mainnet,
+    )
+    .unwrap();
+    spec.validate().unwrap();
+    let spec = match spec {
+        ChainhookFullSpecification::Stacks(spec) => spec,
+        _ => panic!("expected a stacks specification"),
+    };
+    let network_spec = spec.networks.get(&StacksNetwork::Mainnet).unwrap();
+    assert_eq!(
+        network_spec.predicate,
+        StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+            contract_identifier: "SP000000000000000000002Q6VF78.pox".into(),
+            method: "stack-stx".into(),
+            case_insensitive: None,
+            args: None,
+        })
+    );
+    assert_eq!(
+        network_spec.action,
+        HookAction::FileAppend(FileHook {
+            path: INLINE_PREDICATE_OUTPUT_PATH.into(),
+            format: None,
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx: None,
+        })
+    );
+}
+
+#[test_case("SP000000000000000000002Q6VF78.pox" ; "missing method")]
+#[test_case("SP000000000000000000002Q6VF78.pox::" ; "empty method")]
+#[test_case("SP000000000000000000002Q6VF78::stack-stx" ; "missing contract name")]
+fn it_rejects_malformed_stacks_contract_call_flag(contract_call: &str) {
+    assert!(build_stacks_contract_call_predicate(contract_call, &StacksNetwork::Mainnet).is_err());
+}
+
+#[test_case("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", OutputPredicate::P2pkh(ExactMatchingRule::Equals("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".into())) ; "p2pkh")]
+#[test_case("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", OutputPredicate::P2sh(ExactMatchingRule::Equals("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy".into())) ; "p2sh")]
+#[test_case("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", OutputPredicate::P2wpkh(ExactMatchingRule::Equals("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".into())) ; "p2wpkh")]
+#[test_case("bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3", OutputPredicate::P2wsh(ExactMatchingRule::Equals("bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3".into())) ; "p2wsh")]
+#[test_case("bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr", OutputPredicate::P2tr(ExactMatchingRule::Equals("bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr".into())) ; "p2tr")]
+fn it_builds_bitcoin_address_predicate_from_flags(address: &str, expected: OutputPredicate) {
+    let opts = Opts::try_parse_from([
+        "chainhook",
+        "predicates",
+        "scan",
+        "--bitcoin-address",
+        address,
+        "--mainnet",
+    ])
+    .unwrap();
+    let cmd = match opts.command {
+        Command::Predicates(PredicatesCommand::Scan(cmd)) => cmd,
+        _ => panic!("expected predicates scan command"),
+    };
+
+    let spec =
+        build_bitcoin_address_predicate(&cmd.bitcoin_address.unwrap(), &BitcoinNetwork::Mainnet)
+            .unwrap();
+    let spec = match spec {
+        ChainhookFullSpecification::Bitcoin(spec) => spec,
+        _ => panic!("expected a bitcoin specification"),
+    };
+    let network_spec = spec.networks.get(&BitcoinNetwork::Mainnet).unwrap();
+    assert_eq!(
+        network_spec.predicate,
+        BitcoinPredicateType::Outputs(expected)
+    );
+}
+
+#[test_case(BitcoinNetwork::Mainnet, true ; "mainnet address on mainnet")]
+#[test_case(BitcoinNetwork::Testnet, false ; "mainnet address on testnet")]
+fn it_checks_the_network_of_bitcoin_address_flag(network: BitcoinNetwork, expected_ok: bool) {
+    let spec = build_bitcoin_address_predicate("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", &network);
+    assert_eq!(spec.is_ok(), expected_ok);
+}
+
+#[test]
+fn it_requires_a_predicate_path_or_an_inline_predicate() {
+    assert!(Opts::try_parse_from(["chainhook", "predicates", "scan", "--mainnet"]).is_err());
+    assert!(Opts::try_parse_from([
+        "chainhook",
+        "predicates",
+        "scan",
+        "./predicate.json",
+        "--bitcoin-address",
+        "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+        "--mainnet",
+    ])
+    .is_err());
+}
+
+#[test_case(MOCK_CONTRACT_IDENTIFIER, "stack-stx", true ; "existing contract and public method")]
+#[test_case(MOCK_CONTRACT_IDENTIFIER, "stack-sxt", false ; "misspelled method")]
+#[test_case(MOCK_CONTRACT_IDENTIFIER, "get-pox-info", false ; "read only method")]
+#[test_case("SP000000000000000000002Q6VF78.pxo", "stack-stx", false ; "misspelled contract")]
+#[tokio::test]
+async fn it_checks_that_referenced_contracts_exist(
+    contract_identifier: &str,
+    method: &str,
+    exists: bool,
+) {
+    let port = get_free_port().unwrap();
+    let _ = hiro_system_kit::thread_named("Stacks node service")
+        .spawn(move || {
+            let future = mock_stacks_node_contracts(port);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let predicate = StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: contract_identifier.into(),
+        method: method.into(),
+        case_insensitive: None,
+        args: None,
+    });
+    let stacks_node_rpc_url = format!("http://localhost:{port}");
+    let res = check_stacks_predicate_contracts(&predicate, &stacks_node_rpc_url).await;
+    assert_eq!(res.is_ok(), exists, "{:?}", res);
+}
+
+#[test]
+fn it_loads_the_valid_predicates_of_a_directory() {
+    let (working_dir, _) = create_tmp_working_dir().unwrap();
+    let bitcoin_predicate = build_bitcoin_payload(None, None, None, None, Some("bitcoin-uuid"));
+    let stacks_predicate = build_stacks_payload(None, None, None, None, Some("stacks-uuid"));
+    std::fs::write(
+        format!("{working_dir}/1-bitcoin.json"),
+        bitcoin_predicate.to_string(),
+    )
+    .unwrap();
+    std::fs::write(
+        format!("{working_dir}/2-stacks.json"),
+        stacks_predicate.to_string(),
+    )
+    .unwrap();
+    std::fs::write(
+        format!("{working_dir}/3-invalid.json"),
+        "{\"chain\": \"bitcoin\"",
+    )
+    .unwrap();
+    std::fs::write(format!("{working_dir}/README.md"), "not a predicate").unwrap();
+
+    let res = load_predicates_from_dir(&working_dir);
+    std::fs::remove_dir_all(&working_dir).unwrap();
+    let (predicates, errors) = res.unwrap();
+
+    let uuids = predicates
+        .iter()
+        .map(|predicate| predicate.get_uuid())
+        .collect::<Vec<_>>();
+    assert_eq!(uuids, vec!["bitcoin-uuid", "stacks-uuid"]);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("3-invalid.json"), "{}", errors[0]);
+}
+
+#[test]
+fn it_requires_an_existing_predicates_directory() {
+    let res = load_predicates_from_dir("./missing-predicates-dir");
+    assert!(res.is_err());
+}
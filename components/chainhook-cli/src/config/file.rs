@@ -1,3 +1,4 @@
+use chainhook_sdk::chainhooks::types::DuplicateUuidPolicy;
 use chainhook_sdk::types::BitcoinNetwork;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -8,11 +9,13 @@ pub struct ConfigFile {
     pub limits: LimitsConfigFile,
     pub network: NetworkConfigFile,
     pub monitoring: Option<MonitoringConfigFile>,
+    pub predicates: Option<PredicatesConfigFile>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct StorageConfigFile {
     pub working_dir: Option<String>,
+    pub scan_checkpoints_dir: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -21,6 +24,8 @@ pub struct PredicatesApiConfigFile {
     pub database_uri: Option<String>,
     pub display_logs: Option<bool>,
     pub disabled: Option<bool>,
+    pub auth_token: Option<String>,
+    pub stacks_node_rpc_url: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -42,6 +47,12 @@ pub struct LimitsConfigFile {
     pub max_number_of_processing_threads: Option<usize>,
     pub max_number_of_networking_threads: Option<usize>,
     pub max_caching_memory_size_mb: Option<usize>,
+    pub max_number_of_cached_stacks_blocks: Option<usize>,
+    pub max_number_of_cached_bitcoin_blocks: Option<usize>,
+    pub max_number_of_buffered_occurrences: Option<usize>,
+    pub max_number_of_tracked_deliveries: Option<usize>,
+    pub max_number_of_concurrent_deliveries: Option<usize>,
+    pub block_evaluation_timeout_ms: Option<u64>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -53,6 +64,7 @@ pub struct NetworkConfigFile {
     pub bitcoind_zmq_url: Option<String>,
     pub stacks_node_rpc_url: Option<String>,
     pub stacks_events_ingestion_port: Option<u16>,
+    pub bitcoin_max_reorg_depth: Option<usize>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -86,4 +98,11 @@ impl NetworkConfigMode {
 #[derive(Deserialize, Debug, Clone)]
 pub struct MonitoringConfigFile {
     pub prometheus_monitoring_port: Option<u16>,
+    pub scan_metrics_port: Option<u16>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PredicatesConfigFile {
+    pub duplicate_uuid_policy: Option<DuplicateUuidPolicy>,
+    pub predicates_dir: Option<String>,
 }
@@ -7,6 +7,8 @@ pub fn generate_config(network: &BitcoinNetwork) -> String {
     let conf = format!(
         r#"[storage]
 working_dir = "cache"
+# Directory where Bitcoin scans checkpoint their progress, to resume from it when restarted.
+# scan_checkpoints_dir = "cache/checkpoints"
 
 # The HTTP API allows you to register / deregister
 # predicates dynamically.
@@ -15,6 +17,10 @@ working_dir = "cache"
 # [http_api]
 # http_port = 20456
 # database_uri = "redis://localhost:6379/"
+# Optional token required as `Authorization: Bearer <auth_token>` on predicates routes.
+# auth_token = "<secret>"
+# Optional Stacks node used to check that the contracts referenced by registered Stacks predicates exist.
+# stacks_node_rpc_url = "http://localhost:20443"
 
 [network]
 mode = "{mode}"
@@ -32,6 +38,10 @@ stacks_node_rpc_url = "http://localhost:20443"
 # To achieve this, comment out the `stacks_node_rpc_url` line and uncomment the following line:
 # bitcoind_zmq_url = "tcp://0.0.0.0:18543"
 
+# Number of most recent Bitcoin blocks kept in memory to handle reorgs.
+# Reorgs deeper than this are reported as errors.
+# bitcoin_max_reorg_depth = 5
+
 [limits]
 max_number_of_bitcoin_predicates = 100
 max_number_of_concurrent_bitcoin_scans = 100
@@ -46,10 +56,20 @@ max_caching_memory_size_mb = 32000
 [[event_source]]
 tsv_file_url = "https://archive.hiro.so/{network}/stacks-blockchain-api/{network}-stacks-blockchain-api-latest"
 
+# Predicates loaded with the uuid of an already loaded predicate are rejected by default.
+# Set to "suffix" to load them under the first available `<uuid>-<n>` uuid instead.
+# [predicates]
+# duplicate_uuid_policy = "reject"
+# Directory whose `.json` predicates are loaded on startup, and watched for changes
+# when built with the `predicates_watcher` feature.
+# predicates_dir = "./predicates"
+
 # Enables a server that provides metrics that can be scraped by Prometheus.
 # This is disabled by default.
 # [monitoring]
 # prometheus_monitoring_port = 20457
+# Port of the server exposing the progress of Bitcoin scans (requires the `metrics` feature).
+# scan_metrics_port = 20458
 "#,
         mode = mode.as_str(),
         network = network.to_lowercase(),
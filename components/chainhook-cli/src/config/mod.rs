@@ -1,8 +1,12 @@
 pub mod file;
 pub mod generator;
 
+use chainhook_sdk::chainhooks::types::DuplicateUuidPolicy;
+use chainhook_sdk::indexer::bitcoin::DEFAULT_BITCOIN_BLOCK_CACHE_CAPACITY;
+pub use chainhook_sdk::indexer::fork_scratch_pad::DEFAULT_MAX_REORG_DEPTH;
+use chainhook_sdk::indexer::stacks::DEFAULT_STACKS_BLOCK_CACHE_CAPACITY;
 pub use chainhook_sdk::indexer::IndexerConfig;
-use chainhook_sdk::observer::EventObserverConfig;
+use chainhook_sdk::observer::{EventObserverConfig, DEFAULT_DISPATCH_BUFFER_CAPACITY};
 use chainhook_sdk::types::{
     BitcoinBlockSignaling, BitcoinNetwork, StacksNetwork, StacksNodeConfig,
 };
@@ -23,6 +27,7 @@ pub const STACKS_SCAN_THREAD_POOL_SIZE: usize = 10;
 pub const BITCOIN_SCAN_THREAD_POOL_SIZE: usize = 10;
 pub const STACKS_MAX_PREDICATE_REGISTRATION: usize = 50;
 pub const BITCOIN_MAX_PREDICATE_REGISTRATION: usize = 50;
+pub const DEFAULT_MAX_CONCURRENT_DELIVERIES: usize = 1;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Config {
@@ -32,11 +37,15 @@ pub struct Config {
     pub limits: LimitsConfig,
     pub network: IndexerConfig,
     pub monitoring: MonitoringConfig,
+    pub predicates: PredicatesConfig,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct StorageConfig {
     pub working_dir: String,
+    /// Directory where Bitcoin scans checkpoint the last block they fully processed, in order
+    /// to resume from it when restarted. Scans are not checkpointed by default.
+    pub scan_checkpoints_dir: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -50,6 +59,10 @@ pub struct PredicatesApiConfig {
     pub http_port: u16,
     pub database_uri: String,
     pub display_logs: bool,
+    pub auth_token: Option<String>,
+    /// Stacks node the contracts referenced by the registered Stacks predicates are looked
+    /// up on, if any.
+    pub stacks_node_rpc_url: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -79,11 +92,34 @@ pub struct LimitsConfig {
     pub max_number_of_processing_threads: usize,
     pub max_number_of_networking_threads: usize,
     pub max_caching_memory_size_mb: usize,
+    pub max_number_of_cached_stacks_blocks: usize,
+    pub max_number_of_cached_bitcoin_blocks: usize,
+    pub max_number_of_buffered_occurrences: usize,
+    /// Number of delivered transactions remembered by the Bitcoin scans of the process, so
+    /// that they are not delivered twice. 0, the default, disables the deduplication.
+    pub max_number_of_tracked_deliveries: usize,
+    /// Number of predicates whose HTTP occurrences are delivered concurrently by the Bitcoin
+    /// scans, the occurrences of a predicate being delivered in order. 1 by default.
+    pub max_number_of_concurrent_deliveries: usize,
+    /// Time given to the predicates of a scan to evaluate a block, blocks taking longer
+    /// being skipped. No timeout by default.
+    pub block_evaluation_timeout_ms: Option<u64>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct MonitoringConfig {
     pub prometheus_monitoring_port: Option<u16>,
+    /// Port of the server exposing the progress of Bitcoin scans to Prometheus.
+    /// Only served when built with the `metrics` feature.
+    pub scan_metrics_port: Option<u16>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PredicatesConfig {
+    pub duplicate_uuid_policy: DuplicateUuidPolicy,
+    /// Directory whose `.json` predicates are loaded when the service starts. When built with
+    /// the `predicates_watcher` feature, changes to the directory are applied while running.
+    pub predicates_dir: Option<String>,
 }
 impl Config {
     pub fn from_file_path(file_path: &str) -> Result<Config, String> {
@@ -126,6 +162,8 @@ impl Config {
             stacks_network: self.network.stacks_network.clone(),
             data_handler_tx: None,
             prometheus_monitoring_port: self.monitoring.prometheus_monitoring_port,
+            bitcoin_max_reorg_depth: self.network.bitcoin_max_reorg_depth,
+            dispatch_buffer_capacity: self.limits.max_number_of_buffered_occurrences,
         }
     }
 
@@ -150,14 +188,19 @@ impl Config {
                 continue;
             }
         }
-        let prometheus_monitoring_port = if let Some(monitoring) = config_file.monitoring {
-            monitoring.prometheus_monitoring_port
-        } else {
-            None
-        };
+        let (prometheus_monitoring_port, scan_metrics_port) =
+            if let Some(monitoring) = config_file.monitoring {
+                (
+                    monitoring.prometheus_monitoring_port,
+                    monitoring.scan_metrics_port,
+                )
+            } else {
+                (None, None)
+            };
         let config = Config {
             storage: StorageConfig {
                 working_dir: config_file.storage.working_dir.unwrap_or("cache".into()),
+                scan_checkpoints_dir: config_file.storage.scan_checkpoints_dir,
             },
             http_api: match config_file.http_api {
                 None => PredicatesApi::Off,
@@ -169,6 +212,8 @@ impl Config {
                         database_uri: http_api
                             .database_uri
                             .unwrap_or(DEFAULT_REDIS_URI.to_string()),
+                        auth_token: http_api.auth_token,
+                        stacks_node_rpc_url: http_api.stacks_node_rpc_url,
                     }),
                 },
             },
@@ -202,6 +247,27 @@ impl Config {
                     .limits
                     .max_caching_memory_size_mb
                     .unwrap_or(2048),
+                max_number_of_cached_stacks_blocks: config_file
+                    .limits
+                    .max_number_of_cached_stacks_blocks
+                    .unwrap_or(DEFAULT_STACKS_BLOCK_CACHE_CAPACITY),
+                max_number_of_cached_bitcoin_blocks: config_file
+                    .limits
+                    .max_number_of_cached_bitcoin_blocks
+                    .unwrap_or(DEFAULT_BITCOIN_BLOCK_CACHE_CAPACITY),
+                max_number_of_buffered_occurrences: config_file
+                    .limits
+                    .max_number_of_buffered_occurrences
+                    .unwrap_or(DEFAULT_DISPATCH_BUFFER_CAPACITY),
+                max_number_of_tracked_deliveries: config_file
+                    .limits
+                    .max_number_of_tracked_deliveries
+                    .unwrap_or(0),
+                max_number_of_concurrent_deliveries: config_file
+                    .limits
+                    .max_number_of_concurrent_deliveries
+                    .unwrap_or(DEFAULT_MAX_CONCURRENT_DELIVERIES),
+                block_evaluation_timeout_ms: config_file.limits.block_evaluation_timeout_ms,
             },
             network: IndexerConfig {
                 bitcoind_rpc_url: config_file.network.bitcoind_rpc_url.to_string(),
@@ -218,9 +284,24 @@ impl Config {
                 },
                 stacks_network,
                 bitcoin_network,
+                bitcoin_max_reorg_depth: config_file
+                    .network
+                    .bitcoin_max_reorg_depth
+                    .unwrap_or(DEFAULT_MAX_REORG_DEPTH),
             },
             monitoring: MonitoringConfig {
                 prometheus_monitoring_port,
+                scan_metrics_port,
+            },
+            predicates: PredicatesConfig {
+                duplicate_uuid_policy: config_file
+                    .predicates
+                    .as_ref()
+                    .and_then(|predicates| predicates.duplicate_uuid_policy.clone())
+                    .unwrap_or_default(),
+                predicates_dir: config_file
+                    .predicates
+                    .and_then(|predicates| predicates.predicates_dir),
             },
         };
         Ok(config)
@@ -331,6 +412,7 @@ impl Config {
         Config {
             storage: StorageConfig {
                 working_dir: default_cache_path(),
+                scan_checkpoints_dir: None,
             },
             http_api: PredicatesApi::Off,
             event_sources: vec![],
@@ -342,6 +424,12 @@ impl Config {
                 max_number_of_processing_threads: 1.max(num_cpus::get().saturating_sub(1)),
                 max_number_of_networking_threads: 1.max(num_cpus::get().saturating_sub(1)),
                 max_caching_memory_size_mb: 2048,
+                max_number_of_cached_stacks_blocks: DEFAULT_STACKS_BLOCK_CACHE_CAPACITY,
+                max_number_of_cached_bitcoin_blocks: DEFAULT_BITCOIN_BLOCK_CACHE_CAPACITY,
+                max_number_of_buffered_occurrences: DEFAULT_DISPATCH_BUFFER_CAPACITY,
+                max_number_of_tracked_deliveries: 0,
+                max_number_of_concurrent_deliveries: DEFAULT_MAX_CONCURRENT_DELIVERIES,
+                block_evaluation_timeout_ms: None,
             },
             network: IndexerConfig {
                 bitcoind_rpc_url: "http://0.0.0.0:18443".into(),
@@ -352,9 +440,15 @@ impl Config {
                 ),
                 stacks_network: StacksNetwork::Devnet,
                 bitcoin_network: BitcoinNetwork::Regtest,
+                bitcoin_max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
             },
             monitoring: MonitoringConfig {
                 prometheus_monitoring_port: None,
+                scan_metrics_port: None,
+            },
+            predicates: PredicatesConfig {
+                duplicate_uuid_policy: DuplicateUuidPolicy::default(),
+                predicates_dir: None,
             },
         }
     }
@@ -363,6 +457,7 @@ impl Config {
         Config {
             storage: StorageConfig {
                 working_dir: default_cache_path(),
+                scan_checkpoints_dir: None,
             },
             http_api: PredicatesApi::Off,
             event_sources: vec![EventSourceConfig::StacksTsvUrl(UrlConfig {
@@ -376,6 +471,12 @@ impl Config {
                 max_number_of_processing_threads: 1.max(num_cpus::get().saturating_sub(1)),
                 max_number_of_networking_threads: 1.max(num_cpus::get().saturating_sub(1)),
                 max_caching_memory_size_mb: 2048,
+                max_number_of_cached_stacks_blocks: DEFAULT_STACKS_BLOCK_CACHE_CAPACITY,
+                max_number_of_cached_bitcoin_blocks: DEFAULT_BITCOIN_BLOCK_CACHE_CAPACITY,
+                max_number_of_buffered_occurrences: DEFAULT_DISPATCH_BUFFER_CAPACITY,
+                max_number_of_tracked_deliveries: 0,
+                max_number_of_concurrent_deliveries: DEFAULT_MAX_CONCURRENT_DELIVERIES,
+                block_evaluation_timeout_ms: None,
             },
             network: IndexerConfig {
                 bitcoind_rpc_url: "http://0.0.0.0:18332".into(),
@@ -386,9 +487,15 @@ impl Config {
                 ),
                 stacks_network: StacksNetwork::Testnet,
                 bitcoin_network: BitcoinNetwork::Testnet,
+                bitcoin_max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
             },
             monitoring: MonitoringConfig {
                 prometheus_monitoring_port: None,
+                scan_metrics_port: None,
+            },
+            predicates: PredicatesConfig {
+                duplicate_uuid_policy: DuplicateUuidPolicy::default(),
+                predicates_dir: None,
             },
         }
     }
@@ -397,6 +504,7 @@ impl Config {
         Config {
             storage: StorageConfig {
                 working_dir: default_cache_path(),
+                scan_checkpoints_dir: None,
             },
             http_api: PredicatesApi::Off,
             event_sources: vec![EventSourceConfig::StacksTsvUrl(UrlConfig {
@@ -410,6 +518,12 @@ impl Config {
                 max_number_of_processing_threads: 1.max(num_cpus::get().saturating_sub(1)),
                 max_number_of_networking_threads: 1.max(num_cpus::get().saturating_sub(1)),
                 max_caching_memory_size_mb: 2048,
+                max_number_of_cached_stacks_blocks: DEFAULT_STACKS_BLOCK_CACHE_CAPACITY,
+                max_number_of_cached_bitcoin_blocks: DEFAULT_BITCOIN_BLOCK_CACHE_CAPACITY,
+                max_number_of_buffered_occurrences: DEFAULT_DISPATCH_BUFFER_CAPACITY,
+                max_number_of_tracked_deliveries: 0,
+                max_number_of_concurrent_deliveries: DEFAULT_MAX_CONCURRENT_DELIVERIES,
+                block_evaluation_timeout_ms: None,
             },
             network: IndexerConfig {
                 bitcoind_rpc_url: "http://0.0.0.0:8332".into(),
@@ -420,9 +534,15 @@ impl Config {
                 ),
                 stacks_network: StacksNetwork::Mainnet,
                 bitcoin_network: BitcoinNetwork::Mainnet,
+                bitcoin_max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
             },
             monitoring: MonitoringConfig {
                 prometheus_monitoring_port: None,
+                scan_metrics_port: None,
+            },
+            predicates: PredicatesConfig {
+                duplicate_uuid_policy: DuplicateUuidPolicy::default(),
+                predicates_dir: None,
             },
         }
     }
@@ -40,9 +40,11 @@ fn config_from_file_allows_setting_disabled_fields() {
         database_uri: Some(format!("")),
         display_logs: Some(false),
         disabled: Some(false),
+        auth_token: None,
     });
     generated_config_file.monitoring = Some(MonitoringConfigFile {
         prometheus_monitoring_port: Some(20457),
+        scan_metrics_port: Some(20458),
     });
     let generated_config = Config::from_config_file(generated_config_file).unwrap();
     assert!(generated_config.is_http_api_enabled());
@@ -50,6 +52,7 @@ fn config_from_file_allows_setting_disabled_fields() {
         generated_config.monitoring.prometheus_monitoring_port,
         Some(20457)
     );
+    assert_eq!(generated_config.monitoring.scan_metrics_port, Some(20458));
 }
 
 #[test]
@@ -80,6 +83,8 @@ fn is_http_api_enabled_handles_both_modes() {
         http_port: 0,
         database_uri: format!(""),
         display_logs: false,
+        auth_token: None,
+        stacks_node_rpc_url: None,
     });
     assert!(config.is_http_api_enabled());
 }
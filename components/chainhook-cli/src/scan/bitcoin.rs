@@ -1,5 +1,10 @@
 use crate::config::{Config, PredicatesApi};
-use crate::scan::common::get_block_heights_to_scan;
+use crate::scan::common::{
+    evaluate_block_with_timeout, get_block_heights_to_scan, DeliveredOccurrences, DeliveryKey,
+    KafkaTopicWriter, RedisStreamWriter, ScanCheckpoint, ScanError, ScanManifest, ScanSummary,
+};
+#[cfg(feature = "metrics")]
+use crate::scan::metrics::{scan_metrics, start_serving_scan_metrics};
 use crate::service::{
     open_readwrite_predicates_db_conn_or_panic, set_confirmed_expiration_status,
     set_predicate_scanning_status, set_unconfirmed_expiration_status, ScanningData,
@@ -8,27 +13,96 @@ use chainhook_sdk::bitcoincore_rpc::RpcApi;
 use chainhook_sdk::bitcoincore_rpc::{Auth, Client};
 use chainhook_sdk::chainhooks::bitcoin::{
     evaluate_bitcoin_chainhooks_on_chain_event, handle_bitcoin_hook_action,
-    BitcoinChainhookOccurrence, BitcoinTriggerChainhook,
+    serialize_bitcoin_payload_to_json, BitcoinChainhookOccurrence, BitcoinTriggerChainhook,
 };
-use chainhook_sdk::chainhooks::types::BitcoinChainhookSpecification;
+use chainhook_sdk::chainhooks::types::{BitcoinChainhookSpecification, HookAction, HttpHook};
 use chainhook_sdk::indexer;
 use chainhook_sdk::indexer::bitcoin::{
     build_http_client, download_and_parse_block_with_retry, retrieve_block_hash_with_retry,
+    BitcoinBlockCache,
 };
 use chainhook_sdk::indexer::fork_scratch_pad::CONFIRMED_SEGMENT_MINIMUM_LENGTH;
 use chainhook_sdk::observer::{gather_proofs, EventObserverConfig};
 use chainhook_sdk::types::{
-    BitcoinBlockData, BitcoinChainEvent, BitcoinChainUpdatedWithBlocksData, BlockIdentifier, Chain,
+    BitcoinBlockData, BitcoinChainEvent, BitcoinChainUpdatedWithBlocksData, BitcoinTransactionData,
+    BlockIdentifier, Chain,
+};
+use chainhook_sdk::utils::{
+    file_append, send_http_occurrences_batch, send_request_with_backoff, unix_socket_write,
+    zstd_file_append, Context, HttpOccurrenceBatches,
 };
-use chainhook_sdk::utils::{file_append, send_request, Context};
-use std::collections::HashMap;
+use futures_util::stream::{self, StreamExt};
+use reqwest::{Client as HttpClient, RequestBuilder};
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Returns the block the scan of `predicate_spec` starts from, a `start_block_offset_from_tip`
+/// being resolved against `chain_tip`, the chain tip when the scan starts: an offset of N scans
+/// the last N blocks, the chain tip included.
+pub fn resolve_start_block(
+    predicate_spec: &BitcoinChainhookSpecification,
+    chain_tip: u64,
+) -> Result<Option<u64>, String> {
+    let Some(offset) = predicate_spec.start_block_offset_from_tip else {
+        return Ok(predicate_spec.start_block);
+    };
+    if predicate_spec.start_block.is_some() || predicate_spec.blocks.is_some() {
+        return Err(
+            "Chainhook specification field `start_block_offset_from_tip` can not be combined with `start_block` or `blocks`"
+                .into(),
+        );
+    }
+    let start_block = (chain_tip + 1).saturating_sub(offset);
+    if let Some(end_block) = predicate_spec.end_block {
+        if end_block < start_block {
+            return Err(format!(
+                "Chainhook specification field `end_block` ({}) should be greater than the start block resolved from `start_block_offset_from_tip` ({})",
+                end_block, start_block
+            ));
+        }
+    }
+    Ok(Some(start_block))
+}
+
+/// Identifies the range of blocks targeted by `predicate_spec`, as declared by its specification,
+/// so that its scan checkpoint is not reused once the range changes.
+pub fn get_scan_range_key(predicate_spec: &BitcoinChainhookSpecification) -> String {
+    if let Some(ref blocks) = predicate_spec.blocks {
+        let first = blocks.iter().min().cloned().unwrap_or_default();
+        let last = blocks.iter().max().cloned().unwrap_or_default();
+        return format!("blocks-{first}-{last}-{}", blocks.len());
+    }
+    let start = match (
+        predicate_spec.start_block,
+        predicate_spec.start_block_offset_from_tip,
+    ) {
+        (_, Some(offset)) => format!("tip~{offset}"),
+        (Some(start_block), None) => start_block.to_string(),
+        (None, None) => "0".to_string(),
+    };
+    let end = match predicate_spec.end_block {
+        Some(end_block) => end_block.to_string(),
+        None => "tip".to_string(),
+    };
+    format!("{start}-{end}")
+}
 
+/// Scans the Bitcoin chainstate for `predicate_spec`. Raising `cancellation` stops the scan
+/// once the actions of the block being processed are delivered, and returns the partial summary.
 pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
     predicate_spec: &BitcoinChainhookSpecification,
     unfinished_scan_data: Option<ScanningData>,
+    manifest_path: Option<&str>,
+    dry_run: bool,
+    block_cache: &BitcoinBlockCache,
+    deliveries: &DeliveredOccurrences,
+    cancellation: Option<&AtomicBool>,
     config: &Config,
     ctx: &Context,
-) -> Result<bool, String> {
+) -> Result<ScanSummary, ScanError> {
     let auth = Auth::UserPass(
         config.network.bitcoind_rpc_username.clone(),
         config.network.bitcoind_rpc_password.clone(),
@@ -37,32 +111,67 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
     let bitcoin_rpc = match Client::new(&config.network.bitcoind_rpc_url, auth) {
         Ok(con) => con,
         Err(message) => {
-            return Err(format!("Bitcoin RPC error: {}", message.to_string()));
+            return Err(ScanError::Configuration(format!(
+                "Bitcoin RPC error: {}",
+                message.to_string()
+            )));
         }
     };
 
     let mut chain_tip = match bitcoin_rpc.get_blockchain_info() {
         Ok(result) => result.blocks,
         Err(e) => {
-            return Err(format!(
-                "unable to retrieve Bitcoin chain tip ({})",
-                e.to_string()
+            return Err(ScanError::from_rpc_error(
+                "unable to retrieve Bitcoin chain tip",
+                e,
             ));
         }
     };
 
+    // Blocks without enough confirmations are not scanned.
+    let min_confirmations = predicate_spec.min_confirmations.unwrap_or(0);
     let block_heights_to_scan = get_block_heights_to_scan(
         &predicate_spec.blocks,
-        &predicate_spec.start_block,
+        &resolve_start_block(predicate_spec, chain_tip).map_err(ScanError::InvalidSpecification)?,
         &predicate_spec.end_block,
-        &chain_tip,
+        &chain_tip.saturating_sub(min_confirmations),
         &unfinished_scan_data,
-    )?;
+    )
+    .map_err(ScanError::InvalidSpecification)?;
     let mut block_heights_to_scan = match block_heights_to_scan {
         Some(h) => h,
         // no blocks to scan, go straight to streaming
-        None => return Ok(false),
+        None => return Ok(ScanSummary::default()),
     };
+    // Dry runs don't deliver anything, and are not checkpointed. The scans of predicates
+    // registered through the predicates API resume from their scanning status instead.
+    let checkpoint = match (&config.storage.scan_checkpoints_dir, &config.http_api) {
+        (Some(dir), PredicatesApi::Off) if !dry_run => Some(
+            ScanCheckpoint::new(
+                dir,
+                &predicate_spec.uuid,
+                &get_scan_range_key(predicate_spec),
+            )
+            .map_err(ScanError::Io)?,
+        ),
+        _ => None,
+    };
+    if let Some(ref checkpoint) = checkpoint {
+        if let Some(checkpoint_height) = checkpoint.load().map_err(ScanError::Io)? {
+            info!(
+                ctx.expect_logger(),
+                "Resuming scan of predicate {} after checkpointed block #{checkpoint_height}",
+                predicate_spec.uuid
+            );
+            block_heights_to_scan.retain(|block_height| *block_height > checkpoint_height);
+        }
+    }
+    let start_block = block_heights_to_scan.front().cloned().unwrap_or_default();
+
+    #[cfg(feature = "metrics")]
+    if let Some(port) = config.monitoring.scan_metrics_port {
+        start_serving_scan_metrics(port, ctx);
+    }
 
     let mut predicates_db_conn = match config.http_api {
         PredicatesApi::On(ref api_config) => {
@@ -77,11 +186,12 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
     );
 
     let mut last_block_scanned = BlockIdentifier::default();
-    let mut actions_triggered = 0;
+    let mut actions_triggered: u64 = 0;
     let mut err_count = 0;
+    // Once a delivery failed, the checkpoint stays behind the failed block.
+    let mut delivery_failed = false;
 
     let event_observer_config = config.get_event_observer_config();
-    let bitcoin_config = event_observer_config.get_bitcoin_config();
 
     let (mut number_of_blocks_to_scan, mut number_of_blocks_scanned, mut number_of_times_triggered) = {
         let number_of_blocks_to_scan = block_heights_to_scan.len() as u64;
@@ -96,24 +206,51 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
     };
     let mut last_scanned_block_confirmations = 0;
     let http_client = build_http_client();
+    let mut batches = HttpOccurrenceBatches::new();
+    let mut manifest = match manifest_path {
+        Some(path) => Some(ScanManifest::create(path).map_err(ScanError::Io)?),
+        None => None,
+    };
+    let mut redis_writer = RedisStreamWriter::new();
+    let mut kafka_writer = KafkaTopicWriter::new();
+    let evaluation_timeout = config
+        .limits
+        .block_evaluation_timeout_ms
+        .map(Duration::from_millis);
+    let max_concurrent_deliveries = config.limits.max_number_of_concurrent_deliveries;
+    let stop_on_match = predicate_spec.stop_on_match.unwrap_or(false);
+    let predicates = [Arc::new(predicate_spec.clone())];
+    let mut matched_block_height = None;
 
     while let Some(current_block_height) = block_heights_to_scan.pop_front() {
-        if current_block_height > chain_tip {
+        if let Some(cancellation) = cancellation {
+            if cancellation.load(Ordering::SeqCst) {
+                info!(
+                    ctx.expect_logger(),
+                    "Scan of predicate {} cancelled before block #{current_block_height}",
+                    predicate_spec.uuid
+                );
+                block_heights_to_scan.push_front(current_block_height);
+                break;
+            }
+        }
+
+        if current_block_height + min_confirmations > chain_tip {
             let prev_chain_tip = chain_tip;
             // we've scanned up to the chain tip as of the start of this scan
             // so see if the chain has progressed since then
             chain_tip = match bitcoin_rpc.get_blockchain_info() {
                 Ok(result) => result.blocks,
                 Err(e) => {
-                    return Err(format!(
-                        "unable to retrieve Bitcoin chain tip ({})",
-                        e.to_string()
+                    return Err(ScanError::from_rpc_error(
+                        "unable to retrieve Bitcoin chain tip",
+                        e,
                     ));
                 }
             };
             // if the chain hasn't progressed, break out so we can enter streaming mode
             // and put back the block we weren't able to scan
-            if current_block_height > chain_tip {
+            if current_block_height + min_confirmations > chain_tip {
                 block_heights_to_scan.push_front(current_block_height);
                 break;
             } else {
@@ -124,62 +261,92 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
 
         number_of_blocks_scanned += 1;
 
-        let block_hash = retrieve_block_hash_with_retry(
+        let block = match fetch_bitcoin_block_at_height(
+            current_block_height,
+            predicate_spec.action.includes_raw_tx(),
+            block_cache,
             &http_client,
-            &current_block_height,
-            &bitcoin_config,
+            &event_observer_config,
             ctx,
         )
-        .await?;
-        let block_breakdown =
-            download_and_parse_block_with_retry(&http_client, &block_hash, &bitcoin_config, ctx)
-                .await?;
-        last_scanned_block_confirmations = block_breakdown.confirmations;
-        let block = match indexer::bitcoin::standardize_bitcoin_block(
-            block_breakdown,
-            &event_observer_config.bitcoin_network,
-            ctx,
-        ) {
-            Ok(data) => data,
-            Err((e, _)) => {
-                warn!(
-                    ctx.expect_logger(),
-                    "Unable to standardize block #{} {}: {}", current_block_height, block_hash, e
-                );
-                continue;
+        .await?
+        {
+            Some((block, confirmations)) => {
+                last_scanned_block_confirmations = confirmations;
+                block
             }
+            None => continue,
         };
         last_block_scanned = block.block_identifier.clone();
 
         let res = match process_block_with_predicates(
             block,
-            &vec![&predicate_spec],
+            &predicates,
+            evaluation_timeout,
+            &mut batches,
+            &mut manifest,
+            &mut redis_writer,
+            &mut kafka_writer,
+            deliveries,
+            dry_run,
+            max_concurrent_deliveries,
             &event_observer_config,
             ctx,
         )
         .await
         {
             Ok(actions) => {
+                err_count = 0;
                 if actions > 0 {
                     number_of_times_triggered += 1;
+                    if stop_on_match {
+                        matched_block_height = Some(current_block_height);
+                    }
                 }
-                actions_triggered += actions;
+                actions_triggered += actions as u64;
+                #[cfg(feature = "metrics")]
+                scan_metrics().record_block(
+                    &predicate_spec.uuid,
+                    current_block_height,
+                    actions as u64,
+                );
                 Ok(())
             }
             Err(e) => {
                 err_count += 1;
+                delivery_failed = true;
+                #[cfg(feature = "metrics")]
+                scan_metrics().record_block(&predicate_spec.uuid, current_block_height, 0);
                 Err(e)
             }
         };
 
         if err_count >= 3 {
             if res.is_err() {
-                return Err(format!(
+                return Err(ScanError::Action(format!(
                     "Scan aborted (consecutive action errors >= 3): {}",
                     res.unwrap_err()
-                ));
+                )));
             } else {
-                return Err(format!("Scan aborted (consecutive action errors >= 3)"));
+                return Err(ScanError::Action(format!(
+                    "Scan aborted (consecutive action errors >= 3)"
+                )));
+            }
+        }
+
+        for (http, occurrences) in batches.poll(Instant::now()).into_iter() {
+            send_batched_http_occurrences(&http, occurrences, deliveries, ctx)
+                .await
+                .map_err(ScanError::Action)?;
+        }
+
+        // The checkpoint only moves past a block once its occurrences were delivered: never
+        // past a block whose delivery failed, nor while a batch of occurrences is pending.
+        if let Some(ref checkpoint) = checkpoint {
+            if !delivery_failed && batches.is_empty() {
+                checkpoint
+                    .save(current_block_height)
+                    .map_err(ScanError::Io)?;
             }
         }
 
@@ -196,6 +363,31 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
                 );
             }
         }
+
+        if let Some(block_height) = matched_block_height {
+            info!(
+                ctx.expect_logger(),
+                "Predicate {} triggered by block #{block_height}, stopping scan",
+                predicate_spec.uuid
+            );
+            break;
+        }
+    }
+
+    let pending_batches = batches.flush();
+    if !pending_batches.is_empty() {
+        for (http, occurrences) in pending_batches.into_iter() {
+            send_batched_http_occurrences(&http, occurrences, deliveries, ctx)
+                .await
+                .map_err(ScanError::Action)?;
+        }
+        if let Some(ref checkpoint) = checkpoint {
+            if !delivery_failed && last_block_scanned.index > 0 {
+                checkpoint
+                    .save(last_block_scanned.index)
+                    .map_err(ScanError::Io)?;
+            }
+        }
     }
 
     info!(
@@ -214,12 +406,28 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
             ctx,
         );
     }
+    // A completed scan has nothing left to resume.
+    if let Some(ref checkpoint) = checkpoint {
+        if !delivery_failed && (matched_block_height.is_some() || block_heights_to_scan.is_empty())
+        {
+            checkpoint.delete().map_err(ScanError::Io)?;
+        }
+    }
     // if an end block was provided, or a fixed number of blocks were set to be scanned,
     // check to see if we've processed all of the blocks and can expire the predicate.
-    if (predicate_spec.blocks.is_some()
-        || (predicate_spec.end_block.is_some()
-            && predicate_spec.end_block.unwrap() == last_block_scanned.index))
-        && block_heights_to_scan.is_empty()
+    // Scans stopped on a match are complete as well.
+    let mut summary = ScanSummary {
+        blocks_scanned: number_of_blocks_scanned,
+        actions_triggered,
+        start_block,
+        end_block: last_block_scanned.index,
+        predicate_is_expired: false,
+    };
+    if matched_block_height.is_some()
+        || ((predicate_spec.blocks.is_some()
+            || (predicate_spec.end_block.is_some()
+                && predicate_spec.end_block.unwrap() == last_block_scanned.index))
+            && block_heights_to_scan.is_empty())
     {
         if let Some(ref mut predicates_db_conn) = predicates_db_conn {
             set_unconfirmed_expiration_status(
@@ -234,59 +442,896 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
                 set_confirmed_expiration_status(&predicate_spec.key(), predicates_db_conn, ctx);
             }
         }
-        return Ok(true);
+        summary.predicate_is_expired = true;
+    }
+
+    Ok(summary)
+}
+
+/// Progress of a predicate within a scan of several predicates.
+struct PredicateScanProgress {
+    block_heights: BTreeSet<u64>,
+    /// Set when some of the blocks targeted by the predicate are past the chain tip.
+    incomplete: bool,
+    checkpoint: Option<ScanCheckpoint>,
+    summary: ScanSummary,
+    number_of_times_triggered: u64,
+    last_scanned_block_confirmations: i32,
+    consecutive_errors: u32,
+    delivery_failed: bool,
+    matched: bool,
+    cancelled: bool,
+    error: Option<ScanError>,
+}
+
+impl PredicateScanProgress {
+    fn is_scanning(&self) -> bool {
+        !self.matched && !self.cancelled && self.error.is_none()
+    }
+}
+
+/// Scans the Bitcoin chainstate for several predicates in a single pass: every block in the
+/// union of their ranges is fetched once, then evaluated against the predicates targeting it.
+/// Returns the outcome of each scan, in the order the predicates were given. A predicate whose
+/// action keeps failing is dropped from the pass, the others keep being scanned.
+pub async fn scan_bitcoin_chainstate_via_rpc_using_predicates(
+    predicate_specs: &[(&BitcoinChainhookSpecification, Option<&AtomicBool>)],
+    dry_run: bool,
+    block_cache: &BitcoinBlockCache,
+    deliveries: &DeliveredOccurrences,
+    config: &Config,
+    ctx: &Context,
+) -> Result<Vec<Result<ScanSummary, ScanError>>, ScanError> {
+    let auth = Auth::UserPass(
+        config.network.bitcoind_rpc_username.clone(),
+        config.network.bitcoind_rpc_password.clone(),
+    );
+
+    let bitcoin_rpc = match Client::new(&config.network.bitcoind_rpc_url, auth) {
+        Ok(con) => con,
+        Err(message) => {
+            return Err(ScanError::Configuration(format!(
+                "Bitcoin RPC error: {}",
+                message.to_string()
+            )));
+        }
+    };
+
+    let chain_tip = match bitcoin_rpc.get_blockchain_info() {
+        Ok(result) => result.blocks,
+        Err(e) => {
+            return Err(ScanError::from_rpc_error(
+                "unable to retrieve Bitcoin chain tip",
+                e,
+            ));
+        }
+    };
+
+    let mut scans = vec![];
+    let mut block_heights_to_scan = BTreeSet::new();
+    for (predicate_spec, _) in predicate_specs.iter() {
+        let mut scan = PredicateScanProgress {
+            block_heights: BTreeSet::new(),
+            incomplete: false,
+            checkpoint: None,
+            summary: ScanSummary::default(),
+            number_of_times_triggered: 0,
+            last_scanned_block_confirmations: 0,
+            consecutive_errors: 0,
+            delivery_failed: false,
+            matched: false,
+            cancelled: false,
+            error: None,
+        };
+        // Blocks without enough confirmations are not scanned.
+        let scannable_tip = chain_tip.saturating_sub(predicate_spec.min_confirmations.unwrap_or(0));
+        let block_heights =
+            resolve_start_block(predicate_spec, chain_tip).and_then(|start_block| {
+                get_block_heights_to_scan(
+                    &predicate_spec.blocks,
+                    &start_block,
+                    &predicate_spec.end_block,
+                    &scannable_tip,
+                    &None,
+                )
+            });
+        match block_heights {
+            Ok(block_heights) => {
+                for block_height in block_heights.unwrap_or_default().into_iter() {
+                    if block_height <= scannable_tip {
+                        scan.block_heights.insert(block_height);
+                    } else {
+                        scan.incomplete = true;
+                    }
+                }
+            }
+            Err(e) => scan.error = Some(ScanError::InvalidSpecification(e)),
+        }
+        // Same checkpoints as single predicate scans.
+        if let (Some(dir), PredicatesApi::Off, None) = (
+            &config.storage.scan_checkpoints_dir,
+            &config.http_api,
+            &scan.error,
+        ) {
+            if !dry_run {
+                let checkpoint = ScanCheckpoint::new(
+                    dir,
+                    &predicate_spec.uuid,
+                    &get_scan_range_key(predicate_spec),
+                )
+                .map_err(ScanError::Io)?;
+                if let Some(checkpoint_height) = checkpoint.load().map_err(ScanError::Io)? {
+                    info!(
+                        ctx.expect_logger(),
+                        "Resuming scan of predicate {} after checkpointed block #{checkpoint_height}",
+                        predicate_spec.uuid
+                    );
+                    scan.block_heights
+                        .retain(|block_height| *block_height > checkpoint_height);
+                }
+                scan.checkpoint = Some(checkpoint);
+            }
+        }
+        scan.summary.start_block = scan.block_heights.first().cloned().unwrap_or_default();
+        block_heights_to_scan.extend(scan.block_heights.iter().cloned());
+        scans.push(scan);
+    }
+
+    #[cfg(feature = "metrics")]
+    if let Some(port) = config.monitoring.scan_metrics_port {
+        start_serving_scan_metrics(port, ctx);
+    }
+
+    let mut predicates_db_conn = match config.http_api {
+        PredicatesApi::On(ref api_config) => {
+            Some(open_readwrite_predicates_db_conn_or_panic(api_config, ctx))
+        }
+        PredicatesApi::Off => None,
+    };
+
+    info!(
+        ctx.expect_logger(),
+        "Starting evaluation of {} predicates on {} Bitcoin blocks",
+        predicate_specs.len(),
+        block_heights_to_scan.len()
+    );
+
+    let event_observer_config = config.get_event_observer_config();
+    let http_client = build_http_client();
+    let mut batches = HttpOccurrenceBatches::new();
+    let mut redis_writer = RedisStreamWriter::new();
+    let mut kafka_writer = KafkaTopicWriter::new();
+    let evaluation_timeout = config
+        .limits
+        .block_evaluation_timeout_ms
+        .map(Duration::from_millis);
+    let max_concurrent_deliveries = config.limits.max_number_of_concurrent_deliveries;
+    let predicates = predicate_specs
+        .iter()
+        .map(|(predicate_spec, _)| Arc::new((*predicate_spec).clone()))
+        .collect::<Vec<_>>();
+
+    for current_block_height in block_heights_to_scan.into_iter() {
+        for (scan, (predicate_spec, cancellation)) in scans.iter_mut().zip(predicate_specs.iter()) {
+            if scan.is_scanning() && cancellation.is_some_and(|c| c.load(Ordering::SeqCst)) {
+                info!(
+                    ctx.expect_logger(),
+                    "Scan of predicate {} cancelled before block #{current_block_height}",
+                    predicate_spec.uuid
+                );
+                scan.cancelled = true;
+            }
+        }
+        let targeting_predicates = (0..predicate_specs.len())
+            .filter(|i| {
+                scans[*i].is_scanning() && scans[*i].block_heights.contains(&current_block_height)
+            })
+            .collect::<Vec<_>>();
+        if targeting_predicates.is_empty() {
+            continue;
+        }
+        for i in targeting_predicates.iter() {
+            scans[*i].summary.blocks_scanned += 1;
+            scans[*i].summary.end_block = current_block_height;
+        }
+        let include_raw_tx = targeting_predicates
+            .iter()
+            .any(|i| predicate_specs[*i].0.action.includes_raw_tx());
+
+        let Some((block, confirmations)) = fetch_bitcoin_block_at_height(
+            current_block_height,
+            include_raw_tx,
+            block_cache,
+            &http_client,
+            &event_observer_config,
+            ctx,
+        )
+        .await?
+        else {
+            continue;
+        };
+
+        for i in targeting_predicates.iter().cloned() {
+            let predicate_spec = predicate_specs[i].0;
+            let scan = &mut scans[i];
+            scan.last_scanned_block_confirmations = confirmations;
+            match process_block_with_predicates(
+                block.clone(),
+                std::slice::from_ref(&predicates[i]),
+                evaluation_timeout,
+                &mut batches,
+                &mut None,
+                &mut redis_writer,
+                &mut kafka_writer,
+                deliveries,
+                dry_run,
+                max_concurrent_deliveries,
+                &event_observer_config,
+                ctx,
+            )
+            .await
+            {
+                Ok(actions) => {
+                    scan.consecutive_errors = 0;
+                    scan.summary.actions_triggered += actions as u64;
+                    #[cfg(feature = "metrics")]
+                    scan_metrics().record_block(
+                        &predicate_spec.uuid,
+                        current_block_height,
+                        actions as u64,
+                    );
+                    if actions > 0 {
+                        scan.number_of_times_triggered += 1;
+                        if predicate_spec.stop_on_match.unwrap_or(false) {
+                            info!(
+                                ctx.expect_logger(),
+                                "Predicate {} triggered by block #{current_block_height}, stopping its scan",
+                                predicate_spec.uuid
+                            );
+                            scan.matched = true;
+                        }
+                    }
+                }
+                Err(e) => {
+                    scan.consecutive_errors += 1;
+                    scan.delivery_failed = true;
+                    #[cfg(feature = "metrics")]
+                    scan_metrics().record_block(&predicate_spec.uuid, current_block_height, 0);
+                    if scan.consecutive_errors >= 3 {
+                        error!(
+                            ctx.expect_logger(),
+                            "Scan of predicate {} aborted (consecutive action errors >= 3): {e}",
+                            predicate_spec.uuid
+                        );
+                        scan.error = Some(ScanError::Action(format!(
+                            "Scan aborted (consecutive action errors >= 3): {e}"
+                        )));
+                    }
+                }
+            }
+            if let Some(ref mut predicates_db_conn) = predicates_db_conn {
+                let blocks_scanned = scan.summary.blocks_scanned;
+                if blocks_scanned % 10 == 0 || blocks_scanned == 1 {
+                    set_predicate_scanning_status(
+                        &predicate_spec.key(),
+                        scan.block_heights.len() as u64,
+                        blocks_scanned,
+                        scan.number_of_times_triggered,
+                        current_block_height,
+                        predicates_db_conn,
+                        ctx,
+                    );
+                }
+            }
+        }
+
+        for (http, occurrences) in batches.poll(Instant::now()).into_iter() {
+            send_batched_http_occurrences(&http, occurrences, deliveries, ctx)
+                .await
+                .map_err(ScanError::Action)?;
+        }
+
+        // The checkpoints only move past a block once its occurrences were delivered.
+        if batches.is_empty() {
+            for i in targeting_predicates.into_iter() {
+                let scan = &scans[i];
+                if let Some(ref checkpoint) = scan.checkpoint {
+                    if !scan.delivery_failed {
+                        checkpoint
+                            .save(current_block_height)
+                            .map_err(ScanError::Io)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let pending_batches = batches.flush();
+    if !pending_batches.is_empty() {
+        for (http, occurrences) in pending_batches.into_iter() {
+            send_batched_http_occurrences(&http, occurrences, deliveries, ctx)
+                .await
+                .map_err(ScanError::Action)?;
+        }
+        for scan in scans.iter() {
+            if let Some(ref checkpoint) = scan.checkpoint {
+                if !scan.delivery_failed && scan.summary.end_block > 0 {
+                    checkpoint
+                        .save(scan.summary.end_block)
+                        .map_err(ScanError::Io)?;
+                }
+            }
+        }
+    }
+
+    let mut outcomes = vec![];
+    for (scan, (predicate_spec, _)) in scans.into_iter().zip(predicate_specs.iter()) {
+        if let Some(error) = scan.error {
+            outcomes.push(Err(error));
+            continue;
+        }
+        let mut summary = scan.summary;
+        info!(
+            ctx.expect_logger(),
+            "Predicate {}: {} blocks scanned, {} actions triggered",
+            predicate_spec.uuid,
+            summary.blocks_scanned,
+            summary.actions_triggered
+        );
+        if let Some(ref mut predicates_db_conn) = predicates_db_conn {
+            set_predicate_scanning_status(
+                &predicate_spec.key(),
+                scan.block_heights.len() as u64,
+                summary.blocks_scanned,
+                scan.number_of_times_triggered,
+                summary.end_block,
+                predicates_db_conn,
+                ctx,
+            );
+        }
+        // Same expiration rules as single predicate scans.
+        let fully_scanned = !scan.cancelled && !scan.incomplete;
+        if let Some(ref checkpoint) = scan.checkpoint {
+            if !scan.delivery_failed && (scan.matched || fully_scanned) {
+                checkpoint.delete().map_err(ScanError::Io)?;
+            }
+        }
+        if scan.matched
+            || (fully_scanned
+                && (predicate_spec.blocks.is_some()
+                    || predicate_spec.end_block == Some(summary.end_block)))
+        {
+            if let Some(ref mut predicates_db_conn) = predicates_db_conn {
+                set_unconfirmed_expiration_status(
+                    &Chain::Bitcoin,
+                    summary.blocks_scanned,
+                    summary.end_block,
+                    &predicate_spec.key(),
+                    predicates_db_conn,
+                    ctx,
+                );
+                if scan.last_scanned_block_confirmations >= CONFIRMED_SEGMENT_MINIMUM_LENGTH {
+                    set_confirmed_expiration_status(&predicate_spec.key(), predicates_db_conn, ctx);
+                }
+            }
+            summary.predicate_is_expired = true;
+        }
+        outcomes.push(Ok(summary));
     }
 
-    return Ok(false);
+    Ok(outcomes)
+}
+
+/// Retrieves the block at `block_height`, from `block_cache` when possible, along with its
+/// number of confirmations. Returns `None` when the block could not be standardized.
+/// Blocks are only cached without their raw transactions: blocks fetched with `include_raw_tx`
+/// are always downloaded.
+async fn fetch_bitcoin_block_at_height(
+    block_height: u64,
+    include_raw_tx: bool,
+    block_cache: &BitcoinBlockCache,
+    http_client: &HttpClient,
+    event_observer_config: &EventObserverConfig,
+    ctx: &Context,
+) -> Result<Option<(BitcoinBlockData, i32)>, ScanError> {
+    // Only blocks deep enough to be safe from reorgs are cached.
+    if !include_raw_tx {
+        if let Some(block) = block_cache.get(block_height) {
+            return Ok(Some((
+                block.as_ref().clone(),
+                CONFIRMED_SEGMENT_MINIMUM_LENGTH,
+            )));
+        }
+    }
+    let bitcoin_config = event_observer_config.get_bitcoin_config();
+    let block_hash =
+        retrieve_block_hash_with_retry(http_client, &block_height, &bitcoin_config, ctx).await?;
+    let block_breakdown =
+        download_and_parse_block_with_retry(http_client, &block_hash, &bitcoin_config, ctx).await?;
+    let confirmations = block_breakdown.confirmations;
+    let block = match indexer::bitcoin::standardize_bitcoin_block(
+        block_breakdown,
+        &event_observer_config.bitcoin_network,
+        include_raw_tx,
+        ctx,
+    ) {
+        Ok(data) => data,
+        Err((e, _)) => {
+            warn!(
+                ctx.expect_logger(),
+                "Unable to standardize block #{} {}: {}", block_height, block_hash, e
+            );
+            return Ok(None);
+        }
+    };
+    if confirmations >= CONFIRMED_SEGMENT_MINIMUM_LENGTH && !include_raw_tx {
+        block_cache.insert(block_height, block.clone());
+    }
+    Ok(Some((block, confirmations)))
 }
 
 pub async fn process_block_with_predicates(
     block: BitcoinBlockData,
-    predicates: &Vec<&BitcoinChainhookSpecification>,
+    predicates: &[Arc<BitcoinChainhookSpecification>],
+    evaluation_timeout: Option<Duration>,
+    batches: &mut HttpOccurrenceBatches<BatchedHttpOccurrence>,
+    manifest: &mut Option<ScanManifest>,
+    redis_writer: &mut RedisStreamWriter,
+    kafka_writer: &mut KafkaTopicWriter,
+    deliveries: &DeliveredOccurrences,
+    dry_run: bool,
+    max_concurrent_deliveries: usize,
     event_observer_config: &EventObserverConfig,
     ctx: &Context,
 ) -> Result<u32, String> {
+    let block_height = block.block_identifier.index;
     let chain_event =
         BitcoinChainEvent::ChainUpdatedWithBlocks(BitcoinChainUpdatedWithBlocksData {
             new_blocks: vec![block],
             confirmed_blocks: vec![],
         });
 
+    // The chain event and the predicates are moved into the evaluation, which hands the chain
+    // event back along with the positions of the triggers: the triggers themselves borrow it.
+    let evaluated_predicates = predicates.to_vec();
+    let evaluation_ctx = ctx.clone();
+    let Some((chain_event, trigger_positions)) = evaluate_block_with_timeout(
+        block_height,
+        evaluation_timeout,
+        move || {
+            let trigger_positions =
+                locate_bitcoin_triggers(&chain_event, &evaluated_predicates, &evaluation_ctx);
+            (chain_event, trigger_positions)
+        },
+        ctx,
+    )
+    .await?
+    else {
+        return Ok(0);
+    };
+    let predicates_triggered =
+        get_bitcoin_triggers_at_positions(&chain_event, predicates, trigger_positions);
+
+    if let Some(manifest) = manifest.as_mut() {
+        for trigger in predicates_triggered.iter() {
+            for (transactions, block) in trigger.apply.iter() {
+                let txids = transactions
+                    .iter()
+                    .map(|tx| tx.transaction_identifier.hash.clone())
+                    .collect();
+                manifest.append_block(&block.block_identifier, txids)?;
+            }
+        }
+    }
+
+    execute_predicates_action(
+        predicates_triggered,
+        batches,
+        redis_writer,
+        kafka_writer,
+        deliveries,
+        dry_run,
+        max_concurrent_deliveries,
+        &event_observer_config,
+        &ctx,
+    )
+    .await
+}
+
+/// Position of a trigger's predicate, and of the blocks and transactions of its occurrences in
+/// the chain event evaluated. Unlike the trigger, which borrows the chain event, it can be
+/// moved out of the evaluation.
+struct BitcoinTriggerPositions {
+    predicate: usize,
+    apply: Vec<(usize, Vec<usize>)>,
+    rollback: Vec<(usize, Vec<usize>)>,
+}
+
+/// Returns the blocks applied and rolled back by `chain_event`.
+fn get_chain_event_blocks(
+    chain_event: &BitcoinChainEvent,
+) -> (&[BitcoinBlockData], &[BitcoinBlockData]) {
+    match chain_event {
+        BitcoinChainEvent::ChainUpdatedWithBlocks(event) => (&event.new_blocks, &[]),
+        BitcoinChainEvent::ChainUpdatedWithReorg(event) => {
+            (&event.blocks_to_apply, &event.blocks_to_rollback)
+        }
+    }
+}
+
+fn locate_bitcoin_triggers(
+    chain_event: &BitcoinChainEvent,
+    predicates: &[Arc<BitcoinChainhookSpecification>],
+    ctx: &Context,
+) -> Vec<BitcoinTriggerPositions> {
+    let active_predicates = predicates
+        .iter()
+        .map(|predicate| predicate.as_ref())
+        .collect::<Vec<_>>();
     let (predicates_triggered, _predicates_evaluated, _predicates_expired) =
-        evaluate_bitcoin_chainhooks_on_chain_event(&chain_event, predicates, ctx);
+        evaluate_bitcoin_chainhooks_on_chain_event(chain_event, &active_predicates, ctx);
+    let (apply_blocks, rollback_blocks) = get_chain_event_blocks(chain_event);
+    predicates_triggered
+        .iter()
+        .filter_map(|trigger| {
+            let predicate = active_predicates
+                .iter()
+                .position(|predicate| std::ptr::eq(*predicate, trigger.chainhook))?;
+            Some(BitcoinTriggerPositions {
+                predicate,
+                apply: locate_bitcoin_occurrences(&trigger.apply, apply_blocks),
+                rollback: locate_bitcoin_occurrences(&trigger.rollback, rollback_blocks),
+            })
+        })
+        .collect()
+}
+
+fn locate_bitcoin_occurrences(
+    occurrences: &[(Vec<&BitcoinTransactionData>, &BitcoinBlockData)],
+    blocks: &[BitcoinBlockData],
+) -> Vec<(usize, Vec<usize>)> {
+    occurrences
+        .iter()
+        .filter_map(|(transactions, block)| {
+            let block_position = blocks
+                .iter()
+                .position(|candidate| std::ptr::eq(candidate, *block))?;
+            let transaction_positions = block
+                .transactions
+                .iter()
+                .enumerate()
+                .map(|(i, tx)| (tx as *const BitcoinTransactionData, i))
+                .collect::<HashMap<_, _>>();
+            let transactions = transactions
+                .iter()
+                .filter_map(|tx| {
+                    transaction_positions
+                        .get(&(*tx as *const BitcoinTransactionData))
+                        .cloned()
+                })
+                .collect();
+            Some((block_position, transactions))
+        })
+        .collect()
+}
+
+fn get_bitcoin_triggers_at_positions<'a>(
+    chain_event: &'a BitcoinChainEvent,
+    predicates: &'a [Arc<BitcoinChainhookSpecification>],
+    trigger_positions: Vec<BitcoinTriggerPositions>,
+) -> Vec<BitcoinTriggerChainhook<'a>> {
+    let (apply_blocks, rollback_blocks) = get_chain_event_blocks(chain_event);
+    trigger_positions
+        .into_iter()
+        .map(|positions| BitcoinTriggerChainhook {
+            chainhook: predicates[positions.predicate].as_ref(),
+            apply: get_bitcoin_occurrences_at_positions(apply_blocks, positions.apply),
+            rollback: get_bitcoin_occurrences_at_positions(rollback_blocks, positions.rollback),
+        })
+        .collect()
+}
 
-    execute_predicates_action(predicates_triggered, &event_observer_config, &ctx).await
+fn get_bitcoin_occurrences_at_positions<'a>(
+    blocks: &'a [BitcoinBlockData],
+    positions: Vec<(usize, Vec<usize>)>,
+) -> Vec<(Vec<&'a BitcoinTransactionData>, &'a BitcoinBlockData)> {
+    positions
+        .into_iter()
+        .map(|(block_position, transaction_positions)| {
+            let block = &blocks[block_position];
+            let transactions = transaction_positions
+                .into_iter()
+                .map(|i| &block.transactions[i])
+                .collect();
+            (transactions, block)
+        })
+        .collect()
 }
 
 pub async fn execute_predicates_action<'a>(
     hits: Vec<BitcoinTriggerChainhook<'a>>,
+    batches: &mut HttpOccurrenceBatches<BatchedHttpOccurrence>,
+    redis_writer: &mut RedisStreamWriter,
+    kafka_writer: &mut KafkaTopicWriter,
+    deliveries: &DeliveredOccurrences,
+    dry_run: bool,
+    max_concurrent_deliveries: usize,
     config: &EventObserverConfig,
     ctx: &Context,
 ) -> Result<u32, String> {
     let mut actions_triggered = 0;
     let mut proofs = HashMap::new();
+    // HTTP occurrences, grouped by predicate, delivered once all the triggers are handled.
+    let mut http_occurrences: Vec<PredicateHttpOccurrences> = vec![];
     for trigger in hits.into_iter() {
+        if dry_run {
+            actions_triggered += 1;
+            log_dry_run_occurrence(&trigger, ctx);
+            continue;
+        }
+        let chainhook = trigger.chainhook;
+        let Some(trigger) = without_delivered_transactions(trigger, deliveries) else {
+            info!(
+                ctx.expect_logger(),
+                "Skipping occurrence of predicate {} already delivered", chainhook.uuid
+            );
+            continue;
+        };
+        let delivery_keys = get_delivery_keys(&trigger);
         if trigger.chainhook.include_proof {
             gather_proofs(&trigger, &mut proofs, &config, &ctx);
         }
+        if let HookAction::HttpPost(ref http) = chainhook.action {
+            if http.batching.is_some() {
+                actions_triggered += 1;
+                let payload = serialize_bitcoin_payload_to_json(&trigger, &proofs);
+                let occurrence = (payload, delivery_keys);
+                if let Some((http, occurrences)) = batches.push(http, occurrence, Instant::now()) {
+                    send_batched_http_occurrences(&http, occurrences, deliveries, ctx).await?;
+                }
+                continue;
+            }
+        }
         match handle_bitcoin_hook_action(trigger, &proofs) {
             Err(e) => {
                 error!(ctx.expect_logger(), "unable to handle action {}", e);
             }
             Ok(action) => {
                 actions_triggered += 1;
+                if let BitcoinChainhookOccurrence::Http(request, _) = action {
+                    match http_occurrences
+                        .iter_mut()
+                        .find(|(predicate, _)| predicate.uuid == chainhook.uuid)
+                    {
+                        Some((_, occurrences)) => occurrences.push((request, delivery_keys)),
+                        None => http_occurrences.push((chainhook, vec![(request, delivery_keys)])),
+                    }
+                    continue;
+                }
+                // The HTTP occurrences of the previous triggers are delivered first,
+                // so that actions are executed in the order of their triggers.
+                deliver_pending_http_occurrences(
+                    std::mem::take(&mut http_occurrences),
+                    max_concurrent_deliveries,
+                    deliveries,
+                    ctx,
+                )
+                .await?;
                 match action {
-                    BitcoinChainhookOccurrence::Http(request, _) => {
-                        send_request(request, 10, 3, &ctx).await?
+                    BitcoinChainhookOccurrence::Http(..) => unreachable!(),
+                    BitcoinChainhookOccurrence::File(path, rotation, bytes) => {
+                        file_append(path, bytes, &rotation, &ctx)?
+                    }
+                    BitcoinChainhookOccurrence::UnixSocket(path, bytes) => {
+                        let retry_policy = chainhook.action.get_retry_policy();
+                        unix_socket_write(&path, &bytes, &retry_policy, &ctx).await?
                     }
-                    BitcoinChainhookOccurrence::File(path, bytes) => {
-                        file_append(path, bytes, &ctx)?
+                    BitcoinChainhookOccurrence::ZstdFile(
+                        path,
+                        compression_level,
+                        rotation,
+                        bytes,
+                    ) => zstd_file_append(path, bytes, compression_level, &rotation, &ctx)?,
+                    BitcoinChainhookOccurrence::Redis(redis, bytes) => {
+                        redis_writer
+                            .xadd(&redis, bytes, &redis.get_retry_policy(), &ctx)
+                            .await?
+                    }
+                    BitcoinChainhookOccurrence::Kafka(kafka, key, bytes) => {
+                        kafka_writer
+                            .send(&kafka, key, bytes, &kafka.get_retry_policy(), &ctx)
+                            .await?
                     }
                     BitcoinChainhookOccurrence::Data(_payload) => {}
                 };
+                delivery_keys
+                    .into_iter()
+                    .for_each(|key| deliveries.insert(key));
             }
         }
     }
 
+    deliver_pending_http_occurrences(http_occurrences, max_concurrent_deliveries, deliveries, ctx)
+        .await?;
+
     Ok(actions_triggered)
 }
+
+/// Delivers `http_occurrences` and records the transactions delivered, returning the first
+/// delivery error encountered.
+async fn deliver_pending_http_occurrences<'a>(
+    http_occurrences: Vec<PredicateHttpOccurrences<'a>>,
+    max_concurrent_deliveries: usize,
+    deliveries: &DeliveredOccurrences,
+    ctx: &Context,
+) -> Result<(), String> {
+    if http_occurrences.is_empty() {
+        return Ok(());
+    }
+    let (delivered_keys, mut errors) =
+        deliver_http_occurrences(http_occurrences, max_concurrent_deliveries, ctx).await;
+    delivered_keys
+        .into_iter()
+        .for_each(|key| deliveries.insert(key));
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors.remove(0)),
+    }
+}
+
+/// Occurrence of an HTTP hook batching its occurrences, with the keys of its transactions.
+pub type BatchedHttpOccurrence = (JsonValue, Vec<DeliveryKey>);
+
+/// Posts a batch of occurrences, and records their transactions once the batch is delivered.
+pub async fn send_batched_http_occurrences(
+    http: &HttpHook,
+    occurrences: Vec<BatchedHttpOccurrence>,
+    deliveries: &DeliveredOccurrences,
+    ctx: &Context,
+) -> Result<(), String> {
+    let (payloads, delivery_keys): (Vec<_>, Vec<_>) = occurrences.into_iter().unzip();
+    send_http_occurrences_batch(http, payloads, ctx).await?;
+    delivery_keys
+        .into_iter()
+        .flatten()
+        .for_each(|key| deliveries.insert(key));
+    Ok(())
+}
+
+/// The HTTP occurrences of a predicate, in delivery order, with the keys of their transactions.
+pub type PredicateHttpOccurrences<'a> = (
+    &'a BitcoinChainhookSpecification,
+    Vec<(RequestBuilder, Vec<DeliveryKey>)>,
+);
+
+/// Delivers the occurrences of up to `max_concurrent_deliveries` predicates at once, the
+/// occurrences of a predicate being delivered in order. A failed delivery stops the delivery
+/// of the next occurrences of its predicate, but not the ones of the other predicates.
+/// Returns the keys of the transactions delivered, along with the errors encountered.
+pub async fn deliver_http_occurrences<'a>(
+    http_occurrences: Vec<PredicateHttpOccurrences<'a>>,
+    max_concurrent_deliveries: usize,
+    ctx: &Context,
+) -> (Vec<DeliveryKey>, Vec<String>) {
+    let results = stream::iter(http_occurrences.into_iter().map(
+        |(chainhook, occurrences)| async move {
+            let mut delivered_keys = vec![];
+            for (request, delivery_keys) in occurrences.into_iter() {
+                if let Err(e) = send_http_occurrence(request, chainhook, ctx).await {
+                    return (delivered_keys, Some(e));
+                }
+                delivered_keys.extend(delivery_keys);
+            }
+            (delivered_keys, None)
+        },
+    ))
+    .buffer_unordered(max_concurrent_deliveries.max(1))
+    .collect::<Vec<_>>()
+    .await;
+
+    let mut delivered_keys = vec![];
+    let mut errors = vec![];
+    for (mut keys, error) in results.into_iter() {
+        delivered_keys.append(&mut keys);
+        errors.extend(error);
+    }
+    (delivered_keys, errors)
+}
+
+fn get_delivery_keys(trigger: &BitcoinTriggerChainhook) -> Vec<DeliveryKey> {
+    trigger
+        .apply
+        .iter()
+        .flat_map(|(transactions, block)| {
+            transactions.iter().map(|tx| {
+                (
+                    trigger.chainhook.uuid.clone(),
+                    block.block_identifier.hash.clone(),
+                    tx.transaction_identifier.hash.clone(),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Drops the transactions of `trigger` already delivered to its predicate, and returns `None`
+/// when all of them were: the occurrence is then skipped.
+fn without_delivered_transactions<'a>(
+    mut trigger: BitcoinTriggerChainhook<'a>,
+    deliveries: &DeliveredOccurrences,
+) -> Option<BitcoinTriggerChainhook<'a>> {
+    if !deliveries.is_enabled() || trigger.apply.is_empty() {
+        return Some(trigger);
+    }
+    let chainhook = trigger.chainhook;
+    trigger.apply = trigger
+        .apply
+        .into_iter()
+        .filter_map(|(transactions, block)| {
+            if transactions.is_empty() {
+                return Some((transactions, block));
+            }
+            let transactions = transactions
+                .into_iter()
+                .filter(|tx| {
+                    !deliveries.contains(&(
+                        chainhook.uuid.clone(),
+                        block.block_identifier.hash.clone(),
+                        tx.transaction_identifier.hash.clone(),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            match transactions.is_empty() {
+                true => None,
+                false => Some((transactions, block)),
+            }
+        })
+        .collect();
+    if trigger.apply.is_empty() && trigger.rollback.is_empty() {
+        deliveries.record_deduplicated();
+        #[cfg(feature = "metrics")]
+        scan_metrics().record_deduplicated_delivery(&chainhook.uuid);
+        return None;
+    }
+    Some(trigger)
+}
+
+/// Logs the blocks and transactions an occurrence would have been made of, in place of executing
+/// the predicate's action.
+fn log_dry_run_occurrence(trigger: &BitcoinTriggerChainhook, ctx: &Context) {
+    for (label, blocks) in [("apply", &trigger.apply), ("rollback", &trigger.rollback)] {
+        for (transactions, block) in blocks.iter() {
+            let txids = transactions
+                .iter()
+                .map(|tx| tx.transaction_identifier.hash.as_str())
+                .collect::<Vec<_>>();
+            info!(
+                ctx.expect_logger(),
+                "Dry run: predicate {} would {} block #{} ({}), transactions: [{}]",
+                trigger.chainhook.uuid,
+                label,
+                block.block_identifier.index,
+                block.block_identifier.hash,
+                txids.join(", ")
+            );
+        }
+    }
+}
+
+/// Delivers an occurrence, retrying with the backoff configured on the predicate's action.
+pub async fn send_http_occurrence(
+    request: RequestBuilder,
+    chainhook: &BitcoinChainhookSpecification,
+    ctx: &Context,
+) -> Result<(), String> {
+    let retry_policy = chainhook.action.get_retry_policy();
+    send_request_with_backoff(request, &retry_policy, &ctx)
+        .await
+        .map_err(|e| {
+            error!(
+                ctx.expect_logger(),
+                "Unable to deliver occurrence of predicate {}: {}", chainhook.uuid, e
+            );
+            e
+        })
+}
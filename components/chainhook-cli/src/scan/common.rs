@@ -1,6 +1,486 @@
 use crate::service::ScanningData;
-use chainhook_sdk::utils::{BlockHeights, BlockHeightsError};
-use std::collections::VecDeque;
+use chainhook_sdk::bitcoincore_rpc::{self, jsonrpc};
+use chainhook_sdk::chainhooks::types::{KafkaHook, RedisHook};
+use chainhook_sdk::indexer::bitcoin::BitcoinRpcError;
+use chainhook_sdk::types::BlockIdentifier;
+use chainhook_sdk::utils::{BlockHeights, BlockHeightsError, Context, RetryPolicy};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Outcome of a predicate scan, reported once the scan is over.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanSummary {
+    pub blocks_scanned: u64,
+    pub actions_triggered: u64,
+    pub start_block: u64,
+    pub end_block: u64,
+    /// Set when every block targeted by the predicate was scanned, meaning it can be expired.
+    pub predicate_is_expired: bool,
+}
+
+/// Failure of a scan, letting callers tell an unreachable Bitcoin node from a predicate that
+/// can't be scanned, or from an action that can't be delivered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanError {
+    /// The Bitcoin node could not be reached.
+    RpcConnection(String),
+    /// The Bitcoin node answered with an error, or with a response that could not be parsed.
+    RpcResponse(String),
+    /// The Bitcoin RPC client could not be configured, e.g. from a malformed url.
+    Configuration(String),
+    /// The predicate can't be scanned as specified.
+    InvalidSpecification(String),
+    /// A file used by the scan (manifest, checkpoint) could not be read or written.
+    Io(String),
+    /// The occurrences of the predicate could not be delivered.
+    Action(String),
+}
+
+impl ScanError {
+    /// Classifies an error returned by the Bitcoin RPC client, transport errors meaning
+    /// that the node could not be reached.
+    pub fn from_rpc_error(context: &str, error: bitcoincore_rpc::Error) -> ScanError {
+        let message = format!("{} ({})", context, error.to_string());
+        match error {
+            bitcoincore_rpc::Error::JsonRpc(jsonrpc::error::Error::Transport(_)) => {
+                ScanError::RpcConnection(message)
+            }
+            _ => ScanError::RpcResponse(message),
+        }
+    }
+}
+
+impl From<BitcoinRpcError> for ScanError {
+    fn from(error: BitcoinRpcError) -> ScanError {
+        match error {
+            BitcoinRpcError::Connection(message) => ScanError::RpcConnection(message),
+            BitcoinRpcError::Response(message) => ScanError::RpcResponse(message),
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::RpcConnection(message)
+            | ScanError::RpcResponse(message)
+            | ScanError::Configuration(message)
+            | ScanError::InvalidSpecification(message)
+            | ScanError::Io(message)
+            | ScanError::Action(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+impl From<ScanError> for String {
+    fn from(error: ScanError) -> String {
+        error.to_string()
+    }
+}
+
+/// Identifies the delivery of a transaction to a predicate: predicate uuid, block hash and txid.
+pub type DeliveryKey = (String, String, String);
+
+/// Keys of the transactions delivered by the scans of the process, so that overlapping or
+/// restarted scans don't deliver the same transactions twice. Once `capacity` keys are tracked,
+/// the oldest ones are evicted. A capacity of 0 disables the deduplication.
+pub struct DeliveredOccurrences {
+    capacity: usize,
+    keys: Mutex<DeliveredOccurrencesKeys>,
+    deduplicated: AtomicU64,
+}
+
+#[derive(Default)]
+struct DeliveredOccurrencesKeys {
+    keys: HashSet<DeliveryKey>,
+    recency: VecDeque<DeliveryKey>,
+}
+
+impl DeliveredOccurrences {
+    pub fn new(capacity: usize) -> DeliveredOccurrences {
+        DeliveredOccurrences {
+            capacity,
+            keys: Mutex::new(DeliveredOccurrencesKeys::default()),
+            deduplicated: AtomicU64::new(0),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn contains(&self, key: &DeliveryKey) -> bool {
+        self.keys
+            .lock()
+            .map(|entries| entries.keys.contains(key))
+            .unwrap_or(false)
+    }
+
+    pub fn insert(&self, key: DeliveryKey) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Ok(mut entries) = self.keys.lock() else {
+            return;
+        };
+        if !entries.keys.insert(key.clone()) {
+            return;
+        }
+        entries.recency.push_back(key);
+        while entries.keys.len() > self.capacity {
+            let Some(evicted) = entries.recency.pop_front() else {
+                break;
+            };
+            entries.keys.remove(&evicted);
+        }
+    }
+
+    pub fn record_deduplicated(&self) {
+        self.deduplicated.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Number of occurrences skipped because they were already delivered.
+    pub fn deduplicated(&self) -> u64 {
+        self.deduplicated.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanManifestEntry {
+    pub block_height: u64,
+    pub block_hash: String,
+    pub txids: Vec<String>,
+}
+
+/// Minimal record of the blocks and transactions matched by a scan, written as
+/// newline delimited JSON entries and flushed as matches occur.
+pub struct ScanManifest {
+    file: File,
+}
+
+impl ScanManifest {
+    pub fn create(path: &str) -> Result<ScanManifest, String> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .map_err(|e| format!("unable to create manifest {}: {}", path, e.to_string()))?;
+        Ok(ScanManifest { file })
+    }
+
+    pub fn append_block(
+        &mut self,
+        block_identifier: &BlockIdentifier,
+        txids: Vec<String>,
+    ) -> Result<(), String> {
+        let entry = ScanManifestEntry {
+            block_height: block_identifier.index,
+            block_hash: block_identifier.hash.clone(),
+            txids,
+        };
+        let mut bytes = serde_json::to_vec(&entry)
+            .map_err(|e| format!("unable to serialize manifest entry: {}", e.to_string()))?;
+        bytes.push(b'\n');
+        self.file
+            .write_all(&bytes)
+            .and_then(|_| self.file.flush())
+            .map_err(|e| format!("unable to write manifest entry: {}", e.to_string()))
+    }
+}
+
+/// Height of the last block fully processed by the scan of a predicate, kept in
+/// `<dir>/<predicate uuid>-<scan range>.checkpoint` so that an interrupted scan resumes where it
+/// stopped. Keying the checkpoint by range keeps a predicate re-registered with other bounds
+/// from resuming the scan of its previous range.
+pub struct ScanCheckpoint {
+    path: PathBuf,
+}
+
+impl ScanCheckpoint {
+    pub fn new(
+        dir: &str,
+        predicate_uuid: &str,
+        scan_range: &str,
+    ) -> Result<ScanCheckpoint, String> {
+        std::fs::create_dir_all(dir).map_err(|e| {
+            format!(
+                "unable to create checkpoints directory {}: {}",
+                dir,
+                e.to_string()
+            )
+        })?;
+        let mut path = PathBuf::from(dir);
+        path.push(format!("{predicate_uuid}-{scan_range}.checkpoint"));
+        Ok(ScanCheckpoint { path })
+    }
+
+    /// Returns the height of the last block checkpointed, if any.
+    pub fn load(&self) -> Result<Option<u64>, String> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(format!(
+                    "unable to read checkpoint {}: {}",
+                    self.path.display(),
+                    e.to_string()
+                ))
+            }
+        };
+        content.trim().parse::<u64>().map(Some).map_err(|e| {
+            format!(
+                "unable to parse checkpoint {}: {}",
+                self.path.display(),
+                e.to_string()
+            )
+        })
+    }
+
+    /// Records `block_height` as fully processed. The checkpoint is written to a temporary file
+    /// first, then renamed, so that a crash never leaves a truncated checkpoint behind.
+    pub fn save(&self, block_height: u64) -> Result<(), String> {
+        let tmp_path = self.path.with_extension("checkpoint.tmp");
+        std::fs::write(&tmp_path, block_height.to_string())
+            .and_then(|_| std::fs::rename(&tmp_path, &self.path))
+            .map_err(|e| {
+                format!(
+                    "unable to write checkpoint {}: {}",
+                    self.path.display(),
+                    e.to_string()
+                )
+            })
+    }
+
+    /// Deletes the checkpoint, once the scan completed.
+    pub fn delete(&self) -> Result<(), String> {
+        match std::fs::remove_file(&self.path) {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!(
+                "unable to delete checkpoint {}: {}",
+                self.path.display(),
+                e.to_string()
+            )),
+        }
+    }
+}
+
+/// Appends occurrences to Redis streams. Connections are opened lazily, kept for the whole
+/// scan and re-established when they are lost. The commands are sent from the blocking thread
+/// pool.
+#[derive(Default)]
+pub struct RedisStreamWriter {
+    connections: HashMap<String, redis::Connection>,
+}
+
+impl RedisStreamWriter {
+    pub fn new() -> RedisStreamWriter {
+        RedisStreamWriter::default()
+    }
+
+    pub async fn xadd(
+        &mut self,
+        hook: &RedisHook,
+        bytes: Vec<u8>,
+        retry_policy: &RetryPolicy,
+        ctx: &Context,
+    ) -> Result<(), String> {
+        let args = build_stream_entry_args(hook, bytes)?;
+        let mut backoff_delays = retry_policy.get_backoff_delays();
+        loop {
+            let connection = self.connections.remove(&hook.url);
+            let url = hook.url.clone();
+            let command_args = args.clone();
+            let res = tokio::task::spawn_blocking(move || {
+                let mut connection = match connection {
+                    Some(connection) => connection,
+                    None => redis::Client::open(url.as_str())
+                        .and_then(|client| client.get_connection())
+                        .map_err(|e| format!("unable to connect to redis: {}", e.to_string()))?,
+                };
+                redis::cmd("XADD")
+                    .arg(&command_args)
+                    .query::<String>(&mut connection)
+                    .map(|_entry_id| connection)
+                    .map_err(|e| e.to_string())
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|res| res);
+            let err_msg = match res {
+                Ok(connection) => {
+                    self.connections.insert(hook.url.clone(), connection);
+                    return Ok(());
+                }
+                Err(e) => {
+                    // The connection is dropped, the next attempt will reconnect.
+                    let err_msg = format!(
+                        "unable to append to redis stream {}: {}",
+                        hook.stream_key, e
+                    );
+                    warn!(ctx.expect_logger(), "{}", err_msg);
+                    err_msg
+                }
+            };
+            let Some(backoff_delay) = backoff_delays.next() else {
+                return Err(format!(
+                    "unable to append to redis stream after several retries. most recent error: {}",
+                    err_msg
+                ));
+            };
+            tokio::time::sleep(backoff_delay).await;
+        }
+    }
+}
+
+/// Arguments of the `XADD` command appending an occurrence to the stream of `hook`:
+/// `<stream_key> [MAXLEN ~ <maxlen>] * payload <occurrence json>`.
+pub fn build_stream_entry_args(hook: &RedisHook, bytes: Vec<u8>) -> Result<Vec<String>, String> {
+    let payload = String::from_utf8(bytes)
+        .map_err(|e| format!("unable to encode stream entry: {}", e.to_string()))?;
+    let mut args = vec![hook.stream_key.clone()];
+    if let Some(maxlen) = hook.maxlen {
+        args.push("MAXLEN".to_string());
+        args.push("~".to_string());
+        args.push(maxlen.to_string());
+    }
+    args.push("*".to_string());
+    args.push("payload".to_string());
+    args.push(payload);
+    Ok(args)
+}
+
+/// Sends messages to a Kafka topic. Backed by rdkafka when the `kafka` feature is enabled.
+/// Producers may block: they are called from the blocking thread pool.
+pub trait KafkaProducer: Send {
+    fn send(&self, topic: &str, key: Option<&str>, payload: &[u8]) -> Result<(), String>;
+}
+
+pub type KafkaProducerFactory =
+    Box<dyn Fn(&KafkaHook) -> Result<Box<dyn KafkaProducer>, String> + Send>;
+
+/// Produces occurrences to Kafka topics. A producer is created per broker list on first use,
+/// kept for the whole scan and re-created when a delivery fails.
+pub struct KafkaTopicWriter {
+    producers: HashMap<String, Box<dyn KafkaProducer>>,
+    create_producer: KafkaProducerFactory,
+}
+
+impl Default for KafkaTopicWriter {
+    fn default() -> KafkaTopicWriter {
+        KafkaTopicWriter::with_producer_factory(Box::new(create_kafka_producer))
+    }
+}
+
+impl KafkaTopicWriter {
+    pub fn new() -> KafkaTopicWriter {
+        KafkaTopicWriter::default()
+    }
+
+    pub fn with_producer_factory(create_producer: KafkaProducerFactory) -> KafkaTopicWriter {
+        KafkaTopicWriter {
+            producers: HashMap::new(),
+            create_producer,
+        }
+    }
+
+    pub async fn send(
+        &mut self,
+        hook: &KafkaHook,
+        key: Option<String>,
+        bytes: Vec<u8>,
+        retry_policy: &RetryPolicy,
+        ctx: &Context,
+    ) -> Result<(), String> {
+        let brokers = hook.brokers.join(",");
+        let bytes = Arc::new(bytes);
+        let mut backoff_delays = retry_policy.get_backoff_delays();
+        loop {
+            let res = match self.producers.remove(&brokers) {
+                Some(producer) => Ok(producer),
+                None => (self.create_producer)(hook),
+            };
+            let topic = hook.topic.clone();
+            let message_key = key.clone();
+            let payload = bytes.clone();
+            let res = match res {
+                Ok(producer) => tokio::task::spawn_blocking(move || {
+                    producer
+                        .send(&topic, message_key.as_deref(), &payload)
+                        .map(|_| producer)
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|res| res),
+                Err(e) => Err(e),
+            };
+            let err_msg = match res {
+                Ok(producer) => {
+                    self.producers.insert(brokers, producer);
+                    return Ok(());
+                }
+                Err(e) => {
+                    // The producer is dropped, the next attempt will create a new one.
+                    let err_msg = format!("unable to produce to kafka topic {}: {}", hook.topic, e);
+                    warn!(ctx.expect_logger(), "{}", err_msg);
+                    err_msg
+                }
+            };
+            let Some(backoff_delay) = backoff_delays.next() else {
+                return Err(format!(
+                    "unable to produce to kafka topic after several retries. most recent error: {}",
+                    err_msg
+                ));
+            };
+            tokio::time::sleep(backoff_delay).await;
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+struct RdKafkaProducer(rdkafka::producer::BaseProducer);
+
+#[cfg(feature = "kafka")]
+impl KafkaProducer for RdKafkaProducer {
+    fn send(&self, topic: &str, key: Option<&str>, payload: &[u8]) -> Result<(), String> {
+        use rdkafka::producer::{BaseRecord, Producer};
+        let mut record = BaseRecord::<str, [u8]>::to(topic).payload(payload);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        self.0
+            .send(record)
+            .map_err(|(e, _)| format!("unable to enqueue message: {}", e.to_string()))?;
+        self.0
+            .flush(std::time::Duration::from_secs(10))
+            .map_err(|e| format!("unable to deliver message: {}", e.to_string()))
+    }
+}
+
+#[cfg(feature = "kafka")]
+fn create_kafka_producer(hook: &KafkaHook) -> Result<Box<dyn KafkaProducer>, String> {
+    let producer = rdkafka::config::ClientConfig::new()
+        .set("bootstrap.servers", hook.brokers.join(","))
+        .create()
+        .map_err(|e| format!("unable to create kafka producer: {}", e.to_string()))?;
+    Ok(Box::new(RdKafkaProducer(producer)))
+}
+
+#[cfg(not(feature = "kafka"))]
+fn create_kafka_producer(_hook: &KafkaHook) -> Result<Box<dyn KafkaProducer>, String> {
+    Err(format!(
+        "kafka actions require chainhook to be built with the `kafka` feature"
+    ))
+}
 
 pub fn get_block_heights_to_scan(
     blocks: &Option<Vec<u64>>,
@@ -65,3 +545,36 @@ pub fn get_block_heights_to_scan(
     };
     Ok(block_heights_to_scan)
 }
+
+/// Runs `evaluate` on the blocking thread pool when a `timeout` is set, giving up on the block
+/// at `block_height` if its evaluation takes longer than that. An abandoned evaluation runs to
+/// completion on the pool while the scan moves on. Without a `timeout`, `evaluate` runs inline.
+pub async fn evaluate_block_with_timeout<T, F>(
+    block_height: u64,
+    timeout: Option<Duration>,
+    evaluate: F,
+    ctx: &Context,
+) -> Result<Option<T>, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let Some(timeout) = timeout else {
+        return Ok(Some(evaluate()));
+    };
+    match tokio::time::timeout(timeout, tokio::task::spawn_blocking(evaluate)).await {
+        Ok(Ok(result)) => Ok(Some(result)),
+        Ok(Err(e)) => Err(format!(
+            "unable to evaluate block #{block_height}: {}",
+            e.to_string()
+        )),
+        Err(_) => {
+            warn!(
+                ctx.expect_logger(),
+                "Evaluation of block #{block_height} timed out after {}ms, skipping block",
+                timeout.as_millis()
+            );
+            Ok(None)
+        }
+    }
+}
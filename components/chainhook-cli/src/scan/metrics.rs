@@ -0,0 +1,116 @@
+use chainhook_sdk::monitoring::start_serving_prometheus_metrics;
+use chainhook_sdk::utils::Context;
+use prometheus::{IntCounterVec, IntGaugeVec, Opts, Registry};
+use std::sync::{Once, OnceLock};
+
+const PREDICATE_UUID_LABEL: &str = "predicate_uuid";
+
+/// Progress of the Bitcoin scans, labeled with the uuid of the predicate being scanned.
+#[derive(Debug, Clone)]
+pub struct ScanMetrics {
+    pub blocks_scanned: IntCounterVec,
+    pub actions_triggered: IntCounterVec,
+    pub current_block_height: IntGaugeVec,
+    pub deliveries_deduplicated: IntCounterVec,
+    pub registry: Registry,
+}
+
+impl Default for ScanMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScanMetrics {
+    pub fn new() -> ScanMetrics {
+        let registry = Registry::new();
+        let blocks_scanned = IntCounterVec::new(
+            Opts::new(
+                "chainhook_blocks_scanned_total",
+                "The number of blocks scanned for a predicate.",
+            ),
+            &[PREDICATE_UUID_LABEL],
+        )
+        .unwrap();
+        registry.register(Box::new(blocks_scanned.clone())).unwrap();
+        let actions_triggered = IntCounterVec::new(
+            Opts::new(
+                "chainhook_actions_triggered_total",
+                "The number of actions triggered while scanning for a predicate.",
+            ),
+            &[PREDICATE_UUID_LABEL],
+        )
+        .unwrap();
+        registry
+            .register(Box::new(actions_triggered.clone()))
+            .unwrap();
+        let current_block_height = IntGaugeVec::new(
+            Opts::new(
+                "chainhook_current_block_height",
+                "The height of the latest block scanned for a predicate.",
+            ),
+            &[PREDICATE_UUID_LABEL],
+        )
+        .unwrap();
+        registry
+            .register(Box::new(current_block_height.clone()))
+            .unwrap();
+        let deliveries_deduplicated = IntCounterVec::new(
+            Opts::new(
+                "chainhook_deliveries_deduplicated_total",
+                "The number of occurrences skipped for a predicate, as already delivered.",
+            ),
+            &[PREDICATE_UUID_LABEL],
+        )
+        .unwrap();
+        registry
+            .register(Box::new(deliveries_deduplicated.clone()))
+            .unwrap();
+        ScanMetrics {
+            blocks_scanned,
+            actions_triggered,
+            current_block_height,
+            deliveries_deduplicated,
+            registry,
+        }
+    }
+
+    pub fn record_block(&self, predicate_uuid: &str, block_height: u64, actions: u64) {
+        self.blocks_scanned
+            .with_label_values(&[predicate_uuid])
+            .inc();
+        self.actions_triggered
+            .with_label_values(&[predicate_uuid])
+            .inc_by(actions);
+        self.current_block_height
+            .with_label_values(&[predicate_uuid])
+            .set(block_height as i64);
+    }
+
+    pub fn record_deduplicated_delivery(&self, predicate_uuid: &str) {
+        self.deliveries_deduplicated
+            .with_label_values(&[predicate_uuid])
+            .inc();
+    }
+}
+
+/// Metrics shared by all the scans of the process.
+pub fn scan_metrics() -> &'static ScanMetrics {
+    static SCAN_METRICS: OnceLock<ScanMetrics> = OnceLock::new();
+    SCAN_METRICS.get_or_init(ScanMetrics::new)
+}
+
+/// Serve the scan metrics on `port`. Only the first call starts a server, subsequent
+/// scans keep reporting through it.
+pub fn start_serving_scan_metrics(port: u16, ctx: &Context) {
+    static SERVING: Once = Once::new();
+    SERVING.call_once(|| {
+        let registry = scan_metrics().registry.clone();
+        let ctx = ctx.clone();
+        let _ = hiro_system_kit::thread_named("Scan metrics").spawn(move || {
+            let _ = hiro_system_kit::nestable_block_on(start_serving_prometheus_metrics(
+                port, registry, ctx,
+            ));
+        });
+    });
+}
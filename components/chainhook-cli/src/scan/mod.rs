@@ -1,6 +1,9 @@
 pub mod bitcoin;
 pub mod common;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod stacks;
+pub mod synthetic;
 
 #[cfg(test)]
 pub mod tests;
@@ -1,9 +1,15 @@
 use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crate::{
     archive::download_stacks_dataset_if_required,
     config::{Config, PredicatesApi},
-    scan::common::get_block_heights_to_scan,
+    scan::common::{
+        evaluate_block_with_timeout, get_block_heights_to_scan, KafkaTopicWriter,
+        RedisStreamWriter, ScanManifest,
+    },
     service::{
         open_readwrite_predicates_db_conn_or_panic, set_confirmed_expiration_status,
         set_predicate_scanning_status, set_unconfirmed_expiration_status, ScanningData,
@@ -14,18 +20,28 @@ use crate::{
         open_readonly_stacks_db_conn_with_retry, open_readwrite_stacks_db_conn,
     },
 };
-use chainhook_sdk::types::{BlockIdentifier, Chain};
+use chainhook_sdk::types::{BlockIdentifier, Chain, StacksBlockData, StacksTransactionData};
 use chainhook_sdk::{
     chainhooks::stacks::evaluate_stacks_chainhook_on_blocks,
-    indexer::{self, stacks::standardize_stacks_serialized_block_header, Indexer},
+    indexer::{
+        self,
+        stacks::{standardize_stacks_serialized_block_header, StacksBlockCache},
+        Indexer,
+    },
     utils::Context,
 };
 use chainhook_sdk::{
     chainhooks::{
-        stacks::{handle_stacks_hook_action, StacksChainhookOccurrence, StacksTriggerChainhook},
-        types::StacksChainhookSpecification,
+        stacks::{
+            handle_stacks_hook_action, serialize_stacks_payload_to_json, StacksChainhookOccurrence,
+            StacksTriggerChainhook,
+        },
+        types::{HookAction, StacksChainhookSpecification},
+    },
+    utils::{
+        file_append, send_http_occurrences_batch, send_request_with_backoff, unix_socket_write,
+        zstd_file_append, AbstractStacksBlock, HttpOccurrenceBatches,
     },
-    utils::{file_append, send_request, AbstractStacksBlock},
 };
 use rocksdb::DB;
 
@@ -162,13 +178,84 @@ pub async fn get_canonical_fork_from_tsv(
     Ok(canonical_fork)
 }
 
+/// Evaluates `predicate_spec` on `block`, within `evaluation_timeout` when one is set. Returns
+/// the transactions matched, or `None` when the evaluation of the block timed out.
+async fn evaluate_stacks_block<'a>(
+    block: &'a Arc<StacksBlockData>,
+    predicate_spec: &Arc<StacksChainhookSpecification>,
+    evaluation_timeout: Option<Duration>,
+    ctx: &Context,
+) -> Result<Option<Vec<(Vec<&'a StacksTransactionData>, &'a dyn AbstractStacksBlock)>>, String> {
+    // The evaluation only hands back the positions of the transactions matched, the hits
+    // borrowing the block.
+    let evaluated_block = block.clone();
+    let evaluated_predicate_spec = predicate_spec.clone();
+    let evaluation_ctx = ctx.clone();
+    let Some(positions_per_blocks) = evaluate_block_with_timeout(
+        block.block_identifier.index,
+        evaluation_timeout,
+        move || {
+            let blocks: Vec<&dyn AbstractStacksBlock> = vec![evaluated_block.as_ref()];
+            let (hits_per_blocks, _predicates_expired) = evaluate_stacks_chainhook_on_blocks(
+                blocks,
+                &evaluated_predicate_spec,
+                &evaluation_ctx,
+            );
+            let transaction_positions = evaluated_block
+                .transactions
+                .iter()
+                .enumerate()
+                .map(|(i, tx)| (tx as *const StacksTransactionData, i))
+                .collect::<HashMap<_, _>>();
+            hits_per_blocks
+                .into_iter()
+                .map(|(transactions, _block)| {
+                    transactions
+                        .into_iter()
+                        .filter_map(|tx| {
+                            transaction_positions
+                                .get(&(tx as *const StacksTransactionData))
+                                .cloned()
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        },
+        ctx,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+    let block: &'a StacksBlockData = block.as_ref();
+    let hits_per_blocks = positions_per_blocks
+        .into_iter()
+        .map(|positions| {
+            let transactions = positions
+                .into_iter()
+                .map(|i| &block.transactions[i])
+                .collect();
+            (transactions, block as &dyn AbstractStacksBlock)
+        })
+        .collect();
+    Ok(Some(hits_per_blocks))
+}
+
+/// Scans the Stacks chainstate for `predicate_spec`. Raising `cancellation` stops the scan
+/// once the actions of the block being processed are delivered. Confirmed blocks are read
+/// through `block_cache`, so that the scans sharing it only load each block once.
 pub async fn scan_stacks_chainstate_via_rocksdb_using_predicate(
     predicate_spec: &StacksChainhookSpecification,
     unfinished_scan_data: Option<ScanningData>,
     stacks_db_conn: &DB,
+    block_cache: &StacksBlockCache,
+    manifest_path: Option<&str>,
+    cancellation: Option<&AtomicBool>,
     config: &Config,
     ctx: &Context,
 ) -> Result<(Option<BlockIdentifier>, bool), String> {
+    // Predicates are validated when registered: the ones reloaded from storage may predate
+    // stricter rules, and are still scanned.
     let mut chain_tip = match get_last_unconfirmed_block_height_inserted(stacks_db_conn, ctx) {
         Some(chain_tip) => chain_tip,
         None => match get_last_block_height_inserted(stacks_db_conn, ctx) {
@@ -180,11 +267,13 @@ pub async fn scan_stacks_chainstate_via_rocksdb_using_predicate(
         },
     };
 
+    // Blocks without enough confirmations are not scanned.
+    let min_confirmations = predicate_spec.min_confirmations.unwrap_or(0);
     let block_heights_to_scan = get_block_heights_to_scan(
         &predicate_spec.blocks,
         &predicate_spec.start_block,
         &predicate_spec.end_block,
-        &chain_tip,
+        &chain_tip.saturating_sub(min_confirmations),
         &unfinished_scan_data,
     )?;
     let mut block_heights_to_scan = match block_heights_to_scan {
@@ -201,6 +290,21 @@ pub async fn scan_stacks_chainstate_via_rocksdb_using_predicate(
     };
 
     let proofs = HashMap::new();
+    // Historical scans have no visibility on the mempool.
+    let mempool_timestamps = HashMap::new();
+    let mut manifest = match manifest_path {
+        Some(path) => Some(ScanManifest::create(path)?),
+        None => None,
+    };
+    let mut redis_writer = RedisStreamWriter::new();
+    let mut kafka_writer = KafkaTopicWriter::new();
+    let mut batches = HttpOccurrenceBatches::new();
+    let evaluation_timeout = config
+        .limits
+        .block_evaluation_timeout_ms
+        .map(Duration::from_millis);
+    let shared_predicate_spec = Arc::new(predicate_spec.clone());
+    let retry_policy = predicate_spec.action.get_retry_policy();
     info!(
         ctx.expect_logger(),
         "Starting predicate evaluation on Stacks blocks"
@@ -221,7 +325,23 @@ pub async fn scan_stacks_chainstate_via_rocksdb_using_predicate(
     };
 
     while let Some(current_block_height) = block_heights_to_scan.pop_front() {
-        if current_block_height > chain_tip {
+        if let Some(cancellation) = cancellation {
+            if cancellation.load(Ordering::SeqCst) {
+                info!(
+                    ctx.expect_logger(),
+                    "Scan of predicate {} cancelled before block #{current_block_height}",
+                    predicate_spec.uuid
+                );
+                block_heights_to_scan.push_front(current_block_height);
+                break;
+            }
+        }
+
+        for (http, payloads) in batches.poll(Instant::now()).into_iter() {
+            send_http_occurrences_batch(&http, payloads, ctx).await?;
+        }
+
+        if current_block_height + min_confirmations > chain_tip {
             let prev_chain_tip = chain_tip;
             // we've scanned up to the chain tip as of the start of this scan
             // so see if the chain has progressed since then
@@ -237,7 +357,7 @@ pub async fn scan_stacks_chainstate_via_rocksdb_using_predicate(
             };
             // if the chain hasn't progressed, break out so we can enter streaming mode
             // and put back the block we weren't able to scan
-            if current_block_height > chain_tip {
+            if current_block_height + min_confirmations > chain_tip {
                 block_heights_to_scan.push_front(current_block_height);
                 break;
             } else {
@@ -248,16 +368,23 @@ pub async fn scan_stacks_chainstate_via_rocksdb_using_predicate(
 
         number_of_blocks_scanned += 1;
 
-        let block_data =
-            match get_stacks_block_at_block_height(current_block_height, true, 3, stacks_db_conn) {
-                Ok(Some(block)) => block,
+        // Unconfirmed blocks can still be reorged out, so only confirmed blocks are cached.
+        let block_data = match block_cache.get(current_block_height) {
+            Some(block) => block,
+            None => match get_stacks_block_at_block_height(
+                current_block_height,
+                true,
+                3,
+                stacks_db_conn,
+            ) {
+                Ok(Some(block)) => Arc::new(block),
                 Ok(None) => match get_stacks_block_at_block_height(
                     current_block_height,
                     false,
                     3,
                     stacks_db_conn,
                 ) {
-                    Ok(Some(block)) => block,
+                    Ok(Some(block)) => block_cache.insert(current_block_height, block),
                     Ok(None) => {
                         return Err(format!("Unable to retrieve block {current_block_height}"))
                     }
@@ -272,35 +399,46 @@ pub async fn scan_stacks_chainstate_via_rocksdb_using_predicate(
                         "Unable to retrieve block {current_block_height}: {e}"
                     ))
                 }
-            };
+            },
+        };
         last_block_scanned = block_data.block_identifier.clone();
 
-        let blocks: Vec<&dyn AbstractStacksBlock> = vec![&block_data];
-
-        let (hits_per_blocks, _predicates_expired) =
-            evaluate_stacks_chainhook_on_blocks(blocks, &predicate_spec, ctx);
+        let Some(hits_per_blocks) =
+            evaluate_stacks_block(&block_data, &shared_predicate_spec, evaluation_timeout, ctx)
+                .await?
+        else {
+            continue;
+        };
         if hits_per_blocks.is_empty() {
             continue;
         }
+        if let Some(ref mut manifest) = manifest {
+            append_hits_to_manifest(manifest, &hits_per_blocks)?;
+        }
+        // Historical scans only apply blocks.
+        if !predicate_spec
+            .trigger_on
+            .unwrap_or_default()
+            .includes_apply()
+        {
+            continue;
+        }
 
         let trigger = StacksTriggerChainhook {
             chainhook: &predicate_spec,
             apply: hits_per_blocks,
             rollback: vec![],
         };
-        let res = match handle_stacks_hook_action(trigger, &proofs, &ctx) {
-            Err(e) => {
-                error!(ctx.expect_logger(), "unable to handle action {}", e);
-                Ok(()) // todo: should this error increment our err_count?
-            }
-            Ok(action) => {
+        let res = match predicate_spec.action {
+            HookAction::HttpPost(ref http) if http.batching.is_some() => {
                 number_of_times_triggered += 1;
-                let res = match action {
-                    StacksChainhookOccurrence::Http(request) => {
-                        send_request(request, 3, 1, &ctx).await
+                let payload =
+                    serialize_stacks_payload_to_json(trigger, &proofs, &mempool_timestamps, ctx);
+                let res = match batches.push(http, payload, Instant::now()) {
+                    Some((http, payloads)) => {
+                        send_http_occurrences_batch(&http, payloads, ctx).await
                     }
-                    StacksChainhookOccurrence::File(path, bytes) => file_append(path, bytes, &ctx),
-                    StacksChainhookOccurrence::Data(_payload) => Ok(()),
+                    None => Ok(()),
                 };
                 match res {
                     Err(e) => {
@@ -313,6 +451,51 @@ pub async fn scan_stacks_chainstate_via_rocksdb_using_predicate(
                     }
                 }
             }
+            _ => match handle_stacks_hook_action(trigger, &proofs, &mempool_timestamps, &ctx) {
+                Err(e) => {
+                    error!(ctx.expect_logger(), "unable to handle action {}", e);
+                    Ok(()) // todo: should this error increment our err_count?
+                }
+                Ok(action) => {
+                    number_of_times_triggered += 1;
+                    let res = match action {
+                        StacksChainhookOccurrence::Http(request) => {
+                            send_request_with_backoff(request, &retry_policy, &ctx).await
+                        }
+                        StacksChainhookOccurrence::File(path, rotation, bytes) => {
+                            file_append(path, bytes, &rotation, &ctx)
+                        }
+                        StacksChainhookOccurrence::UnixSocket(path, bytes) => {
+                            unix_socket_write(&path, &bytes, &retry_policy, &ctx).await
+                        }
+                        StacksChainhookOccurrence::ZstdFile(
+                            path,
+                            compression_level,
+                            rotation,
+                            bytes,
+                        ) => zstd_file_append(path, bytes, compression_level, &rotation, &ctx),
+                        StacksChainhookOccurrence::Redis(redis, bytes) => {
+                            redis_writer.xadd(&redis, bytes, &retry_policy, &ctx).await
+                        }
+                        StacksChainhookOccurrence::Kafka(kafka, key, bytes) => {
+                            kafka_writer
+                                .send(&kafka, key, bytes, &retry_policy, &ctx)
+                                .await
+                        }
+                        StacksChainhookOccurrence::Data(_payload) => Ok(()),
+                    };
+                    match res {
+                        Err(e) => {
+                            err_count += 1;
+                            Err(e)
+                        }
+                        Ok(_) => {
+                            err_count = 0;
+                            Ok(())
+                        }
+                    }
+                }
+            },
         };
         // We abort after 3 consecutive errors
         if err_count >= 3 {
@@ -340,6 +523,9 @@ pub async fn scan_stacks_chainstate_via_rocksdb_using_predicate(
             }
         }
     }
+    for (http, payloads) in batches.flush().into_iter() {
+        send_http_occurrences_batch(&http, payloads, ctx).await?;
+    }
     info!(
         ctx.expect_logger(),
         "{number_of_blocks_scanned} blocks scanned, {number_of_times_triggered} blocks triggering predicate"
@@ -404,29 +590,52 @@ pub async fn scan_stacks_chainstate_via_rocksdb_using_predicate(
 
 pub async fn scan_stacks_chainstate_via_csv_using_predicate(
     predicate_spec: &StacksChainhookSpecification,
+    manifest_path: Option<&str>,
     config: &mut Config,
+    block_cache: &StacksBlockCache,
     ctx: &Context,
 ) -> Result<BlockIdentifier, String> {
+    predicate_spec.validate().map_err(|errors| {
+        format!(
+            "Invalid predicate {}: {}",
+            predicate_spec.uuid,
+            errors.join(", ")
+        )
+    })?;
     let start_block = match predicate_spec.start_block {
         Some(start_block) => start_block,
         None => 0,
     };
-    if let Some(end_block) = predicate_spec.end_block {
-        if start_block > end_block {
-            return Err(
-                "Chainhook specification field `end_block` should be greater than `start_block`."
-                    .into(),
-            );
-        }
-    }
 
     let _ = download_stacks_dataset_if_required(config, ctx).await;
 
     let mut canonical_fork = get_canonical_fork_from_tsv(config, None, ctx).await?;
+    // Blocks without enough confirmations in the archive are not scanned.
+    let last_confirmed_block = canonical_fork
+        .iter()
+        .map(|(block_identifier, _, _)| block_identifier.index)
+        .max()
+        .unwrap_or(0)
+        .saturating_sub(predicate_spec.min_confirmations.unwrap_or(0));
 
     let mut indexer = Indexer::new(config.network.clone());
 
     let proofs = HashMap::new();
+    // Historical scans have no visibility on the mempool.
+    let mempool_timestamps = HashMap::new();
+    let mut manifest = match manifest_path {
+        Some(path) => Some(ScanManifest::create(path)?),
+        None => None,
+    };
+    let mut redis_writer = RedisStreamWriter::new();
+    let mut kafka_writer = KafkaTopicWriter::new();
+    let mut batches = HttpOccurrenceBatches::new();
+    let evaluation_timeout = config
+        .limits
+        .block_evaluation_timeout_ms
+        .map(Duration::from_millis);
+    let shared_predicate_spec = Arc::new(predicate_spec.clone());
+    let retry_policy = predicate_spec.action.get_retry_policy();
 
     let mut occurrences_found = 0;
     let mut blocks_scanned = 0;
@@ -437,6 +646,9 @@ pub async fn scan_stacks_chainstate_via_csv_using_predicate(
     let mut last_block_scanned = BlockIdentifier::default();
     let mut err_count = 0;
     for (block_identifier, _parent_block_identifier, blob) in canonical_fork.drain(..) {
+        for (http, payloads) in batches.poll(Instant::now()).into_iter() {
+            send_http_occurrences_batch(&http, payloads, ctx).await?;
+        }
         if block_identifier.index < start_block {
             continue;
         }
@@ -445,15 +657,20 @@ pub async fn scan_stacks_chainstate_via_csv_using_predicate(
                 break;
             }
         }
+        if block_identifier.index > last_confirmed_block {
+            break;
+        }
 
         last_block_scanned = block_identifier;
         blocks_scanned += 1;
-        let block_data = match indexer::stacks::standardize_stacks_serialized_block(
-            &indexer.config,
-            &blob,
-            &mut indexer.stacks_context,
-            ctx,
-        ) {
+        let block_data = match block_cache.get_or_standardize(&last_block_scanned, || {
+            indexer::stacks::standardize_stacks_serialized_block(
+                &indexer.config,
+                &blob,
+                &mut indexer.stacks_context,
+                ctx,
+            )
+        }) {
             Ok(block) => block,
             Err(e) => {
                 error!(&ctx.expect_logger(), "{e}");
@@ -461,20 +678,50 @@ pub async fn scan_stacks_chainstate_via_csv_using_predicate(
             }
         };
 
-        let blocks: Vec<&dyn AbstractStacksBlock> = vec![&block_data];
-
-        let (hits_per_blocks, _predicates_expired) =
-            evaluate_stacks_chainhook_on_blocks(blocks, &predicate_spec, ctx);
+        let Some(hits_per_blocks) =
+            evaluate_stacks_block(&block_data, &shared_predicate_spec, evaluation_timeout, ctx)
+                .await?
+        else {
+            continue;
+        };
         if hits_per_blocks.is_empty() {
             continue;
         }
+        if let Some(ref mut manifest) = manifest {
+            append_hits_to_manifest(manifest, &hits_per_blocks)?;
+        }
+        // Historical scans only apply blocks.
+        if !predicate_spec
+            .trigger_on
+            .unwrap_or_default()
+            .includes_apply()
+        {
+            continue;
+        }
 
         let trigger = StacksTriggerChainhook {
             chainhook: &predicate_spec,
             apply: hits_per_blocks,
             rollback: vec![],
         };
-        match handle_stacks_hook_action(trigger, &proofs, &ctx) {
+        if let HookAction::HttpPost(ref http) = predicate_spec.action {
+            if http.batching.is_some() {
+                occurrences_found += 1;
+                let payload =
+                    serialize_stacks_payload_to_json(trigger, &proofs, &mempool_timestamps, ctx);
+                if let Some((http, payloads)) = batches.push(http, payload, Instant::now()) {
+                    match send_http_occurrences_batch(&http, payloads, ctx).await {
+                        Ok(_) => err_count = 0,
+                        Err(_) => err_count += 1,
+                    }
+                }
+                if err_count >= 3 {
+                    return Err(format!("Scan aborted (consecutive action errors >= 3)"));
+                }
+                continue;
+            }
+        }
+        match handle_stacks_hook_action(trigger, &proofs, &mempool_timestamps, &ctx) {
             Err(e) => {
                 error!(ctx.expect_logger(), "unable to handle action {}", e);
             }
@@ -482,9 +729,28 @@ pub async fn scan_stacks_chainstate_via_csv_using_predicate(
                 occurrences_found += 1;
                 let res = match action {
                     StacksChainhookOccurrence::Http(request) => {
-                        send_request(request, 10, 3, &ctx).await
+                        send_request_with_backoff(request, &retry_policy, &ctx).await
+                    }
+                    StacksChainhookOccurrence::File(path, rotation, bytes) => {
+                        file_append(path, bytes, &rotation, &ctx)
+                    }
+                    StacksChainhookOccurrence::UnixSocket(path, bytes) => {
+                        unix_socket_write(&path, &bytes, &retry_policy, &ctx).await
+                    }
+                    StacksChainhookOccurrence::ZstdFile(
+                        path,
+                        compression_level,
+                        rotation,
+                        bytes,
+                    ) => zstd_file_append(path, bytes, compression_level, &rotation, &ctx),
+                    StacksChainhookOccurrence::Redis(redis, bytes) => {
+                        redis_writer.xadd(&redis, bytes, &retry_policy, &ctx).await
+                    }
+                    StacksChainhookOccurrence::Kafka(kafka, key, bytes) => {
+                        kafka_writer
+                            .send(&kafka, key, bytes, &retry_policy, &ctx)
+                            .await
                     }
-                    StacksChainhookOccurrence::File(path, bytes) => file_append(path, bytes, &ctx),
                     StacksChainhookOccurrence::Data(_payload) => unreachable!(),
                 };
                 if res.is_err() {
@@ -499,6 +765,9 @@ pub async fn scan_stacks_chainstate_via_csv_using_predicate(
             return Err(format!("Scan aborted (consecutive action errors >= 3)"));
         }
     }
+    for (http, payloads) in batches.flush().into_iter() {
+        send_http_occurrences_batch(&http, payloads, ctx).await?;
+    }
     info!(
         ctx.expect_logger(),
         "{blocks_scanned} blocks scanned, {occurrences_found} occurrences found"
@@ -507,6 +776,20 @@ pub async fn scan_stacks_chainstate_via_csv_using_predicate(
     Ok(last_block_scanned)
 }
 
+fn append_hits_to_manifest(
+    manifest: &mut ScanManifest,
+    hits_per_blocks: &[(Vec<&StacksTransactionData>, &dyn AbstractStacksBlock)],
+) -> Result<(), String> {
+    for (transactions, block) in hits_per_blocks.iter() {
+        let txids = transactions
+            .iter()
+            .map(|tx| tx.transaction_identifier.hash.clone())
+            .collect();
+        manifest.append_block(block.get_identifier(), txids)?;
+    }
+    Ok(())
+}
+
 pub async fn consolidate_local_stacks_chainstate_using_csv(
     config: &mut Config,
     ctx: &Context,
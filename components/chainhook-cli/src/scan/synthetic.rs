@@ -0,0 +1,856 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::scan::bitcoin::{execute_predicates_action, send_batched_http_occurrences};
+use crate::scan::common::{DeliveredOccurrences, KafkaTopicWriter, RedisStreamWriter};
+use chainhook_sdk::bitcoin::Address;
+use chainhook_sdk::chainhooks::bitcoin::BitcoinTriggerChainhook;
+use chainhook_sdk::chainhooks::stacks::{
+    handle_stacks_hook_action, StacksChainhookOccurrence, StacksTriggerChainhook,
+    DEFAULT_PRINT_EVENT_NAME_KEYS,
+};
+use chainhook_sdk::chainhooks::types::{
+    BitcoinChainhookSpecification, BitcoinPredicateType, BlockIdentifierIndexRule,
+    ChainhookSpecification, ContractIdentifierRule, ExactMatchingRule, MatchingRule,
+    OpReturnEncoding, OutputPredicate, StacksChainhookSpecification, StacksContractCallArgMatch,
+    StacksContractDeploymentPredicate, StacksEventType, StacksMemoMatchingRule, StacksPredicate,
+    StacksPrincipalType, StacksPrintEventBasedPredicate, StacksTrait, TimeRange, ValueMatchingRule,
+    VersionMatchingRule,
+};
+use chainhook_sdk::types::{
+    BitcoinBlockData, BitcoinBlockMetadata, BitcoinTransactionData, BitcoinTransactionMetadata,
+    BlockIdentifier, DataMapInsertEventData, DataVarSetEventData, FTBurnEventData, FTMintEventData,
+    FTTransferEventData, NFTBurnEventData, NFTMintEventData, NFTTransferEventData,
+    STXBurnEventData, STXLockEventData, STXMintEventData, STXTransferEventData,
+    SmartContractEventData, StacksBlockData, StacksBlockMetadata, StacksContractCallData,
+    StacksContractDeploymentData, StacksTransactionData, StacksTransactionEvent,
+    StacksTransactionEventPayload, StacksTransactionEventPosition, StacksTransactionKind,
+    StacksTransactionMetadata, StacksTransactionPosition, StacksTransactionReceipt,
+    TransactionIdentifier, TxOut,
+};
+use chainhook_sdk::utils::{
+    file_append, send_request_with_backoff, unix_socket_write, zstd_file_append, Context,
+    HttpOccurrenceBatches, RetryPolicy,
+};
+
+pub const SYNTHETIC_SENDER: &str = "ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG";
+pub const SYNTHETIC_RECIPIENT: &str = "ST2JHG361ZXG51QTKY2NQCVBPPRRE2KZB1HR05NNC";
+const SYNTHETIC_CONTRACT_NAME: &str = "synthetic-contract";
+const SYNTHETIC_METHOD: &str = "synthetic-method";
+const SYNTHETIC_PRINT_VALUE: &str = "synthetic occurrence";
+const SYNTHETIC_SIP09_SOURCE: &str =
+    "(impl-trait 'SP2PABAF9FTAJYNFZH93XENAJ8FVY99RRM50D2JG9.nft-trait.nft-trait)";
+const SYNTHETIC_SIP10_SOURCE: &str =
+    "(impl-trait 'SP3FBR2AGK5H9QBDH3EEN6DF8EK8JY7RX8QJ5SVTE.sip-010-trait-ft-standard.sip-010-trait)";
+
+/// Build a fake occurrence shaped after the predicate of `spec`, and deliver it through the
+/// action of `spec`, without scanning any chain. Useful to check that a receiver is
+/// reachable and accepts the payloads (and authorization header) sent by chainhook.
+pub async fn send_test_occurrence(
+    spec: &ChainhookSpecification,
+    config: &Config,
+    ctx: &Context,
+) -> Result<(), String> {
+    match spec {
+        ChainhookSpecification::Stacks(spec) => {
+            let block = build_synthetic_stacks_block(spec);
+            let trigger = StacksTriggerChainhook {
+                chainhook: spec,
+                apply: vec![(block.transactions.iter().collect(), &block)],
+                rollback: vec![],
+            };
+            // A test occurrence is given up on quickly, whatever its sink.
+            let retry_policy = RetryPolicy {
+                max_retries: 2,
+                initial_backoff_ms: 1_000,
+                backoff_multiplier: 1.0,
+            };
+            match handle_stacks_hook_action(trigger, &HashMap::new(), &HashMap::new(), ctx)? {
+                StacksChainhookOccurrence::Http(request) => {
+                    send_request_with_backoff(request, &retry_policy, ctx).await
+                }
+                StacksChainhookOccurrence::File(path, rotation, bytes) => {
+                    file_append(path, bytes, &rotation, ctx)
+                }
+                StacksChainhookOccurrence::UnixSocket(path, bytes) => {
+                    unix_socket_write(&path, &bytes, &retry_policy, ctx).await
+                }
+                StacksChainhookOccurrence::ZstdFile(path, compression_level, rotation, bytes) => {
+                    zstd_file_append(path, bytes, compression_level, &rotation, ctx)
+                }
+                StacksChainhookOccurrence::Redis(redis, bytes) => {
+                    RedisStreamWriter::new()
+                        .xadd(&redis, bytes, &retry_policy, ctx)
+                        .await
+                }
+                StacksChainhookOccurrence::Kafka(kafka, key, bytes) => {
+                    KafkaTopicWriter::new()
+                        .send(&kafka, key, bytes, &retry_policy, ctx)
+                        .await
+                }
+                StacksChainhookOccurrence::Data(_payload) => Ok(()),
+            }
+        }
+        ChainhookSpecification::Bitcoin(spec) => {
+            // Synthetic transactions can't be proven against bitcoind.
+            let mut spec = spec.clone();
+            spec.include_proof = false;
+            let block = build_synthetic_bitcoin_block(&spec, ctx)?;
+            let trigger = BitcoinTriggerChainhook {
+                chainhook: &spec,
+                apply: vec![(block.transactions.iter().collect(), &block)],
+                rollback: vec![],
+            };
+            let event_observer_config = config.get_event_observer_config();
+            let mut batches = HttpOccurrenceBatches::new();
+            let deliveries = DeliveredOccurrences::new(0);
+            execute_predicates_action(
+                vec![trigger],
+                &mut batches,
+                &mut RedisStreamWriter::new(),
+                &mut KafkaTopicWriter::new(),
+                &deliveries,
+                false,
+                config.limits.max_number_of_concurrent_deliveries,
+                &event_observer_config,
+                ctx,
+            )
+            .await?;
+            // The occurrence of a hook batching its occurrences is sent as a batch of one.
+            for (http, occurrences) in batches.flush().into_iter() {
+                send_batched_http_occurrences(&http, occurrences, &deliveries, ctx).await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Build a Stacks block containing a single transaction satisfying the predicate of `spec`.
+/// Wildcards (`*`, `<prefix>*`, `<contract_identifier>::*`) are replaced with concrete values
+/// they match. Contract identifiers given as regexes and print events matched with a regex
+/// can't be derived from the predicate: placeholder values are used instead.
+pub fn build_synthetic_stacks_block(spec: &StacksChainhookSpecification) -> StacksBlockData {
+    let block_height = match &spec.predicate {
+        // Block height predicates evaluate `between` bounds exclusively.
+        StacksPredicate::BlockHeight(BlockIdentifierIndexRule::Between(a, _)) => {
+            a.saturating_add(1)
+        }
+        StacksPredicate::BlockHeight(rule) => value_matching(rule),
+        _ => spec.start_block.unwrap_or(1),
+    };
+    let default_contract_identifier = format!("{SYNTHETIC_SENDER}.{SYNTHETIC_CONTRACT_NAME}");
+    let mut txid = synthetic_hash(block_height);
+    let mut sender = SYNTHETIC_SENDER.to_string();
+    let mut nonce = 0;
+    let mut success = true;
+    let mut kind = StacksTransactionKind::ContractCall(StacksContractCallData {
+        contract_identifier: default_contract_identifier.clone(),
+        method: SYNTHETIC_METHOD.to_string(),
+        args: vec![],
+    });
+    let mut events = vec![];
+
+    let mut pending_predicates = vec![&spec.predicate];
+    while let Some(predicate) = pending_predicates.pop() {
+        match predicate {
+            // All the sub-predicates of `all_of` are satisfied by the same transaction, the
+            // first sub-predicate of `any_of` is enough, and negations are left unsatisfied.
+            StacksPredicate::AllOf(combination) => {
+                pending_predicates.extend(combination.predicates.iter().rev())
+            }
+            StacksPredicate::AnyOf(combination) => {
+                pending_predicates.extend(combination.predicates.first())
+            }
+            StacksPredicate::Not(_) => {}
+            StacksPredicate::BlockHeight(_) | StacksPredicate::BlockTime(_) => {}
+            StacksPredicate::ContractDeployment(rule) => {
+                let contract_identifier = match rule {
+                    StacksContractDeploymentPredicate::Deployer(rule) if rule.deployer() != "*" => {
+                        format!("{}.{SYNTHETIC_CONTRACT_NAME}", rule.deployer())
+                    }
+                    StacksContractDeploymentPredicate::ContractIdentifier(rule) => {
+                        contract_identifier_matching(rule)
+                    }
+                    _ => default_contract_identifier.clone(),
+                };
+                // A comment line is the smallest source of an arbitrary size.
+                let code = match rule {
+                    StacksContractDeploymentPredicate::SourceSize(
+                        ValueMatchingRule::HigherThan(size),
+                    ) => ";".repeat(*size as usize + 1),
+                    StacksContractDeploymentPredicate::ImplementTrait(StacksTrait::Sip09) => {
+                        SYNTHETIC_SIP09_SOURCE.to_string()
+                    }
+                    StacksContractDeploymentPredicate::ImplementTrait(_) => {
+                        SYNTHETIC_SIP10_SOURCE.to_string()
+                    }
+                    StacksContractDeploymentPredicate::SourceContains(contains)
+                        if contains != "*" =>
+                    {
+                        contains.clone()
+                    }
+                    _ => String::new(),
+                };
+                kind = StacksTransactionKind::ContractDeployment(StacksContractDeploymentData {
+                    contract_identifier,
+                    code,
+                });
+            }
+            StacksPredicate::ContractCall(expected_contract_call)
+            | StacksPredicate::ContractCallPostConditionAbort(expected_contract_call) => {
+                kind = StacksTransactionKind::ContractCall(StacksContractCallData {
+                    contract_identifier: contract_identifier_matching(
+                        &expected_contract_call.contract_identifier,
+                    ),
+                    method: expected_contract_call.method.clone(),
+                    args: contract_call_args_matching(
+                        expected_contract_call.args.as_deref().unwrap_or_default(),
+                    ),
+                });
+                // Post-condition aborts are failed transactions with an `(ok ...)` result.
+                success = !matches!(
+                    predicate,
+                    StacksPredicate::ContractCallPostConditionAbort(_)
+                );
+            }
+            StacksPredicate::PrintEvent(expected_event) => {
+                if let Some(method) = expected_event.emitting_method() {
+                    kind = StacksTransactionKind::ContractCall(StacksContractCallData {
+                        contract_identifier: default_contract_identifier.clone(),
+                        method: method.clone(),
+                        args: vec![],
+                    });
+                }
+                let hex_value = match expected_event {
+                    StacksPrintEventBasedPredicate::Contains { contains, .. }
+                        if contains != "*" =>
+                    {
+                        clarity_string_hex(contains)
+                    }
+                    StacksPrintEventBasedPredicate::EventName {
+                        event_name,
+                        event_name_key,
+                        ..
+                    } => {
+                        let key = event_name_key
+                            .as_deref()
+                            .unwrap_or(DEFAULT_PRINT_EVENT_NAME_KEYS[0]);
+                        clarity_tuple_hex(key, event_name)
+                    }
+                    _ => clarity_string_hex(SYNTHETIC_PRINT_VALUE),
+                };
+                events.push(StacksTransactionEventPayload::SmartContractEvent(
+                    SmartContractEventData {
+                        contract_identifier: contract_identifier_matching(
+                            expected_event.contract_identifier(),
+                        ),
+                        topic: "print".to_string(),
+                        hex_value,
+                    },
+                ));
+            }
+            StacksPredicate::FtEvent(expected_event) => {
+                if let Some(ref method) = expected_event.emitting_method {
+                    kind = StacksTransactionKind::ContractCall(StacksContractCallData {
+                        contract_identifier: default_contract_identifier.clone(),
+                        method: method.clone(),
+                        args: vec![],
+                    });
+                }
+                let mut event = synthetic_event(
+                    &StacksEventType::FtEvent,
+                    expected_event.actions.first().map(|a| a.as_str()),
+                    Some(&asset_identifier_matching(
+                        &expected_event.asset_identifier,
+                        "synthetic-token",
+                    )),
+                );
+                set_event_principals(
+                    &mut event,
+                    &expected_event.sender,
+                    &expected_event.recipient,
+                );
+                events.push(event);
+            }
+            StacksPredicate::NftEvent(expected_event) => {
+                let mut event = synthetic_event(
+                    &StacksEventType::NftEvent,
+                    expected_event.actions.first().map(|a| a.as_str()),
+                    Some(&asset_identifier_matching(
+                        &expected_event.asset_identifier,
+                        "synthetic-nft",
+                    )),
+                );
+                if let Some(ref token_id_range) = expected_event.token_id_range {
+                    let token_id = clarity_uint_hex(token_id_range.min);
+                    match event {
+                        StacksTransactionEventPayload::NFTMintEvent(ref mut data) => {
+                            data.hex_asset_identifier = token_id
+                        }
+                        StacksTransactionEventPayload::NFTBurnEvent(ref mut data) => {
+                            data.hex_asset_identifier = token_id
+                        }
+                        StacksTransactionEventPayload::NFTTransferEvent(ref mut data) => {
+                            data.hex_asset_identifier = token_id
+                        }
+                        _ => {}
+                    }
+                }
+                set_event_principals(
+                    &mut event,
+                    &expected_event.sender,
+                    &expected_event.recipient,
+                );
+                events.push(event);
+            }
+            StacksPredicate::StxEvent(expected_event) => {
+                let mut event = synthetic_event(
+                    &StacksEventType::StxEvent,
+                    expected_event.actions.first().map(|a| a.as_str()),
+                    None,
+                );
+                // Lower bounds are inclusive and upper bounds exclusive.
+                let amount = match (
+                    expected_event.amount_greater_than,
+                    expected_event.amount_less_than,
+                ) {
+                    (Some(min), _) => Some(min),
+                    (None, Some(max)) => Some(max.saturating_sub(1)),
+                    (None, None) => None,
+                };
+                if let Some(amount) = amount {
+                    let amount = amount.to_string();
+                    match event {
+                        StacksTransactionEventPayload::STXMintEvent(ref mut data) => {
+                            data.amount = amount
+                        }
+                        StacksTransactionEventPayload::STXBurnEvent(ref mut data) => {
+                            data.amount = amount
+                        }
+                        StacksTransactionEventPayload::STXLockEvent(ref mut data) => {
+                            data.locked_amount = amount
+                        }
+                        StacksTransactionEventPayload::STXTransferEvent(ref mut data) => {
+                            data.amount = amount
+                        }
+                        _ => {}
+                    }
+                }
+                set_event_principals(
+                    &mut event,
+                    &expected_event.sender,
+                    &expected_event.recipient,
+                );
+                events.push(event);
+            }
+            StacksPredicate::Txid(ExactMatchingRule::Equals(expected_txid)) => {
+                txid = expected_txid.clone();
+            }
+            StacksPredicate::Nonce(expected_nonce) => {
+                if let Some(ref expected_sender) = expected_nonce.sender {
+                    sender = expected_sender.clone();
+                }
+                nonce = value_matching(&expected_nonce.nonce);
+            }
+            StacksPredicate::EventRatio(expected_ratio) => {
+                events.push(synthetic_event(&expected_ratio.event_type, None, None));
+            }
+            StacksPredicate::EventCount(expected_count) => {
+                for _ in 0..=expected_count.higher_than {
+                    events.push(synthetic_event(&StacksEventType::PrintEvent, None, None));
+                }
+            }
+            StacksPredicate::EventSequence(expected_sequence) => {
+                for constraint in expected_sequence.events.iter() {
+                    events.push(synthetic_event(
+                        &constraint.event_type,
+                        constraint.action.as_deref(),
+                        constraint.asset_identifier.as_ref(),
+                    ));
+                }
+            }
+            StacksPredicate::StxTransferRecipient(expected_recipient) => {
+                let recipient = match expected_recipient.principal_type {
+                    StacksPrincipalType::Standard => SYNTHETIC_RECIPIENT.to_string(),
+                    StacksPrincipalType::Contract => default_contract_identifier.clone(),
+                };
+                // Regex rules can't be satisfied generically: only `contains` rules get a memo.
+                let memo = match expected_recipient.memo {
+                    Some(StacksMemoMatchingRule::Contains(ref expected)) => {
+                        Some(format!("0x{}", hex::encode(expected.as_bytes())))
+                    }
+                    _ => None,
+                };
+                kind = StacksTransactionKind::NativeTokenTransfer;
+                events.push(StacksTransactionEventPayload::STXTransferEvent(
+                    STXTransferEventData {
+                        sender: sender.clone(),
+                        recipient,
+                        amount: "1000000".to_string(),
+                        memo,
+                    },
+                ));
+            }
+            StacksPredicate::Coinbase(expected_coinbase) => {
+                if let Some(ref recipient) = expected_coinbase.recipient {
+                    sender = recipient.clone();
+                }
+                kind = StacksTransactionKind::Coinbase;
+            }
+            StacksPredicate::ContractInteractions(expected_interactions) => {
+                for contract_identifier in expected_interactions.all_of.iter() {
+                    events.push(StacksTransactionEventPayload::SmartContractEvent(
+                        SmartContractEventData {
+                            contract_identifier: contract_identifier.clone(),
+                            topic: "print".to_string(),
+                            hex_value: clarity_string_hex(SYNTHETIC_PRINT_VALUE),
+                        },
+                    ));
+                }
+            }
+        }
+    }
+    let events = events
+        .into_iter()
+        .enumerate()
+        .map(|(index, event_payload)| StacksTransactionEvent {
+            event_payload,
+            position: StacksTransactionEventPosition {
+                index: index as u32,
+            },
+        })
+        .collect();
+    let description = match &kind {
+        StacksTransactionKind::ContractCall(data) => {
+            format!("invoked: {}::{}()", data.contract_identifier, data.method)
+        }
+        StacksTransactionKind::ContractDeployment(data) => {
+            format!("deployed: {}", data.contract_identifier)
+        }
+        StacksTransactionKind::Coinbase => "coinbase".to_string(),
+        _ => format!("transferred: 1000000 µSTX from {sender} to {SYNTHETIC_RECIPIENT}"),
+    };
+    let transaction = StacksTransactionData {
+        transaction_identifier: TransactionIdentifier { hash: txid },
+        operations: vec![],
+        metadata: StacksTransactionMetadata {
+            success,
+            raw_tx: "0x00".to_string(),
+            result: "(ok true)".to_string(),
+            sender,
+            nonce,
+            fee: 0,
+            kind,
+            receipt: StacksTransactionReceipt::new(HashSet::new(), HashSet::new(), events),
+            description,
+            sponsor: None,
+            execution_cost: None,
+            position: StacksTransactionPosition::anchor_block(0),
+            proof: None,
+            contract_abi: None,
+        },
+    };
+    StacksBlockData {
+        block_identifier: BlockIdentifier {
+            index: block_height,
+            hash: synthetic_hash(block_height),
+        },
+        parent_block_identifier: BlockIdentifier {
+            index: block_height.saturating_sub(1),
+            hash: synthetic_hash(block_height.saturating_sub(1)),
+        },
+        timestamp: match &spec.predicate {
+            StacksPredicate::BlockTime(range) => block_time_matching(range),
+            _ => 0,
+        },
+        transactions: vec![transaction],
+        metadata: StacksBlockMetadata {
+            bitcoin_anchor_block_identifier: BlockIdentifier {
+                index: 0,
+                hash: synthetic_hash(0),
+            },
+            pox_cycle_index: 0,
+            pox_cycle_position: 0,
+            pox_cycle_length: 0,
+            confirm_microblock_identifier: None,
+            stacks_block_hash: synthetic_hash(block_height),
+            network: Some(spec.network.clone()),
+        },
+    }
+}
+
+/// Build a Bitcoin block containing a single transaction satisfying the predicate of `spec`.
+/// Predicates on inputs, descriptors, distinct address counts, fee rates and protocol
+/// operations can't be satisfied by a standalone transaction: they are refused.
+pub fn build_synthetic_bitcoin_block(
+    spec: &BitcoinChainhookSpecification,
+    ctx: &Context,
+) -> Result<BitcoinBlockData, String> {
+    let block_height = spec.start_block.unwrap_or(1);
+    let mut txid = synthetic_hash(block_height);
+    let mut version = 2;
+    let mut output = TxOut {
+        value: 10_000,
+        script_pubkey: "0x6a".to_string(),
+    };
+    match &spec.predicate {
+        BitcoinPredicateType::Block => {}
+        BitcoinPredicateType::Txid(ExactMatchingRule::Equals(expected_txid)) => {
+            txid = expected_txid.clone();
+        }
+        BitcoinPredicateType::TransactionVersion(rule) => {
+            version = match rule {
+                VersionMatchingRule::Equals(expected) => *expected,
+                VersionMatchingRule::In(expected) => expected.first().copied().unwrap_or(version),
+            };
+        }
+        BitcoinPredicateType::OpReturn { contains, encoding } => {
+            if contains != "*" {
+                let data = match encoding {
+                    OpReturnEncoding::Hex => {
+                        hex::decode(contains.strip_prefix("0x").unwrap_or(contains))
+                            .unwrap_or_default()
+                    }
+                    OpReturnEncoding::Utf8 => contains.as_bytes().to_vec(),
+                };
+                output.script_pubkey = op_return_script(&data);
+            }
+        }
+        BitcoinPredicateType::Outputs(OutputPredicate::OpReturn(rule)) => {
+            let pattern = match rule {
+                MatchingRule::Equals(pattern)
+                | MatchingRule::StartsWith(pattern)
+                | MatchingRule::EndsWith(pattern) => pattern,
+            };
+            // Patterns prefixed with `0x` are hex encoded, others are ASCII.
+            let data = match pattern.strip_prefix("0x") {
+                Some(encoded) => hex::decode(encoded)
+                    .map_err(|e| format!("invalid op_return pattern {pattern}: {e}"))?,
+                None => pattern.as_bytes().to_vec(),
+            };
+            output.script_pubkey = op_return_script(&data);
+        }
+        BitcoinPredicateType::Outputs(OutputPredicate::P2pkh(ExactMatchingRule::Equals(
+            address,
+        )))
+        | BitcoinPredicateType::Outputs(OutputPredicate::P2sh(ExactMatchingRule::Equals(
+            address,
+        )))
+        | BitcoinPredicateType::Outputs(OutputPredicate::P2wpkh(ExactMatchingRule::Equals(
+            address,
+        )))
+        | BitcoinPredicateType::Outputs(OutputPredicate::P2wsh(ExactMatchingRule::Equals(
+            address,
+        )))
+        | BitcoinPredicateType::Outputs(OutputPredicate::P2tr(ExactMatchingRule::Equals(
+            address,
+        ))) => {
+            let address = Address::from_str(address)
+                .map_err(|e| format!("invalid address {address}: {e}"))?
+                .assume_checked();
+            output.script_pubkey = format!("0x{}", hex::encode(address.script_pubkey().as_bytes()));
+        }
+        BitcoinPredicateType::Outputs(OutputPredicate::TotalValue(rule)) => {
+            output.value = match rule {
+                ValueMatchingRule::HigherThan(threshold) => threshold.saturating_add(1),
+                ValueMatchingRule::LowerThan(threshold) => threshold.saturating_sub(1),
+            };
+        }
+        _ => {
+            return Err(format!(
+                "unable to build a synthetic occurrence for {}",
+                spec.predicate.describe()
+            ))
+        }
+    }
+    let transaction = BitcoinTransactionData {
+        transaction_identifier: TransactionIdentifier { hash: txid },
+        operations: vec![],
+        metadata: BitcoinTransactionMetadata {
+            version,
+            inputs: vec![],
+            outputs: vec![output],
+            stacks_operations: vec![],
+            ordinal_operations: vec![],
+            proof: None,
+            fee: 0,
+            raw_tx: None,
+            vsize: None,
+        },
+    };
+    let block = BitcoinBlockData {
+        block_identifier: BlockIdentifier {
+            index: block_height,
+            hash: synthetic_hash(block_height),
+        },
+        parent_block_identifier: BlockIdentifier {
+            index: block_height.saturating_sub(1),
+            hash: synthetic_hash(block_height.saturating_sub(1)),
+        },
+        timestamp: 0,
+        transactions: vec![transaction],
+        metadata: BitcoinBlockMetadata {
+            network: spec.network.clone(),
+        },
+    };
+    // Rules that can't be satisfied (e.g. a total value lower than 0) are caught here.
+    if !spec
+        .predicate
+        .evaluate_transaction_predicate(&block.transactions[0], &spec.network, ctx)
+    {
+        return Err(format!(
+            "unable to build a synthetic occurrence for {}",
+            spec.predicate.describe()
+        ));
+    }
+    Ok(block)
+}
+
+/// OP_RETURN script pushing `data`, data longer than 255 bytes being truncated.
+fn op_return_script(data: &[u8]) -> String {
+    let data = &data[..data.len().min(255)];
+    match data.len() {
+        // Data up to 75 bytes is pushed directly, its length being the opcode.
+        0..=75 => format!("0x6a{:02x}{}", data.len(), hex::encode(data)),
+        _ => format!("0x6a4c{:02x}{}", data.len(), hex::encode(data)),
+    }
+}
+
+fn synthetic_hash(index: u64) -> String {
+    format!("0x{:064x}", index)
+}
+
+fn value_matching(rule: &BlockIdentifierIndexRule) -> u64 {
+    match *rule {
+        BlockIdentifierIndexRule::Equals(a) => a,
+        BlockIdentifierIndexRule::HigherThan(a) => a.saturating_add(1),
+        BlockIdentifierIndexRule::LowerThan(a) => a.saturating_sub(1),
+        BlockIdentifierIndexRule::Between(a, _) => a,
+    }
+}
+
+fn block_time_matching(range: &TimeRange) -> i64 {
+    match (range.after, range.before) {
+        (Some(after), _) => after.saturating_add(1) as i64,
+        (None, Some(before)) => before.saturating_sub(1) as i64,
+        (None, None) => 1,
+    }
+}
+
+fn contract_identifier_matching(rule: &ContractIdentifierRule) -> String {
+    match rule {
+        ContractIdentifierRule::Equals(contract_identifier) if contract_identifier != "*" => {
+            match contract_identifier.strip_suffix('*') {
+                Some(prefix) => format!("{prefix}{SYNTHETIC_CONTRACT_NAME}"),
+                None => contract_identifier.clone(),
+            }
+        }
+        _ => format!("{SYNTHETIC_SENDER}.{SYNTHETIC_CONTRACT_NAME}"),
+    }
+}
+
+/// `*` is replaced with an asset of the synthetic contract, `<contract_identifier>::*` with an
+/// asset of the given contract.
+fn asset_identifier_matching(asset_identifier: &str, asset_name: &str) -> String {
+    if asset_identifier == "*" {
+        return format!("{SYNTHETIC_SENDER}.{SYNTHETIC_CONTRACT_NAME}::{asset_name}");
+    }
+    match asset_identifier.strip_suffix("::*") {
+        Some(contract_identifier) => format!("{contract_identifier}::{asset_name}"),
+        None => asset_identifier.to_string(),
+    }
+}
+
+/// Arguments satisfying the expected ones, `*` being replaced with `u1`. Arguments without
+/// constraints are set to `u1` as well.
+fn contract_call_args_matching(expected_args: &[StacksContractCallArgMatch]) -> Vec<String> {
+    let Some(len) = expected_args
+        .iter()
+        .map(|expected| expected.index + 1)
+        .max()
+    else {
+        return vec![];
+    };
+    let mut args = vec![clarity_uint_hex(1); len];
+    for expected in expected_args
+        .iter()
+        .filter(|expected| expected.equals != "*")
+    {
+        args[expected.index] = expected.equals.clone();
+    }
+    args
+}
+
+/// Sets the sender and recipient expected by a predicate on a synthetic asset event.
+fn set_event_principals(
+    event: &mut StacksTransactionEventPayload,
+    expected_sender: &Option<String>,
+    expected_recipient: &Option<String>,
+) {
+    let expected_sender = expected_sender.as_ref().filter(|sender| *sender != "*");
+    let expected_recipient = expected_recipient
+        .as_ref()
+        .filter(|recipient| *recipient != "*");
+    let (sender, recipient) = match event {
+        StacksTransactionEventPayload::FTMintEvent(data) => (None, Some(&mut data.recipient)),
+        StacksTransactionEventPayload::FTBurnEvent(data) => (Some(&mut data.sender), None),
+        StacksTransactionEventPayload::FTTransferEvent(data) => {
+            (Some(&mut data.sender), Some(&mut data.recipient))
+        }
+        StacksTransactionEventPayload::NFTMintEvent(data) => (None, Some(&mut data.recipient)),
+        StacksTransactionEventPayload::NFTBurnEvent(data) => (Some(&mut data.sender), None),
+        StacksTransactionEventPayload::NFTTransferEvent(data) => {
+            (Some(&mut data.sender), Some(&mut data.recipient))
+        }
+        StacksTransactionEventPayload::STXMintEvent(data) => (None, Some(&mut data.recipient)),
+        StacksTransactionEventPayload::STXBurnEvent(data) => (Some(&mut data.sender), None),
+        StacksTransactionEventPayload::STXLockEvent(data) => (Some(&mut data.locked_address), None),
+        StacksTransactionEventPayload::STXTransferEvent(data) => {
+            (Some(&mut data.sender), Some(&mut data.recipient))
+        }
+        _ => return,
+    };
+    if let (Some(sender), Some(expected_sender)) = (sender, expected_sender) {
+        *sender = expected_sender.clone();
+    }
+    if let (Some(recipient), Some(expected_recipient)) = (recipient, expected_recipient) {
+        *recipient = expected_recipient.clone();
+    }
+}
+
+fn synthetic_event(
+    event_type: &StacksEventType,
+    action: Option<&str>,
+    asset_identifier: Option<&String>,
+) -> StacksTransactionEventPayload {
+    let contract_identifier = format!("{SYNTHETIC_SENDER}.{SYNTHETIC_CONTRACT_NAME}");
+    let sender = SYNTHETIC_SENDER.to_string();
+    let recipient = SYNTHETIC_RECIPIENT.to_string();
+    match event_type {
+        StacksEventType::FtEvent => {
+            let asset_class_identifier = asset_identifier
+                .cloned()
+                .unwrap_or_else(|| format!("{contract_identifier}::synthetic-token"));
+            let amount = "1000".to_string();
+            match action {
+                Some("mint") => StacksTransactionEventPayload::FTMintEvent(FTMintEventData {
+                    asset_class_identifier,
+                    recipient,
+                    amount,
+                }),
+                Some("burn") => StacksTransactionEventPayload::FTBurnEvent(FTBurnEventData {
+                    asset_class_identifier,
+                    sender,
+                    amount,
+                }),
+                _ => StacksTransactionEventPayload::FTTransferEvent(FTTransferEventData {
+                    asset_class_identifier,
+                    sender,
+                    recipient,
+                    amount,
+                }),
+            }
+        }
+        StacksEventType::NftEvent => {
+            let asset_class_identifier = asset_identifier
+                .cloned()
+                .unwrap_or_else(|| format!("{contract_identifier}::synthetic-nft"));
+            let hex_asset_identifier = clarity_uint_hex(1);
+            match action {
+                Some("mint") => StacksTransactionEventPayload::NFTMintEvent(NFTMintEventData {
+                    asset_class_identifier,
+                    hex_asset_identifier,
+                    recipient,
+                }),
+                Some("burn") => StacksTransactionEventPayload::NFTBurnEvent(NFTBurnEventData {
+                    asset_class_identifier,
+                    hex_asset_identifier,
+                    sender,
+                }),
+                _ => StacksTransactionEventPayload::NFTTransferEvent(NFTTransferEventData {
+                    asset_class_identifier,
+                    hex_asset_identifier,
+                    sender,
+                    recipient,
+                }),
+            }
+        }
+        StacksEventType::StxEvent => {
+            let amount = "1000000".to_string();
+            match action {
+                Some("mint") => StacksTransactionEventPayload::STXMintEvent(STXMintEventData {
+                    recipient,
+                    amount,
+                }),
+                Some("burn") => {
+                    StacksTransactionEventPayload::STXBurnEvent(STXBurnEventData { sender, amount })
+                }
+                Some("lock") => StacksTransactionEventPayload::STXLockEvent(STXLockEventData {
+                    locked_amount: amount,
+                    unlock_height: "0".to_string(),
+                    locked_address: sender,
+                }),
+                _ => StacksTransactionEventPayload::STXTransferEvent(STXTransferEventData {
+                    sender,
+                    recipient,
+                    amount,
+                    memo: None,
+                }),
+            }
+        }
+        StacksEventType::PrintEvent => {
+            StacksTransactionEventPayload::SmartContractEvent(SmartContractEventData {
+                contract_identifier,
+                topic: "print".to_string(),
+                hex_value: clarity_string_hex(SYNTHETIC_PRINT_VALUE),
+            })
+        }
+        StacksEventType::DataVarEvent => {
+            StacksTransactionEventPayload::DataVarSetEvent(DataVarSetEventData {
+                contract_identifier,
+                var: "synthetic-var".to_string(),
+                hex_new_value: clarity_string_hex(SYNTHETIC_PRINT_VALUE),
+            })
+        }
+        StacksEventType::DataMapEvent => {
+            StacksTransactionEventPayload::DataMapInsertEvent(DataMapInsertEventData {
+                contract_identifier,
+                map: "synthetic-map".to_string(),
+                hex_inserted_key: clarity_string_hex("synthetic-key"),
+                hex_inserted_value: clarity_string_hex(SYNTHETIC_PRINT_VALUE),
+            })
+        }
+    }
+}
+
+/// Consensus serialization of a Clarity `uint`.
+fn clarity_uint_hex(value: u128) -> String {
+    let mut bytes = vec![0x01];
+    bytes.extend_from_slice(&value.to_be_bytes());
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Consensus serialization of a Clarity `string-ascii` (or `string-utf8`, for non ascii
+/// content) value.
+fn clarity_string_hex(value: &str) -> String {
+    format!("0x{}", hex::encode(clarity_string_bytes(value)))
+}
+
+/// Consensus serialization of a Clarity tuple holding a single `string-ascii` field.
+fn clarity_tuple_hex(key: &str, value: &str) -> String {
+    let mut bytes = vec![0x0c];
+    bytes.extend_from_slice(&1u32.to_be_bytes());
+    bytes.push(key.len() as u8);
+    bytes.extend_from_slice(key.as_bytes());
+    bytes.extend(clarity_string_bytes(value));
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn clarity_string_bytes(value: &str) -> Vec<u8> {
+    let type_prefix = if value.is_ascii() { 0x0d } else { 0x0e };
+    let mut bytes = vec![type_prefix];
+    bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(value.as_bytes());
+    bytes
+}
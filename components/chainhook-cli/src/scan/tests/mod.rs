@@ -1,11 +1,50 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use chainhook_sdk::utils::MAX_BLOCK_HEIGHTS_ENTRIES;
+use chainhook_sdk::chainhooks::bitcoin::BitcoinTriggerChainhook;
+use chainhook_sdk::chainhooks::stacks::{
+    evaluate_stacks_chainhook_on_blocks, serialize_stacks_payload_to_json, StacksTriggerChainhook,
+};
+use chainhook_sdk::chainhooks::types::{
+    BitcoinChainhookSpecification, BitcoinPredicateType, ChainhookSpecification, ExactMatchingRule,
+    FileFormat, FileHook, HookAction, HttpHook, KafkaHook, OccurrenceBatchingPolicy, RedisHook,
+    StacksChainhookSpecification, StacksContractCallBasedPredicate, StacksPredicate,
+};
+use chainhook_sdk::indexer::bitcoin::BitcoinBlockCache;
+use chainhook_sdk::types::{BitcoinNetwork, StacksNetwork};
+use chainhook_sdk::utils::{
+    AbstractStacksBlock, Context, HttpOccurrenceBatches, MAX_BLOCK_HEIGHTS_ENTRIES,
+};
 use test_case::test_case;
 
+use crate::config::Config;
+use crate::service::tests::helpers::mock_bitcoin_rpc::{
+    mock_bitcoin_rpc, mock_bitcoin_rpc_counting_blocks, mock_failing_bitcoin_rpc,
+};
+use crate::service::tests::helpers::mock_http_endpoint::{
+    mock_cancelling_http_endpoint, mock_flaky_http_endpoint, mock_recording_http_endpoint,
+    mock_slow_http_endpoint, CancellingEndpoint, FlakyEndpoint, RecordingEndpoint, SlowEndpoint,
+};
+use crate::service::tests::helpers::mock_stacks_node::create_tmp_working_dir;
+use crate::service::tests::helpers::{branch_and_height_to_prefixed_hash, get_free_port};
 use crate::service::ScanningData;
 
-use super::common::get_block_heights_to_scan;
+use super::bitcoin::{
+    execute_predicates_action, get_scan_range_key, resolve_start_block,
+    scan_bitcoin_chainstate_via_rpc_using_predicate,
+    scan_bitcoin_chainstate_via_rpc_using_predicates, send_batched_http_occurrences,
+    send_http_occurrence,
+};
+use super::common::{
+    build_stream_entry_args, evaluate_block_with_timeout, get_block_heights_to_scan,
+    DeliveredOccurrences, KafkaProducer, KafkaTopicWriter, RedisStreamWriter, ScanCheckpoint,
+    ScanError, ScanManifestEntry, ScanSummary,
+};
+use super::synthetic::{
+    build_synthetic_bitcoin_block, build_synthetic_stacks_block, send_test_occurrence,
+};
 
 fn expect_exceeded_max_entries_error(
     (result, _expected_entries): (Result<Option<VecDeque<u64>>, String>, Option<VecDeque<u64>>),
@@ -112,3 +151,2072 @@ fn test_get_block_heights_to_scan(
         expected,
     )
 }
+
+#[test_case(Some(1), None, None, 10 => Ok(Some(1)) ; "absolute start_block is kept")]
+#[test_case(None, Some(3), None, 10 => Ok(Some(8)) ; "offset is resolved against the chain tip")]
+#[test_case(None, Some(1), None, 10 => Ok(Some(10)) ; "offset of one only scans the chain tip")]
+#[test_case(None, Some(20), None, 10 => Ok(Some(0)) ; "offset deeper than the chain starts from genesis")]
+#[test_case(None, Some(3), Some(9), 10 => Ok(Some(8)) ; "offset with an end_block above the resolved start")]
+#[test_case(None, Some(3), Some(5), 10 => Err("Chainhook specification field `end_block` (5) should be greater than the start block resolved from `start_block_offset_from_tip` (8)".to_string()) ; "offset with an end_block below the resolved start errors")]
+#[test_case(Some(1), Some(3), None, 10 => Err("Chainhook specification field `start_block_offset_from_tip` can not be combined with `start_block` or `blocks`".to_string()) ; "offset with a start_block errors")]
+fn test_resolve_start_block(
+    start_block: Option<u64>,
+    start_block_offset_from_tip: Option<u64>,
+    end_block: Option<u64>,
+    chain_tip: u64,
+) -> Result<Option<u64>, String> {
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "relative-start".into(),
+        owner_uuid: None,
+        name: "relative-start".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block,
+        start_block_offset_from_tip,
+        end_block,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::Noop,
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    resolve_start_block(&predicate_spec, chain_tip)
+}
+
+#[test_case(None, Some(1), None, None => "1-tip".to_string() ; "open ended range")]
+#[test_case(None, Some(1), None, Some(5) => "1-5".to_string() ; "bounded range")]
+#[test_case(None, None, Some(3), None => "tip~3-tip".to_string() ; "range relative to the chain tip")]
+#[test_case(Some(vec![4, 2, 9]), None, None, None => "blocks-2-9-3".to_string() ; "list of blocks")]
+fn test_get_scan_range_key(
+    blocks: Option<Vec<u64>>,
+    start_block: Option<u64>,
+    start_block_offset_from_tip: Option<u64>,
+    end_block: Option<u64>,
+) -> String {
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "range".into(),
+        owner_uuid: None,
+        name: "range".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks,
+        start_block,
+        start_block_offset_from_tip,
+        end_block,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::Noop,
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    get_scan_range_key(&predicate_spec)
+}
+
+#[test_case(None, 8, 10 ; "up to the chain tip")]
+#[test_case(Some(9), 8, 9 ; "up to the end_block")]
+#[tokio::test]
+async fn it_scans_from_an_offset_of_the_chain_tip(
+    end_block: Option<u64>,
+    expected_start_block: u64,
+    expected_end_block: u64,
+) {
+    let port = get_free_port().unwrap();
+    let _ = hiro_system_kit::thread_named("Bitcoin rpc service")
+        .spawn(move || {
+            let future = mock_bitcoin_rpc(port, 10);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let mut config = Config::devnet_default();
+    config.network.bitcoind_rpc_url = format!("http://localhost:{port}");
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "relative-start".into(),
+        owner_uuid: None,
+        name: "relative-start".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        start_block_offset_from_tip: Some(3),
+        end_block,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::Noop,
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let summary = scan_bitcoin_chainstate_via_rpc_using_predicate(
+        &predicate_spec,
+        None,
+        None,
+        false,
+        &BitcoinBlockCache::new(config.limits.max_number_of_cached_bitcoin_blocks),
+        &DeliveredOccurrences::new(0),
+        None,
+        &config,
+        &Context {
+            logger: Some(hiro_system_kit::log::setup_logger()),
+            tracer: false,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(summary.start_block, expected_start_block);
+    assert_eq!(summary.end_block, expected_end_block);
+    assert_eq!(
+        summary.blocks_scanned,
+        expected_end_block - expected_start_block + 1
+    );
+}
+
+enum StubRpc {
+    Unreachable,
+    Failing,
+    Healthy,
+}
+
+#[test_case(StubRpc::Unreachable, None => matches ScanError::RpcConnection(_) ; "unreachable node")]
+#[test_case(StubRpc::Failing, None => matches ScanError::RpcResponse(_) ; "node answering errors")]
+#[test_case(StubRpc::Healthy, Some(1) => matches ScanError::InvalidSpecification(_) ; "offset combined with a start_block")]
+#[tokio::test]
+async fn it_reports_the_kind_of_scan_failure(
+    stub_rpc: StubRpc,
+    start_block: Option<u64>,
+) -> ScanError {
+    let port = get_free_port().unwrap();
+    match stub_rpc {
+        StubRpc::Unreachable => {}
+        StubRpc::Failing | StubRpc::Healthy => {
+            let _ = hiro_system_kit::thread_named("Bitcoin rpc service")
+                .spawn(move || {
+                    let future = async move {
+                        match stub_rpc {
+                            StubRpc::Failing => mock_failing_bitcoin_rpc(port).await,
+                            _ => mock_bitcoin_rpc(port, 10).await,
+                        }
+                    };
+                    let _ = hiro_system_kit::nestable_block_on(future);
+                })
+                .expect("unable to spawn thread");
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    let mut config = Config::devnet_default();
+    config.network.bitcoind_rpc_url = format!("http://localhost:{port}");
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "failing".into(),
+        owner_uuid: None,
+        name: "failing".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block,
+        start_block_offset_from_tip: Some(3),
+        end_block: None,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::Noop,
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+
+    scan_bitcoin_chainstate_via_rpc_using_predicate(
+        &predicate_spec,
+        None,
+        None,
+        false,
+        &BitcoinBlockCache::new(config.limits.max_number_of_cached_bitcoin_blocks),
+        &DeliveredOccurrences::new(0),
+        None,
+        &config,
+        &Context {
+            logger: Some(hiro_system_kit::log::setup_logger()),
+            tracer: false,
+        },
+    )
+    .await
+    .unwrap_err()
+}
+
+#[test_case(None, vec![3, 4], 5 ; "scans the whole range")]
+#[test_case(Some(true), vec![3], 3 ; "stops at the first matching block")]
+#[tokio::test]
+async fn it_writes_a_manifest_of_the_matched_blocks(
+    stop_on_match: Option<bool>,
+    expected_heights: Vec<u64>,
+    expected_end_block: u64,
+) {
+    let port = get_free_port().unwrap();
+    let _ = hiro_system_kit::thread_named("Bitcoin rpc service")
+        .spawn(move || {
+            let future = mock_bitcoin_rpc(port, 5);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let mut config = Config::devnet_default();
+    config.network.bitcoind_rpc_url = format!("http://localhost:{port}");
+    // The mock node spends the coinbase of block #n+1 in block #n: this txid is found twice.
+    let txid = branch_and_height_to_prefixed_hash(Some('0'), 4);
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "manifest".into(),
+        owner_uuid: None,
+        name: "manifest".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: Some(1),
+        start_block_offset_from_tip: None,
+        end_block: Some(5),
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Txid(ExactMatchingRule::Equals(txid.clone())),
+        action: HookAction::Noop,
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let manifest_path = std::env::temp_dir().join(format!("chainhook-manifest-{port}.json"));
+    let manifest_path = manifest_path.to_str().unwrap();
+
+    let summary = scan_bitcoin_chainstate_via_rpc_using_predicate(
+        &predicate_spec,
+        None,
+        Some(manifest_path),
+        false,
+        &BitcoinBlockCache::new(config.limits.max_number_of_cached_bitcoin_blocks),
+        &DeliveredOccurrences::new(0),
+        None,
+        &config,
+        &Context {
+            logger: Some(hiro_system_kit::log::setup_logger()),
+            tracer: false,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        summary,
+        ScanSummary {
+            blocks_scanned: expected_end_block,
+            actions_triggered: expected_heights.len() as u64,
+            start_block: 1,
+            end_block: expected_end_block,
+            predicate_is_expired: true,
+        }
+    );
+
+    let manifest = std::fs::read_to_string(manifest_path).unwrap();
+    std::fs::remove_file(manifest_path).unwrap();
+    let entries = manifest
+        .lines()
+        .map(|line| serde_json::from_str::<ScanManifestEntry>(line).unwrap())
+        .map(|entry| (entry.block_height, entry.txids))
+        .collect::<Vec<_>>();
+    let expected = expected_heights
+        .into_iter()
+        .map(|height| (height, vec![txid.clone()]))
+        .collect::<Vec<_>>();
+    assert_eq!(entries, expected);
+}
+
+#[tokio::test]
+async fn it_does_not_execute_actions_in_dry_run() {
+    let rpc_port = get_free_port().unwrap();
+    let _ = hiro_system_kit::thread_named("Bitcoin rpc service")
+        .spawn(move || {
+            let future = mock_bitcoin_rpc(rpc_port, 5);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    let http_port = get_free_port().unwrap();
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let endpoint = FlakyEndpoint {
+        failures: 0,
+        attempts: attempts.clone(),
+    };
+    let _ = hiro_system_kit::thread_named("Http endpoint")
+        .spawn(move || {
+            let future = mock_flaky_http_endpoint(http_port, endpoint);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let mut config = Config::devnet_default();
+    config.network.bitcoind_rpc_url = format!("http://localhost:{rpc_port}");
+    let file_path = std::env::temp_dir().join(format!("chainhook-dry-run-{rpc_port}.json"));
+    let actions = vec![
+        HookAction::HttpPost(HttpHook {
+            url: format!("http://localhost:{http_port}/"),
+            authorization_header: "".into(),
+            batching: None,
+            max_retries: None,
+            initial_backoff_ms: None,
+            backoff_multiplier: None,
+            signing_secret: None,
+            template: None,
+            gzip: None,
+            gzip_threshold_bytes: None,
+            include_raw_tx: None,
+        }),
+        HookAction::FileAppend(FileHook {
+            path: file_path.to_str().unwrap().to_string(),
+            format: None,
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx: None,
+        }),
+    ];
+    // The mock node spends the coinbase of block #n+1 in block #n: this txid is found twice.
+    let txid = branch_and_height_to_prefixed_hash(Some('0'), 4);
+    for action in actions.into_iter() {
+        let predicate_spec = BitcoinChainhookSpecification {
+            uuid: "dry-run".into(),
+            owner_uuid: None,
+            name: "dry-run".into(),
+            network: BitcoinNetwork::Regtest,
+            version: 1,
+            blocks: None,
+            start_block: Some(1),
+            start_block_offset_from_tip: None,
+            end_block: Some(5),
+            expire_after_occurrence: None,
+            predicate: BitcoinPredicateType::Txid(ExactMatchingRule::Equals(txid.clone())),
+            action,
+            include_proof: false,
+            include_inputs: false,
+            include_outputs: false,
+            include_witness: false,
+            identifier_format: None,
+            stop_on_match: None,
+            trigger_on: None,
+            min_confirmations: None,
+            enabled: true,
+            expired_at: None,
+        };
+        let summary = scan_bitcoin_chainstate_via_rpc_using_predicate(
+            &predicate_spec,
+            None,
+            None,
+            true,
+            &BitcoinBlockCache::new(config.limits.max_number_of_cached_bitcoin_blocks),
+            &DeliveredOccurrences::new(0),
+            None,
+            &config,
+            &Context {
+                logger: Some(hiro_system_kit::log::setup_logger()),
+                tracer: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.blocks_scanned, 5);
+        assert_eq!(summary.actions_triggered, 2);
+    }
+
+    assert_eq!(attempts.load(Ordering::SeqCst), 0);
+    assert!(!file_path.exists());
+}
+
+#[tokio::test]
+async fn it_returns_a_partial_summary_when_the_scan_is_cancelled() {
+    let rpc_port = get_free_port().unwrap();
+    let _ = hiro_system_kit::thread_named("Bitcoin rpc service")
+        .spawn(move || {
+            let future = mock_bitcoin_rpc(rpc_port, 5);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    let http_port = get_free_port().unwrap();
+    let cancellation = Arc::new(AtomicBool::new(false));
+    // Cancelled while delivering the occurrence of the second block.
+    let endpoint = CancellingEndpoint {
+        requests_before_cancellation: 2,
+        requests: AtomicUsize::new(0),
+        cancellation: cancellation.clone(),
+    };
+    let _ = hiro_system_kit::thread_named("Http endpoint")
+        .spawn(move || {
+            let future = mock_cancelling_http_endpoint(http_port, endpoint);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let mut config = Config::devnet_default();
+    config.network.bitcoind_rpc_url = format!("http://localhost:{rpc_port}");
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "cancelled".into(),
+        owner_uuid: None,
+        name: "cancelled".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: Some(1),
+        start_block_offset_from_tip: None,
+        end_block: Some(5),
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::HttpPost(HttpHook {
+            url: format!("http://localhost:{http_port}/"),
+            authorization_header: "".into(),
+            batching: None,
+            max_retries: None,
+            initial_backoff_ms: None,
+            backoff_multiplier: None,
+            signing_secret: None,
+            template: None,
+            gzip: None,
+            gzip_threshold_bytes: None,
+            include_raw_tx: None,
+        }),
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let summary = scan_bitcoin_chainstate_via_rpc_using_predicate(
+        &predicate_spec,
+        None,
+        None,
+        false,
+        &BitcoinBlockCache::new(config.limits.max_number_of_cached_bitcoin_blocks),
+        &DeliveredOccurrences::new(0),
+        Some(&cancellation),
+        &config,
+        &Context {
+            logger: Some(hiro_system_kit::log::setup_logger()),
+            tracer: false,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        summary,
+        ScanSummary {
+            blocks_scanned: 2,
+            actions_triggered: 2,
+            start_block: 1,
+            end_block: 2,
+            predicate_is_expired: false,
+        }
+    );
+}
+
+#[tokio::test]
+async fn it_resumes_an_interrupted_scan_from_its_checkpoint() {
+    let rpc_port = get_free_port().unwrap();
+    let _ = hiro_system_kit::thread_named("Bitcoin rpc service")
+        .spawn(move || {
+            let future = mock_bitcoin_rpc(rpc_port, 5);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    // The first scan is interrupted once the occurrence of its second block is delivered.
+    let cancelling_port = get_free_port().unwrap();
+    let cancellation = Arc::new(AtomicBool::new(false));
+    let endpoint = CancellingEndpoint {
+        requests_before_cancellation: 2,
+        requests: AtomicUsize::new(0),
+        cancellation: cancellation.clone(),
+    };
+    let _ = hiro_system_kit::thread_named("Http endpoint")
+        .spawn(move || {
+            let future = mock_cancelling_http_endpoint(cancelling_port, endpoint);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    let recording_port = get_free_port().unwrap();
+    let bodies = Arc::new(Mutex::new(vec![]));
+    let endpoint = RecordingEndpoint {
+        bodies: bodies.clone(),
+    };
+    let _ = hiro_system_kit::thread_named("Http endpoint")
+        .spawn(move || {
+            let future = mock_recording_http_endpoint(recording_port, endpoint);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let (working_dir, _) = create_tmp_working_dir().unwrap();
+    let mut config = Config::devnet_default();
+    config.network.bitcoind_rpc_url = format!("http://localhost:{rpc_port}");
+    config.storage.scan_checkpoints_dir = Some(working_dir.clone());
+    let build_predicate_spec = |http_port: u16| BitcoinChainhookSpecification {
+        uuid: "checkpointed".into(),
+        owner_uuid: None,
+        name: "checkpointed".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: Some(1),
+        start_block_offset_from_tip: None,
+        end_block: Some(5),
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::HttpPost(HttpHook {
+            url: format!("http://localhost:{http_port}/"),
+            authorization_header: "".into(),
+            batching: None,
+            max_retries: None,
+            initial_backoff_ms: None,
+            backoff_multiplier: None,
+            signing_secret: None,
+            template: None,
+            gzip: None,
+            gzip_threshold_bytes: None,
+            include_raw_tx: None,
+        }),
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let ctx = Context {
+        logger: Some(hiro_system_kit::log::setup_logger()),
+        tracer: false,
+    };
+    let block_cache = BitcoinBlockCache::new(0);
+
+    let interrupted_summary = scan_bitcoin_chainstate_via_rpc_using_predicate(
+        &build_predicate_spec(cancelling_port),
+        None,
+        None,
+        false,
+        &block_cache,
+        &DeliveredOccurrences::new(0),
+        Some(&cancellation),
+        &config,
+        &ctx,
+    )
+    .await
+    .unwrap();
+    let checkpoint = ScanCheckpoint::new(
+        &working_dir,
+        "checkpointed",
+        &get_scan_range_key(&build_predicate_spec(cancelling_port)),
+    )
+    .unwrap();
+    let interrupted_checkpoint = checkpoint.load().unwrap();
+
+    let resumed_summary = scan_bitcoin_chainstate_via_rpc_using_predicate(
+        &build_predicate_spec(recording_port),
+        None,
+        None,
+        false,
+        &block_cache,
+        &DeliveredOccurrences::new(0),
+        None,
+        &config,
+        &ctx,
+    )
+    .await
+    .unwrap();
+    let completed_checkpoint = checkpoint.load().unwrap();
+    std::fs::remove_dir_all(&working_dir).unwrap();
+
+    assert_eq!(interrupted_summary.end_block, 2);
+    assert_eq!(interrupted_checkpoint, Some(2));
+    assert_eq!(completed_checkpoint, None);
+    assert_eq!(resumed_summary.start_block, 3);
+    assert_eq!(resumed_summary.blocks_scanned, 3);
+    let resumed_heights = bodies
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|body| {
+            let occurrence = serde_json::from_str::<serde_json::Value>(body).unwrap();
+            occurrence["apply"][0]["block_identifier"]["index"]
+                .as_u64()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(resumed_heights, vec![3, 4, 5]);
+}
+
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn it_exposes_the_scan_progress_to_prometheus() {
+    let rpc_port = get_free_port().unwrap();
+    let _ = hiro_system_kit::thread_named("Bitcoin rpc service")
+        .spawn(move || {
+            let future = mock_bitcoin_rpc(rpc_port, 5);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let metrics_port = get_free_port().unwrap();
+    let mut config = Config::devnet_default();
+    config.network.bitcoind_rpc_url = format!("http://localhost:{rpc_port}");
+    config.monitoring.scan_metrics_port = Some(metrics_port);
+    // The mock node spends the coinbase of block #n+1 in block #n: this txid is found twice.
+    let txid = branch_and_height_to_prefixed_hash(Some('0'), 4);
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "metrics".into(),
+        owner_uuid: None,
+        name: "metrics".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: Some(1),
+        start_block_offset_from_tip: None,
+        end_block: Some(5),
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Txid(ExactMatchingRule::Equals(txid)),
+        action: HookAction::Noop,
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let summary = scan_bitcoin_chainstate_via_rpc_using_predicate(
+        &predicate_spec,
+        None,
+        None,
+        false,
+        &BitcoinBlockCache::new(config.limits.max_number_of_cached_bitcoin_blocks),
+        &DeliveredOccurrences::new(0),
+        None,
+        &config,
+        &Context {
+            logger: Some(hiro_system_kit::log::setup_logger()),
+            tracer: false,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(summary.blocks_scanned, 5);
+
+    let metrics = reqwest::get(format!("http://localhost:{metrics_port}/metrics"))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    for expected in [
+        "chainhook_blocks_scanned_total{predicate_uuid=\"metrics\"} 5",
+        "chainhook_actions_triggered_total{predicate_uuid=\"metrics\"} 2",
+        "chainhook_current_block_height{predicate_uuid=\"metrics\"} 5",
+    ] {
+        assert!(
+            metrics.lines().any(|line| line == expected),
+            "{expected} not found in {metrics}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn it_reuses_cached_blocks_across_bitcoin_scans() {
+    let port = get_free_port().unwrap();
+    let blocks_fetched = Arc::new(AtomicUsize::new(0));
+    let moved_blocks_fetched = blocks_fetched.clone();
+    // Blocks are only cached once deep enough to be safe from reorgs: keep the scanned
+    // heights well below the chain tip.
+    let _ = hiro_system_kit::thread_named("Bitcoin rpc service")
+        .spawn(move || {
+            let future = mock_bitcoin_rpc_counting_blocks(port, 20, moved_blocks_fetched);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let mut config = Config::devnet_default();
+    config.network.bitcoind_rpc_url = format!("http://localhost:{port}");
+    let block_cache = BitcoinBlockCache::new(config.limits.max_number_of_cached_bitcoin_blocks);
+    let txid = branch_and_height_to_prefixed_hash(Some('0'), 4);
+    let ctx = Context {
+        logger: Some(hiro_system_kit::log::setup_logger()),
+        tracer: false,
+    };
+    for (uuid, start_block, end_block, expected_blocks_fetched) in
+        [("first", 1, 5, 5), ("second", 3, 7, 7), ("third", 1, 7, 7)]
+    {
+        let predicate_spec = BitcoinChainhookSpecification {
+            uuid: uuid.into(),
+            owner_uuid: None,
+            name: uuid.into(),
+            network: BitcoinNetwork::Regtest,
+            version: 1,
+            blocks: None,
+            start_block: Some(start_block),
+            start_block_offset_from_tip: None,
+            end_block: Some(end_block),
+            expire_after_occurrence: None,
+            predicate: BitcoinPredicateType::Txid(ExactMatchingRule::Equals(txid.clone())),
+            action: HookAction::Noop,
+            include_proof: false,
+            include_inputs: false,
+            include_outputs: false,
+            include_witness: false,
+            identifier_format: None,
+            stop_on_match: None,
+            trigger_on: None,
+            min_confirmations: None,
+            enabled: true,
+            expired_at: None,
+        };
+        let summary = scan_bitcoin_chainstate_via_rpc_using_predicate(
+            &predicate_spec,
+            None,
+            None,
+            false,
+            &block_cache,
+            &DeliveredOccurrences::new(0),
+            None,
+            &config,
+            &ctx,
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.blocks_scanned, end_block - start_block + 1);
+        assert_eq!(
+            blocks_fetched.load(Ordering::SeqCst),
+            expected_blocks_fetched
+        );
+    }
+    assert_eq!(block_cache.len(), 7);
+}
+
+#[tokio::test]
+async fn it_fetches_each_block_once_when_scanning_several_bitcoin_predicates() {
+    let port = get_free_port().unwrap();
+    let blocks_fetched = Arc::new(AtomicUsize::new(0));
+    let moved_blocks_fetched = blocks_fetched.clone();
+    let _ = hiro_system_kit::thread_named("Bitcoin rpc service")
+        .spawn(move || {
+            let future = mock_bitcoin_rpc_counting_blocks(port, 5, moved_blocks_fetched);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let mut config = Config::devnet_default();
+    config.network.bitcoind_rpc_url = format!("http://localhost:{port}");
+    // Caching disabled: blocks shared by the predicates must not be fetched twice regardless.
+    let block_cache = BitcoinBlockCache::new(0);
+    let ctx = Context {
+        logger: Some(hiro_system_kit::log::setup_logger()),
+        tracer: false,
+    };
+    let build_spec = |uuid: &str, start_block: u64, txid: String| BitcoinChainhookSpecification {
+        uuid: uuid.into(),
+        owner_uuid: None,
+        name: uuid.into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: Some(start_block),
+        start_block_offset_from_tip: None,
+        end_block: Some(5),
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Txid(ExactMatchingRule::Equals(txid)),
+        action: HookAction::Noop,
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let matching = build_spec(
+        "matching",
+        1,
+        branch_and_height_to_prefixed_hash(Some('0'), 4),
+    );
+    let other = build_spec("other", 3, format!("0x{}", "f".repeat(64)));
+
+    let summaries = scan_bitcoin_chainstate_via_rpc_using_predicates(
+        &[(&matching, None), (&other, None)],
+        false,
+        &block_cache,
+        &DeliveredOccurrences::new(0),
+        &config,
+        &ctx,
+    )
+    .await
+    .unwrap()
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap();
+    assert_eq!(blocks_fetched.load(Ordering::SeqCst), 5);
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0].blocks_scanned, 5);
+    assert_eq!(summaries[0].start_block, 1);
+    assert!(summaries[0].actions_triggered > 0);
+    assert_eq!(summaries[1].blocks_scanned, 3);
+    assert_eq!(summaries[1].start_block, 3);
+    assert_eq!(summaries[1].actions_triggered, 0);
+    assert!(summaries.iter().all(|summary| summary.end_block == 5));
+    assert!(summaries.iter().all(|summary| summary.predicate_is_expired));
+}
+
+#[tokio::test]
+async fn it_skips_blocks_whose_evaluation_times_out() {
+    let ctx = Context {
+        logger: Some(hiro_system_kit::log::setup_logger()),
+        tracer: false,
+    };
+    let timeout = Some(Duration::from_millis(200));
+    let mut evaluated_blocks = vec![];
+    for block_height in 1..=3u64 {
+        let evaluated = evaluate_block_with_timeout(
+            block_height,
+            timeout,
+            move || {
+                // Artificially slow predicate evaluation on block #2.
+                if block_height == 2 {
+                    std::thread::sleep(Duration::from_secs(2));
+                }
+                block_height
+            },
+            &ctx,
+        )
+        .await
+        .unwrap();
+        evaluated_blocks.extend(evaluated);
+    }
+    assert_eq!(evaluated_blocks, vec![1, 3]);
+}
+
+#[tokio::test]
+async fn it_waits_for_block_evaluation_without_timeout() {
+    let evaluated = evaluate_block_with_timeout(
+        1,
+        None,
+        || {
+            std::thread::sleep(Duration::from_millis(300));
+            true
+        },
+        &Context::empty(),
+    )
+    .await;
+    assert_eq!(evaluated, Ok(Some(true)));
+}
+
+#[tokio::test]
+async fn it_reports_a_block_evaluation_failure() {
+    let evaluated = evaluate_block_with_timeout(
+        1,
+        Some(Duration::from_secs(1)),
+        || -> bool { panic!("evaluation failure") },
+        &Context::empty(),
+    )
+    .await;
+    assert!(evaluated.is_err());
+}
+
+fn build_stacks_test_spec(
+    predicate: StacksPredicate,
+    action: HookAction,
+) -> StacksChainhookSpecification {
+    StacksChainhookSpecification {
+        uuid: "synthetic".to_string(),
+        owner_uuid: None,
+        name: "synthetic".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: Some(true),
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate,
+        action,
+        enabled: true,
+        expired_at: None,
+    }
+}
+
+#[test_case(json!({"scope": "block_height", "higher_than": 100}) ; "block height")]
+#[test_case(json!({"scope": "block_height", "between": [100, 102]}) ; "block height between")]
+#[test_case(json!({"scope": "contract_deployment", "deployer": "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5"}) ; "contract deployment")]
+#[test_case(json!({"scope": "contract_call", "contract_identifier": "SP000000000000000000002Q6VF78.pox", "method": "stack-stx"}) ; "contract call")]
+#[test_case(json!({"scope": "contract_call", "contract_identifier": "SP000000000000000000002Q6VF78.pox*", "method": "stack-stx", "args": [{"index": 0, "equals": "*"}, {"index": 2, "equals": "100"}]}) ; "contract call wildcards")]
+#[test_case(json!({"scope": "contract_call_post_condition_abort", "contract_identifier": "SP000000000000000000002Q6VF78.pox", "method": "stack-stx"}) ; "post condition abort")]
+#[test_case(json!({"scope": "print_event", "contract_identifier": "*", "contains": "vault", "emitting_method": "deposit"}) ; "print event contains")]
+#[test_case(json!({"scope": "print_event", "contract_identifier": "*", "event_name": "deposit"}) ; "print event name")]
+#[test_case(json!({"scope": "ft_event", "asset_identifier": "SP000000000000000000002Q6VF78.token::token", "actions": ["burn"]}) ; "ft event")]
+#[test_case(json!({"scope": "ft_event", "asset_identifier": "*", "actions": ["transfer"]}) ; "ft event any asset")]
+#[test_case(json!({"scope": "nft_event", "asset_identifier": "SP000000000000000000002Q6VF78.nft::nft", "actions": ["mint"]}) ; "nft event")]
+#[test_case(json!({"scope": "nft_event", "asset_identifier": "SP000000000000000000002Q6VF78.nft::*", "actions": ["burn"]}) ; "nft event any asset of a contract")]
+#[test_case(json!({"scope": "nft_event", "asset_identifier": "SP000000000000000000002Q6VF78.nft::nft", "actions": ["mint"], "token_id_range": {"min": 1000, "max": 2000}}) ; "nft event token id range")]
+#[test_case(json!({"scope": "stx_event", "actions": ["lock"]}) ; "stx event")]
+#[test_case(json!({"scope": "txid", "equals": "0xfaaac1833dc4883e7ec28f61e35b41f896c395f8d288b1a177155de2abd6052f"}) ; "txid")]
+#[test_case(json!({"scope": "nonce", "sender": "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5", "nonce": {"between": [5, 10]}}) ; "nonce")]
+#[test_case(json!({"scope": "event_count", "higher_than": 3}) ; "event count")]
+#[test_case(json!({"scope": "event_sequence", "events": [{"event_type": "ft_event", "action": "mint"}, {"event_type": "print_event"}]}) ; "event sequence")]
+#[test_case(json!({"scope": "stx_transfer_recipient", "principal_type": "contract"}) ; "stx transfer recipient")]
+#[test_case(json!({"scope": "stx_transfer_recipient", "principal_type": "standard", "memo": {"contains": "invoice"}}) ; "stx transfer recipient memo")]
+#[test_case(json!({"scope": "contract_interactions", "all_of": ["ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.amm-swap", "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.lending-pool"]}) ; "contract interactions")]
+fn it_builds_synthetic_stacks_occurrences_matching_the_predicate(predicate: serde_json::Value) {
+    let predicate: StacksPredicate = serde_json::from_value(predicate).unwrap();
+    let spec = StacksChainhookSpecification {
+        capture_all_events: Some(true),
+        ..build_stacks_test_spec(predicate, HookAction::Noop)
+    };
+    let ctx = Context::empty();
+    let block = build_synthetic_stacks_block(&spec);
+    let blocks: Vec<&dyn AbstractStacksBlock> = vec![&block];
+    let (occurrences, _expired) = evaluate_stacks_chainhook_on_blocks(blocks, &spec, &ctx);
+    assert_eq!(occurrences.len(), 1);
+
+    let payload = serialize_stacks_payload_to_json(
+        StacksTriggerChainhook {
+            chainhook: &spec,
+            apply: occurrences,
+            rollback: vec![],
+        },
+        &HashMap::new(),
+        &HashMap::new(),
+        &ctx,
+    );
+    assert_eq!(payload["chainhook"]["uuid"], "synthetic");
+    let transaction = &payload["apply"][0]["transactions"][0];
+    assert_eq!(
+        transaction["transaction_identifier"]["hash"],
+        block.transactions[0].transaction_identifier.hash
+    );
+    assert_eq!(
+        transaction["metadata"]["receipt"]["events"]
+            .as_array()
+            .unwrap()
+            .len(),
+        block.transactions[0].metadata.receipt.events.len()
+    );
+}
+
+#[test_case(json!({"scope": "block"}), true ; "block")]
+#[test_case(json!({"scope": "txid", "equals": "0xfaaac1833dc4883e7ec28f61e35b41f896c395f8d288b1a177155de2abd6052f"}), true ; "txid")]
+#[test_case(json!({"scope": "transaction_version", "in": [1, 2]}), true ; "transaction version")]
+#[test_case(json!({"scope": "op_return", "contains": "vault", "encoding": "utf8"}), true ; "op_return contains")]
+#[test_case(json!({"scope": "outputs", "op_return": {"starts_with": "X2["}}), true ; "outputs op_return")]
+#[test_case(json!({"scope": "outputs", "p2pkh": {"equals": "mr1iPkD9N3RJZZxXRk7xF9d36gffa6exNC"}}), true ; "outputs p2pkh")]
+#[test_case(json!({"scope": "outputs", "p2wpkh": {"equals": "bcrt1qnxknq3wqtphv7sfwy07m7e4sr6ut9yt6ed99jg"}}), true ; "outputs p2wpkh")]
+#[test_case(json!({"scope": "outputs", "total_value": {"higher_than": 100000}}), true ; "outputs total value")]
+#[test_case(json!({"scope": "outputs", "total_value": {"lower_than": 0}}), false ; "unsatisfiable total value")]
+#[test_case(json!({"scope": "inputs", "txid": {"txid": "0xfaaac1833dc4883e7ec28f61e35b41f896c395f8d288b1a177155de2abd6052f", "vout": 0}}), false ; "inputs")]
+#[test_case(json!({"scope": "stacks_protocol", "operation": "block_committed"}), false ; "stacks protocol")]
+fn it_builds_synthetic_bitcoin_occurrences_matching_the_predicate(
+    predicate: serde_json::Value,
+    supported: bool,
+) {
+    let spec = BitcoinChainhookSpecification {
+        uuid: "synthetic".into(),
+        owner_uuid: None,
+        name: "synthetic".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        start_block_offset_from_tip: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        predicate: serde_json::from_value(predicate).unwrap(),
+        action: HookAction::Noop,
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let ctx = Context::empty();
+    let block = build_synthetic_bitcoin_block(&spec, &ctx);
+    assert_eq!(block.is_ok(), supported, "{:?}", block.as_ref().err());
+    if let Ok(block) = block {
+        assert!(spec.predicate.evaluate_transaction_predicate(
+            &block.transactions[0],
+            &spec.network,
+            &ctx
+        ));
+    }
+}
+
+#[test_case(Some(3), true, 3 ; "delivers once the endpoint recovers")]
+#[test_case(Some(1), false, 2 ; "gives up after max_retries")]
+#[tokio::test]
+async fn it_retries_http_occurrences_with_backoff(
+    max_retries: Option<u16>,
+    delivered: bool,
+    expected_attempts: usize,
+) {
+    let port = get_free_port().unwrap();
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let endpoint = FlakyEndpoint {
+        failures: 2,
+        attempts: attempts.clone(),
+    };
+    let _ = hiro_system_kit::thread_named("Flaky http endpoint")
+        .spawn(move || {
+            let future = mock_flaky_http_endpoint(port, endpoint);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let url = format!("http://localhost:{port}/");
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "retry".into(),
+        owner_uuid: None,
+        name: "retry".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        start_block_offset_from_tip: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::HttpPost(HttpHook {
+            url: url.clone(),
+            authorization_header: "".into(),
+            batching: None,
+            max_retries,
+            initial_backoff_ms: Some(10),
+            backoff_multiplier: Some(2.0),
+            signing_secret: None,
+            template: None,
+            gzip: None,
+            gzip_threshold_bytes: None,
+            include_raw_tx: None,
+        }),
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let request = reqwest::Client::new().post(&url).body("{}");
+
+    let ctx = Context {
+        logger: Some(hiro_system_kit::log::setup_logger()),
+        tracer: false,
+    };
+    let res = send_http_occurrence(request, &predicate_spec, &ctx).await;
+
+    assert_eq!(res.is_ok(), delivered, "{:?}", res);
+    assert_eq!(attempts.load(Ordering::SeqCst), expected_attempts);
+}
+
+#[tokio::test]
+async fn it_sends_a_synthetic_occurrence_through_the_predicate_action() {
+    let path = std::env::temp_dir().join(format!(
+        "chainhook-test-occurrence-{}.json",
+        get_free_port().unwrap()
+    ));
+    std::fs::File::create(&path).unwrap();
+    let predicate = StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "SP000000000000000000002Q6VF78.pox".into(),
+        method: "stack-stx".into(),
+        case_insensitive: None,
+        args: None,
+    });
+    let spec = build_stacks_test_spec(
+        predicate,
+        HookAction::FileAppend(FileHook {
+            path: path.to_str().unwrap().to_string(),
+            format: Some(FileFormat::JsonLines),
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx: None,
+        }),
+    );
+    let config = Config::default(true, false, false, &None).unwrap();
+
+    send_test_occurrence(
+        &ChainhookSpecification::Stacks(spec),
+        &config,
+        &Context::empty(),
+    )
+    .await
+    .unwrap();
+
+    let occurrences = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let occurrences = occurrences.lines().collect::<Vec<_>>();
+    assert_eq!(occurrences.len(), 1);
+    let occurrence: serde_json::Value = serde_json::from_str(occurrences[0]).unwrap();
+    assert_eq!(
+        occurrence["apply"][0]["transactions"][0]["metadata"]["kind"]["data"]["method"],
+        "stack-stx"
+    );
+}
+
+#[test_case(Some(FileFormat::JsonLines), true ; "json lines")]
+#[test_case(Some(FileFormat::PrettyJson), false ; "pretty json")]
+#[test_case(None, true ; "json lines by default")]
+#[tokio::test]
+async fn it_appends_occurrences_to_a_file_following_its_format(
+    format: Option<FileFormat>,
+    one_line_per_occurrence: bool,
+) {
+    let path = std::env::temp_dir().join(format!(
+        "chainhook-test-file-format-{}.json",
+        get_free_port().unwrap()
+    ));
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "file".into(),
+        owner_uuid: None,
+        name: "file".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        start_block_offset_from_tip: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::FileAppend(FileHook {
+            path: path.to_str().unwrap().to_string(),
+            format,
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx: None,
+        }),
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let blocks = (1..=2)
+        .map(|block_height| {
+            build_synthetic_bitcoin_block(
+                &BitcoinChainhookSpecification {
+                    start_block: Some(block_height),
+                    ..predicate_spec.clone()
+                },
+                &Context::empty(),
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let config = Config::default(true, false, false, &None).unwrap();
+
+    for block in blocks.iter() {
+        let trigger = BitcoinTriggerChainhook {
+            chainhook: &predicate_spec,
+            apply: vec![(block.transactions.iter().collect(), block)],
+            rollback: vec![],
+        };
+        execute_predicates_action(
+            vec![trigger],
+            &mut HttpOccurrenceBatches::new(),
+            &mut RedisStreamWriter::new(),
+            &mut KafkaTopicWriter::new(),
+            &DeliveredOccurrences::new(0),
+            false,
+            1,
+            &config.get_event_observer_config(),
+            &Context::empty(),
+        )
+        .await
+        .unwrap();
+    }
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(content.ends_with('\n'));
+    let occurrences = serde_json::Deserializer::from_str(&content)
+        .into_iter::<serde_json::Value>()
+        .map(|occurrence| occurrence.unwrap()["apply"][0]["block_identifier"]["index"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(occurrences, vec![1, 2]);
+    assert_eq!(
+        content.lines().count() == occurrences.len(),
+        one_line_per_occurrence
+    );
+}
+
+#[test_case(None, vec!["occurrences", "*", "payload", "{\"apply\":[]}"] ; "without maxlen")]
+#[test_case(Some(1000), vec!["occurrences", "MAXLEN", "~", "1000", "*", "payload", "{\"apply\":[]}"] ; "with maxlen")]
+fn test_build_stream_entry_args(maxlen: Option<usize>, expected: Vec<&str>) {
+    let hook = RedisHook {
+        url: "redis://localhost:6379/".into(),
+        stream_key: "occurrences".into(),
+        maxlen,
+        max_retries: None,
+        initial_backoff_ms: None,
+        backoff_multiplier: None,
+    };
+    let args = build_stream_entry_args(&hook, br#"{"apply":[]}"#.to_vec()).unwrap();
+    assert_eq!(args, expected);
+}
+
+type ProducedMessages = Arc<Mutex<Vec<(String, Option<String>, Vec<u8>)>>>;
+
+struct RecordingKafkaProducer {
+    messages: ProducedMessages,
+}
+
+impl KafkaProducer for RecordingKafkaProducer {
+    fn send(&self, topic: &str, key: Option<&str>, payload: &[u8]) -> Result<(), String> {
+        self.messages.lock().unwrap().push((
+            topic.to_string(),
+            key.map(|k| k.to_string()),
+            payload.to_vec(),
+        ));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn it_produces_kafka_occurrences_with_a_single_producer() {
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "kafka".into(),
+        owner_uuid: None,
+        name: "kafka".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        start_block_offset_from_tip: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::Kafka(KafkaHook {
+            brokers: vec!["localhost:9092".into()],
+            topic: "occurrences".into(),
+            key_template: Some("{uuid}-{block_height}".into()),
+            max_retries: None,
+            initial_backoff_ms: None,
+            backoff_multiplier: None,
+        }),
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let blocks = (1..=3)
+        .map(|block_height| {
+            build_synthetic_bitcoin_block(
+                &BitcoinChainhookSpecification {
+                    start_block: Some(block_height),
+                    ..predicate_spec.clone()
+                },
+                &Context::empty(),
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let producers_created = Arc::new(AtomicUsize::new(0));
+    let messages: ProducedMessages = Arc::new(Mutex::new(vec![]));
+    let mut kafka_writer = {
+        let producers_created = producers_created.clone();
+        let messages = messages.clone();
+        KafkaTopicWriter::with_producer_factory(Box::new(move |_hook: &KafkaHook| {
+            producers_created.fetch_add(1, Ordering::SeqCst);
+            let producer: Box<dyn KafkaProducer> = Box::new(RecordingKafkaProducer {
+                messages: messages.clone(),
+            });
+            Ok(producer)
+        }))
+    };
+    let config = Config::default(true, false, false, &None).unwrap();
+
+    for block in blocks.iter() {
+        let trigger = BitcoinTriggerChainhook {
+            chainhook: &predicate_spec,
+            apply: vec![(block.transactions.iter().collect(), block)],
+            rollback: vec![],
+        };
+        execute_predicates_action(
+            vec![trigger],
+            &mut HttpOccurrenceBatches::new(),
+            &mut RedisStreamWriter::new(),
+            &mut kafka_writer,
+            &DeliveredOccurrences::new(0),
+            false,
+            1,
+            &config.get_event_observer_config(),
+            &Context::empty(),
+        )
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(producers_created.load(Ordering::SeqCst), 1);
+    let messages = messages.lock().unwrap();
+    let keys = messages
+        .iter()
+        .map(|(topic, key, _)| {
+            assert_eq!(topic, "occurrences");
+            key.clone().unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec!["kafka-1", "kafka-2", "kafka-3"]);
+    let occurrence: serde_json::Value = serde_json::from_slice(&messages[2].2).unwrap();
+    assert_eq!(occurrence["apply"][0]["block_identifier"]["index"], 3);
+}
+
+#[tokio::test]
+async fn it_retries_kafka_occurrences_following_the_action_settings() {
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "kafka".into(),
+        owner_uuid: None,
+        name: "kafka".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: Some(1),
+        start_block_offset_from_tip: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::Kafka(KafkaHook {
+            brokers: vec!["localhost:9092".into()],
+            topic: "occurrences".into(),
+            key_template: None,
+            max_retries: Some(2),
+            initial_backoff_ms: Some(10),
+            backoff_multiplier: None,
+        }),
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let block = build_synthetic_bitcoin_block(&predicate_spec, &Context::empty()).unwrap();
+
+    let producers_created = Arc::new(AtomicUsize::new(0));
+    let mut kafka_writer = {
+        let producers_created = producers_created.clone();
+        KafkaTopicWriter::with_producer_factory(Box::new(move |_hook: &KafkaHook| {
+            producers_created.fetch_add(1, Ordering::SeqCst);
+            Err("broker unavailable".to_string())
+        }))
+    };
+    let config = Config::default(true, false, false, &None).unwrap();
+    let trigger = BitcoinTriggerChainhook {
+        chainhook: &predicate_spec,
+        apply: vec![(block.transactions.iter().collect(), &block)],
+        rollback: vec![],
+    };
+    let res = execute_predicates_action(
+        vec![trigger],
+        &mut HttpOccurrenceBatches::new(),
+        &mut RedisStreamWriter::new(),
+        &mut kafka_writer,
+        &DeliveredOccurrences::new(0),
+        false,
+        1,
+        &config.get_event_observer_config(),
+        &Context {
+            logger: Some(hiro_system_kit::log::setup_logger()),
+            tracer: false,
+        },
+    )
+    .await;
+
+    assert!(res.is_err());
+    assert_eq!(producers_created.load(Ordering::SeqCst), 3);
+}
+
+#[test_case(100, false, vec![3] ; "all occurrences in one request")]
+#[test_case(2, false, vec![2, 1] ; "chunked by max batch size")]
+#[test_case(100, true, vec![1, 1, 1] ; "hooks configured differently are not batched together")]
+#[tokio::test]
+async fn it_posts_the_occurrences_of_a_block_together(
+    max_batch_size: usize,
+    distinct_hooks: bool,
+    expected_batches: Vec<usize>,
+) {
+    let http_port = get_free_port().unwrap();
+    let bodies = Arc::new(Mutex::new(vec![]));
+    let endpoint = RecordingEndpoint {
+        bodies: bodies.clone(),
+    };
+    let _ = hiro_system_kit::thread_named("Http endpoint")
+        .spawn(move || {
+            let future = mock_recording_http_endpoint(http_port, endpoint);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let predicate_specs = (0..3)
+        .map(|i| BitcoinChainhookSpecification {
+            uuid: format!("batch-{i}"),
+            owner_uuid: None,
+            name: format!("batch-{i}"),
+            network: BitcoinNetwork::Regtest,
+            version: 1,
+            blocks: None,
+            start_block: Some(1),
+            start_block_offset_from_tip: None,
+            end_block: None,
+            expire_after_occurrence: None,
+            predicate: BitcoinPredicateType::Block,
+            action: HookAction::HttpPost(HttpHook {
+                url: format!("http://localhost:{http_port}/"),
+                authorization_header: match distinct_hooks {
+                    true => format!("Bearer {i}"),
+                    false => "".into(),
+                },
+                batching: Some(OccurrenceBatchingPolicy {
+                    window_secs: 60,
+                    max_batch_size,
+                }),
+                max_retries: None,
+                initial_backoff_ms: None,
+                backoff_multiplier: None,
+                signing_secret: None,
+                template: None,
+                gzip: None,
+                gzip_threshold_bytes: None,
+                include_raw_tx: None,
+            }),
+            include_proof: false,
+            include_inputs: false,
+            include_outputs: false,
+            include_witness: false,
+            identifier_format: None,
+            stop_on_match: None,
+            trigger_on: None,
+            min_confirmations: None,
+            enabled: true,
+            expired_at: None,
+        })
+        .collect::<Vec<_>>();
+    let block = build_synthetic_bitcoin_block(&predicate_specs[0], &Context::empty()).unwrap();
+    let triggers = predicate_specs
+        .iter()
+        .map(|predicate_spec| BitcoinTriggerChainhook {
+            chainhook: predicate_spec,
+            apply: vec![(block.transactions.iter().collect(), &block)],
+            rollback: vec![],
+        })
+        .collect::<Vec<_>>();
+    let config = Config::default(true, false, false, &None).unwrap();
+    let mut batches = HttpOccurrenceBatches::new();
+
+    let actions_triggered = execute_predicates_action(
+        triggers,
+        &mut batches,
+        &mut RedisStreamWriter::new(),
+        &mut KafkaTopicWriter::new(),
+        &DeliveredOccurrences::new(0),
+        false,
+        1,
+        &config.get_event_observer_config(),
+        &Context::empty(),
+    )
+    .await
+    .unwrap();
+    for (http, occurrences) in batches.flush().into_iter() {
+        send_batched_http_occurrences(
+            &http,
+            occurrences,
+            &DeliveredOccurrences::new(0),
+            &Context::empty(),
+        )
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(actions_triggered, 3);
+    let bodies = bodies.lock().unwrap();
+    let batches = bodies
+        .iter()
+        .map(|body| serde_json::from_str::<serde_json::Value>(body).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        batches
+            .iter()
+            .map(|batch| batch["occurrences"].as_array().unwrap().len())
+            .collect::<Vec<_>>(),
+        expected_batches
+    );
+    let uuids = batches
+        .iter()
+        .flat_map(|batch| batch["occurrences"].as_array().unwrap().clone())
+        .map(|occurrence| {
+            assert_eq!(occurrence["apply"][0]["block_identifier"]["index"], 1);
+            assert_eq!(occurrence["rollback"], serde_json::json!([]));
+            occurrence["chainhook"]["uuid"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(uuids, vec!["batch-0", "batch-1", "batch-2"]);
+}
+
+#[tokio::test]
+async fn it_records_batched_occurrences_as_delivered_once_their_batch_is() {
+    let http_port = get_free_port().unwrap();
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "batched".into(),
+        owner_uuid: None,
+        name: "batched".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: Some(1),
+        start_block_offset_from_tip: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::HttpPost(HttpHook {
+            url: format!("http://localhost:{http_port}/"),
+            authorization_header: "".into(),
+            batching: Some(OccurrenceBatchingPolicy {
+                window_secs: 60,
+                max_batch_size: 10,
+            }),
+            max_retries: Some(0),
+            initial_backoff_ms: None,
+            backoff_multiplier: None,
+            signing_secret: None,
+            template: None,
+            gzip: None,
+            gzip_threshold_bytes: None,
+            include_raw_tx: None,
+        }),
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let block = build_synthetic_bitcoin_block(&predicate_spec, &Context::empty()).unwrap();
+    let delivery_key = (
+        predicate_spec.uuid.clone(),
+        block.block_identifier.hash.clone(),
+        block.transactions[0].transaction_identifier.hash.clone(),
+    );
+    let trigger = BitcoinTriggerChainhook {
+        chainhook: &predicate_spec,
+        apply: vec![(block.transactions.iter().collect(), &block)],
+        rollback: vec![],
+    };
+    let config = Config::default(true, false, false, &None).unwrap();
+    let deliveries = DeliveredOccurrences::new(16);
+    let mut batches = HttpOccurrenceBatches::new();
+
+    execute_predicates_action(
+        vec![trigger],
+        &mut batches,
+        &mut RedisStreamWriter::new(),
+        &mut KafkaTopicWriter::new(),
+        &deliveries,
+        false,
+        1,
+        &config.get_event_observer_config(),
+        &Context::empty(),
+    )
+    .await
+    .unwrap();
+    // Queued, not delivered yet
+    assert!(!deliveries.contains(&delivery_key));
+
+    // Nothing listens on the port of the hook: the batch is not delivered
+    for (http, occurrences) in batches.flush().into_iter() {
+        assert!(
+            send_batched_http_occurrences(&http, occurrences, &deliveries, &Context::empty())
+                .await
+                .is_err()
+        );
+    }
+    assert!(!deliveries.contains(&delivery_key));
+}
+
+#[tokio::test]
+async fn it_delivers_an_occurrence_once() {
+    let http_port = get_free_port().unwrap();
+    let bodies = Arc::new(Mutex::new(vec![]));
+    let endpoint = RecordingEndpoint {
+        bodies: bodies.clone(),
+    };
+    let _ = hiro_system_kit::thread_named("Http endpoint")
+        .spawn(move || {
+            let future = mock_recording_http_endpoint(http_port, endpoint);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let predicate_spec = BitcoinChainhookSpecification {
+        uuid: "deliveries".into(),
+        owner_uuid: None,
+        name: "deliveries".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: Some(1),
+        start_block_offset_from_tip: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Block,
+        action: HookAction::HttpPost(HttpHook {
+            url: format!("http://localhost:{http_port}/"),
+            authorization_header: "".into(),
+            batching: None,
+            max_retries: None,
+            initial_backoff_ms: None,
+            backoff_multiplier: None,
+            signing_secret: None,
+            template: None,
+            gzip: None,
+            gzip_threshold_bytes: None,
+            include_raw_tx: None,
+        }),
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let block = build_synthetic_bitcoin_block(&predicate_spec, &Context::empty()).unwrap();
+    let config = Config::default(true, false, false, &None).unwrap();
+    let deliveries = DeliveredOccurrences::new(16);
+    let ctx = Context {
+        logger: Some(hiro_system_kit::log::setup_logger()),
+        tracer: false,
+    };
+
+    let mut actions_triggered = 0;
+    for _ in 0..2 {
+        let trigger = BitcoinTriggerChainhook {
+            chainhook: &predicate_spec,
+            apply: vec![(block.transactions.iter().collect(), &block)],
+            rollback: vec![],
+        };
+        actions_triggered += execute_predicates_action(
+            vec![trigger],
+            &mut HttpOccurrenceBatches::new(),
+            &mut RedisStreamWriter::new(),
+            &mut KafkaTopicWriter::new(),
+            &deliveries,
+            false,
+            1,
+            &config.get_event_observer_config(),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(actions_triggered, 1);
+    assert_eq!(bodies.lock().unwrap().len(), 1);
+    assert_eq!(deliveries.deduplicated(), 1);
+}
+
+#[test_case(1, 6 ; "sequentially")]
+#[test_case(3, 2 ; "three predicates at once")]
+#[test_case(6, 1 ; "all predicates at once")]
+#[tokio::test]
+async fn it_delivers_the_occurrences_of_several_predicates_concurrently(
+    max_concurrent_deliveries: usize,
+    expected_rounds: u32,
+) {
+    let delay = Duration::from_millis(300);
+    let http_port = get_free_port().unwrap();
+    let unreachable_port = get_free_port().unwrap();
+    let requests = Arc::new(AtomicUsize::new(0));
+    let endpoint = SlowEndpoint {
+        delay,
+        requests: requests.clone(),
+    };
+    let _ = hiro_system_kit::thread_named("Http endpoint")
+        .spawn(move || {
+            let future = mock_slow_http_endpoint(http_port, endpoint);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    // Six predicates posting to the slow endpoint, and a last one to an unreachable endpoint.
+    let predicate_specs = (0..7)
+        .map(|i| BitcoinChainhookSpecification {
+            uuid: format!("concurrent-{i}"),
+            owner_uuid: None,
+            name: format!("concurrent-{i}"),
+            network: BitcoinNetwork::Regtest,
+            version: 1,
+            blocks: None,
+            start_block: Some(1),
+            start_block_offset_from_tip: None,
+            end_block: None,
+            expire_after_occurrence: None,
+            predicate: BitcoinPredicateType::Block,
+            action: HookAction::HttpPost(HttpHook {
+                url: match i {
+                    6 => format!("http://localhost:{unreachable_port}/"),
+                    _ => format!("http://localhost:{http_port}/"),
+                },
+                authorization_header: "".into(),
+                batching: None,
+                max_retries: Some(0),
+                initial_backoff_ms: None,
+                backoff_multiplier: None,
+                signing_secret: None,
+                template: None,
+                gzip: None,
+                gzip_threshold_bytes: None,
+                include_raw_tx: None,
+            }),
+            include_proof: false,
+            include_inputs: false,
+            include_outputs: false,
+            include_witness: false,
+            identifier_format: None,
+            stop_on_match: None,
+            trigger_on: None,
+            min_confirmations: None,
+            enabled: true,
+            expired_at: None,
+        })
+        .collect::<Vec<_>>();
+    let block = build_synthetic_bitcoin_block(&predicate_specs[0], &Context::empty()).unwrap();
+    let triggers = predicate_specs
+        .iter()
+        .map(|predicate_spec| BitcoinTriggerChainhook {
+            chainhook: predicate_spec,
+            apply: vec![(block.transactions.iter().collect(), &block)],
+            rollback: vec![],
+        })
+        .collect::<Vec<_>>();
+    let config = Config::default(true, false, false, &None).unwrap();
+    let ctx = Context {
+        logger: Some(hiro_system_kit::log::setup_logger()),
+        tracer: false,
+    };
+
+    let started_at = Instant::now();
+    let res = execute_predicates_action(
+        triggers,
+        &mut HttpOccurrenceBatches::new(),
+        &mut RedisStreamWriter::new(),
+        &mut KafkaTopicWriter::new(),
+        &DeliveredOccurrences::new(0),
+        false,
+        max_concurrent_deliveries,
+        &config.get_event_observer_config(),
+        &ctx,
+    )
+    .await;
+    let elapsed = started_at.elapsed();
+
+    assert!(res.is_err());
+    assert_eq!(requests.load(Ordering::SeqCst), 6);
+    assert!(elapsed >= delay * expected_rounds, "{:?}", elapsed);
+    assert!(elapsed < delay * (expected_rounds + 1), "{:?}", elapsed);
+}
+
+#[tokio::test]
+async fn it_retries_a_delivery_without_delaying_the_other_predicates() {
+    let delay = Duration::from_millis(300);
+    let http_port = get_free_port().unwrap();
+    let unreachable_port = get_free_port().unwrap();
+    let requests = Arc::new(AtomicUsize::new(0));
+    let endpoint = SlowEndpoint {
+        delay,
+        requests: requests.clone(),
+    };
+    let _ = hiro_system_kit::thread_named("Http endpoint")
+        .spawn(move || {
+            let future = mock_slow_http_endpoint(http_port, endpoint);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    // A first predicate posting to an unreachable endpoint, retried once after twice the
+    // delay of the endpoint, followed by two predicates posting to the slow endpoint.
+    let predicate_specs = (0..3)
+        .map(|i| BitcoinChainhookSpecification {
+            uuid: format!("retried-{i}"),
+            owner_uuid: None,
+            name: format!("retried-{i}"),
+            network: BitcoinNetwork::Regtest,
+            version: 1,
+            blocks: None,
+            start_block: Some(1),
+            start_block_offset_from_tip: None,
+            end_block: None,
+            expire_after_occurrence: None,
+            predicate: BitcoinPredicateType::Block,
+            action: HookAction::HttpPost(HttpHook {
+                url: match i {
+                    0 => format!("http://localhost:{unreachable_port}/"),
+                    _ => format!("http://localhost:{http_port}/"),
+                },
+                authorization_header: "".into(),
+                batching: None,
+                max_retries: Some(1),
+                initial_backoff_ms: Some(2 * delay.as_millis() as u64),
+                backoff_multiplier: None,
+                signing_secret: None,
+                template: None,
+                gzip: None,
+                gzip_threshold_bytes: None,
+                include_raw_tx: None,
+            }),
+            include_proof: false,
+            include_inputs: false,
+            include_outputs: false,
+            include_witness: false,
+            identifier_format: None,
+            stop_on_match: None,
+            trigger_on: None,
+            min_confirmations: None,
+            enabled: true,
+            expired_at: None,
+        })
+        .collect::<Vec<_>>();
+    let block = build_synthetic_bitcoin_block(&predicate_specs[0], &Context::empty()).unwrap();
+    let triggers = predicate_specs
+        .iter()
+        .map(|predicate_spec| BitcoinTriggerChainhook {
+            chainhook: predicate_spec,
+            apply: vec![(block.transactions.iter().collect(), &block)],
+            rollback: vec![],
+        })
+        .collect::<Vec<_>>();
+    let config = Config::default(true, false, false, &None).unwrap();
+    let ctx = Context {
+        logger: Some(hiro_system_kit::log::setup_logger()),
+        tracer: false,
+    };
+
+    let started_at = Instant::now();
+    let res = execute_predicates_action(
+        triggers,
+        &mut HttpOccurrenceBatches::new(),
+        &mut RedisStreamWriter::new(),
+        &mut KafkaTopicWriter::new(),
+        &DeliveredOccurrences::new(0),
+        false,
+        2,
+        &config.get_event_observer_config(),
+        &ctx,
+    )
+    .await;
+    let elapsed = started_at.elapsed();
+
+    // The slow predicates are delivered one after the other while the first one waits
+    // before its retry.
+    assert!(res.is_err());
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    assert!(elapsed >= delay * 2, "{:?}", elapsed);
+    assert!(elapsed < delay * 3, "{:?}", elapsed);
+}
+
+#[tokio::test]
+async fn it_executes_the_actions_in_the_order_of_their_triggers() {
+    let http_port = get_free_port().unwrap();
+    let requests = Arc::new(AtomicUsize::new(0));
+    let endpoint = SlowEndpoint {
+        delay: Duration::from_millis(0),
+        requests: requests.clone(),
+    };
+    let _ = hiro_system_kit::thread_named("Http endpoint")
+        .spawn(move || {
+            let future = mock_slow_http_endpoint(http_port, endpoint);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let (working_dir, _) = create_tmp_working_dir().unwrap();
+    let path = std::path::Path::new(&working_dir).join("occurrences.json");
+    let actions = vec![
+        HookAction::HttpPost(HttpHook {
+            url: format!("http://localhost:{http_port}/"),
+            authorization_header: "".into(),
+            batching: None,
+            max_retries: Some(0),
+            initial_backoff_ms: None,
+            backoff_multiplier: None,
+            signing_secret: None,
+            template: None,
+            gzip: None,
+            gzip_threshold_bytes: None,
+            include_raw_tx: None,
+        }),
+        HookAction::FileAppend(FileHook {
+            path: path.to_str().unwrap().to_string(),
+            format: None,
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx: None,
+        }),
+    ];
+    let predicate_specs = actions
+        .into_iter()
+        .enumerate()
+        .map(|(i, action)| BitcoinChainhookSpecification {
+            uuid: format!("ordered-{i}"),
+            owner_uuid: None,
+            name: format!("ordered-{i}"),
+            network: BitcoinNetwork::Regtest,
+            version: 1,
+            blocks: None,
+            start_block: Some(1),
+            start_block_offset_from_tip: None,
+            end_block: None,
+            expire_after_occurrence: None,
+            predicate: BitcoinPredicateType::Block,
+            action,
+            include_proof: false,
+            include_inputs: false,
+            include_outputs: false,
+            include_witness: false,
+            identifier_format: None,
+            stop_on_match: None,
+            trigger_on: None,
+            min_confirmations: None,
+            enabled: true,
+            expired_at: None,
+        })
+        .collect::<Vec<_>>();
+    let block = build_synthetic_bitcoin_block(&predicate_specs[0], &Context::empty()).unwrap();
+    let triggers = predicate_specs
+        .iter()
+        .map(|predicate_spec| BitcoinTriggerChainhook {
+            chainhook: predicate_spec,
+            apply: vec![(block.transactions.iter().collect(), &block)],
+            rollback: vec![],
+        })
+        .collect::<Vec<_>>();
+    let config = Config::default(true, false, false, &None).unwrap();
+    let ctx = Context {
+        logger: Some(hiro_system_kit::log::setup_logger()),
+        tracer: false,
+    };
+
+    // The file action fails once the HTTP occurrence was delivered.
+    std::fs::create_dir(&path).unwrap();
+    let res = execute_predicates_action(
+        triggers,
+        &mut HttpOccurrenceBatches::new(),
+        &mut RedisStreamWriter::new(),
+        &mut KafkaTopicWriter::new(),
+        &DeliveredOccurrences::new(0),
+        false,
+        1,
+        &config.get_event_observer_config(),
+        &ctx,
+    )
+    .await;
+
+    assert!(res.is_err());
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
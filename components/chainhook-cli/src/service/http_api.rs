@@ -5,27 +5,35 @@ use std::{
 };
 
 use chainhook_sdk::{
-    chainhooks::types::{ChainhookFullSpecification, ChainhookSpecification},
+    chainhooks::{
+        stacks::check_stacks_predicate_contracts,
+        types::{ChainhookFullSpecification, ChainhookSpecification},
+    },
     observer::ObserverCommand,
+    types::StacksNetwork,
     utils::Context,
 };
 use hiro_system_kit::slog;
 use redis::{Commands, Connection};
+use rocket::request::{FromRequest, Outcome, Request};
 use rocket::serde::json::{json, Json, Value as JsonValue};
 use rocket::State;
 use rocket::{
     config::{self, Config, LogLevel},
     Shutdown,
 };
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
 use rocket_okapi::{okapi::openapi3::OpenApi, openapi, openapi_get_routes_spec};
 use std::error::Error;
 
 use crate::config::PredicatesApiConfig;
 
-use super::{open_readwrite_predicates_db_conn, PredicateStatus};
+use super::{get_unacked_occurrences, open_readwrite_predicates_db_conn, PredicateStatus};
 
 pub async fn start_predicate_api_server(
     api_config: PredicatesApiConfig,
+    stacks_network: StacksNetwork,
     observer_commands_tx: Sender<ObserverCommand>,
     ctx: Context,
 ) -> Result<Shutdown, Box<dyn Error>> {
@@ -57,6 +65,7 @@ pub async fn start_predicate_api_server(
     let ignite = rocket::custom(control_config)
         .manage(background_job_tx_mutex)
         .manage(api_config)
+        .manage(stacks_network)
         .manage(ctx_cloned)
         .mount("/", routes)
         .ignite()
@@ -70,6 +79,49 @@ pub async fn start_predicate_api_server(
     Ok(ingestion_shutdown)
 }
 
+/// Bearer token attached to the incoming request, if any.
+pub struct ApiToken(Option<String>);
+
+impl ApiToken {
+    pub fn is_authorized(&self, api_config: &PredicatesApiConfig) -> bool {
+        match api_config.auth_token {
+            None => true,
+            Some(ref expected_token) => self.0.as_ref() == Some(expected_token),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiToken {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let token = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .map(|token| token.to_string());
+        Outcome::Success(ApiToken(token))
+    }
+}
+
+impl<'r> OpenApiFromRequest<'r> for ApiToken {
+    fn from_request_input(
+        _gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(RequestHeaderInput::None)
+    }
+}
+
+fn unauthorized_response() -> Json<JsonValue> {
+    Json(json!({
+        "status": 401,
+        "error": "Missing or invalid authorization token",
+    }))
+}
+
 #[openapi(tag = "Health Check")]
 #[get("/ping")]
 fn handle_ping(ctx: &State<Context>) -> Json<JsonValue> {
@@ -83,10 +135,14 @@ fn handle_ping(ctx: &State<Context>) -> Json<JsonValue> {
 #[openapi(tag = "Managing Predicates")]
 #[get("/v1/chainhooks", format = "application/json")]
 fn handle_get_predicates(
+    api_token: ApiToken,
     api_config: &State<PredicatesApiConfig>,
     ctx: &State<Context>,
 ) -> Json<JsonValue> {
     ctx.try_log(|logger| slog::info!(logger, "Handling HTTP GET /v1/chainhooks"));
+    if !api_token.is_authorized(api_config) {
+        return unauthorized_response();
+    }
     match open_readwrite_predicates_db_conn(api_config) {
         Ok(mut predicates_db_conn) => {
             let predicates = match get_entries_from_predicates_db(&mut predicates_db_conn, &ctx) {
@@ -119,13 +175,18 @@ fn handle_get_predicates(
 
 #[openapi(tag = "Managing Predicates")]
 #[post("/v1/chainhooks", format = "application/json", data = "<predicate>")]
-fn handle_create_predicate(
+async fn handle_create_predicate(
     predicate: Result<Json<ChainhookFullSpecification>, rocket::serde::json::Error>,
+    api_token: ApiToken,
     api_config: &State<PredicatesApiConfig>,
+    stacks_network: &State<StacksNetwork>,
     background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
     ctx: &State<Context>,
 ) -> Json<JsonValue> {
     ctx.try_log(|logger| slog::info!(logger, "Handling HTTP POST /v1/chainhooks"));
+    if !api_token.is_authorized(api_config) {
+        return unauthorized_response();
+    }
     let predicate = match predicate {
         Err(e) => {
             return Json(json!({
@@ -145,6 +206,21 @@ fn handle_create_predicate(
         }
     };
 
+    if let (Some(stacks_node_rpc_url), ChainhookFullSpecification::Stacks(spec)) =
+        (&api_config.stacks_node_rpc_url, &predicate)
+    {
+        if let Some(network_spec) = spec.networks.get(stacks_network.inner()) {
+            if let Err(e) =
+                check_stacks_predicate_contracts(&network_spec.predicate, stacks_node_rpc_url).await
+            {
+                return Json(json!({
+                    "status": 422,
+                    "error": e,
+                }));
+            }
+        }
+    }
+
     let predicate_uuid = predicate.get_uuid().to_string();
 
     if let Ok(mut predicates_db_conn) = open_readwrite_predicates_db_conn(api_config) {
@@ -181,6 +257,7 @@ fn handle_create_predicate(
 #[get("/v1/chainhooks/<predicate_uuid>", format = "application/json")]
 fn handle_get_predicate(
     predicate_uuid: String,
+    api_token: ApiToken,
     api_config: &State<PredicatesApiConfig>,
     ctx: &State<Context>,
 ) -> Json<JsonValue> {
@@ -191,6 +268,9 @@ fn handle_get_predicate(
             predicate_uuid
         )
     });
+    if !api_token.is_authorized(api_config) {
+        return unauthorized_response();
+    }
 
     match open_readwrite_predicates_db_conn(api_config) {
         Ok(mut predicates_db_conn) => {
@@ -219,10 +299,65 @@ fn handle_get_predicate(
     }
 }
 
+#[openapi(tag = "Managing Predicates")]
+#[get(
+    "/v1/chainhooks/<predicate_uuid>/unacked_occurrences",
+    format = "application/json"
+)]
+fn handle_get_unacked_occurrences(
+    predicate_uuid: String,
+    api_token: ApiToken,
+    api_config: &State<PredicatesApiConfig>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        slog::info!(
+            logger,
+            "Handling HTTP GET /v1/chainhooks/{}/unacked_occurrences",
+            predicate_uuid
+        )
+    });
+    if !api_token.is_authorized(api_config) {
+        return unauthorized_response();
+    }
+
+    match open_readwrite_predicates_db_conn(api_config) {
+        Ok(mut predicates_db_conn) => {
+            match get_unacked_occurrences(&predicate_uuid, &mut predicates_db_conn) {
+                Ok(occurrences) => {
+                    let result = occurrences
+                        .into_iter()
+                        .map(|(idempotency_key, status)| {
+                            json!({
+                                "idempotency_key": idempotency_key,
+                                "status": status,
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    Json(json!({
+                        "status": 200,
+                        "result": result
+                    }))
+                }
+                Err(e) => Json(json!({
+                    "status": 500,
+                    "message": e,
+                })),
+            }
+        }
+        Err(e) => Json(json!({
+            "status": 500,
+            "message": e,
+        })),
+    }
+}
+
 #[openapi(tag = "Managing Predicates")]
 #[delete("/v1/chainhooks/stacks/<predicate_uuid>", format = "application/json")]
 fn handle_delete_stacks_predicate(
     predicate_uuid: String,
+    api_token: ApiToken,
+    api_config: &State<PredicatesApiConfig>,
     background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
     ctx: &State<Context>,
 ) -> Json<JsonValue> {
@@ -233,6 +368,9 @@ fn handle_delete_stacks_predicate(
             predicate_uuid
         )
     });
+    if !api_token.is_authorized(api_config) {
+        return unauthorized_response();
+    }
 
     let background_job_tx = background_job_tx.inner();
     match background_job_tx.lock() {
@@ -252,6 +390,8 @@ fn handle_delete_stacks_predicate(
 #[delete("/v1/chainhooks/bitcoin/<predicate_uuid>", format = "application/json")]
 fn handle_delete_bitcoin_predicate(
     predicate_uuid: String,
+    api_token: ApiToken,
+    api_config: &State<PredicatesApiConfig>,
     background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
     ctx: &State<Context>,
 ) -> Json<JsonValue> {
@@ -262,6 +402,9 @@ fn handle_delete_bitcoin_predicate(
             predicate_uuid
         )
     });
+    if !api_token.is_authorized(api_config) {
+        return unauthorized_response();
+    }
 
     let background_job_tx = background_job_tx.inner();
     match background_job_tx.lock() {
@@ -277,6 +420,123 @@ fn handle_delete_bitcoin_predicate(
     }))
 }
 
+#[openapi(tag = "Managing Predicates")]
+#[delete("/v1/chainhooks/<predicate_uuid>", format = "application/json")]
+fn handle_delete_predicate(
+    predicate_uuid: String,
+    api_token: ApiToken,
+    api_config: &State<PredicatesApiConfig>,
+    background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        slog::info!(
+            logger,
+            "Handling HTTP DELETE /v1/chainhooks/{}",
+            predicate_uuid
+        )
+    });
+    if !api_token.is_authorized(api_config) {
+        return unauthorized_response();
+    }
+
+    let predicate = match open_readwrite_predicates_db_conn(api_config) {
+        Ok(mut predicates_db_conn) => match get_entry_from_predicates_db(
+            &ChainhookSpecification::either_stx_or_btc_key(&predicate_uuid),
+            &mut predicates_db_conn,
+            &ctx,
+        ) {
+            Ok(Some((predicate, _))) => predicate,
+            _ => {
+                return Json(json!({
+                    "status": 404,
+                }))
+            }
+        },
+        Err(e) => {
+            return Json(json!({
+                "status": 500,
+                "message": e,
+            }))
+        }
+    };
+
+    let command = match predicate {
+        ChainhookSpecification::Stacks(_) => {
+            ObserverCommand::DeregisterStacksPredicate(predicate_uuid)
+        }
+        ChainhookSpecification::Bitcoin(_) => {
+            ObserverCommand::DeregisterBitcoinPredicate(predicate_uuid)
+        }
+    };
+    let background_job_tx = background_job_tx.inner();
+    match background_job_tx.lock() {
+        Ok(tx) => {
+            let _ = tx.send(command);
+        }
+        _ => {}
+    };
+
+    Json(json!({
+        "status": 200,
+        "result": "Ok",
+    }))
+}
+
+#[openapi(tag = "Managing Dispatch")]
+#[post("/v1/dispatch/pause")]
+fn handle_pause_dispatch(
+    api_token: ApiToken,
+    api_config: &State<PredicatesApiConfig>,
+    background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| slog::info!(logger, "Handling HTTP POST /v1/dispatch/pause"));
+    if !api_token.is_authorized(api_config) {
+        return unauthorized_response();
+    }
+
+    let background_job_tx = background_job_tx.inner();
+    match background_job_tx.lock() {
+        Ok(tx) => {
+            let _ = tx.send(ObserverCommand::PauseDispatch);
+        }
+        _ => {}
+    };
+
+    Json(json!({
+        "status": 200,
+        "result": "Ok",
+    }))
+}
+
+#[openapi(tag = "Managing Dispatch")]
+#[post("/v1/dispatch/resume")]
+fn handle_resume_dispatch(
+    api_token: ApiToken,
+    api_config: &State<PredicatesApiConfig>,
+    background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| slog::info!(logger, "Handling HTTP POST /v1/dispatch/resume"));
+    if !api_token.is_authorized(api_config) {
+        return unauthorized_response();
+    }
+
+    let background_job_tx = background_job_tx.inner();
+    match background_job_tx.lock() {
+        Ok(tx) => {
+            let _ = tx.send(ObserverCommand::ResumeDispatch);
+        }
+        _ => {}
+    };
+
+    Json(json!({
+        "status": 200,
+        "result": "Ok",
+    }))
+}
+
 pub fn get_entry_from_predicates_db(
     predicate_key: &str,
     predicate_db_conn: &mut Connection,
@@ -368,13 +628,19 @@ pub fn get_routes_spec() -> (Vec<rocket::Route>, OpenApi) {
         handle_ping,
         handle_get_predicates,
         handle_get_predicate,
+        handle_get_unacked_occurrences,
         handle_create_predicate,
+        handle_delete_predicate,
         handle_delete_bitcoin_predicate,
-        handle_delete_stacks_predicate
+        handle_delete_stacks_predicate,
+        handle_pause_dispatch,
+        handle_resume_dispatch
     ]
 }
 
-fn serialized_predicate_with_status(
+/// The predicate API responses leave the `then_that` actions out, as they may hold secrets
+/// (`authorization_header`, `signing_secret`).
+pub(crate) fn serialized_predicate_with_status(
     predicate: &ChainhookSpecification,
     status: &PredicateStatus,
 ) -> JsonValue {
@@ -1,10 +1,14 @@
 pub(crate) mod http_api;
+#[cfg(feature = "predicates_watcher")]
+pub mod predicates_watcher;
 mod runloops;
 
 use crate::config::{Config, PredicatesApi, PredicatesApiConfig};
 use crate::scan::stacks::consolidate_local_stacks_chainstate_using_csv;
 use crate::service::http_api::{load_predicates_from_redis, start_predicate_api_server};
-use crate::service::runloops::{start_bitcoin_scan_runloop, start_stacks_scan_runloop};
+use crate::service::runloops::{
+    start_bitcoin_scan_runloop, start_stacks_scan_runloop, ScanCancellations,
+};
 use crate::storage::{
     confirm_entries_in_stacks_blocks, draft_entries_in_stacks_blocks, open_readwrite_stacks_db_conn,
 };
@@ -13,13 +17,14 @@ use chainhook_sdk::chainhooks::types::{ChainhookConfig, ChainhookFullSpecificati
 
 use chainhook_sdk::chainhooks::types::ChainhookSpecification;
 use chainhook_sdk::observer::{
-    start_event_observer, HookExpirationData, ObserverCommand, ObserverEvent,
-    PredicateEvaluationReport,
+    start_event_observer, HookExpirationData, ObserverCommand, ObserverEvent, OccurrenceDelivery,
+    OccurrenceDeliveryStatus, PredicateEvaluationReport,
 };
 use chainhook_sdk::types::{Chain, StacksChainEvent};
 use chainhook_sdk::utils::Context;
 use redis::{Commands, Connection};
 
+use std::collections::HashMap;
 use std::sync::mpsc::channel;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -40,6 +45,8 @@ impl Service {
         predicates_from_startup: Vec<ChainhookFullSpecification>,
     ) -> Result<(), String> {
         let mut chainhook_config = ChainhookConfig::new();
+        chainhook_config.duplicate_uuid_policy =
+            self.config.predicates.duplicate_uuid_policy.clone();
 
         // store all predicates from Redis that were in the process of scanning when
         // chainhook was shutdown - we need to resume where we left off
@@ -59,6 +66,18 @@ impl Service {
             };
             for (predicate, status) in registered_predicates.into_iter() {
                 let predicate_uuid = predicate.uuid().to_string();
+                // Stored predicates may predate stricter validation rules: they are still
+                // loaded, so that upgrading does not drop them.
+                if let ChainhookSpecification::Stacks(ref spec) = predicate {
+                    if let Err(errors) = spec.validate() {
+                        warn!(
+                            self.ctx.expect_logger(),
+                            "Predicate {} retrieved from storage is no longer valid: {}",
+                            predicate_uuid,
+                            errors.join(", ")
+                        );
+                    }
+                }
                 match status {
                     PredicateStatus::Scanning(scanning_data) => {
                         leftover_scans.push((predicate.clone(), Some(scanning_data)));
@@ -84,11 +103,14 @@ impl Service {
                         continue;
                     }
                 }
+                let description = predicate.describe();
                 match chainhook_config.register_specification(predicate) {
                     Ok(_) => {
                         info!(
                             self.ctx.expect_logger(),
-                            "Predicate {} retrieved from storage and loaded", predicate_uuid,
+                            "Predicate {} retrieved from storage and loaded ({})",
+                            predicate_uuid,
+                            description,
                         );
                     }
                     Err(e) => {
@@ -135,8 +157,9 @@ impl Service {
                     newly_registered_predicates.push(spec.clone());
                     info!(
                         self.ctx.expect_logger(),
-                        "Predicate {} retrieved from config and loaded",
+                        "Predicate {} retrieved from config and loaded ({})",
                         spec.uuid(),
+                        spec.describe(),
                     );
                 }
                 Err(e) => {
@@ -162,17 +185,22 @@ impl Service {
                 consolidate_local_stacks_chainstate_using_csv(&mut self.config, &self.ctx).await;
         }
 
+        // Scans get cancelled when their predicate is deregistered, or when the service terminates
+        let scan_cancellations = ScanCancellations::new();
+
         // Stacks scan operation threadpool
         let (stacks_scan_op_tx, stacks_scan_op_rx) = crossbeam_channel::unbounded();
         let ctx = self.ctx.clone();
         let config = self.config.clone();
         let observer_command_tx_moved = observer_command_tx.clone();
+        let cancellations = scan_cancellations.clone();
         let _ = hiro_system_kit::thread_named("Stacks scan runloop")
             .spawn(move || {
                 start_stacks_scan_runloop(
                     &config,
                     stacks_scan_op_rx,
                     observer_command_tx_moved,
+                    cancellations,
                     &ctx,
                 );
             })
@@ -183,12 +211,14 @@ impl Service {
         let ctx = self.ctx.clone();
         let config = self.config.clone();
         let observer_command_tx_moved = observer_command_tx.clone();
+        let cancellations = scan_cancellations.clone();
         let _ = hiro_system_kit::thread_named("Bitcoin scan runloop")
             .spawn(move || {
                 start_bitcoin_scan_runloop(
                     &config,
                     bitcoin_scan_op_rx,
                     observer_command_tx_moved,
+                    cancellations,
                     &ctx,
                 );
             })
@@ -203,14 +233,36 @@ impl Service {
             );
             let ctx = self.ctx.clone();
             let api_config = api_config.clone();
+            let stacks_network = config.network.stacks_network.clone();
             let moved_observer_command_tx = observer_command_tx.clone();
             // Test and initialize a database connection
             let _ = hiro_system_kit::thread_named("HTTP Predicate API").spawn(move || {
-                let future = start_predicate_api_server(api_config, moved_observer_command_tx, ctx);
+                let future = start_predicate_api_server(
+                    api_config,
+                    stacks_network,
+                    moved_observer_command_tx,
+                    ctx,
+                );
                 let _ = hiro_system_kit::nestable_block_on(future);
             });
         }
 
+        // Apply the changes made to the predicates directory, if required
+        #[cfg(feature = "predicates_watcher")]
+        if let Some(ref predicates_dir) = self.config.predicates.predicates_dir {
+            let ctx = self.ctx.clone();
+            let predicates_dir = predicates_dir.clone();
+            let moved_observer_command_tx = observer_command_tx.clone();
+            let _ =
+                hiro_system_kit::thread_named("Predicates directory watcher").spawn(move || {
+                    predicates_watcher::start_predicates_dir_watcher(
+                        predicates_dir,
+                        moved_observer_command_tx,
+                        &ctx,
+                    );
+                });
+        }
+
         let observer_event_tx_moved = observer_event_tx.clone();
         let moved_observer_command_tx = observer_command_tx.clone();
         let _ = start_event_observer(
@@ -315,6 +367,7 @@ impl Service {
                     }
                 }
                 ObserverEvent::PredicateDeregistered(spec) => {
+                    scan_cancellations.cancel(spec.uuid());
                     if let PredicatesApi::On(ref config) = self.config.http_api {
                         let Ok(mut predicates_db_conn) =
                             open_readwrite_predicates_db_conn_verbose(&config, &ctx)
@@ -331,6 +384,30 @@ impl Service {
                                 e.to_string()
                             );
                         }
+                        let res: Result<(), redis::RedisError> =
+                            predicates_db_conn.del(get_occurrence_deliveries_key(spec.uuid()));
+                        if let Err(e) = res {
+                            error!(
+                                self.ctx.expect_logger(),
+                                "unable to delete predicate occurrence deliveries: {}",
+                                e.to_string()
+                            );
+                        }
+                    }
+                }
+                ObserverEvent::OccurrenceDeliveryUpdated((delivery, status)) => {
+                    if let PredicatesApi::On(ref config) = self.config.http_api {
+                        let Ok(mut predicates_db_conn) =
+                            open_readwrite_predicates_db_conn_verbose(&config, &ctx)
+                        else {
+                            continue;
+                        };
+                        update_occurrence_delivery_status(
+                            &delivery,
+                            status,
+                            &mut predicates_db_conn,
+                            &ctx,
+                        );
                     }
                 }
                 ObserverEvent::BitcoinChainEvent((chain_update, report)) => {
@@ -537,6 +614,7 @@ impl Service {
                 }
                 ObserverEvent::Terminate => {
                     info!(self.ctx.expect_logger(), "Terminating runloop");
+                    scan_cancellations.cancel_all();
                     break;
                 }
                 _ => {}
@@ -967,6 +1045,70 @@ pub fn set_confirmed_expiration_status(
     );
 }
 
+fn get_occurrence_deliveries_key(predicate_uuid: &str) -> String {
+    format!("deliveries:{}", predicate_uuid)
+}
+
+pub fn update_occurrence_delivery_status(
+    delivery: &OccurrenceDelivery,
+    status: OccurrenceDeliveryStatus,
+    predicates_db_conn: &mut Connection,
+    ctx: &Context,
+) {
+    let key = get_occurrence_deliveries_key(&delivery.predicate_uuid);
+    let serialized_status = json!(status).to_string();
+    // Only the occurrences not acknowledged yet are kept, so that the hash of a predicate
+    // doesn't grow with every delivery.
+    let res = match status {
+        OccurrenceDeliveryStatus::Acked => {
+            predicates_db_conn.hdel::<_, _, ()>(&key, &delivery.idempotency_key)
+        }
+        _ => predicates_db_conn.hset::<_, _, _, ()>(
+            &key,
+            &delivery.idempotency_key,
+            &serialized_status,
+        ),
+    };
+    if let Err(e) = res {
+        error!(
+            ctx.expect_logger(),
+            "Error updating occurrence delivery status: {}",
+            e.to_string()
+        );
+    } else {
+        debug!(
+            ctx.expect_logger(),
+            "Updating occurrence {} delivery status: {serialized_status}", delivery.idempotency_key
+        );
+    }
+}
+
+/// Returns the idempotency keys of the occurrences of a predicate that were not acknowledged
+/// by the receiver, along with their delivery status (pending or failed).
+pub fn get_unacked_occurrences(
+    predicate_uuid: &str,
+    predicates_db_conn: &mut Connection,
+) -> Result<Vec<(String, OccurrenceDeliveryStatus)>, String> {
+    let key = get_occurrence_deliveries_key(predicate_uuid);
+    let entries: HashMap<String, String> = predicates_db_conn.hgetall(&key).map_err(|e| {
+        format!(
+            "unable to load occurrence deliveries associated with key {}: {}",
+            key,
+            e.to_string()
+        )
+    })?;
+    let mut unacked_occurrences = vec![];
+    for (idempotency_key, encoded_status) in entries.into_iter() {
+        let status: OccurrenceDeliveryStatus =
+            serde_json::from_str(&encoded_status).map_err(|e| format!("{}", e.to_string()))?;
+        if status != OccurrenceDeliveryStatus::Acked {
+            unacked_occurrences.push((idempotency_key, status));
+        }
+    }
+    unacked_occurrences.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(unacked_occurrences)
+}
+
 fn get_predicate_expiration_key(chain: &Chain, block_height: u64) -> String {
     match chain {
         Chain::Bitcoin => format!("expires_at:bitcoin_block:{}", block_height),
@@ -0,0 +1,112 @@
+use crate::cli::{list_predicates_paths_in_dir, load_predicates_from_dir};
+
+use chainhook_sdk::chainhooks::types::ChainhookFullSpecification;
+use chainhook_sdk::observer::ObserverCommand;
+use chainhook_sdk::utils::Context;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, SystemTime};
+
+pub const PREDICATES_DIR_POLLING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Polls `predicates_dir` and, whenever one of its `.json` files is added, modified or
+/// removed, re-reads the directory and reconciles the predicates registered from it.
+/// The predicates found when the watcher starts are expected to have been registered on startup.
+pub fn start_predicates_dir_watcher(
+    predicates_dir: String,
+    observer_command_tx: Sender<ObserverCommand>,
+    ctx: &Context,
+) {
+    let mut fingerprint = fingerprint_predicates_dir(&predicates_dir);
+    let mut active_predicates = load_predicates_from_dir(&predicates_dir)
+        .map(|(predicates, _)| predicates)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|predicate| (predicate.get_uuid().to_string(), predicate))
+        .collect::<BTreeMap<_, _>>();
+    loop {
+        std::thread::sleep(PREDICATES_DIR_POLLING_INTERVAL);
+        let latest_fingerprint = fingerprint_predicates_dir(&predicates_dir);
+        if latest_fingerprint == fingerprint {
+            continue;
+        }
+        fingerprint = latest_fingerprint;
+
+        let (loaded_predicates, errors) = match load_predicates_from_dir(&predicates_dir) {
+            Ok(res) => res,
+            Err(e) => {
+                warn!(ctx.expect_logger(), "Unable to reload predicates: {e}");
+                continue;
+            }
+        };
+        for e in errors.iter() {
+            error!(ctx.expect_logger(), "Skipping predicate: {e}");
+        }
+        let commands = reconcile_predicates(&mut active_predicates, loaded_predicates);
+        info!(
+            ctx.expect_logger(),
+            "Predicates directory {} changed, applying {} update(s)",
+            predicates_dir,
+            commands.len()
+        );
+        for command in commands.into_iter() {
+            if observer_command_tx.send(command).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Computes the commands turning `active_predicates` into `loaded_predicates`: predicates
+/// removed from the directory are deregistered, new ones registered, and the modified ones
+/// deregistered then registered again. `active_predicates` is updated accordingly.
+pub fn reconcile_predicates(
+    active_predicates: &mut BTreeMap<String, ChainhookFullSpecification>,
+    loaded_predicates: Vec<ChainhookFullSpecification>,
+) -> Vec<ObserverCommand> {
+    let loaded_predicates = loaded_predicates
+        .into_iter()
+        .map(|predicate| (predicate.get_uuid().to_string(), predicate))
+        .collect::<BTreeMap<_, _>>();
+    let mut commands = vec![];
+    for (uuid, predicate) in active_predicates.iter() {
+        match loaded_predicates.get(uuid) {
+            Some(loaded_predicate) if loaded_predicate == predicate => {}
+            _ => commands.push(build_deregistration_command(predicate)),
+        }
+    }
+    for (uuid, loaded_predicate) in loaded_predicates.iter() {
+        match active_predicates.get(uuid) {
+            Some(predicate) if predicate == loaded_predicate => {}
+            _ => commands.push(ObserverCommand::RegisterPredicate(loaded_predicate.clone())),
+        }
+    }
+    *active_predicates = loaded_predicates;
+    commands
+}
+
+fn build_deregistration_command(predicate: &ChainhookFullSpecification) -> ObserverCommand {
+    match predicate {
+        ChainhookFullSpecification::Bitcoin(spec) => {
+            ObserverCommand::DeregisterBitcoinPredicate(spec.uuid.clone())
+        }
+        ChainhookFullSpecification::Stacks(spec) => {
+            ObserverCommand::DeregisterStacksPredicate(spec.uuid.clone())
+        }
+    }
+}
+
+fn fingerprint_predicates_dir(predicates_dir: &str) -> BTreeMap<PathBuf, Option<SystemTime>> {
+    list_predicates_paths_in_dir(predicates_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|path| {
+            let modified = std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (path, modified)
+        })
+        .collect()
+}
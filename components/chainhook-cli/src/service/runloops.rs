@@ -1,9 +1,12 @@
-use std::sync::mpsc::Sender;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc::Sender, Arc, Mutex};
 
 use chainhook_sdk::{
     chainhooks::types::{
         BitcoinChainhookSpecification, ChainhookSpecification, StacksChainhookSpecification,
     },
+    indexer::{bitcoin::BitcoinBlockCache, stacks::StacksBlockCache},
     observer::ObserverCommand,
     utils::Context,
 };
@@ -12,7 +15,11 @@ use threadpool::ThreadPool;
 use crate::{
     config::{Config, PredicatesApi},
     scan::{
-        bitcoin::scan_bitcoin_chainstate_via_rpc_using_predicate,
+        bitcoin::{
+            scan_bitcoin_chainstate_via_rpc_using_predicate,
+            scan_bitcoin_chainstate_via_rpc_using_predicates,
+        },
+        common::{DeliveredOccurrences, ScanError, ScanSummary},
         stacks::scan_stacks_chainstate_via_rocksdb_using_predicate,
     },
     service::{
@@ -23,6 +30,49 @@ use crate::{
 
 use super::ScanningData;
 
+/// Cancellation flags of the ongoing scans, raised when their predicate gets deregistered or
+/// when the service terminates. Cancelled scans stop once the actions of the block being
+/// processed are delivered.
+#[derive(Clone, Default)]
+pub struct ScanCancellations {
+    terminating: Arc<AtomicBool>,
+    scans: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+impl ScanCancellations {
+    pub fn new() -> ScanCancellations {
+        ScanCancellations::default()
+    }
+
+    /// Returns the cancellation flag of a scan of the predicate, already raised if the service
+    /// is terminating.
+    pub fn start_scan(&self, predicate_uuid: &str) -> Arc<AtomicBool> {
+        let cancellation = Arc::new(AtomicBool::new(self.terminating.load(Ordering::SeqCst)));
+        self.scans
+            .lock()
+            .unwrap()
+            .insert(predicate_uuid.to_string(), cancellation.clone());
+        cancellation
+    }
+
+    pub fn end_scan(&self, predicate_uuid: &str) {
+        self.scans.lock().unwrap().remove(predicate_uuid);
+    }
+
+    pub fn cancel(&self, predicate_uuid: &str) {
+        if let Some(cancellation) = self.scans.lock().unwrap().get(predicate_uuid) {
+            cancellation.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn cancel_all(&self) {
+        self.terminating.store(true, Ordering::SeqCst);
+        for cancellation in self.scans.lock().unwrap().values() {
+            cancellation.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
 pub fn start_stacks_scan_runloop(
     config: &Config,
     stacks_scan_op_rx: crossbeam_channel::Receiver<(
@@ -30,13 +80,20 @@ pub fn start_stacks_scan_runloop(
         Option<ScanningData>,
     )>,
     observer_command_tx: Sender<ObserverCommand>,
+    cancellations: ScanCancellations,
     ctx: &Context,
 ) {
     let stacks_scan_pool = ThreadPool::new(config.limits.max_number_of_concurrent_stacks_scans);
+    // Shared by the scans, so that predicates scanning overlapping ranges load blocks once.
+    let block_cache = Arc::new(StacksBlockCache::new(
+        config.limits.max_number_of_cached_stacks_blocks,
+    ));
     while let Ok((predicate_spec, unfinished_scan_data)) = stacks_scan_op_rx.recv() {
         let moved_ctx = ctx.clone();
         let moved_config = config.clone();
+        let block_cache = block_cache.clone();
         let observer_command_tx = observer_command_tx.clone();
+        let cancellations = cancellations.clone();
         stacks_scan_pool.execute(move || {
             let stacks_db_conn =
                 match open_readonly_stacks_db_conn(&moved_config.expected_cache_path(), &moved_ctx)
@@ -52,14 +109,19 @@ pub fn start_stacks_scan_runloop(
                     }
                 };
 
+            let cancellation = cancellations.start_scan(&predicate_spec.uuid);
             let op = scan_stacks_chainstate_via_rocksdb_using_predicate(
                 &predicate_spec,
                 unfinished_scan_data,
                 &stacks_db_conn,
+                &block_cache,
+                None,
+                Some(&cancellation),
                 &moved_config,
                 &moved_ctx,
             );
             let res = hiro_system_kit::nestable_block_on(op);
+            cancellations.end_scan(&predicate_spec.uuid);
             let (last_block_scanned, predicate_is_expired) = match res {
                 Ok(last_block_scanned) => last_block_scanned,
                 Err(e) => {
@@ -101,7 +163,8 @@ pub fn start_stacks_scan_runloop(
                     );
                 }
             }
-            if !predicate_is_expired {
+            // The predicate of a cancelled scan is either deregistered, or resumed on restart.
+            if !predicate_is_expired && !cancellation.load(Ordering::SeqCst) {
                 let _ = observer_command_tx.send(ObserverCommand::EnablePredicate(
                     ChainhookSpecification::Stacks(predicate_spec),
                 ));
@@ -119,53 +182,165 @@ pub fn start_bitcoin_scan_runloop(
         Option<ScanningData>,
     )>,
     observer_command_tx: Sender<ObserverCommand>,
+    cancellations: ScanCancellations,
     ctx: &Context,
 ) {
     let bitcoin_scan_pool = ThreadPool::new(config.limits.max_number_of_concurrent_bitcoin_scans);
+    // Shared by the scans, so that predicates scanning overlapping ranges fetch blocks once.
+    let block_cache = Arc::new(BitcoinBlockCache::new(
+        config.limits.max_number_of_cached_bitcoin_blocks,
+    ));
+    let deliveries = Arc::new(DeliveredOccurrences::new(
+        config.limits.max_number_of_tracked_deliveries,
+    ));
 
-    while let Ok((predicate_spec, unfinished_scan_data)) = bitcoin_scan_op_rx.recv() {
+    while let Ok(scan_op) = bitcoin_scan_op_rx.recv() {
+        // The scans queued together (e.g. the predicates registered when the service starts)
+        // share a single pass over the chainstate. Resumed scans keep their own progress.
+        let mut new_scans = vec![];
+        let mut single_scans = vec![];
+        for (predicate_spec, unfinished_scan_data) in
+            std::iter::once(scan_op).chain(bitcoin_scan_op_rx.try_iter())
+        {
+            match unfinished_scan_data {
+                None => new_scans.push(predicate_spec),
+                Some(scan_data) => single_scans.push((predicate_spec, Some(scan_data))),
+            }
+        }
+        if new_scans.len() == 1 {
+            single_scans.extend(
+                new_scans
+                    .drain(..)
+                    .map(|predicate_spec| (predicate_spec, None)),
+            );
+        }
+
+        for (predicate_spec, unfinished_scan_data) in single_scans.into_iter() {
+            let moved_ctx = ctx.clone();
+            let moved_config = config.clone();
+            let block_cache = block_cache.clone();
+            let deliveries = deliveries.clone();
+            let observer_command_tx = observer_command_tx.clone();
+            let cancellations = cancellations.clone();
+            bitcoin_scan_pool.execute(move || {
+                let cancellation = cancellations.start_scan(&predicate_spec.uuid);
+                let op = scan_bitcoin_chainstate_via_rpc_using_predicate(
+                    &predicate_spec,
+                    unfinished_scan_data,
+                    None,
+                    false,
+                    &block_cache,
+                    &deliveries,
+                    Some(&cancellation),
+                    &moved_config,
+                    &moved_ctx,
+                );
+                let res = hiro_system_kit::nestable_block_on(op);
+                cancellations.end_scan(&predicate_spec.uuid);
+                handle_bitcoin_scan_outcome(
+                    predicate_spec,
+                    res,
+                    &cancellation,
+                    &observer_command_tx,
+                    &moved_config,
+                    &moved_ctx,
+                );
+            });
+        }
+
+        if new_scans.is_empty() {
+            continue;
+        }
         let moved_ctx = ctx.clone();
         let moved_config = config.clone();
+        let block_cache = block_cache.clone();
+        let deliveries = deliveries.clone();
         let observer_command_tx = observer_command_tx.clone();
+        let cancellations = cancellations.clone();
         bitcoin_scan_pool.execute(move || {
-            let op = scan_bitcoin_chainstate_via_rpc_using_predicate(
-                &predicate_spec,
-                unfinished_scan_data,
+            let scan_cancellations = new_scans
+                .iter()
+                .map(|predicate_spec| cancellations.start_scan(&predicate_spec.uuid))
+                .collect::<Vec<_>>();
+            let predicate_specs = new_scans
+                .iter()
+                .zip(scan_cancellations.iter())
+                .map(|(predicate_spec, cancellation)| (predicate_spec, Some(cancellation.as_ref())))
+                .collect::<Vec<_>>();
+            let op = scan_bitcoin_chainstate_via_rpc_using_predicates(
+                &predicate_specs,
+                false,
+                &block_cache,
+                &deliveries,
                 &moved_config,
                 &moved_ctx,
             );
-
-            let predicate_is_expired = match hiro_system_kit::nestable_block_on(op) {
-                Ok(predicate_is_expired) => predicate_is_expired,
-                Err(e) => {
-                    error!(
-                        moved_ctx.expect_logger(),
-                        "Unable to evaluate predicate on Bitcoin chainstate: {e}",
-                    );
-
-                    // Update predicate status in redis
-                    if let PredicatesApi::On(ref api_config) = moved_config.http_api {
-                        let status = PredicateStatus::Interrupted(format!(
-                            "Unable to evaluate predicate on Bitcoin chainstate: {e}"
-                        ));
-                        let mut predicates_db_conn =
-                            open_readwrite_predicates_db_conn_or_panic(api_config, &moved_ctx);
-                        update_predicate_status(
-                            &predicate_spec.key(),
-                            status,
-                            &mut predicates_db_conn,
-                            &moved_ctx,
-                        );
-                    }
-                    return;
-                }
+            let res = hiro_system_kit::nestable_block_on(op);
+            for predicate_spec in new_scans.iter() {
+                cancellations.end_scan(&predicate_spec.uuid);
+            }
+            let outcomes = match res {
+                Ok(outcomes) => outcomes,
+                Err(e) => new_scans.iter().map(|_| Err(e.clone())).collect(),
             };
-            if !predicate_is_expired {
-                let _ = observer_command_tx.send(ObserverCommand::EnablePredicate(
-                    ChainhookSpecification::Bitcoin(predicate_spec),
-                ));
+            for ((predicate_spec, res), cancellation) in new_scans
+                .into_iter()
+                .zip(outcomes.into_iter())
+                .zip(scan_cancellations.iter())
+            {
+                handle_bitcoin_scan_outcome(
+                    predicate_spec,
+                    res,
+                    cancellation,
+                    &observer_command_tx,
+                    &moved_config,
+                    &moved_ctx,
+                );
             }
         });
     }
     let _ = bitcoin_scan_pool.join();
 }
+
+/// Interrupts the predicate of a failed scan, or hands the predicate of a scan that left blocks
+/// to evaluate over to the observer.
+fn handle_bitcoin_scan_outcome(
+    predicate_spec: BitcoinChainhookSpecification,
+    res: Result<ScanSummary, ScanError>,
+    cancellation: &AtomicBool,
+    observer_command_tx: &Sender<ObserverCommand>,
+    config: &Config,
+    ctx: &Context,
+) {
+    let predicate_is_expired = match res {
+        Ok(summary) => summary.predicate_is_expired,
+        Err(e) => {
+            error!(
+                ctx.expect_logger(),
+                "Unable to evaluate predicate on Bitcoin chainstate: {e}",
+            );
+
+            // Update predicate status in redis
+            if let PredicatesApi::On(ref api_config) = config.http_api {
+                let status = PredicateStatus::Interrupted(format!(
+                    "Unable to evaluate predicate on Bitcoin chainstate: {e}"
+                ));
+                let mut predicates_db_conn =
+                    open_readwrite_predicates_db_conn_or_panic(api_config, ctx);
+                update_predicate_status(
+                    &predicate_spec.key(),
+                    status,
+                    &mut predicates_db_conn,
+                    ctx,
+                );
+            }
+            return;
+        }
+    };
+    // The predicate of a cancelled scan is either deregistered, or resumed on restart.
+    if !predicate_is_expired && !cancellation.load(Ordering::SeqCst) {
+        let _ = observer_command_tx.send(ObserverCommand::EnablePredicate(
+            ChainhookSpecification::Bitcoin(predicate_spec),
+        ));
+    }
+}
@@ -12,6 +12,7 @@ use std::collections::HashMap;
 use std::net::IpAddr;
 use std::net::Ipv4Addr;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::RwLock;
 
@@ -84,11 +85,13 @@ fn handle_increment_chain_tip(
 fn handle_rpc(
     rpc: Json<Rpc>,
     fork_tracker_rw_lock: &State<Arc<RwLock<HashMap<BranchKey, ForkData>>>>,
+    blocks_fetched: &State<Arc<AtomicUsize>>,
 ) -> Value {
     let rpc = rpc.into_inner();
     let fork_tracker = fork_tracker_rw_lock.inner().read().unwrap();
     match rpc.method.as_str() {
         "getblock" => {
+            blocks_fetched.fetch_add(1, Ordering::SeqCst);
             let hash = rpc.params[0].as_str().unwrap();
             let mut chars = hash.chars();
             let branch = chars.next().unwrap();
@@ -124,6 +127,7 @@ fn handle_rpc(
 
             let coinbase = BitcoinTransactionFullBreakdown {
                 txid: branch_and_height_to_hash_str(Some(branch), height),
+                version: 1,
                 vin: vec![BitcoinTransactionInputFullBreakdown {
                     sequence: 0,
                     txid: None,
@@ -144,9 +148,12 @@ fn handle_rpc(
                         address: None,
                     },
                 }],
+                hex: None,
+                vsize: None,
             };
             let tx = BitcoinTransactionFullBreakdown {
                 txid: branch_and_height_to_hash_str(Some(branch), height + 1),
+                version: 2,
                 vin: vec![BitcoinTransactionInputFullBreakdown {
                     sequence: 0,
                     txid: Some(branch_and_height_to_hash_str(Some(branch), height + 1)),
@@ -156,6 +163,7 @@ fn handle_rpc(
                     prevout: Some(BitcoinTransactionInputPrevoutFullBreakdown {
                         height: height,
                         value: Amount::ZERO,
+                        script_pub_key: None,
                     }),
                 }],
                 vout: vec![BitcoinTransactionOutputFullBreakdown {
@@ -170,6 +178,8 @@ fn handle_rpc(
                         address: None,
                     },
                 }],
+                hex: None,
+                vsize: None,
             };
             let block = BitcoinBlockFullBreakdown {
                 hash: hash.into(),
@@ -285,11 +295,34 @@ fn handle_rpc(
     }
 }
 
+#[post("/", format = "application/json", data = "<rpc>")]
+fn handle_failing_rpc(rpc: Json<Rpc>) -> Value {
+    let rpc = rpc.into_inner();
+    json!({
+        "id": serde_json::to_value(rpc.id).unwrap(),
+        "jsonrpc": rpc.jsonrpc,
+        "result": null,
+        "error": {
+            "code": -28,
+            "message": "Loading block index...",
+        },
+    })
+}
+
 type BranchKey = char;
 type Height = u64;
 type ForkPoint = (BranchKey, Height);
 type ForkData = (Height, Option<ForkPoint>);
 pub async fn mock_bitcoin_rpc(port: u16, starting_chain_tip: u64) {
+    mock_bitcoin_rpc_counting_blocks(port, starting_chain_tip, Arc::new(AtomicUsize::new(0))).await
+}
+
+/// Same as `mock_bitcoin_rpc`, counting the `getblock` requests in `blocks_fetched`.
+pub async fn mock_bitcoin_rpc_counting_blocks(
+    port: u16,
+    starting_chain_tip: u64,
+    blocks_fetched: Arc<AtomicUsize>,
+) {
     let config = Config::figment()
         .merge(("port", port))
         .merge(("address", IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))))
@@ -300,8 +333,23 @@ pub async fn mock_bitcoin_rpc(port: u16, starting_chain_tip: u64) {
     let _rocket = rocket::build()
         .configure(config)
         .manage(fork_tracker_rw_lock)
+        .manage(blocks_fetched)
         .mount("/", routes![handle_rpc, handle_increment_chain_tip])
         .launch()
         .await
         .unwrap();
 }
+
+/// Bitcoin RPC answering every request with an error, as a node still warming up does.
+pub async fn mock_failing_bitcoin_rpc(port: u16) {
+    let config = Config::figment()
+        .merge(("port", port))
+        .merge(("address", IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))))
+        .merge(("log_level", "off"));
+    let _rocket = rocket::build()
+        .configure(config)
+        .mount("/", routes![handle_failing_rpc])
+        .launch()
+        .await
+        .unwrap();
+}
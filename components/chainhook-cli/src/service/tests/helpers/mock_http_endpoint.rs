@@ -0,0 +1,110 @@
+use rocket::http::Status;
+use rocket::Config;
+use rocket::State;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Endpoint answering `503 Service Unavailable` to its first `failures` requests.
+pub struct FlakyEndpoint {
+    pub failures: usize,
+    pub attempts: Arc<AtomicUsize>,
+}
+
+#[post("/", data = "<_body>")]
+fn handle_occurrence(endpoint: &State<FlakyEndpoint>, _body: String) -> Status {
+    let attempt = endpoint.attempts.fetch_add(1, Ordering::SeqCst);
+    if attempt < endpoint.failures {
+        Status::ServiceUnavailable
+    } else {
+        Status::Ok
+    }
+}
+
+pub async fn mock_flaky_http_endpoint(port: u16, endpoint: FlakyEndpoint) {
+    let _rocket = rocket::build()
+        .configure(endpoint_config(port))
+        .manage(endpoint)
+        .mount("/", routes![handle_occurrence])
+        .launch()
+        .await
+        .unwrap();
+}
+
+/// Endpoint keeping the body of every request it receives.
+pub struct RecordingEndpoint {
+    pub bodies: Arc<Mutex<Vec<String>>>,
+}
+
+#[post("/", data = "<body>")]
+fn record_occurrence(endpoint: &State<RecordingEndpoint>, body: String) -> Status {
+    endpoint.bodies.lock().unwrap().push(body);
+    Status::Ok
+}
+
+pub async fn mock_recording_http_endpoint(port: u16, endpoint: RecordingEndpoint) {
+    let _rocket = rocket::build()
+        .configure(endpoint_config(port))
+        .manage(endpoint)
+        .mount("/", routes![record_occurrence])
+        .launch()
+        .await
+        .unwrap();
+}
+
+/// Endpoint raising `cancellation` once it received `requests_before_cancellation` requests.
+pub struct CancellingEndpoint {
+    pub requests_before_cancellation: usize,
+    pub requests: AtomicUsize,
+    pub cancellation: Arc<AtomicBool>,
+}
+
+#[post("/", data = "<_body>")]
+fn cancel_after_occurrences(endpoint: &State<CancellingEndpoint>, _body: String) -> Status {
+    let requests = endpoint.requests.fetch_add(1, Ordering::SeqCst) + 1;
+    if requests >= endpoint.requests_before_cancellation {
+        endpoint.cancellation.store(true, Ordering::SeqCst);
+    }
+    Status::Ok
+}
+
+pub async fn mock_cancelling_http_endpoint(port: u16, endpoint: CancellingEndpoint) {
+    let _rocket = rocket::build()
+        .configure(endpoint_config(port))
+        .manage(endpoint)
+        .mount("/", routes![cancel_after_occurrences])
+        .launch()
+        .await
+        .unwrap();
+}
+
+/// Endpoint answering every request once `delay` elapsed.
+pub struct SlowEndpoint {
+    pub delay: Duration,
+    pub requests: Arc<AtomicUsize>,
+}
+
+#[post("/", data = "<_body>")]
+async fn answer_after_delay(endpoint: &State<SlowEndpoint>, _body: String) -> Status {
+    rocket::tokio::time::sleep(endpoint.delay).await;
+    endpoint.requests.fetch_add(1, Ordering::SeqCst);
+    Status::Ok
+}
+
+pub async fn mock_slow_http_endpoint(port: u16, endpoint: SlowEndpoint) {
+    let _rocket = rocket::build()
+        .configure(endpoint_config(port))
+        .manage(endpoint)
+        .mount("/", routes![answer_after_delay])
+        .launch()
+        .await
+        .unwrap();
+}
+
+fn endpoint_config(port: u16) -> rocket::figment::Figment {
+    Config::figment()
+        .merge(("port", port))
+        .merge(("address", IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))))
+        .merge(("log_level", "off"))
+}
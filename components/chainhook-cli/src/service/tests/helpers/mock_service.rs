@@ -5,12 +5,15 @@ use crate::config::MonitoringConfig;
 use crate::config::PathConfig;
 use crate::config::PredicatesApi;
 use crate::config::PredicatesApiConfig;
+use crate::config::PredicatesConfig;
 use crate::config::StorageConfig;
 use crate::config::DEFAULT_REDIS_URI;
 use crate::service::http_api::start_predicate_api_server;
 use crate::service::PredicateStatus;
 use crate::service::Service;
 use chainhook_sdk::chainhooks::types::ChainhookFullSpecification;
+use chainhook_sdk::chainhooks::types::DuplicateUuidPolicy;
+use chainhook_sdk::indexer::fork_scratch_pad::DEFAULT_MAX_REORG_DEPTH;
 use chainhook_sdk::indexer::IndexerConfig;
 use chainhook_sdk::observer::ObserverCommand;
 use chainhook_sdk::types::BitcoinBlockSignaling;
@@ -123,6 +126,33 @@ pub async fn call_register_predicate(
     call_observer_svc(&url, Method::POST, Some(predicate)).await
 }
 
+pub async fn call_register_predicate_with_auth_token(
+    predicate: &JsonValue,
+    port: u16,
+    auth_token: &str,
+) -> Result<JsonValue, String> {
+    let url = format!("http://localhost:{port}/v1/chainhooks");
+    reqwest::Client::new()
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {auth_token}"))
+        .json(predicate)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to make POST request to {url}: {e}",))?
+        .json::<JsonValue>()
+        .await
+        .map_err(|e| format!("Failed to deserialize response of POST request to {url}: {e}",))
+}
+
+pub async fn call_deregister_predicate_by_uuid(
+    predicate_uuid: &str,
+    port: u16,
+) -> Result<JsonValue, String> {
+    let url = format!("http://localhost:{port}/v1/chainhooks/{predicate_uuid}");
+    call_observer_svc(&url, Method::DELETE, None).await
+}
+
 pub async fn call_deregister_predicate(
     chain: &Chain,
     predicate_uuid: &str,
@@ -195,7 +225,11 @@ pub async fn call_prometheus(port: u16) -> Result<String, String> {
         .map_err(|e| format!("Failed to deserialize response of GET request to {url}: {e}",))
 }
 
-pub async fn build_predicate_api_server(port: u16) -> (Receiver<ObserverCommand>, Shutdown) {
+pub async fn build_predicate_api_server(
+    port: u16,
+    auth_token: Option<String>,
+    stacks_node_rpc_url: Option<String>,
+) -> (Receiver<ObserverCommand>, Shutdown) {
     let ctx = Context {
         logger: None,
         tracer: false,
@@ -204,10 +238,12 @@ pub async fn build_predicate_api_server(port: u16) -> (Receiver<ObserverCommand>
         http_port: port,
         display_logs: true,
         database_uri: DEFAULT_REDIS_URI.to_string(),
+        auth_token,
+        stacks_node_rpc_url,
     };
 
     let (tx, rx) = channel();
-    let shutdown = start_predicate_api_server(api_config, tx, ctx)
+    let shutdown = start_predicate_api_server(api_config, StacksNetwork::Devnet, tx, ctx)
         .await
         .unwrap();
 
@@ -291,11 +327,14 @@ pub fn get_chainhook_config(
         http_port: chainhook_port,
         display_logs: true,
         database_uri: format!("redis://localhost:{redis_port}/"),
+        auth_token: None,
+        stacks_node_rpc_url: None,
     };
     Config {
         http_api: PredicatesApi::On(api_config),
         storage: StorageConfig {
             working_dir: working_dir.into(),
+            scan_checkpoints_dir: None,
         },
         event_sources: vec![EventSourceConfig::StacksTsvPath(PathConfig {
             file_path: PathBuf::from(tsv_dir),
@@ -308,6 +347,12 @@ pub fn get_chainhook_config(
             max_number_of_processing_threads: 16,
             max_number_of_networking_threads: 16,
             max_caching_memory_size_mb: 32000,
+            max_number_of_cached_stacks_blocks: 128,
+            max_number_of_cached_bitcoin_blocks: 128,
+            max_number_of_buffered_occurrences: 1024,
+            max_number_of_tracked_deliveries: 0,
+            max_number_of_concurrent_deliveries: 1,
+            block_evaluation_timeout_ms: None,
         },
         network: IndexerConfig {
             bitcoin_network: BitcoinNetwork::Regtest,
@@ -319,9 +364,15 @@ pub fn get_chainhook_config(
                 rpc_url: format!("http://localhost:{stacks_rpc_port}"),
                 ingestion_port: stacks_ingestion_port,
             }),
+            bitcoin_max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
         },
         monitoring: MonitoringConfig {
             prometheus_monitoring_port: prometheus_port,
+            scan_metrics_port: None,
+        },
+        predicates: PredicatesConfig {
+            duplicate_uuid_policy: DuplicateUuidPolicy::default(),
+            predicates_dir: None,
         },
     }
 }
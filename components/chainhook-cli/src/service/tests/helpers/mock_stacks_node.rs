@@ -8,6 +8,10 @@ use chainhook_sdk::types::{
     STXTransferEventData, SmartContractEventData, StacksTransactionEventPayload,
 };
 
+use rocket::serde::json::{Json, Value};
+use rocket::Config;
+use std::net::{IpAddr, Ipv4Addr};
+
 use super::{branch_and_height_to_prefixed_hash, height_to_prefixed_hash};
 
 pub const TEST_WORKING_DIR: &str = "src/service/tests/fixtures/tmp";
@@ -144,6 +148,7 @@ pub fn create_stacks_new_block(height: u64, burn_block_height: u64) -> NewBlock
             sender: format!(""),
             recipient: format!(""),
             amount: format!("1"),
+            memo: None,
         }),
     ));
     events.push(create_stacks_new_event(
@@ -423,3 +428,35 @@ pub async fn create_burn_fork_at(
     call_new_burn_block(stacks_ingestion_port, branch, burn_block_height).await?;
     Ok(())
 }
+
+pub const MOCK_CONTRACT_IDENTIFIER: &str = "SP000000000000000000002Q6VF78.pox";
+
+#[get("/v2/contracts/interface/<contract_address>/<contract_name>")]
+fn handle_contract_interface(contract_address: &str, contract_name: &str) -> Option<Json<Value>> {
+    if format!("{contract_address}.{contract_name}") != MOCK_CONTRACT_IDENTIFIER {
+        return None;
+    }
+    Some(Json(json!({
+        "functions": [
+            { "name": "stack-stx", "access": "public", "args": [], "outputs": {} },
+            { "name": "get-pox-info", "access": "read_only", "args": [], "outputs": {} },
+        ],
+        "variables": [],
+        "maps": [],
+        "fungible_tokens": [],
+        "non_fungible_tokens": [],
+    })))
+}
+
+pub async fn mock_stacks_node_contracts(port: u16) {
+    let config = Config::figment()
+        .merge(("port", port))
+        .merge(("address", IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))))
+        .merge(("log_level", "off"));
+    let _rocket = rocket::build()
+        .configure(config)
+        .mount("/", routes![handle_contract_interface])
+        .launch()
+        .await
+        .unwrap();
+}
@@ -2,6 +2,7 @@ use std::net::TcpListener;
 
 pub mod build_predicates;
 pub mod mock_bitcoin_rpc;
+pub mod mock_http_endpoint;
 pub mod mock_service;
 pub mod mock_stacks_node;
 
@@ -1,8 +1,9 @@
 use chainhook_sdk::chainhooks::types::{
     ChainhookFullSpecification, ChainhookSpecification, StacksChainhookFullSpecification,
 };
-use chainhook_sdk::types::{Chain, StacksNetwork};
+use chainhook_sdk::types::{BitcoinNetwork, Chain, StacksNetwork};
 use chainhook_sdk::utils::Context;
+use redis::Commands;
 use rocket::serde::json::Value as JsonValue;
 use rocket::Shutdown;
 use std::fs::{self};
@@ -13,7 +14,10 @@ use std::thread::sleep;
 use std::time::Duration;
 use test_case::test_case;
 
-use chainhook_sdk::observer::ObserverCommand;
+use chainhook_sdk::observer::{
+    ObserverCommand, OccurrenceDelivery,
+    OccurrenceDeliveryStatus::{Acked, Failed, Pending},
+};
 
 use self::helpers::build_predicates::{build_bitcoin_payload, build_stacks_payload, DEFAULT_UUID};
 use self::helpers::mock_bitcoin_rpc::mock_bitcoin_rpc;
@@ -22,20 +26,25 @@ use self::helpers::mock_service::{
     start_chainhook_service, start_redis,
 };
 use self::helpers::mock_stacks_node::{
-    create_tmp_working_dir, mine_burn_block, mine_stacks_block, write_stacks_blocks_to_tsv,
+    create_tmp_working_dir, mine_burn_block, mine_stacks_block, mock_stacks_node_contracts,
+    write_stacks_blocks_to_tsv, MOCK_CONTRACT_IDENTIFIER,
 };
 use crate::scan::stacks::consolidate_local_stacks_chainstate_using_csv;
 use crate::service::tests::helpers::build_predicates::get_random_uuid;
 use crate::service::tests::helpers::get_free_port;
 use crate::service::tests::helpers::mock_service::{
-    build_predicate_api_server, call_get_predicate, call_register_predicate, get_chainhook_config,
+    build_predicate_api_server, call_deregister_predicate_by_uuid, call_get_predicate,
+    call_register_predicate, call_register_predicate_with_auth_token, get_chainhook_config,
     get_predicate_status,
 };
 use crate::service::tests::helpers::mock_stacks_node::create_burn_fork_at;
 use crate::service::{PredicateStatus, PredicateStatus::*, ScanningData, StreamingData};
 
-use super::http_api::document_predicate_api_server;
-use super::{update_predicate_spec, update_predicate_status};
+use super::http_api::{document_predicate_api_server, serialized_predicate_with_status};
+use super::{
+    get_unacked_occurrences, update_occurrence_delivery_status, update_predicate_spec,
+    update_predicate_status,
+};
 
 pub mod helpers;
 mod observer_tests;
@@ -47,7 +56,7 @@ async fn test_register_predicate(predicate: JsonValue) -> Result<(), (String, Sh
     let port = listener.local_addr().unwrap().port();
     drop(listener);
 
-    let (rx, shutdown) = build_predicate_api_server(port).await;
+    let (rx, shutdown) = build_predicate_api_server(port, None, None).await;
 
     let moved_shutdown = shutdown.clone();
     let res = call_register_predicate(&predicate, port)
@@ -98,6 +107,76 @@ async fn test_register_predicate(predicate: JsonValue) -> Result<(), (String, Sh
     Ok(())
 }
 
+#[tokio::test]
+async fn it_requires_auth_token_when_configured() {
+    let port = get_free_port().unwrap();
+    let (rx, shutdown) = build_predicate_api_server(port, Some("secret".into()), None).await;
+    let predicate = build_bitcoin_payload(None, None, None, None, None);
+
+    let res = call_register_predicate(&predicate, port).await;
+    let res_with_invalid_token =
+        call_register_predicate_with_auth_token(&predicate, port, "not-secret").await;
+    let res_with_token = call_register_predicate_with_auth_token(&predicate, port, "secret").await;
+    let command = rx.recv_timeout(Duration::from_secs(5));
+    shutdown.notify();
+
+    assert_eq!(res.unwrap().get("status"), Some(&json!(401)));
+    assert_eq!(
+        res_with_invalid_token.unwrap().get("status"),
+        Some(&json!(401))
+    );
+    assert_eq!(res_with_token.unwrap().get("status"), Some(&json!(200)));
+    match command {
+        Ok(ObserverCommand::RegisterPredicate(_)) => {}
+        _ => panic!("expected predicate to be registered once authorized"),
+    }
+}
+
+#[test_case("", json!({}) ; "empty name")]
+#[test_case("test", json!({"expire_after_occurrence": 0}) ; "zero expire_after_occurrence")]
+#[tokio::test]
+async fn it_rejects_invalid_bitcoin_predicates(name: &str, filter: JsonValue) {
+    let mut predicate = build_bitcoin_payload(None, None, None, Some(filter), None);
+    predicate["name"] = json!(name);
+    let port = get_free_port().unwrap();
+    let (_rx, shutdown) = build_predicate_api_server(port, None, None).await;
+    let res = call_register_predicate(&predicate, port).await;
+    shutdown.notify();
+
+    assert_eq!(res.unwrap().get("status"), Some(&json!(422)));
+}
+
+#[test_case("stack-stx", 200 ; "existing contract and public method")]
+#[test_case("stack-sxt", 422 ; "misspelled method")]
+#[tokio::test]
+async fn it_checks_the_contracts_of_registered_stacks_predicates(method: &str, status: u64) {
+    let stacks_node_port = get_free_port().unwrap();
+    let _ = hiro_system_kit::thread_named("Stacks node service")
+        .spawn(move || {
+            let future = mock_stacks_node_contracts(stacks_node_port);
+            let _ = hiro_system_kit::nestable_block_on(future);
+        })
+        .expect("unable to spawn thread");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let port = get_free_port().unwrap();
+    let (_rx, shutdown) = build_predicate_api_server(
+        port,
+        None,
+        Some(format!("http://localhost:{stacks_node_port}")),
+    )
+    .await;
+    let if_this = json!({"scope": "any_of", "predicates": [
+        {"scope": "txid", "equals": "0xfaaac1833dc4883e7ec28f61e35b41f896c395f8d288b1a177155de2abd6052f"},
+        {"scope": "not", "predicate": {"scope": "contract_call", "contract_identifier": MOCK_CONTRACT_IDENTIFIER, "method": method}},
+    ]});
+    let predicate = build_stacks_payload(Some("devnet"), Some(if_this), None, None, None);
+    let res = call_register_predicate(&predicate, port).await;
+    shutdown.notify();
+
+    assert_eq!(res.unwrap().get("status"), Some(&json!(status)));
+}
+
 #[test_case("mainnet" ; "mainnet")]
 #[test_case("testnet" ; "testnet")]
 #[test_case("regtest" ; "regtest")]
@@ -148,6 +227,8 @@ async fn it_handles_bitcoin_if_this_predicates(if_this: JsonValue) {
 #[test_case(json!("noop") ; "with noop action")]
 #[test_case(json!({"http_post": {"url": "http://localhost:1234", "authorization_header": "Bearer FYRPnz2KHj6HueFmaJ8GGD3YMbirEFfh"}}) ; "with http_post action")]
 #[test_case(json!({"file_append": {"path": "./path"}}) ; "with file_append action")]
+#[test_case(json!({"unix_socket": {"path": "/tmp/chainhook.sock"}}) ; "with unix_socket action")]
+#[test_case(json!({"zstd_file_append": {"path": "./occurrences.zst", "compression_level": 19}}) ; "with zstd_file_append action")]
 #[tokio::test]
 async fn it_handles_bitcoin_then_that_predicates(then_that: JsonValue) {
     let predicate = build_bitcoin_payload(None, None, Some(then_that), None, None);
@@ -231,6 +312,8 @@ async fn it_handles_stacks_if_this_predicates(if_this: JsonValue) {
 #[test_case(json!("noop") ; "with noop action")]
 #[test_case(json!({"http_post": {"url": "http://localhost:1234", "authorization_header": "Bearer FYRPnz2KHj6HueFmaJ8GGD3YMbirEFfh"}}) ; "with http_post action")]
 #[test_case(json!({"file_append": {"path": "./path"}}) ; "with file_append action")]
+#[test_case(json!({"unix_socket": {"path": "/tmp/chainhook.sock"}}) ; "with unix_socket action")]
+#[test_case(json!({"zstd_file_append": {"path": "./occurrences.zst", "compression_level": 19}}) ; "with zstd_file_append action")]
 #[tokio::test]
 async fn it_handles_stacks_then_that_predicates(then_that: JsonValue) {
     let predicate = build_stacks_payload(None, None, Some(then_that), None, None);
@@ -887,11 +970,13 @@ async fn test_bitcoin_predicate_status_is_updated_with_reorg(
     redis_process.kill().unwrap();
 }
 
-#[test_case(Chain::Stacks; "for stacks chain")]
-#[test_case(Chain::Bitcoin; "for bitcoin chain")]
+#[test_case(Chain::Stacks, false; "for stacks chain")]
+#[test_case(Chain::Bitcoin, false; "for bitcoin chain")]
+#[test_case(Chain::Stacks, true; "for stacks chain by uuid")]
+#[test_case(Chain::Bitcoin, true; "for bitcoin chain by uuid")]
 #[tokio::test]
 #[cfg_attr(not(feature = "redis_tests"), ignore)]
-async fn test_deregister_predicate(chain: Chain) {
+async fn test_deregister_predicate(chain: Chain, by_uuid_only: bool) {
     let (mut redis_process, working_dir, chainhook_service_port, redis_port, _, _, _) = match &chain
     {
         Chain::Stacks => setup_stacks_chainhook_test(3, None, None).await,
@@ -936,14 +1021,17 @@ async fn test_deregister_predicate(chain: Chain) {
         });
     assert_eq!(result.get("status"), Some(&json!(200)));
 
-    let result = call_deregister_predicate(&chain, uuid, chainhook_service_port)
-        .await
-        .unwrap_or_else(|e| {
-            std::fs::remove_dir_all(&working_dir).unwrap();
-            flush_redis(redis_port);
-            redis_process.kill().unwrap();
-            panic!("test failed with error: {e}");
-        });
+    let result = if by_uuid_only {
+        call_deregister_predicate_by_uuid(uuid, chainhook_service_port).await
+    } else {
+        call_deregister_predicate(&chain, uuid, chainhook_service_port).await
+    }
+    .unwrap_or_else(|e| {
+        std::fs::remove_dir_all(&working_dir).unwrap();
+        flush_redis(redis_port);
+        redis_process.kill().unwrap();
+        panic!("test failed with error: {e}");
+    });
     assert_eq!(result.get("status"), Some(&json!(200)));
 
     let mut attempts = 0;
@@ -1084,6 +1172,47 @@ async fn it_allows_specifying_startup_predicate() {
     assert_confirmed_expiration_status((result, None, None));
 }
 
+#[tokio::test]
+#[cfg_attr(not(feature = "redis_tests"), ignore)]
+async fn it_prunes_acknowledged_occurrence_deliveries() {
+    let redis_port = get_free_port().unwrap();
+    let mut redis_process = start_redis(redis_port)
+        .await
+        .unwrap_or_else(|e| panic!("test failed with error: {e}"));
+    flush_redis(redis_port);
+    let client = redis::Client::open(format!("redis://localhost:{redis_port}/")).unwrap();
+    let mut connection = client.get_connection().unwrap();
+    let ctx = Context::empty();
+
+    let uuid = get_random_uuid();
+    for (idempotency_key, statuses) in [
+        ("acked", vec![Pending, Acked]),
+        ("failed", vec![Pending, Failed]),
+        ("pending", vec![Pending]),
+    ] {
+        let delivery = OccurrenceDelivery {
+            predicate_uuid: uuid.clone(),
+            idempotency_key: idempotency_key.to_string(),
+        };
+        for status in statuses.into_iter() {
+            update_occurrence_delivery_status(&delivery, status, &mut connection, &ctx);
+        }
+    }
+    let unacked = get_unacked_occurrences(&uuid, &mut connection);
+    let tracked: usize = connection.hlen(format!("deliveries:{uuid}")).unwrap();
+    flush_redis(redis_port);
+    redis_process.kill().unwrap();
+
+    assert_eq!(
+        unacked.unwrap(),
+        vec![
+            ("failed".to_string(), Failed),
+            ("pending".to_string(), Pending)
+        ]
+    );
+    assert_eq!(tracked, 2);
+}
+
 #[tokio::test]
 #[cfg_attr(not(feature = "redis_tests"), ignore)]
 async fn register_predicate_responds_409_if_uuid_in_use() {
@@ -1130,3 +1259,132 @@ fn it_generates_open_api_spec() {
         "breaking change detected: open api spec has been updated"
     )
 }
+
+#[cfg(feature = "predicates_watcher")]
+#[test]
+fn it_reconciles_the_predicates_of_a_directory() {
+    use super::predicates_watcher::reconcile_predicates;
+    use std::collections::BTreeMap;
+
+    let parse = |payload: JsonValue| -> ChainhookFullSpecification {
+        serde_json::from_value(payload).unwrap()
+    };
+    let unchanged = parse(build_bitcoin_payload(
+        None,
+        None,
+        None,
+        None,
+        Some("unchanged"),
+    ));
+    let modified = parse(build_stacks_payload(
+        None,
+        None,
+        None,
+        None,
+        Some("modified"),
+    ));
+    let removed = parse(build_bitcoin_payload(
+        None,
+        None,
+        None,
+        None,
+        Some("removed"),
+    ));
+    let added = parse(build_stacks_payload(None, None, None, None, Some("added")));
+    let modified_update = parse(build_stacks_payload(
+        None,
+        None,
+        Some(json!({"file_append": {"path": "./occurrences.json"}})),
+        None,
+        Some("modified"),
+    ));
+
+    let mut active_predicates = vec![unchanged.clone(), modified, removed]
+        .into_iter()
+        .map(|predicate| (predicate.get_uuid().to_string(), predicate))
+        .collect::<BTreeMap<_, _>>();
+    let commands = reconcile_predicates(
+        &mut active_predicates,
+        vec![unchanged, modified_update.clone(), added.clone()],
+    );
+
+    assert_eq!(
+        commands,
+        vec![
+            ObserverCommand::DeregisterStacksPredicate("modified".into()),
+            ObserverCommand::DeregisterBitcoinPredicate("removed".into()),
+            ObserverCommand::RegisterPredicate(added),
+            ObserverCommand::RegisterPredicate(modified_update),
+        ]
+    );
+    assert_eq!(
+        active_predicates.keys().collect::<Vec<_>>(),
+        vec!["added", "modified", "unchanged"]
+    );
+
+    let commands = reconcile_predicates(
+        &mut active_predicates.clone(),
+        active_predicates.values().cloned().collect(),
+    );
+    assert!(commands.is_empty());
+}
+
+#[test]
+fn it_cancels_the_scans_of_deregistered_predicates_and_on_termination() {
+    use super::runloops::ScanCancellations;
+    use std::sync::atomic::Ordering;
+
+    let cancellations = ScanCancellations::new();
+    let deregistered = cancellations.start_scan("deregistered");
+    let scanning = cancellations.start_scan("scanning");
+
+    cancellations.cancel("deregistered");
+    assert!(deregistered.load(Ordering::SeqCst));
+    assert!(!scanning.load(Ordering::SeqCst));
+
+    cancellations.end_scan("deregistered");
+    cancellations.cancel_all();
+    assert!(scanning.load(Ordering::SeqCst));
+    // Scans starting while the service terminates are cancelled right away
+    assert!(cancellations.start_scan("late").load(Ordering::SeqCst));
+}
+
+#[test_case(Chain::Bitcoin; "bitcoin")]
+#[test_case(Chain::Stacks; "stacks")]
+fn it_redacts_the_signing_secret_from_the_predicate_api_responses(chain: Chain) {
+    let then_that = json!({
+        "http_post": {
+            "url": "http://localhost:3000/api/v1/wrapBtc",
+            "authorization_header": "Bearer cn389ncoiwuencr",
+            "signing_secret": "5f8c2b1e9d7a"
+        }
+    });
+    let spec = match chain {
+        Chain::Bitcoin => {
+            let payload = build_bitcoin_payload(None, None, Some(then_that), None, None);
+            match serde_json::from_value(payload).unwrap() {
+                ChainhookFullSpecification::Bitcoin(predicate) => ChainhookSpecification::Bitcoin(
+                    predicate
+                        .into_selected_network_specification(&BitcoinNetwork::Mainnet)
+                        .unwrap(),
+                ),
+                _ => unreachable!(),
+            }
+        }
+        Chain::Stacks => {
+            let payload = build_stacks_payload(None, None, Some(then_that), None, None);
+            match serde_json::from_value(payload).unwrap() {
+                ChainhookFullSpecification::Stacks(predicate) => ChainhookSpecification::Stacks(
+                    predicate
+                        .into_selected_network_specification(&StacksNetwork::Mainnet)
+                        .unwrap(),
+                ),
+                _ => unreachable!(),
+            }
+        }
+    };
+    let response = serialized_predicate_with_status(&spec, &PredicateStatus::New).to_string();
+    assert!(response.contains(DEFAULT_UUID));
+    assert!(!response.contains("5f8c2b1e9d7a"));
+    assert!(!response.contains("cn389ncoiwuencr"));
+}
@@ -1,7 +1,8 @@
 use std::{sync::mpsc::channel, thread::sleep, time::Duration};
 
 use chainhook_sdk::{
-    observer::{start_event_observer, EventObserverConfig},
+    indexer::fork_scratch_pad::DEFAULT_MAX_REORG_DEPTH,
+    observer::{start_event_observer, EventObserverConfig, DEFAULT_DISPATCH_BUFFER_CAPACITY},
     types::{BitcoinNetwork, StacksNodeConfig},
     utils::Context,
 };
@@ -164,6 +165,7 @@ async fn start_and_ping_event_observer(config: EventObserverConfig, ingestion_po
     await_observer_started(ingestion_port).await;
 }
 #[test_case("/drop_mempool_tx", Method::POST, None)]
+#[test_case("/drop_mempool_tx", Method::POST, Some(&json!({"dropped_txids": ["0xfaaac1833dc4883e7ec28f61e35b41f896c395f8d288b1a177155de2abd6052f"], "reason": "ReplaceByFee"})))]
 #[test_case("/attachments/new", Method::POST, None)]
 #[test_case("/mined_block", Method::POST, Some(&json!({})))]
 #[test_case("/mined_microblock", Method::POST, Some(&json!({})))]
@@ -196,6 +198,8 @@ async fn it_responds_200_for_unimplemented_endpoints(
         stacks_network: chainhook_sdk::types::StacksNetwork::Devnet,
         data_handler_tx: None,
         prometheus_monitoring_port: None,
+        bitcoin_max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
+        dispatch_buffer_capacity: DEFAULT_DISPATCH_BUFFER_CAPACITY,
     };
     start_and_ping_event_observer(config, ingestion_port).await;
     let url = format!("http://localhost:{ingestion_port}{endpoint}");
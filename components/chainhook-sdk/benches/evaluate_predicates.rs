@@ -0,0 +1,94 @@
+use chainhook_sdk::chainhooks::stacks::{
+    evaluate_stacks_chainhook_on_chain_event, evaluate_stacks_chainhooks_on_chain_event,
+};
+use chainhook_sdk::chainhooks::types::{
+    HookAction, StacksChainhookSpecification, StacksPredicate, StacksPrintEventBasedPredicate,
+};
+use chainhook_sdk::types::{
+    StacksBlockData, StacksBlockUpdate, StacksChainEvent, StacksChainUpdatedWithBlocksData,
+    StacksNetwork,
+};
+use chainhook_sdk::utils::Context;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const NUMBER_OF_PREDICATES: usize = 500;
+
+fn build_chain_event() -> StacksChainEvent {
+    let block: StacksBlockData = serde_json::from_str(std::include_str!(
+        "../src/chainhooks/tests/fixtures/stacks/testnet/107605.json"
+    ))
+    .unwrap();
+    StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks: vec![StacksBlockUpdate {
+            block,
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        }],
+        confirmed_blocks: vec![],
+    })
+}
+
+fn build_chainhook(index: usize) -> StacksChainhookSpecification {
+    StacksChainhookSpecification {
+        uuid: format!("predicate-{index}"),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::MatchesRegex {
+            contract_identifier: "*".into(),
+            regex: format!("(vault|loan)-{index}"),
+            emitting_method: None,
+        }),
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
+    }
+}
+
+fn bench_evaluate_predicates(c: &mut Criterion) {
+    let chain_event = build_chain_event();
+    let chainhooks = (0..NUMBER_OF_PREDICATES)
+        .map(build_chainhook)
+        .collect::<Vec<_>>();
+    let ctx = Context::empty();
+
+    let mut group = c.benchmark_group("evaluate_500_stacks_predicates");
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            chainhooks
+                .iter()
+                .map(|chainhook| {
+                    evaluate_stacks_chainhook_on_chain_event(&chain_event, chainhook, 0, &ctx)
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| {
+            evaluate_stacks_chainhooks_on_chain_event(
+                black_box(&chain_event),
+                chainhooks.iter().collect(),
+                &ctx,
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_evaluate_predicates);
+criterion_main!(benches);
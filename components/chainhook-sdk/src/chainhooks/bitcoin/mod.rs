@@ -1,12 +1,22 @@
 use super::types::{
-    BitcoinChainhookSpecification, BitcoinPredicateType, DescriptorMatchingRule, ExactMatchingRule,
-    HookAction, InputPredicate, MatchingRule, OrdinalOperations, OutputPredicate, StacksOperations,
+    get_bitcoin_network, BitcoinChainhookSpecification, BitcoinPredicateType,
+    DescriptorMatchingRule, ExactMatchingRule, FileRotationPolicy, HookAction, InputPredicate,
+    KafkaHook, MatchingRule, OpReturnEncoding, OrdinalInscriptionTransferPredicate,
+    OrdinalOperations, OutputPredicate, RedisHook, StacksOperations, TriggerOn, ValueMatchingRule,
 };
-use crate::utils::Context;
-
-use bitcoincore_rpc_json::bitcoin::{address::Payload, Address};
+use super::PARALLEL_EVALUATION_THRESHOLD;
+use crate::utils::{build_http_occurrence_request, Context};
+
+use bitcoincore_rpc_json::bitcoin::hashes::{sha256, Hash};
+use bitcoincore_rpc_json::bitcoin::script::Instruction;
+use bitcoincore_rpc_json::bitcoin::{
+    address::{AddressType, Payload},
+    Address, Script,
+};
+use chainhook_types::bitcoin::TxIn;
 use chainhook_types::{
-    BitcoinBlockData, BitcoinChainEvent, BitcoinTransactionData, BlockIdentifier, OrdinalOperation,
+    BitcoinBlockData, BitcoinChainEvent, BitcoinNetwork, BitcoinTransactionData, BlockIdentifier,
+    OrdinalInscriptionRevealData, OrdinalInscriptionTransferData, OrdinalOperation,
     StacksBaseChainOperation, TransactionIdentifier,
 };
 
@@ -15,10 +25,11 @@ use hiro_system_kit::slog;
 use miniscript::bitcoin::secp256k1::Secp256k1;
 use miniscript::Descriptor;
 
-use reqwest::{Client, Method};
+use rayon::prelude::*;
+
 use serde_json::Value as JsonValue;
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     str::FromStr,
 };
 
@@ -26,12 +37,80 @@ use reqwest::RequestBuilder;
 
 use hex::FromHex;
 
+/// Coinbase inputs do not spend any output: they reference a null outpoint (zeroed txid, vout
+/// 0xffffffff).
+fn is_coinbase_input(input: &TxIn) -> bool {
+    let txid = &input.previous_output.txid.hash;
+    input.previous_output.vout == u32::MAX
+        && txid
+            .strip_prefix("0x")
+            .unwrap_or(txid)
+            .chars()
+            .all(|c| c == '0')
+}
+
 pub struct BitcoinTriggerChainhook<'a> {
     pub chainhook: &'a BitcoinChainhookSpecification,
     pub apply: Vec<(Vec<&'a BitcoinTransactionData>, &'a BitcoinBlockData)>,
     pub rollback: Vec<(Vec<&'a BitcoinTransactionData>, &'a BitcoinBlockData)>,
 }
 
+impl<'a> BitcoinTriggerChainhook<'a> {
+    /// Splits the trigger following the `trigger_on` of its predicate: `rollback` only keeps
+    /// the rollbacks, `both` delivers the applies and the rollbacks as distinct triggers.
+    /// Triggers left without blocks are dropped, as well as the triggers without applied
+    /// transactions of the actions that only deliver those.
+    pub fn split_by_trigger_on(self) -> Vec<BitcoinTriggerChainhook<'a>> {
+        let BitcoinTriggerChainhook {
+            chainhook,
+            apply,
+            rollback,
+        } = self;
+        let triggers = match chainhook.trigger_on.unwrap_or_default() {
+            TriggerOn::Apply => vec![(apply, rollback)],
+            TriggerOn::Rollback => vec![(vec![], rollback)],
+            TriggerOn::Both => vec![(apply, vec![]), (vec![], rollback)],
+        };
+        triggers
+            .into_iter()
+            .filter(|(apply, rollback)| {
+                match chainhook.action.delivers_applied_transactions_only() {
+                    true => apply
+                        .iter()
+                        .any(|(transactions, _)| !transactions.is_empty()),
+                    false => !apply.is_empty() || !rollback.is_empty(),
+                }
+            })
+            .map(|(apply, rollback)| BitcoinTriggerChainhook {
+                chainhook,
+                apply,
+                rollback,
+            })
+            .collect()
+    }
+
+    /// Occurrences counted toward `expire_after_occurrence` for the trigger: the distinct
+    /// blocks it applies or rolls back.
+    pub fn count_occurrences(&self) -> u64 {
+        let applied_blocks = self
+            .apply
+            .iter()
+            .map(|(_, block)| &block.block_identifier)
+            .collect::<HashSet<_>>()
+            .len();
+        let rolled_back_blocks = self
+            .rollback
+            .iter()
+            .map(|(_, block)| &block.block_identifier)
+            .collect::<HashSet<_>>()
+            .len();
+        self.chainhook
+            .trigger_on
+            .unwrap_or_default()
+            .count_occurrences(applied_blocks, rolled_back_blocks)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BitcoinTransactionPayload {
     pub block: BitcoinBlockData,
@@ -87,7 +166,12 @@ impl BitcoinChainhookOccurrencePayload {
 
 pub enum BitcoinChainhookOccurrence {
     Http(RequestBuilder, BitcoinChainhookOccurrencePayload),
-    File(String, Vec<u8>),
+    File(String, FileRotationPolicy, Vec<u8>),
+    UnixSocket(String, Vec<u8>),
+    ZstdFile(String, i32, FileRotationPolicy, Vec<u8>),
+    Redis(RedisHook, Vec<u8>),
+    /// Kafka action, message key (if any) and message payload.
+    Kafka(KafkaHook, Option<String>, Vec<u8>),
     Data(BitcoinChainhookOccurrencePayload),
 }
 
@@ -100,95 +184,175 @@ pub fn evaluate_bitcoin_chainhooks_on_chain_event<'a>(
     BTreeMap<&'a str, &'a BlockIdentifier>,
     BTreeMap<&'a str, &'a BlockIdentifier>,
 ) {
+    let evaluations = if active_chainhooks.len() >= PARALLEL_EVALUATION_THRESHOLD {
+        active_chainhooks
+            .par_iter()
+            .map(|chainhook| evaluate_bitcoin_chainhook_on_chain_event(chain_event, chainhook, ctx))
+            .collect::<Vec<_>>()
+    } else {
+        active_chainhooks
+            .iter()
+            .map(|chainhook| evaluate_bitcoin_chainhook_on_chain_event(chain_event, chainhook, ctx))
+            .collect::<Vec<_>>()
+    };
+
     let mut evaluated_predicates = BTreeMap::new();
     let mut triggered_predicates = vec![];
     let mut expired_predicates = BTreeMap::new();
+    // Evaluations are merged following the order of `active_chainhooks`, parallel or not.
+    for (trigger, mut evaluated, mut expired) in evaluations.into_iter() {
+        if let Some(trigger) = trigger {
+            triggered_predicates.append(&mut trigger.split_by_trigger_on());
+        }
+        evaluated_predicates.append(&mut evaluated);
+        expired_predicates.append(&mut expired);
+    }
+    (
+        triggered_predicates,
+        evaluated_predicates,
+        expired_predicates,
+    )
+}
+
+pub fn evaluate_bitcoin_chainhook_on_chain_event<'a>(
+    chain_event: &'a BitcoinChainEvent,
+    chainhook: &'a BitcoinChainhookSpecification,
+    ctx: &Context,
+) -> (
+    Option<BitcoinTriggerChainhook<'a>>,
+    BTreeMap<&'a str, &'a BlockIdentifier>,
+    BTreeMap<&'a str, &'a BlockIdentifier>,
+) {
+    let mut evaluated_predicates = BTreeMap::new();
+    let mut expired_predicates = BTreeMap::new();
+    let mut apply = vec![];
+    let mut rollback = vec![];
 
     match chain_event {
         BitcoinChainEvent::ChainUpdatedWithBlocks(event) => {
-            for chainhook in active_chainhooks.iter() {
-                let mut apply = vec![];
-                let rollback = vec![];
-                let end_block = chainhook.end_block.unwrap_or(u64::MAX);
-
-                for block in event.new_blocks.iter() {
-                    evaluated_predicates.insert(chainhook.uuid.as_str(), &block.block_identifier);
-                    if end_block >= block.block_identifier.index {
-                        let mut hits = vec![];
-                        for tx in block.transactions.iter() {
-                            if chainhook.predicate.evaluate_transaction_predicate(&tx, ctx) {
-                                hits.push(tx);
-                            }
-                        }
-                        if hits.len() > 0 {
-                            apply.push((hits, block));
-                        }
-                    } else {
-                        expired_predicates.insert(chainhook.uuid.as_str(), &block.block_identifier);
-                    }
-                }
-
-                if !apply.is_empty() {
-                    triggered_predicates.push(BitcoinTriggerChainhook {
-                        chainhook,
-                        apply,
-                        rollback,
-                    })
-                }
+            for block in event.new_blocks.iter() {
+                evaluated_predicates.insert(chainhook.uuid.as_str(), &block.block_identifier);
+                let (mut occurrences, mut expirations) =
+                    evaluate_bitcoin_chainhook_on_blocks(vec![block], chainhook, ctx);
+                apply.append(&mut occurrences);
+                expired_predicates.append(&mut expirations);
             }
         }
         BitcoinChainEvent::ChainUpdatedWithReorg(event) => {
-            for chainhook in active_chainhooks.iter() {
-                let mut apply = vec![];
-                let mut rollback = vec![];
-                let end_block = chainhook.end_block.unwrap_or(u64::MAX);
-
-                for block in event.blocks_to_rollback.iter() {
-                    if end_block >= block.block_identifier.index {
-                        let mut hits = vec![];
-                        for tx in block.transactions.iter() {
-                            if chainhook.predicate.evaluate_transaction_predicate(&tx, ctx) {
-                                hits.push(tx);
-                            }
-                        }
-                        if hits.len() > 0 {
-                            rollback.push((hits, block));
-                        }
-                    } else {
-                        expired_predicates.insert(chainhook.uuid.as_str(), &block.block_identifier);
-                    }
-                }
-                for block in event.blocks_to_apply.iter() {
-                    evaluated_predicates.insert(chainhook.uuid.as_str(), &block.block_identifier);
-                    if end_block >= block.block_identifier.index {
-                        let mut hits = vec![];
-                        for tx in block.transactions.iter() {
-                            if chainhook.predicate.evaluate_transaction_predicate(&tx, ctx) {
-                                hits.push(tx);
-                            }
-                        }
-                        if hits.len() > 0 {
-                            apply.push((hits, block));
-                        }
-                    } else {
-                        expired_predicates.insert(chainhook.uuid.as_str(), &block.block_identifier);
-                    }
-                }
-                if !apply.is_empty() || !rollback.is_empty() {
-                    triggered_predicates.push(BitcoinTriggerChainhook {
-                        chainhook,
-                        apply,
-                        rollback,
-                    })
+            for block in event.blocks_to_rollback.iter() {
+                let (mut occurrences, mut expirations) =
+                    evaluate_bitcoin_chainhook_on_blocks(vec![block], chainhook, ctx);
+                rollback.append(&mut occurrences);
+                expired_predicates.append(&mut expirations);
+            }
+            for block in event.blocks_to_apply.iter() {
+                evaluated_predicates.insert(chainhook.uuid.as_str(), &block.block_identifier);
+                let (mut occurrences, mut expirations) =
+                    evaluate_bitcoin_chainhook_on_blocks(vec![block], chainhook, ctx);
+                apply.append(&mut occurrences);
+                expired_predicates.append(&mut expirations);
+            }
+        }
+    }
+    let trigger = if !apply.is_empty() || !rollback.is_empty() {
+        Some(BitcoinTriggerChainhook {
+            chainhook,
+            apply,
+            rollback,
+        })
+    } else {
+        None
+    };
+    (trigger, evaluated_predicates, expired_predicates)
+}
+
+/// Evaluates `active_chainhooks` against blocks the caller already standardized (e.g. with
+/// its own indexer), without going through a chain event. Blocks are considered applied:
+/// the returned triggers never carry rollbacks.
+pub fn evaluate_bitcoin_chainhooks_on_blocks<'a>(
+    blocks: Vec<&'a BitcoinBlockData>,
+    active_chainhooks: &[&'a BitcoinChainhookSpecification],
+    ctx: &Context,
+) -> (
+    Vec<BitcoinTriggerChainhook<'a>>,
+    BTreeMap<&'a str, &'a BlockIdentifier>,
+) {
+    let evaluations = if active_chainhooks.len() >= PARALLEL_EVALUATION_THRESHOLD {
+        active_chainhooks
+            .par_iter()
+            .map(|chainhook| {
+                let (apply, expired) =
+                    evaluate_bitcoin_chainhook_on_blocks(blocks.clone(), chainhook, ctx);
+                (*chainhook, apply, expired)
+            })
+            .collect::<Vec<_>>()
+    } else {
+        active_chainhooks
+            .iter()
+            .map(|chainhook| {
+                let (apply, expired) =
+                    evaluate_bitcoin_chainhook_on_blocks(blocks.clone(), chainhook, ctx);
+                (*chainhook, apply, expired)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut triggered_predicates = vec![];
+    let mut expired_predicates = BTreeMap::new();
+    for (chainhook, apply, mut expired) in evaluations.into_iter() {
+        if !apply.is_empty() {
+            let trigger = BitcoinTriggerChainhook {
+                chainhook,
+                apply,
+                rollback: vec![],
+            };
+            triggered_predicates.append(&mut trigger.split_by_trigger_on());
+        }
+        expired_predicates.append(&mut expired);
+    }
+    (triggered_predicates, expired_predicates)
+}
+
+pub fn evaluate_bitcoin_chainhook_on_blocks<'a>(
+    blocks: Vec<&'a BitcoinBlockData>,
+    chainhook: &'a BitcoinChainhookSpecification,
+    ctx: &Context,
+) -> (
+    Vec<(Vec<&'a BitcoinTransactionData>, &'a BitcoinBlockData)>,
+    BTreeMap<&'a str, &'a BlockIdentifier>,
+) {
+    let mut occurrences = vec![];
+    let mut expired_predicates = BTreeMap::new();
+    let end_block = chainhook.end_block.unwrap_or(u64::MAX);
+    for block in blocks {
+        if end_block >= block.block_identifier.index {
+            let mut hits = vec![];
+            for tx in block.transactions.iter() {
+                if chainhook
+                    .predicate
+                    .evaluate_transaction_predicate(&tx, &chainhook.network, ctx)
+                {
+                    hits.push(tx);
                 }
             }
+            if hits.len() > 0 {
+                occurrences.push((hits, block));
+            }
+        } else {
+            expired_predicates.insert(chainhook.uuid.as_str(), &block.block_identifier);
         }
     }
-    (
-        triggered_predicates,
-        evaluated_predicates,
-        expired_predicates,
-    )
+    (occurrences, expired_predicates)
+}
+
+/// Hex encoded sha256 digest of the body of a revealed inscription.
+pub fn get_inscription_content_hash(reveal: &OrdinalInscriptionRevealData) -> Option<String> {
+    let content_bytes = reveal
+        .content_bytes
+        .strip_prefix("0x")
+        .unwrap_or(&reveal.content_bytes);
+    let content = hex::decode(content_bytes).ok()?;
+    Some(hex::encode(sha256::Hash::hash(&content).to_byte_array()))
 }
 
 pub fn serialize_bitcoin_payload_to_json<'a>(
@@ -196,11 +360,12 @@ pub fn serialize_bitcoin_payload_to_json<'a>(
     proofs: &HashMap<&'a TransactionIdentifier, String>,
 ) -> JsonValue {
     let predicate_spec = trigger.chainhook;
+    let identifier_format = predicate_spec.identifier_format.clone().unwrap_or_default();
     json!({
         "apply": trigger.apply.iter().map(|(transactions, block)| {
             json!({
-                "block_identifier": block.block_identifier,
-                "parent_block_identifier": block.parent_block_identifier,
+                "block_identifier": identifier_format.serialize_block_identifier(&block.block_identifier),
+                "parent_block_identifier": identifier_format.serialize_block_identifier(&block.parent_block_identifier),
                 "timestamp": block.timestamp,
                 "transactions": serialize_bitcoin_transactions_to_json(&predicate_spec, &transactions, proofs),
                 "metadata": block.metadata,
@@ -208,8 +373,8 @@ pub fn serialize_bitcoin_payload_to_json<'a>(
         }).collect::<Vec<_>>(),
         "rollback": trigger.rollback.iter().map(|(transactions, block)| {
             json!({
-                "block_identifier": block.block_identifier,
-                "parent_block_identifier": block.parent_block_identifier,
+                "block_identifier": identifier_format.serialize_block_identifier(&block.block_identifier),
+                "parent_block_identifier": identifier_format.serialize_block_identifier(&block.parent_block_identifier),
                 "timestamp": block.timestamp,
                 "transactions": serialize_bitcoin_transactions_to_json(&predicate_spec, &transactions, proofs),
                 "metadata": block.metadata,
@@ -228,6 +393,7 @@ pub fn serialize_bitcoin_transactions_to_json<'a>(
     transactions: &Vec<&BitcoinTransactionData>,
     proofs: &HashMap<&'a TransactionIdentifier, String>,
 ) -> Vec<JsonValue> {
+    let identifier_format = predicate_spec.identifier_format.clone().unwrap_or_default();
     transactions
         .into_iter()
         .map(|transaction| {
@@ -268,8 +434,13 @@ pub fn serialize_bitcoin_transactions_to_json<'a>(
                 "proof".into(),
                 json!(proofs.get(&transaction.transaction_identifier)),
             );
+            if predicate_spec.action.includes_raw_tx() {
+                if let Some(ref raw_tx) = transaction.metadata.raw_tx {
+                    metadata.insert("raw_tx".into(), json!(raw_tx));
+                }
+            }
             json!({
-                "transaction_identifier": transaction.transaction_identifier,
+                "transaction_identifier": identifier_format.serialize_transaction_identifier(&transaction.transaction_identifier),
                 "operations": transaction.operations,
                 "metadata": metadata
             })
@@ -283,30 +454,55 @@ pub fn handle_bitcoin_hook_action<'a>(
 ) -> Result<BitcoinChainhookOccurrence, String> {
     match &trigger.chainhook.action {
         HookAction::HttpPost(http) => {
-            let client = Client::builder()
-                .build()
-                .map_err(|e| format!("unable to build http client: {}", e.to_string()))?;
-            let host = format!("{}", http.url);
-            let method = Method::POST;
-            let body = serde_json::to_vec(&serialize_bitcoin_payload_to_json(&trigger, proofs))
-                .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
-            let request = client
-                .request(method, &host)
-                .header("Content-Type", "application/json")
-                .header("Authorization", http.authorization_header.clone())
-                .body(body);
+            let payload = serialize_bitcoin_payload_to_json(&trigger, proofs);
+            let request = build_http_occurrence_request(http, payload)?;
 
             let data = BitcoinChainhookOccurrencePayload::from_trigger(trigger);
             Ok(BitcoinChainhookOccurrence::Http(request, data))
         }
         HookAction::FileAppend(disk) => {
+            let bytes =
+                disk.serialize_occurrence(&serialize_bitcoin_payload_to_json(&trigger, proofs))?;
+            Ok(BitcoinChainhookOccurrence::File(
+                disk.path.to_string(),
+                disk.get_rotation_policy(),
+                bytes,
+            ))
+        }
+        HookAction::UnixSocket(socket) => {
             let bytes = serde_json::to_vec(&serialize_bitcoin_payload_to_json(&trigger, proofs))
                 .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
-            Ok(BitcoinChainhookOccurrence::File(
+            Ok(BitcoinChainhookOccurrence::UnixSocket(
+                socket.path.to_string(),
+                bytes,
+            ))
+        }
+        HookAction::ZstdFileAppend(disk) => {
+            let bytes = serde_json::to_vec(&serialize_bitcoin_payload_to_json(&trigger, proofs))
+                .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
+            Ok(BitcoinChainhookOccurrence::ZstdFile(
                 disk.path.to_string(),
+                disk.get_compression_level(),
+                disk.get_rotation_policy(),
                 bytes,
             ))
         }
+        HookAction::Redis(redis) => {
+            let bytes = serde_json::to_vec(&serialize_bitcoin_payload_to_json(&trigger, proofs))
+                .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
+            Ok(BitcoinChainhookOccurrence::Redis(redis.clone(), bytes))
+        }
+        HookAction::Kafka(kafka) => {
+            let block_identifier = trigger
+                .apply
+                .last()
+                .or(trigger.rollback.last())
+                .map(|(_, block)| &block.block_identifier);
+            let key = kafka.render_key(&trigger.chainhook.uuid, block_identifier);
+            let bytes = serde_json::to_vec(&serialize_bitcoin_payload_to_json(&trigger, proofs))
+                .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
+            Ok(BitcoinChainhookOccurrence::Kafka(kafka.clone(), key, bytes))
+        }
         HookAction::Noop => Ok(BitcoinChainhookOccurrence::Data(
             BitcoinChainhookOccurrencePayload::from_trigger(trigger),
         )),
@@ -332,10 +528,29 @@ impl OpReturn {
     }
 }
 
+/// Returns the data pushed after the OP_RETURN opcode of `script_pubkey`, or None if the script
+/// is not an OP_RETURN script.
+fn get_op_return_data(script_pubkey: &str) -> Option<Vec<u8>> {
+    let bytes =
+        Vec::<u8>::from_hex(script_pubkey.strip_prefix("0x").unwrap_or(script_pubkey)).ok()?;
+    let script = Script::from_bytes(&bytes);
+    if !script.is_op_return() {
+        return None;
+    }
+    let mut data = vec![];
+    for instruction in script.instructions().skip(1) {
+        if let Ok(Instruction::PushBytes(push)) = instruction {
+            data.extend_from_slice(push.as_bytes());
+        }
+    }
+    Some(data)
+}
+
 impl BitcoinPredicateType {
     pub fn evaluate_transaction_predicate(
         &self,
         tx: &BitcoinTransactionData,
+        network: &BitcoinNetwork,
         ctx: &Context,
     ) -> bool {
         // TODO(lgalabru): follow-up on this implementation
@@ -344,6 +559,44 @@ impl BitcoinPredicateType {
             BitcoinPredicateType::Txid(ExactMatchingRule::Equals(txid)) => {
                 tx.transaction_identifier.hash.eq(txid)
             }
+            BitcoinPredicateType::TransactionVersion(rule) => rule.matches(tx.metadata.version),
+            BitcoinPredicateType::FeeRate { sats_per_vbyte_gt } => {
+                // Coinbase inputs are left out of standardized transactions.
+                if tx.metadata.inputs.is_empty() || tx.metadata.inputs.iter().any(is_coinbase_input)
+                {
+                    return false;
+                }
+                match tx.metadata.vsize {
+                    Some(vsize) if vsize > 0 => {
+                        tx.metadata.fee as f64 / vsize as f64 > *sats_per_vbyte_gt
+                    }
+                    _ => false,
+                }
+            }
+            BitcoinPredicateType::OpReturn { contains, encoding } => {
+                let expected_data = match (contains.as_str(), encoding) {
+                    ("*", _) => None,
+                    (pattern, OpReturnEncoding::Hex) => {
+                        match Vec::<u8>::from_hex(pattern.strip_prefix("0x").unwrap_or(pattern)) {
+                            Ok(bytes) => Some(bytes),
+                            Err(_) => return false,
+                        }
+                    }
+                    (pattern, OpReturnEncoding::Utf8) => Some(pattern.as_bytes().to_vec()),
+                };
+                tx.metadata.outputs.iter().any(|output| {
+                    match (get_op_return_data(&output.script_pubkey), &expected_data) {
+                        (Some(_), None) => true,
+                        (Some(data), Some(expected)) => {
+                            expected.is_empty()
+                                || data
+                                    .windows(expected.len())
+                                    .any(|window| window == expected.as_slice())
+                        }
+                        (None, _) => false,
+                    }
+                })
+            }
             BitcoinPredicateType::Outputs(OutputPredicate::OpReturn(rule)) => {
                 for output in tx.metadata.outputs.iter() {
                     // opret contains the op_return data section prefixed with `0x`.
@@ -432,6 +685,26 @@ impl BitcoinPredicateType {
                 }
                 false
             }
+            BitcoinPredicateType::Outputs(OutputPredicate::P2tr(ExactMatchingRule::Equals(
+                encoded_address,
+            ))) => {
+                // Taproot addresses are bech32m encoded witness v1 programs: other witness
+                // versions, bech32 encoded, are rejected.
+                let address = match Address::from_str(encoded_address) {
+                    Ok(address) => address.assume_checked(),
+                    Err(_) => return false,
+                };
+                if address.address_type() != Some(AddressType::P2tr) {
+                    return false;
+                }
+                let address_bytes = hex::encode(address.script_pubkey().as_bytes());
+                for output in tx.metadata.outputs.iter() {
+                    if output.script_pubkey[2..] == address_bytes {
+                        return true;
+                    }
+                }
+                false
+            }
             BitcoinPredicateType::Outputs(OutputPredicate::Descriptor(
                 DescriptorMatchingRule { expression, range },
             )) => {
@@ -476,10 +749,46 @@ impl BitcoinPredicateType {
 
                 false
             }
+            BitcoinPredicateType::Outputs(OutputPredicate::TotalValue(rule)) => {
+                // Summing over all the outputs of the transaction, in sats.
+                let total_value = tx
+                    .metadata
+                    .outputs
+                    .iter()
+                    .fold(0u64, |total, output| total.saturating_add(output.value));
+                match rule {
+                    ValueMatchingRule::HigherThan(threshold) => total_value > *threshold,
+                    ValueMatchingRule::LowerThan(threshold) => total_value < *threshold,
+                }
+            }
+            BitcoinPredicateType::Outputs(OutputPredicate::DistinctAddressCount(rule)) => {
+                // OP_RETURN and other non standard outputs are not paying an address,
+                // and are left out of the count.
+                let mut addresses = HashSet::new();
+                for output in tx.metadata.outputs.iter() {
+                    let Ok(script_bytes) = hex::decode(&output.script_pubkey[2..]) else {
+                        continue;
+                    };
+                    if Payload::from_script(Script::from_bytes(&script_bytes)).is_ok() {
+                        addresses.insert(script_bytes);
+                    }
+                }
+                let count = addresses.len() as u64;
+                match rule {
+                    ValueMatchingRule::HigherThan(threshold) => count > *threshold,
+                    ValueMatchingRule::LowerThan(threshold) => count < *threshold,
+                }
+            }
             BitcoinPredicateType::Inputs(InputPredicate::Txid(predicate)) => {
                 // TODO(lgalabru): add support for transaction chainhing, if enabled
+                // Standardized txids are lowercased and 0x prefixed, the predicate's txid is
+                // normalized the same way before being compared.
+                let expected_txid = TransactionIdentifier::new(&predicate.txid);
                 for input in tx.metadata.inputs.iter() {
-                    if input.previous_output.txid.hash.eq(&predicate.txid)
+                    if is_coinbase_input(input) {
+                        continue;
+                    }
+                    if input.previous_output.txid.hash.eq(&expected_txid.hash)
                         && input.previous_output.vout.eq(&predicate.vout)
                     {
                         return true;
@@ -487,6 +796,30 @@ impl BitcoinPredicateType {
                 }
                 false
             }
+            BitcoinPredicateType::Inputs(InputPredicate::Address(rule)) => {
+                let expected_scripts = rule
+                    .addresses()
+                    .iter()
+                    .filter_map(|encoded_address| Address::from_str(encoded_address).ok())
+                    .filter_map(|address| {
+                        address.require_network(get_bitcoin_network(network)).ok()
+                    })
+                    .map(|address| hex::encode(address.script_pubkey().as_bytes()))
+                    .collect::<Vec<_>>();
+                for input in tx.metadata.inputs.iter() {
+                    // Prevouts are only resolved by nodes serving full block breakdowns.
+                    let Some(ref script_pubkey) = input.previous_output.script_pubkey else {
+                        continue;
+                    };
+                    if expected_scripts
+                        .iter()
+                        .any(|expected| script_pubkey[2..].eq(expected))
+                    {
+                        return true;
+                    }
+                }
+                false
+            }
             BitcoinPredicateType::Inputs(InputPredicate::WitnessScript(_)) => {
                 // TODO(lgalabru)
                 unimplemented!()
@@ -540,8 +873,70 @@ impl BitcoinPredicateType {
                 }
                 false
             }
+            BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionRevealed(
+                expected_reveal,
+            )) => {
+                for op in tx.metadata.ordinal_operations.iter() {
+                    let reveal = match op {
+                        OrdinalOperation::InscriptionRevealed(reveal) => reveal,
+                        OrdinalOperation::InscriptionTransferred(_) => continue,
+                    };
+                    if let Some(ref rule) = expected_reveal.ordinal_number {
+                        if !rule.matches(reveal.ordinal_number) {
+                            continue;
+                        }
+                    }
+                    if let Some(ref expected_hash) = expected_reveal.content_hash {
+                        let expected_hash =
+                            expected_hash.strip_prefix("0x").unwrap_or(expected_hash);
+                        match get_inscription_content_hash(reveal) {
+                            Some(content_hash)
+                                if content_hash.eq_ignore_ascii_case(expected_hash) => {}
+                            _ => continue,
+                        }
+                    }
+                    if !expected_reveal.is_content_type_matching(&reveal.content_type) {
+                        continue;
+                    }
+                    return true;
+                }
+                false
+            }
+            BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionTransferred(
+                expected_transfer,
+            )) => {
+                for op in tx.metadata.ordinal_operations.iter() {
+                    let transfer = match op {
+                        OrdinalOperation::InscriptionTransferred(transfer) => transfer,
+                        OrdinalOperation::InscriptionRevealed(_) => continue,
+                    };
+                    if is_inscription_transfer_matching(expected_transfer, transfer) {
+                        return true;
+                    }
+                }
+                false
+            }
+        }
+    }
+}
+
+fn is_inscription_transfer_matching(
+    expected_transfer: &OrdinalInscriptionTransferPredicate,
+    transfer: &OrdinalInscriptionTransferData,
+) -> bool {
+    if let Some(ordinal_number) = expected_transfer.ordinal_number {
+        if transfer.ordinal_number != ordinal_number {
+            return false;
+        }
+    }
+    if let Some(ref satpoint) = expected_transfer.satpoint {
+        if !transfer.satpoint_pre_transfer.eq(satpoint)
+            && !transfer.satpoint_post_transfer.eq(satpoint)
+        {
+            return false;
         }
     }
+    true
 }
 
 #[cfg(test)]
@@ -1,7 +1,15 @@
-use super::super::types::MatchingRule;
+use super::super::types::{
+    AddressMatchingRule, BlockIdentifierIndexRule, FileHook, MatchingRule, OpReturnEncoding,
+    OrdinalInscriptionRevealPredicate, OrdinalInscriptionTransferPredicate, TxinPredicate,
+    ValueMatchingRule, VersionMatchingRule,
+};
 use super::*;
 use crate::types::BitcoinTransactionMetadata;
-use chainhook_types::bitcoin::TxOut;
+use chainhook_types::bitcoin::{OutPoint, TxIn, TxOut};
+use chainhook_types::{
+    BitcoinBlockMetadata, BitcoinNetwork, OrdinalInscriptionNumber, OrdinalInscriptionRevealData,
+    OrdinalInscriptionTransferData, OrdinalInscriptionTransferDestination,
+};
 
 use test_case::test_case;
 
@@ -101,6 +109,808 @@ fn test_descriptor_evaluation(addr: &str, expr: &str) {
     script_pubkey_evaluation(OutputPredicate::Descriptor(rule.clone()), "0xffff", false);
 }
 
+// Output of the first BIP86 receive address: OP_1 pushing the 32 bytes taproot output key.
+const P2TR_SCRIPT_PUBKEY: &str =
+    "0x5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c";
+
+#[test_case(
+    "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+    true;
+    "P2tr: matches the taproot address of the output"
+)]
+#[test_case(
+    "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+    false;
+    "P2tr: does not match another taproot address"
+)]
+#[test_case(
+    "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+    false;
+    "P2tr: does not match a witness v0 address"
+)]
+fn test_p2tr_evaluation(address: &str, matches: bool) {
+    script_pubkey_evaluation(
+        OutputPredicate::P2tr(ExactMatchingRule::Equals(address.to_string())),
+        P2TR_SCRIPT_PUBKEY,
+        matches,
+    );
+}
+
+#[test_case(
+    vec![40_000, 70_000],
+    ValueMatchingRule::HigherThan(100_000),
+    true;
+    "TotalValue: HigherThan matches outputs summing over the threshold"
+)]
+#[test_case(
+    vec![40_000, 50_000],
+    ValueMatchingRule::HigherThan(100_000),
+    false;
+    "TotalValue: HigherThan does not match outputs summing under the threshold"
+)]
+#[test_case(
+    vec![80_000],
+    ValueMatchingRule::HigherThan(50_000),
+    true;
+    "TotalValue: HigherThan matches a single output over the threshold"
+)]
+#[test_case(
+    vec![40_000, 50_000],
+    ValueMatchingRule::LowerThan(100_000),
+    true;
+    "TotalValue: LowerThan matches outputs summing under the threshold"
+)]
+#[test_case(
+    vec![u64::MAX, 1],
+    ValueMatchingRule::HigherThan(100_000),
+    true;
+    "TotalValue: sum of outputs does not overflow"
+)]
+fn test_total_value_evaluation(values: Vec<u64>, rule: ValueMatchingRule, matches: bool) {
+    let predicate = BitcoinPredicateType::Outputs(OutputPredicate::TotalValue(rule));
+    let outputs = values
+        .into_iter()
+        .map(|value| TxOut {
+            value,
+            script_pubkey: String::from("0x"),
+        })
+        .collect();
+    let tx = build_transaction_with_outputs(outputs);
+    let ctx = Context::empty();
+
+    assert_eq!(
+        matches,
+        predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx)
+    );
+}
+
+#[test_case(
+    build_payout_outputs(&[1, 2, 3, 4, 5]),
+    ValueMatchingRule::HigherThan(4),
+    true;
+    "DistinctAddressCount: HigherThan matches a payout to many distinct addresses"
+)]
+#[test_case(
+    build_payout_outputs(&[1, 2, 3, 4]),
+    ValueMatchingRule::HigherThan(4),
+    false;
+    "DistinctAddressCount: HigherThan is exclusive"
+)]
+#[test_case(
+    build_payout_outputs(&[1, 1, 2, 2, 2, 3]),
+    ValueMatchingRule::HigherThan(3),
+    false;
+    "DistinctAddressCount: repeated addresses are counted once"
+)]
+#[test_case(
+    build_payout_outputs(&[1, 1, 2, 2, 2, 3]),
+    ValueMatchingRule::LowerThan(4),
+    true;
+    "DistinctAddressCount: LowerThan matches repeated addresses"
+)]
+#[test_case(
+    [
+        build_payout_outputs(&[1, 2]),
+        vec![
+            TxOut {
+                value: 0,
+                script_pubkey: String::from("0x6a0b68656c6c6f20776f726c64"),
+            },
+            TxOut {
+                value: 0,
+                script_pubkey: String::from("0x51"),
+            },
+        ],
+    ]
+    .concat(),
+    ValueMatchingRule::HigherThan(2),
+    false;
+    "DistinctAddressCount: OP_RETURN and non address outputs are not counted"
+)]
+fn test_distinct_address_count_evaluation(
+    outputs: Vec<TxOut>,
+    rule: ValueMatchingRule,
+    matches: bool,
+) {
+    let predicate = BitcoinPredicateType::Outputs(OutputPredicate::DistinctAddressCount(rule));
+    let tx = build_transaction_with_outputs(outputs);
+    let ctx = Context::empty();
+
+    assert_eq!(
+        matches,
+        predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx)
+    );
+}
+
+// Builds outputs alternating between P2PKH and P2WPKH scripts, one per seed,
+// so that identical seeds pay the same address.
+fn build_payout_outputs(seeds: &[u8]) -> Vec<TxOut> {
+    seeds
+        .iter()
+        .map(|seed| {
+            let pubkey_hash = hex::encode([*seed; 20]);
+            let script_pubkey = if seed % 2 == 0 {
+                format!("0x0014{}", pubkey_hash)
+            } else {
+                format!("0x76a914{}88ac", pubkey_hash)
+            };
+            TxOut {
+                value: 10_000,
+                script_pubkey,
+            }
+        })
+        .collect()
+}
+
+#[test_case("*", OpReturnEncoding::Hex, vec![0, 1]; "OpReturn: wildcard matches any OP_RETURN output")]
+#[test_case("MRKR", OpReturnEncoding::Utf8, vec![0]; "OpReturn: utf8 marker")]
+#[test_case("0x4D524B520102", OpReturnEncoding::Hex, vec![0]; "OpReturn: hex pattern spanning several pushes")]
+#[test_case("524b52", OpReturnEncoding::Hex, vec![0]; "OpReturn: hex pattern without prefix")]
+#[test_case("d524", OpReturnEncoding::Hex, vec![]; "OpReturn: hex patterns are matched on byte boundaries")]
+#[test_case("hello", OpReturnEncoding::Utf8, vec![1]; "OpReturn: utf8 data pushed with OP_PUSHDATA1")]
+#[test_case("68656c6c6f", OpReturnEncoding::Hex, vec![1]; "OpReturn: hex data pushed with OP_PUSHDATA1")]
+#[test_case("world", OpReturnEncoding::Utf8, vec![]; "OpReturn: missing utf8 data")]
+fn test_op_return_data_evaluation(
+    contains: &str,
+    encoding: OpReturnEncoding,
+    expected_transactions: Vec<usize>,
+) {
+    let block = build_block_with_op_return_outputs();
+    let predicate = BitcoinPredicateType::OpReturn {
+        contains: contains.to_string(),
+        encoding,
+    };
+    assert!(predicate.validate().is_ok());
+
+    let actual_transactions = block
+        .transactions
+        .iter()
+        .enumerate()
+        .filter(|(_, tx)| {
+            predicate.evaluate_transaction_predicate(
+                tx,
+                &BitcoinNetwork::Mainnet,
+                &Context::empty(),
+            )
+        })
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    assert_eq!(actual_transactions, expected_transactions);
+}
+
+#[test_case(-1.0 ; "negative threshold")]
+#[test_case(f64::NAN ; "NaN threshold")]
+#[test_case(f64::INFINITY ; "infinite threshold")]
+fn invalid_fee_rate_threshold_is_rejected(sats_per_vbyte_gt: f64) {
+    let predicate = BitcoinPredicateType::FeeRate { sats_per_vbyte_gt };
+    assert!(predicate.validate().is_err());
+}
+
+#[test]
+fn invalid_op_return_hex_pattern_is_rejected() {
+    let predicate = BitcoinPredicateType::OpReturn {
+        contains: String::from("0x4d5"),
+        encoding: OpReturnEncoding::Hex,
+    };
+    assert!(predicate.validate().is_err());
+}
+
+// Block carrying:
+// - a payout and an OP_RETURN output pushing the `MRKR` marker then 0x0102ff,
+// - an OP_RETURN output pushing `hello` with OP_PUSHDATA1,
+// - a payout without OP_RETURN output.
+fn build_block_with_op_return_outputs() -> BitcoinBlockData {
+    let mut marker_outputs = build_payout_outputs(&[2]);
+    marker_outputs.push(TxOut {
+        value: 0,
+        script_pubkey: String::from("0x6a044d524b52030102ff"),
+    });
+    BitcoinBlockData {
+        block_identifier: BlockIdentifier {
+            index: 2,
+            hash: String::from("0x02"),
+        },
+        parent_block_identifier: BlockIdentifier {
+            index: 1,
+            hash: String::from("0x01"),
+        },
+        timestamp: 0,
+        transactions: vec![
+            build_transaction_with_outputs(marker_outputs),
+            build_transaction_with_outputs(vec![TxOut {
+                value: 0,
+                script_pubkey: String::from("0x6a4c0568656c6c6f"),
+            }]),
+            build_transaction_with_outputs(build_payout_outputs(&[3])),
+        ],
+        metadata: BitcoinBlockMetadata {
+            network: BitcoinNetwork::Regtest,
+        },
+    }
+}
+
+#[test]
+fn test_evaluating_predicates_on_standardized_blocks() {
+    let block = BitcoinBlockData {
+        block_identifier: BlockIdentifier {
+            index: 2,
+            hash: String::from("0x02"),
+        },
+        parent_block_identifier: BlockIdentifier {
+            index: 1,
+            hash: String::from("0x01"),
+        },
+        timestamp: 0,
+        transactions: vec![
+            build_transaction_with_outputs(vec![TxOut {
+                value: 10_000,
+                script_pubkey: String::from("0x6a0b68656c6c6f20776f726c64"),
+            }]),
+            build_transaction_with_outputs(build_payout_outputs(&[1, 2, 3])),
+        ],
+        metadata: BitcoinBlockMetadata {
+            network: BitcoinNetwork::Regtest,
+        },
+    };
+    let build_specification = |uuid: &str, predicate: OutputPredicate, end_block: Option<u64>| {
+        BitcoinChainhookSpecification {
+            uuid: uuid.to_string(),
+            owner_uuid: None,
+            name: uuid.to_string(),
+            network: BitcoinNetwork::Regtest,
+            version: 1,
+            blocks: None,
+            start_block: None,
+            start_block_offset_from_tip: None,
+            end_block,
+            expire_after_occurrence: None,
+            predicate: BitcoinPredicateType::Outputs(predicate),
+            action: HookAction::Noop,
+            include_proof: false,
+            include_inputs: false,
+            include_outputs: false,
+            include_witness: false,
+            identifier_format: None,
+            stop_on_match: None,
+            trigger_on: None,
+            min_confirmations: None,
+            enabled: true,
+            expired_at: None,
+        }
+    };
+    let op_return = build_specification(
+        "op_return",
+        OutputPredicate::OpReturn(MatchingRule::StartsWith(String::from("hello"))),
+        None,
+    );
+    let payout = build_specification(
+        "payout",
+        OutputPredicate::DistinctAddressCount(ValueMatchingRule::HigherThan(5)),
+        None,
+    );
+    let expired = build_specification(
+        "expired",
+        OutputPredicate::OpReturn(MatchingRule::StartsWith(String::from("hello"))),
+        Some(1),
+    );
+
+    let (triggered, expired_predicates) = evaluate_bitcoin_chainhooks_on_blocks(
+        vec![&block],
+        &[&op_return, &payout, &expired],
+        &Context::empty(),
+    );
+
+    assert_eq!(triggered.len(), 1);
+    assert_eq!(triggered[0].chainhook.uuid, "op_return");
+    assert_eq!(triggered[0].apply.len(), 1);
+    assert_eq!(
+        triggered[0].apply[0].0[0].metadata.outputs,
+        block.transactions[0].metadata.outputs
+    );
+    assert!(triggered[0].rollback.is_empty());
+    assert_eq!(
+        expired_predicates.get("expired"),
+        Some(&&block.block_identifier)
+    );
+}
+
+#[test_case(1, VersionMatchingRule::Equals(2), false ; "TransactionVersion: Equals does not match version 1")]
+#[test_case(2, VersionMatchingRule::Equals(2), true ; "TransactionVersion: Equals matches version 2")]
+#[test_case(3, VersionMatchingRule::Equals(2), false ; "TransactionVersion: Equals does not match version 3")]
+#[test_case(1, VersionMatchingRule::In(vec![2, 3]), false ; "TransactionVersion: In does not match version 1")]
+#[test_case(2, VersionMatchingRule::In(vec![2, 3]), true ; "TransactionVersion: In matches version 2")]
+#[test_case(3, VersionMatchingRule::In(vec![2, 3]), true ; "TransactionVersion: In matches version 3")]
+fn test_transaction_version_evaluation(version: u32, rule: VersionMatchingRule, matches: bool) {
+    let predicate = BitcoinPredicateType::TransactionVersion(rule);
+    let mut tx = build_transaction_with_outputs(vec![]);
+    tx.metadata.version = version;
+    let ctx = Context::empty();
+
+    assert_eq!(
+        matches,
+        predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx)
+    );
+}
+
+const SPENDING_ADDRESS: &str = "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa";
+const OTHER_ADDRESS: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+#[test_case(
+    Some(SPENDING_ADDRESS),
+    AddressMatchingRule::Equals(SPENDING_ADDRESS.into()),
+    true;
+    "Address: Equals matches a transaction spending from the address"
+)]
+#[test_case(
+    Some(SPENDING_ADDRESS),
+    AddressMatchingRule::In(vec![OTHER_ADDRESS.into(), SPENDING_ADDRESS.into()]),
+    true;
+    "Address: In matches a transaction spending from one of the addresses"
+)]
+#[test_case(
+    Some(OTHER_ADDRESS),
+    AddressMatchingRule::Equals(SPENDING_ADDRESS.into()),
+    false;
+    "Address: Equals does not match a transaction spending from another address"
+)]
+#[test_case(
+    None,
+    AddressMatchingRule::Equals(SPENDING_ADDRESS.into()),
+    false;
+    "Address: does not match inputs with unresolved prevouts"
+)]
+#[test_case(
+    Some(SPENDING_ADDRESS),
+    AddressMatchingRule::Equals("not-an-address".into()),
+    false;
+    "Address: invalid addresses are ignored"
+)]
+#[test_case(
+    Some(SPENDING_ADDRESS),
+    AddressMatchingRule::Equals("mpXwg4jMtRhuSpVq4xS3HFHmCmWp9NyGKt".into()),
+    false;
+    "Address: addresses of another network are ignored"
+)]
+fn test_input_address_evaluation(
+    spent_address: Option<&str>,
+    rule: AddressMatchingRule,
+    matches: bool,
+) {
+    let predicate = BitcoinPredicateType::Inputs(InputPredicate::Address(rule));
+    let mut tx = build_transaction_with_outputs(vec![]);
+    tx.metadata.inputs = vec![build_input_spending_from(spent_address)];
+    let ctx = Context::empty();
+
+    assert_eq!(
+        matches,
+        predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx)
+    );
+}
+
+#[test]
+fn test_input_address_evaluation_on_full_breakdown_prevout() {
+    // Prevout of a `getblock <hash> 3` input, as returned by bitcoind 25.0+.
+    let prevout: crate::indexer::bitcoin::BitcoinTransactionInputPrevoutFullBreakdown =
+        serde_json::from_value(json!({
+            "generated": false,
+            "height": 820000,
+            "value": 0.0015,
+            "scriptPubKey": {
+                "asm": "OP_DUP OP_HASH160 62e907b15cbf27d5425399ebf6f0fb50ebb88f18 OP_EQUALVERIFY OP_CHECKSIG",
+                "desc": "addr(1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa)#7a6gfe6c",
+                "hex": "76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac",
+                "address": "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+                "type": "pubkeyhash"
+            }
+        }))
+        .unwrap();
+    let script_pubkey = prevout
+        .script_pub_key
+        .map(|script_pub_key| format!("0x{}", hex::encode(script_pub_key.hex)));
+    let mut tx = build_transaction_with_outputs(vec![]);
+    tx.metadata.inputs = vec![build_input_spending_from(None)];
+    tx.metadata.inputs[0].previous_output.script_pubkey = script_pubkey;
+    let predicate = BitcoinPredicateType::Inputs(InputPredicate::Address(
+        AddressMatchingRule::Equals(SPENDING_ADDRESS.into()),
+    ));
+    let ctx = Context::empty();
+
+    assert!(predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx));
+}
+
+const SPENT_TXID: &str = "0x6b3a6b43f0a1d6e8d3cd40ca27d9a3a4a0bdc71e4a9c3d3b8ad52e7fe53a8a51";
+const NULL_TXID: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+#[test_case(SPENT_TXID, 1, vec!["0x02"]; "Txid: exact outpoint")]
+#[test_case(&SPENT_TXID[2..], 1, vec!["0x02"]; "Txid: txid without 0x prefix")]
+#[test_case(SPENT_TXID, 2, vec![]; "Txid: unspent vout")]
+#[test_case(NULL_TXID, u32::MAX, vec![]; "Txid: coinbase inputs are skipped")]
+fn test_input_txid_evaluation(txid: &str, vout: u32, expected_txids: Vec<&str>) {
+    let block = build_block_spending_outpoints();
+    let predicate = BitcoinChainhookSpecification {
+        uuid: "inputs".into(),
+        owner_uuid: None,
+        name: "inputs".into(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        start_block_offset_from_tip: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Inputs(InputPredicate::Txid(TxinPredicate {
+            txid: txid.to_string(),
+            vout,
+        })),
+        action: HookAction::Noop,
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let (triggered, _) =
+        evaluate_bitcoin_chainhooks_on_blocks(vec![&block], &[&predicate], &Context::empty());
+
+    let matched_txids = triggered
+        .iter()
+        .flat_map(|trigger| trigger.apply.iter())
+        .flat_map(|(transactions, _)| transactions.iter())
+        .map(|tx| tx.transaction_identifier.hash.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(matched_txids, expected_txids);
+}
+
+#[test_case("0x6b3a"; "Txid: truncated txid")]
+#[test_case("not-a-txid"; "Txid: non hex txid")]
+fn invalid_input_txid_is_rejected(txid: &str) {
+    let predicate = BitcoinPredicateType::Inputs(InputPredicate::Txid(TxinPredicate {
+        txid: txid.to_string(),
+        vout: 0,
+    }));
+    assert!(predicate.validate().is_err());
+}
+
+// Block carrying:
+// - a coinbase transaction (0x01), referencing the null outpoint,
+// - a transaction (0x02) spending SPENT_TXID:1,
+// - a transaction (0x03) spending SPENT_TXID:0.
+fn build_block_spending_outpoints() -> BitcoinBlockData {
+    let spending = |txid: &str, inputs: Vec<TxIn>| {
+        let mut tx = build_transaction_with_outputs(build_payout_outputs(&[1]));
+        tx.transaction_identifier = TransactionIdentifier {
+            hash: String::from(txid),
+        };
+        tx.metadata.inputs = inputs;
+        tx
+    };
+    BitcoinBlockData {
+        block_identifier: BlockIdentifier {
+            index: 2,
+            hash: String::from("0x02"),
+        },
+        parent_block_identifier: BlockIdentifier {
+            index: 1,
+            hash: String::from("0x01"),
+        },
+        timestamp: 0,
+        transactions: vec![
+            spending(
+                "0x01",
+                vec![build_input_spending_outpoint(NULL_TXID, u32::MAX)],
+            ),
+            spending("0x02", vec![build_input_spending_outpoint(SPENT_TXID, 1)]),
+            spending("0x03", vec![build_input_spending_outpoint(SPENT_TXID, 0)]),
+        ],
+        metadata: BitcoinBlockMetadata {
+            network: BitcoinNetwork::Regtest,
+        },
+    }
+}
+
+fn build_input_spending_outpoint(txid: &str, vout: u32) -> TxIn {
+    let mut input = build_input_spending_from(None);
+    input.previous_output.txid = TransactionIdentifier::new(txid);
+    input.previous_output.vout = vout;
+    input
+}
+
+fn build_input_spending_from(address: Option<&str>) -> TxIn {
+    let script_pubkey = address.map(|address| {
+        let script_pubkey = Address::from_str(address)
+            .unwrap()
+            .assume_checked()
+            .script_pubkey();
+        format!("0x{}", hex::encode(script_pubkey))
+    });
+    TxIn {
+        previous_output: OutPoint {
+            txid: TransactionIdentifier {
+                hash: String::from(""),
+            },
+            vout: 0,
+            value: 10_000,
+            block_height: 0,
+            script_pubkey,
+        },
+        script_sig: String::from("0x"),
+        sequence: 0,
+        witness: vec![],
+    }
+}
+
+const TRANSFERRED_SAT: u64 = 1_252_201_400_444_387;
+const SATPOINT_PRE_TRANSFER: &str =
+    "5b79df5b2af1e5c0b2a4a0e5a3d3c3e6a9ad3bfb4fbd3c6a1d1f5f2fdfa66a9b:0:0";
+const SATPOINT_POST_TRANSFER: &str =
+    "d5e2a1c6ba4b54a8c2c16dbd2a5b9e3a49a3f0a5bcbd1f0e0d1b7b2e2f6ab1c0:1:0";
+
+#[test_case(
+    OrdinalInscriptionTransferPredicate { ordinal_number: Some(TRANSFERRED_SAT), satpoint: None },
+    true;
+    "InscriptionTransferred: matches the sat being moved"
+)]
+#[test_case(
+    OrdinalInscriptionTransferPredicate { ordinal_number: Some(TRANSFERRED_SAT + 1), satpoint: None },
+    false;
+    "InscriptionTransferred: does not match another sat"
+)]
+#[test_case(
+    OrdinalInscriptionTransferPredicate { ordinal_number: None, satpoint: Some(SATPOINT_POST_TRANSFER.into()) },
+    true;
+    "InscriptionTransferred: matches a transfer to the satpoint"
+)]
+#[test_case(
+    OrdinalInscriptionTransferPredicate { ordinal_number: None, satpoint: Some(SATPOINT_PRE_TRANSFER.into()) },
+    true;
+    "InscriptionTransferred: matches a transfer from the satpoint"
+)]
+#[test_case(
+    OrdinalInscriptionTransferPredicate { ordinal_number: Some(TRANSFERRED_SAT + 1), satpoint: Some(SATPOINT_POST_TRANSFER.into()) },
+    false;
+    "InscriptionTransferred: requires both constraints to match"
+)]
+fn test_inscription_transferred_evaluation(
+    expected_transfer: OrdinalInscriptionTransferPredicate,
+    matches: bool,
+) {
+    let predicate = BitcoinPredicateType::OrdinalsProtocol(
+        OrdinalOperations::InscriptionTransferred(expected_transfer),
+    );
+    let tx = build_inscription_transfer();
+    let ctx = Context::empty();
+
+    assert_eq!(
+        matches,
+        predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx)
+    );
+}
+
+fn build_inscription_transfer() -> BitcoinTransactionData {
+    let mut tx = build_transaction_with_outputs(vec![]);
+    tx.metadata.ordinal_operations = vec![OrdinalOperation::InscriptionTransferred(
+        OrdinalInscriptionTransferData {
+            ordinal_number: TRANSFERRED_SAT,
+            destination: OrdinalInscriptionTransferDestination::Transferred(
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".into(),
+            ),
+            satpoint_pre_transfer: SATPOINT_PRE_TRANSFER.into(),
+            satpoint_post_transfer: SATPOINT_POST_TRANSFER.into(),
+            post_transfer_output_value: Some(10_000),
+            tx_index: 0,
+        },
+    )];
+    tx
+}
+
+// First sat mined in block 9.
+const REVEALED_SAT: u64 = 450_000_000_000;
+
+#[test_case(
+    BlockIdentifierIndexRule::Equals(REVEALED_SAT),
+    true;
+    "InscriptionRevealed: Equals matches the inscribed sat"
+)]
+#[test_case(
+    BlockIdentifierIndexRule::Between(450_000_000_000, 499_999_999_999),
+    true;
+    "InscriptionRevealed: Between matches a sat of the range"
+)]
+#[test_case(
+    BlockIdentifierIndexRule::Between(0, REVEALED_SAT - 1),
+    false;
+    "InscriptionRevealed: Between does not match a sat out of the range"
+)]
+#[test_case(
+    BlockIdentifierIndexRule::HigherThan(REVEALED_SAT),
+    false;
+    "InscriptionRevealed: HigherThan is exclusive"
+)]
+#[test_case(
+    BlockIdentifierIndexRule::LowerThan(REVEALED_SAT + 1),
+    true;
+    "InscriptionRevealed: LowerThan matches a lower sat"
+)]
+fn test_inscription_revealed_evaluation(rule: BlockIdentifierIndexRule, matches: bool) {
+    let predicate = BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionRevealed(
+        OrdinalInscriptionRevealPredicate {
+            ordinal_number: Some(rule),
+            content_hash: None,
+            content_type: None,
+        },
+    ));
+    let mut tx = build_transaction_with_outputs(vec![]);
+    tx.metadata.ordinal_operations = vec![OrdinalOperation::InscriptionRevealed(
+        build_inscription_reveal(REVEALED_SAT, "0x68656c6c6f"),
+    )];
+    let ctx = Context::empty();
+
+    assert_eq!(
+        matches,
+        predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx)
+    );
+}
+
+// sha256("hello")
+const HELLO_CONTENT_HASH: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+#[test_case("0x68656c6c6f", HELLO_CONTENT_HASH, true ; "InscriptionRevealed: content_hash matches the body")]
+#[test_case("0x68656c6c6f", "0x2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824", true ; "InscriptionRevealed: content_hash ignores prefix and case")]
+#[test_case("0x776f726c64", HELLO_CONTENT_HASH, false ; "InscriptionRevealed: content_hash does not match another body")]
+fn test_inscription_revealed_content_hash_evaluation(
+    content_bytes: &str,
+    content_hash: &str,
+    matches: bool,
+) {
+    let predicate = BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionRevealed(
+        OrdinalInscriptionRevealPredicate {
+            ordinal_number: None,
+            content_hash: Some(content_hash.to_string()),
+            content_type: None,
+        },
+    ));
+    let mut tx = build_transaction_with_outputs(vec![]);
+    tx.metadata.ordinal_operations = vec![OrdinalOperation::InscriptionRevealed(
+        build_inscription_reveal(REVEALED_SAT, content_bytes),
+    )];
+    let ctx = Context::empty();
+
+    assert_eq!(
+        matches,
+        predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx)
+    );
+}
+
+#[test_case("image/png", "image/png", true ; "InscriptionRevealed: content_type matches the same type")]
+#[test_case("image/png", "IMAGE/PNG", true ; "InscriptionRevealed: content_type ignores case")]
+#[test_case("image/png", "image/jpeg", false ; "InscriptionRevealed: content_type does not match another type")]
+#[test_case("text/plain;charset=utf-8", "text/plain", false ; "InscriptionRevealed: content_type requires parameters to match")]
+#[test_case("image/webp", "image/*", true ; "InscriptionRevealed: content_type prefix matches a type of the family")]
+#[test_case("text/plain;charset=utf-8", "image/*", false ; "InscriptionRevealed: content_type prefix does not match another family")]
+#[test_case("text/plain;charset=utf-8", "text/plain*", true ; "InscriptionRevealed: content_type prefix matches parameters")]
+fn test_inscription_revealed_content_type_evaluation(
+    content_type: &str,
+    expected_content_type: &str,
+    matches: bool,
+) {
+    let predicate = BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionRevealed(
+        OrdinalInscriptionRevealPredicate {
+            ordinal_number: None,
+            content_hash: None,
+            content_type: Some(expected_content_type.to_string()),
+        },
+    ));
+    let mut reveal = build_inscription_reveal(REVEALED_SAT, "0x68656c6c6f");
+    reveal.content_type = content_type.to_string();
+    let mut tx = build_transaction_with_outputs(vec![]);
+    tx.metadata.ordinal_operations = vec![OrdinalOperation::InscriptionRevealed(reveal)];
+    let ctx = Context::empty();
+
+    assert_eq!(
+        matches,
+        predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx)
+    );
+}
+
+#[test]
+fn test_inscription_revealed_content_type_filters_reveals_of_a_transaction() {
+    let predicate = BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionRevealed(
+        OrdinalInscriptionRevealPredicate {
+            ordinal_number: None,
+            content_hash: None,
+            content_type: Some("image/*".to_string()),
+        },
+    ));
+    let mut text_reveal = build_inscription_reveal(REVEALED_SAT, "0x68656c6c6f");
+    text_reveal.content_type = "text/plain;charset=utf-8".to_string();
+    let mut image_reveal = build_inscription_reveal(REVEALED_SAT + 1, "0x68656c6c6f");
+    image_reveal.content_type = "image/png".to_string();
+    let ctx = Context::empty();
+
+    let mut tx = build_transaction_with_outputs(vec![]);
+    tx.metadata.ordinal_operations = vec![OrdinalOperation::InscriptionRevealed(text_reveal)];
+    assert!(!predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx));
+    tx.metadata
+        .ordinal_operations
+        .push(OrdinalOperation::InscriptionRevealed(image_reveal));
+    assert!(predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx));
+}
+
+fn build_inscription_reveal(
+    ordinal_number: u64,
+    content_bytes: &str,
+) -> OrdinalInscriptionRevealData {
+    OrdinalInscriptionRevealData {
+        content_bytes: content_bytes.to_string(),
+        content_type: "text/plain;charset=utf-8".to_string(),
+        content_length: (content_bytes.len() - 2) / 2,
+        inscription_number: OrdinalInscriptionNumber::zero(),
+        inscription_fee: 0,
+        inscription_output_value: 10_000,
+        inscription_id: String::from(""),
+        inscription_input_index: 0,
+        inscription_pointer: None,
+        inscriber_address: None,
+        delegate: None,
+        metaprotocol: None,
+        metadata: None,
+        parent: None,
+        ordinal_number,
+        ordinal_block_height: 9,
+        ordinal_offset: 0,
+        tx_index: 0,
+        transfers_pre_inscription: 0,
+        satpoint_post_inscription: String::from(""),
+        curse_type: None,
+    }
+}
+
+fn build_transaction_with_outputs(outputs: Vec<TxOut>) -> BitcoinTransactionData {
+    BitcoinTransactionData {
+        transaction_identifier: TransactionIdentifier {
+            hash: String::from(""),
+        },
+        operations: vec![],
+        metadata: BitcoinTransactionMetadata {
+            version: 2,
+            fee: 0,
+            raw_tx: None,
+            vsize: None,
+            proof: None,
+            inputs: vec![],
+            stacks_operations: vec![],
+            ordinal_operations: vec![],
+            outputs,
+        },
+    }
+}
+
 // script_pubkey_evaluation is a helper that evaluates a a script_pubkey against a transaction predicate.
 fn script_pubkey_evaluation(output: OutputPredicate, script_pubkey: &str, matches: bool) {
     let predicate = BitcoinPredicateType::Outputs(output);
@@ -116,7 +926,10 @@ fn script_pubkey_evaluation(output: OutputPredicate, script_pubkey: &str, matche
         },
         operations: vec![],
         metadata: BitcoinTransactionMetadata {
+            version: 2,
             fee: 0,
+            raw_tx: None,
+            vsize: None,
             proof: None,
             inputs: vec![],
             stacks_operations: vec![],
@@ -131,5 +944,209 @@ fn script_pubkey_evaluation(output: OutputPredicate, script_pubkey: &str, matche
         tracer: false,
     };
 
-    assert_eq!(matches, predicate.evaluate_transaction_predicate(&tx, &ctx));
+    assert_eq!(
+        matches,
+        predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx)
+    );
+}
+
+#[test_case(json!({"scope": "block"}), "Any block"; "block")]
+#[test_case(json!({"scope": "txid", "equals": "0xfaaa"}), "Transaction 0xfaaa"; "txid")]
+#[test_case(json!({"scope": "transaction_version", "in": [1, 2]}), "Transaction version in 1, 2"; "transaction version")]
+#[test_case(json!({"scope": "inputs", "txid": {"txid": "0xfaaa", "vout": 1}}), "Input spending 0xfaaa:1"; "input txid")]
+#[test_case(json!({"scope": "inputs", "witness_script": {"starts_with": "0x5120"}}), "Input witness script starting with 0x5120"; "input witness script")]
+#[test_case(json!({"scope": "inputs", "address": {"in": ["bc1qa", "bc1qb"]}}), "Input spending from bc1qa, bc1qb"; "input address")]
+#[test_case(json!({"scope": "outputs", "op_return": {"equals": "0x69d3"}}), "OP_RETURN output equal to 0x69d3"; "op_return")]
+#[test_case(json!({"scope": "outputs", "p2wpkh": {"equals": "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"}}), "P2WPKH output to bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"; "p2wpkh")]
+#[test_case(json!({"scope": "outputs", "p2tr": {"equals": "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"}}), "P2TR output to bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"; "p2tr")]
+#[test_case(json!({"scope": "outputs", "descriptor": {"expression": "wpkh(02f6)"}}), "Output matching descriptor wpkh(02f6)"; "descriptor")]
+#[test_case(json!({"scope": "outputs", "total_value": {"higher_than": 100000}}), "Total output value higher than 100000 sats"; "total value")]
+#[test_case(json!({"scope": "fee_rate", "sats_per_vbyte_gt": 12.5}), "Fee rate higher than 12.5 sats/vB"; "fee rate")]
+#[test_case(json!({"scope": "stacks_protocol", "operation": "block_committed"}), "Stacks block committed operation"; "stacks protocol")]
+#[test_case(json!({"scope": "ordinals_protocol", "operation": "inscription_feed"}), "Inscription revealed or transferred"; "inscription feed")]
+#[test_case(json!({"scope": "ordinals_protocol", "operation": "inscription_transferred", "satpoint": "abcd:0:0"}), "Inscription transfer from or to abcd:0:0"; "inscription transferred from or to a satpoint")]
+#[test_case(json!({"scope": "ordinals_protocol", "operation": "inscription_revealed", "ordinal_number": {"between": [1, 2]}}), "Inscription reveal on sat between 1 and 2"; "inscription revealed")]
+#[test_case(json!({"scope": "ordinals_protocol", "operation": "inscription_revealed", "content_type": "image/*"}), "Inscription reveal with content type image/*"; "inscription revealed with content type")]
+fn test_bitcoin_predicate_description(predicate: JsonValue, expected: &str) {
+    let predicate: BitcoinPredicateType = serde_json::from_value(predicate).unwrap();
+    assert_eq!(predicate.describe(), expected);
+}
+
+const RAW_TX: &str = "0200000001518a3ae57f2ed58a3b3d9c4a1ec7bda0a4a3d927ca40cdd3e8d6a1f0436b3a6b0100000000fdffffff01e02202000000000016001462e907b15cbf27d5425399ebf6f0fb50ebb88f1800000000";
+const RAW_TXID: &str = "b35e8752af4b2bde2e4b2071e86669735bf9a9d264d51988cb899bc0b1af5077";
+
+// Block returned by `getblock <hash> 3`, with a coinbase and a transaction spending
+// `SPENT_TXID:1` (150,000 sats) to a 140,000 sats output, paying 10,000 sats for 82 vbytes
+// (~121.95 sats/vB), reduced to the fields chainhook relies on.
+fn build_full_block_breakdown() -> crate::indexer::bitcoin::BitcoinBlockFullBreakdown {
+    serde_json::from_value(json!({
+        "hash": "0000000000000000000000000000000000000000000000000000000000000002",
+        "height": 2,
+        "time": 0,
+        "nonce": 0,
+        "previousblockhash": "0000000000000000000000000000000000000000000000000000000000000001",
+        "confirmations": 1,
+        "tx": [
+            {
+                "txid": "0000000000000000000000000000000000000000000000000000000000000003",
+                "version": 2,
+                "vsize": 100,
+                "vin": [{ "sequence": 4294967295u32 }],
+                "vout": [{ "value": 50.0, "n": 0, "scriptPubKey": { "asm": "", "hex": "51" } }]
+            },
+            {
+                "txid": RAW_TXID,
+                "version": 2,
+                "hex": RAW_TX,
+                "vsize": 82,
+                "vin": [{
+                    "sequence": 4294967293u32,
+                    "txid": &SPENT_TXID[2..],
+                    "vout": 1,
+                    "scriptSig": { "hex": "" },
+                    "prevout": { "height": 1, "value": 0.0015 }
+                }],
+                "vout": [{
+                    "value": 0.0014,
+                    "n": 0,
+                    "scriptPubKey": {
+                        "asm": "",
+                        "hex": "001462e907b15cbf27d5425399ebf6f0fb50ebb88f18"
+                    }
+                }]
+            }
+        ]
+    }))
+    .unwrap()
+}
+
+#[test_case(true => vec![false, true] ; "raw transactions kept when requested")]
+#[test_case(false => vec![false, false] ; "raw transactions dropped otherwise")]
+fn test_raw_transactions_in_standardized_blocks(include_raw_tx: bool) -> Vec<bool> {
+    let block = crate::indexer::bitcoin::standardize_bitcoin_block(
+        build_full_block_breakdown(),
+        &BitcoinNetwork::Regtest,
+        include_raw_tx,
+        &Context::empty(),
+    )
+    .unwrap();
+    block
+        .transactions
+        .iter()
+        .map(|tx| tx.metadata.raw_tx.is_some())
+        .collect()
+}
+
+#[test_case(Some(true) => true ; "raw transactions included when requested")]
+#[test_case(Some(false) => false ; "raw transactions excluded when not requested")]
+#[test_case(None => false ; "raw transactions excluded by default")]
+fn test_raw_transactions_in_occurrences(include_raw_tx: Option<bool>) -> bool {
+    let ctx = Context::empty();
+    let block = crate::indexer::bitcoin::standardize_bitcoin_block(
+        build_full_block_breakdown(),
+        &BitcoinNetwork::Regtest,
+        true,
+        &ctx,
+    )
+    .unwrap();
+    let spec = BitcoinChainhookSpecification {
+        uuid: "raw_tx".to_string(),
+        owner_uuid: None,
+        name: "raw_tx".to_string(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        start_block_offset_from_tip: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::Txid(ExactMatchingRule::Equals(format!("0x{RAW_TXID}"))),
+        action: HookAction::FileAppend(FileHook {
+            path: "raw_tx.json".to_string(),
+            format: None,
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx,
+        }),
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        identifier_format: None,
+        stop_on_match: None,
+        trigger_on: None,
+        min_confirmations: None,
+        enabled: true,
+        expired_at: None,
+    };
+    let (mut triggered, _) = evaluate_bitcoin_chainhooks_on_blocks(vec![&block], &[&spec], &ctx);
+    assert_eq!(triggered.len(), 1);
+    let occurrence = handle_bitcoin_hook_action(triggered.remove(0), &HashMap::new()).unwrap();
+    let BitcoinChainhookOccurrence::File(_, _, bytes) = occurrence else {
+        panic!("expected a file occurrence");
+    };
+    let payload: JsonValue = serde_json::from_slice(&bytes).unwrap();
+    let Some(raw_tx) = payload["apply"][0]["transactions"][0]["metadata"]["raw_tx"].as_str() else {
+        return false;
+    };
+    let bytes = hex::decode(raw_tx.strip_prefix("0x").unwrap()).unwrap();
+    let tx: bitcoincore_rpc_json::bitcoin::Transaction =
+        bitcoincore_rpc_json::bitcoin::consensus::deserialize(&bytes).unwrap();
+    assert_eq!(tx.txid().to_string(), RAW_TXID);
+    true
+}
+
+#[test_case(10_000, Some(100), 99.9, true ; "FeeRate: above the threshold")]
+#[test_case(10_000, Some(100), 100.0, false ; "FeeRate: at the threshold")]
+#[test_case(10_000, Some(100), 100.1, false ; "FeeRate: below the threshold")]
+#[test_case(1_000, Some(141), 7.09, true ; "FeeRate: fractional fee rate above the threshold")]
+#[test_case(1_000, Some(141), 7.1, false ; "FeeRate: fractional fee rate below the threshold")]
+#[test_case(10_000, None, 0.0, false ; "FeeRate: unknown vsize")]
+#[test_case(10_000, Some(0), 0.0, false ; "FeeRate: zero vsize")]
+fn test_fee_rate_evaluation(fee: u64, vsize: Option<u64>, sats_per_vbyte_gt: f64, matches: bool) {
+    let predicate = BitcoinPredicateType::FeeRate { sats_per_vbyte_gt };
+    let mut tx = build_transaction_with_outputs(vec![]);
+    tx.metadata.inputs = vec![build_input_spending_outpoint(SPENT_TXID, 1)];
+    tx.metadata.fee = fee;
+    tx.metadata.vsize = vsize;
+    let ctx = Context::empty();
+
+    assert_eq!(
+        matches,
+        predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx)
+    );
+}
+
+#[test]
+fn test_fee_rate_evaluation_skips_coinbase_transactions() {
+    let predicate = BitcoinPredicateType::FeeRate {
+        sats_per_vbyte_gt: 0.0,
+    };
+    let mut tx = build_transaction_with_outputs(vec![]);
+    tx.metadata.inputs = vec![build_input_spending_outpoint(NULL_TXID, u32::MAX)];
+    tx.metadata.fee = 10_000;
+    tx.metadata.vsize = Some(100);
+    let ctx = Context::empty();
+
+    assert!(!predicate.evaluate_transaction_predicate(&tx, &BitcoinNetwork::Mainnet, &ctx));
+}
+
+#[test_case(121.9 => vec![format!("0x{RAW_TXID}")] ; "threshold below the fee rate of the fixture")]
+#[test_case(122.0 => Vec::<String>::new() ; "threshold above the fee rate of the fixture")]
+fn test_fee_rate_evaluation_on_standardized_blocks(sats_per_vbyte_gt: f64) -> Vec<String> {
+    let ctx = Context::empty();
+    let block = crate::indexer::bitcoin::standardize_bitcoin_block(
+        build_full_block_breakdown(),
+        &BitcoinNetwork::Regtest,
+        false,
+        &ctx,
+    )
+    .unwrap();
+    let predicate = BitcoinPredicateType::FeeRate { sats_per_vbyte_gt };
+    block
+        .transactions
+        .iter()
+        .filter(|tx| predicate.evaluate_transaction_predicate(tx, &BitcoinNetwork::Mainnet, &ctx))
+        .map(|tx| tx.transaction_identifier.hash.clone())
+        .collect()
 }
@@ -1,6 +1,11 @@
 pub mod bitcoin;
+#[cfg(feature = "schema")]
+pub mod schema;
 pub mod stacks;
 pub mod types;
 
+/// Number of active predicates above which predicates are evaluated in parallel.
+pub const PARALLEL_EVALUATION_THRESHOLD: usize = 64;
+
 #[cfg(test)]
 pub mod tests;
@@ -0,0 +1,17 @@
+use super::types::{BitcoinChainhookFullSpecification, StacksChainhookFullSpecification};
+use schemars::schema_for;
+use serde_json::Value as JsonValue;
+
+/// JSON Schema describing the Stacks chainhook specifications accepted by the registration
+/// API, covering every predicate and action variant.
+pub fn generate_stacks_predicate_schema() -> JsonValue {
+    let schema = schema_for!(StacksChainhookFullSpecification);
+    serde_json::to_value(schema).expect("unable to serialize Stacks chainhook schema")
+}
+
+/// JSON Schema describing the Bitcoin chainhook specifications accepted by the registration
+/// API, covering every predicate and action variant.
+pub fn generate_bitcoin_predicate_schema() -> JsonValue {
+    let schema = schema_for!(BitcoinChainhookFullSpecification);
+    serde_json::to_value(schema).expect("unable to serialize Bitcoin chainhook schema")
+}
@@ -1,24 +1,36 @@
-use crate::utils::{AbstractStacksBlock, Context};
+pub mod replay;
+pub mod traits;
+
+use crate::utils::{build_http_occurrence_request, AbstractStacksBlock, Context};
 
 use super::types::{
-    BlockIdentifierIndexRule, ExactMatchingRule, HookAction, StacksChainhookSpecification,
-    StacksContractDeploymentPredicate, StacksPredicate, StacksPrintEventBasedPredicate,
+    get_compiled_regex, BlockIdentifierIndexRule, ContractIdentifierRule, DerivedFieldRule,
+    ExactMatchingRule, FileRotationPolicy, HookAction, IdentifierFormat, KafkaHook,
+    NftTokenIdRange, RedisHook, StacksChainhookSpecification, StacksContractCallArgMatch,
+    StacksContractDeploymentPredicate, StacksEventConstraint, StacksEventType,
+    StacksFtEventBasedPredicate, StacksNftEventBasedPredicate, StacksPredicate,
+    StacksPrincipalType, StacksPrintEventBasedPredicate, StacksStxEventBasedPredicate,
+    StacksStxTransferRecipientBasedPredicate, TriggerOn, ValueMatchingRule,
 };
+use super::PARALLEL_EVALUATION_THRESHOLD;
 use chainhook_types::{
     BlockIdentifier, StacksChainEvent, StacksTransactionData, StacksTransactionEvent,
     StacksTransactionEventPayload, StacksTransactionKind, TransactionIdentifier,
 };
 use hiro_system_kit::slog;
+use rayon::prelude::*;
 use regex::Regex;
-use reqwest::{Client, Method};
+use reqwest::{Client, StatusCode};
 use serde_json::Value as JsonValue;
+use stacks_rpc_client::clarity::codec::{StacksTransaction, TransactionPayload};
 use stacks_rpc_client::clarity::stacks_common::codec::StacksMessageCodec;
 use stacks_rpc_client::clarity::vm::types::{CharType, SequenceData, Value as ClarityValue};
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Cursor;
 
 use reqwest::RequestBuilder;
 
+#[derive(Clone)]
 pub struct StacksTriggerChainhook<'a> {
     pub chainhook: &'a StacksChainhookSpecification,
     pub apply: Vec<(Vec<&'a StacksTransactionData>, &'a dyn AbstractStacksBlock)>,
@@ -47,17 +59,151 @@ pub struct StacksChainhookOccurrencePayload {
     pub apply: Vec<StacksApplyTransactionPayload>,
     pub rollback: Vec<StacksRollbackTransactionPayload>,
     pub chainhook: StacksChainhookPayload,
+    /// The serialized occurrence, restricted to the fields selected by the `projection` of
+    /// the predicate, when it has one.
+    pub projection: Option<JsonValue>,
 }
 pub enum StacksChainhookOccurrence {
     Http(RequestBuilder),
-    File(String, Vec<u8>),
+    File(String, FileRotationPolicy, Vec<u8>),
+    UnixSocket(String, Vec<u8>),
+    ZstdFile(String, i32, FileRotationPolicy, Vec<u8>),
+    Redis(RedisHook, Vec<u8>),
+    /// Kafka action, message key (if any) and message payload.
+    Kafka(KafkaHook, Option<String>, Vec<u8>),
     Data(StacksChainhookOccurrencePayload),
 }
 
+impl StacksChainhookOccurrencePayload {
+    pub fn from_trigger<'a>(
+        trigger: StacksTriggerChainhook<'a>,
+        mempool_timestamps: &HashMap<TransactionIdentifier, i64>,
+        ctx: &Context,
+    ) -> StacksChainhookOccurrencePayload {
+        let chainhook = trigger.chainhook;
+        let projection = chainhook.projection.as_ref().map(|_| {
+            serialize_stacks_payload_to_json(
+                trigger.clone(),
+                &HashMap::new(),
+                mempool_timestamps,
+                ctx,
+            )
+        });
+        let capture = |transaction: &StacksTransactionData| {
+            let mut transaction = transaction.clone();
+            transaction
+                .metadata
+                .receipt
+                .events
+                .retain(|event| is_captured_event(event, chainhook));
+            transaction
+        };
+        StacksChainhookOccurrencePayload {
+            apply: trigger
+                .apply
+                .into_iter()
+                .map(|(transactions, block)| {
+                    let transactions = transactions.into_iter().map(&capture).collect::<Vec<_>>();
+                    StacksApplyTransactionPayload {
+                        block_identifier: block.get_identifier().clone(),
+                        transactions,
+                    }
+                })
+                .collect::<Vec<_>>(),
+            rollback: trigger
+                .rollback
+                .into_iter()
+                .map(|(transactions, block)| {
+                    let transactions = transactions.into_iter().map(&capture).collect::<Vec<_>>();
+                    StacksRollbackTransactionPayload {
+                        block_identifier: block.get_identifier().clone(),
+                        transactions,
+                    }
+                })
+                .collect::<Vec<_>>(),
+            chainhook: StacksChainhookPayload {
+                uuid: chainhook.uuid.clone(),
+            },
+            projection,
+        }
+    }
+}
+
 impl<'a> StacksTriggerChainhook<'a> {
     pub fn should_decode_clarity_value(&self) -> bool {
         self.chainhook.decode_clarity_values.unwrap_or(false)
     }
+
+    /// Splits the trigger following the `trigger_on` of its predicate: `rollback` only keeps
+    /// the rollbacks, `both` delivers the applies and the rollbacks as distinct triggers.
+    /// Triggers left without blocks are dropped, as well as the triggers without applied
+    /// transactions of the actions that only deliver those.
+    pub fn split_by_trigger_on(self) -> Vec<StacksTriggerChainhook<'a>> {
+        let StacksTriggerChainhook {
+            chainhook,
+            apply,
+            rollback,
+        } = self;
+        let triggers = match chainhook.trigger_on.unwrap_or_default() {
+            TriggerOn::Apply => vec![(apply, rollback)],
+            TriggerOn::Rollback => vec![(vec![], rollback)],
+            TriggerOn::Both => vec![(apply, vec![]), (vec![], rollback)],
+        };
+        triggers
+            .into_iter()
+            .filter(|(apply, rollback)| {
+                match chainhook.action.delivers_applied_transactions_only() {
+                    true => apply
+                        .iter()
+                        .any(|(transactions, _)| !transactions.is_empty()),
+                    false => !apply.is_empty() || !rollback.is_empty(),
+                }
+            })
+            .map(|(apply, rollback)| StacksTriggerChainhook {
+                chainhook,
+                apply,
+                rollback,
+            })
+            .collect()
+    }
+
+    /// Occurrences counted toward `expire_after_occurrence` for the trigger, see
+    /// `count_triggering_blocks`.
+    pub fn count_occurrences(&self) -> u64 {
+        self.chainhook
+            .trigger_on
+            .unwrap_or_default()
+            .count_occurrences(
+                count_triggering_blocks(&self.apply),
+                count_triggering_blocks(&self.rollback),
+            )
+    }
+}
+
+/// Number of distinct blocks triggering `occurrences`: microblocks count along with the anchor
+/// block confirming them, i.e. the blocks and microblocks building on the same anchor block
+/// are a single block.
+pub fn count_triggering_blocks(
+    occurrences: &[(Vec<&StacksTransactionData>, &dyn AbstractStacksBlock)],
+) -> usize {
+    occurrences
+        .iter()
+        .map(|(_, block)| block.get_anchor_block_identifier())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Whether `occurrences` already reached `occurrences_limit` triggering blocks, none of them
+/// building on `anchor_block_identifier`.
+fn is_occurrences_limit_reached(
+    occurrences: &[(Vec<&StacksTransactionData>, &dyn AbstractStacksBlock)],
+    anchor_block_identifier: &BlockIdentifier,
+    occurrences_limit: usize,
+) -> bool {
+    count_triggering_blocks(occurrences) >= occurrences_limit
+        && !occurrences
+            .iter()
+            .any(|(_, block)| block.get_anchor_block_identifier() == anchor_block_identifier)
 }
 
 pub fn evaluate_stacks_chainhooks_on_chain_event<'a>(
@@ -69,193 +215,463 @@ pub fn evaluate_stacks_chainhooks_on_chain_event<'a>(
     BTreeMap<&'a str, &'a BlockIdentifier>,
     BTreeMap<&'a str, &'a BlockIdentifier>,
 ) {
+    evaluate_stacks_chainhooks_on_chain_event_with_occurrences(
+        chain_event,
+        active_chainhooks,
+        &HashMap::new(),
+        ctx,
+    )
+}
+
+/// Same as `evaluate_stacks_chainhooks_on_chain_event`, for predicates that already triggered
+/// the number of occurrences tracked in `occurrences`, by uuid: these count towards their
+/// `expire_after_occurrence`.
+pub fn evaluate_stacks_chainhooks_on_chain_event_with_occurrences<'a>(
+    chain_event: &'a StacksChainEvent,
+    active_chainhooks: Vec<&'a StacksChainhookSpecification>,
+    occurrences: &HashMap<String, u64>,
+    ctx: &Context,
+) -> (
+    Vec<StacksTriggerChainhook<'a>>,
+    BTreeMap<&'a str, &'a BlockIdentifier>,
+    BTreeMap<&'a str, &'a BlockIdentifier>,
+) {
+    let evaluate = |chainhook: &&'a StacksChainhookSpecification| {
+        let tracked_occurrences = *occurrences.get(&chainhook.uuid).unwrap_or(&0);
+        evaluate_stacks_chainhook_on_chain_event(chain_event, *chainhook, tracked_occurrences, ctx)
+    };
+    let evaluations = if active_chainhooks.len() >= PARALLEL_EVALUATION_THRESHOLD {
+        active_chainhooks
+            .par_iter()
+            .map(evaluate)
+            .collect::<Vec<_>>()
+    } else {
+        active_chainhooks.iter().map(evaluate).collect::<Vec<_>>()
+    };
+
     let mut triggered_predicates = vec![];
     let mut evaluated_predicates = BTreeMap::new();
     let mut expired_predicates = BTreeMap::new();
+    // Evaluations are merged following the order of `active_chainhooks`, parallel or not.
+    for (trigger, mut evaluated, mut expired) in evaluations.into_iter() {
+        if let Some(trigger) = trigger {
+            triggered_predicates.append(&mut trigger.split_by_trigger_on());
+        }
+        evaluated_predicates.append(&mut evaluated);
+        expired_predicates.append(&mut expired);
+    }
+    (
+        triggered_predicates,
+        evaluated_predicates,
+        expired_predicates,
+    )
+}
+
+/// A block matched by a predicate, along with the transactions of the block that matched.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StacksPredicateMatch {
+    pub predicate_uuid: String,
+    pub matched_block: BlockIdentifier,
+    pub matched_txids: Vec<String>,
+    /// Set when the block is rolled back by the chain event rather than applied.
+    pub rollback: bool,
+}
+
+/// Evaluates `predicates` on `chain_event`, and returns the blocks and transactions each of
+/// them matched, in the order of `predicates`. Unlike `evaluate_stacks_chainhooks_on_chain_event`,
+/// the result doesn't borrow from its arguments and carries nothing related to the actions of
+/// the predicates, which can be left to `HookAction::Noop`: embedders deliver the matches
+/// their own way.
+pub fn match_stacks_predicates_on_chain_event(
+    chain_event: &StacksChainEvent,
+    predicates: &[StacksChainhookSpecification],
+    ctx: &Context,
+) -> Vec<StacksPredicateMatch> {
+    let (triggers, _, _) =
+        evaluate_stacks_chainhooks_on_chain_event(chain_event, predicates.iter().collect(), ctx);
+    let mut matches = vec![];
+    for trigger in triggers.into_iter() {
+        let occurrences = trigger
+            .apply
+            .iter()
+            .map(|occurrence| (occurrence, false))
+            .chain(trigger.rollback.iter().map(|occurrence| (occurrence, true)));
+        for ((transactions, block), rollback) in occurrences {
+            matches.push(StacksPredicateMatch {
+                predicate_uuid: trigger.chainhook.uuid.clone(),
+                matched_block: block.get_identifier().clone(),
+                matched_txids: transactions
+                    .iter()
+                    .map(|transaction| transaction.transaction_identifier.hash.clone())
+                    .collect(),
+                rollback,
+            });
+        }
+    }
+    matches
+}
+
+/// Evaluates `chainhook` on `chain_event`, `occurrences` being the number of occurrences
+/// it already triggered.
+pub fn evaluate_stacks_chainhook_on_chain_event<'a>(
+    chain_event: &'a StacksChainEvent,
+    chainhook: &'a StacksChainhookSpecification,
+    occurrences: u64,
+    ctx: &Context,
+) -> (
+    Option<StacksTriggerChainhook<'a>>,
+    BTreeMap<&'a str, &'a BlockIdentifier>,
+    BTreeMap<&'a str, &'a BlockIdentifier>,
+) {
+    let mut evaluated_predicates = BTreeMap::new();
+    let mut expired_predicates = BTreeMap::new();
+    let mut apply = vec![];
+    let mut rollback = vec![];
+    // Once `expire_after_occurrence` distinct blocks were applied, counting the `occurrences` of
+    // the previous events, the remaining blocks of the event are not evaluated anymore, except
+    // for the microblocks of the blocks already applied. The applies of `rollback` only
+    // predicates are not occurrences.
+    let occurrences_limit = match chainhook.expire_after_occurrence {
+        Some(limit) if limit > 0 && chainhook.trigger_on.unwrap_or_default().includes_apply() => {
+            limit.saturating_sub(occurrences) as usize
+        }
+        _ => usize::MAX,
+    };
+    // Blocks outside of [`start_block`, `end_block`] are neither applied nor rolled back. Once a
+    // block past `end_block` is applied, the predicate is expired and the remaining blocks of the
+    // event are not evaluated anymore.
+    let start_block = chainhook.start_block.unwrap_or(0);
+    let end_block = chainhook.end_block.unwrap_or(u64::MAX);
+    let is_in_range = |block_height: u64| start_block <= block_height && block_height <= end_block;
     match chain_event {
         StacksChainEvent::ChainUpdatedWithBlocks(update) => {
-            for chainhook in active_chainhooks.iter() {
-                let mut apply = vec![];
-                let mut rollback = vec![];
-                for block_update in update.new_blocks.iter() {
-                    evaluated_predicates.insert(
-                        chainhook.uuid.as_str(),
-                        &block_update.block.block_identifier,
-                    );
+            for block_update in update.new_blocks.iter() {
+                if is_occurrences_limit_reached(
+                    &apply,
+                    &block_update.block.parent_block_identifier,
+                    occurrences_limit,
+                ) {
+                    break;
+                }
+                let block_identifier = &block_update.block.block_identifier;
+                if block_identifier.index > end_block {
+                    expired_predicates.insert(chainhook.uuid.as_str(), block_identifier);
+                    break;
+                }
+                if block_identifier.index < start_block {
+                    continue;
+                }
+                if is_from_another_network(&block_update.block, chainhook, ctx) {
+                    continue;
+                }
+                evaluated_predicates.insert(chainhook.uuid.as_str(), block_identifier);
+                let mut microblocks_txids = HashSet::new();
 
-                    for parents_microblock_to_apply in
-                        block_update.parent_microblocks_to_apply.iter()
-                    {
-                        let (mut occurrences, mut expirations) =
-                            evaluate_stacks_chainhook_on_blocks(
-                                vec![parents_microblock_to_apply],
-                                chainhook,
-                                ctx,
-                            );
-                        apply.append(&mut occurrences);
-                        expired_predicates.append(&mut expirations);
-                    }
-                    for parents_microblock_to_rolllback in
-                        block_update.parent_microblocks_to_rollback.iter()
-                    {
-                        let (mut occurrences, mut expirations) =
-                            evaluate_stacks_chainhook_on_blocks(
-                                vec![parents_microblock_to_rolllback],
-                                chainhook,
-                                ctx,
-                            );
-                        rollback.append(&mut occurrences);
-                        expired_predicates.append(&mut expirations);
+                for parents_microblock_to_apply in block_update.parent_microblocks_to_apply.iter() {
+                    if !is_in_range(
+                        parents_microblock_to_apply
+                            .metadata
+                            .anchor_block_identifier
+                            .index,
+                    ) {
+                        continue;
                     }
-
                     let (mut occurrences, mut expirations) = evaluate_stacks_chainhook_on_blocks(
-                        vec![&block_update.block],
+                        vec![parents_microblock_to_apply],
                         chainhook,
                         ctx,
                     );
+                    microblocks_txids.extend(get_occurrences_txids(&occurrences));
                     apply.append(&mut occurrences);
                     expired_predicates.append(&mut expirations);
                 }
-                if !apply.is_empty() || !rollback.is_empty() {
-                    triggered_predicates.push(StacksTriggerChainhook {
-                        chainhook,
-                        apply,
-                        rollback,
-                    })
-                }
-            }
-        }
-        StacksChainEvent::ChainUpdatedWithMicroblocks(update) => {
-            for chainhook in active_chainhooks.iter() {
-                let mut apply = vec![];
-                let rollback = vec![];
-
-                for microblock_to_apply in update.new_microblocks.iter() {
-                    evaluated_predicates.insert(
-                        chainhook.uuid.as_str(),
-                        &microblock_to_apply.metadata.anchor_block_identifier,
-                    );
-
+                for parents_microblock_to_rolllback in
+                    block_update.parent_microblocks_to_rollback.iter()
+                {
+                    if !is_in_range(
+                        parents_microblock_to_rolllback
+                            .metadata
+                            .anchor_block_identifier
+                            .index,
+                    ) {
+                        continue;
+                    }
                     let (mut occurrences, mut expirations) = evaluate_stacks_chainhook_on_blocks(
-                        vec![microblock_to_apply],
+                        vec![parents_microblock_to_rolllback],
                         chainhook,
                         ctx,
                     );
-                    apply.append(&mut occurrences);
+                    rollback.append(&mut occurrences);
                     expired_predicates.append(&mut expirations);
                 }
-                if !apply.is_empty() || !rollback.is_empty() {
-                    triggered_predicates.push(StacksTriggerChainhook {
-                        chainhook,
-                        apply,
-                        rollback,
-                    })
+
+                let (occurrences, mut expirations) =
+                    evaluate_stacks_chainhook_on_blocks(vec![&block_update.block], chainhook, ctx);
+                apply.append(&mut without_transactions(occurrences, &microblocks_txids));
+                expired_predicates.append(&mut expirations);
+            }
+        }
+        StacksChainEvent::ChainUpdatedWithMicroblocks(update) => {
+            for microblock_to_apply in update.new_microblocks.iter() {
+                let anchor_block_identifier = &microblock_to_apply.metadata.anchor_block_identifier;
+                if is_occurrences_limit_reached(&apply, anchor_block_identifier, occurrences_limit)
+                {
+                    break;
+                }
+                if anchor_block_identifier.index > end_block {
+                    expired_predicates.insert(chainhook.uuid.as_str(), anchor_block_identifier);
+                    break;
+                }
+                if anchor_block_identifier.index < start_block {
+                    continue;
+                }
+                if is_from_another_network(microblock_to_apply, chainhook, ctx) {
+                    continue;
                 }
+                evaluated_predicates.insert(chainhook.uuid.as_str(), anchor_block_identifier);
+
+                let (mut occurrences, mut expirations) =
+                    evaluate_stacks_chainhook_on_blocks(vec![microblock_to_apply], chainhook, ctx);
+                apply.append(&mut occurrences);
+                expired_predicates.append(&mut expirations);
             }
         }
         StacksChainEvent::ChainUpdatedWithMicroblocksReorg(update) => {
-            for chainhook in active_chainhooks.iter() {
-                let mut apply = vec![];
-                let mut rollback = vec![];
-
-                for microblock_to_apply in update.microblocks_to_apply.iter() {
-                    evaluated_predicates.insert(
-                        chainhook.uuid.as_str(),
-                        &microblock_to_apply.metadata.anchor_block_identifier,
-                    );
-                    let (mut occurrences, mut expirations) = evaluate_stacks_chainhook_on_blocks(
-                        vec![microblock_to_apply],
-                        chainhook,
-                        ctx,
-                    );
-                    apply.append(&mut occurrences);
-                    expired_predicates.append(&mut expirations);
+            for microblock_to_apply in update.microblocks_to_apply.iter() {
+                let anchor_block_identifier = &microblock_to_apply.metadata.anchor_block_identifier;
+                if is_occurrences_limit_reached(&apply, anchor_block_identifier, occurrences_limit)
+                {
+                    break;
                 }
-                for microblock_to_rollback in update.microblocks_to_rollback.iter() {
-                    let (mut occurrences, mut expirations) = evaluate_stacks_chainhook_on_blocks(
-                        vec![microblock_to_rollback],
-                        chainhook,
-                        ctx,
-                    );
-                    rollback.append(&mut occurrences);
-                    expired_predicates.append(&mut expirations);
+                if anchor_block_identifier.index > end_block {
+                    expired_predicates.insert(chainhook.uuid.as_str(), anchor_block_identifier);
+                    break;
                 }
-                if !apply.is_empty() || !rollback.is_empty() {
-                    triggered_predicates.push(StacksTriggerChainhook {
-                        chainhook,
-                        apply,
-                        rollback,
-                    })
+                if anchor_block_identifier.index < start_block {
+                    continue;
+                }
+                if is_from_another_network(microblock_to_apply, chainhook, ctx) {
+                    continue;
                 }
+                evaluated_predicates.insert(chainhook.uuid.as_str(), anchor_block_identifier);
+                let (mut occurrences, mut expirations) =
+                    evaluate_stacks_chainhook_on_blocks(vec![microblock_to_apply], chainhook, ctx);
+                apply.append(&mut occurrences);
+                expired_predicates.append(&mut expirations);
+            }
+            for microblock_to_rollback in update.microblocks_to_rollback.iter() {
+                if !is_in_range(
+                    microblock_to_rollback
+                        .metadata
+                        .anchor_block_identifier
+                        .index,
+                ) || is_from_another_network(microblock_to_rollback, chainhook, ctx)
+                {
+                    continue;
+                }
+                let (mut occurrences, mut expirations) = evaluate_stacks_chainhook_on_blocks(
+                    vec![microblock_to_rollback],
+                    chainhook,
+                    ctx,
+                );
+                rollback.append(&mut occurrences);
+                expired_predicates.append(&mut expirations);
             }
         }
         StacksChainEvent::ChainUpdatedWithReorg(update) => {
-            for chainhook in active_chainhooks.iter() {
-                let mut apply = vec![];
-                let mut rollback = vec![];
-
-                for block_update in update.blocks_to_apply.iter() {
-                    evaluated_predicates.insert(
-                        chainhook.uuid.as_str(),
-                        &block_update.block.block_identifier,
-                    );
-                    for parents_microblock_to_apply in
-                        block_update.parent_microblocks_to_apply.iter()
-                    {
-                        let (mut occurrences, mut expirations) =
-                            evaluate_stacks_chainhook_on_blocks(
-                                vec![parents_microblock_to_apply],
-                                chainhook,
-                                ctx,
-                            );
-                        apply.append(&mut occurrences);
-                        expired_predicates.append(&mut expirations);
+            for block_update in update.blocks_to_apply.iter() {
+                if is_occurrences_limit_reached(
+                    &apply,
+                    &block_update.block.parent_block_identifier,
+                    occurrences_limit,
+                ) {
+                    break;
+                }
+                let block_identifier = &block_update.block.block_identifier;
+                if block_identifier.index > end_block {
+                    expired_predicates.insert(chainhook.uuid.as_str(), block_identifier);
+                    break;
+                }
+                if block_identifier.index < start_block {
+                    continue;
+                }
+                if is_from_another_network(&block_update.block, chainhook, ctx) {
+                    continue;
+                }
+                evaluated_predicates.insert(chainhook.uuid.as_str(), block_identifier);
+                let mut microblocks_txids = HashSet::new();
+                for parents_microblock_to_apply in block_update.parent_microblocks_to_apply.iter() {
+                    if !is_in_range(
+                        parents_microblock_to_apply
+                            .metadata
+                            .anchor_block_identifier
+                            .index,
+                    ) {
+                        continue;
                     }
-
                     let (mut occurrences, mut expirations) = evaluate_stacks_chainhook_on_blocks(
-                        vec![&block_update.block],
+                        vec![parents_microblock_to_apply],
                         chainhook,
                         ctx,
                     );
+                    microblocks_txids.extend(get_occurrences_txids(&occurrences));
                     apply.append(&mut occurrences);
                     expired_predicates.append(&mut expirations);
                 }
-                for block_update in update.blocks_to_rollback.iter() {
-                    for parents_microblock_to_rollback in
-                        block_update.parent_microblocks_to_rollback.iter()
-                    {
-                        let (mut occurrences, mut expirations) =
-                            evaluate_stacks_chainhook_on_blocks(
-                                vec![parents_microblock_to_rollback],
-                                chainhook,
-                                ctx,
-                            );
-                        rollback.append(&mut occurrences);
-                        expired_predicates.append(&mut expirations);
+
+                let (occurrences, mut expirations) =
+                    evaluate_stacks_chainhook_on_blocks(vec![&block_update.block], chainhook, ctx);
+                apply.append(&mut without_transactions(occurrences, &microblocks_txids));
+                expired_predicates.append(&mut expirations);
+            }
+            for block_update in update.blocks_to_rollback.iter() {
+                if !is_in_range(block_update.block.block_identifier.index)
+                    || is_from_another_network(&block_update.block, chainhook, ctx)
+                {
+                    continue;
+                }
+                let mut microblocks_txids = HashSet::new();
+                for parents_microblock_to_rollback in
+                    block_update.parent_microblocks_to_rollback.iter()
+                {
+                    if !is_in_range(
+                        parents_microblock_to_rollback
+                            .metadata
+                            .anchor_block_identifier
+                            .index,
+                    ) {
+                        continue;
                     }
                     let (mut occurrences, mut expirations) = evaluate_stacks_chainhook_on_blocks(
-                        vec![&block_update.block],
+                        vec![parents_microblock_to_rollback],
                         chainhook,
                         ctx,
                     );
+                    microblocks_txids.extend(get_occurrences_txids(&occurrences));
                     rollback.append(&mut occurrences);
                     expired_predicates.append(&mut expirations);
                 }
-                if !apply.is_empty() || !rollback.is_empty() {
-                    triggered_predicates.push(StacksTriggerChainhook {
-                        chainhook,
-                        apply,
-                        rollback,
-                    })
-                }
+                let (occurrences, mut expirations) =
+                    evaluate_stacks_chainhook_on_blocks(vec![&block_update.block], chainhook, ctx);
+                rollback.append(&mut without_transactions(occurrences, &microblocks_txids));
+                expired_predicates.append(&mut expirations);
             }
         }
     }
-    (
-        triggered_predicates,
-        evaluated_predicates,
-        expired_predicates,
-    )
+    let trigger = if !apply.is_empty() || !rollback.is_empty() {
+        Some(StacksTriggerChainhook {
+            chainhook,
+            apply,
+            rollback,
+        })
+    } else {
+        None
+    };
+    (trigger, evaluated_predicates, expired_predicates)
+}
+
+fn get_occurrences_txids<'a>(
+    occurrences: &[(Vec<&'a StacksTransactionData>, &'a dyn AbstractStacksBlock)],
+) -> Vec<&'a str> {
+    occurrences
+        .iter()
+        .flat_map(|(transactions, _)| transactions.iter())
+        .map(|tx| tx.transaction_identifier.hash.as_str())
+        .collect()
+}
+
+/// A transaction confirmed in a microblock is included again by the anchor block confirming the
+/// microblock: the anchor block occurrences drop the transactions already matched in its parent
+/// microblocks, so that each transaction is only reported once.
+fn without_transactions<'a>(
+    occurrences: Vec<(Vec<&'a StacksTransactionData>, &'a dyn AbstractStacksBlock)>,
+    txids: &HashSet<&str>,
+) -> Vec<(Vec<&'a StacksTransactionData>, &'a dyn AbstractStacksBlock)> {
+    occurrences
+        .into_iter()
+        .filter_map(|(transactions, block)| {
+            let transactions = transactions
+                .into_iter()
+                .filter(|tx| !txids.contains(tx.transaction_identifier.hash.as_str()))
+                .collect::<Vec<_>>();
+            match transactions.is_empty() {
+                true => None,
+                false => Some((transactions, block)),
+            }
+        })
+        .collect()
+}
+
+/// Blocks received from a node of another network than the predicate's are never evaluated.
+/// Blocks whose network is unknown are evaluated.
+fn is_from_another_network(
+    block: &dyn AbstractStacksBlock,
+    chainhook: &StacksChainhookSpecification,
+    ctx: &Context,
+) -> bool {
+    match block.get_network() {
+        Some(network) if network != &chainhook.network => {
+            ctx.try_log(|logger| {
+                slog::debug!(
+                    logger,
+                    "Skipping {:?} block #{} for predicate {} registered on {:?}",
+                    network,
+                    block.get_identifier().index,
+                    chainhook.uuid,
+                    chainhook.network
+                )
+            });
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Evaluates `active_chainhooks` against blocks the caller already standardized (e.g. with
+/// its own indexer), without going through a chain event. Blocks are considered applied:
+/// the returned triggers never carry rollbacks.
+pub fn evaluate_stacks_chainhooks_on_blocks<'a>(
+    blocks: Vec<&'a dyn AbstractStacksBlock>,
+    active_chainhooks: Vec<&'a StacksChainhookSpecification>,
+    ctx: &Context,
+) -> (
+    Vec<StacksTriggerChainhook<'a>>,
+    BTreeMap<&'a str, &'a BlockIdentifier>,
+) {
+    let evaluations = if active_chainhooks.len() >= PARALLEL_EVALUATION_THRESHOLD {
+        active_chainhooks
+            .par_iter()
+            .map(|chainhook| {
+                let (apply, expired) =
+                    evaluate_stacks_chainhook_on_blocks(blocks.clone(), chainhook, ctx);
+                (*chainhook, apply, expired)
+            })
+            .collect::<Vec<_>>()
+    } else {
+        active_chainhooks
+            .iter()
+            .map(|chainhook| {
+                let (apply, expired) =
+                    evaluate_stacks_chainhook_on_blocks(blocks.clone(), chainhook, ctx);
+                (*chainhook, apply, expired)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut triggered_predicates = vec![];
+    let mut expired_predicates = BTreeMap::new();
+    for (chainhook, apply, mut expired) in evaluations.into_iter() {
+        if !apply.is_empty() {
+            let trigger = StacksTriggerChainhook {
+                chainhook,
+                apply,
+                rollback: vec![],
+            };
+            triggered_predicates.append(&mut trigger.split_by_trigger_on());
+        }
+        expired_predicates.append(&mut expired);
+    }
+    (triggered_predicates, expired_predicates)
 }
 
 pub fn evaluate_stacks_chainhook_on_blocks<'a>(
@@ -313,13 +729,25 @@ pub fn evaluate_stacks_predicate_on_block<'a>(
         StacksPredicate::BlockHeight(BlockIdentifierIndexRule::Equals(a)) => {
             block.get_identifier().index.eq(a)
         }
+        StacksPredicate::BlockTime(range) => range.contains(block.get_timestamp()),
         StacksPredicate::ContractDeployment(_)
         | StacksPredicate::ContractCall(_)
         | StacksPredicate::FtEvent(_)
         | StacksPredicate::NftEvent(_)
         | StacksPredicate::StxEvent(_)
         | StacksPredicate::PrintEvent(_)
-        | StacksPredicate::Txid(_) => unreachable!(),
+        | StacksPredicate::Txid(_)
+        | StacksPredicate::Nonce(_)
+        | StacksPredicate::EventRatio(_)
+        | StacksPredicate::EventCount(_)
+        | StacksPredicate::EventSequence(_)
+        | StacksPredicate::StxTransferRecipient(_)
+        | StacksPredicate::ContractCallPostConditionAbort(_)
+        | StacksPredicate::ContractInteractions(_)
+        | StacksPredicate::Coinbase(_)
+        | StacksPredicate::AllOf(_)
+        | StacksPredicate::AnyOf(_)
+        | StacksPredicate::Not(_) => unreachable!(),
     }
 }
 
@@ -328,230 +756,727 @@ pub fn evaluate_stacks_predicate_on_transaction<'a>(
     chainhook: &'a StacksChainhookSpecification,
     ctx: &Context,
 ) -> bool {
-    match &chainhook.predicate {
+    let decode_clarity_values = chainhook.decode_clarity_values.unwrap_or(false);
+    evaluate_predicate_on_transaction(
+        transaction,
+        &chainhook.predicate,
+        decode_clarity_values,
+        ctx,
+    )
+}
+
+fn evaluate_predicate_on_transaction(
+    transaction: &StacksTransactionData,
+    predicate: &StacksPredicate,
+    decode_clarity_values: bool,
+    ctx: &Context,
+) -> bool {
+    match predicate {
+        // Combined predicates are all evaluated against the same transaction.
+        StacksPredicate::AllOf(combination) => combination.predicates.iter().all(|predicate| {
+            evaluate_predicate_on_transaction(transaction, predicate, decode_clarity_values, ctx)
+        }),
+        StacksPredicate::AnyOf(combination) => combination.predicates.iter().any(|predicate| {
+            evaluate_predicate_on_transaction(transaction, predicate, decode_clarity_values, ctx)
+        }),
+        StacksPredicate::Not(negation) => !evaluate_predicate_on_transaction(
+            transaction,
+            &negation.predicate,
+            decode_clarity_values,
+            ctx,
+        ),
         StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::Deployer(
             expected_deployer,
         )) => match &transaction.metadata.kind {
             StacksTransactionKind::ContractDeployment(actual_deployment) => {
-                if expected_deployer.eq("*") {
-                    true
-                } else {
-                    actual_deployment
-                        .contract_identifier
-                        .starts_with(expected_deployer)
+                expected_deployer.matches(&actual_deployment.contract_identifier)
+            }
+            _ => false,
+        },
+        StacksPredicate::ContractDeployment(
+            StacksContractDeploymentPredicate::ContractIdentifier(expected_contract_identifier),
+        ) => match &transaction.metadata.kind {
+            StacksTransactionKind::ContractDeployment(actual_deployment) => {
+                expected_contract_identifier.matches(&actual_deployment.contract_identifier)
+            }
+            _ => false,
+        },
+        StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::SourceSize(
+            expected_size,
+        )) => match &transaction.metadata.kind {
+            StacksTransactionKind::ContractDeployment(actual_deployment) => {
+                let source_size = actual_deployment.code.len() as u64;
+                match expected_size {
+                    ValueMatchingRule::HigherThan(size) => source_size > *size,
+                    ValueMatchingRule::LowerThan(size) => source_size < *size,
                 }
             }
             _ => false,
         },
+        StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::SourceContains(
+            expected_source,
+        )) => match &transaction.metadata.kind {
+            StacksTransactionKind::ContractDeployment(actual_deployment) => {
+                expected_source == "*" || actual_deployment.code.contains(expected_source.as_str())
+            }
+            _ => false,
+        },
         StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::ImplementTrait(
             stacks_trait,
-        )) => match stacks_trait {
-            _ => match &transaction.metadata.kind {
-                StacksTransactionKind::ContractDeployment(_actual_deployment) => {
-                    ctx.try_log(|logger| {
-                        slog::warn!(
-                            logger,
-                            "StacksContractDeploymentPredicate::ImplementTrait uninmplemented"
-                        )
-                    });
-                    false
-                }
-                _ => false,
-            },
+        )) => match &transaction.metadata.kind {
+            StacksTransactionKind::ContractDeployment(actual_deployment) => {
+                traits::contract_implements_trait(&actual_deployment.code, stacks_trait)
+            }
+            _ => false,
         },
         StacksPredicate::ContractCall(expected_contract_call) => match &transaction.metadata.kind {
             StacksTransactionKind::ContractCall(actual_contract_call) => {
-                actual_contract_call
-                    .contract_identifier
-                    .eq(&expected_contract_call.contract_identifier)
+                expected_contract_call
+                    .matches_contract_identifier(&actual_contract_call.contract_identifier)
                     && actual_contract_call
                         .method
                         .eq(&expected_contract_call.method)
+                    && match &expected_contract_call.args {
+                        Some(expected_args) => contract_call_args_match(
+                            expected_args,
+                            &actual_contract_call.args,
+                            decode_clarity_values,
+                        ),
+                        None => true,
+                    }
             }
             _ => false,
         },
         StacksPredicate::FtEvent(expected_event) => {
-            let expecting_mint = expected_event.actions.contains(&"mint".to_string());
-            let expecting_transfer = expected_event.actions.contains(&"transfer".to_string());
-            let expecting_burn = expected_event.actions.contains(&"burn".to_string());
-
-            for event in transaction.metadata.receipt.events.iter() {
-                match (
-                    &event.event_payload,
-                    expecting_mint,
-                    expecting_transfer,
-                    expecting_burn,
-                ) {
-                    (StacksTransactionEventPayload::FTMintEvent(ft_event), true, _, _) => {
-                        if ft_event
-                            .asset_class_identifier
-                            .eq(&expected_event.asset_identifier)
-                        {
-                            return true;
-                        }
-                    }
-                    (StacksTransactionEventPayload::FTTransferEvent(ft_event), _, true, _) => {
-                        if ft_event
-                            .asset_class_identifier
-                            .eq(&expected_event.asset_identifier)
-                        {
-                            return true;
-                        }
-                    }
-                    (StacksTransactionEventPayload::FTBurnEvent(ft_event), _, _, true) => {
-                        if ft_event
-                            .asset_class_identifier
-                            .eq(&expected_event.asset_identifier)
-                        {
-                            return true;
+            is_emitted_by_method(transaction, expected_event.emitting_method.as_ref())
+                && transaction
+                    .metadata
+                    .receipt
+                    .events
+                    .iter()
+                    .any(|event| ft_event_matches(&event.event_payload, expected_event))
+        }
+        StacksPredicate::NftEvent(expected_event) => transaction
+            .metadata
+            .receipt
+            .events
+            .iter()
+            .any(|event| nft_event_matches(&event.event_payload, expected_event)),
+        StacksPredicate::StxEvent(expected_event) => transaction
+            .metadata
+            .receipt
+            .events
+            .iter()
+            .any(|event| stx_event_matches(&event.event_payload, expected_event)),
+        StacksPredicate::PrintEvent(expected_event) => {
+            if !is_emitted_by_method(transaction, expected_event.emitting_method()) {
+                return false;
+            }
+            let expected_regex = match expected_event {
+                StacksPrintEventBasedPredicate::MatchesRegex { regex, .. } => {
+                    match get_compiled_regex(regex) {
+                        Ok(regex) => Some(regex),
+                        Err(_) => {
+                            ctx.try_log(|logger| {
+                                slog::error!(
+                                    logger,
+                                    "unable to parse print_event matching rule as regex"
+                                )
+                            });
+                            return false;
                         }
                     }
-                    _ => continue,
+                }
+                _ => None,
+            };
+            transaction.metadata.receipt.events.iter().any(|event| {
+                print_event_matches(
+                    &event.event_payload,
+                    expected_event,
+                    expected_regex.as_ref(),
+                )
+            })
+        }
+        StacksPredicate::Txid(ExactMatchingRule::Equals(txid)) => {
+            txid.eq(&transaction.transaction_identifier.hash)
+        }
+        StacksPredicate::Nonce(expected_nonce) => {
+            if let Some(ref sender) = expected_nonce.sender {
+                if !transaction.metadata.sender.eq(sender) {
+                    return false;
                 }
             }
-            false
+            expected_nonce.nonce.matches(transaction.metadata.nonce)
         }
-        StacksPredicate::NftEvent(expected_event) => {
-            let expecting_mint = expected_event.actions.contains(&"mint".to_string());
-            let expecting_transfer = expected_event.actions.contains(&"transfer".to_string());
-            let expecting_burn = expected_event.actions.contains(&"burn".to_string());
-
+        StacksPredicate::EventRatio(expected_ratio) => {
+            let events = &transaction.metadata.receipt.events;
+            // A transaction without events has no ratio to speak of.
+            if events.is_empty() {
+                return false;
+            }
+            let matched_events = events
+                .iter()
+                .filter(|event| {
+                    event_payload_matches_type(&event.event_payload, &expected_ratio.event_type)
+                })
+                .count() as u64;
+            matched_events * 100 > u64::from(expected_ratio.higher_than) * events.len() as u64
+        }
+        StacksPredicate::EventCount(expected_count) => {
+            transaction.metadata.receipt.events.len() as u64 > expected_count.higher_than
+        }
+        StacksPredicate::Coinbase(expected_coinbase) => {
+            if !matches!(transaction.metadata.kind, StacksTransactionKind::Coinbase) {
+                return false;
+            }
+            match expected_coinbase.recipient {
+                Some(ref recipient) => get_coinbase_recipient(transaction).eq(recipient),
+                None => true,
+            }
+        }
+        StacksPredicate::ContractInteractions(expected_interactions) => {
+            let contracts = get_contracts_interacted_with(transaction);
+            expected_interactions
+                .all_of
+                .iter()
+                .all(|contract_identifier| contracts.contains(contract_identifier.as_str()))
+        }
+        StacksPredicate::EventSequence(expected_sequence) => {
+            let mut expected_events = expected_sequence.events.iter().peekable();
             for event in transaction.metadata.receipt.events.iter() {
-                match (
-                    &event.event_payload,
-                    expecting_mint,
-                    expecting_transfer,
-                    expecting_burn,
-                ) {
-                    (StacksTransactionEventPayload::NFTMintEvent(nft_event), true, _, _) => {
-                        if nft_event
-                            .asset_class_identifier
-                            .eq(&expected_event.asset_identifier)
-                        {
-                            return true;
-                        }
-                    }
-                    (StacksTransactionEventPayload::NFTTransferEvent(nft_event), _, true, _) => {
-                        if nft_event
-                            .asset_class_identifier
-                            .eq(&expected_event.asset_identifier)
-                        {
-                            return true;
-                        }
+                if let Some(constraint) = expected_events.peek() {
+                    if event_payload_matches_constraint(&event.event_payload, constraint) {
+                        expected_events.next();
                     }
-                    (StacksTransactionEventPayload::NFTBurnEvent(nft_event), _, _, true) => {
-                        if nft_event
-                            .asset_class_identifier
-                            .eq(&expected_event.asset_identifier)
-                        {
-                            return true;
-                        }
-                    }
-                    _ => continue,
                 }
             }
-            false
+            expected_events.peek().is_none()
         }
-        StacksPredicate::StxEvent(expected_event) => {
-            let expecting_mint = expected_event.actions.contains(&"mint".to_string());
-            let expecting_transfer = expected_event.actions.contains(&"transfer".to_string());
-            let expecting_lock = expected_event.actions.contains(&"lock".to_string());
-            let expecting_burn = expected_event.actions.contains(&"burn".to_string());
-
-            for event in transaction.metadata.receipt.events.iter() {
-                match (
-                    &event.event_payload,
-                    expecting_mint,
-                    expecting_transfer,
-                    expecting_lock,
-                    expecting_burn,
-                ) {
-                    (StacksTransactionEventPayload::STXMintEvent(_), true, _, _, _) => return true,
-                    (StacksTransactionEventPayload::STXTransferEvent(_), _, true, _, _) => {
-                        return true
-                    }
-                    (StacksTransactionEventPayload::STXLockEvent(_), _, _, true, _) => return true,
-                    (StacksTransactionEventPayload::STXBurnEvent(_), _, _, _, true) => return true,
-                    _ => continue,
+        StacksPredicate::StxTransferRecipient(expected_recipient) => {
+            transaction.metadata.receipt.events.iter().any(|event| {
+                stx_transfer_recipient_matches(&event.event_payload, expected_recipient)
+            })
+        }
+        StacksPredicate::ContractCallPostConditionAbort(expected_contract_call) => {
+            match &transaction.metadata.kind {
+                StacksTransactionKind::ContractCall(actual_contract_call) => {
+                    expected_contract_call
+                        .matches_contract_identifier(&actual_contract_call.contract_identifier)
+                        && actual_contract_call
+                            .method
+                            .eq(&expected_contract_call.method)
+                        && is_aborted_by_post_condition(transaction)
                 }
+                _ => false,
             }
-            false
+        }
+        StacksPredicate::BlockHeight(_) | StacksPredicate::BlockTime(_) => unreachable!(),
+    }
+}
+
+/// Returns whether `event` is one of the events `predicate` is looking for, or None when
+/// `predicate` is not about specific events (e.g. a contract call).
+fn evaluate_predicate_on_event(
+    event: &StacksTransactionEvent,
+    predicate: &StacksPredicate,
+) -> Option<bool> {
+    let payload = &event.event_payload;
+    match predicate {
+        StacksPredicate::AllOf(combination) | StacksPredicate::AnyOf(combination) => combination
+            .predicates
+            .iter()
+            .filter_map(|predicate| evaluate_predicate_on_event(event, predicate))
+            .reduce(|matched, other| matched || other),
+        StacksPredicate::FtEvent(expected_event) => Some(ft_event_matches(payload, expected_event)),
+        StacksPredicate::NftEvent(expected_event) => {
+            Some(nft_event_matches(payload, expected_event))
+        }
+        StacksPredicate::StxEvent(expected_event) => {
+            Some(stx_event_matches(payload, expected_event))
         }
         StacksPredicate::PrintEvent(expected_event) => {
-            for event in transaction.metadata.receipt.events.iter() {
-                match &event.event_payload {
-                    StacksTransactionEventPayload::SmartContractEvent(actual) => {
-                        if actual.topic == "print" {
-                            match expected_event {
-                                StacksPrintEventBasedPredicate::Contains {
-                                    contract_identifier,
-                                    contains,
-                                } => {
-                                    if contract_identifier == &actual.contract_identifier
-                                        || contract_identifier == "*"
-                                    {
-                                        if contains == "*" {
-                                            return true;
-                                        }
-                                        let value = format!(
-                                            "{}",
-                                            expect_decoded_clarity_value(&actual.hex_value)
-                                        );
-                                        if value.contains(contains) {
-                                            return true;
-                                        }
-                                    }
-                                }
-                                StacksPrintEventBasedPredicate::MatchesRegex {
-                                    contract_identifier,
-                                    regex,
-                                } => {
-                                    if contract_identifier == &actual.contract_identifier
-                                        || contract_identifier == "*"
-                                    {
-                                        if let Ok(regex) = Regex::new(regex) {
-                                            let value = format!(
-                                                "{}",
-                                                expect_decoded_clarity_value(&actual.hex_value)
-                                            );
-                                            if regex.is_match(&value) {
-                                                return true;
-                                            }
-                                        } else {
-                                            ctx.try_log(|logger| {
-                                                slog::error!(logger, "unable to parse print_event matching rule as regex")
-                                            });
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
+            let expected_regex = match expected_event {
+                StacksPrintEventBasedPredicate::MatchesRegex { regex, .. } => {
+                    get_compiled_regex(regex).ok()
                 }
+                _ => None,
+            };
+            Some(print_event_matches(
+                payload,
+                expected_event,
+                expected_regex.as_ref(),
+            ))
+        }
+        StacksPredicate::EventRatio(expected_ratio) => Some(event_payload_matches_type(
+            payload,
+            &expected_ratio.event_type,
+        )),
+        StacksPredicate::EventSequence(expected_sequence) => Some(
+            expected_sequence
+                .events
+                .iter()
+                .any(|constraint| event_payload_matches_constraint(payload, constraint)),
+        ),
+        StacksPredicate::StxTransferRecipient(expected_recipient) => {
+            Some(stx_transfer_recipient_matches(payload, expected_recipient))
+        }
+        _ => None,
+    }
+}
+
+/// Unless the chainhook captures all the events, only the events matching its predicate are
+/// attached to its occurrences. Predicates that are not about specific events keep them all.
+fn is_captured_event(
+    event: &StacksTransactionEvent,
+    chainhook: &StacksChainhookSpecification,
+) -> bool {
+    if chainhook.capture_all_events.unwrap_or(false) {
+        return true;
+    }
+    evaluate_predicate_on_event(event, &chainhook.predicate).unwrap_or(true)
+}
+
+fn ft_event_matches(
+    payload: &StacksTransactionEventPayload,
+    expected_event: &StacksFtEventBasedPredicate,
+) -> bool {
+    let expecting = |action: &str| expected_event.actions.iter().any(|a| a == action);
+    let (asset_class_identifier, sender, recipient) = match payload {
+        StacksTransactionEventPayload::FTMintEvent(ft_event) if expecting("mint") => (
+            &ft_event.asset_class_identifier,
+            None,
+            Some(&ft_event.recipient),
+        ),
+        StacksTransactionEventPayload::FTTransferEvent(ft_event) if expecting("transfer") => (
+            &ft_event.asset_class_identifier,
+            Some(&ft_event.sender),
+            Some(&ft_event.recipient),
+        ),
+        StacksTransactionEventPayload::FTBurnEvent(ft_event) if expecting("burn") => (
+            &ft_event.asset_class_identifier,
+            Some(&ft_event.sender),
+            None,
+        ),
+        _ => return false,
+    };
+    asset_identifier_matches(&expected_event.asset_identifier, asset_class_identifier)
+        && principal_matches(&expected_event.sender, sender)
+        && principal_matches(&expected_event.recipient, recipient)
+}
+
+fn nft_event_matches(
+    payload: &StacksTransactionEventPayload,
+    expected_event: &StacksNftEventBasedPredicate,
+) -> bool {
+    let expecting = |action: &str| expected_event.actions.iter().any(|a| a == action);
+    let (asset_class_identifier, hex_asset_identifier, sender, recipient) = match payload {
+        StacksTransactionEventPayload::NFTMintEvent(nft_event) if expecting("mint") => (
+            &nft_event.asset_class_identifier,
+            &nft_event.hex_asset_identifier,
+            None,
+            Some(&nft_event.recipient),
+        ),
+        StacksTransactionEventPayload::NFTTransferEvent(nft_event) if expecting("transfer") => (
+            &nft_event.asset_class_identifier,
+            &nft_event.hex_asset_identifier,
+            Some(&nft_event.sender),
+            Some(&nft_event.recipient),
+        ),
+        StacksTransactionEventPayload::NFTBurnEvent(nft_event) if expecting("burn") => (
+            &nft_event.asset_class_identifier,
+            &nft_event.hex_asset_identifier,
+            Some(&nft_event.sender),
+            None,
+        ),
+        _ => return false,
+    };
+    asset_identifier_matches(&expected_event.asset_identifier, asset_class_identifier)
+        && nft_token_id_in_range(hex_asset_identifier, &expected_event.token_id_range)
+        && principal_matches(&expected_event.sender, sender)
+        && principal_matches(&expected_event.recipient, recipient)
+}
+
+fn stx_event_matches(
+    payload: &StacksTransactionEventPayload,
+    expected_event: &StacksStxEventBasedPredicate,
+) -> bool {
+    let expecting = |action: &str| expected_event.actions.iter().any(|a| a == action);
+    let (amount, sender, recipient) = match payload {
+        StacksTransactionEventPayload::STXMintEvent(data) if expecting("mint") => {
+            (&data.amount, None, Some(&data.recipient))
+        }
+        StacksTransactionEventPayload::STXTransferEvent(data) if expecting("transfer") => {
+            (&data.amount, Some(&data.sender), Some(&data.recipient))
+        }
+        StacksTransactionEventPayload::STXLockEvent(data) if expecting("lock") => {
+            (&data.locked_amount, Some(&data.locked_address), None)
+        }
+        StacksTransactionEventPayload::STXBurnEvent(data) if expecting("burn") => {
+            (&data.amount, Some(&data.sender), None)
+        }
+        _ => return false,
+    };
+    stx_amount_in_range(amount, expected_event)
+        && principal_matches(&expected_event.sender, sender)
+        && principal_matches(&expected_event.recipient, recipient)
+}
+
+fn print_event_matches(
+    payload: &StacksTransactionEventPayload,
+    expected_event: &StacksPrintEventBasedPredicate,
+    expected_regex: Option<&Regex>,
+) -> bool {
+    let StacksTransactionEventPayload::SmartContractEvent(actual) = payload else {
+        return false;
+    };
+    if actual.topic != "print"
+        || !expected_event
+            .contract_identifier()
+            .matches(&actual.contract_identifier)
+    {
+        return false;
+    }
+    match expected_event {
+        StacksPrintEventBasedPredicate::Contains { contains, .. } => {
+            contains == "*"
+                || format!("{}", expect_decoded_clarity_value(&actual.hex_value)).contains(contains)
+        }
+        StacksPrintEventBasedPredicate::MatchesRegex { .. } => {
+            let value = format!("{}", expect_decoded_clarity_value(&actual.hex_value));
+            expected_regex.is_some_and(|regex| regex.is_match(&value))
+        }
+        StacksPrintEventBasedPredicate::EventName {
+            event_name,
+            event_name_key,
+            ..
+        } => {
+            let keys = match event_name_key {
+                Some(key) => vec![key.as_str()],
+                None => DEFAULT_PRINT_EVENT_NAME_KEYS.to_vec(),
+            };
+            let actual_event_name = try_decode_clarity_value(&actual.hex_value)
+                .and_then(|value| get_print_event_name(&value, &keys));
+            actual_event_name.as_ref() == Some(event_name)
+        }
+    }
+}
+
+fn stx_transfer_recipient_matches(
+    payload: &StacksTransactionEventPayload,
+    expected_recipient: &StacksStxTransferRecipientBasedPredicate,
+) -> bool {
+    let StacksTransactionEventPayload::STXTransferEvent(data) = payload else {
+        return false;
+    };
+    if !StacksPrincipalType::from_principal(&data.recipient).eq(&expected_recipient.principal_type)
+    {
+        return false;
+    }
+    match &expected_recipient.memo {
+        None => true,
+        Some(expected_memo) => data
+            .memo
+            .as_deref()
+            .and_then(decode_ascii_memo)
+            .is_some_and(|memo| expected_memo.matches(&memo)),
+    }
+}
+
+/// Memos are fixed size buffers, zero padded on the right: the padding is stripped before
+/// decoding, and memos that are not plain ASCII are discarded.
+fn decode_ascii_memo(hex_memo: &str) -> Option<String> {
+    let hex_memo = hex_memo.strip_prefix("0x").unwrap_or(hex_memo);
+    let mut bytes = hex::decode(hex_memo).ok()?;
+    while bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    if bytes.is_empty() || !bytes.is_ascii() {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Post-conditions are only checked once a call returned `(ok ...)`: a failed transaction
+/// carrying an `ok` result was aborted by a post-condition rather than by its response.
+fn is_aborted_by_post_condition(transaction: &StacksTransactionData) -> bool {
+    !transaction.metadata.success && transaction.metadata.result.starts_with("(ok ")
+}
+
+fn event_payload_matches_type(
+    payload: &StacksTransactionEventPayload,
+    event_type: &StacksEventType,
+) -> bool {
+    let actual_type = match payload {
+        StacksTransactionEventPayload::FTMintEvent(_)
+        | StacksTransactionEventPayload::FTTransferEvent(_)
+        | StacksTransactionEventPayload::FTBurnEvent(_) => StacksEventType::FtEvent,
+        StacksTransactionEventPayload::NFTMintEvent(_)
+        | StacksTransactionEventPayload::NFTTransferEvent(_)
+        | StacksTransactionEventPayload::NFTBurnEvent(_) => StacksEventType::NftEvent,
+        StacksTransactionEventPayload::STXMintEvent(_)
+        | StacksTransactionEventPayload::STXTransferEvent(_)
+        | StacksTransactionEventPayload::STXLockEvent(_)
+        | StacksTransactionEventPayload::STXBurnEvent(_) => StacksEventType::StxEvent,
+        StacksTransactionEventPayload::DataVarSetEvent(_) => StacksEventType::DataVarEvent,
+        StacksTransactionEventPayload::DataMapInsertEvent(_)
+        | StacksTransactionEventPayload::DataMapUpdateEvent(_)
+        | StacksTransactionEventPayload::DataMapDeleteEvent(_) => StacksEventType::DataMapEvent,
+        StacksTransactionEventPayload::SmartContractEvent(event) => {
+            if event.topic != "print" {
+                return false;
             }
-            false
+            StacksEventType::PrintEvent
         }
-        StacksPredicate::Txid(ExactMatchingRule::Equals(txid)) => {
-            txid.eq(&transaction.transaction_identifier.hash)
+    };
+    actual_type.eq(event_type)
+}
+
+fn event_payload_matches_constraint(
+    payload: &StacksTransactionEventPayload,
+    constraint: &StacksEventConstraint,
+) -> bool {
+    if !event_payload_matches_type(payload, &constraint.event_type) {
+        return false;
+    }
+    let (action, asset_identifier) = match payload {
+        StacksTransactionEventPayload::FTMintEvent(data) => {
+            ("mint", Some(&data.asset_class_identifier))
+        }
+        StacksTransactionEventPayload::FTTransferEvent(data) => {
+            ("transfer", Some(&data.asset_class_identifier))
+        }
+        StacksTransactionEventPayload::FTBurnEvent(data) => {
+            ("burn", Some(&data.asset_class_identifier))
+        }
+        StacksTransactionEventPayload::NFTMintEvent(data) => {
+            ("mint", Some(&data.asset_class_identifier))
+        }
+        StacksTransactionEventPayload::NFTTransferEvent(data) => {
+            ("transfer", Some(&data.asset_class_identifier))
+        }
+        StacksTransactionEventPayload::NFTBurnEvent(data) => {
+            ("burn", Some(&data.asset_class_identifier))
+        }
+        StacksTransactionEventPayload::STXMintEvent(_) => ("mint", None),
+        StacksTransactionEventPayload::STXTransferEvent(_) => ("transfer", None),
+        StacksTransactionEventPayload::STXLockEvent(_) => ("lock", None),
+        StacksTransactionEventPayload::STXBurnEvent(_) => ("burn", None),
+        _ => ("", None),
+    };
+    if let Some(ref expected_action) = constraint.action {
+        if !expected_action.eq(action) {
+            return false;
+        }
+    }
+    if let Some(ref expected_asset_identifier) = constraint.asset_identifier {
+        if asset_identifier != Some(expected_asset_identifier) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Query the Stacks node at `stacks_node_rpc_url` for the contracts (and public methods)
+/// referenced by `predicate`, including the predicates it combines, so that misspelled
+/// identifiers are caught before registration.
+pub async fn check_stacks_predicate_contracts(
+    predicate: &StacksPredicate,
+    stacks_node_rpc_url: &str,
+) -> Result<(), String> {
+    let mut references = vec![];
+    collect_referenced_contracts(predicate, &mut references);
+    for (contract_identifier, method) in references.into_iter() {
+        check_stacks_contract(contract_identifier, method, stacks_node_rpc_url).await?;
+    }
+    Ok(())
+}
+
+/// Contracts (and public methods) referenced by `predicate` and by the predicates it combines.
+fn collect_referenced_contracts<'a>(
+    predicate: &'a StacksPredicate,
+    references: &mut Vec<(&'a str, Option<&'a String>)>,
+) {
+    let (contract_identifier, method) = match predicate {
+        StacksPredicate::AllOf(combination) | StacksPredicate::AnyOf(combination) => {
+            for predicate in combination.predicates.iter() {
+                collect_referenced_contracts(predicate, references);
+            }
+            return;
+        }
+        StacksPredicate::Not(negation) => {
+            collect_referenced_contracts(&negation.predicate, references);
+            return;
+        }
+        // Regexes can target contracts that are yet to be deployed
+        StacksPredicate::ContractCall(expected_contract_call)
+        | StacksPredicate::ContractCallPostConditionAbort(expected_contract_call) => {
+            match &expected_contract_call.contract_identifier {
+                ContractIdentifierRule::Equals(contract_identifier) => (
+                    contract_identifier.as_str(),
+                    Some(&expected_contract_call.method),
+                ),
+                ContractIdentifierRule::MatchesRegex { .. } => return,
+            }
+        }
+        StacksPredicate::PrintEvent(expected_event) => match expected_event.contract_identifier() {
+            ContractIdentifierRule::Equals(contract_identifier) => {
+                (contract_identifier.as_str(), None)
+            }
+            ContractIdentifierRule::MatchesRegex { .. } => return,
+        },
+        StacksPredicate::FtEvent(expected_event) => (
+            asset_contract_identifier(&expected_event.asset_identifier),
+            None,
+        ),
+        StacksPredicate::NftEvent(expected_event) => (
+            asset_contract_identifier(&expected_event.asset_identifier),
+            None,
+        ),
+        _ => return,
+    };
+    if contract_identifier.ends_with('*') {
+        return;
+    }
+    references.push((contract_identifier, method));
+}
+
+async fn check_stacks_contract(
+    contract_identifier: &str,
+    method: Option<&String>,
+    stacks_node_rpc_url: &str,
+) -> Result<(), String> {
+    let (contract_address, contract_name) = contract_identifier.split_once('.').ok_or(format!(
+        "invalid contract identifier {}",
+        contract_identifier
+    ))?;
+
+    let url = format!(
+        "{}/v2/contracts/interface/{}/{}",
+        stacks_node_rpc_url.trim_end_matches('/'),
+        contract_address,
+        contract_name
+    );
+    let client = Client::builder()
+        .build()
+        .map_err(|e| format!("unable to build http client: {}", e.to_string()))?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("unable to reach stacks node: {}", e.to_string()))?;
+    let status = response.status();
+    if status == StatusCode::NOT_FOUND {
+        return Err(format!(
+            "contract {} not found on chain",
+            contract_identifier
+        ));
+    }
+    if !status.is_success() {
+        return Err(format!(
+            "unable to retrieve contract {} interface: {}",
+            contract_identifier, status
+        ));
+    }
+    let Some(method) = method else {
+        return Ok(());
+    };
+    let interface: JsonValue = response
+        .json()
+        .await
+        .map_err(|e| format!("unable to parse contract interface: {}", e.to_string()))?;
+    let method_found = interface["functions"]
+        .as_array()
+        .map(|functions| {
+            functions.iter().any(|function| {
+                function["name"].as_str() == Some(method.as_str())
+                    && function["access"].as_str() == Some("public")
+            })
+        })
+        .unwrap_or(false);
+    if !method_found {
+        return Err(format!(
+            "public method {} not found in contract {}",
+            method, contract_identifier
+        ));
+    }
+    Ok(())
+}
+
+/// Contracts called or deployed by the transaction, emitting its events, or defining the
+/// assets it moves.
+fn get_contracts_interacted_with(transaction: &StacksTransactionData) -> HashSet<&str> {
+    let mut contracts = HashSet::new();
+    match &transaction.metadata.kind {
+        StacksTransactionKind::ContractCall(contract_call) => {
+            contracts.insert(contract_call.contract_identifier.as_str());
+        }
+        StacksTransactionKind::ContractDeployment(deployment) => {
+            contracts.insert(deployment.contract_identifier.as_str());
+        }
+        _ => {}
+    }
+    for event in transaction.metadata.receipt.events.iter() {
+        let contract_identifier = match &event.event_payload {
+            StacksTransactionEventPayload::SmartContractEvent(data) => &data.contract_identifier,
+            StacksTransactionEventPayload::DataVarSetEvent(data) => &data.contract_identifier,
+            StacksTransactionEventPayload::DataMapInsertEvent(data) => &data.contract_identifier,
+            StacksTransactionEventPayload::DataMapUpdateEvent(data) => &data.contract_identifier,
+            StacksTransactionEventPayload::DataMapDeleteEvent(data) => &data.contract_identifier,
+            StacksTransactionEventPayload::FTTransferEvent(data) => &data.asset_class_identifier,
+            StacksTransactionEventPayload::FTMintEvent(data) => &data.asset_class_identifier,
+            StacksTransactionEventPayload::FTBurnEvent(data) => &data.asset_class_identifier,
+            StacksTransactionEventPayload::NFTTransferEvent(data) => &data.asset_class_identifier,
+            StacksTransactionEventPayload::NFTMintEvent(data) => &data.asset_class_identifier,
+            StacksTransactionEventPayload::NFTBurnEvent(data) => &data.asset_class_identifier,
+            StacksTransactionEventPayload::STXTransferEvent(_)
+            | StacksTransactionEventPayload::STXMintEvent(_)
+            | StacksTransactionEventPayload::STXLockEvent(_)
+            | StacksTransactionEventPayload::STXBurnEvent(_) => continue,
+        };
+        contracts.insert(asset_contract_identifier(contract_identifier));
+    }
+    contracts
+}
+
+fn asset_contract_identifier(asset_identifier: &str) -> &str {
+    match asset_identifier.split_once("::") {
+        Some((contract_identifier, _)) => contract_identifier,
+        None => asset_identifier,
+    }
+}
+
+/// `*` matches any asset, `<contract_identifier>::*` any asset class defined by the contract.
+/// Other identifiers are matched exactly.
+fn asset_identifier_matches(expected_asset_identifier: &str, asset_class_identifier: &str) -> bool {
+    if expected_asset_identifier == "*" {
+        return true;
+    }
+    match expected_asset_identifier.strip_suffix("::*") {
+        Some(expected_contract_identifier) => {
+            asset_class_identifier.contains("::")
+                && asset_contract_identifier(asset_class_identifier) == expected_contract_identifier
         }
-        StacksPredicate::BlockHeight(_) => unreachable!(),
+        None => asset_class_identifier == expected_asset_identifier,
     }
 }
 
 fn serialize_stacks_block(
     block: &dyn AbstractStacksBlock,
     transactions: Vec<&StacksTransactionData>,
+    chainhook: &StacksChainhookSpecification,
     decode_clarity_values: bool,
+    max_clarity_value_length: Option<usize>,
     include_contract_abi: bool,
+    mempool_timestamps: Option<&HashMap<TransactionIdentifier, i64>>,
+    identifier_format: &IdentifierFormat,
     ctx: &Context,
 ) -> serde_json::Value {
     json!({
-        "block_identifier": block.get_identifier(),
-        "parent_block_identifier": block.get_parent_identifier(),
+        "block_identifier": identifier_format.serialize_block_identifier(block.get_identifier()),
+        "parent_block_identifier": identifier_format.serialize_block_identifier(block.get_parent_identifier()),
         "timestamp": block.get_timestamp(),
         "transactions": transactions.into_iter().map(|transaction| {
-            serialize_stacks_transaction(&transaction, decode_clarity_values, include_contract_abi, ctx)
+            serialize_stacks_transaction(&transaction, chainhook, decode_clarity_values, max_clarity_value_length, include_contract_abi, mempool_timestamps, identifier_format, ctx)
         }).collect::<Vec<_>>(),
         "metadata": block.get_serialized_metadata(),
     })
@@ -559,18 +1484,22 @@ fn serialize_stacks_block(
 
 fn serialize_stacks_transaction(
     transaction: &StacksTransactionData,
+    chainhook: &StacksChainhookSpecification,
     decode_clarity_values: bool,
+    max_clarity_value_length: Option<usize>,
     include_contract_abi: bool,
+    mempool_timestamps: Option<&HashMap<TransactionIdentifier, i64>>,
+    identifier_format: &IdentifierFormat,
     ctx: &Context,
 ) -> serde_json::Value {
     let mut json = json!({
-        "transaction_identifier": transaction.transaction_identifier,
+        "transaction_identifier": identifier_format.serialize_transaction_identifier(&transaction.transaction_identifier),
         "operations": transaction.operations,
         "metadata": {
             "success": transaction.metadata.success,
             "raw_tx": transaction.metadata.raw_tx,
             "result": if decode_clarity_values {
-                serialized_decoded_clarity_value(&transaction.metadata.result, ctx)
+                truncate_decoded_clarity_value(serialized_decoded_clarity_value(&transaction.metadata.result, ctx), max_clarity_value_length)
             } else  {
                 json!(transaction.metadata.result)
             },
@@ -582,8 +1511,8 @@ fn serialize_stacks_transaction(
                 "mutated_contracts_radius": transaction.metadata.receipt.mutated_contracts_radius,
                 "mutated_assets_radius": transaction.metadata.receipt.mutated_assets_radius,
                 "contract_calls_stack": transaction.metadata.receipt.contract_calls_stack,
-                "events": transaction.metadata.receipt.events.iter().map(|event| {
-                    if decode_clarity_values { serialized_event_with_decoded_clarity_value(event, ctx) } else { json!(event) }
+                "events": transaction.metadata.receipt.events.iter().filter(|event| is_captured_event(event, chainhook)).map(|event| {
+                    if decode_clarity_values { serialized_event_with_decoded_clarity_value(event, max_clarity_value_length, ctx) } else { json!(event) }
                 }).collect::<Vec<serde_json::Value>>(),
             },
             "description": transaction.metadata.description,
@@ -592,18 +1521,38 @@ fn serialize_stacks_transaction(
             "position": transaction.metadata.position
         },
     });
+    if decode_clarity_values {
+        if let StacksTransactionKind::ContractCall(ref contract_call) = transaction.metadata.kind {
+            json["metadata"]["kind"]["data"]["decoded_args"] =
+                serialized_decoded_contract_call_args(
+                    &contract_call.args,
+                    max_clarity_value_length,
+                );
+        }
+    }
     if include_contract_abi {
         if let Some(abi) = &transaction.metadata.contract_abi {
             json["metadata"]["contract_abi"] = json!(abi);
         }
     }
+    if let Some(mempool_timestamps) = mempool_timestamps {
+        json["metadata"]["mempool_first_seen_at"] =
+            json!(mempool_timestamps.get(&transaction.transaction_identifier));
+    }
     json
 }
 
 pub fn serialized_event_with_decoded_clarity_value(
     event: &StacksTransactionEvent,
+    max_clarity_value_length: Option<usize>,
     ctx: &Context,
 ) -> serde_json::Value {
+    let decode = |hex_value: &str| {
+        truncate_decoded_clarity_value(
+            serialized_decoded_clarity_value(hex_value, ctx),
+            max_clarity_value_length,
+        )
+    };
     match &event.event_payload {
         StacksTransactionEventPayload::STXTransferEvent(payload) => {
             json!({
@@ -638,7 +1587,7 @@ pub fn serialized_event_with_decoded_clarity_value(
                 "type": "NFTTransferEvent",
                 "data": {
                     "asset_class_identifier": payload.asset_class_identifier,
-                    "asset_identifier": serialized_decoded_clarity_value(&payload.hex_asset_identifier, ctx),
+                    "asset_identifier": decode(&payload.hex_asset_identifier),
                     "sender": payload.sender,
                     "recipient": payload.recipient,
                 },
@@ -650,7 +1599,7 @@ pub fn serialized_event_with_decoded_clarity_value(
                 "type": "NFTMintEvent",
                 "data": {
                     "asset_class_identifier": payload.asset_class_identifier,
-                    "asset_identifier": serialized_decoded_clarity_value(&payload.hex_asset_identifier, ctx),
+                    "asset_identifier": decode(&payload.hex_asset_identifier),
                     "recipient": payload.recipient,
                 },
                 "position": event.position
@@ -661,7 +1610,7 @@ pub fn serialized_event_with_decoded_clarity_value(
                 "type": "NFTBurnEvent",
                 "data": {
                     "asset_class_identifier": payload.asset_class_identifier,
-                    "asset_identifier": serialized_decoded_clarity_value(&payload.hex_asset_identifier, ctx),
+                    "asset_identifier": decode(&payload.hex_asset_identifier),
                     "sender": payload.sender,
                 },
                 "position": event.position
@@ -694,7 +1643,7 @@ pub fn serialized_event_with_decoded_clarity_value(
                 "data": {
                     "contract_identifier": payload.contract_identifier,
                     "var": payload.var,
-                    "new_value": serialized_decoded_clarity_value(&payload.hex_new_value, ctx),
+                    "new_value": decode(&payload.hex_new_value),
                 },
                 "position": event.position
             })
@@ -705,8 +1654,8 @@ pub fn serialized_event_with_decoded_clarity_value(
                 "data": {
                     "contract_identifier": payload.contract_identifier,
                     "map": payload.map,
-                    "inserted_key": serialized_decoded_clarity_value(&payload.hex_inserted_key, ctx),
-                    "inserted_value": serialized_decoded_clarity_value(&payload.hex_inserted_value, ctx),
+                    "inserted_key": decode(&payload.hex_inserted_key),
+                    "inserted_value": decode(&payload.hex_inserted_value),
                 },
                 "position": event.position
             })
@@ -717,8 +1666,8 @@ pub fn serialized_event_with_decoded_clarity_value(
                 "data": {
                     "contract_identifier": payload.contract_identifier,
                     "map": payload.map,
-                    "key": serialized_decoded_clarity_value(&payload.hex_key, ctx),
-                    "new_value": serialized_decoded_clarity_value(&payload.hex_new_value, ctx),
+                    "key": decode(&payload.hex_key),
+                    "new_value": decode(&payload.hex_new_value),
                 },
                 "position": event.position
             })
@@ -729,7 +1678,7 @@ pub fn serialized_event_with_decoded_clarity_value(
                 "data": {
                     "contract_identifier": payload.contract_identifier,
                     "map": payload.map,
-                    "deleted_key": serialized_decoded_clarity_value(&payload.hex_deleted_key, ctx),
+                    "deleted_key": decode(&payload.hex_deleted_key),
                 },
                 "position": event.position
             })
@@ -740,7 +1689,7 @@ pub fn serialized_event_with_decoded_clarity_value(
                 "data": {
                     "contract_identifier": payload.contract_identifier,
                     "topic": payload.topic,
-                    "value": serialized_decoded_clarity_value(&payload.hex_value, ctx),
+                    "value": decode(&payload.hex_value),
                 },
                 "position": event.position
             })
@@ -748,6 +1697,44 @@ pub fn serialized_event_with_decoded_clarity_value(
     }
 }
 
+/// Amounts are only parsed when the predicate is bounded, an unparsable amount never matching.
+fn stx_amount_in_range(amount: &str, expected_event: &StacksStxEventBasedPredicate) -> bool {
+    if expected_event.amount_greater_than.is_none() && expected_event.amount_less_than.is_none() {
+        return true;
+    }
+    let Ok(amount) = amount.parse::<u128>() else {
+        return false;
+    };
+    expected_event
+        .amount_greater_than
+        .map_or(true, |min| amount >= min)
+        && expected_event
+            .amount_less_than
+            .map_or(true, |max| amount < max)
+}
+
+/// Events without a sender (mints) or a recipient (burns, locks) never match a filter on it.
+fn principal_matches(expected_principal: &Option<String>, principal: Option<&String>) -> bool {
+    match expected_principal.as_deref() {
+        None | Some("*") => true,
+        Some(expected_principal) => principal.is_some_and(|p| p == expected_principal),
+    }
+}
+
+/// Token ids that are not integers never fall within a range.
+fn nft_token_id_in_range(hex_token_id: &str, token_id_range: &Option<NftTokenIdRange>) -> bool {
+    let Some(token_id_range) = token_id_range else {
+        return true;
+    };
+    match try_decode_clarity_value(hex_token_id) {
+        Some(ClarityValue::UInt(token_id)) => token_id_range.contains(token_id),
+        Some(ClarityValue::Int(token_id)) if token_id >= 0 => {
+            token_id_range.contains(token_id as u128)
+        }
+        _ => false,
+    }
+}
+
 pub fn expect_decoded_clarity_value(hex_value: &str) -> ClarityValue {
     try_decode_clarity_value(hex_value)
         .expect("unable to decode clarity value emitted by stacks-node")
@@ -759,6 +1746,42 @@ pub fn try_decode_clarity_value(hex_value: &str) -> Option<ClarityValue> {
     ClarityValue::consensus_deserialize(&mut Cursor::new(&value_bytes)).ok()
 }
 
+/// Truncate the strings (decoded Clarity strings, buffers and principals) of `value` that are
+/// longer than `max_length` bytes, appending a `…(truncated N bytes)` marker.
+pub fn truncate_decoded_clarity_value(
+    value: serde_json::Value,
+    max_length: Option<usize>,
+) -> serde_json::Value {
+    let Some(max_length) = max_length else {
+        return value;
+    };
+    match value {
+        serde_json::Value::String(string) if string.len() > max_length => {
+            let mut end = max_length;
+            while !string.is_char_boundary(end) {
+                end -= 1;
+            }
+            json!(format!(
+                "{}…(truncated {} bytes)",
+                &string[..end],
+                string.len() - end
+            ))
+        }
+        serde_json::Value::Array(values) => serde_json::Value::Array(
+            values
+                .into_iter()
+                .map(|value| truncate_decoded_clarity_value(value, Some(max_length)))
+                .collect(),
+        ),
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, truncate_decoded_clarity_value(value, Some(max_length))))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
 pub fn serialized_decoded_clarity_value(hex_value: &str, ctx: &Context) -> serde_json::Value {
     let hex_value = match hex_value.strip_prefix("0x") {
         Some(hex_value) => hex_value,
@@ -780,6 +1803,117 @@ pub fn serialized_decoded_clarity_value(hex_value: &str, ctx: &Context) -> serde
     value
 }
 
+/// Returns true if every expected argument is present in `args` with the expected value. With
+/// `decode_clarity_values`, arguments that are serialized Clarity values are compared in their
+/// decoded form, other arguments being compared as is.
+fn contract_call_args_match(
+    expected_args: &[StacksContractCallArgMatch],
+    args: &[String],
+    decode_clarity_values: bool,
+) -> bool {
+    expected_args.iter().all(|expected| {
+        let Some(arg) = args.get(expected.index) else {
+            return false;
+        };
+        if expected.equals == "*" {
+            return true;
+        }
+        let decoded = if decode_clarity_values {
+            try_decode_contract_call_arg(arg)
+        } else {
+            None
+        };
+        match decoded.map(|value| serialize_to_json(&value)) {
+            Some(serde_json::Value::String(value)) => value == expected.equals,
+            Some(value) => value.to_string() == expected.equals,
+            None => arg == &expected.equals,
+        }
+    })
+}
+
+/// Principal the block reward of a coinbase transaction is paid to: the alternate recipient
+/// carried by the coinbase payload when set, the miner issuing the transaction otherwise.
+fn get_coinbase_recipient(transaction: &StacksTransactionData) -> String {
+    let alt_recipient = transaction
+        .metadata
+        .raw_tx
+        .strip_prefix("0x")
+        .and_then(|raw_tx| hex::decode(raw_tx).ok())
+        .and_then(|bytes| StacksTransaction::consensus_deserialize(&mut Cursor::new(&bytes)).ok())
+        .and_then(|tx| match tx.payload {
+            TransactionPayload::Coinbase(_, alt_recipient) => alt_recipient,
+            _ => None,
+        });
+    match alt_recipient {
+        Some(recipient) => recipient.to_string(),
+        None => transaction.metadata.sender.clone(),
+    }
+}
+
+/// Decodes an argument holding exactly one serialized Clarity value (trailing bytes are rejected,
+/// so that e.g. buffers displayed as hex are not mistaken for serialized values).
+fn try_decode_contract_call_arg(arg: &str) -> Option<ClarityValue> {
+    let bytes = hex::decode(arg.strip_prefix("0x")?).ok()?;
+    let mut cursor = Cursor::new(&bytes);
+    let value = ClarityValue::consensus_deserialize(&mut cursor).ok()?;
+    (cursor.position() as usize == bytes.len()).then_some(value)
+}
+
+/// Decoded form of the arguments of a contract call, aligned with the raw arguments: arguments
+/// that are not serialized Clarity values are kept as is.
+fn serialized_decoded_contract_call_args(
+    args: &[String],
+    max_clarity_value_length: Option<usize>,
+) -> serde_json::Value {
+    args.iter()
+        .map(|arg| match try_decode_contract_call_arg(arg) {
+            Some(value) => {
+                truncate_decoded_clarity_value(serialize_to_json(&value), max_clarity_value_length)
+            }
+            None => json!(arg),
+        })
+        .collect()
+}
+
+/// Events don't record the function that emitted them: they are attributed to the method
+/// invoked by the transaction, including events emitted by nested contract calls.
+fn is_emitted_by_method(
+    transaction: &StacksTransactionData,
+    emitting_method: Option<&String>,
+) -> bool {
+    let Some(expected_method) = emitting_method else {
+        return true;
+    };
+    match &transaction.metadata.kind {
+        StacksTransactionKind::ContractCall(actual_contract_call) => {
+            actual_contract_call.method.eq(expected_method)
+        }
+        _ => false,
+    }
+}
+
+/// Tuple fields conventionally used by contracts to name the events they print.
+pub const DEFAULT_PRINT_EVENT_NAME_KEYS: [&str; 2] = ["event", "event-name"];
+
+/// Extract the name of a print event emitted as a tuple, looking up `keys` in order.
+/// Only string values are considered.
+pub fn get_print_event_name(value: &ClarityValue, keys: &[&str]) -> Option<String> {
+    let ClarityValue::Tuple(data) = value else {
+        return None;
+    };
+    for key in keys.iter() {
+        let Some((_, value)) = data.data_map.iter().find(|(name, _)| name.as_str() == *key) else {
+            continue;
+        };
+        if let ClarityValue::Sequence(SequenceData::String(_)) = value {
+            if let Some(name) = serialize_to_json(value).as_str() {
+                return Some(name.to_string());
+            }
+        }
+    }
+    None
+}
+
 pub fn serialize_to_json(value: &ClarityValue) -> serde_json::Value {
     match value {
         ClarityValue::Int(int) => json!(int),
@@ -838,89 +1972,243 @@ pub fn serialize_to_json(value: &ClarityValue) -> serde_json::Value {
 pub fn serialize_stacks_payload_to_json<'a>(
     trigger: StacksTriggerChainhook<'a>,
     _proofs: &HashMap<&'a TransactionIdentifier, String>,
+    mempool_timestamps: &HashMap<TransactionIdentifier, i64>,
     ctx: &Context,
 ) -> JsonValue {
     let decode_clarity_values = trigger.should_decode_clarity_value();
+    let max_clarity_value_length = trigger.chainhook.max_clarity_value_length;
     let include_contract_abi = trigger.chainhook.include_contract_abi.unwrap_or(false);
-    json!({
+    let mempool_timestamps = match trigger.chainhook.include_mempool_timestamps {
+        Some(true) => Some(mempool_timestamps),
+        _ => None,
+    };
+    let identifier_format = trigger
+        .chainhook
+        .identifier_format
+        .clone()
+        .unwrap_or_default();
+    let chainhook = trigger.chainhook;
+    let mut payload = json!({
         "apply": trigger.apply.into_iter().map(|(transactions, block)| {
-            serialize_stacks_block(block, transactions, decode_clarity_values, include_contract_abi, ctx)
+            serialize_stacks_block(block, transactions, chainhook, decode_clarity_values, max_clarity_value_length, include_contract_abi, mempool_timestamps, &identifier_format, ctx)
         }).collect::<Vec<_>>(),
         "rollback": trigger.rollback.into_iter().map(|(transactions, block)| {
-            serialize_stacks_block(block, transactions, decode_clarity_values, include_contract_abi, ctx)
+            serialize_stacks_block(block, transactions, chainhook, decode_clarity_values, max_clarity_value_length, include_contract_abi, mempool_timestamps, &identifier_format, ctx)
         }).collect::<Vec<_>>(),
         "chainhook": {
-            "uuid": trigger.chainhook.uuid,
-            "predicate": trigger.chainhook.predicate,
-            "is_streaming_blocks": trigger.chainhook.enabled
+            "uuid": chainhook.uuid,
+            "predicate": chainhook.predicate,
+            "is_streaming_blocks": chainhook.enabled
         }
-    })
+    });
+    if let Some(ref derived_fields) = chainhook.derived_fields {
+        inject_derived_fields(&mut payload, derived_fields);
+    }
+    let Some(ref paths) = chainhook.projection else {
+        return payload;
+    };
+    let mut projection = project_json_payload(&payload, paths, ctx);
+    // Receivers tell applied blocks from rolled back ones with these keys, whatever the
+    // selected fields.
+    for key in ["apply", "rollback"] {
+        if projection.get(key).is_none() {
+            let blocks = payload[key].as_array().map_or(0, |blocks| blocks.len());
+            projection[key] = JsonValue::Array(vec![json!({}); blocks]);
+        }
+    }
+    projection
+}
+
+/// Insert, under the `derived` key of each transaction of `payload`, the fields computed by
+/// `derived_fields`. Fields that can't be computed for a transaction are left out.
+fn inject_derived_fields(
+    payload: &mut JsonValue,
+    derived_fields: &BTreeMap<String, DerivedFieldRule>,
+) {
+    for key in ["apply", "rollback"] {
+        let Some(blocks) = payload
+            .get_mut(key)
+            .and_then(|blocks| blocks.as_array_mut())
+        else {
+            continue;
+        };
+        for block in blocks.iter_mut() {
+            let Some(transactions) = block
+                .get_mut("transactions")
+                .and_then(|transactions| transactions.as_array_mut())
+            else {
+                continue;
+            };
+            for transaction in transactions.iter_mut() {
+                let derived = derived_fields
+                    .iter()
+                    .filter_map(|(name, rule)| {
+                        rule.compute(transaction).map(|value| (name.clone(), value))
+                    })
+                    .collect::<serde_json::Map<_, _>>();
+                transaction["derived"] = JsonValue::Object(derived);
+            }
+        }
+    }
+}
+
+/// Keep only the fields of `payload` selected by `paths`. Path segments are separated
+/// with `.`, and a segment suffixed with `[]` selects the field in every item of an array,
+/// e.g. `apply[].transactions[].transaction_identifier.hash`. Unknown paths are ignored.
+pub fn project_json_payload(payload: &JsonValue, paths: &[String], ctx: &Context) -> JsonValue {
+    let mut projection = JsonValue::Object(serde_json::Map::new());
+    for path in paths.iter() {
+        let segments = path.split('.').collect::<Vec<_>>();
+        match project_json_path(payload, &segments) {
+            Some(projected) => merge_json_projection(&mut projection, projected),
+            None => ctx.try_log(|logger| {
+                slog::debug!(logger, "Ignoring unknown projection path {}", path)
+            }),
+        }
+    }
+    projection
+}
+
+fn project_json_path(value: &JsonValue, segments: &[&str]) -> Option<JsonValue> {
+    let Some((segment, segments)) = segments.split_first() else {
+        return Some(value.clone());
+    };
+    let (key, is_array) = match segment.strip_suffix("[]") {
+        Some(key) => (key, true),
+        None => (*segment, false),
+    };
+    let field = value.get(key)?;
+    let projected = if is_array {
+        let items = field
+            .as_array()?
+            .iter()
+            .map(|item| project_json_path(item, segments))
+            .collect::<Vec<_>>();
+        // Items missing the field are kept as null, as long as one of them has it.
+        if !items.is_empty() && items.iter().all(|item| item.is_none()) {
+            return None;
+        }
+        JsonValue::Array(
+            items
+                .into_iter()
+                .map(|item| item.unwrap_or(JsonValue::Null))
+                .collect(),
+        )
+    } else {
+        project_json_path(field, segments)?
+    };
+    let mut object = serde_json::Map::new();
+    object.insert(key.to_string(), projected);
+    Some(JsonValue::Object(object))
+}
+
+fn merge_json_projection(target: &mut JsonValue, source: JsonValue) {
+    match (target, source) {
+        (JsonValue::Object(target), JsonValue::Object(source)) => {
+            for (key, value) in source.into_iter() {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_json_projection(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (JsonValue::Array(target), JsonValue::Array(source)) => {
+            for (existing, value) in target.iter_mut().zip(source.into_iter()) {
+                merge_json_projection(existing, value);
+            }
+        }
+        (target, source) => {
+            if target.is_null() {
+                *target = source;
+            }
+        }
+    }
 }
 
 pub fn handle_stacks_hook_action<'a>(
     trigger: StacksTriggerChainhook<'a>,
     proofs: &HashMap<&'a TransactionIdentifier, String>,
+    mempool_timestamps: &HashMap<TransactionIdentifier, i64>,
     ctx: &Context,
 ) -> Result<StacksChainhookOccurrence, String> {
     match &trigger.chainhook.action {
         HookAction::HttpPost(http) => {
-            let client = Client::builder()
-                .build()
-                .map_err(|e| format!("unable to build http client: {}", e.to_string()))?;
-            let host = format!("{}", http.url);
-            let method = Method::POST;
-            let body = serde_json::to_vec(&serialize_stacks_payload_to_json(trigger, proofs, ctx))
-                .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
-            Ok(StacksChainhookOccurrence::Http(
-                client
-                    .request(method, &host)
-                    .header("Content-Type", "application/json")
-                    .header("Authorization", http.authorization_header.clone())
-                    .body(body),
-            ))
+            let payload =
+                serialize_stacks_payload_to_json(trigger, proofs, mempool_timestamps, ctx);
+            let request = build_http_occurrence_request(http, payload)?;
+            Ok(StacksChainhookOccurrence::Http(request))
         }
         HookAction::FileAppend(disk) => {
-            let bytes = serde_json::to_vec(&serialize_stacks_payload_to_json(trigger, proofs, ctx))
-                .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
+            let bytes = disk.serialize_occurrence(&serialize_stacks_payload_to_json(
+                trigger,
+                proofs,
+                mempool_timestamps,
+                ctx,
+            ))?;
             Ok(StacksChainhookOccurrence::File(
                 disk.path.to_string(),
+                disk.get_rotation_policy(),
+                bytes,
+            ))
+        }
+        HookAction::UnixSocket(socket) => {
+            let bytes = serde_json::to_vec(&serialize_stacks_payload_to_json(
+                trigger,
+                proofs,
+                mempool_timestamps,
+                ctx,
+            ))
+            .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
+            Ok(StacksChainhookOccurrence::UnixSocket(
+                socket.path.to_string(),
+                bytes,
+            ))
+        }
+        HookAction::ZstdFileAppend(disk) => {
+            let bytes = serde_json::to_vec(&serialize_stacks_payload_to_json(
+                trigger,
+                proofs,
+                mempool_timestamps,
+                ctx,
+            ))
+            .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
+            Ok(StacksChainhookOccurrence::ZstdFile(
+                disk.path.to_string(),
+                disk.get_compression_level(),
+                disk.get_rotation_policy(),
                 bytes,
             ))
         }
+        HookAction::Redis(redis) => {
+            let bytes = serde_json::to_vec(&serialize_stacks_payload_to_json(
+                trigger,
+                proofs,
+                mempool_timestamps,
+                ctx,
+            ))
+            .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
+            Ok(StacksChainhookOccurrence::Redis(redis.clone(), bytes))
+        }
+        HookAction::Kafka(kafka) => {
+            let block_identifier = trigger
+                .apply
+                .last()
+                .or(trigger.rollback.last())
+                .map(|(_, block)| block.get_identifier().clone());
+            let key = kafka.render_key(&trigger.chainhook.uuid, block_identifier.as_ref());
+            let bytes = serde_json::to_vec(&serialize_stacks_payload_to_json(
+                trigger,
+                proofs,
+                mempool_timestamps,
+                ctx,
+            ))
+            .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
+            Ok(StacksChainhookOccurrence::Kafka(kafka.clone(), key, bytes))
+        }
         HookAction::Noop => Ok(StacksChainhookOccurrence::Data(
-            StacksChainhookOccurrencePayload {
-                apply: trigger
-                    .apply
-                    .into_iter()
-                    .map(|(transactions, block)| {
-                        let transactions = transactions
-                            .into_iter()
-                            .map(|t| t.clone())
-                            .collect::<Vec<_>>();
-                        StacksApplyTransactionPayload {
-                            block_identifier: block.get_identifier().clone(),
-                            transactions,
-                        }
-                    })
-                    .collect::<Vec<_>>(),
-                rollback: trigger
-                    .rollback
-                    .into_iter()
-                    .map(|(transactions, block)| {
-                        let transactions = transactions
-                            .into_iter()
-                            .map(|t| t.clone())
-                            .collect::<Vec<_>>();
-                        StacksRollbackTransactionPayload {
-                            block_identifier: block.get_identifier().clone(),
-                            transactions,
-                        }
-                    })
-                    .collect::<Vec<_>>(),
-                chainhook: StacksChainhookPayload {
-                    uuid: trigger.chainhook.uuid.clone(),
-                },
-            },
+            StacksChainhookOccurrencePayload::from_trigger(trigger, mempool_timestamps, ctx),
         )),
     }
 }
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chainhook_types::{BlockIdentifier, StacksChainEvent};
+use hiro_system_kit::slog;
+use serde_json::Value as JsonValue;
+
+use super::{evaluate_stacks_chainhooks_on_chain_event, StacksChainhookOccurrencePayload};
+use crate::chainhooks::types::StacksChainhookSpecification;
+use crate::indexer::stacks::{standardize_stacks_marshalled_block, StacksBlockCache};
+use crate::indexer::{Indexer, IndexerConfig};
+use crate::utils::Context;
+
+/// Event posted by a Stacks node to one of its event observers, as recorded in an event log.
+#[derive(Debug, Deserialize)]
+pub struct StacksNodeEventLogEntry {
+    /// Event observer endpoint the event was posted to, e.g. `/new_block`.
+    pub path: String,
+    pub payload: JsonValue,
+}
+
+/// Reconstructs the chain events of a directory of event logs, one `.json` file per event,
+/// processed in the lexicographic order of their file names. Blocks and microblocks are
+/// standardized and go through the same fork handling as the live observer; other events
+/// (mempool, burn blocks, etc) are skipped.
+/// Blocks already standardized in `block_cache` (e.g. by a previous replay of an overlapping
+/// range) are reused instead of being standardized again.
+pub fn load_stacks_chain_events_from_event_logs(
+    event_logs_dir: &Path,
+    indexer_config: &IndexerConfig,
+    block_cache: &StacksBlockCache,
+    ctx: &Context,
+) -> Result<Vec<StacksChainEvent>, String> {
+    let mut event_log_paths = fs::read_dir(event_logs_dir)
+        .map_err(|e| {
+            format!(
+                "unable to read event logs directory {}: {}",
+                event_logs_dir.display(),
+                e.to_string()
+            )
+        })?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .collect::<Vec<PathBuf>>();
+    event_log_paths.sort();
+
+    let mut indexer = Indexer::new(indexer_config.clone());
+    let mut chain_events = vec![];
+    for event_log_path in event_log_paths.iter() {
+        let content = fs::read_to_string(event_log_path).map_err(|e| {
+            format!(
+                "unable to read event log {}: {}",
+                event_log_path.display(),
+                e.to_string()
+            )
+        })?;
+        let entry: StacksNodeEventLogEntry = serde_json::from_str(&content).map_err(|e| {
+            format!(
+                "unable to parse event log {}: {}",
+                event_log_path.display(),
+                e.to_string()
+            )
+        })?;
+        let chain_event = match entry.path.as_str() {
+            "/new_block" => {
+                let block_identifier = match (
+                    entry
+                        .payload
+                        .get("block_height")
+                        .and_then(|height| height.as_u64()),
+                    entry
+                        .payload
+                        .get("index_block_hash")
+                        .and_then(|hash| hash.as_str()),
+                ) {
+                    (Some(index), Some(hash)) => Some(BlockIdentifier {
+                        index,
+                        hash: hash.to_string(),
+                    }),
+                    _ => None,
+                };
+                match block_identifier {
+                    Some(block_identifier) => {
+                        let block = block_cache
+                            .get_or_standardize(&block_identifier, || {
+                                standardize_stacks_marshalled_block(
+                                    &indexer.config,
+                                    entry.payload,
+                                    &mut indexer.stacks_context,
+                                    ctx,
+                                )
+                            })?
+                            .as_ref()
+                            .clone();
+                        indexer.handle_stacks_standardized_block(block, ctx)?
+                    }
+                    None => indexer.handle_stacks_marshalled_block(entry.payload, ctx)?,
+                }
+            }
+            "/new_microblocks" => {
+                indexer.handle_stacks_marshalled_microblock_trail(entry.payload, ctx)?
+            }
+            _ => {
+                ctx.try_log(|logger| {
+                    slog::debug!(
+                        logger,
+                        "Skipping {} event from {}",
+                        entry.path,
+                        event_log_path.display()
+                    )
+                });
+                continue;
+            }
+        };
+        if let Some(chain_event) = chain_event {
+            chain_events.push(chain_event);
+        }
+    }
+    Ok(chain_events)
+}
+
+/// Replays a directory of event logs, without a Stacks node, and returns the occurrences
+/// of the given predicates.
+pub fn replay_stacks_event_logs(
+    event_logs_dir: &Path,
+    indexer_config: &IndexerConfig,
+    chainhooks: Vec<&StacksChainhookSpecification>,
+    block_cache: &StacksBlockCache,
+    ctx: &Context,
+) -> Result<Vec<StacksChainhookOccurrencePayload>, String> {
+    let chain_events =
+        load_stacks_chain_events_from_event_logs(event_logs_dir, indexer_config, block_cache, ctx)?;
+    let mut occurrences = vec![];
+    for chain_event in chain_events.iter() {
+        let (triggered, _predicates_evaluated, _predicates_expired) =
+            evaluate_stacks_chainhooks_on_chain_event(chain_event, chainhooks.clone(), ctx);
+        // Event logs carry no mempool timestamps.
+        occurrences.extend(triggered.into_iter().map(|trigger| {
+            StacksChainhookOccurrencePayload::from_trigger(trigger, &HashMap::new(), ctx)
+        }));
+    }
+    Ok(occurrences)
+}
@@ -0,0 +1,147 @@
+use crate::chainhooks::types::StacksTrait;
+use std::collections::{HashMap, HashSet};
+
+/// Names under which the SIP-009 and SIP-010 traits are deployed, as referenced by `impl-trait`
+/// (e.g. `'SP2PABAF9FTAJYNFZH93XENAJ8FVY99RRM50D2JG9.nft-trait.nft-trait`).
+const SIP09_TRAIT_NAMES: &[&str] = &["nft-trait", "sip-009-trait"];
+const SIP10_TRAIT_NAMES: &[&str] = &["sip-010-trait", "ft-trait"];
+
+/// Functions required by each trait, along with their number of arguments.
+const SIP09_FUNCTIONS: &[(&str, usize)] = &[
+    ("get-last-token-id", 0),
+    ("get-token-uri", 1),
+    ("get-owner", 1),
+    ("transfer", 3),
+];
+const SIP10_FUNCTIONS: &[(&str, usize)] = &[
+    ("transfer", 4),
+    ("get-name", 0),
+    ("get-symbol", 0),
+    ("get-decimals", 0),
+    ("get-balance", 1),
+    ("get-total-supply", 0),
+    ("get-token-uri", 0),
+];
+
+/// Returns true if the Clarity `source` explicitly implements `stacks_trait` with `impl-trait`,
+/// or defines all the functions the trait requires.
+pub fn contract_implements_trait(source: &str, stacks_trait: &StacksTrait) -> bool {
+    let interface = ContractInterface::parse(source);
+    match stacks_trait {
+        StacksTrait::Sip09 => interface.implements(SIP09_TRAIT_NAMES, SIP09_FUNCTIONS),
+        StacksTrait::Sip10 => interface.implements(SIP10_TRAIT_NAMES, SIP10_FUNCTIONS),
+        StacksTrait::Any => {
+            interface.implements(SIP09_TRAIT_NAMES, SIP09_FUNCTIONS)
+                || interface.implements(SIP10_TRAIT_NAMES, SIP10_FUNCTIONS)
+        }
+    }
+}
+
+enum Expression {
+    Atom(String),
+    List(Vec<Expression>),
+}
+
+struct ContractInterface {
+    implemented_traits: HashSet<String>,
+    functions: HashMap<String, usize>,
+}
+
+impl ContractInterface {
+    fn parse(source: &str) -> ContractInterface {
+        let mut implemented_traits = HashSet::new();
+        let mut functions = HashMap::new();
+        for expression in parse_expressions(source) {
+            let Expression::List(list) = expression else {
+                continue;
+            };
+            match list.as_slice() {
+                [Expression::Atom(keyword), Expression::Atom(trait_identifier), ..]
+                    if keyword == "impl-trait" =>
+                {
+                    // Only the trait name is compared, the deployer of the trait varies across networks.
+                    if let Some(trait_name) = trait_identifier.rsplit('.').next() {
+                        implemented_traits.insert(trait_name.to_string());
+                    }
+                }
+                [Expression::Atom(keyword), Expression::List(signature), ..]
+                    if keyword == "define-public" || keyword == "define-read-only" =>
+                {
+                    if let Some(Expression::Atom(name)) = signature.first() {
+                        functions.insert(name.to_string(), signature.len() - 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+        ContractInterface {
+            implemented_traits,
+            functions,
+        }
+    }
+
+    fn implements(&self, trait_names: &[&str], required_functions: &[(&str, usize)]) -> bool {
+        trait_names
+            .iter()
+            .any(|name| self.implemented_traits.contains(*name))
+            || required_functions
+                .iter()
+                .all(|(name, args)| self.functions.get(*name) == Some(args))
+    }
+}
+
+/// Splits a Clarity source into its top-level expressions, skipping comments and string literals.
+/// Unbalanced parentheses are tolerated: unclosed expressions are dropped.
+fn parse_expressions(source: &str) -> Vec<Expression> {
+    let mut stack: Vec<Vec<Expression>> = vec![vec![]];
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+                if let Some(list) = stack.last_mut() {
+                    list.push(Expression::Atom(String::new()));
+                }
+            }
+            '(' => stack.push(vec![]),
+            ')' => {
+                if stack.len() > 1 {
+                    let list = stack.pop().unwrap();
+                    if let Some(parent) = stack.last_mut() {
+                        parent.push(Expression::List(list));
+                    }
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut atom = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | ';' | '"') {
+                        break;
+                    }
+                    atom.push(c);
+                    chars.next();
+                }
+                if let Some(list) = stack.last_mut() {
+                    list.push(Expression::Atom(atom));
+                }
+            }
+        }
+    }
+    stack.swap_remove(0)
+}
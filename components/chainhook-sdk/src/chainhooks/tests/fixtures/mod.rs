@@ -1,10 +1,13 @@
+use chainhook_types::{
+    BlockIdentifier, StacksBlockData, StacksMicroblockData, StacksMicroblockMetadata,
+    StacksTransactionEvent, StacksTransactionEventPosition,
+};
 use chainhook_types::{
     FTBurnEventData, FTMintEventData, FTTransferEventData, NFTBurnEventData, NFTMintEventData,
     NFTTransferEventData, STXBurnEventData, STXLockEventData, STXMintEventData,
     STXTransferEventData, SmartContractEventData, StacksTransactionData,
-    StacksTransactionEventPayload,
+    StacksTransactionEventPayload, StacksTransactionKind,
 };
-use chainhook_types::{StacksBlockData, StacksTransactionEvent};
 use std::collections::HashMap;
 
 lazy_static! {
@@ -46,6 +49,59 @@ pub fn build_stacks_testnet_block_with_contract_deployment() -> StacksBlockData
     base_block.transactions.push(base_transaction);
     base_block
 }
+pub fn build_stacks_testnet_block_with_contract_sources(sources: &[String]) -> StacksBlockData {
+    let mut base_block =
+        load_stacks_block_fixture(std::include_str!("stacks/testnet/base/block.json"));
+    for source in sources.iter() {
+        let mut deploy_transaction = get_contract_deploy_transaction();
+        if let StacksTransactionKind::ContractDeployment(ref mut deployment) =
+            deploy_transaction.metadata.kind
+        {
+            deployment.code = source.clone();
+        }
+        base_block.transactions.push(deploy_transaction);
+    }
+    base_block
+}
+
+pub fn build_stacks_testnet_block_with_sip009_contract_deployment() -> StacksBlockData {
+    build_stacks_testnet_block_with_contract_deployment_of(
+        "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.monkey-sip09",
+        std::include_str!("stacks/testnet/contracts/sip009-nft.clar"),
+    )
+}
+
+pub fn build_stacks_testnet_block_with_sip010_contract_deployment() -> StacksBlockData {
+    build_stacks_testnet_block_with_contract_deployment_of(
+        "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token",
+        std::include_str!("stacks/testnet/contracts/sip010-token.clar"),
+    )
+}
+
+pub fn build_stacks_testnet_block_with_unchecked_vault_contract_deployment() -> StacksBlockData {
+    build_stacks_testnet_block_with_contract_deployment_of(
+        "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.unchecked-vault",
+        std::include_str!("stacks/testnet/contracts/unchecked-vault.clar"),
+    )
+}
+
+fn build_stacks_testnet_block_with_contract_deployment_of(
+    contract_identifier: &str,
+    source: &str,
+) -> StacksBlockData {
+    let mut base_block =
+        load_stacks_block_fixture(std::include_str!("stacks/testnet/base/block.json"));
+    let mut deploy_transaction = get_contract_deploy_transaction();
+    if let StacksTransactionKind::ContractDeployment(ref mut deployment) =
+        deploy_transaction.metadata.kind
+    {
+        deployment.contract_identifier = contract_identifier.to_string();
+        deployment.code = source.to_string();
+    }
+    base_block.transactions.push(deploy_transaction);
+    base_block
+}
+
 pub fn build_stacks_testnet_block_with_contract_call() -> StacksBlockData {
     let mut base_block =
         load_stacks_block_fixture(std::include_str!("stacks/testnet/base/block.json"));
@@ -55,6 +111,124 @@ pub fn build_stacks_testnet_block_with_contract_call() -> StacksBlockData {
     base_block
 }
 
+pub fn build_stacks_testnet_block_with_contract_call_result(
+    success: bool,
+    result: &str,
+) -> StacksBlockData {
+    let mut base_block =
+        load_stacks_block_fixture(std::include_str!("stacks/testnet/base/block.json"));
+    let mut base_transaction = get_contract_call_transaction();
+
+    base_transaction.metadata.success = success;
+    base_transaction.metadata.result = result.to_string();
+    base_block.transactions.push(base_transaction);
+    base_block
+}
+
+/// One contract call transaction per method, each emitting the same `events`.
+pub fn build_stacks_testnet_block_with_methods_emitting_events(
+    methods: &[&str],
+    events: &[StacksTransactionEvent],
+) -> StacksBlockData {
+    let mut base_block =
+        load_stacks_block_fixture(std::include_str!("stacks/testnet/base/block.json"));
+    for method in methods.iter() {
+        let mut base_transaction = get_contract_call_transaction();
+        if let StacksTransactionKind::ContractCall(ref mut contract_call) =
+            base_transaction.metadata.kind
+        {
+            contract_call.method = method.to_string();
+        }
+        base_transaction.metadata.receipt.events = events.to_vec();
+        base_block.transactions.push(base_transaction);
+    }
+    base_block
+}
+
+/// For each contract: a deployment, and a call to the contract emitting a print event.
+pub fn build_stacks_testnet_block_with_contracts(contract_identifiers: &[&str]) -> StacksBlockData {
+    let mut base_block =
+        load_stacks_block_fixture(std::include_str!("stacks/testnet/base/block.json"));
+    for contract_identifier in contract_identifiers.iter() {
+        let mut deploy_transaction = get_contract_deploy_transaction();
+        if let StacksTransactionKind::ContractDeployment(ref mut deployment) =
+            deploy_transaction.metadata.kind
+        {
+            deployment.contract_identifier = contract_identifier.to_string();
+        }
+        base_block.transactions.push(deploy_transaction);
+
+        let mut call_transaction = get_contract_call_transaction();
+        if let StacksTransactionKind::ContractCall(ref mut contract_call) =
+            call_transaction.metadata.kind
+        {
+            contract_call.contract_identifier = contract_identifier.to_string();
+        }
+        call_transaction.metadata.receipt.events = vec![StacksTransactionEvent {
+            event_payload: StacksTransactionEventPayload::SmartContractEvent(
+                SmartContractEventData {
+                    topic: "print".to_string(),
+                    contract_identifier: contract_identifier.to_string(),
+                    hex_value: PRINT_EVENT_HEX.to_string(),
+                },
+            ),
+            position: StacksTransactionEventPosition { index: 0 },
+        }];
+        base_block.transactions.push(call_transaction);
+    }
+    base_block
+}
+
+/// Builds a block with one contract call transaction per token id, each minting the token.
+pub fn build_stacks_testnet_block_with_nft_mints(
+    asset_identifier: &str,
+    hex_token_ids: &[&str],
+) -> StacksBlockData {
+    let mut base_block =
+        load_stacks_block_fixture(std::include_str!("stacks/testnet/base/block.json"));
+    for hex_token_id in hex_token_ids.iter() {
+        let mut mint_transaction = get_contract_call_transaction();
+        mint_transaction.metadata.receipt.events = vec![StacksTransactionEvent {
+            event_payload: StacksTransactionEventPayload::NFTMintEvent(NFTMintEventData {
+                asset_class_identifier: asset_identifier.to_string(),
+                hex_asset_identifier: hex_token_id.to_string(),
+                recipient: "".to_string(),
+            }),
+            position: StacksTransactionEventPosition { index: 0 },
+        }];
+        base_block.transactions.push(mint_transaction);
+    }
+    base_block
+}
+
+pub fn build_stacks_testnet_block_with_coinbase() -> StacksBlockData {
+    let mut base_block =
+        load_stacks_block_fixture(std::include_str!("stacks/testnet/base/block.json"));
+    let base_transaction = get_coinbase_transaction();
+
+    base_block.transactions.push(base_transaction);
+    base_block
+}
+
+/// Microblock confirming the contract call transaction, anchored by `anchor_block`.
+pub fn build_stacks_testnet_microblock_with_contract_call(
+    anchor_block: &StacksBlockData,
+) -> StacksMicroblockData {
+    StacksMicroblockData {
+        block_identifier: BlockIdentifier {
+            index: 0,
+            hash: "0x01".to_string(),
+        },
+        parent_block_identifier: anchor_block.parent_block_identifier.clone(),
+        timestamp: anchor_block.timestamp,
+        transactions: vec![get_contract_call_transaction()],
+        metadata: StacksMicroblockMetadata {
+            anchor_block_identifier: anchor_block.block_identifier.clone(),
+            network: anchor_block.metadata.network.clone(),
+        },
+    }
+}
+
 pub fn get_contract_call_transaction() -> StacksTransactionData {
     serde_json::from_str(std::include_str!(
         "stacks/testnet/base/transaction_contract_call.json"
@@ -68,6 +242,13 @@ pub fn get_contract_deploy_transaction() -> StacksTransactionData {
     .unwrap()
 }
 
+pub fn get_coinbase_transaction() -> StacksTransactionData {
+    serde_json::from_str(std::include_str!(
+        "stacks/testnet/base/transaction_coinbase.json"
+    ))
+    .unwrap()
+}
+
 pub fn get_expected_occurrence() -> String {
     std::include_str!("stacks/testnet/occurrence.json").to_owned()
 }
@@ -89,13 +270,95 @@ pub fn get_all_event_payload_types() -> Vec<StacksTransactionEventPayload> {
         get_test_event_payload_by_type("smart_contract_not_print_event"),
     ]
 }
+/// A complex transaction: every event type, emitted twice (26 events).
+pub fn get_high_event_count_payload_types() -> Vec<StacksTransactionEventPayload> {
+    let mut payloads = get_all_event_payload_types();
+    payloads.extend(get_all_event_payload_types());
+    payloads
+}
+/// A simple transaction, emitting a single STX transfer event.
+pub fn get_low_event_count_payload_types() -> Vec<StacksTransactionEventPayload> {
+    vec![get_test_event_payload_by_type("stx_transfer")]
+}
+/// Three fungible token events out of four, alongside a single STX event.
+pub fn get_mixed_event_payload_types() -> Vec<StacksTransactionEventPayload> {
+    vec![
+        get_test_event_payload_by_type("ft_mint"),
+        get_test_event_payload_by_type("stx_transfer"),
+        get_test_event_payload_by_type("ft_transfer"),
+        get_test_event_payload_by_type("ft_burn"),
+    ]
+}
+/// A swap on a DEX pool, whose LP tokens are then deposited into a lending protocol.
+pub fn get_cross_protocol_event_payload_types() -> Vec<StacksTransactionEventPayload> {
+    vec![
+        get_test_event_payload_by_type("ft_transfer_of_amm_lp_token"),
+        get_test_event_payload_by_type("smart_contract_print_event"),
+    ]
+}
+/// A fungible token burn immediately followed by a mint of the same asset.
+pub fn get_burn_then_mint_event_payload_types() -> Vec<StacksTransactionEventPayload> {
+    vec![
+        get_test_event_payload_by_type("ft_burn"),
+        get_test_event_payload_by_type("ft_mint"),
+    ]
+}
+
+/// Same events as `get_burn_then_mint_event_payload_types`, shuffled.
+pub fn get_mint_then_burn_event_payload_types() -> Vec<StacksTransactionEventPayload> {
+    vec![
+        get_test_event_payload_by_type("ft_mint"),
+        get_test_event_payload_by_type("ft_burn"),
+    ]
+}
 pub fn get_test_event_payload_by_type(event_type: &str) -> StacksTransactionEventPayload {
     match event_type {
         "stx_transfer" => StacksTransactionEventPayload::STXTransferEvent(STXTransferEventData {
             sender: "".to_string(),
             recipient: "".to_string(),
             amount: "".to_string(),
+            memo: None,
         }),
+        "stx_transfer_to_standard_principal" => {
+            StacksTransactionEventPayload::STXTransferEvent(STXTransferEventData {
+                sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+                recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+                amount: "1000".to_string(),
+                memo: None,
+            })
+        }
+        "stx_transfer_to_contract_principal" => {
+            StacksTransactionEventPayload::STXTransferEvent(STXTransferEventData {
+                sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+                recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token".to_string(),
+                amount: "1000".to_string(),
+                memo: None,
+            })
+        }
+        "stx_transfer_with_ascii_memo" => {
+            StacksTransactionEventPayload::STXTransferEvent(STXTransferEventData {
+                sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+                recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+                amount: "1000".to_string(),
+                // "invoice-1234", zero padded to 34 bytes
+                memo: Some(
+                    "0x696e766f6963652d3132333400000000000000000000000000000000000000000000"
+                        .to_string(),
+                ),
+            })
+        }
+        "stx_transfer_with_binary_memo" => {
+            StacksTransactionEventPayload::STXTransferEvent(STXTransferEventData {
+                sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+                recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+                amount: "1000".to_string(),
+                // 0xfffe followed by "invoice-1234", zero padded to 34 bytes
+                memo: Some(
+                    "0xfffe696e766f6963652d313233340000000000000000000000000000000000000000"
+                        .to_string(),
+                ),
+            })
+        }
         "stx_mint" => StacksTransactionEventPayload::STXMintEvent(STXMintEventData {
             recipient: "".to_string(),
             amount: "".to_string(),
@@ -131,6 +394,15 @@ pub fn get_test_event_payload_by_type(event_type: &str) -> StacksTransactionEven
             amount: "".to_string(),
             recipient: "".to_string(),
         }),
+        "ft_transfer_of_amm_lp_token" => {
+            StacksTransactionEventPayload::FTTransferEvent(FTTransferEventData {
+                sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+                asset_class_identifier:
+                    "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.amm-swap::lp-token".to_string(),
+                amount: "1000".to_string(),
+                recipient: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".to_string(),
+            })
+        }
         "ft_mint" => StacksTransactionEventPayload::FTMintEvent(FTMintEventData {
             asset_class_identifier: "asset-id".to_string(),
             recipient: "".to_string(),
@@ -153,6 +425,30 @@ pub fn get_test_event_payload_by_type(event_type: &str) -> StacksTransactionEven
                 hex_value: PRINT_EVENT_HEX.to_string(),
             })
         }
+        "smart_contract_print_event_named" => {
+            StacksTransactionEventPayload::SmartContractEvent(SmartContractEventData {
+                topic: "print".to_string(),
+                contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data"
+                    .to_string(),
+                hex_value: NAMED_PRINT_EVENT_HEX.to_string(),
+            })
+        }
+        "smart_contract_print_event_event_name_key" => {
+            StacksTransactionEventPayload::SmartContractEvent(SmartContractEventData {
+                topic: "print".to_string(),
+                contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data"
+                    .to_string(),
+                hex_value: EVENT_NAME_KEY_PRINT_EVENT_HEX.to_string(),
+            })
+        }
+        "smart_contract_print_event_custom_name_key" => {
+            StacksTransactionEventPayload::SmartContractEvent(SmartContractEventData {
+                topic: "print".to_string(),
+                contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data"
+                    .to_string(),
+                hex_value: CUSTOM_NAME_KEY_PRINT_EVENT_HEX.to_string(),
+            })
+        }
         "smart_contract_print_event_empty" => {
             StacksTransactionEventPayload::SmartContractEvent(SmartContractEventData {
                 topic: "print".to_string(),
@@ -175,3 +471,12 @@ pub fn get_test_event_payload_by_type(event_type: &str) -> StacksTransactionEven
 static PRINT_EVENT_HEX: &str = "0x0d00000010616263736f6d652d76616c7565616263"; // "abcsome-valueabc"
 
 static EMPTY_EVENT_HEX: &str = "0x0d00000000";
+
+// { amount: u100, event: "deposit" }
+static NAMED_PRINT_EVENT_HEX: &str = "0x0c0000000206616d6f756e740100000000000000000000000000000064056576656e740d000000076465706f736974";
+
+// { amount: u100, event-name: "deposit" }
+static EVENT_NAME_KEY_PRINT_EVENT_HEX: &str = "0x0c0000000206616d6f756e7401000000000000000000000000000000640a6576656e742d6e616d650d000000076465706f736974";
+
+// { action: "deposit", amount: u100 }
+static CUSTOM_NAME_KEY_PRINT_EVENT_HEX: &str = "0x0c0000000206616374696f6e0d000000076465706f73697406616d6f756e740100000000000000000000000000000064";
@@ -1,33 +1,67 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use self::fixtures::get_all_event_payload_types;
+use self::fixtures::{
+    get_all_event_payload_types, get_burn_then_mint_event_payload_types,
+    get_cross_protocol_event_payload_types, get_high_event_count_payload_types,
+    get_low_event_count_payload_types, get_mint_then_burn_event_payload_types,
+    get_mixed_event_payload_types,
+};
 
 use super::{
     stacks::{
-        evaluate_stacks_chainhooks_on_chain_event, handle_stacks_hook_action,
+        evaluate_stacks_chainhook_on_chain_event, evaluate_stacks_chainhooks_on_blocks,
+        evaluate_stacks_chainhooks_on_chain_event,
+        evaluate_stacks_chainhooks_on_chain_event_with_occurrences,
+        evaluate_stacks_predicate_on_transaction, handle_stacks_hook_action,
+        match_stacks_predicates_on_chain_event,
+        replay::{load_stacks_chain_events_from_event_logs, replay_stacks_event_logs},
+        traits::contract_implements_trait,
         StacksChainhookOccurrence, StacksTriggerChainhook,
     },
     types::{
-        ExactMatchingRule, FileHook, StacksChainhookSpecification,
-        StacksContractCallBasedPredicate, StacksContractDeploymentPredicate,
-        StacksFtEventBasedPredicate, StacksNftEventBasedPredicate, StacksPrintEventBasedPredicate,
-        StacksTrait,
+        BitcoinChainhookFullSpecification, BlockIdentifierIndexRule, ChainhookConfig,
+        ChainhookFullSpecification, ContractIdentifierRule, DerivedFieldRule, DuplicateUuidPolicy,
+        ExactMatchingRule, FileHook, IdentifierFormat, NftTokenIdRange,
+        StacksChainhookFullSpecification, StacksChainhookNetworkSpecification,
+        StacksChainhookSpecification, StacksChainhookSpecificationBuilder, StacksCoinbasePredicate,
+        StacksContractCallArgMatch, StacksContractCallBasedPredicate,
+        StacksContractDeploymentPredicate, StacksContractInteractionsBasedPredicate,
+        StacksDeployerRule, StacksEventConstraint, StacksEventCountBasedPredicate,
+        StacksEventRatioBasedPredicate, StacksEventSequenceBasedPredicate, StacksEventType,
+        StacksFtEventBasedPredicate, StacksMemoMatchingRule, StacksNegatedPredicate,
+        StacksNftEventBasedPredicate, StacksNonceBasedPredicate, StacksPredicateCombination,
+        StacksPrincipalType, StacksPrintEventBasedPredicate,
+        StacksStxTransferRecipientBasedPredicate, StacksTrait, TimeRange, TriggerOn,
+        ValueMatchingRule,
+    },
+    PARALLEL_EVALUATION_THRESHOLD,
+};
+use crate::{
+    chainhooks::stacks::{project_json_payload, serialize_stacks_payload_to_json},
+    indexer::{
+        fork_scratch_pad::DEFAULT_MAX_REORG_DEPTH,
+        stacks::{StacksBlockCache, DEFAULT_STACKS_BLOCK_CACHE_CAPACITY},
+        IndexerConfig,
     },
+    utils::{Context, MAX_BLOCK_HEIGHTS_ENTRIES},
 };
-use crate::{chainhooks::stacks::serialize_stacks_payload_to_json, utils::Context};
 use crate::{
     chainhooks::{
         tests::fixtures::{get_expected_occurrence, get_test_event_payload_by_type},
-        types::{HookAction, StacksPredicate, StacksStxEventBasedPredicate},
+        types::{HookAction, HttpHook, StacksPredicate, StacksStxEventBasedPredicate},
     },
     utils::AbstractStacksBlock,
 };
 use chainhook_types::{
-    StacksBlockUpdate, StacksChainEvent, StacksChainUpdatedWithBlocksData, StacksNetwork,
-    StacksTransactionData, StacksTransactionEvent, StacksTransactionEventPayload,
-    StacksTransactionEventPosition,
+    BitcoinBlockSignaling, BitcoinNetwork, BlockIdentifier, FTTransferEventData,
+    STXTransferEventData, SmartContractEventData, StacksBlockUpdate, StacksChainEvent,
+    StacksChainUpdatedWithBlocksData, StacksChainUpdatedWithMicroblocksData,
+    StacksChainUpdatedWithReorgData, StacksNetwork, StacksNodeConfig, StacksTransactionData,
+    StacksTransactionEvent, StacksTransactionEventPayload, StacksTransactionEventPosition,
+    StacksTransactionKind,
 };
-use serde_json::Value as JsonValue;
+use serde_json::{json, Value as JsonValue};
+use std::path::PathBuf;
 use test_case::test_case;
 
 pub mod fixtures;
@@ -37,7 +71,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("ft_mint")]],
     StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["mint".to_string()]
+        actions: vec!["mint".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "FtEvent predicates match mint event"
@@ -46,7 +83,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("ft_transfer")]],
     StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["transfer".to_string()]
+        actions: vec!["transfer".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "FtEvent predicates match transfer event"
@@ -65,7 +105,10 @@ pub mod fixtures;
     })]],
     StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["transfer".to_string()]
+        actions: vec!["transfer".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "FtEvent predicates match transfer event if matching event is not first in transaction"
@@ -74,7 +117,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("ft_burn")]],
     StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["burn".to_string()]
+        actions: vec!["burn".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "FtEvent predicates match burn event"
@@ -83,7 +129,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("ft_mint")]],
     StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
         asset_identifier: "wrong-id".to_string(),
-        actions: vec!["mint".to_string()]
+        actions: vec!["mint".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
     }),
     0;
     "FtEvent predicates reject no-match asset id for mint event"
@@ -92,7 +141,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("ft_transfer")]],
     StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
         asset_identifier: "wrong-id".to_string(),
-        actions: vec!["transfer".to_string()]
+        actions: vec!["transfer".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
     }),
     0;
     "FtEvent predicates reject no-match asset id for transfer event"
@@ -101,7 +153,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("ft_burn")]],
     StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
         asset_identifier: "wrong-id".to_string(),
-        actions: vec!["burn".to_string()]
+        actions: vec!["burn".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
     }),
     0;
     "FtEvent predicates reject no-match asset id for burn event"
@@ -110,7 +165,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("ft_mint")],vec![get_test_event_payload_by_type("ft_transfer")],vec![get_test_event_payload_by_type("ft_burn")]],
     StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["mint".to_string(),"transfer".to_string(), "burn".to_string()]
+        actions: vec!["mint".to_string(),"transfer".to_string(), "burn".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
     }),
     3;
     "FtEvent predicates match multiple events"
@@ -119,17 +177,190 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("ft_transfer")],vec![get_test_event_payload_by_type("ft_burn")]],
     StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["mint".to_string()]
+        actions: vec!["mint".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
     }),
     0;
     "FtEvent predicates don't match if missing event"
 )]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::FTTransferEvent(chainhook_types::FTTransferEventData {
+        sender: "".to_string(),
+        asset_class_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token::cbtc".to_string(),
+        amount: "".to_string(),
+        recipient: "".to_string(),
+    })]],
+    StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
+        asset_identifier: "*".to_string(),
+        actions: vec!["transfer".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
+    }),
+    1;
+    "FtEvent predicates match any asset with a wildcard"
+)]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::FTTransferEvent(chainhook_types::FTTransferEventData {
+        sender: "".to_string(),
+        asset_class_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token::cbtc".to_string(),
+        amount: "".to_string(),
+        recipient: "".to_string(),
+    })]],
+    StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
+        asset_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token::*".to_string(),
+        actions: vec!["transfer".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
+    }),
+    1;
+    "FtEvent predicates match any asset class of a contract"
+)]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::FTTransferEvent(chainhook_types::FTTransferEventData {
+        sender: "".to_string(),
+        asset_class_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token-v2::cbtc".to_string(),
+        amount: "".to_string(),
+        recipient: "".to_string(),
+    })]],
+    StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
+        asset_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token::*".to_string(),
+        actions: vec!["transfer".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
+    }),
+    0;
+    "FtEvent predicates reject asset classes of other contracts"
+)]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::FTTransferEvent(chainhook_types::FTTransferEventData {
+        sender: "".to_string(),
+        asset_class_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token::cbtc".to_string(),
+        amount: "".to_string(),
+        recipient: "".to_string(),
+    })]],
+    StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
+        asset_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token::cbtc".to_string(),
+        actions: vec!["transfer".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
+    }),
+    1;
+    "FtEvent predicates match fully specified asset identifiers"
+)]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::FTTransferEvent(chainhook_types::FTTransferEventData {
+        sender: "".to_string(),
+        asset_class_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token::cbtc-v2".to_string(),
+        amount: "".to_string(),
+        recipient: "".to_string(),
+    })]],
+    StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
+        asset_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token::cbtc".to_string(),
+        actions: vec!["transfer".to_string()],
+        emitting_method: None,
+        sender: None,
+        recipient: None,
+    }),
+    0;
+    "FtEvent predicates reject other asset classes of the contract"
+)]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::FTTransferEvent(chainhook_types::FTTransferEventData {
+        sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+        asset_class_identifier: "asset-id".to_string(),
+        amount: "1000".to_string(),
+        recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+    })]],
+    StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
+        asset_identifier: "asset-id".to_string(),
+        actions: vec!["transfer".to_string()],
+        emitting_method: None,
+        sender: Some("ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string()),
+        recipient: Some("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string()),
+    }),
+    1;
+    "FtEvent predicates match sender and recipient"
+)]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::FTTransferEvent(chainhook_types::FTTransferEventData {
+        sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+        asset_class_identifier: "asset-id".to_string(),
+        amount: "1000".to_string(),
+        recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+    })]],
+    StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
+        asset_identifier: "asset-id".to_string(),
+        actions: vec!["transfer".to_string()],
+        emitting_method: None,
+        sender: Some("ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string()),
+        recipient: Some("ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string()),
+    }),
+    0;
+    "FtEvent predicates reject transfers to another recipient"
+)]
 // NftEvent predicate tests
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::NFTMintEvent(chainhook_types::NFTMintEventData {
+        asset_class_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.monkey-sip09::monkey".to_string(),
+        hex_asset_identifier: "0x0100000000000000000000000000000001".to_string(),
+        recipient: "".to_string(),
+    })]],
+    StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
+        asset_identifier: "*".to_string(),
+        actions: vec!["mint".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
+    }),
+    1;
+    "NftEvent predicates match any asset with a wildcard"
+)]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::NFTMintEvent(chainhook_types::NFTMintEventData {
+        asset_class_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.monkey-sip09::monkey".to_string(),
+        hex_asset_identifier: "0x0100000000000000000000000000000001".to_string(),
+        recipient: "".to_string(),
+    })]],
+    StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
+        asset_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.monkey-sip09::*".to_string(),
+        actions: vec!["mint".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
+    }),
+    1;
+    "NftEvent predicates match any asset class of a contract"
+)]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::NFTMintEvent(chainhook_types::NFTMintEventData {
+        asset_class_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.monkey-sip10::monkey".to_string(),
+        hex_asset_identifier: "0x0100000000000000000000000000000001".to_string(),
+        recipient: "".to_string(),
+    })]],
+    StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
+        asset_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.monkey-sip09::*".to_string(),
+        actions: vec!["mint".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
+    }),
+    0;
+    "NftEvent predicates reject asset classes of other contracts"
+)]
 #[test_case(
     vec![vec![get_test_event_payload_by_type("nft_mint")]],
     StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["mint".to_string()]
+        actions: vec!["mint".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "NftEvent predicates match mint event"
@@ -138,7 +369,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("nft_transfer")]],
     StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["transfer".to_string()]
+        actions: vec!["transfer".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "NftEvent predicates match transfer event"
@@ -157,7 +391,10 @@ pub mod fixtures;
     })]],
     StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["transfer".to_string()]
+        actions: vec!["transfer".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "NftEvent predicates match transfer event if matching event is not first in transaction"
@@ -166,7 +403,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("nft_burn")]],
     StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["burn".to_string()]
+        actions: vec!["burn".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "NftEvent predicates match burn event"
@@ -175,7 +415,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("nft_mint")]],
     StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
         asset_identifier: "wrong-id".to_string(),
-        actions: vec!["mint".to_string()]
+        actions: vec!["mint".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
     }),
     0;
     "NftEvent predicates reject no-match asset id for mint event"
@@ -184,7 +427,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("nft_transfer")]],
     StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
         asset_identifier: "wrong-id".to_string(),
-        actions: vec!["transfer".to_string()]
+        actions: vec!["transfer".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
     }),
     0;
     "NftEvent predicates reject no-match asset id for transfer event"
@@ -193,7 +439,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("nft_burn")]],
     StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
         asset_identifier: "wrong-id".to_string(),
-        actions: vec!["burn".to_string()]
+        actions: vec!["burn".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
     }),
     0;
     "NftEvent predicates reject no-match asset id for burn event"
@@ -202,7 +451,10 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("nft_mint")],vec![get_test_event_payload_by_type("nft_transfer")],vec![get_test_event_payload_by_type("nft_burn")]],
     StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["mint".to_string(),"transfer".to_string(), "burn".to_string()]
+        actions: vec!["mint".to_string(),"transfer".to_string(), "burn".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
     }),
     3;
     "NftEvent predicates match multiple events"
@@ -211,16 +463,56 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("nft_transfer")],vec![get_test_event_payload_by_type("nft_burn")]],
     StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
         asset_identifier: "asset-id".to_string(),
-        actions: vec!["mint".to_string()]
+        actions: vec!["mint".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: None,
     }),
     0;
     "NftEvent predicates don't match if missing event"
 )]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::NFTTransferEvent(chainhook_types::NFTTransferEventData {
+        asset_class_identifier: "asset-id".to_string(),
+        hex_asset_identifier: "0x0100000000000000000000000000000001".to_string(),
+        sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+        recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+    })]],
+    StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
+        asset_identifier: "asset-id".to_string(),
+        actions: vec!["transfer".to_string()],
+        token_id_range: None,
+        sender: None,
+        recipient: Some("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string()),
+    }),
+    1;
+    "NftEvent predicates match recipient"
+)]
+#[test_case(
+    vec![vec![StacksTransactionEventPayload::NFTMintEvent(chainhook_types::NFTMintEventData {
+        asset_class_identifier: "asset-id".to_string(),
+        hex_asset_identifier: "0x0100000000000000000000000000000001".to_string(),
+        recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+    })]],
+    StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
+        asset_identifier: "asset-id".to_string(),
+        actions: vec!["mint".to_string()],
+        token_id_range: None,
+        sender: Some("ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string()),
+        recipient: None,
+    }),
+    0;
+    "NftEvent predicates with a sender don't match mint events"
+)]
 // StxEvent predicate tests
 #[test_case(
     vec![vec![get_test_event_payload_by_type("stx_mint")]],
     StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
-        actions: vec!["mint".to_string()]
+        actions: vec!["mint".to_string()],
+        amount_greater_than: None,
+        amount_less_than: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "StxEvent predicates match mint event"
@@ -228,7 +520,11 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("stx_transfer")]],
     StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
-        actions: vec!["transfer".to_string()]
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: None,
+        amount_less_than: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "StxEvent predicates match transfer event"
@@ -236,7 +532,11 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("stx_lock")]],
     StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
-        actions: vec!["lock".to_string()]
+        actions: vec!["lock".to_string()],
+        amount_greater_than: None,
+        amount_less_than: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "StxEvent predicates match lock event"
@@ -244,7 +544,11 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("stx_burn")]],
     StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
-        actions: vec!["burn".to_string()]
+        actions: vec!["burn".to_string()],
+        amount_greater_than: None,
+        amount_less_than: None,
+        sender: None,
+        recipient: None,
     }),
     1;
     "StxEvent predicates match burn event"
@@ -252,7 +556,11 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("stx_mint")],vec![get_test_event_payload_by_type("stx_transfer")],vec![get_test_event_payload_by_type("stx_lock")]],
     StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
-        actions: vec!["mint".to_string(), "transfer".to_string(), "lock".to_string()]
+        actions: vec!["mint".to_string(), "transfer".to_string(), "lock".to_string()],
+        amount_greater_than: None,
+        amount_less_than: None,
+        sender: None,
+        recipient: None,
     }),
     3;
     "StxEvent predicates match multiple events"
@@ -260,17 +568,118 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("stx_transfer")],vec![get_test_event_payload_by_type("stx_lock")]],
     StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
-        actions: vec!["mint".to_string()]
+        actions: vec!["mint".to_string()],
+        amount_greater_than: None,
+        amount_less_than: None,
+        sender: None,
+        recipient: None,
     }),
     0;
     "StxEvent predicates don't match if missing event"
 )]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")]],
+    StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: None,
+        amount_less_than: Some(1000),
+        sender: None,
+        recipient: None,
+    }),
+    0;
+    "StxEvent predicates don't match amount above upper bound (exclusive)"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")]],
+    StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: Some(500),
+        amount_less_than: Some(2000),
+        sender: None,
+        recipient: None,
+    }),
+    1;
+    "StxEvent predicates match amount within bounds"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")]],
+    StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: Some(1000),
+        amount_less_than: None,
+        sender: None,
+        recipient: None,
+    }),
+    1;
+    "StxEvent predicates match amount at lower bound (inclusive)"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")]],
+    StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: Some(2000),
+        amount_less_than: None,
+        sender: None,
+        recipient: None,
+    }),
+    0;
+    "StxEvent predicates don't match amount below lower bound"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer")]],
+    StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: Some(0),
+        amount_less_than: None,
+        sender: None,
+        recipient: None,
+    }),
+    0;
+    "StxEvent predicates don't match unparsable amount when bounded"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")]],
+    StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: None,
+        amount_less_than: None,
+        sender: Some("ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string()),
+        recipient: None,
+    }),
+    1;
+    "StxEvent predicates match sender"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")]],
+    StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: None,
+        amount_less_than: None,
+        sender: Some("*".to_string()),
+        recipient: Some("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string()),
+    }),
+    1;
+    "StxEvent predicates match recipient with any sender"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")]],
+    StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: None,
+        amount_less_than: None,
+        sender: Some("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string()),
+        recipient: None,
+    }),
+    0;
+    "StxEvent predicates reject transfers from another sender"
+)]
 // PrintEvent predicate tests
 #[test_case(
     vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
     StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
-        contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".to_string(),
-        contains: "some-value".to_string()
+        contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".into(),
+        contains: "some-value".to_string(),
+        emitting_method: None,
     }),
     1;
     "PrintEvent predicate matches contract_identifier and contains"
@@ -278,8 +687,9 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("smart_contract_not_print_event")]],
     StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
-        contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".to_string(),
+        contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".into(),
         contains: "some-value".to_string(),
+        emitting_method: None,
     }),
     0;
     "PrintEvent predicate does not check events with topic other than print"
@@ -287,8 +697,9 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
     StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
-        contract_identifier: "wront-id".to_string(),
+        contract_identifier: "wront-id".into(),
         contains: "some-value".to_string(),
+        emitting_method: None,
     }),
     0;
     "PrintEvent predicate rejects non matching contract_identifier"
@@ -297,8 +708,9 @@ pub mod fixtures;
     vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
     StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
         contract_identifier:
-            "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".to_string(),
+            "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".into(),
         contains: "wrong-value".to_string(),
+        emitting_method: None,
     }),
     0;
     "PrintEvent predicate rejects non matching contains value"
@@ -306,8 +718,9 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
     StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
-        contract_identifier: "*".to_string(),
+        contract_identifier: "*".into(),
         contains: "some-value".to_string(),
+        emitting_method: None,
     }),
     1;
     "PrintEvent predicate contract_identifier wildcard checks all print events for match"
@@ -315,8 +728,9 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
     StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
-        contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".to_string(),
+        contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".into(),
         contains: "*".to_string(),
+        emitting_method: None,
     }),
     1;
     "PrintEvent predicate contains wildcard matches all values for matching events"
@@ -324,8 +738,9 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("smart_contract_print_event")], vec![get_test_event_payload_by_type("smart_contract_print_event_empty")]],
     StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
-        contract_identifier: "*".to_string(),
+        contract_identifier: "*".into(),
         contains: "*".to_string(),
+        emitting_method: None,
     }),
     2;
     "PrintEvent predicate contract_identifier wildcard and contains wildcard matches all values on all print events"
@@ -333,8 +748,9 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
     StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::MatchesRegex {
-        contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".to_string(),
+        contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".into(),
         regex: "(some)|(value)".to_string(),
+        emitting_method: None,
     }),
     1;
     "PrintEvent predicate matches contract_identifier and regex"
@@ -342,8 +758,9 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
     StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::MatchesRegex {
-        contract_identifier: "*".to_string(),
+        contract_identifier: "*".into(),
         regex: "(some)|(value)".to_string(),
+        emitting_method: None,
     }),
     1;
     "PrintEvent predicate contract_identifier wildcard checks all print events for match with regex"
@@ -351,121 +768,343 @@ pub mod fixtures;
 #[test_case(
     vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
     StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::MatchesRegex {
-        contract_identifier: "*".to_string(),
+        contract_identifier: "*".into(),
+        regex: "(active)|(pending)".to_string(),
+        emitting_method: None,
+    }),
+    0;
+    "PrintEvent predicate does not match values not matching regex"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::MatchesRegex {
+        contract_identifier: "*".into(),
         regex: "[".to_string(),
+        emitting_method: None,
     }),
     0
     ;
     "PrintEvent predicate does not match invalid regex"
 )]
-fn test_stacks_predicates(
-    blocks_with_events: Vec<Vec<StacksTransactionEventPayload>>,
-    predicate: StacksPredicate,
-    expected_applies: u64,
-) {
-    // Prepare block
-    let new_blocks = blocks_with_events
-        .into_iter()
-        .map(|payloads| StacksBlockUpdate {
-            block: fixtures::build_stacks_testnet_block_from_smart_contract_event_data(
-                payloads
-                    .into_iter()
-                    .enumerate()
-                    .map(|(index, payload)| StacksTransactionEvent {
-                        event_payload: payload,
-                        position: StacksTransactionEventPosition {
-                            index: index as u32,
-                        },
-                    })
-                    .collect::<Vec<_>>()
-                    .as_ref(),
-            ),
-            parent_microblocks_to_apply: vec![],
-            parent_microblocks_to_rollback: vec![],
-        })
-        .collect();
-    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
-        new_blocks,
-        confirmed_blocks: vec![],
-    });
-    // Prepare predicate
-    let chainhook = StacksChainhookSpecification {
-        uuid: "".to_string(),
-        owner_uuid: None,
-        name: "".to_string(),
-        network: StacksNetwork::Testnet,
-        version: 1,
-        blocks: None,
-        start_block: None,
-        end_block: None,
-        expire_after_occurrence: None,
-        capture_all_events: None,
-        decode_clarity_values: None,
-        include_contract_abi: None,
-        predicate: predicate,
-        action: HookAction::Noop,
-        enabled: true,
-        expired_at: None,
-    };
-
-    let predicates = vec![&chainhook];
-    let (triggered, _predicates_evaluated, _expired) =
-        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
-
-    if expected_applies == 0 {
-        assert_eq!(triggered.len(), 0)
-    } else {
-        let actual_applies: u64 = triggered[0].apply.len().try_into().unwrap();
-        assert_eq!(actual_applies, expected_applies);
-    }
-}
-
 #[test_case(
-    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::Deployer("ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9".to_string())),
+    vec![vec![get_test_event_payload_by_type("smart_contract_print_event_named")]],
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::EventName {
+        contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".into(),
+        event_name: "deposit".to_string(),
+        event_name_key: None,
+        emitting_method: None,
+    }),
     1;
-    "Deployer predicate matches by contract deployer"
+    "PrintEvent predicate matches event name"
 )]
 #[test_case(
-    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::Deployer("*".to_string())),
+    vec![vec![get_test_event_payload_by_type("smart_contract_print_event_event_name_key")]],
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::EventName {
+        contract_identifier: "*".into(),
+        event_name: "deposit".to_string(),
+        event_name_key: None,
+        emitting_method: None,
+    }),
     1;
-    "Deployer predicate wildcard deployer catches all occurrences"
+    "PrintEvent predicate matches event name under event-name key"
 )]
 #[test_case(
-    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::Deployer("wrong-deployer".to_string())),
+    vec![vec![get_test_event_payload_by_type("smart_contract_print_event_named")]],
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::EventName {
+        contract_identifier: "*".into(),
+        event_name: "withdraw".to_string(),
+        event_name_key: None,
+        emitting_method: None,
+    }),
     0;
-    "Deployer predicate does not match non-matching deployer"
+    "PrintEvent predicate rejects non matching event name"
 )]
 #[test_case(
-    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::ImplementTrait(StacksTrait::Sip09)),
+    vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::EventName {
+        contract_identifier: "*".into(),
+        event_name: "some-value".to_string(),
+        event_name_key: None,
+        emitting_method: None,
+    }),
     0;
-    "ImplementSip predicate returns no values for Sip09"
+    "PrintEvent predicate event name does not match non tuple values"
 )]
 #[test_case(
-    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::ImplementTrait(StacksTrait::Sip10)),
+    vec![vec![get_test_event_payload_by_type("smart_contract_print_event_custom_name_key")]],
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::EventName {
+        contract_identifier: "*".into(),
+        event_name: "deposit".to_string(),
+        event_name_key: Some("action".to_string()),
+        emitting_method: None,
+    }),
+    1;
+    "PrintEvent predicate matches event name under custom key"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("smart_contract_print_event_custom_name_key")]],
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::EventName {
+        contract_identifier: "*".into(),
+        event_name: "deposit".to_string(),
+        event_name_key: None,
+        emitting_method: None,
+    }),
     0;
-    "ImplementSip predicate returns no values for Sip10"
+    "PrintEvent predicate event name ignores custom key by default"
 )]
+// EventCount predicate tests
 #[test_case(
-    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::ImplementTrait(StacksTrait::Any)),
+    vec![get_high_event_count_payload_types()],
+    StacksPredicate::EventCount(StacksEventCountBasedPredicate { higher_than: 20 }),
+    1;
+    "EventCount predicate matches transaction with more events than threshold"
+)]
+#[test_case(
+    vec![get_high_event_count_payload_types()],
+    StacksPredicate::EventCount(StacksEventCountBasedPredicate { higher_than: 26 }),
     0;
-    "ImplementSip predicate returns no values for Any"
+    "EventCount predicate does not match when count equals threshold"
 )]
-fn test_stacks_predicate_contract_deploy(predicate: StacksPredicate, expected_applies: u64) {
-    // Prepare block
-    let new_blocks = vec![
-        StacksBlockUpdate {
-            block: fixtures::build_stacks_testnet_block_with_contract_deployment(),
-            parent_microblocks_to_apply: vec![],
-            parent_microblocks_to_rollback: vec![],
-        },
-        StacksBlockUpdate {
-            block: fixtures::build_stacks_testnet_block_with_contract_call(),
-            parent_microblocks_to_apply: vec![],
-            parent_microblocks_to_rollback: vec![],
-        },
-    ];
-    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
-        new_blocks,
+#[test_case(
+    vec![get_low_event_count_payload_types()],
+    StacksPredicate::EventCount(StacksEventCountBasedPredicate { higher_than: 20 }),
+    0;
+    "EventCount predicate does not match transaction with few events"
+)]
+#[test_case(
+    vec![get_high_event_count_payload_types(), get_low_event_count_payload_types()],
+    StacksPredicate::EventCount(StacksEventCountBasedPredicate { higher_than: 1 }),
+    1;
+    "EventCount predicate only matches the high event count transaction"
+)]
+// ContractInteractions predicate tests
+#[test_case(
+    vec![get_cross_protocol_event_payload_types()],
+    StacksPredicate::ContractInteractions(StacksContractInteractionsBasedPredicate {
+        all_of: vec![
+            "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.amm-swap".to_string(),
+            "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".to_string(),
+        ],
+    }),
+    1;
+    "ContractInteractions predicate matches transaction touching all contracts"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
+    StacksPredicate::ContractInteractions(StacksContractInteractionsBasedPredicate {
+        all_of: vec![
+            "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.amm-swap".to_string(),
+            "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".to_string(),
+        ],
+    }),
+    0;
+    "ContractInteractions predicate does not match transaction touching one contract"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("smart_contract_print_event")]],
+    StacksPredicate::ContractInteractions(StacksContractInteractionsBasedPredicate {
+        all_of: vec![
+            "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".to_string(),
+            "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.loan-data".to_string(),
+        ],
+    }),
+    1;
+    "ContractInteractions predicate includes the called contract"
+)]
+// EventRatio predicate tests
+#[test_case(
+    vec![get_mixed_event_payload_types()],
+    StacksPredicate::EventRatio(StacksEventRatioBasedPredicate {
+        event_type: StacksEventType::FtEvent,
+        higher_than: 50,
+    }),
+    1;
+    "EventRatio predicate matches when ratio is above threshold"
+)]
+#[test_case(
+    vec![get_mixed_event_payload_types()],
+    StacksPredicate::EventRatio(StacksEventRatioBasedPredicate {
+        event_type: StacksEventType::FtEvent,
+        higher_than: 75,
+    }),
+    0;
+    "EventRatio predicate does not match when ratio equals threshold"
+)]
+#[test_case(
+    vec![get_mixed_event_payload_types()],
+    StacksPredicate::EventRatio(StacksEventRatioBasedPredicate {
+        event_type: StacksEventType::StxEvent,
+        higher_than: 20,
+    }),
+    1;
+    "EventRatio predicate matches minority event type with low threshold"
+)]
+#[test_case(
+    vec![get_mixed_event_payload_types()],
+    StacksPredicate::EventRatio(StacksEventRatioBasedPredicate {
+        event_type: StacksEventType::NftEvent,
+        higher_than: 0,
+    }),
+    0;
+    "EventRatio predicate does not match absent event type"
+)]
+#[test_case(
+    vec![vec![]],
+    StacksPredicate::EventRatio(StacksEventRatioBasedPredicate {
+        event_type: StacksEventType::FtEvent,
+        higher_than: 0,
+    }),
+    0;
+    "EventRatio predicate does not match transaction without events"
+)]
+// EventSequence predicate tests
+#[test_case(
+    vec![get_burn_then_mint_event_payload_types()],
+    build_burn_then_mint_sequence("asset-id"),
+    1;
+    "EventSequence predicate matches events in order"
+)]
+#[test_case(
+    vec![get_mint_then_burn_event_payload_types()],
+    build_burn_then_mint_sequence("asset-id"),
+    0;
+    "EventSequence predicate does not match shuffled events"
+)]
+#[test_case(
+    vec![vec![
+        get_test_event_payload_by_type("ft_burn"),
+        get_test_event_payload_by_type("stx_transfer"),
+        get_test_event_payload_by_type("ft_mint"),
+    ]],
+    build_burn_then_mint_sequence("asset-id"),
+    1;
+    "EventSequence predicate matches events in order with unrelated events interleaved"
+)]
+#[test_case(
+    vec![get_burn_then_mint_event_payload_types()],
+    build_burn_then_mint_sequence("wrong-id"),
+    0;
+    "EventSequence predicate does not match events of another asset"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("ft_burn")]],
+    build_burn_then_mint_sequence("asset-id"),
+    0;
+    "EventSequence predicate does not match partial sequence"
+)]
+// StxTransferRecipient predicate tests
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_contract_principal")]],
+    StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+        principal_type: StacksPrincipalType::Contract,
+        memo: None,
+    }),
+    1;
+    "StxTransferRecipient predicate matches transfer to contract principal"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")]],
+    StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+        principal_type: StacksPrincipalType::Contract,
+        memo: None,
+    }),
+    0;
+    "StxTransferRecipient predicate does not match transfer to standard principal"
+)]
+#[test_case(
+    vec![
+        vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")],
+        vec![get_test_event_payload_by_type("stx_transfer_to_contract_principal")],
+    ],
+    StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+        principal_type: StacksPrincipalType::Contract,
+        memo: None,
+    }),
+    1;
+    "StxTransferRecipient predicate only matches the transfer to contract principal"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")]],
+    StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+        principal_type: StacksPrincipalType::Standard,
+        memo: None,
+    }),
+    1;
+    "StxTransferRecipient predicate matches transfer to standard principal"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_with_ascii_memo")]],
+    StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+        principal_type: StacksPrincipalType::Standard,
+        memo: Some(StacksMemoMatchingRule::Contains("invoice".to_string())),
+    }),
+    1;
+    "StxTransferRecipient predicate matches ASCII memo containing pattern"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_with_ascii_memo")]],
+    StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+        principal_type: StacksPrincipalType::Standard,
+        memo: Some(StacksMemoMatchingRule::Contains("refund".to_string())),
+    }),
+    0;
+    "StxTransferRecipient predicate does not match ASCII memo missing pattern"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_with_ascii_memo")]],
+    StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+        principal_type: StacksPrincipalType::Standard,
+        memo: Some(StacksMemoMatchingRule::MatchesRegex(r"^invoice-\d+$".to_string())),
+    }),
+    1;
+    "StxTransferRecipient predicate matches ASCII memo against regex"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_with_binary_memo")]],
+    StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+        principal_type: StacksPrincipalType::Standard,
+        memo: Some(StacksMemoMatchingRule::Contains("invoice".to_string())),
+    }),
+    0;
+    "StxTransferRecipient predicate skips non-ASCII memo"
+)]
+#[test_case(
+    vec![vec![get_test_event_payload_by_type("stx_transfer_to_standard_principal")]],
+    StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+        principal_type: StacksPrincipalType::Standard,
+        memo: Some(StacksMemoMatchingRule::Contains("invoice".to_string())),
+    }),
+    0;
+    "StxTransferRecipient predicate does not match transfer without memo"
+)]
+fn test_stacks_predicates(
+    blocks_with_events: Vec<Vec<StacksTransactionEventPayload>>,
+    predicate: StacksPredicate,
+    expected_applies: u64,
+) {
+    // Prepare block
+    let new_blocks = blocks_with_events
+        .into_iter()
+        .map(|payloads| StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_from_smart_contract_event_data(
+                payloads
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, payload)| StacksTransactionEvent {
+                        event_payload: payload,
+                        position: StacksTransactionEventPosition {
+                            index: index as u32,
+                        },
+                    })
+                    .collect::<Vec<_>>()
+                    .as_ref(),
+            ),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        })
+        .collect();
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks,
         confirmed_blocks: vec![],
     });
     // Prepare predicate
@@ -482,6 +1121,13 @@ fn test_stacks_predicate_contract_deploy(predicate: StacksPredicate, expected_ap
         capture_all_events: None,
         decode_clarity_values: None,
         include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
         predicate: predicate,
         action: HookAction::Noop,
         enabled: true,
@@ -489,63 +1135,87 @@ fn test_stacks_predicate_contract_deploy(predicate: StacksPredicate, expected_ap
     };
 
     let predicates = vec![&chainhook];
-    let (triggered, _predicates_evaluated, _predicates_expired) =
+    let (triggered, _predicates_evaluated, _expired) =
         evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
 
     if expected_applies == 0 {
         assert_eq!(triggered.len(), 0)
-    } else if triggered.len() == 0 {
-        panic!("expected more than one block to be applied, but no predicates were triggered")
     } else {
         let actual_applies: u64 = triggered[0].apply.len().try_into().unwrap();
         assert_eq!(actual_applies, expected_applies);
     }
 }
 
-#[test]
-fn verify_optional_addition_of_contract_abi() {
-    // "mine" two blocks
-    //  - one contract deploy (which should have a contract abi) and
-    //  - one contract call (which should not)
-    let new_blocks = vec![
-        StacksBlockUpdate {
-            block: fixtures::build_stacks_testnet_block_with_contract_deployment(),
-            parent_microblocks_to_apply: vec![],
-            parent_microblocks_to_rollback: vec![],
-        },
-        StacksBlockUpdate {
-            block: fixtures::build_stacks_testnet_block_with_contract_call(),
-            parent_microblocks_to_apply: vec![],
-            parent_microblocks_to_rollback: vec![],
+#[test_case(
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::EventName {
+        contract_identifier: "*".into(),
+        event_name: "deposit".to_string(),
+        event_name_key: None,
+        emitting_method: Some("swap".to_string()),
+    }),
+    vec!["swap"];
+    "PrintEvent predicate only matches events emitted by method"
+)]
+#[test_case(
+    StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
+        asset_identifier: "asset-id".to_string(),
+        actions: vec!["mint".to_string()],
+        emitting_method: Some("add-liquidity".to_string()),
+        sender: None,
+        recipient: None,
+    }),
+    vec!["add-liquidity"];
+    "FtEvent predicate only matches events emitted by method"
+)]
+#[test_case(
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
+        contract_identifier: "*".into(),
+        contains: "deposit".to_string(),
+        emitting_method: None,
+    }),
+    vec!["swap", "add-liquidity"];
+    "PrintEvent predicate without emitting method matches events emitted by any method"
+)]
+#[test_case(
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
+        contract_identifier: "*".into(),
+        contains: "deposit".to_string(),
+        emitting_method: Some("remove-liquidity".to_string()),
+    }),
+    vec![];
+    "PrintEvent predicate rejects events emitted by other methods"
+)]
+fn test_stacks_event_predicates_with_emitting_method(
+    predicate: StacksPredicate,
+    expected_methods: Vec<&str>,
+) {
+    let events = vec![
+        get_test_event_payload_by_type("smart_contract_print_event_named"),
+        get_test_event_payload_by_type("ft_mint"),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(index, payload)| StacksTransactionEvent {
+        event_payload: payload,
+        position: StacksTransactionEventPosition {
+            index: index as u32,
         },
-    ];
-    let event: StacksChainEvent =
-        StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
-            new_blocks,
-            confirmed_blocks: vec![],
-        });
-    let mut contract_deploy_chainhook = StacksChainhookSpecification {
-        uuid: "contract-deploy".to_string(),
-        owner_uuid: None,
-        name: "".to_string(),
-        network: StacksNetwork::Testnet,
-        version: 1,
-        blocks: None,
-        start_block: None,
-        end_block: None,
-        expire_after_occurrence: None,
-        capture_all_events: None,
-        decode_clarity_values: None,
-        include_contract_abi: Some(true),
-        predicate: StacksPredicate::ContractDeployment(
-            StacksContractDeploymentPredicate::Deployer("*".to_string()),
+    })
+    .collect::<Vec<_>>();
+    let new_blocks = vec![StacksBlockUpdate {
+        block: fixtures::build_stacks_testnet_block_with_methods_emitting_events(
+            &["swap", "add-liquidity"],
+            &events,
         ),
-        action: HookAction::Noop,
-        enabled: true,
-        expired_at: None,
-    };
-    let contract_call_chainhook = StacksChainhookSpecification {
-        uuid: "contract-call".to_string(),
+        parent_microblocks_to_apply: vec![],
+        parent_microblocks_to_rollback: vec![],
+    }];
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks,
+        confirmed_blocks: vec![],
+    });
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
         owner_uuid: None,
         name: "".to_string(),
         network: StacksNetwork::Testnet,
@@ -556,104 +1226,101 @@ fn verify_optional_addition_of_contract_abi() {
         expire_after_occurrence: None,
         capture_all_events: None,
         decode_clarity_values: None,
-        include_contract_abi: Some(true),
-        predicate: StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
-            contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".to_string(),
-            method: "commit-block".to_string(),
-        }),
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate,
         action: HookAction::Noop,
         enabled: true,
         expired_at: None,
     };
 
-    let predicates = vec![&contract_deploy_chainhook, &contract_call_chainhook];
-    let (triggered, _blocks, _) =
-        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
-    assert_eq!(triggered.len(), 2);
-
-    for t in triggered.into_iter() {
-        let result = serialize_stacks_payload_to_json(t, &HashMap::new(), &Context::empty());
-        let result = result.as_object().unwrap();
-        let uuid = result.get("chainhook").unwrap().get("uuid").unwrap();
-        let apply_blocks = result.get("apply").unwrap();
-        for block in apply_blocks.as_array().unwrap() {
-            let transactions = block.get("transactions").unwrap();
-            for transaction in transactions.as_array().unwrap() {
-                let contract_abi = transaction.get("metadata").unwrap().get("contract_abi");
-                if uuid == "contract-call" {
-                    assert_eq!(contract_abi, None);
-                } else if uuid == "contract-deploy" {
-                    assert!(contract_abi.is_some())
-                } else {
-                    unreachable!()
-                }
-            }
-        }
-    }
-    contract_deploy_chainhook.include_contract_abi = Some(false);
-    let predicates = vec![&contract_deploy_chainhook, &contract_call_chainhook];
-    let (triggered, _blocks, _) =
-        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
-    assert_eq!(triggered.len(), 2);
+    let (triggered, _predicates_evaluated, _predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, vec![&chainhook], &Context::empty());
 
-    for t in triggered.into_iter() {
-        let result = serialize_stacks_payload_to_json(t, &HashMap::new(), &Context::empty());
-        let result = result.as_object().unwrap();
-        let apply_blocks = result.get("apply").unwrap();
-        for block in apply_blocks.as_array().unwrap() {
-            let transactions = block.get("transactions").unwrap();
-            for transaction in transactions.as_array().unwrap() {
-                let contract_abi = transaction.get("metadata").unwrap().get("contract_abi");
-                assert_eq!(contract_abi, None);
-            }
-        }
+    if expected_methods.is_empty() {
+        assert_eq!(triggered.len(), 0);
+        return;
     }
+    let (transactions, _block) = &triggered[0].apply[0];
+    let actual_methods = transactions
+        .iter()
+        .map(|tx| match &tx.metadata.kind {
+            StacksTransactionKind::ContractCall(contract_call) => contract_call.method.as_str(),
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(actual_methods, expected_methods);
 }
 
 #[test_case(
-    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
-        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".to_string(),
-        method: "commit-block".to_string()
-    }),
+    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::Deployer("ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9".into())),
     1;
-    "ContractCall predicate matches by contract identifier and method"
+    "Deployer predicate matches by contract deployer"
 )]
 #[test_case(
-    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
-        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".to_string(),
-        method: "wrong-method".to_string()
-    }),
-    0;
-    "ContractCall predicate does not match for wrong method"
+    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::Deployer("*".into())),
+    3;
+    "Deployer predicate wildcard deployer catches all occurrences"
 )]
 #[test_case(
-    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
-        contract_identifier: "wrong-id".to_string(),
-        method: "commit-block".to_string()
-    }),
+    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::Deployer("st13f481sbr0r7z6nmmh8yv2fjjyxa5jpa0ad3hp9".into())),
     0;
-    "ContractCall predicate does not match for wrong contract identifier"
+    "Deployer predicate compares deployers case-sensitively by default"
 )]
 #[test_case(
-    StacksPredicate::Txid(ExactMatchingRule::Equals("0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string())),
+    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::Deployer(StacksDeployerRule::EqualsWithOptions {
+        equals: "st13f481sbr0r7z6nmmh8yv2fjjyxa5jpa0ad3hp9".to_string(),
+        case_insensitive: Some(true),
+    })),
     1;
-    "Txid predicate matches by a transaction's id"
+    "Deployer predicate matches deployers case insensitively"
 )]
 #[test_case(
-    StacksPredicate::Txid(ExactMatchingRule::Equals("wrong-id".to_string())),
+    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::Deployer("wrong-deployer".into())),
     0;
-    "Txid predicate rejects non matching id"
+    "Deployer predicate does not match non-matching deployer"
 )]
-fn test_stacks_predicate_contract_call(predicate: StacksPredicate, expected_applies: u64) {
+#[test_case(
+    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::ImplementTrait(StacksTrait::Sip09)),
+    1;
+    "ImplementSip predicate matches the deployment of a Sip09 contract"
+)]
+#[test_case(
+    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::ImplementTrait(StacksTrait::Sip10)),
+    1;
+    "ImplementSip predicate matches the deployment of a Sip10 contract"
+)]
+#[test_case(
+    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::ImplementTrait(StacksTrait::Any)),
+    2;
+    "ImplementSip predicate matches the deployment of either a Sip09 or a Sip10 contract"
+)]
+fn test_stacks_predicate_contract_deploy(predicate: StacksPredicate, expected_applies: u64) {
     // Prepare block
     let new_blocks = vec![
+        StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_with_contract_deployment(),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        },
         StacksBlockUpdate {
             block: fixtures::build_stacks_testnet_block_with_contract_call(),
             parent_microblocks_to_apply: vec![],
             parent_microblocks_to_rollback: vec![],
         },
         StacksBlockUpdate {
-            block: fixtures::build_stacks_testnet_block_with_contract_deployment(),
+            block: fixtures::build_stacks_testnet_block_with_sip009_contract_deployment(),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        },
+        StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_with_sip010_contract_deployment(),
             parent_microblocks_to_apply: vec![],
             parent_microblocks_to_rollback: vec![],
         },
@@ -676,6 +1343,13 @@ fn test_stacks_predicate_contract_call(predicate: StacksPredicate, expected_appl
         capture_all_events: None,
         decode_clarity_values: None,
         include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
         predicate: predicate,
         action: HookAction::Noop,
         enabled: true,
@@ -696,8 +1370,42 @@ fn test_stacks_predicate_contract_call(predicate: StacksPredicate, expected_appl
     }
 }
 
-#[test]
-fn test_stacks_hook_action_noop() {
+#[test_case(
+    StacksPredicate::Coinbase(StacksCoinbasePredicate { recipient: None }),
+    1;
+    "Coinbase predicate matches the coinbase transaction of a block"
+)]
+#[test_case(
+    StacksPredicate::Coinbase(StacksCoinbasePredicate {
+        recipient: Some("ST2X2FYCY01Y7YR2TGC2Y6661NFF3SMH0NGXPWTV5".to_string()),
+    }),
+    1;
+    "Coinbase predicate matches the coinbase transaction paying the recipient"
+)]
+#[test_case(
+    StacksPredicate::Coinbase(StacksCoinbasePredicate {
+        recipient: Some("ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9".to_string()),
+    }),
+    0;
+    "Coinbase predicate does not match the coinbase transaction paying another recipient"
+)]
+fn test_stacks_predicate_coinbase(predicate: StacksPredicate, expected_applies: u64) {
+    let new_blocks = vec![
+        StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_with_coinbase(),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        },
+        StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_with_contract_call(),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        },
+    ];
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks,
+        confirmed_blocks: vec![],
+    });
     let chainhook = StacksChainhookSpecification {
         uuid: "".to_string(),
         owner_uuid: None,
@@ -711,118 +1419,3036 @@ fn test_stacks_hook_action_noop() {
         capture_all_events: None,
         decode_clarity_values: None,
         include_contract_abi: None,
-        predicate: StacksPredicate::Txid(ExactMatchingRule::Equals(
-            "0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string(),
-        )),
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate,
         action: HookAction::Noop,
         enabled: true,
         expired_at: None,
     };
 
-    let apply_block_data = fixtures::build_stacks_testnet_block_with_contract_call();
-    let apply_transactions = apply_block_data.transactions.iter().map(|t| t).collect();
-    let apply_blocks: &dyn AbstractStacksBlock = &apply_block_data;
-
-    let rollback_block_data = fixtures::build_stacks_testnet_block_with_contract_deployment();
-    let rollback_transactions = rollback_block_data.transactions.iter().map(|t| t).collect();
-    let rollback_blocks: &dyn AbstractStacksBlock = &apply_block_data;
-    let trigger = StacksTriggerChainhook {
-        chainhook: &chainhook,
-        apply: vec![(apply_transactions, apply_blocks)],
-        rollback: vec![(rollback_transactions, rollback_blocks)],
-    };
+    let predicates = vec![&chainhook];
+    let (triggered, _predicates_evaluated, _predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
 
-    let proofs = HashMap::new();
-    let ctx = Context {
-        logger: None,
-        tracer: false,
-    };
-    let occurrence = handle_stacks_hook_action(trigger, &proofs, &ctx).unwrap();
-    if let StacksChainhookOccurrence::Data(data) = occurrence {
-        assert_eq!(data.apply.len(), 1);
-        assert_eq!(
-            data.apply[0].block_identifier.hash,
-            apply_block_data.block_identifier.hash
-        );
-        assert_eq!(data.rollback.len(), 1);
+    if expected_applies == 0 {
+        assert_eq!(triggered.len(), 0)
+    } else {
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].apply.len() as u64, expected_applies);
+        let (transactions, _) = &triggered[0].apply[0];
+        assert_eq!(transactions.len(), 1);
         assert_eq!(
-            data.rollback[0].block_identifier.hash,
-            rollback_block_data.block_identifier.hash
+            transactions[0].metadata.kind,
+            StacksTransactionKind::Coinbase
         );
-    } else {
-        panic!("wrong occurrence type");
     }
 }
 
-#[test]
-fn test_stacks_hook_action_file_append() {
+#[test_case(false, false, vec![(true, 1)] ; "transaction confirmed in a microblock")]
+#[test_case(true, false, vec![(true, 1)] ; "transaction confirmed in a microblock and the anchor block")]
+#[test_case(true, true, vec![(true, 1)] ; "rollback of a transaction confirmed in a microblock and the anchor block")]
+fn test_stacks_predicate_on_parent_microblocks(
+    included_by_anchor_block: bool,
+    reorg: bool,
+    // (occurrence from a microblock, number of transactions) of the applies, or of the
+    // rollbacks when the block update is reorged
+    expected_occurrences: Vec<(bool, usize)>,
+) {
+    let anchor_block = match included_by_anchor_block {
+        true => fixtures::build_stacks_testnet_block_with_contract_call(),
+        false => fixtures::build_stacks_testnet_block_with_coinbase(),
+    };
+    let microblock = fixtures::build_stacks_testnet_microblock_with_contract_call(&anchor_block);
+    let block_update = StacksBlockUpdate {
+        block: anchor_block,
+        parent_microblocks_to_apply: match reorg {
+            true => vec![],
+            false => vec![microblock.clone()],
+        },
+        parent_microblocks_to_rollback: match reorg {
+            true => vec![microblock],
+            false => vec![],
+        },
+    };
+    let event = match reorg {
+        true => StacksChainEvent::ChainUpdatedWithReorg(StacksChainUpdatedWithReorgData {
+            blocks_to_rollback: vec![block_update],
+            blocks_to_apply: vec![],
+            confirmed_blocks: vec![],
+        }),
+        false => StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+            new_blocks: vec![block_update],
+            confirmed_blocks: vec![],
+        }),
+    };
+    let chainhook = StacksChainhookSpecification::builder()
+        .uuid("microblocks")
+        .network(StacksNetwork::Testnet)
+        .predicate(StacksPredicate::Txid(ExactMatchingRule::Equals(
+            "0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string(),
+        )))
+        .build()
+        .unwrap();
+
+    let (triggered, _predicates_evaluated, _predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, vec![&chainhook], &Context::empty());
+
+    assert_eq!(triggered.len(), 1);
+    let occurrences = match reorg {
+        true => &triggered[0].rollback,
+        false => &triggered[0].apply,
+    };
+    let actual_occurrences = occurrences
+        .iter()
+        .map(|(transactions, block)| (block.get_identifier().hash == "0x01", transactions.len()))
+        .collect::<Vec<_>>();
+    assert_eq!(actual_occurrences, expected_occurrences);
+}
+
+#[test_case(StacksNetwork::Mainnet, Some(StacksNetwork::Testnet), false, 0; "testnet block is skipped by mainnet predicate")]
+#[test_case(StacksNetwork::Testnet, Some(StacksNetwork::Testnet), false, 1; "testnet block is evaluated by testnet predicate")]
+#[test_case(StacksNetwork::Mainnet, None, false, 1; "block from unknown network is evaluated")]
+#[test_case(StacksNetwork::Mainnet, Some(StacksNetwork::Testnet), true, 0; "testnet microblock is skipped by mainnet predicate")]
+#[test_case(StacksNetwork::Testnet, Some(StacksNetwork::Testnet), true, 1; "testnet microblock is evaluated by testnet predicate")]
+fn test_stacks_predicate_network_mismatch(
+    network: StacksNetwork,
+    block_network: Option<StacksNetwork>,
+    in_microblock: bool,
+    expected_applies: u64,
+) {
+    let mut block = fixtures::build_stacks_testnet_block_with_contract_call();
+    block.metadata.network = block_network;
+    let event = if in_microblock {
+        StacksChainEvent::ChainUpdatedWithMicroblocks(StacksChainUpdatedWithMicroblocksData {
+            new_microblocks: vec![
+                fixtures::build_stacks_testnet_microblock_with_contract_call(&block),
+            ],
+        })
+    } else {
+        StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+            new_blocks: vec![StacksBlockUpdate {
+                block,
+                parent_microblocks_to_apply: vec![],
+                parent_microblocks_to_rollback: vec![],
+            }],
+            confirmed_blocks: vec![],
+        })
+    };
     let chainhook = StacksChainhookSpecification {
         uuid: "".to_string(),
         owner_uuid: None,
         name: "".to_string(),
-        network: StacksNetwork::Testnet,
+        network,
         version: 1,
         blocks: None,
         start_block: None,
         end_block: None,
         expire_after_occurrence: None,
         capture_all_events: None,
-        decode_clarity_values: Some(true),
+        decode_clarity_values: None,
         include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
         predicate: StacksPredicate::Txid(ExactMatchingRule::Equals(
             "0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string(),
         )),
-        action: HookAction::FileAppend(FileHook {
-            path: "./".to_string(),
-        }),
+        action: HookAction::Noop,
         enabled: true,
         expired_at: None,
     };
-    let payloads = get_all_event_payload_types();
-    let apply_blocks = payloads
-        .into_iter()
-        .map(|payload| {
-            fixtures::build_stacks_testnet_block_from_smart_contract_event_data(&vec![
-                StacksTransactionEvent {
-                    event_payload: payload,
-                    position: StacksTransactionEventPosition { index: 0 },
-                },
-            ])
+
+    let predicates = vec![&chainhook];
+    let (triggered, predicates_evaluated, _predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
+
+    if expected_applies == 0 {
+        assert_eq!(triggered.len(), 0);
+        assert!(predicates_evaluated.is_empty());
+    } else {
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].apply.len() as u64, expected_applies);
+    }
+}
+
+#[test_case(StacksTrait::Sip09, "(impl-trait .nft-trait.nft-trait)", true; "explicit Sip09 impl-trait")]
+#[test_case(StacksTrait::Sip10, "(impl-trait 'SP3FBR2AGK5H9QBDH3EEN6DF8EK8JY7RX8QJ5SVTE.sip-010-trait-ft-standard.sip-010-trait)", true; "explicit Sip10 impl-trait")]
+#[test_case(StacksTrait::Sip10, "(use-trait ft-trait 'SP3FBR2AGK5H9QBDH3EEN6DF8EK8JY7RX8QJ5SVTE.sip-010-trait-ft-standard.sip-010-trait)", false; "use-trait is not an implementation")]
+#[test_case(StacksTrait::Sip10, ";; (impl-trait .sip-010-trait-ft-standard.sip-010-trait)", false; "commented out impl-trait is ignored")]
+#[test_case(StacksTrait::Sip09, include_str!("fixtures/stacks/testnet/contracts/sip010-token.clar"), false; "Sip10 signatures do not implement Sip09")]
+#[test_case(StacksTrait::Any, include_str!("fixtures/stacks/testnet/contracts/sip010-token.clar"), true; "Sip10 signatures implement any trait")]
+#[test_case(StacksTrait::Sip10, &include_str!("fixtures/stacks/testnet/contracts/sip010-token.clar").replace("(get-total-supply)", "(get-supply)"), false; "missing Sip10 function")]
+fn test_stacks_contract_implements_trait(stacks_trait: StacksTrait, source: &str, expected: bool) {
+    assert_eq!(contract_implements_trait(source, &stacks_trait), expected);
+}
+
+#[test_case(ValueMatchingRule::HigherThan(1_000), vec![LARGE_CONTRACT_SOURCE_LEN]; "SourceSize predicate matches sources larger than the threshold")]
+#[test_case(ValueMatchingRule::LowerThan(1_000), vec![SMALL_CONTRACT_SOURCE_LEN]; "SourceSize predicate matches sources smaller than the threshold")]
+#[test_case(ValueMatchingRule::HigherThan(LARGE_CONTRACT_SOURCE_LEN as u64), vec![]; "SourceSize predicate threshold is exclusive")]
+fn test_stacks_predicate_contract_deploy_source_size(
+    rule: ValueMatchingRule,
+    expected_source_lengths: Vec<usize>,
+) {
+    let sources = vec![
+        "(".repeat(SMALL_CONTRACT_SOURCE_LEN),
+        "(".repeat(LARGE_CONTRACT_SOURCE_LEN),
+    ];
+    let block = fixtures::build_stacks_testnet_block_with_contract_sources(&sources);
+    let chainhook = build_replay_specification(StacksPredicate::ContractDeployment(
+        StacksContractDeploymentPredicate::SourceSize(rule),
+    ));
+
+    let actual_source_lengths = block
+        .transactions
+        .iter()
+        .filter(|tx| evaluate_stacks_predicate_on_transaction(tx, &chainhook, &Context::empty()))
+        .map(|tx| match &tx.metadata.kind {
+            StacksTransactionKind::ContractDeployment(deployment) => deployment.code.len(),
+            _ => unreachable!(),
         })
         .collect::<Vec<_>>();
-    let apply: Vec<(Vec<&StacksTransactionData>, &dyn AbstractStacksBlock)> = apply_blocks
+    assert_eq!(actual_source_lengths, expected_source_lengths);
+}
+
+#[test_case("(as-contract (stx-transfer? amount tx-sender recipient))", vec!["ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.unchecked-vault"]; "SourceContains predicate matches a function body")]
+#[test_case("fungible-token", vec!["ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.monkey-sip09", "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token"]; "SourceContains predicate matches every source containing the string")]
+#[test_case("*", vec!["ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.monkey-sip09", "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token", "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.unchecked-vault"]; "SourceContains predicate matches any deployment with a wildcard")]
+#[test_case("(define-public (withdraw-all", vec![]; "SourceContains predicate does not match sources without the string")]
+#[test_case("AS-CONTRACT", vec![]; "SourceContains predicate is case sensitive")]
+fn test_stacks_predicate_contract_deploy_source_contains(
+    contains: &str,
+    expected_contract_identifiers: Vec<&str>,
+) {
+    let blocks = vec![
+        fixtures::build_stacks_testnet_block_with_sip009_contract_deployment(),
+        fixtures::build_stacks_testnet_block_with_sip010_contract_deployment(),
+        fixtures::build_stacks_testnet_block_with_unchecked_vault_contract_deployment(),
+    ];
+    let chainhook = build_replay_specification(StacksPredicate::ContractDeployment(
+        StacksContractDeploymentPredicate::SourceContains(contains.to_string()),
+    ));
+    assert!(chainhook.predicate.validate().is_ok());
+
+    let actual_contract_identifiers = blocks
         .iter()
-        .map(|b| {
-            (
-                b.transactions.iter().map(|t| t).collect(),
-                b as &dyn AbstractStacksBlock,
-            )
+        .flat_map(|block| block.transactions.iter())
+        .filter(|tx| evaluate_stacks_predicate_on_transaction(tx, &chainhook, &Context::empty()))
+        .map(|tx| match &tx.metadata.kind {
+            StacksTransactionKind::ContractDeployment(deployment) => {
+                deployment.contract_identifier.clone()
+            }
+            _ => unreachable!(),
         })
-        .collect();
+        .collect::<Vec<_>>();
+    assert_eq!(actual_contract_identifiers, expected_contract_identifiers);
+}
 
-    let rollback_block_data = fixtures::build_stacks_testnet_block_with_contract_deployment();
-    let rollback_transactions = rollback_block_data.transactions.iter().map(|t| t).collect();
-    let rollback_block: &dyn AbstractStacksBlock = &rollback_block_data;
-    let trigger = StacksTriggerChainhook {
-        chainhook: &chainhook,
-        apply: apply,
-        rollback: vec![(rollback_transactions, rollback_block)],
-    };
+const SMALL_CONTRACT_SOURCE_LEN: usize = 64;
+const LARGE_CONTRACT_SOURCE_LEN: usize = 64_000;
 
-    let proofs = HashMap::new();
-    let ctx = Context {
-        logger: None,
-        tracer: false,
+#[test]
+fn verify_optional_addition_of_mempool_timestamps() {
+    let new_blocks = vec![
+        StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_with_contract_deployment(),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        },
+        StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_with_contract_call(),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        },
+    ];
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks,
+        confirmed_blocks: vec![],
+    });
+    let mut chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: Some(true),
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::BlockHeight(BlockIdentifierIndexRule::HigherThan(0)),
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
     };
-    let occurrence = handle_stacks_hook_action(trigger, &proofs, &ctx).unwrap();
-    if let StacksChainhookOccurrence::File(path, bytes) = occurrence {
-        assert_eq!(path, "./".to_string());
-        let json: JsonValue = serde_json::from_slice(&bytes).unwrap();
-        let obj = json.as_object().unwrap();
-        let actual = serde_json::to_string_pretty(obj).unwrap();
-        let expected = get_expected_occurrence();
-        assert_eq!(expected, actual);
-    } else {
-        panic!("wrong occurrence type");
+    // Only the contract call was seen in the mempool
+    let contract_call_txid = fixtures::get_contract_call_transaction().transaction_identifier;
+    let mut mempool_timestamps = HashMap::new();
+    mempool_timestamps.insert(contract_call_txid.clone(), 1_700_000_000);
+
+    let (triggered, _, _) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, vec![&chainhook], &Context::empty());
+    let result = serialize_stacks_payload_to_json(
+        triggered.into_iter().next().unwrap(),
+        &HashMap::new(),
+        &mempool_timestamps,
+        &Context::empty(),
+    );
+    let mut first_seen_timestamps = vec![];
+    for block in result["apply"].as_array().unwrap() {
+        for transaction in block["transactions"].as_array().unwrap() {
+            let metadata = transaction["metadata"].as_object().unwrap();
+            let txid = transaction["transaction_identifier"]["hash"]
+                .as_str()
+                .unwrap();
+            first_seen_timestamps.push((
+                txid == contract_call_txid.hash,
+                metadata.get("mempool_first_seen_at").cloned(),
+            ));
+        }
     }
+    assert_eq!(
+        first_seen_timestamps,
+        vec![
+            (false, Some(JsonValue::Null)),
+            (true, Some(json!(1_700_000_000))),
+        ]
+    );
+
+    chainhook.include_mempool_timestamps = None;
+    let (triggered, _, _) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, vec![&chainhook], &Context::empty());
+    let result = serialize_stacks_payload_to_json(
+        triggered.into_iter().next().unwrap(),
+        &HashMap::new(),
+        &mempool_timestamps,
+        &Context::empty(),
+    );
+    for block in result["apply"].as_array().unwrap() {
+        for transaction in block["transactions"].as_array().unwrap() {
+            assert_eq!(transaction["metadata"].get("mempool_first_seen_at"), None);
+        }
+    }
+}
+
+#[test]
+fn verify_optional_addition_of_contract_abi() {
+    // "mine" two blocks
+    //  - one contract deploy (which should have a contract abi) and
+    //  - one contract call (which should not)
+    let new_blocks = vec![
+        StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_with_contract_deployment(),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        },
+        StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_with_contract_call(),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        },
+    ];
+    let event: StacksChainEvent =
+        StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+            new_blocks,
+            confirmed_blocks: vec![],
+        });
+    let mut contract_deploy_chainhook = StacksChainhookSpecification {
+        uuid: "contract-deploy".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: Some(true),
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::ContractDeployment(
+            StacksContractDeploymentPredicate::Deployer("*".into()),
+        ),
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
+    };
+    let contract_call_chainhook = StacksChainhookSpecification {
+        uuid: "contract-call".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: Some(true),
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+            contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+            method: "commit-block".to_string(),
+            case_insensitive: None,
+            args: None,
+        }),
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let predicates = vec![&contract_deploy_chainhook, &contract_call_chainhook];
+    let (triggered, _blocks, _) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
+    assert_eq!(triggered.len(), 2);
+
+    for t in triggered.into_iter() {
+        let result = serialize_stacks_payload_to_json(
+            t,
+            &HashMap::new(),
+            &HashMap::new(),
+            &Context::empty(),
+        );
+        let result = result.as_object().unwrap();
+        let uuid = result.get("chainhook").unwrap().get("uuid").unwrap();
+        let apply_blocks = result.get("apply").unwrap();
+        for block in apply_blocks.as_array().unwrap() {
+            let transactions = block.get("transactions").unwrap();
+            for transaction in transactions.as_array().unwrap() {
+                let contract_abi = transaction.get("metadata").unwrap().get("contract_abi");
+                if uuid == "contract-call" {
+                    assert_eq!(contract_abi, None);
+                } else if uuid == "contract-deploy" {
+                    assert!(contract_abi.is_some())
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+    contract_deploy_chainhook.include_contract_abi = Some(false);
+    let predicates = vec![&contract_deploy_chainhook, &contract_call_chainhook];
+    let (triggered, _blocks, _) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
+    assert_eq!(triggered.len(), 2);
+
+    for t in triggered.into_iter() {
+        let result = serialize_stacks_payload_to_json(
+            t,
+            &HashMap::new(),
+            &HashMap::new(),
+            &Context::empty(),
+        );
+        let result = result.as_object().unwrap();
+        let apply_blocks = result.get("apply").unwrap();
+        for block in apply_blocks.as_array().unwrap() {
+            let transactions = block.get("transactions").unwrap();
+            for transaction in transactions.as_array().unwrap() {
+                let contract_abi = transaction.get("metadata").unwrap().get("contract_abi");
+                assert_eq!(contract_abi, None);
+            }
+        }
+    }
+}
+
+#[test]
+fn verify_projection_of_occurrence_fields() {
+    let new_blocks = vec![StacksBlockUpdate {
+        block: fixtures::build_stacks_testnet_block_with_contract_call(),
+        parent_microblocks_to_apply: vec![],
+        parent_microblocks_to_rollback: vec![],
+    }];
+    let event: StacksChainEvent =
+        StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+            new_blocks,
+            confirmed_blocks: vec![],
+        });
+    let chainhook = StacksChainhookSpecification {
+        uuid: "projection".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: None,
+        projection: Some(vec![
+            "apply[].block_identifier.index".to_string(),
+            "apply[].transactions[].transaction_identifier.hash".to_string(),
+            "apply[].transactions[].metadata.success".to_string(),
+            "apply[].transactions[].unknown_field".to_string(),
+            "chainhook.uuid".to_string(),
+        ]),
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+            contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+            method: "commit-block".to_string(),
+            case_insensitive: None,
+            args: None,
+        }),
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let predicates = vec![&chainhook];
+    let (triggered, _blocks, _) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
+    assert_eq!(triggered.len(), 1);
+
+    let trigger = triggered.into_iter().next().unwrap();
+    let block = trigger.apply[0].1;
+    let transaction = trigger.apply[0].0[0];
+    let expected = json!({
+        "apply": [{
+            "block_identifier": { "index": block.get_identifier().index },
+            "transactions": [{
+                "transaction_identifier": { "hash": transaction.transaction_identifier.hash },
+                "metadata": { "success": transaction.metadata.success },
+            }],
+        }],
+        "rollback": [],
+        "chainhook": { "uuid": "projection" },
+    });
+    let result = serialize_stacks_payload_to_json(
+        trigger.clone(),
+        &HashMap::new(),
+        &HashMap::new(),
+        &Context::empty(),
+    );
+    assert_eq!(result, expected);
+
+    let occurrence =
+        handle_stacks_hook_action(trigger, &HashMap::new(), &HashMap::new(), &Context::empty());
+    let Ok(StacksChainhookOccurrence::Data(payload)) = occurrence else {
+        panic!("expected data occurrence");
+    };
+    assert_eq!(payload.projection, Some(expected));
+}
+
+#[test_case(None ; "object identifiers by default")]
+#[test_case(Some(IdentifierFormat::Object) ; "object identifiers")]
+#[test_case(Some(IdentifierFormat::Compact) ; "compact identifiers")]
+fn verify_serialization_of_occurrence_identifiers(identifier_format: Option<IdentifierFormat>) {
+    let new_blocks = vec![StacksBlockUpdate {
+        block: fixtures::build_stacks_testnet_block_with_contract_call(),
+        parent_microblocks_to_apply: vec![],
+        parent_microblocks_to_rollback: vec![],
+    }];
+    let event: StacksChainEvent =
+        StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+            new_blocks,
+            confirmed_blocks: vec![],
+        });
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: identifier_format.clone(),
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+            contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+            method: "commit-block".to_string(),
+            case_insensitive: None,
+            args: None,
+        }),
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let predicates = vec![&chainhook];
+    let (triggered, _blocks, _) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
+    assert_eq!(triggered.len(), 1);
+
+    let trigger = triggered.into_iter().next().unwrap();
+    let block_identifier = trigger.apply[0].1.get_identifier().clone();
+    let parent_block_identifier = trigger.apply[0].1.get_parent_identifier().clone();
+    let transaction_identifier = trigger.apply[0].0[0].transaction_identifier.clone();
+    let result = serialize_stacks_payload_to_json(
+        trigger,
+        &HashMap::new(),
+        &HashMap::new(),
+        &Context::empty(),
+    );
+    let (
+        expected_block_identifier,
+        expected_parent_block_identifier,
+        expected_transaction_identifier,
+    ) = match identifier_format {
+        Some(IdentifierFormat::Compact) => (
+            json!(format!(
+                "{}:{}",
+                block_identifier.index, block_identifier.hash
+            )),
+            json!(format!(
+                "{}:{}",
+                parent_block_identifier.index, parent_block_identifier.hash
+            )),
+            json!(transaction_identifier.hash),
+        ),
+        _ => (
+            json!({ "index": block_identifier.index, "hash": block_identifier.hash }),
+            json!({
+                "index": parent_block_identifier.index,
+                "hash": parent_block_identifier.hash
+            }),
+            json!({ "hash": transaction_identifier.hash }),
+        ),
+    };
+    assert_eq!(
+        result["apply"][0]["block_identifier"],
+        expected_block_identifier
+    );
+    assert_eq!(
+        result["apply"][0]["parent_block_identifier"],
+        expected_parent_block_identifier
+    );
+    assert_eq!(
+        result["apply"][0]["transactions"][0]["transaction_identifier"],
+        expected_transaction_identifier
+    );
+}
+
+#[test]
+fn projection_keeps_array_alignment_and_ignores_unknown_paths() {
+    let payload = json!({
+        "apply": [
+            { "transactions": [{ "txid": "0x01", "fee": 1 }, { "fee": 2 }] },
+            { "transactions": [] },
+        ],
+        "rollback": [],
+    });
+    let paths = vec![
+        "apply[].transactions[].txid".to_string(),
+        "rollback[].transactions".to_string(),
+        "missing.path".to_string(),
+    ];
+    let result = project_json_payload(&payload, &paths, &Context::empty());
+    assert_eq!(
+        result,
+        json!({
+            "apply": [
+                { "transactions": [{ "txid": "0x01" }, null] },
+                { "transactions": [] },
+            ],
+            "rollback": [],
+        })
+    );
+}
+
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+        method: "commit-block".to_string(),
+        case_insensitive: None,
+        args: None,
+    }),
+    1;
+    "ContractCall predicate matches by contract identifier and method"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+        method: "wrong-method".to_string(),
+        case_insensitive: None,
+        args: None,
+    }),
+    0;
+    "ContractCall predicate does not match for wrong method"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.Subnet-V1".into(),
+        method: "commit-block".to_string(),
+        case_insensitive: None,
+        args: None,
+    }),
+    0;
+    "ContractCall predicate compares mixed-case contract names case-sensitively by default"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.Subnet-V1".into(),
+        method: "commit-block".to_string(),
+        case_insensitive: Some(true),
+        args: None,
+    }),
+    1;
+    "ContractCall predicate matches mixed-case contract names when case insensitive"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: ContractIdentifierRule::MatchesRegex {
+            matches_regex: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9\\.SUBNET-v\\d+".to_string(),
+        },
+        method: "commit-block".to_string(),
+        case_insensitive: Some(true),
+        args: None,
+    }),
+    1;
+    "ContractCall predicate matches contract identifier regexes case insensitively"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.Subnet-V1".into(),
+        method: "Commit-Block".to_string(),
+        case_insensitive: Some(true),
+        args: None,
+    }),
+    0;
+    "ContractCall predicate still compares methods case-sensitively"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "wrong-id".into(),
+        method: "commit-block".to_string(),
+        case_insensitive: None,
+        args: None,
+    }),
+    0;
+    "ContractCall predicate does not match for wrong contract identifier"
+)]
+#[test_case(
+    StacksPredicate::Txid(ExactMatchingRule::Equals("0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string())),
+    1;
+    "Txid predicate matches by a transaction's id"
+)]
+#[test_case(
+    StacksPredicate::Txid(ExactMatchingRule::Equals("wrong-id".to_string())),
+    0;
+    "Txid predicate rejects non matching id"
+)]
+#[test_case(
+    StacksPredicate::Nonce(StacksNonceBasedPredicate {
+        sender: Some("ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9".to_string()),
+        nonce: BlockIdentifierIndexRule::Between(4000, 4064),
+    }),
+    1;
+    "Nonce predicate matches sender transactions within an inclusive range"
+)]
+#[test_case(
+    StacksPredicate::Nonce(StacksNonceBasedPredicate {
+        sender: Some("ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9".to_string()),
+        nonce: BlockIdentifierIndexRule::Between(0, 5000),
+    }),
+    2;
+    "Nonce predicate matches all sender transactions within range"
+)]
+#[test_case(
+    StacksPredicate::Nonce(StacksNonceBasedPredicate {
+        sender: None,
+        nonce: BlockIdentifierIndexRule::Equals(33),
+    }),
+    1;
+    "Nonce predicate matches any sender when sender is omitted"
+)]
+#[test_case(
+    StacksPredicate::Nonce(StacksNonceBasedPredicate {
+        sender: Some("wrong-sender".to_string()),
+        nonce: BlockIdentifierIndexRule::Between(0, 5000),
+    }),
+    0;
+    "Nonce predicate does not match transactions from another sender"
+)]
+#[test_case(
+    StacksPredicate::Nonce(StacksNonceBasedPredicate {
+        sender: Some("ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9".to_string()),
+        nonce: BlockIdentifierIndexRule::HigherThan(5000),
+    }),
+    0;
+    "Nonce predicate does not match nonces out of range"
+)]
+fn test_stacks_predicate_contract_call(predicate: StacksPredicate, expected_applies: u64) {
+    // Prepare block
+    let new_blocks = vec![
+        StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_with_contract_call(),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        },
+        StacksBlockUpdate {
+            block: fixtures::build_stacks_testnet_block_with_contract_deployment(),
+            parent_microblocks_to_apply: vec![],
+            parent_microblocks_to_rollback: vec![],
+        },
+    ];
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks,
+        confirmed_blocks: vec![],
+    });
+    // Prepare predicate
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: predicate,
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let predicates = vec![&chainhook];
+    let (triggered, _predicates_evaluated, _predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
+
+    if expected_applies == 0 {
+        assert_eq!(triggered.len(), 0)
+    } else if triggered.len() == 0 {
+        panic!("expected more than one block to be applied, but no predicates were triggered")
+    } else {
+        let actual_applies: u64 = triggered[0].apply.len().try_into().unwrap();
+        assert_eq!(actual_applies, expected_applies);
+    }
+}
+
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: ContractIdentifierRule::MatchesRegex {
+            matches_regex: r"ST[A-Z0-9]+\.subnet-v\d+".to_string(),
+        },
+        method: "commit-block".to_string(),
+        case_insensitive: None,
+        args: None,
+    }),
+    3;
+    "ContractCall predicate matches versioned contracts with a regex"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: ContractIdentifierRule::MatchesRegex {
+            matches_regex: r"ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9\.subnet-v\d+".to_string(),
+        },
+        method: "commit-block".to_string(),
+        case_insensitive: None,
+        args: None,
+    }),
+    2;
+    "ContractCall predicate regex must match the whole contract identifier"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v2".into(),
+        method: "commit-block".to_string(),
+        case_insensitive: None,
+        args: None,
+    }),
+    1;
+    "ContractCall predicate still matches exact contract identifiers"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-*".into(),
+        method: "commit-block".to_string(),
+        case_insensitive: None,
+        args: None,
+    }),
+    3;
+    "ContractCall predicate matches contract identifiers by prefix"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.*".into(),
+        method: "commit-block".to_string(),
+        case_insensitive: None,
+        args: None,
+    }),
+    3;
+    "ContractCall predicate matches every contract of a deployer"
+)]
+#[test_case(
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.subnet-*".into(),
+        method: "commit-block".to_string(),
+        case_insensitive: None,
+        args: None,
+    }),
+    1;
+    "ContractCall predicate prefix does not match other deployers"
+)]
+#[test_case(
+    StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::ContractIdentifier(
+        ContractIdentifierRule::MatchesRegex {
+            matches_regex: r"ST[A-Z0-9]+\.subnet-v\d+".to_string(),
+        },
+    )),
+    3;
+    "ContractDeployment predicate matches versioned contracts with a regex"
+)]
+#[test_case(
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
+        contract_identifier: ContractIdentifierRule::MatchesRegex {
+            matches_regex: r"ST[A-Z0-9]+\.subnet-v\d+".to_string(),
+        },
+        contains: "some-value".to_string(),
+        emitting_method: None,
+    }),
+    3;
+    "PrintEvent predicate matches events of versioned contracts with a regex"
+)]
+fn test_stacks_predicates_matching_contract_identifier_regex(
+    predicate: StacksPredicate,
+    expected_transactions: usize,
+) {
+    predicate.validate().unwrap();
+    let new_blocks = vec![StacksBlockUpdate {
+        block: fixtures::build_stacks_testnet_block_with_contracts(&[
+            "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1",
+            "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v2",
+            "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v2-legacy",
+            "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5.subnet-v3",
+        ]),
+        parent_microblocks_to_apply: vec![],
+        parent_microblocks_to_rollback: vec![],
+    }];
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks,
+        confirmed_blocks: vec![],
+    });
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate,
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let (triggered, _predicates_evaluated, _predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, vec![&chainhook], &Context::empty());
+    let (transactions, _block) = &triggered[0].apply[0];
+    assert_eq!(transactions.len(), expected_transactions);
+}
+
+const NFT_ASSET_IDENTIFIER: &str = "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.collection::token";
+// u999, u1000, u1500, u2000, u2001
+const NFT_UINT_TOKEN_IDS: [&str; 5] = [
+    "0x01000000000000000000000000000003e7",
+    "0x01000000000000000000000000000003e8",
+    "0x01000000000000000000000000000005dc",
+    "0x01000000000000000000000000000007d0",
+    "0x01000000000000000000000000000007d1",
+];
+// 1500
+const NFT_INT_TOKEN_ID: &str = "0x00000000000000000000000000000005dc";
+// "1500"
+const NFT_STRING_TOKEN_ID: &str = "0x0d0000000431353030";
+// Not a clarity value
+const NFT_MALFORMED_TOKEN_ID: &str = "0xff";
+
+#[test_case(
+    Some(NftTokenIdRange { min: 1000, max: 2000 }),
+    vec![NFT_UINT_TOKEN_IDS[1], NFT_UINT_TOKEN_IDS[2], NFT_UINT_TOKEN_IDS[3], NFT_INT_TOKEN_ID];
+    "matches token ids within the range, bounds included"
+)]
+#[test_case(
+    Some(NftTokenIdRange { min: 1500, max: 1500 }),
+    vec![NFT_UINT_TOKEN_IDS[2], NFT_INT_TOKEN_ID];
+    "matches a single token id"
+)]
+#[test_case(
+    Some(NftTokenIdRange { min: 3000, max: 4000 }),
+    vec![];
+    "does not match token ids outside of the range"
+)]
+#[test_case(
+    None,
+    vec![NFT_UINT_TOKEN_IDS[0], NFT_UINT_TOKEN_IDS[1], NFT_UINT_TOKEN_IDS[2], NFT_UINT_TOKEN_IDS[3], NFT_UINT_TOKEN_IDS[4], NFT_INT_TOKEN_ID, NFT_STRING_TOKEN_ID, NFT_MALFORMED_TOKEN_ID];
+    "matches every token id without a range"
+)]
+fn test_stacks_predicate_nft_token_id_range(
+    token_id_range: Option<NftTokenIdRange>,
+    expected_token_ids: Vec<&str>,
+) {
+    let predicate = StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
+        asset_identifier: NFT_ASSET_IDENTIFIER.to_string(),
+        actions: vec!["mint".to_string()],
+        token_id_range,
+        sender: None,
+        recipient: None,
+    });
+    predicate.validate().unwrap();
+    let mut hex_token_ids = NFT_UINT_TOKEN_IDS.to_vec();
+    hex_token_ids.extend([
+        NFT_INT_TOKEN_ID,
+        NFT_STRING_TOKEN_ID,
+        NFT_MALFORMED_TOKEN_ID,
+    ]);
+    let new_blocks = vec![StacksBlockUpdate {
+        block: fixtures::build_stacks_testnet_block_with_nft_mints(
+            NFT_ASSET_IDENTIFIER,
+            &hex_token_ids,
+        ),
+        parent_microblocks_to_apply: vec![],
+        parent_microblocks_to_rollback: vec![],
+    }];
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks,
+        confirmed_blocks: vec![],
+    });
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate,
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let (triggered, _predicates_evaluated, _predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, vec![&chainhook], &Context::empty());
+    if expected_token_ids.is_empty() {
+        assert_eq!(triggered.len(), 0);
+        return;
+    }
+    let (transactions, _block) = &triggered[0].apply[0];
+    let matched_token_ids = transactions
+        .iter()
+        .flat_map(|tx| tx.metadata.receipt.events.iter())
+        .map(|event| match &event.event_payload {
+            StacksTransactionEventPayload::NFTMintEvent(data) => data.hex_asset_identifier.as_str(),
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(matched_token_ids, expected_token_ids);
+}
+
+#[test]
+fn inverted_nft_token_id_range_is_rejected() {
+    let predicate = StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
+        asset_identifier: NFT_ASSET_IDENTIFIER.to_string(),
+        actions: vec!["mint".to_string()],
+        token_id_range: Some(NftTokenIdRange {
+            min: 2000,
+            max: 1000,
+        }),
+        sender: None,
+        recipient: None,
+    });
+    assert!(predicate.validate().is_err());
+}
+
+#[test]
+fn invalid_memo_regex_is_rejected() {
+    let predicate =
+        StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+            principal_type: StacksPrincipalType::Standard,
+            memo: Some(StacksMemoMatchingRule::MatchesRegex(
+                "invoice-[0-9".to_string(),
+            )),
+        });
+    assert!(predicate.validate().is_err());
+}
+
+#[test]
+fn empty_stx_amount_range_is_rejected() {
+    let predicate = StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: Some(1000),
+        amount_less_than: Some(1000),
+        sender: None,
+        recipient: None,
+    });
+    assert!(predicate.validate().is_err());
+}
+
+#[test]
+fn invalid_print_event_regex_is_rejected() {
+    let predicate = StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::MatchesRegex {
+        contract_identifier: "*".into(),
+        regex: "\"status\":\"(active|pending\"".to_string(),
+        emitting_method: None,
+    });
+    assert!(predicate.validate().is_err());
+}
+
+#[test]
+fn contract_identifier_prefix_without_deployer_is_rejected() {
+    let predicate = StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "subnet-*".into(),
+        method: "commit-block".to_string(),
+        case_insensitive: None,
+        args: None,
+    });
+    assert!(predicate.validate().is_err());
+}
+
+#[test]
+fn invalid_contract_identifier_regex_is_rejected_at_registration() {
+    let spec: StacksChainhookFullSpecification = serde_json::from_value(json!({
+        "uuid": "1",
+        "name": "regex",
+        "version": 1,
+        "networks": {
+            "testnet": {
+                "if_this": {
+                    "scope": "contract_call",
+                    "contract_identifier": { "matches_regex": "ST[A-Z0-9+\\.pool" },
+                    "method": "swap"
+                },
+                "then_that": "noop"
+            }
+        }
+    }))
+    .unwrap();
+    let predicate = &spec
+        .networks
+        .get(&StacksNetwork::Testnet)
+        .unwrap()
+        .predicate;
+    assert_eq!(
+        predicate,
+        &StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+            contract_identifier: ContractIdentifierRule::MatchesRegex {
+                matches_regex: "ST[A-Z0-9+\\.pool".to_string(),
+            },
+            method: "swap".to_string(),
+            case_insensitive: None,
+            args: None,
+        })
+    );
+    assert!(predicate.validate().is_err());
+}
+
+#[test_case(false, "(ok true)", 1 ; "matches a call aborted by a post-condition")]
+#[test_case(true, "(ok true)", 0 ; "does not match a successful call")]
+#[test_case(false, "(err u1)", 0 ; "does not match a call aborted by its response")]
+fn test_stacks_predicate_contract_call_post_condition_abort(
+    success: bool,
+    result: &str,
+    expected_applies: u64,
+) {
+    let new_blocks = vec![StacksBlockUpdate {
+        block: fixtures::build_stacks_testnet_block_with_contract_call_result(success, result),
+        parent_microblocks_to_apply: vec![],
+        parent_microblocks_to_rollback: vec![],
+    }];
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks,
+        confirmed_blocks: vec![],
+    });
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::ContractCallPostConditionAbort(
+            StacksContractCallBasedPredicate {
+                contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+                method: "commit-block".to_string(),
+                case_insensitive: None,
+                args: None,
+            },
+        ),
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let predicates = vec![&chainhook];
+    let (triggered, _predicates_evaluated, _predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, predicates, &Context::empty());
+
+    let actual_applies: u64 = triggered
+        .first()
+        .map(|trigger| trigger.apply.len().try_into().unwrap())
+        .unwrap_or(0);
+    assert_eq!(actual_applies, expected_applies);
+}
+
+#[test_case(
+    StacksPredicate::AllOf(StacksPredicateCombination {
+        predicates: vec![build_swap_contract_call("swap"), build_some_value_print_event()],
+    }),
+    vec!["swap"];
+    "AllOf predicate matches a call and a print event of the same transaction"
+)]
+#[test_case(
+    StacksPredicate::AllOf(StacksPredicateCombination {
+        predicates: vec![build_swap_contract_call("add-liquidity"), build_some_value_print_event()],
+    }),
+    vec![];
+    "AllOf predicate does not match a call and a print event of distinct transactions"
+)]
+#[test_case(
+    StacksPredicate::AnyOf(StacksPredicateCombination {
+        predicates: vec![build_swap_contract_call("add-liquidity"), build_some_value_print_event()],
+    }),
+    vec!["swap", "add-liquidity", "remove-liquidity"];
+    "AnyOf predicate matches transactions satisfying either predicate"
+)]
+#[test_case(
+    StacksPredicate::AllOf(StacksPredicateCombination {
+        predicates: vec![
+            build_some_value_print_event(),
+            StacksPredicate::Not(StacksNegatedPredicate {
+                predicate: Box::new(build_swap_contract_call("swap")),
+            }),
+        ],
+    }),
+    vec!["remove-liquidity"];
+    "Not predicate excludes transactions matching the negated predicate"
+)]
+fn test_stacks_combined_predicates(predicate: StacksPredicate, expected_methods: Vec<&str>) {
+    let print_events = vec![StacksTransactionEvent {
+        event_payload: get_test_event_payload_by_type("smart_contract_print_event"),
+        position: StacksTransactionEventPosition { index: 0 },
+    }];
+    let mut block =
+        fixtures::build_stacks_testnet_block_with_methods_emitting_events(&["swap"], &print_events);
+    block.transactions.extend(
+        fixtures::build_stacks_testnet_block_with_methods_emitting_events(&["add-liquidity"], &[])
+            .transactions,
+    );
+    block.transactions.extend(
+        fixtures::build_stacks_testnet_block_with_methods_emitting_events(
+            &["remove-liquidity"],
+            &print_events,
+        )
+        .transactions,
+    );
+    let chainhook = build_replay_specification(predicate);
+    assert!(chainhook.predicate.validate().is_ok());
+
+    let actual_methods = block
+        .transactions
+        .iter()
+        .filter(|tx| evaluate_stacks_predicate_on_transaction(tx, &chainhook, &Context::empty()))
+        .map(|tx| match &tx.metadata.kind {
+            StacksTransactionKind::ContractCall(contract_call) => contract_call.method.as_str(),
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(actual_methods, expected_methods);
+}
+
+#[test]
+fn block_height_predicates_can_not_be_combined() {
+    let predicate = StacksPredicate::AllOf(StacksPredicateCombination {
+        predicates: vec![
+            build_some_value_print_event(),
+            StacksPredicate::BlockHeight(BlockIdentifierIndexRule::HigherThan(100)),
+        ],
+    });
+    assert!(predicate.validate().is_err());
+    let predicate = StacksPredicate::AnyOf(StacksPredicateCombination { predicates: vec![] });
+    assert!(predicate.validate().is_err());
+}
+
+fn build_swap_contract_call(method: &str) -> StacksPredicate {
+    StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+        method: method.to_string(),
+        case_insensitive: None,
+        args: None,
+    })
+}
+
+fn build_some_value_print_event() -> StacksPredicate {
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
+        contract_identifier: "*".into(),
+        contains: "some-value".to_string(),
+        emitting_method: None,
+    })
+}
+
+#[test]
+fn test_parallel_evaluation_preserves_predicates_order() {
+    let new_blocks = vec![StacksBlockUpdate {
+        block: fixtures::build_stacks_testnet_block_with_contract_call(),
+        parent_microblocks_to_apply: vec![],
+        parent_microblocks_to_rollback: vec![],
+    }];
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks,
+        confirmed_blocks: vec![],
+    });
+    // Every third predicate targets the method called in the block.
+    let chainhooks = (0..PARALLEL_EVALUATION_THRESHOLD * 2)
+        .map(|i| StacksChainhookSpecification {
+            uuid: format!("{i}"),
+            owner_uuid: None,
+            name: "".to_string(),
+            network: StacksNetwork::Testnet,
+            version: 1,
+            blocks: None,
+            start_block: None,
+            end_block: None,
+            expire_after_occurrence: None,
+            capture_all_events: None,
+            decode_clarity_values: None,
+            include_contract_abi: None,
+            projection: None,
+            include_mempool_timestamps: None,
+            identifier_format: None,
+            derived_fields: None,
+            max_clarity_value_length: None,
+            trigger_on: None,
+            min_confirmations: None,
+            predicate: StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+                contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+                method: match i % 3 {
+                    0 => "commit-block".to_string(),
+                    _ => "wrong-method".to_string(),
+                },
+                case_insensitive: None,
+                args: None,
+            }),
+            action: HookAction::Noop,
+            enabled: true,
+            expired_at: None,
+        })
+        .collect::<Vec<_>>();
+
+    let (triggered, evaluated, expired) = evaluate_stacks_chainhooks_on_chain_event(
+        &event,
+        chainhooks.iter().collect(),
+        &Context::empty(),
+    );
+
+    let mut expected_triggered = vec![];
+    let mut expected_evaluated = BTreeMap::new();
+    for chainhook in chainhooks.iter() {
+        let (trigger, mut evaluated, _) =
+            evaluate_stacks_chainhook_on_chain_event(&event, chainhook, 0, &Context::empty());
+        if let Some(trigger) = trigger {
+            expected_triggered.push(trigger.chainhook.uuid.as_str());
+        }
+        expected_evaluated.append(&mut evaluated);
+    }
+    let triggered = triggered
+        .iter()
+        .map(|trigger| trigger.chainhook.uuid.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(triggered.len(), chainhooks.len().div_ceil(3));
+    assert_eq!(triggered, expected_triggered);
+    assert_eq!(evaluated, expected_evaluated);
+    assert!(expired.is_empty());
+}
+
+#[test_case(None, 0, false, 3; "without limit")]
+#[test_case(Some(2), 0, false, 2; "limited to two occurrences")]
+#[test_case(Some(5), 0, false, 3; "limit above the number of blocks")]
+#[test_case(Some(3), 2, false, 1; "counting the occurrences of the previous events")]
+#[test_case(Some(2), 0, true, 4; "parent microblocks counted along with their anchor block")]
+fn test_stacks_expire_after_occurrence_within_chain_event(
+    expire_after_occurrence: Option<u64>,
+    tracked_occurrences: u64,
+    with_parent_microblocks: bool,
+    expected_applies: usize,
+) {
+    let new_blocks = (0..3u64)
+        .map(|i| {
+            let mut block = fixtures::build_stacks_testnet_block_with_contract_call();
+            block.parent_block_identifier = BlockIdentifier {
+                index: i,
+                hash: format!("0x{:064x}", i),
+            };
+            block.block_identifier = BlockIdentifier {
+                index: i + 1,
+                hash: format!("0x{:064x}", i + 1),
+            };
+            let mut parent_microblocks_to_apply = vec![];
+            if with_parent_microblocks {
+                // Microblock extending the parent of the block, with its own contract call.
+                let mut microblock =
+                    fixtures::build_stacks_testnet_microblock_with_contract_call(&block);
+                microblock.metadata.anchor_block_identifier = block.parent_block_identifier.clone();
+                microblock.transactions[0].transaction_identifier.hash = format!("0x{:064x}", 0xff);
+                parent_microblocks_to_apply.push(microblock);
+            }
+            StacksBlockUpdate {
+                block,
+                parent_microblocks_to_apply,
+                parent_microblocks_to_rollback: vec![],
+            }
+        })
+        .collect();
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks,
+        confirmed_blocks: vec![],
+    });
+    let chainhook = StacksChainhookSpecification {
+        expire_after_occurrence,
+        ..build_replay_specification(StacksPredicate::ContractCall(
+            StacksContractCallBasedPredicate {
+                contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+                method: "commit-block".to_string(),
+                case_insensitive: None,
+                args: None,
+            },
+        ))
+    };
+
+    let occurrences = HashMap::from([(chainhook.uuid.clone(), tracked_occurrences)]);
+    let (triggered, _predicates_evaluated, _predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event_with_occurrences(
+            &event,
+            vec![&chainhook],
+            &occurrences,
+            &Context::empty(),
+        );
+    assert_eq!(triggered.len(), 1);
+    assert_eq!(triggered[0].apply.len(), expected_applies);
+    assert!(
+        tracked_occurrences + triggered[0].count_occurrences()
+            <= expire_after_occurrence.unwrap_or(u64::MAX)
+    );
+}
+
+const UINT_100_ARG: &str = "0x0100000000000000000000000000000064";
+const TRUE_ARG: &str = "0x03";
+
+#[test_case(None, vec![(0, "0xdfc5ef2cdd71061f64131f804a483cf09a3edae9adbe94b9c8c7e4f22797c3c9")], false, true; "raw argument")]
+#[test_case(None, vec![(0, "0xdead")], false, false; "wrong raw argument")]
+#[test_case(None, vec![(0, "0xdfc5ef2cdd71061f64131f804a483cf09a3edae9adbe94b9c8c7e4f22797c3c9"), (1, "0xdead")], false, false; "all arguments must match")]
+#[test_case(None, vec![(2, "*")], false, true; "wildcard")]
+#[test_case(None, vec![(3, "*")], false, false; "wildcard on a missing argument")]
+#[test_case(Some(vec![UINT_100_ARG, TRUE_ARG]), vec![(0, "100"), (1, "true")], true, true; "decoded arguments")]
+#[test_case(Some(vec![UINT_100_ARG, TRUE_ARG]), vec![(0, "100")], false, false; "decoded value without decode_clarity_values")]
+#[test_case(Some(vec![UINT_100_ARG, TRUE_ARG]), vec![(0, UINT_100_ARG)], false, true; "hex value without decode_clarity_values")]
+#[test_case(None, vec![(2, "0x0000000000000000000000000000000000000000000000000000000000000000")], true, true; "buffers are not mistaken for serialized values")]
+fn test_stacks_contract_call_args(
+    args: Option<Vec<&str>>,
+    expected_args: Vec<(usize, &str)>,
+    decode_clarity_values: bool,
+    matches: bool,
+) {
+    let mut transaction = fixtures::get_contract_call_transaction();
+    if let (Some(args), StacksTransactionKind::ContractCall(contract_call)) =
+        (args, &mut transaction.metadata.kind)
+    {
+        contract_call.args = args.into_iter().map(|arg| arg.to_string()).collect();
+    }
+    let chainhook = StacksChainhookSpecification {
+        decode_clarity_values: Some(decode_clarity_values),
+        ..build_replay_specification(StacksPredicate::ContractCall(
+            StacksContractCallBasedPredicate {
+                contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+                method: "commit-block".to_string(),
+                case_insensitive: None,
+                args: Some(
+                    expected_args
+                        .into_iter()
+                        .map(|(index, equals)| StacksContractCallArgMatch {
+                            index,
+                            equals: equals.to_string(),
+                        })
+                        .collect(),
+                ),
+            },
+        ))
+    };
+
+    assert_eq!(
+        evaluate_stacks_predicate_on_transaction(&transaction, &chainhook, &Context::empty()),
+        matches
+    );
+}
+
+fn build_contract_call_block_updates(heights: &[u64]) -> Vec<StacksBlockUpdate> {
+    heights
+        .iter()
+        .map(|height| {
+            let mut block = fixtures::build_stacks_testnet_block_with_contract_call();
+            block.block_identifier.index = *height;
+            StacksBlockUpdate {
+                block,
+                parent_microblocks_to_apply: vec![],
+                parent_microblocks_to_rollback: vec![],
+            }
+        })
+        .collect()
+}
+
+fn build_contract_call_range_specification(
+    start_block: Option<u64>,
+    end_block: Option<u64>,
+) -> StacksChainhookSpecification {
+    StacksChainhookSpecification {
+        start_block,
+        end_block,
+        ..build_replay_specification(StacksPredicate::ContractCall(
+            StacksContractCallBasedPredicate {
+                contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+                method: "commit-block".to_string(),
+                case_insensitive: None,
+                args: None,
+            },
+        ))
+    }
+}
+
+#[test_case(None, None, vec![10, 11, 12, 13], None; "without bounds")]
+#[test_case(Some(11), None, vec![11, 12, 13], None; "with a start block")]
+#[test_case(None, Some(11), vec![10, 11], Some(12); "with an end block")]
+#[test_case(Some(11), Some(12), vec![11, 12], Some(13); "with start and end blocks")]
+fn test_stacks_chain_event_honors_block_range(
+    start_block: Option<u64>,
+    end_block: Option<u64>,
+    expected_applies: Vec<u64>,
+    expected_expiration: Option<u64>,
+) {
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks: build_contract_call_block_updates(&[10, 11, 12, 13]),
+        confirmed_blocks: vec![],
+    });
+    let chainhook = build_contract_call_range_specification(start_block, end_block);
+
+    let (triggered, _predicates_evaluated, predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, vec![&chainhook], &Context::empty());
+    let applies = triggered[0]
+        .apply
+        .iter()
+        .map(|(_, block)| block.get_identifier().index)
+        .collect::<Vec<_>>();
+    assert_eq!(applies, expected_applies);
+    assert_eq!(
+        predicates_expired
+            .get(chainhook.uuid.as_str())
+            .map(|block_identifier| block_identifier.index),
+        expected_expiration
+    );
+}
+
+#[test]
+fn test_stacks_reorg_honors_block_range() {
+    let event = StacksChainEvent::ChainUpdatedWithReorg(StacksChainUpdatedWithReorgData {
+        blocks_to_rollback: build_contract_call_block_updates(&[10, 11, 12]),
+        blocks_to_apply: build_contract_call_block_updates(&[10, 11, 12]),
+        confirmed_blocks: vec![],
+    });
+    let chainhook = build_contract_call_range_specification(Some(11), Some(11));
+
+    let (triggered, _predicates_evaluated, predicates_expired) =
+        evaluate_stacks_chainhooks_on_chain_event(&event, vec![&chainhook], &Context::empty());
+    fn heights(
+        occurrences: &[(Vec<&StacksTransactionData>, &dyn AbstractStacksBlock)],
+    ) -> Vec<u64> {
+        occurrences
+            .iter()
+            .map(|(_, block)| block.get_identifier().index)
+            .collect()
+    }
+    assert_eq!(heights(&triggered[0].apply), vec![11]);
+    assert_eq!(heights(&triggered[0].rollback), vec![11]);
+    assert_eq!(
+        predicates_expired
+            .get(chainhook.uuid.as_str())
+            .map(|block_identifier| block_identifier.index),
+        Some(12)
+    );
+}
+
+#[test]
+fn test_matching_predicates_on_chain_event() {
+    let event = StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks: build_contract_call_block_updates(&[10, 11]),
+        confirmed_blocks: vec![],
+    });
+    let matching_predicate = build_contract_call_range_specification(None, None);
+    let other_predicate = StacksChainhookSpecification {
+        uuid: "other".into(),
+        ..build_replay_specification(build_swap_contract_call("swap-x-for-y"))
+    };
+    let contract_call_txid = fixtures::build_stacks_testnet_block_with_contract_call()
+        .transactions
+        .last()
+        .unwrap()
+        .transaction_identifier
+        .hash
+        .clone();
+
+    let matches = match_stacks_predicates_on_chain_event(
+        &event,
+        &[other_predicate, matching_predicate],
+        &Context::empty(),
+    );
+
+    assert_eq!(matches.len(), 2);
+    for (predicate_match, height) in matches.iter().zip([10, 11]) {
+        assert_eq!(predicate_match.predicate_uuid, "replay");
+        assert_eq!(predicate_match.matched_block.index, height);
+        assert_eq!(
+            predicate_match.matched_txids,
+            vec![contract_call_txid.clone()]
+        );
+        assert!(!predicate_match.rollback);
+    }
+    let serialized = serde_json::to_value(&matches[0]).unwrap();
+    assert_eq!(serialized["predicate_uuid"], json!("replay"));
+    assert_eq!(serialized["matched_block"]["index"], json!(10));
+    assert_eq!(serialized["matched_txids"], json!([contract_call_txid]));
+}
+
+#[test]
+fn test_evaluating_predicates_on_standardized_blocks() {
+    let block = fixtures::build_stacks_testnet_block_with_contract_call();
+    let contract_call = |method: &str| {
+        StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+            contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+            method: method.to_string(),
+            case_insensitive: None,
+            args: None,
+        })
+    };
+    let matching = StacksChainhookSpecification {
+        uuid: "matching".to_string(),
+        ..build_replay_specification(contract_call("commit-block"))
+    };
+    let not_matching = StacksChainhookSpecification {
+        uuid: "not-matching".to_string(),
+        ..build_replay_specification(contract_call("wrong-method"))
+    };
+    let expired = StacksChainhookSpecification {
+        uuid: "expired".to_string(),
+        end_block: Some(block.block_identifier.index - 1),
+        ..build_replay_specification(contract_call("commit-block"))
+    };
+
+    let (triggered, expired_predicates) = evaluate_stacks_chainhooks_on_blocks(
+        vec![&block],
+        vec![&matching, &not_matching, &expired],
+        &Context::empty(),
+    );
+
+    assert_eq!(triggered.len(), 1);
+    assert_eq!(triggered[0].chainhook.uuid, "matching");
+    assert_eq!(triggered[0].apply.len(), 1);
+    assert_eq!(triggered[0].apply[0].0.len(), 1);
+    assert!(triggered[0].rollback.is_empty());
+    assert_eq!(
+        expired_predicates.get("expired"),
+        Some(&&block.block_identifier)
+    );
+}
+
+#[test]
+fn test_stacks_hook_action_noop() {
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::Txid(ExactMatchingRule::Equals(
+            "0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string(),
+        )),
+        action: HookAction::Noop,
+        enabled: true,
+        expired_at: None,
+    };
+
+    let apply_block_data = fixtures::build_stacks_testnet_block_with_contract_call();
+    let apply_transactions = apply_block_data.transactions.iter().map(|t| t).collect();
+    let apply_blocks: &dyn AbstractStacksBlock = &apply_block_data;
+
+    let rollback_block_data = fixtures::build_stacks_testnet_block_with_contract_deployment();
+    let rollback_transactions = rollback_block_data.transactions.iter().map(|t| t).collect();
+    let rollback_blocks: &dyn AbstractStacksBlock = &apply_block_data;
+    let trigger = StacksTriggerChainhook {
+        chainhook: &chainhook,
+        apply: vec![(apply_transactions, apply_blocks)],
+        rollback: vec![(rollback_transactions, rollback_blocks)],
+    };
+
+    let proofs = HashMap::new();
+    let ctx = Context {
+        logger: None,
+        tracer: false,
+    };
+    let occurrence = handle_stacks_hook_action(trigger, &proofs, &HashMap::new(), &ctx).unwrap();
+    if let StacksChainhookOccurrence::Data(data) = occurrence {
+        assert_eq!(data.apply.len(), 1);
+        assert_eq!(
+            data.apply[0].block_identifier.hash,
+            apply_block_data.block_identifier.hash
+        );
+        assert_eq!(data.rollback.len(), 1);
+        assert_eq!(
+            data.rollback[0].block_identifier.hash,
+            rollback_block_data.block_identifier.hash
+        );
+    } else {
+        panic!("wrong occurrence type");
+    }
+}
+
+#[test]
+fn test_stacks_hook_action_file_append() {
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: Some(true),
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::Txid(ExactMatchingRule::Equals(
+            "0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string(),
+        )),
+        action: HookAction::FileAppend(FileHook {
+            path: "./".to_string(),
+            format: None,
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx: None,
+        }),
+        enabled: true,
+        expired_at: None,
+    };
+    let payloads = get_all_event_payload_types();
+    let apply_blocks = payloads
+        .into_iter()
+        .map(|payload| {
+            fixtures::build_stacks_testnet_block_from_smart_contract_event_data(&vec![
+                StacksTransactionEvent {
+                    event_payload: payload,
+                    position: StacksTransactionEventPosition { index: 0 },
+                },
+            ])
+        })
+        .collect::<Vec<_>>();
+    let apply: Vec<(Vec<&StacksTransactionData>, &dyn AbstractStacksBlock)> = apply_blocks
+        .iter()
+        .map(|b| {
+            (
+                b.transactions.iter().map(|t| t).collect(),
+                b as &dyn AbstractStacksBlock,
+            )
+        })
+        .collect();
+
+    let rollback_block_data = fixtures::build_stacks_testnet_block_with_contract_deployment();
+    let rollback_transactions = rollback_block_data.transactions.iter().map(|t| t).collect();
+    let rollback_block: &dyn AbstractStacksBlock = &rollback_block_data;
+    let trigger = StacksTriggerChainhook {
+        chainhook: &chainhook,
+        apply: apply,
+        rollback: vec![(rollback_transactions, rollback_block)],
+    };
+
+    let proofs = HashMap::new();
+    let ctx = Context {
+        logger: None,
+        tracer: false,
+    };
+    let occurrence = handle_stacks_hook_action(trigger, &proofs, &HashMap::new(), &ctx).unwrap();
+    if let StacksChainhookOccurrence::File(path, _, bytes) = occurrence {
+        assert_eq!(path, "./".to_string());
+        let json: JsonValue = serde_json::from_slice(&bytes).unwrap();
+        let obj = json.as_object().unwrap();
+        let actual = serde_json::to_string_pretty(obj).unwrap();
+        let expected = get_expected_occurrence();
+        assert_eq!(expected, actual);
+    } else {
+        panic!("wrong occurrence type");
+    }
+}
+
+#[test_case(None ; "object identifiers")]
+#[test_case(Some(IdentifierFormat::Compact) ; "compact identifiers")]
+fn test_stacks_hook_action_renders_http_template(identifier_format: Option<IdentifierFormat>) {
+    let txid = "0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8";
+    let mut builder = StacksChainhookSpecification::builder()
+        .uuid("4ecc-4ecc-435b-9948-d5eeca1c3ce6")
+        .network(StacksNetwork::Testnet)
+        .predicate(StacksPredicate::Txid(ExactMatchingRule::Equals(
+            txid.to_string(),
+        )))
+        .action(HookAction::HttpPost(HttpHook {
+            url: "http://localhost:20455/chainhook".to_string(),
+            authorization_header: "".to_string(),
+            batching: None,
+            max_retries: None,
+            initial_backoff_ms: None,
+            backoff_multiplier: None,
+            signing_secret: None,
+            template: Some(
+                r#"{"height": {block_height}, "txid": "{txid}", "predicate": "{predicate_uuid}", "transaction": {event_json}}"#
+                    .to_string(),
+            ),
+            gzip: None,
+            gzip_threshold_bytes: None,
+            include_raw_tx: None,
+        }));
+    if let Some(identifier_format) = identifier_format {
+        builder = builder.identifier_format(identifier_format);
+    }
+    let chainhook = builder.build().unwrap();
+    assert!(chainhook.action.validate().is_ok());
+    let block = fixtures::build_stacks_testnet_block_with_contract_call();
+    let trigger = StacksTriggerChainhook {
+        chainhook: &chainhook,
+        apply: vec![(
+            block.transactions.iter().collect(),
+            &block as &dyn AbstractStacksBlock,
+        )],
+        rollback: vec![],
+    };
+
+    let occurrence =
+        handle_stacks_hook_action(trigger, &HashMap::new(), &HashMap::new(), &Context::empty())
+            .unwrap();
+    let StacksChainhookOccurrence::Http(request) = occurrence else {
+        panic!("wrong occurrence type");
+    };
+    let request = request.build().unwrap();
+    let body: JsonValue =
+        serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["height"], json!(1));
+    assert_eq!(body[0]["txid"], json!(txid));
+    assert_eq!(
+        body[0]["predicate"],
+        json!("4ecc-4ecc-435b-9948-d5eeca1c3ce6")
+    );
+    assert_eq!(
+        body[0]["transaction"]["metadata"]["kind"]["type"],
+        json!("ContractCall")
+    );
+}
+
+#[test_case(r#"{"txid": "{txid}"}"#, json!([{"txid": "0x01"}, {"txid": "0x02"}]) ; "json template")]
+#[test_case("{txid}", json!(["0x01", "0x02"]) ; "plain template")]
+fn test_http_template_renders_transactions_as_array(template: &str, expected: JsonValue) {
+    let payload = json!({
+        "chainhook": { "uuid": "1" },
+        "apply": [{
+            "block_identifier": { "index": 1, "hash": "0xaa" },
+            "transactions": [
+                { "transaction_identifier": { "hash": "0x01" } },
+                { "transaction_identifier": { "hash": "0x02" } },
+            ],
+        }],
+        "rollback": [],
+    });
+    assert_eq!(HttpHook::render_template(template, &payload), expected);
+}
+
+#[test_case(None, 1 ; "rollback is delivered without template")]
+#[test_case(Some("{txid}"), 0 ; "rollback is not delivered with template")]
+fn test_http_template_skips_occurrences_without_applied_transactions(
+    template: Option<&str>,
+    expected_triggers: usize,
+) {
+    let chainhook = StacksChainhookSpecification::builder()
+        .uuid("4ecc-4ecc-435b-9948-d5eeca1c3ce6")
+        .network(StacksNetwork::Testnet)
+        .predicate(StacksPredicate::Txid(ExactMatchingRule::Equals(
+            "0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string(),
+        )))
+        .action(HookAction::HttpPost(HttpHook {
+            url: "http://localhost:20455/chainhook".to_string(),
+            authorization_header: "".to_string(),
+            batching: None,
+            max_retries: None,
+            initial_backoff_ms: None,
+            backoff_multiplier: None,
+            signing_secret: None,
+            template: template.map(|template| template.to_string()),
+            gzip: None,
+            gzip_threshold_bytes: None,
+            include_raw_tx: None,
+        }))
+        .build()
+        .unwrap();
+    let block = fixtures::build_stacks_testnet_block_with_contract_call();
+    let trigger = StacksTriggerChainhook {
+        chainhook: &chainhook,
+        apply: vec![],
+        rollback: vec![(
+            block.transactions.iter().collect(),
+            &block as &dyn AbstractStacksBlock,
+        )],
+    };
+    assert_eq!(trigger.split_by_trigger_on().len(), expected_triggers);
+}
+
+#[test_case(r#"{"txid": "{txid}", "height": {block_height}}"#, true ; "json template")]
+#[test_case("{predicate_uuid} {event_json}", true ; "plain template")]
+#[test_case("{txid} {block_hash}", false ; "unknown placeholder")]
+fn test_http_template_placeholders_are_validated(template: &str, expected_valid: bool) {
+    let action = HookAction::HttpPost(HttpHook {
+        url: "http://localhost:20455/chainhook".to_string(),
+        authorization_header: "".to_string(),
+        batching: None,
+        max_retries: None,
+        initial_backoff_ms: None,
+        backoff_multiplier: None,
+        signing_secret: None,
+        template: Some(template.to_string()),
+        gzip: None,
+        gzip_threshold_bytes: None,
+        include_raw_tx: None,
+    });
+    assert_eq!(action.validate().is_ok(), expected_valid);
+}
+
+#[test]
+fn test_stacks_hook_action_injects_derived_fields() {
+    let asset_identifier = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token::cbtc";
+    let mut derived_fields = BTreeMap::new();
+    derived_fields.insert(
+        "notional".to_string(),
+        DerivedFieldRule::Multiply {
+            source: "/metadata/receipt/events/0/data/amount".to_string(),
+            factor: 0.5,
+        },
+    );
+    derived_fields.insert(
+        "symbol".to_string(),
+        DerivedFieldRule::Lookup {
+            source: "/metadata/receipt/events/0/data/asset_identifier".to_string(),
+            mapping: BTreeMap::from([(asset_identifier.to_string(), "CBTC".to_string())]),
+        },
+    );
+    derived_fields.insert(
+        "missing".to_string(),
+        DerivedFieldRule::Multiply {
+            source: "/metadata/receipt/events/1/data/amount".to_string(),
+            factor: 0.5,
+        },
+    );
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: None,
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: Some(derived_fields),
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
+            asset_identifier: asset_identifier.to_string(),
+            actions: vec!["transfer".to_string()],
+            emitting_method: None,
+            sender: None,
+            recipient: None,
+        }),
+        action: HookAction::FileAppend(FileHook {
+            path: "./".to_string(),
+            format: None,
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx: None,
+        }),
+        enabled: true,
+        expired_at: None,
+    };
+    let block = fixtures::build_stacks_testnet_block_from_smart_contract_event_data(&vec![
+        StacksTransactionEvent {
+            event_payload: StacksTransactionEventPayload::FTTransferEvent(FTTransferEventData {
+                asset_class_identifier: asset_identifier.to_string(),
+                sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+                recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+                amount: "2500".to_string(),
+            }),
+            position: StacksTransactionEventPosition { index: 0 },
+        },
+    ]);
+    let trigger = StacksTriggerChainhook {
+        chainhook: &chainhook,
+        apply: vec![(
+            block.transactions.iter().collect(),
+            &block as &dyn AbstractStacksBlock,
+        )],
+        rollback: vec![],
+    };
+
+    let occurrence =
+        handle_stacks_hook_action(trigger, &HashMap::new(), &HashMap::new(), &Context::empty())
+            .unwrap();
+    let StacksChainhookOccurrence::File(_, _, bytes) = occurrence else {
+        panic!("wrong occurrence type");
+    };
+    let json: JsonValue = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        json["apply"][0]["transactions"][0]["derived"],
+        json!({ "notional": 1250.0, "symbol": "CBTC" })
+    );
+}
+
+#[test]
+fn test_stacks_hook_action_truncates_large_clarity_values() {
+    let contract_identifier = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.oracle";
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values: Some(true),
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: Some(16),
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::Txid(ExactMatchingRule::Equals(
+            "0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string(),
+        )),
+        action: HookAction::FileAppend(FileHook {
+            path: "./".to_string(),
+            format: None,
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx: None,
+        }),
+        enabled: true,
+        expired_at: None,
+    };
+    // (buff 100) filled with 0xab, followed by the (string-ascii 2) "ok".
+    let large_buffer = format!("0x0200000064{}", "ab".repeat(100));
+    let small_string = "0x0d000000026f6b".to_string();
+    let block = fixtures::build_stacks_testnet_block_from_smart_contract_event_data(
+        &[large_buffer, small_string]
+            .into_iter()
+            .enumerate()
+            .map(|(index, hex_value)| StacksTransactionEvent {
+                event_payload: StacksTransactionEventPayload::SmartContractEvent(
+                    SmartContractEventData {
+                        contract_identifier: contract_identifier.to_string(),
+                        topic: "print".to_string(),
+                        hex_value,
+                    },
+                ),
+                position: StacksTransactionEventPosition {
+                    index: index as u32,
+                },
+            })
+            .collect::<Vec<_>>(),
+    );
+    let trigger = StacksTriggerChainhook {
+        chainhook: &chainhook,
+        apply: vec![(
+            block.transactions.iter().collect(),
+            &block as &dyn AbstractStacksBlock,
+        )],
+        rollback: vec![],
+    };
+
+    let occurrence =
+        handle_stacks_hook_action(trigger, &HashMap::new(), &HashMap::new(), &Context::empty())
+            .unwrap();
+    let StacksChainhookOccurrence::File(_, _, bytes) = occurrence else {
+        panic!("wrong occurrence type");
+    };
+    let json: JsonValue = serde_json::from_slice(&bytes).unwrap();
+    let events = &json["apply"][0]["transactions"][0]["metadata"]["receipt"]["events"];
+    assert_eq!(
+        events[0]["data"]["value"],
+        json!(format!("0x{}…(truncated 186 bytes)", "ab".repeat(7)))
+    );
+    assert_eq!(events[1]["data"]["value"], json!("ok"));
+}
+
+#[test_case(Some(true), true; "contract call arguments are decoded")]
+#[test_case(None, false; "contract call arguments are left encoded by default")]
+fn test_stacks_hook_action_decodes_contract_call_args(
+    decode_clarity_values: Option<bool>,
+    expect_decoded_args: bool,
+) {
+    let chainhook = StacksChainhookSpecification {
+        uuid: "".to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        network: StacksNetwork::Testnet,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expire_after_occurrence: None,
+        capture_all_events: None,
+        decode_clarity_values,
+        include_contract_abi: None,
+        projection: None,
+        include_mempool_timestamps: None,
+        identifier_format: None,
+        derived_fields: None,
+        max_clarity_value_length: None,
+        trigger_on: None,
+        min_confirmations: None,
+        predicate: StacksPredicate::Txid(ExactMatchingRule::Equals(
+            "0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string(),
+        )),
+        action: HookAction::FileAppend(FileHook {
+            path: "./".to_string(),
+            format: None,
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx: None,
+        }),
+        enabled: true,
+        expired_at: None,
+    };
+    // u100 and the (string-ascii 2) "ok", followed by an argument that is not a serialized value.
+    let args = vec![
+        "0x0100000000000000000000000000000064".to_string(),
+        "0x0d000000026f6b".to_string(),
+        "0xdfc5ef2cdd71061f64131f804a483cf09a3edae9adbe94b9c8c7e4f22797c3c9".to_string(),
+    ];
+    let mut block = fixtures::build_stacks_testnet_block_with_contract_call();
+    if let StacksTransactionKind::ContractCall(ref mut contract_call) =
+        block.transactions[0].metadata.kind
+    {
+        contract_call.args = args.clone();
+    }
+    let trigger = StacksTriggerChainhook {
+        chainhook: &chainhook,
+        apply: vec![(
+            block.transactions.iter().collect(),
+            &block as &dyn AbstractStacksBlock,
+        )],
+        rollback: vec![],
+    };
+
+    let occurrence =
+        handle_stacks_hook_action(trigger, &HashMap::new(), &HashMap::new(), &Context::empty())
+            .unwrap();
+    let StacksChainhookOccurrence::File(_, _, bytes) = occurrence else {
+        panic!("wrong occurrence type");
+    };
+    let json: JsonValue = serde_json::from_slice(&bytes).unwrap();
+    let contract_call = &json["apply"][0]["transactions"][0]["metadata"]["kind"]["data"];
+    assert_eq!(contract_call["args"], json!(args));
+    if expect_decoded_args {
+        assert_eq!(contract_call["decoded_args"], json!([100, "ok", args[2]]));
+    } else {
+        assert!(contract_call.get("decoded_args").is_none());
+    }
+}
+
+#[test_case(None, vec![1]; "only the matching events are attached by default")]
+#[test_case(Some(false), vec![1]; "only the matching events are attached")]
+#[test_case(Some(true), vec![0, 1, 2]; "all the events are attached when captured")]
+fn test_stacks_hook_action_captures_events(
+    capture_all_events: Option<bool>,
+    expected_positions: Vec<u64>,
+) {
+    let asset_identifier = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token::cbtc";
+    let mut builder = StacksChainhookSpecification::builder()
+        .uuid("capture")
+        .network(StacksNetwork::Testnet)
+        .predicate(StacksPredicate::FtEvent(StacksFtEventBasedPredicate {
+            asset_identifier: asset_identifier.to_string(),
+            actions: vec!["transfer".to_string()],
+            emitting_method: None,
+            sender: None,
+            recipient: None,
+        }))
+        .action(HookAction::FileAppend(FileHook {
+            path: "./".to_string(),
+            format: None,
+            max_size_bytes: None,
+            max_lines: None,
+            include_raw_tx: None,
+        }));
+    if let Some(capture_all_events) = capture_all_events {
+        builder = builder.capture_all_events(capture_all_events);
+    }
+    let chainhook = builder.build().unwrap();
+    let block = fixtures::build_stacks_testnet_block_from_smart_contract_event_data(&vec![
+        StacksTransactionEvent {
+            event_payload: StacksTransactionEventPayload::SmartContractEvent(
+                SmartContractEventData {
+                    contract_identifier: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.cbtc-token"
+                        .to_string(),
+                    topic: "print".to_string(),
+                    hex_value: "0x0d000000026f6b".to_string(),
+                },
+            ),
+            position: StacksTransactionEventPosition { index: 0 },
+        },
+        StacksTransactionEvent {
+            event_payload: StacksTransactionEventPayload::FTTransferEvent(FTTransferEventData {
+                asset_class_identifier: asset_identifier.to_string(),
+                sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+                recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+                amount: "2500".to_string(),
+            }),
+            position: StacksTransactionEventPosition { index: 1 },
+        },
+        StacksTransactionEvent {
+            event_payload: StacksTransactionEventPayload::STXTransferEvent(STXTransferEventData {
+                sender: "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5".to_string(),
+                recipient: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+                amount: "1000".to_string(),
+                memo: None,
+            }),
+            position: StacksTransactionEventPosition { index: 2 },
+        },
+    ]);
+    let transactions = block
+        .transactions
+        .iter()
+        .filter(|tx| evaluate_stacks_predicate_on_transaction(tx, &chainhook, &Context::empty()))
+        .collect::<Vec<_>>();
+    assert_eq!(transactions.len(), 1);
+    let trigger = StacksTriggerChainhook {
+        chainhook: &chainhook,
+        apply: vec![(transactions, &block as &dyn AbstractStacksBlock)],
+        rollback: vec![],
+    };
+
+    let occurrence =
+        handle_stacks_hook_action(trigger, &HashMap::new(), &HashMap::new(), &Context::empty())
+            .unwrap();
+    let StacksChainhookOccurrence::File(_, _, bytes) = occurrence else {
+        panic!("wrong occurrence type");
+    };
+    let json: JsonValue = serde_json::from_slice(&bytes).unwrap();
+    let positions = json["apply"][0]["transactions"][0]["metadata"]["receipt"]["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["position"]["index"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(positions, expected_positions);
+}
+
+#[test_case(None, true, vec![(1, 1)], 1; "rollbacks are attached to the applies by default")]
+#[test_case(Some(TriggerOn::Apply), false, vec![(1, 0)], 1; "applies trigger")]
+#[test_case(Some(TriggerOn::Rollback), true, vec![(0, 1)], 1; "only rollbacks trigger")]
+#[test_case(Some(TriggerOn::Rollback), false, vec![], 0; "applies without rollback do not trigger")]
+#[test_case(Some(TriggerOn::Both), true, vec![(1, 0), (0, 1)], 1; "applies and rollbacks trigger separately")]
+fn test_stacks_hook_action_trigger_on(
+    trigger_on: Option<TriggerOn>,
+    with_rollback: bool,
+    expected_occurrences: Vec<(usize, usize)>,
+    expected_counted_occurrences: u64,
+) {
+    let contract_identifier = "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1";
+    let mut builder = StacksChainhookSpecification::builder()
+        .uuid("trigger-on")
+        .network(StacksNetwork::Testnet)
+        .predicate(StacksPredicate::ContractCall(
+            StacksContractCallBasedPredicate {
+                contract_identifier: contract_identifier.into(),
+                method: "commit-block".to_string(),
+                case_insensitive: None,
+                args: None,
+            },
+        ));
+    if let Some(trigger_on) = trigger_on {
+        builder = builder.trigger_on(trigger_on);
+    }
+    let chainhook = builder.build().unwrap();
+    let applied_block = fixtures::build_stacks_testnet_block_with_contracts(&[contract_identifier]);
+    let rolled_back_block =
+        fixtures::build_stacks_testnet_block_with_contracts(&[contract_identifier]);
+    let rollback = match with_rollback {
+        true => vec![(
+            rolled_back_block.transactions.iter().collect(),
+            &rolled_back_block as &dyn AbstractStacksBlock,
+        )],
+        false => vec![],
+    };
+    let trigger = StacksTriggerChainhook {
+        chainhook: &chainhook,
+        apply: vec![(
+            applied_block.transactions.iter().collect(),
+            &applied_block as &dyn AbstractStacksBlock,
+        )],
+        rollback,
+    };
+
+    let triggers = trigger.split_by_trigger_on();
+    // Occurrences counted toward `expire_after_occurrence`: a reorg is only counted once.
+    let counted_occurrences = triggers
+        .iter()
+        .map(|trigger| trigger.count_occurrences())
+        .sum::<u64>();
+    assert_eq!(counted_occurrences, expected_counted_occurrences);
+    let occurrences = triggers
+        .into_iter()
+        .map(|trigger| {
+            let occurrence = handle_stacks_hook_action(
+                trigger,
+                &HashMap::new(),
+                &HashMap::new(),
+                &Context::empty(),
+            )
+            .unwrap();
+            let StacksChainhookOccurrence::Data(payload) = occurrence else {
+                panic!("wrong occurrence type");
+            };
+            (payload.apply.len(), payload.rollback.len())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(occurrences, expected_occurrences);
+}
+
+#[test_case(Some(1_000), Some(2_000), 1_500, true; "matches blocks within the window")]
+#[test_case(Some(1_000), Some(2_000), 2_500, false; "ignores blocks outside of the window")]
+#[test_case(Some(1_000), None, 1_000, false; "excludes the after bound")]
+#[test_case(Some(1_000), None, 1_001, true; "matches blocks right after the after bound")]
+#[test_case(None, Some(2_000), 2_000, false; "excludes the before bound")]
+#[test_case(None, Some(2_000), 1_999, true; "matches blocks right before the before bound")]
+#[test_case(None, Some(2_000), 0, false; "ignores blocks without timestamp")]
+fn test_stacks_predicate_block_time(
+    after: Option<u64>,
+    before: Option<u64>,
+    timestamp: i64,
+    expected_match: bool,
+) {
+    let chainhook = StacksChainhookSpecification::builder()
+        .uuid("block-time")
+        .network(StacksNetwork::Testnet)
+        .predicate(StacksPredicate::BlockTime(TimeRange { after, before }))
+        .build()
+        .unwrap();
+    let mut block = fixtures::build_stacks_testnet_block_with_contracts(&[
+        "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1",
+    ]);
+    block.timestamp = timestamp;
+
+    let (triggered, _expired_predicates) =
+        evaluate_stacks_chainhooks_on_blocks(vec![&block], vec![&chainhook], &Context::empty());
+
+    assert_eq!(triggered.len(), expected_match as usize);
+    if expected_match {
+        let (transactions, _block) = &triggered[0].apply[0];
+        assert_eq!(transactions.len(), block.transactions.len());
+    }
+}
+
+#[test_case(TimeRange { after: None, before: None }; "without bounds")]
+#[test_case(TimeRange { after: Some(2_000), before: Some(1_000) }; "with inverted bounds")]
+fn invalid_block_time_range_is_rejected(range: TimeRange) {
+    assert!(StacksPredicate::BlockTime(range).validate().is_err());
+}
+
+#[test]
+fn block_time_predicates_can_not_be_combined() {
+    let predicate = StacksPredicate::AllOf(StacksPredicateCombination {
+        predicates: vec![
+            build_some_value_print_event(),
+            StacksPredicate::BlockTime(TimeRange {
+                after: Some(1_000),
+                before: None,
+            }),
+        ],
+    });
+    assert_eq!(
+        predicate.validate(),
+        Err("block_time predicates can not be combined with all_of / any_of / not".to_string())
+    );
+}
+
+fn build_burn_then_mint_sequence(asset_identifier: &str) -> StacksPredicate {
+    StacksPredicate::EventSequence(StacksEventSequenceBasedPredicate {
+        events: vec![
+            StacksEventConstraint {
+                event_type: StacksEventType::FtEvent,
+                action: Some("burn".to_string()),
+                asset_identifier: Some(asset_identifier.to_string()),
+            },
+            StacksEventConstraint {
+                event_type: StacksEventType::FtEvent,
+                action: Some("mint".to_string()),
+                asset_identifier: Some(asset_identifier.to_string()),
+            },
+        ],
+    })
+}
+
+fn build_anonymous_contract_call_specification(
+    uuid: &str,
+    method: &str,
+) -> StacksChainhookFullSpecification {
+    let mut networks = BTreeMap::new();
+    networks.insert(
+        StacksNetwork::Testnet,
+        StacksChainhookNetworkSpecification {
+            start_block: None,
+            end_block: None,
+            blocks: None,
+            expire_after_occurrence: None,
+            capture_all_events: None,
+            decode_clarity_values: None,
+            include_contract_abi: None,
+            projection: None,
+            include_mempool_timestamps: None,
+            identifier_format: None,
+            derived_fields: None,
+            max_clarity_value_length: None,
+            trigger_on: None,
+            min_confirmations: None,
+            predicate: StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
+                contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.subnet-v1".into(),
+                method: method.to_string(),
+                case_insensitive: None,
+                args: None,
+            }),
+            action: HookAction::Noop,
+        },
+    );
+    StacksChainhookFullSpecification {
+        uuid: uuid.to_string(),
+        owner_uuid: None,
+        name: "".to_string(),
+        version: 1,
+        networks,
+    }
+}
+
+#[test]
+fn test_anonymous_specifications_derive_stable_uuids() {
+    let derive = |uuid: &str, method: &str| {
+        build_anonymous_contract_call_specification(uuid, method)
+            .into_selected_network_specification(&StacksNetwork::Testnet)
+            .unwrap()
+            .uuid
+    };
+
+    let uuid = derive("", "commit-block");
+    assert_eq!(uuid.len(), 36);
+    assert_eq!(uuid, derive("", "commit-block"));
+    assert_ne!(uuid, derive("", "register-new-block"));
+    assert_eq!(
+        "1f0c1a53-2e6e-4d76-95f5-b8a3c6cb7c1d",
+        derive("1f0c1a53-2e6e-4d76-95f5-b8a3c6cb7c1d", "commit-block")
+    );
+}
+
+#[test_case(DuplicateUuidPolicy::Reject, None ; "reject policy refuses the duplicate")]
+#[test_case(DuplicateUuidPolicy::Suffix, Some("1f0c1a53-2e6e-4d76-95f5-b8a3c6cb7c1d-1") ; "suffix policy renames the duplicate")]
+fn test_duplicate_uuid_policy(policy: DuplicateUuidPolicy, expected_uuid: Option<&str>) {
+    let uuid = "1f0c1a53-2e6e-4d76-95f5-b8a3c6cb7c1d";
+    let networks = (&BitcoinNetwork::Testnet, &StacksNetwork::Testnet);
+    let mut config = ChainhookConfig::new();
+    config.duplicate_uuid_policy = policy;
+
+    let first = config
+        .register_full_specification(
+            networks,
+            ChainhookFullSpecification::Stacks(build_anonymous_contract_call_specification(
+                uuid,
+                "commit-block",
+            )),
+        )
+        .unwrap();
+    assert_eq!(first.uuid(), uuid);
+
+    let second = config.register_full_specification(
+        networks,
+        ChainhookFullSpecification::Stacks(build_anonymous_contract_call_specification(
+            uuid,
+            "register-new-block",
+        )),
+    );
+    match expected_uuid {
+        Some(expected_uuid) => assert_eq!(second.unwrap().uuid(), expected_uuid),
+        None => assert!(second.is_err()),
+    }
+    assert_eq!(
+        config.stacks_chainhooks.len(),
+        if expected_uuid.is_some() { 2 } else { 1 }
+    );
+    assert_eq!(config.stacks_chainhooks[0].uuid, uuid);
+}
+
+fn get_event_logs_indexer_config() -> IndexerConfig {
+    IndexerConfig {
+        bitcoin_network: BitcoinNetwork::Regtest,
+        stacks_network: StacksNetwork::Testnet,
+        bitcoind_rpc_url: "http://localhost:18443".to_string(),
+        bitcoind_rpc_username: "devnet".to_string(),
+        bitcoind_rpc_password: "devnet".to_string(),
+        bitcoin_block_signaling: BitcoinBlockSignaling::Stacks(
+            StacksNodeConfig::default_localhost(20445),
+        ),
+        bitcoin_max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
+    }
+}
+
+fn get_event_logs_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/chainhooks/tests/fixtures/stacks/testnet/event_logs")
+}
+
+fn build_replay_specification(predicate: StacksPredicate) -> StacksChainhookSpecification {
+    StacksChainhookSpecification::builder()
+        .uuid("replay")
+        .name("")
+        .network(StacksNetwork::Testnet)
+        .predicate(predicate)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_stacks_specification_builder_defaults() {
+    let predicate = StacksPredicate::Txid(ExactMatchingRule::Equals(
+        "0xb92c2ade84a8b85f4c72170680ae42e65438aea4db72ba4b2d6a6960f4141ce8".to_string(),
+    ));
+    let spec = StacksChainhookSpecification::builder()
+        .uuid("builder")
+        .network(StacksNetwork::Testnet)
+        .predicate(predicate.clone())
+        .start_block(10)
+        .end_block(20)
+        .build()
+        .unwrap();
+    assert_eq!(
+        spec,
+        StacksChainhookSpecification {
+            uuid: "builder".to_string(),
+            owner_uuid: None,
+            name: "builder".to_string(),
+            network: StacksNetwork::Testnet,
+            version: 1,
+            blocks: None,
+            start_block: Some(10),
+            end_block: Some(20),
+            expire_after_occurrence: None,
+            capture_all_events: None,
+            decode_clarity_values: None,
+            include_contract_abi: None,
+            projection: None,
+            include_mempool_timestamps: None,
+            identifier_format: None,
+            derived_fields: None,
+            max_clarity_value_length: None,
+            trigger_on: None,
+            min_confirmations: None,
+            predicate,
+            action: HookAction::Noop,
+            enabled: true,
+            expired_at: None,
+        }
+    );
+}
+
+#[test_case(
+    StacksChainhookSpecification::builder()
+        .network(StacksNetwork::Testnet)
+        .predicate(StacksPredicate::Txid(ExactMatchingRule::Equals("0x00".into()))),
+    "missing field `uuid`";
+    "missing uuid"
+)]
+#[test_case(
+    StacksChainhookSpecification::builder()
+        .uuid("builder")
+        .predicate(StacksPredicate::Txid(ExactMatchingRule::Equals("0x00".into()))),
+    "missing field `network`";
+    "missing network"
+)]
+#[test_case(
+    StacksChainhookSpecification::builder()
+        .uuid("builder")
+        .network(StacksNetwork::Testnet),
+    "missing field `predicate`";
+    "missing predicate"
+)]
+#[test_case(
+    StacksChainhookSpecification::builder()
+        .uuid("builder")
+        .network(StacksNetwork::Testnet)
+        .predicate(StacksPredicate::Txid(ExactMatchingRule::Equals("0x00".into())))
+        .start_block(20)
+        .end_block(10),
+    "Chainhook specification field `end_block` should be greater than `start_block`.";
+    "inverted block range"
+)]
+fn test_stacks_specification_builder_errors(
+    builder: StacksChainhookSpecificationBuilder,
+    expected_error: &str,
+) {
+    assert_eq!(builder.build(), Err(expected_error.to_string()));
+}
+
+#[test]
+fn test_loading_stacks_chain_events_from_event_logs() {
+    let chain_events = load_stacks_chain_events_from_event_logs(
+        &get_event_logs_dir(),
+        &get_event_logs_indexer_config(),
+        &StacksBlockCache::new(DEFAULT_STACKS_BLOCK_CACHE_CAPACITY),
+        &Context::empty(),
+    )
+    .unwrap();
+    let applied_blocks = chain_events
+        .iter()
+        .flat_map(|chain_event| match chain_event {
+            StacksChainEvent::ChainUpdatedWithBlocks(data) => data
+                .new_blocks
+                .iter()
+                .map(|update| update.block.block_identifier.index)
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(applied_blocks, vec![1, 2, 3]);
+}
+
+#[test_case(
+    StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+        actions: vec!["transfer".to_string()],
+        amount_greater_than: None,
+        amount_less_than: None,
+        sender: None,
+        recipient: None,
+    }),
+    vec![1, 3];
+    "StxEvent predicate matches transfers of replayed blocks"
+)]
+#[test_case(
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.vault".into(),
+        contains: "deposit".to_string(),
+        emitting_method: None,
+    }),
+    vec![2];
+    "PrintEvent predicate matches print events of replayed blocks"
+)]
+#[test_case(
+    StacksPredicate::PrintEvent(StacksPrintEventBasedPredicate::Contains {
+        contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.vault".into(),
+        contains: "withdraw".to_string(),
+        emitting_method: None,
+    }),
+    vec![];
+    "PrintEvent predicate does not match replayed blocks"
+)]
+fn test_replaying_stacks_event_logs(predicate: StacksPredicate, expected_blocks: Vec<u64>) {
+    let chainhook = build_replay_specification(predicate);
+    let occurrences = replay_stacks_event_logs(
+        &get_event_logs_dir(),
+        &get_event_logs_indexer_config(),
+        vec![&chainhook],
+        &StacksBlockCache::new(DEFAULT_STACKS_BLOCK_CACHE_CAPACITY),
+        &Context::empty(),
+    )
+    .unwrap();
+    let matched_blocks = occurrences
+        .iter()
+        .flat_map(|occurrence| {
+            assert_eq!(occurrence.chainhook.uuid, "replay");
+            occurrence
+                .apply
+                .iter()
+                .map(|apply| apply.block_identifier.index)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(matched_blocks, expected_blocks);
+}
+
+#[test_case(DEFAULT_STACKS_BLOCK_CACHE_CAPACITY, 3; "blocks are standardized once across replays")]
+#[test_case(1, 6; "blocks evicted from the cache are standardized again")]
+fn test_replaying_stacks_event_logs_reuses_cached_blocks(
+    capacity: usize,
+    expected_standardizations: u64,
+) {
+    let transfers =
+        build_replay_specification(StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+            actions: vec!["transfer".to_string()],
+            amount_greater_than: None,
+            amount_less_than: None,
+            sender: None,
+            recipient: None,
+        }));
+    let deposits = build_replay_specification(StacksPredicate::PrintEvent(
+        StacksPrintEventBasedPredicate::Contains {
+            contract_identifier: "ST13F481SBR0R7Z6NMMH8YV2FJJYXA5JPA0AD3HP9.vault".into(),
+            contains: "deposit".to_string(),
+            emitting_method: None,
+        },
+    ));
+    let block_cache = StacksBlockCache::new(capacity);
+    let mut matched_blocks = vec![];
+    for chainhook in [&transfers, &deposits] {
+        let occurrences = replay_stacks_event_logs(
+            &get_event_logs_dir(),
+            &get_event_logs_indexer_config(),
+            vec![chainhook],
+            &block_cache,
+            &Context::empty(),
+        )
+        .unwrap();
+        matched_blocks.push(
+            occurrences
+                .iter()
+                .flat_map(|occurrence| occurrence.apply.iter())
+                .map(|apply| apply.block_identifier.index)
+                .collect::<Vec<_>>(),
+        );
+    }
+    assert_eq!(matched_blocks, vec![vec![1, 3], vec![2]]);
+    assert_eq!(block_cache.insertions(), expected_standardizations);
+}
+
+#[test_case(json!({"scope": "block_height", "between": [100, 200]}), "Block height between 100 and 200"; "block height")]
+#[test_case(json!({"scope": "block_time", "after": 1700000000}), "Block time after 1700000000"; "block time")]
+#[test_case(json!({"scope": "contract_deployment", "deployer": "*"}), "Any contract deployment"; "any contract deployment")]
+#[test_case(json!({"scope": "contract_deployment", "implement_trait": "sip10"}), "Contract deployment implementing SIP-010"; "contract deployment implementing trait")]
+#[test_case(json!({"scope": "contract_deployment", "source_contains": "(as-contract"}), "Contract deployment with a source containing \"(as-contract\""; "contract deployment source contains")]
+#[test_case(json!({"scope": "contract_call", "contract_identifier": "SP000000000000000000002Q6VF78.pox", "method": "stack-stx"}), "Call of stack-stx on SP000000000000000000002Q6VF78.pox"; "contract call")]
+#[test_case(json!({"scope": "print_event", "contract_identifier": {"matches_regex": "SP[A-Z0-9]+\\.pool-v\\d+"}, "contains": "swap"}), "Print event of contracts matching /SP[A-Z0-9]+\\.pool-v\\d+/ containing \"swap\""; "print event contains")]
+#[test_case(json!({"scope": "print_event", "contract_identifier": "*", "event_name": "deposit", "emitting_method": "deposit"}), "Print event deposit of any contract emitted by deposit"; "print event name")]
+#[test_case(json!({"scope": "ft_event", "asset_identifier": "asset-id", "actions": ["transfer"]}), "FT transfer of asset-id"; "ft event")]
+#[test_case(json!({"scope": "nft_event", "asset_identifier": "asset-id", "actions": ["mint", "burn"], "token_id_range": {"min": 1, "max": 10}}), "NFT mint/burn of asset-id with token id between 1 and 10"; "nft event")]
+#[test_case(json!({"scope": "stx_event", "actions": ["lock"]}), "STX lock"; "stx event")]
+#[test_case(json!({"scope": "stx_event", "actions": ["transfer"], "sender": "*", "recipient": "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM"}), "STX transfer to ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM"; "stx event with recipient")]
+#[test_case(json!({"scope": "txid", "equals": "0xfaaa"}), "Transaction 0xfaaa"; "txid")]
+#[test_case(json!({"scope": "nonce", "sender": "ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5", "nonce": {"lower_than": 5}}), "Nonce lower than 5 of ST3AXH4EBHD63FCFPTZ8GR29TNTVWDYPGY0KDY5E5"; "nonce")]
+#[test_case(json!({"scope": "event_ratio", "event_type": "print_event", "higher_than": 50}), "More than 50% of print events"; "event ratio")]
+#[test_case(json!({"scope": "event_count", "higher_than": 3}), "More than 3 events"; "event count")]
+#[test_case(json!({"scope": "event_sequence", "events": [{"event_type": "ft_event", "action": "burn", "asset_identifier": "asset-id"}, {"event_type": "print_event"}]}), "Event sequence FT burn of asset-id then print"; "event sequence")]
+#[test_case(json!({"scope": "stx_transfer_recipient", "principal_type": "contract", "memo": {"contains": "invoice"}}), "STX transfer to a contract principal with memo containing \"invoice\""; "stx transfer recipient")]
+#[test_case(json!({"scope": "contract_call_post_condition_abort", "contract_identifier": "SP000000000000000000002Q6VF78.pox", "method": "stack-stx"}), "Post condition abort of call of stack-stx on SP000000000000000000002Q6VF78.pox"; "post condition abort")]
+#[test_case(json!({"scope": "contract_interactions", "all_of": ["SP1.amm", "SP2.vault"]}), "Interaction with all of SP1.amm, SP2.vault"; "contract interactions")]
+#[test_case(json!({"scope": "all_of", "predicates": [{"scope": "contract_call", "contract_identifier": "SP000000000000000000002Q6VF78.pox", "method": "stack-stx"}, {"scope": "not", "predicate": {"scope": "stx_event", "actions": ["lock"]}}]}), "All of (Call of stack-stx on SP000000000000000000002Q6VF78.pox, Not (STX lock))"; "all of")]
+#[test_case(json!({"scope": "any_of", "predicates": [{"scope": "txid", "equals": "0xfaaa"}, {"scope": "event_count", "higher_than": 3}]}), "Any of (Transaction 0xfaaa, More than 3 events)"; "any of")]
+fn test_stacks_predicate_description(predicate: JsonValue, expected: &str) {
+    let predicate: StacksPredicate = serde_json::from_value(predicate).unwrap();
+    assert_eq!(predicate.describe(), expected);
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn generated_schemas_accept_known_good_specifications() {
+    use super::schema::{generate_bitcoin_predicate_schema, generate_stacks_predicate_schema};
+    use jsonschema::JSONSchema;
+
+    let stacks_spec: StacksChainhookFullSpecification = serde_json::from_value(json!({
+        "uuid": "1",
+        "name": "stacking",
+        "version": 1,
+        "networks": {
+            "testnet": {
+                "start_block": 100,
+                "if_this": {
+                    "scope": "contract_call",
+                    "contract_identifier": "SP000000000000000000002Q6VF78.pox",
+                    "method": "stack-stx"
+                },
+                "then_that": {
+                    "http_post": {
+                        "url": "http://localhost:3000/api/v1/events",
+                        "authorization_header": "Bearer cn389ncoiwuencr"
+                    }
+                }
+            }
+        }
+    }))
+    .unwrap();
+    let bitcoin_spec: BitcoinChainhookFullSpecification = serde_json::from_value(json!({
+        "uuid": "2",
+        "name": "txid",
+        "version": 1,
+        "networks": {
+            "regtest": {
+                "blocks": [1, 2, 3],
+                "if_this": {
+                    "scope": "txid",
+                    "equals": "0xfaaac1833dc4883e7ec28f61e35b41f896c395f8d288b1a177155de2abd6052f"
+                },
+                "then_that": {
+                    "file_append": {
+                        "path": "txid.json"
+                    }
+                }
+            }
+        }
+    }))
+    .unwrap();
+
+    for (schema, spec) in [
+        (
+            generate_stacks_predicate_schema(),
+            serde_json::to_value(&stacks_spec).unwrap(),
+        ),
+        (
+            generate_bitcoin_predicate_schema(),
+            serde_json::to_value(&bitcoin_spec).unwrap(),
+        ),
+    ] {
+        let schema = JSONSchema::compile(&schema).unwrap();
+        if let Err(errors) = schema.validate(&spec) {
+            panic!(
+                "{spec} rejected: {}",
+                errors.map(|e| e.to_string()).collect::<Vec<_>>().join(", ")
+            );
+        }
+        let mut invalid_spec = spec.clone();
+        invalid_spec["version"] = json!("one");
+        assert!(!schema.is_valid(&invalid_spec));
+    }
+}
+
+fn build_stacking_specification() -> StacksChainhookSpecification {
+    StacksChainhookSpecification::builder()
+        .uuid("stacking")
+        .name("stacking")
+        .network(StacksNetwork::Testnet)
+        .predicate(StacksPredicate::ContractCall(
+            StacksContractCallBasedPredicate {
+                contract_identifier: "SP000000000000000000002Q6VF78.pox".into(),
+                method: "stack-stx".to_string(),
+                case_insensitive: None,
+                args: None,
+            },
+        ))
+        .build()
+        .unwrap()
+}
+
+#[test_case(|spec| spec.uuid = "".into(), "field `uuid` should not be empty"; "empty uuid")]
+#[test_case(|spec| spec.name = " ".into(), "field `name` should not be empty"; "blank name")]
+#[test_case(|spec| { spec.start_block = Some(200); spec.end_block = Some(100) }, "`end_block` should be greater than `start_block`"; "inverted block range")]
+#[test_case(|spec| spec.expire_after_occurrence = Some(0), "`expire_after_occurrence` should be greater than 0"; "zero expire_after_occurrence")]
+#[test_case(|spec| spec.predicate = StacksPredicate::ContractCall(StacksContractCallBasedPredicate { contract_identifier: "SP000000000000000000002Q6VF78.pox".into(), method: "".to_string(), case_insensitive: None, args: None }), "field `method` should not be empty"; "empty method")]
+#[test_case(|spec| spec.predicate = StacksPredicate::ContractCall(StacksContractCallBasedPredicate { contract_identifier: "pox".into(), method: "stack-stx".to_string(), case_insensitive: None, args: None }), "should be formatted as <deployer>.<contract_name>"; "malformed contract identifier")]
+#[test_case(|spec| spec.action = HookAction::FileAppend(FileHook { path: "stacking.json".into(), format: None, max_size_bytes: None, max_lines: None, include_raw_tx: Some(true) }), "`include_raw_tx` is only supported by Bitcoin predicates"; "raw transactions of a stacks predicate")]
+fn invalid_stacks_specification_is_rejected(
+    update_spec: fn(&mut StacksChainhookSpecification),
+    expected_error: &str,
+) {
+    let mut spec = build_stacking_specification();
+    update_spec(&mut spec);
+    let errors = spec.validate().unwrap_err();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains(expected_error), "{}", errors[0]);
+}
+
+#[test]
+fn valid_stacks_specification_is_accepted() {
+    let mut spec = build_stacking_specification();
+    spec.start_block = Some(100);
+    spec.end_block = Some(200);
+    spec.expire_after_occurrence = Some(1);
+    assert_eq!(spec.validate(), Ok(()));
+}
+
+#[test]
+fn stacks_specification_errors_are_all_reported() {
+    let mut spec = build_stacking_specification();
+    spec.name = "".into();
+    spec.expire_after_occurrence = Some(0);
+    assert_eq!(spec.validate().unwrap_err().len(), 2);
+}
+
+#[test_case(0, false ; "zero offset")]
+#[test_case(1, true ; "offset of one block")]
+#[test_case(MAX_BLOCK_HEIGHTS_ENTRIES, true ; "largest offset")]
+#[test_case(MAX_BLOCK_HEIGHTS_ENTRIES + 1, false ; "offset above the maximum number of blocks to scan")]
+fn bitcoin_start_block_offset_from_tip_is_bounded(offset: u64, expected_valid: bool) {
+    let spec: BitcoinChainhookFullSpecification = serde_json::from_value(json!({
+        "uuid": "1",
+        "name": "relative-start",
+        "version": 1,
+        "networks": {
+            "regtest": {
+                "start_block_offset_from_tip": offset,
+                "if_this": {"scope": "block"},
+                "then_that": "noop"
+            }
+        }
+    }))
+    .unwrap();
+    assert_eq!(spec.validate().is_ok(), expected_valid);
+    assert_eq!(
+        ChainhookFullSpecification::Bitcoin(spec).validate().is_ok(),
+        expected_valid
+    );
+}
+
+#[test_case("/name", json!(""), "field `name` should not be empty"; "empty name")]
+#[test_case("/networks/regtest/start_block", json!(10), "`end_block` should be greater than `start_block`"; "inverted block range")]
+#[test_case("/networks/regtest/expire_after_occurrence", json!(0), "`expire_after_occurrence` should be greater than 0"; "zero expire_after_occurrence")]
+#[test_case("/networks/regtest/if_this", json!({"scope": "op_return", "contains": "0xzz", "encoding": "hex"}), "invalid op_return hex pattern"; "malformed op_return")]
+#[test_case("/networks/regtest/if_this", json!({"scope": "inputs", "address": {"equals": "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"}}), "should only contain Regtest addresses"; "input address of another network")]
+fn invalid_bitcoin_specification_is_rejected(
+    pointer: &str,
+    value: JsonValue,
+    expected_error: &str,
+) {
+    let mut spec = json!({
+        "uuid": "1",
+        "name": "op_return",
+        "version": 1,
+        "networks": {
+            "regtest": {
+                "start_block": 1,
+                "end_block": 5,
+                "if_this": {"scope": "op_return", "contains": "0x69", "encoding": "hex"},
+                "then_that": "noop"
+            }
+        }
+    });
+    let valid_spec: BitcoinChainhookFullSpecification =
+        serde_json::from_value(spec.clone()).unwrap();
+    assert_eq!(valid_spec.validate(), Ok(()));
+
+    *spec.pointer_mut(pointer).unwrap() = value;
+    let invalid_spec: BitcoinChainhookFullSpecification = serde_json::from_value(spec).unwrap();
+    let errors = invalid_spec.validate().unwrap_err();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains(expected_error), "{}", errors[0]);
 }
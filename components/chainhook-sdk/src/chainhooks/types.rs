@@ -1,18 +1,40 @@
 use std::collections::BTreeMap;
-
-use chainhook_types::{BitcoinNetwork, StacksNetwork};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use bitcoincore_rpc::bitcoin::hashes::{sha256, Hash};
+use chainhook_types::{BitcoinNetwork, BlockIdentifier, StacksNetwork, TransactionIdentifier};
+use dashmap::DashMap;
+use hex::FromHex;
+use regex::Regex;
 use reqwest::Url;
 use serde::ser::{SerializeSeq, Serializer};
 use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value as JsonValue;
 
 use schemars::JsonSchema;
 
-use crate::utils::MAX_BLOCK_HEIGHTS_ENTRIES;
+use crate::utils::{RetryPolicy, MAX_BLOCK_HEIGHTS_ENTRIES};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct ChainhookConfig {
     pub stacks_chainhooks: Vec<StacksChainhookSpecification>,
     pub bitcoin_chainhooks: Vec<BitcoinChainhookSpecification>,
+    #[serde(default)]
+    pub duplicate_uuid_policy: DuplicateUuidPolicy,
+}
+
+/// How to handle a specification registered with the uuid of an already registered one.
+/// Letting both through would silently share the per-uuid state (expiration, scanning
+/// progress, deliveries) between two different predicates.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateUuidPolicy {
+    /// Refuse the registration.
+    #[default]
+    Reject,
+    /// Register the specification under the first available `<uuid>-<n>` uuid.
+    Suffix,
 }
 
 impl ChainhookConfig {
@@ -20,6 +42,36 @@ impl ChainhookConfig {
         ChainhookConfig {
             stacks_chainhooks: vec![],
             bitcoin_chainhooks: vec![],
+            duplicate_uuid_policy: DuplicateUuidPolicy::default(),
+        }
+    }
+
+    fn is_uuid_registered(&self, uuid: &str) -> bool {
+        self.stacks_chainhooks.iter().any(|spec| spec.uuid.eq(uuid))
+            || self
+                .bitcoin_chainhooks
+                .iter()
+                .any(|spec| spec.uuid.eq(uuid))
+    }
+
+    fn resolve_duplicate_uuid(&self, uuid: &str) -> Result<String, String> {
+        if !self.is_uuid_registered(uuid) {
+            return Ok(uuid.to_string());
+        }
+        match self.duplicate_uuid_policy {
+            DuplicateUuidPolicy::Reject => {
+                Err(format!("predicate uuid {} is already registered", uuid))
+            }
+            DuplicateUuidPolicy::Suffix => {
+                let mut suffix = 1;
+                loop {
+                    let candidate = format!("{}-{}", uuid, suffix);
+                    if !self.is_uuid_registered(&candidate) {
+                        return Ok(candidate);
+                    }
+                    suffix += 1;
+                }
+            }
         }
     }
 
@@ -30,12 +82,14 @@ impl ChainhookConfig {
     ) -> Result<ChainhookSpecification, String> {
         let spec = match hook {
             ChainhookFullSpecification::Stacks(hook) => {
-                let spec = hook.into_selected_network_specification(networks.1)?;
+                let mut spec = hook.into_selected_network_specification(networks.1)?;
+                spec.uuid = self.resolve_duplicate_uuid(&spec.uuid)?;
                 self.stacks_chainhooks.push(spec.clone());
                 ChainhookSpecification::Stacks(spec)
             }
             ChainhookFullSpecification::Bitcoin(hook) => {
-                let spec = hook.into_selected_network_specification(networks.0)?;
+                let mut spec = hook.into_selected_network_specification(networks.0)?;
+                spec.uuid = self.resolve_duplicate_uuid(&spec.uuid)?;
                 self.bitcoin_chainhooks.push(spec.clone());
                 ChainhookSpecification::Bitcoin(spec)
             }
@@ -69,11 +123,21 @@ impl ChainhookConfig {
     pub fn register_specification(&mut self, spec: ChainhookSpecification) -> Result<(), String> {
         match spec {
             ChainhookSpecification::Stacks(spec) => {
-                let spec = spec.clone();
+                let mut spec = spec.clone();
+                if spec.uuid.is_empty() {
+                    spec.uuid =
+                        derive_specification_uuid(&spec.network, &spec.predicate, &spec.action);
+                }
+                spec.uuid = self.resolve_duplicate_uuid(&spec.uuid)?;
                 self.stacks_chainhooks.push(spec);
             }
             ChainhookSpecification::Bitcoin(spec) => {
-                let spec = spec.clone();
+                let mut spec = spec.clone();
+                if spec.uuid.is_empty() {
+                    spec.uuid =
+                        derive_specification_uuid(&spec.network, &spec.predicate, &spec.action);
+                }
+                spec.uuid = self.resolve_duplicate_uuid(&spec.uuid)?;
                 self.bitcoin_chainhooks.push(spec);
             }
         };
@@ -194,6 +258,14 @@ impl ChainhookSpecification {
             Self::Stacks(data) => &data.uuid,
         }
     }
+
+    /// Human readable description of the predicate of the specification.
+    pub fn describe(&self) -> String {
+        match &self {
+            Self::Bitcoin(data) => data.predicate.describe(),
+            Self::Stacks(data) => data.predicate.describe(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -208,6 +280,8 @@ pub struct BitcoinChainhookSpecification {
     pub blocks: Option<Vec<u64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_block_offset_from_tip: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub end_block: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -218,6 +292,14 @@ pub struct BitcoinChainhookSpecification {
     pub include_inputs: bool,
     pub include_outputs: bool,
     pub include_witness: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier_format: Option<IdentifierFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_on_match: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_on: Option<TriggerOn>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_confirmations: Option<u64>,
     pub enabled: bool,
     pub expired_at: Option<u64>,
 }
@@ -228,6 +310,58 @@ impl BitcoinChainhookSpecification {
     }
 }
 
+fn validate_required_field(field: &str, value: &str, errors: &mut Vec<String>) {
+    if value.trim().is_empty() {
+        errors.push(format!(
+            "Chainhook specification field `{}` should not be empty",
+            field
+        ));
+    }
+}
+
+fn validate_start_block_offset_from_tip(offset: u64) -> Result<(), String> {
+    if offset == 0 || offset > MAX_BLOCK_HEIGHTS_ENTRIES {
+        return Err(format!(
+            "Chainhook specification field `start_block_offset_from_tip` should be between 1 and {}",
+            MAX_BLOCK_HEIGHTS_ENTRIES
+        ));
+    }
+    Ok(())
+}
+
+/// Raw transactions are only delivered by Bitcoin predicates.
+fn validate_stacks_action(action: &HookAction) -> Result<(), String> {
+    if action.includes_raw_tx() {
+        return Err(
+            "Chainhook specification field `include_raw_tx` is only supported by Bitcoin predicates"
+                .into(),
+        );
+    }
+    Ok(())
+}
+
+fn validate_block_scope(
+    start_block: &Option<u64>,
+    end_block: &Option<u64>,
+    expire_after_occurrence: &Option<u64>,
+    errors: &mut Vec<String>,
+) {
+    if let (Some(start_block), Some(end_block)) = (start_block, end_block) {
+        if start_block > end_block {
+            errors.push(
+                "Chainhook specification field `end_block` should be greater than `start_block`."
+                    .into(),
+            );
+        }
+    }
+    if let Some(0) = expire_after_occurrence {
+        errors.push(
+            "Chainhook specification field `expire_after_occurrence` should be greater than 0"
+                .into(),
+        );
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case", tag = "chain")]
 pub enum ChainhookFullSpecification {
@@ -239,8 +373,14 @@ impl ChainhookFullSpecification {
     pub fn validate(&self) -> Result<(), String> {
         match &self {
             Self::Bitcoin(data) => {
+                data.validate().map_err(|errors| errors.join(", "))?;
                 for (_, spec) in data.networks.iter() {
-                    let _ = spec.action.validate()?;
+                    // Relative start blocks are only resolved when the scan starts: the number
+                    // of blocks to scan is bounded by the offset itself.
+                    if let Some(offset) = spec.start_block_offset_from_tip {
+                        validate_start_block_offset_from_tip(offset)?;
+                        continue;
+                    }
                     if let Some(end_block) = spec.end_block {
                         let start_block = spec.start_block.unwrap_or(0);
                         if start_block > end_block {
@@ -258,6 +398,8 @@ impl ChainhookFullSpecification {
             Self::Stacks(data) => {
                 for (_, spec) in data.networks.iter() {
                     let _ = spec.action.validate()?;
+                    let _ = validate_stacks_action(&spec.action)?;
+                    let _ = spec.predicate.validate()?;
                     if let Some(end_block) = spec.end_block {
                         let start_block = spec.start_block.unwrap_or(0);
                         if start_block > end_block {
@@ -304,6 +446,59 @@ pub struct BitcoinChainhookFullSpecification {
 }
 
 impl BitcoinChainhookFullSpecification {
+    /// Checks the specification of every network, returning all the errors found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = vec![];
+        validate_required_field("name", &self.name, &mut errors);
+        if self.networks.is_empty() {
+            errors.push("Chainhook specification field `networks` should not be empty".into());
+        }
+        for (network, spec) in self.networks.iter() {
+            validate_block_scope(
+                &spec.start_block,
+                &spec.end_block,
+                &spec.expire_after_occurrence,
+                &mut errors,
+            );
+            if let Some(offset) = spec.start_block_offset_from_tip {
+                if let Err(e) = validate_start_block_offset_from_tip(offset) {
+                    errors.push(e);
+                }
+            }
+            if spec.start_block_offset_from_tip.is_some()
+                && (spec.start_block.is_some() || spec.blocks.is_some())
+            {
+                errors.push(
+                    "Chainhook specification field `start_block_offset_from_tip` can not be combined with `start_block` or `blocks`"
+                        .into(),
+                );
+            }
+            if let Err(e) = spec.predicate.validate() {
+                errors.push(e);
+            }
+            if let BitcoinPredicateType::Inputs(InputPredicate::Address(rule)) = &spec.predicate {
+                for address in rule.addresses() {
+                    let is_valid = bitcoincore_rpc::bitcoin::Address::from_str(address)
+                        .map(|address| address.is_valid_for_network(get_bitcoin_network(network)))
+                        .unwrap_or(false);
+                    if !is_valid {
+                        errors.push(format!(
+                            "Chainhook specification field `predicate.inputs.address` should only contain {:?} addresses, found {}",
+                            network, address
+                        ));
+                    }
+                }
+            }
+            if let Err(e) = spec.action.validate() {
+                errors.push(e);
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
     pub fn into_selected_network_specification(
         mut self,
         network: &BitcoinNetwork,
@@ -312,13 +507,19 @@ impl BitcoinChainhookFullSpecification {
             .networks
             .remove(network)
             .ok_or("Network unknown".to_string())?;
+        let uuid = if self.uuid.is_empty() {
+            derive_specification_uuid(network, &spec.predicate, &spec.action)
+        } else {
+            self.uuid
+        };
         Ok(BitcoinChainhookSpecification {
-            uuid: self.uuid,
+            uuid,
             owner_uuid: self.owner_uuid,
             name: self.name,
             network: network.clone(),
             version: self.version,
             start_block: spec.start_block,
+            start_block_offset_from_tip: spec.start_block_offset_from_tip,
             end_block: spec.end_block,
             blocks: spec.blocks,
             expire_after_occurrence: spec.expire_after_occurrence,
@@ -328,6 +529,10 @@ impl BitcoinChainhookFullSpecification {
             include_inputs: spec.include_inputs.unwrap_or(false),
             include_outputs: spec.include_outputs.unwrap_or(false),
             include_witness: spec.include_witness.unwrap_or(false),
+            identifier_format: spec.identifier_format,
+            stop_on_match: spec.stop_on_match,
+            trigger_on: spec.trigger_on,
+            min_confirmations: spec.min_confirmations,
             enabled: false,
             expired_at: None,
         })
@@ -340,6 +545,9 @@ pub struct BitcoinChainhookNetworkSpecification {
     pub blocks: Option<Vec<u64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_block: Option<u64>,
+    /// Start the scan this number of blocks below the chain tip, as known when the scan starts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_block_offset_from_tip: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub end_block: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -352,6 +560,19 @@ pub struct BitcoinChainhookNetworkSpecification {
     pub include_outputs: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub include_witness: Option<bool>,
+    /// Serialization of the block and transaction identifiers of the occurrences.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier_format: Option<IdentifierFormat>,
+    /// End historical scans right after the first block triggering the predicate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_on_match: Option<bool>,
+    /// Block updates triggering the action, defaults to `apply`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_on: Option<TriggerOn>,
+    /// Number of blocks that must be mined on top of a block before its occurrence is
+    /// delivered. Occurrences of blocks orphaned in the meantime are dropped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_confirmations: Option<u64>,
     #[serde(rename = "if_this")]
     pub predicate: BitcoinPredicateType,
     #[serde(rename = "then_that")]
@@ -377,8 +598,13 @@ impl StacksChainhookFullSpecification {
             .networks
             .remove(network)
             .ok_or("Network unknown".to_string())?;
+        let uuid = if self.uuid.is_empty() {
+            derive_specification_uuid(network, &spec.predicate, &spec.action)
+        } else {
+            self.uuid
+        };
         Ok(StacksChainhookSpecification {
-            uuid: self.uuid,
+            uuid,
             owner_uuid: self.owner_uuid,
             name: self.name,
             network: network.clone(),
@@ -390,6 +616,13 @@ impl StacksChainhookFullSpecification {
             decode_clarity_values: spec.decode_clarity_values,
             expire_after_occurrence: spec.expire_after_occurrence,
             include_contract_abi: spec.include_contract_abi,
+            projection: spec.projection,
+            include_mempool_timestamps: spec.include_mempool_timestamps,
+            identifier_format: spec.identifier_format,
+            derived_fields: spec.derived_fields,
+            max_clarity_value_length: spec.max_clarity_value_length,
+            trigger_on: spec.trigger_on,
+            min_confirmations: spec.min_confirmations,
             predicate: spec.predicate,
             action: spec.action,
             enabled: false,
@@ -414,6 +647,29 @@ pub struct StacksChainhookNetworkSpecification {
     pub decode_clarity_values: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub include_contract_abi: Option<bool>,
+    /// Paths of the occurrence fields to deliver, e.g. `apply[].transactions[].metadata.success`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projection: Option<Vec<String>>,
+    /// Include, for each transaction, the unix timestamp at which it was first seen in the mempool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_mempool_timestamps: Option<bool>,
+    /// Serialization of the block and transaction identifiers of the occurrences.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier_format: Option<IdentifierFormat>,
+    /// Fields computed for each transaction of the occurrences, keyed by name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived_fields: Option<BTreeMap<String, DerivedFieldRule>>,
+    /// Maximum length of the decoded Clarity strings and buffers of the occurrences,
+    /// longer values are truncated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_clarity_value_length: Option<usize>,
+    /// Block updates triggering the action, defaults to `apply`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_on: Option<TriggerOn>,
+    /// Number of blocks that must be mined on top of a block before its occurrence is
+    /// delivered. Occurrences of blocks orphaned in the meantime are dropped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_confirmations: Option<u64>,
     #[serde(rename = "if_this")]
     pub predicate: StacksPredicate,
     #[serde(rename = "then_that")]
@@ -425,34 +681,562 @@ pub struct StacksChainhookNetworkSpecification {
 pub enum HookAction {
     HttpPost(HttpHook),
     FileAppend(FileHook),
+    UnixSocket(UnixSocketHook),
+    ZstdFileAppend(ZstdFileHook),
+    Redis(RedisHook),
+    Kafka(KafkaHook),
     Noop,
 }
 
+/// Block updates triggering the action of a predicate.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerOn {
+    /// Applied blocks, along with the rollbacks of the blocks previously matched.
+    #[default]
+    Apply,
+    /// Only the rollbacks of the blocks previously matched, e.g. to be notified of reorgs.
+    Rollback,
+    /// Applied blocks and rollbacks, delivered as distinct occurrences.
+    Both,
+}
+
+impl TriggerOn {
+    pub fn includes_apply(&self) -> bool {
+        !matches!(self, TriggerOn::Rollback)
+    }
+
+    /// Occurrences counted toward `expire_after_occurrence` for a trigger applying and rolling
+    /// back that many blocks: the blocks triggering the action, rollbacks only counting for
+    /// `rollback`, as the blocks they roll back were counted when applied otherwise.
+    pub fn count_occurrences(&self, applied_blocks: usize, rolled_back_blocks: usize) -> u64 {
+        match self {
+            TriggerOn::Rollback => rolled_back_blocks as u64,
+            TriggerOn::Apply | TriggerOn::Both => applied_blocks as u64,
+        }
+    }
+}
+
 impl HookAction {
     pub fn validate(&self) -> Result<(), String> {
         match &self {
             HookAction::HttpPost(spec) => {
                 let _ = Url::parse(&spec.url)
                     .map_err(|e| format!("hook action url invalid ({})", e.to_string()))?;
+                if let Some(ref batching) = spec.batching {
+                    batching.validate()?;
+                }
+                if !(spec.get_backoff_multiplier() >= 1.0) {
+                    return Err(format!(
+                        "hook action http_post backoff_multiplier should be greater than or equal to 1"
+                    ));
+                }
+                if let Some(ref secret) = spec.signing_secret {
+                    if secret.is_empty() {
+                        return Err(format!(
+                            "hook action http_post signing_secret should not be empty"
+                        ));
+                    }
+                }
+                if let Some(ref template) = spec.template {
+                    if spec.batching.is_some() {
+                        return Err(format!(
+                            "hook action http_post template can not be combined with batching"
+                        ));
+                    }
+                    for captures in get_http_template_regex().captures_iter(template) {
+                        if !HTTP_TEMPLATE_PLACEHOLDERS.contains(&&captures[1]) {
+                            return Err(format!(
+                                "hook action http_post template placeholder {} unknown (expected one of {})",
+                                &captures[0],
+                                HTTP_TEMPLATE_PLACEHOLDERS.join(", ")
+                            ));
+                        }
+                    }
+                }
+            }
+            HookAction::FileAppend(spec) => {
+                if spec.max_size_bytes == Some(0) {
+                    return Err(format!(
+                        "hook action file_append max_size_bytes should be greater than 0"
+                    ));
+                }
+                if spec.max_lines == Some(0) {
+                    return Err(format!(
+                        "hook action file_append max_lines should be greater than 0"
+                    ));
+                }
+            }
+            HookAction::UnixSocket(spec) => {
+                if spec.path.is_empty() {
+                    return Err(format!("hook action unix_socket path should not be empty"));
+                }
+                if !(spec.get_retry_policy().backoff_multiplier >= 1.0) {
+                    return Err(format!(
+                        "hook action unix_socket backoff_multiplier should be greater than or equal to 1"
+                    ));
+                }
+            }
+            HookAction::ZstdFileAppend(spec) => {
+                if !spec.path.ends_with(".zst") {
+                    return Err(format!(
+                        "hook action zstd_file_append path should end with .zst"
+                    ));
+                }
+                let compression_level = spec.get_compression_level();
+                if !(1..=22).contains(&compression_level) {
+                    return Err(format!(
+                        "hook action zstd_file_append compression_level should be between 1 and 22"
+                    ));
+                }
+            }
+            HookAction::Redis(spec) => {
+                let _ = Url::parse(&spec.url)
+                    .map_err(|e| format!("hook action redis url invalid ({})", e.to_string()))?;
+                if spec.stream_key.is_empty() {
+                    return Err(format!("hook action redis stream_key should not be empty"));
+                }
+                if spec.maxlen == Some(0) {
+                    return Err(format!("hook action redis maxlen should be greater than 0"));
+                }
+                if !(spec.get_retry_policy().backoff_multiplier >= 1.0) {
+                    return Err(format!(
+                        "hook action redis backoff_multiplier should be greater than or equal to 1"
+                    ));
+                }
+            }
+            HookAction::Kafka(spec) => {
+                if spec.brokers.is_empty() || spec.brokers.iter().any(|b| b.is_empty()) {
+                    return Err(format!("hook action kafka brokers should not be empty"));
+                }
+                if spec.topic.is_empty() {
+                    return Err(format!("hook action kafka topic should not be empty"));
+                }
+                if let Some(ref key_template) = spec.key_template {
+                    if key_template.is_empty() {
+                        return Err(format!(
+                            "hook action kafka key_template should not be empty"
+                        ));
+                    }
+                }
+                if !(spec.get_retry_policy().backoff_multiplier >= 1.0) {
+                    return Err(format!(
+                        "hook action kafka backoff_multiplier should be greater than or equal to 1"
+                    ));
+                }
             }
-            HookAction::FileAppend(_) => {}
             HookAction::Noop => {}
         }
         Ok(())
     }
+
+    /// Whether the action only delivers the transactions applied by an occurrence: the body of
+    /// an HTTP action rendered from a `template` does not render rollbacks.
+    pub fn delivers_applied_transactions_only(&self) -> bool {
+        match self {
+            HookAction::HttpPost(spec) => spec.template.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Retries of the deliveries of the action's occurrences, when scanning.
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        match self {
+            HookAction::HttpPost(spec) => spec.get_retry_policy(),
+            HookAction::UnixSocket(spec) => spec.get_retry_policy(),
+            HookAction::Redis(spec) => spec.get_retry_policy(),
+            HookAction::Kafka(spec) => spec.get_retry_policy(),
+            HookAction::FileAppend(_) | HookAction::ZstdFileAppend(_) | HookAction::Noop => {
+                RetryPolicy::default()
+            }
+        }
+    }
+
+    /// Whether the occurrences of Bitcoin predicates carry the raw transactions they matched.
+    pub fn includes_raw_tx(&self) -> bool {
+        match self {
+            HookAction::HttpPost(spec) => spec.include_raw_tx.unwrap_or(false),
+            HookAction::FileAppend(spec) => spec.include_raw_tx.unwrap_or(false),
+            _ => false,
+        }
+    }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct HttpHook {
     pub url: String,
     pub authorization_header: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batching: Option<OccurrenceBatchingPolicy>,
+    /// Number of retries after a failed delivery (connection error or non 2xx response).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u16>,
+    /// Delay before the first retry, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_backoff_ms: Option<u64>,
+    /// Factor applied to the delay after each retry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_multiplier: Option<f64>,
+    /// When set, each request carries the hex encoded HMAC-SHA256 of its body, keyed with this
+    /// secret, in the `X-Chainhook-Signature` header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_secret: Option<String>,
+    /// Body rendered for each transaction applied by an occurrence, in place of the full
+    /// payload. See [`HTTP_TEMPLATE_PLACEHOLDERS`] for the supported placeholders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Gzip request bodies larger than `gzip_threshold_bytes`, with a `Content-Encoding: gzip`
+    /// header. Signatures are computed on the gzipped body, as sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gzip: Option<bool>,
+    /// Size above which bodies are compressed when `gzip` is set, defaults to 64 KiB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gzip_threshold_bytes: Option<usize>,
+    /// Include the hex encoded raw transaction of each matched Bitcoin transaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_raw_tx: Option<bool>,
+}
+
+impl std::fmt::Debug for HttpHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpHook")
+            .field("url", &self.url)
+            .field("authorization_header", &self.authorization_header)
+            .field("batching", &self.batching)
+            .field("max_retries", &self.max_retries)
+            .field("initial_backoff_ms", &self.initial_backoff_ms)
+            .field("backoff_multiplier", &self.backoff_multiplier)
+            .field(
+                "signing_secret",
+                &self.signing_secret.as_ref().map(|_| "<redacted>"),
+            )
+            .field("template", &self.template)
+            .field("gzip", &self.gzip)
+            .field("gzip_threshold_bytes", &self.gzip_threshold_bytes)
+            .field("include_raw_tx", &self.include_raw_tx)
+            .finish()
+    }
+}
+
+pub const DEFAULT_HTTP_MAX_RETRIES: u16 = 9;
+pub const DEFAULT_HTTP_INITIAL_BACKOFF_MS: u64 = 3_000;
+pub const DEFAULT_HTTP_BACKOFF_MULTIPLIER: f64 = 1.0;
+pub const DEFAULT_HTTP_GZIP_THRESHOLD_BYTES: usize = 64 * 1024;
+
+impl HttpHook {
+    pub fn get_max_retries(&self) -> u16 {
+        self.max_retries.unwrap_or(DEFAULT_HTTP_MAX_RETRIES)
+    }
+
+    pub fn get_initial_backoff_ms(&self) -> u64 {
+        self.initial_backoff_ms
+            .unwrap_or(DEFAULT_HTTP_INITIAL_BACKOFF_MS)
+    }
+
+    pub fn get_backoff_multiplier(&self) -> f64 {
+        self.backoff_multiplier
+            .unwrap_or(DEFAULT_HTTP_BACKOFF_MULTIPLIER)
+    }
+
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.get_max_retries(),
+            initial_backoff_ms: self.get_initial_backoff_ms(),
+            backoff_multiplier: self.get_backoff_multiplier(),
+        }
+    }
+
+    /// Whether a body of `body_len` bytes is sent gzipped.
+    pub fn should_gzip(&self, body_len: usize) -> bool {
+        self.gzip.unwrap_or(false)
+            && body_len
+                > self
+                    .gzip_threshold_bytes
+                    .unwrap_or(DEFAULT_HTTP_GZIP_THRESHOLD_BYTES)
+    }
+
+    /// Renders the `template` for each transaction applied by the occurrence `payload`, as a
+    /// JSON array. Rendered bodies that are not valid JSON are posted as JSON strings.
+    pub fn render_template(template: &str, payload: &JsonValue) -> JsonValue {
+        let predicate_uuid = payload["chainhook"]["uuid"].as_str().unwrap_or_default();
+        let mut bodies = vec![];
+        for block in payload["apply"].as_array().into_iter().flatten() {
+            // Identifiers are objects, or strings when serialized with the compact format.
+            let block_height = match &block["block_identifier"] {
+                JsonValue::String(compact) => compact.split(':').next().unwrap_or_default().into(),
+                block_identifier => block_identifier["index"].to_string(),
+            };
+            for transaction in block["transactions"].as_array().into_iter().flatten() {
+                let txid = match &transaction["transaction_identifier"] {
+                    JsonValue::String(hash) => hash.as_str(),
+                    transaction_identifier => {
+                        transaction_identifier["hash"].as_str().unwrap_or_default()
+                    }
+                };
+                let body = get_http_template_regex().replace_all(
+                    template,
+                    |captures: &regex::Captures| match &captures[1] {
+                        "block_height" => block_height.clone(),
+                        "txid" => txid.to_string(),
+                        "predicate_uuid" => predicate_uuid.to_string(),
+                        "event_json" => transaction.to_string(),
+                        _ => captures[0].to_string(),
+                    },
+                );
+                bodies.push(
+                    serde_json::from_str(&body)
+                        .unwrap_or_else(|_| JsonValue::String(body.into_owned())),
+                );
+            }
+        }
+        JsonValue::Array(bodies)
+    }
+}
+
+/// Retries of the deliveries of an action, the settings left unset defaulting to the ones of
+/// HTTP actions.
+fn build_retry_policy(
+    max_retries: Option<u16>,
+    initial_backoff_ms: Option<u64>,
+    backoff_multiplier: Option<f64>,
+) -> RetryPolicy {
+    RetryPolicy {
+        max_retries: max_retries.unwrap_or(DEFAULT_HTTP_MAX_RETRIES),
+        initial_backoff_ms: initial_backoff_ms.unwrap_or(DEFAULT_HTTP_INITIAL_BACKOFF_MS),
+        backoff_multiplier: backoff_multiplier.unwrap_or(DEFAULT_HTTP_BACKOFF_MULTIPLIER),
+    }
+}
+
+/// Placeholders supported by the `template` of HTTP actions.
+pub const HTTP_TEMPLATE_PLACEHOLDERS: [&str; 4] =
+    ["block_height", "txid", "predicate_uuid", "event_json"];
+
+/// Matches the `{placeholder}` of a template. Other braces, e.g. the ones of a JSON
+/// template, are kept as is.
+fn get_http_template_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\{([a-z_]+)\}").unwrap())
+}
+
+/// Accumulate occurrences and deliver them together, every `window_secs` seconds
+/// or as soon as `max_batch_size` occurrences are pending, whichever comes first.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OccurrenceBatchingPolicy {
+    pub window_secs: u64,
+    pub max_batch_size: usize,
+}
+
+impl OccurrenceBatchingPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window_secs == 0 {
+            return Err(format!(
+                "hook action batching window_secs should be greater than 0"
+            ));
+        }
+        if self.max_batch_size == 0 {
+            return Err(format!(
+                "hook action batching max_batch_size should be greater than 0"
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct FileHook {
     pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<FileFormat>,
+    /// Once the file holds at least this many bytes, it is renamed with a timestamp suffix
+    /// and the next occurrences are appended to a new file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_bytes: Option<u64>,
+    /// Once the file holds at least this many lines, it is renamed with a timestamp suffix
+    /// and the next occurrences are appended to a new file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lines: Option<u64>,
+    /// Include the hex encoded raw transaction of each matched Bitcoin transaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_raw_tx: Option<bool>,
+}
+
+impl FileHook {
+    pub fn get_rotation_policy(&self) -> FileRotationPolicy {
+        FileRotationPolicy {
+            max_size_bytes: self.max_size_bytes,
+            max_lines: self.max_lines,
+        }
+    }
+
+    /// Serializes an occurrence following the hook's `format`, `json_lines` by default.
+    pub fn serialize_occurrence(&self, payload: &JsonValue) -> Result<Vec<u8>, String> {
+        match self.format {
+            Some(FileFormat::JsonLines) | None => serde_json::to_vec(payload),
+            Some(FileFormat::PrettyJson) => serde_json::to_vec_pretty(payload),
+        }
+        .map_err(|e| format!("unable to serialize payload {}", e.to_string()))
+    }
+}
+
+/// Thresholds past which the file written by a `file_append` action is rotated.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileRotationPolicy {
+    pub max_size_bytes: Option<u64>,
+    pub max_lines: Option<u64>,
+}
+
+/// Layout of the occurrences appended by a `file_append` action.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FileFormat {
+    /// One indented JSON document per occurrence.
+    PrettyJson,
+    /// One compact JSON object per line, per occurrence.
+    JsonLines,
+}
+
+/// Stream occurrences, as newline delimited JSON, to the Unix domain socket listening at `path`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UnixSocketHook {
+    pub path: String,
+    /// Number of retries after a failed delivery.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u16>,
+    /// Delay before the first retry, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_backoff_ms: Option<u64>,
+    /// Factor applied to the delay after each retry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_multiplier: Option<f64>,
+}
+
+impl UnixSocketHook {
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        build_retry_policy(
+            self.max_retries,
+            self.initial_backoff_ms,
+            self.backoff_multiplier,
+        )
+    }
+}
+
+pub const DEFAULT_ZSTD_COMPRESSION_LEVEL: i32 = 3;
+
+/// Append occurrences to a zstd compressed `.zst` file, one frame per occurrence.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ZstdFileHook {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_level: Option<i32>,
+    /// Once the file holds at least this many compressed bytes, it is renamed with a
+    /// timestamp suffix and the next occurrences are appended to a new file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_bytes: Option<u64>,
+    /// Once the file holds at least this many lines, it is renamed with a timestamp suffix
+    /// and the next occurrences are appended to a new file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lines: Option<u64>,
+}
+
+impl ZstdFileHook {
+    pub fn get_compression_level(&self) -> i32 {
+        self.compression_level
+            .unwrap_or(DEFAULT_ZSTD_COMPRESSION_LEVEL)
+    }
+
+    pub fn get_rotation_policy(&self) -> FileRotationPolicy {
+        FileRotationPolicy {
+            max_size_bytes: self.max_size_bytes,
+            max_lines: self.max_lines,
+        }
+    }
+}
+
+/// Append occurrences to the Redis stream `stream_key` (`XADD`), optionally capping the stream
+/// at approximately `maxlen` entries.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RedisHook {
+    pub url: String,
+    pub stream_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maxlen: Option<usize>,
+    /// Number of retries after a failed delivery.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u16>,
+    /// Delay before the first retry, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_backoff_ms: Option<u64>,
+    /// Factor applied to the delay after each retry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_multiplier: Option<f64>,
+}
+
+impl RedisHook {
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        build_retry_policy(
+            self.max_retries,
+            self.initial_backoff_ms,
+            self.backoff_multiplier,
+        )
+    }
+}
+
+/// Produce occurrences to the Kafka `topic` of the cluster reachable through `brokers`.
+/// Messages are keyed with `key_template`, where `{uuid}`, `{block_height}` and `{block_hash}`
+/// are replaced with the uuid of the predicate and the last block of the occurrence.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct KafkaHook {
+    pub brokers: Vec<String>,
+    pub topic: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_template: Option<String>,
+    /// Number of retries after a failed delivery.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u16>,
+    /// Delay before the first retry, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_backoff_ms: Option<u64>,
+    /// Factor applied to the delay after each retry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_multiplier: Option<f64>,
+}
+
+impl KafkaHook {
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        build_retry_policy(
+            self.max_retries,
+            self.initial_backoff_ms,
+            self.backoff_multiplier,
+        )
+    }
+
+    pub fn render_key(
+        &self,
+        uuid: &str,
+        block_identifier: Option<&BlockIdentifier>,
+    ) -> Option<String> {
+        let key_template = self.key_template.as_ref()?;
+        let (block_height, block_hash) = match block_identifier {
+            Some(block_identifier) => (
+                block_identifier.index.to_string(),
+                block_identifier.hash.clone(),
+            ),
+            None => (String::new(), String::new()),
+        };
+        Some(
+            key_template
+                .replace("{uuid}", uuid)
+                .replace("{block_height}", &block_height)
+                .replace("{block_hash}", &block_hash),
+        )
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
@@ -516,10 +1300,172 @@ impl BitcoinTransactionFilterPredicate {
 pub enum BitcoinPredicateType {
     Block,
     Txid(ExactMatchingRule),
+    TransactionVersion(VersionMatchingRule),
     Inputs(InputPredicate),
     Outputs(OutputPredicate),
     StacksProtocol(StacksOperations),
     OrdinalsProtocol(OrdinalOperations),
+    /// Match transactions with an OP_RETURN output whose pushed data contains `contains`
+    /// (`*` matching any OP_RETURN output).
+    OpReturn {
+        contains: String,
+        encoding: OpReturnEncoding,
+    },
+    /// Match transactions paying more than `sats_per_vbyte_gt` sats per virtual byte, the fee
+    /// being the value of the spent outputs minus the value of the outputs. Coinbase
+    /// transactions never match, nor do transactions whose virtual size was not provided by
+    /// bitcoind (`getblock` responses below verbosity 2).
+    FeeRate {
+        sats_per_vbyte_gt: f64,
+    },
+}
+
+/// How the `contains` pattern of an `op_return` predicate should be read.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OpReturnEncoding {
+    Hex,
+    Utf8,
+}
+
+impl BitcoinPredicateType {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            BitcoinPredicateType::OpReturn {
+                contains,
+                encoding: OpReturnEncoding::Hex,
+            } if contains != "*" => {
+                let hex = contains.strip_prefix("0x").unwrap_or(contains);
+                Vec::<u8>::from_hex(hex)
+                    .map_err(|e| format!("invalid op_return hex pattern {}: {}", contains, e))?;
+            }
+            BitcoinPredicateType::Inputs(InputPredicate::Txid(TxinPredicate { txid, .. })) => {
+                let hex = txid.strip_prefix("0x").unwrap_or(txid);
+                <[u8; 32]>::from_hex(hex)
+                    .map_err(|e| format!("invalid input txid {}: {}", txid, e))?;
+            }
+            BitcoinPredicateType::FeeRate { sats_per_vbyte_gt }
+                if !(sats_per_vbyte_gt.is_finite() && *sats_per_vbyte_gt >= 0.0) =>
+            {
+                return Err(format!(
+                    "fee_rate predicate field `sats_per_vbyte_gt` should be a non negative number"
+                ));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Human readable description of the predicate, e.g. "P2WPKH output to bc1q...".
+    pub fn describe(&self) -> String {
+        match &self {
+            BitcoinPredicateType::Block => format!("Any block"),
+            BitcoinPredicateType::Txid(ExactMatchingRule::Equals(txid)) => {
+                format!("Transaction {}", txid)
+            }
+            BitcoinPredicateType::TransactionVersion(VersionMatchingRule::Equals(version)) => {
+                format!("Transaction version {}", version)
+            }
+            BitcoinPredicateType::TransactionVersion(VersionMatchingRule::In(versions)) => {
+                format!("Transaction version in {}", join_descriptions(versions))
+            }
+            BitcoinPredicateType::Inputs(InputPredicate::Txid(txin)) => {
+                format!("Input spending {}:{}", txin.txid, txin.vout)
+            }
+            BitcoinPredicateType::Inputs(InputPredicate::WitnessScript(rule)) => {
+                format!("Input witness script {}", rule.describe())
+            }
+            BitcoinPredicateType::Inputs(InputPredicate::Address(rule)) => {
+                format!(
+                    "Input spending from {}",
+                    join_descriptions(rule.addresses())
+                )
+            }
+            BitcoinPredicateType::Outputs(OutputPredicate::OpReturn(rule)) => {
+                format!("OP_RETURN output {}", rule.describe())
+            }
+            BitcoinPredicateType::Outputs(OutputPredicate::P2pkh(ExactMatchingRule::Equals(
+                address,
+            ))) => format!("P2PKH output to {}", address),
+            BitcoinPredicateType::Outputs(OutputPredicate::P2sh(ExactMatchingRule::Equals(
+                address,
+            ))) => format!("P2SH output to {}", address),
+            BitcoinPredicateType::Outputs(OutputPredicate::P2wpkh(ExactMatchingRule::Equals(
+                address,
+            ))) => format!("P2WPKH output to {}", address),
+            BitcoinPredicateType::Outputs(OutputPredicate::P2wsh(ExactMatchingRule::Equals(
+                address,
+            ))) => format!("P2WSH output to {}", address),
+            BitcoinPredicateType::Outputs(OutputPredicate::P2tr(ExactMatchingRule::Equals(
+                address,
+            ))) => format!("P2TR output to {}", address),
+            BitcoinPredicateType::Outputs(OutputPredicate::Descriptor(descriptor)) => {
+                format!("Output matching descriptor {}", descriptor.expression)
+            }
+            BitcoinPredicateType::Outputs(OutputPredicate::TotalValue(rule)) => match rule {
+                ValueMatchingRule::HigherThan(value) => {
+                    format!("Total output value higher than {} sats", value)
+                }
+                ValueMatchingRule::LowerThan(value) => {
+                    format!("Total output value lower than {} sats", value)
+                }
+            },
+            BitcoinPredicateType::Outputs(OutputPredicate::DistinctAddressCount(rule)) => {
+                match rule {
+                    ValueMatchingRule::HigherThan(count) => {
+                        format!("Outputs paying more than {} distinct addresses", count)
+                    }
+                    ValueMatchingRule::LowerThan(count) => {
+                        format!("Outputs paying fewer than {} distinct addresses", count)
+                    }
+                }
+            }
+            BitcoinPredicateType::StacksProtocol(operation) => {
+                let operation = match operation {
+                    StacksOperations::StackerRewarded => "stacker rewarded",
+                    StacksOperations::BlockCommitted => "block committed",
+                    StacksOperations::LeaderRegistered => "leader registered",
+                    StacksOperations::StxTransferred => "STX transferred",
+                    StacksOperations::StxLocked => "STX locked",
+                };
+                format!("Stacks {} operation", operation)
+            }
+            BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionFeed) => {
+                format!("Inscription revealed or transferred")
+            }
+            BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionTransferred(
+                transfer,
+            )) => format!("Inscription transfer{}", transfer.describe()),
+            BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionRevealed(
+                reveal,
+            )) => {
+                let mut description = format!("Inscription reveal");
+                if let Some(ref rule) = reveal.ordinal_number {
+                    description.push_str(&format!(" on sat {}", rule.describe()));
+                }
+                if let Some(ref content_hash) = reveal.content_hash {
+                    description.push_str(&format!(" with content hash {}", content_hash));
+                }
+                if let Some(ref content_type) = reveal.content_type {
+                    description.push_str(&format!(" with content type {}", content_type));
+                }
+                description
+            }
+            BitcoinPredicateType::OpReturn { contains, .. } if contains.eq("*") => {
+                format!("Any OP_RETURN output")
+            }
+            BitcoinPredicateType::OpReturn { contains, encoding } => {
+                let encoding = match encoding {
+                    OpReturnEncoding::Hex => "hex",
+                    OpReturnEncoding::Utf8 => "utf8",
+                };
+                format!("OP_RETURN output containing {} ({})", contains, encoding)
+            }
+            BitcoinPredicateType::FeeRate { sats_per_vbyte_gt } => {
+                format!("Fee rate higher than {} sats/vB", sats_per_vbyte_gt)
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
@@ -527,6 +1473,25 @@ pub enum BitcoinPredicateType {
 pub enum InputPredicate {
     Txid(TxinPredicate),
     WitnessScript(MatchingRule),
+    /// Match transactions spending from the given address(es). Input addresses are derived
+    /// from the script_pubkey of the spent outputs (prevouts).
+    Address(AddressMatchingRule),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressMatchingRule {
+    Equals(String),
+    In(Vec<String>),
+}
+
+impl AddressMatchingRule {
+    pub fn addresses(&self) -> &[String] {
+        match self {
+            AddressMatchingRule::Equals(address) => std::slice::from_ref(address),
+            AddressMatchingRule::In(addresses) => addresses,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
@@ -537,7 +1502,10 @@ pub enum OutputPredicate {
     P2sh(ExactMatchingRule),
     P2wpkh(ExactMatchingRule),
     P2wsh(ExactMatchingRule),
+    P2tr(ExactMatchingRule),
     Descriptor(DescriptorMatchingRule),
+    TotalValue(ValueMatchingRule),
+    DistinctAddressCount(ValueMatchingRule),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
@@ -554,19 +1522,87 @@ pub enum StacksOperations {
 #[serde(rename_all = "snake_case", tag = "operation")]
 pub enum OrdinalOperations {
     InscriptionFeed,
+    InscriptionTransferred(OrdinalInscriptionTransferPredicate),
+    InscriptionRevealed(OrdinalInscriptionRevealPredicate),
 }
 
-pub fn get_stacks_canonical_magic_bytes(network: &BitcoinNetwork) -> [u8; 2] {
-    match network {
-        BitcoinNetwork::Mainnet => *b"X2",
-        BitcoinNetwork::Testnet => *b"T2",
-        BitcoinNetwork::Regtest => *b"id",
-        BitcoinNetwork::Signet => unreachable!(),
-    }
+/// Match inscription transfers moving a given sat. When both constraints are
+/// provided, both of them have to be satisfied.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OrdinalInscriptionTransferPredicate {
+    /// Ordinal number of the sat carrying the inscription.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ordinal_number: Option<u64>,
+    /// Satpoint (`<txid>:<vout>:<offset>`) the inscription is moved from or to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub satpoint: Option<String>,
 }
 
-pub struct PoxConfig {
-    pub genesis_block_height: u64,
+impl OrdinalInscriptionTransferPredicate {
+    fn describe(&self) -> String {
+        let mut description = String::new();
+        if let Some(ordinal_number) = self.ordinal_number {
+            description.push_str(&format!(" of sat {}", ordinal_number));
+        }
+        if let Some(ref satpoint) = self.satpoint {
+            description.push_str(&format!(" from or to {}", satpoint));
+        }
+        description
+    }
+}
+
+/// Match inscription reveals on sats satisfying the given constraints. The ordinal
+/// number of the inscribed sat is provided by the ordinals index augmenting the block.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OrdinalInscriptionRevealPredicate {
+    /// `between` bounds are inclusive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ordinal_number: Option<BlockIdentifierIndexRule>,
+    /// Hex encoded sha256 digest of the inscription body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Content type of the inscription, e.g. `image/png`, or a prefix ending with `*`,
+    /// e.g. `image/*`. Compared regardless of casing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+impl OrdinalInscriptionRevealPredicate {
+    pub fn is_content_type_matching(&self, content_type: &str) -> bool {
+        let Some(ref expected) = self.content_type else {
+            return true;
+        };
+        let content_type = content_type.to_ascii_lowercase();
+        let expected = expected.to_ascii_lowercase();
+        match expected.strip_suffix('*') {
+            Some(prefix) => content_type.starts_with(prefix),
+            None => content_type == expected,
+        }
+    }
+}
+
+pub fn get_bitcoin_network(network: &BitcoinNetwork) -> bitcoincore_rpc::bitcoin::Network {
+    match network {
+        BitcoinNetwork::Mainnet => bitcoincore_rpc::bitcoin::Network::Bitcoin,
+        BitcoinNetwork::Testnet => bitcoincore_rpc::bitcoin::Network::Testnet,
+        BitcoinNetwork::Regtest => bitcoincore_rpc::bitcoin::Network::Regtest,
+        BitcoinNetwork::Signet => bitcoincore_rpc::bitcoin::Network::Signet,
+    }
+}
+
+pub fn get_stacks_canonical_magic_bytes(network: &BitcoinNetwork) -> [u8; 2] {
+    match network {
+        BitcoinNetwork::Mainnet => *b"X2",
+        BitcoinNetwork::Testnet => *b"T2",
+        BitcoinNetwork::Regtest => *b"id",
+        BitcoinNetwork::Signet => unreachable!(),
+    }
+}
+
+pub struct PoxConfig {
+    pub genesis_block_height: u64,
     pub prepare_phase_len: u64,
     pub reward_phase_len: u64,
     pub rewarded_addresses_per_block: usize,
@@ -665,6 +1701,60 @@ pub enum BlockIdentifierIndexRule {
     Between(u64, u64),
 }
 
+impl BlockIdentifierIndexRule {
+    /// `Between` bounds are inclusive. Block height predicates keep evaluating them
+    /// exclusively, see `evaluate_stacks_predicate_on_block`.
+    pub fn matches(&self, value: u64) -> bool {
+        match *self {
+            BlockIdentifierIndexRule::Equals(a) => value == a,
+            BlockIdentifierIndexRule::HigherThan(a) => value > a,
+            BlockIdentifierIndexRule::LowerThan(a) => value < a,
+            BlockIdentifierIndexRule::Between(a, b) => value >= a && value <= b,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            BlockIdentifierIndexRule::Equals(a) => format!("{}", a),
+            BlockIdentifierIndexRule::HigherThan(a) => format!("higher than {}", a),
+            BlockIdentifierIndexRule::LowerThan(a) => format!("lower than {}", a),
+            BlockIdentifierIndexRule::Between(a, b) => format!("between {} and {}", a, b),
+        }
+    }
+}
+
+/// Window of block timestamps (Unix time, in seconds), bounds excluded. Evaluated on the block
+/// header, so it can not be combined with `all_of` / `any_of` / `not`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TimeRange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<u64>,
+}
+
+impl TimeRange {
+    /// Blocks without a timestamp (e.g. 0) are never in the window.
+    pub fn contains(&self, timestamp: i64) -> bool {
+        if timestamp <= 0 {
+            return false;
+        }
+        let timestamp = timestamp as u64;
+        if let Some(after) = self.after {
+            if timestamp <= after {
+                return false;
+            }
+        }
+        if let Some(before) = self.before {
+            if timestamp >= before {
+                return false;
+            }
+        }
+        true
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Scope {
@@ -680,12 +1770,172 @@ pub enum MatchingRule {
     EndsWith(String),
 }
 
+impl MatchingRule {
+    fn describe(&self) -> String {
+        match self {
+            MatchingRule::Equals(value) => format!("equal to {}", value),
+            MatchingRule::StartsWith(value) => format!("starting with {}", value),
+            MatchingRule::EndsWith(value) => format!("ending with {}", value),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExactMatchingRule {
     Equals(String),
 }
 
+/// Contract identifier targeted by a Stacks predicate: either a fully qualified identifier
+/// (`*` matching any contract), or a regex that must match the whole identifier,
+/// e.g. `{ "matches_regex": "SP[A-Z0-9]+\\.pool-v\\d+" }`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum ContractIdentifierRule {
+    Equals(String),
+    MatchesRegex { matches_regex: String },
+}
+
+impl ContractIdentifierRule {
+    pub fn matches(&self, contract_identifier: &str) -> bool {
+        self.matches_with_casing(contract_identifier, false)
+    }
+
+    /// Same as `matches`, but lowercases both sides of the comparison (or compiles the regex
+    /// case-insensitively) when `case_insensitive` is set.
+    pub fn matches_with_casing(&self, contract_identifier: &str, case_insensitive: bool) -> bool {
+        match self {
+            ContractIdentifierRule::Equals(expected) if expected.eq("*") => true,
+            ContractIdentifierRule::Equals(expected) if expected.ends_with('*') => {
+                let prefix = &expected[..expected.len() - 1];
+                match case_insensitive {
+                    true => contract_identifier
+                        .to_lowercase()
+                        .starts_with(&prefix.to_lowercase()),
+                    false => contract_identifier.starts_with(prefix),
+                }
+            }
+            ContractIdentifierRule::Equals(expected) if case_insensitive => {
+                expected.to_lowercase() == contract_identifier.to_lowercase()
+            }
+            ContractIdentifierRule::Equals(expected) => expected.eq(contract_identifier),
+            ContractIdentifierRule::MatchesRegex { matches_regex } => {
+                let regex = match case_insensitive {
+                    true => get_contract_identifier_regex(&format!("(?i){}", matches_regex)),
+                    false => get_contract_identifier_regex(matches_regex),
+                };
+                match regex {
+                    Ok(regex) => regex.is_match(contract_identifier),
+                    Err(_) => false,
+                }
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ContractIdentifierRule::Equals(expected) if expected.eq("*") => {
+                format!("any contract")
+            }
+            ContractIdentifierRule::Equals(expected) if expected.ends_with('*') => {
+                format!(
+                    "contracts starting with {}",
+                    &expected[..expected.len() - 1]
+                )
+            }
+            ContractIdentifierRule::Equals(expected) => expected.to_string(),
+            ContractIdentifierRule::MatchesRegex { matches_regex } => {
+                format!("contracts matching /{}/", matches_regex)
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ContractIdentifierRule::MatchesRegex { matches_regex } => {
+                get_contract_identifier_regex(matches_regex).map_err(|e| {
+                    format!("invalid contract_identifier regex {}: {}", matches_regex, e)
+                })?;
+            }
+            ContractIdentifierRule::Equals(expected)
+                if expected.ends_with('*') && !expected.eq("*") && !expected.contains('.') =>
+            {
+                return Err(format!(
+                    "contract_identifier prefix {} should include the deployer address",
+                    expected
+                ));
+            }
+            ContractIdentifierRule::Equals(expected)
+                if expected.eq("*") || expected.ends_with('*') => {}
+            ContractIdentifierRule::Equals(expected) => match expected.split_once('.') {
+                Some((deployer, contract_name))
+                    if !deployer.is_empty()
+                        && !contract_name.is_empty()
+                        && !contract_name.contains('.') => {}
+                _ => {
+                    return Err(format!(
+                        "contract_identifier {} should be formatted as <deployer>.<contract_name>",
+                        expected
+                    ));
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for ContractIdentifierRule {
+    fn from(contract_identifier: &str) -> Self {
+        ContractIdentifierRule::Equals(contract_identifier.to_string())
+    }
+}
+
+impl From<String> for ContractIdentifierRule {
+    fn from(contract_identifier: String) -> Self {
+        ContractIdentifierRule::Equals(contract_identifier)
+    }
+}
+
+fn get_contract_identifier_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    get_compiled_regex(&format!("^(?:{})$", pattern))
+}
+
+/// Regexes are compiled once per pattern, when the predicates using them are validated, and
+/// shared by every predicate evaluation.
+pub(crate) fn get_compiled_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    static REGEXES: OnceLock<DashMap<String, Regex>> = OnceLock::new();
+    let regexes = REGEXES.get_or_init(DashMap::new);
+    if let Some(regex) = regexes.get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = Regex::new(pattern)?;
+    regexes.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionMatchingRule {
+    Equals(u32),
+    In(Vec<u32>),
+}
+
+impl VersionMatchingRule {
+    pub fn matches(&self, version: u32) -> bool {
+        match self {
+            VersionMatchingRule::Equals(expected) => version.eq(expected),
+            VersionMatchingRule::In(expected) => expected.contains(&version),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueMatchingRule {
+    HigherThan(u64),
+    LowerThan(u64),
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct DescriptorMatchingRule {
@@ -710,6 +1960,75 @@ where
     }
 }
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentifierFormat {
+    /// `{ "index": 42, "hash": "0x..." }` for blocks, `{ "hash": "0x..." }` for transactions.
+    #[default]
+    Object,
+    /// `"42:0x..."` for blocks, `"0x..."` for transactions.
+    Compact,
+}
+
+impl IdentifierFormat {
+    pub fn serialize_block_identifier(&self, block_identifier: &BlockIdentifier) -> JsonValue {
+        match self {
+            IdentifierFormat::Object => json!(block_identifier),
+            IdentifierFormat::Compact => json!(format!(
+                "{}:{}",
+                block_identifier.index, block_identifier.hash
+            )),
+        }
+    }
+
+    pub fn serialize_transaction_identifier(
+        &self,
+        transaction_identifier: &TransactionIdentifier,
+    ) -> JsonValue {
+        match self {
+            IdentifierFormat::Object => json!(transaction_identifier),
+            IdentifierFormat::Compact => json!(transaction_identifier.hash),
+        }
+    }
+}
+
+/// Field computed from a serialized transaction, and delivered under its `derived` key.
+/// `source` is a JSON pointer into the transaction, e.g. `/metadata/receipt/events/0/data/amount`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DerivedFieldRule {
+    /// Value of `source` (a number, or a numeric string) multiplied by `factor`.
+    Multiply { source: String, factor: f64 },
+    /// Entry of `mapping` keyed by the value of `source`.
+    Lookup {
+        source: String,
+        mapping: BTreeMap<String, String>,
+    },
+}
+
+impl DerivedFieldRule {
+    /// Returns `None` when `source` is missing, not numeric, or not part of the mapping.
+    pub fn compute(&self, transaction: &JsonValue) -> Option<JsonValue> {
+        match self {
+            DerivedFieldRule::Multiply { source, factor } => {
+                let value = match transaction.pointer(source)? {
+                    JsonValue::Number(number) => number.as_f64()?,
+                    JsonValue::String(string) => string.parse::<f64>().ok()?,
+                    _ => return None,
+                };
+                serde_json::Number::from_f64(value * factor).map(JsonValue::Number)
+            }
+            DerivedFieldRule::Lookup { source, mapping } => {
+                let key = match transaction.pointer(source)? {
+                    JsonValue::String(string) => string.clone(),
+                    value => value.to_string(),
+                };
+                mapping.get(&key).map(|value| json!(value))
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum BlockIdentifierHashRule {
@@ -738,6 +2057,20 @@ pub struct StacksChainhookSpecification {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub decode_clarity_values: Option<bool>,
     pub include_contract_abi: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projection: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_mempool_timestamps: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier_format: Option<IdentifierFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived_fields: Option<BTreeMap<String, DerivedFieldRule>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_clarity_value_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_on: Option<TriggerOn>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_confirmations: Option<u64>,
     #[serde(rename = "predicate")]
     pub predicate: StacksPredicate,
     pub action: HookAction,
@@ -750,9 +2083,40 @@ impl StacksChainhookSpecification {
         ChainhookSpecification::stacks_key(&self.uuid)
     }
 
+    /// Checks the specification, returning all the errors found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = vec![];
+        validate_required_field("uuid", &self.uuid, &mut errors);
+        validate_required_field("name", &self.name, &mut errors);
+        validate_block_scope(
+            &self.start_block,
+            &self.end_block,
+            &self.expire_after_occurrence,
+            &mut errors,
+        );
+        if let Err(e) = self.predicate.validate() {
+            errors.push(e);
+        }
+        if let Err(e) = self.action.validate() {
+            errors.push(e);
+        }
+        if let Err(e) = validate_stacks_action(&self.action) {
+            errors.push(e);
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    pub fn builder() -> StacksChainhookSpecificationBuilder {
+        StacksChainhookSpecificationBuilder::default()
+    }
+
     pub fn is_predicate_targeting_block_header(&self) -> bool {
         match &self.predicate {
             StacksPredicate::BlockHeight(_)
+            | StacksPredicate::BlockTime(_)
             // | &StacksPredicate::BitcoinBlockHeight(_)
             => true,
             _ => false,
@@ -760,11 +2124,195 @@ impl StacksChainhookSpecification {
     }
 }
 
+/// Builds a [`StacksChainhookSpecification`] without having to spell out every optional field.
+/// `uuid`, `network` and `predicate` are required; `name` defaults to the uuid, `version` to 1,
+/// `action` to `noop`, and the predicate is enabled.
+#[derive(Clone, Debug, Default)]
+pub struct StacksChainhookSpecificationBuilder {
+    uuid: Option<String>,
+    owner_uuid: Option<String>,
+    name: Option<String>,
+    network: Option<StacksNetwork>,
+    version: Option<u32>,
+    blocks: Option<Vec<u64>>,
+    start_block: Option<u64>,
+    end_block: Option<u64>,
+    expire_after_occurrence: Option<u64>,
+    capture_all_events: Option<bool>,
+    decode_clarity_values: Option<bool>,
+    include_contract_abi: Option<bool>,
+    projection: Option<Vec<String>>,
+    include_mempool_timestamps: Option<bool>,
+    identifier_format: Option<IdentifierFormat>,
+    derived_fields: Option<BTreeMap<String, DerivedFieldRule>>,
+    max_clarity_value_length: Option<usize>,
+    trigger_on: Option<TriggerOn>,
+    min_confirmations: Option<u64>,
+    predicate: Option<StacksPredicate>,
+    action: Option<HookAction>,
+    enabled: Option<bool>,
+}
+
+impl StacksChainhookSpecificationBuilder {
+    pub fn uuid(mut self, uuid: impl Into<String>) -> Self {
+        self.uuid = Some(uuid.into());
+        self
+    }
+
+    pub fn owner_uuid(mut self, owner_uuid: impl Into<String>) -> Self {
+        self.owner_uuid = Some(owner_uuid.into());
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn network(mut self, network: StacksNetwork) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    pub fn version(mut self, version: u32) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    pub fn blocks(mut self, blocks: Vec<u64>) -> Self {
+        self.blocks = Some(blocks);
+        self
+    }
+
+    pub fn start_block(mut self, start_block: u64) -> Self {
+        self.start_block = Some(start_block);
+        self
+    }
+
+    pub fn end_block(mut self, end_block: u64) -> Self {
+        self.end_block = Some(end_block);
+        self
+    }
+
+    pub fn expire_after_occurrence(mut self, expire_after_occurrence: u64) -> Self {
+        self.expire_after_occurrence = Some(expire_after_occurrence);
+        self
+    }
+
+    pub fn capture_all_events(mut self, capture_all_events: bool) -> Self {
+        self.capture_all_events = Some(capture_all_events);
+        self
+    }
+
+    pub fn decode_clarity_values(mut self, decode_clarity_values: bool) -> Self {
+        self.decode_clarity_values = Some(decode_clarity_values);
+        self
+    }
+
+    pub fn include_contract_abi(mut self, include_contract_abi: bool) -> Self {
+        self.include_contract_abi = Some(include_contract_abi);
+        self
+    }
+
+    pub fn projection(mut self, projection: Vec<String>) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    pub fn include_mempool_timestamps(mut self, include_mempool_timestamps: bool) -> Self {
+        self.include_mempool_timestamps = Some(include_mempool_timestamps);
+        self
+    }
+
+    pub fn identifier_format(mut self, identifier_format: IdentifierFormat) -> Self {
+        self.identifier_format = Some(identifier_format);
+        self
+    }
+
+    pub fn derived_fields(mut self, derived_fields: BTreeMap<String, DerivedFieldRule>) -> Self {
+        self.derived_fields = Some(derived_fields);
+        self
+    }
+
+    pub fn max_clarity_value_length(mut self, max_clarity_value_length: usize) -> Self {
+        self.max_clarity_value_length = Some(max_clarity_value_length);
+        self
+    }
+
+    pub fn trigger_on(mut self, trigger_on: TriggerOn) -> Self {
+        self.trigger_on = Some(trigger_on);
+        self
+    }
+
+    pub fn min_confirmations(mut self, min_confirmations: u64) -> Self {
+        self.min_confirmations = Some(min_confirmations);
+        self
+    }
+
+    pub fn predicate(mut self, predicate: StacksPredicate) -> Self {
+        self.predicate = Some(predicate);
+        self
+    }
+
+    pub fn action(mut self, action: HookAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = Some(enabled);
+        self
+    }
+
+    pub fn build(self) -> Result<StacksChainhookSpecification, String> {
+        let uuid = self.uuid.ok_or("missing field `uuid`")?;
+        let network = self.network.ok_or("missing field `network`")?;
+        let predicate = self.predicate.ok_or("missing field `predicate`")?;
+        let action = self.action.unwrap_or(HookAction::Noop);
+        predicate.validate()?;
+        action.validate()?;
+        if let Some(end_block) = self.end_block {
+            if self.start_block.unwrap_or(0) > end_block {
+                return Err(
+                    "Chainhook specification field `end_block` should be greater than `start_block`."
+                        .into(),
+                );
+            }
+        }
+        Ok(StacksChainhookSpecification {
+            name: self.name.unwrap_or_else(|| uuid.clone()),
+            uuid,
+            owner_uuid: self.owner_uuid,
+            network,
+            version: self.version.unwrap_or(1),
+            blocks: self.blocks,
+            start_block: self.start_block,
+            end_block: self.end_block,
+            expire_after_occurrence: self.expire_after_occurrence,
+            capture_all_events: self.capture_all_events,
+            decode_clarity_values: self.decode_clarity_values,
+            include_contract_abi: self.include_contract_abi,
+            projection: self.projection,
+            include_mempool_timestamps: self.include_mempool_timestamps,
+            identifier_format: self.identifier_format,
+            derived_fields: self.derived_fields,
+            max_clarity_value_length: self.max_clarity_value_length,
+            trigger_on: self.trigger_on,
+            min_confirmations: self.min_confirmations,
+            predicate,
+            action,
+            enabled: self.enabled.unwrap_or(true),
+            expired_at: None,
+        })
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "scope")]
 pub enum StacksPredicate {
     BlockHeight(BlockIdentifierIndexRule),
+    BlockTime(TimeRange),
     ContractDeployment(StacksContractDeploymentPredicate),
     ContractCall(StacksContractCallBasedPredicate),
     PrintEvent(StacksPrintEventBasedPredicate),
@@ -772,22 +2320,453 @@ pub enum StacksPredicate {
     NftEvent(StacksNftEventBasedPredicate),
     StxEvent(StacksStxEventBasedPredicate),
     Txid(ExactMatchingRule),
+    Nonce(StacksNonceBasedPredicate),
+    EventRatio(StacksEventRatioBasedPredicate),
+    EventCount(StacksEventCountBasedPredicate),
+    EventSequence(StacksEventSequenceBasedPredicate),
+    StxTransferRecipient(StacksStxTransferRecipientBasedPredicate),
+    ContractCallPostConditionAbort(StacksContractCallBasedPredicate),
+    ContractInteractions(StacksContractInteractionsBasedPredicate),
+    Coinbase(StacksCoinbasePredicate),
+    AllOf(StacksPredicateCombination),
+    AnyOf(StacksPredicateCombination),
+    Not(StacksNegatedPredicate),
+}
+
+impl StacksPredicate {
+    pub fn validate(&self) -> Result<(), String> {
+        match &self {
+            StacksPredicate::AllOf(combination) | StacksPredicate::AnyOf(combination) => {
+                if combination.predicates.is_empty() {
+                    return Err(format!(
+                        "all_of / any_of predicate field `predicates` should not be empty"
+                    ));
+                }
+                for predicate in combination.predicates.iter() {
+                    predicate.validate_combined()?;
+                }
+            }
+            StacksPredicate::Not(negation) => {
+                negation.predicate.validate_combined()?;
+            }
+            StacksPredicate::EventRatio(expected_ratio) => {
+                if expected_ratio.higher_than >= 100 {
+                    return Err(format!(
+                        "event_ratio predicate field `higher_than` should be lower than 100"
+                    ));
+                }
+            }
+            StacksPredicate::EventSequence(expected_sequence) => {
+                if expected_sequence.events.is_empty() {
+                    return Err(format!(
+                        "event_sequence predicate field `events` should not be empty"
+                    ));
+                }
+            }
+            StacksPredicate::ContractCall(expected_contract_call)
+            | StacksPredicate::ContractCallPostConditionAbort(expected_contract_call) => {
+                expected_contract_call.contract_identifier.validate()?;
+                if expected_contract_call.method.trim().is_empty() {
+                    return Err(format!(
+                        "contract_call predicate field `method` should not be empty"
+                    ));
+                }
+            }
+            StacksPredicate::PrintEvent(expected_event) => {
+                expected_event.validate()?;
+            }
+            StacksPredicate::NftEvent(StacksNftEventBasedPredicate {
+                token_id_range: Some(token_id_range),
+                ..
+            }) => {
+                if token_id_range.min > token_id_range.max {
+                    return Err(format!(
+                        "nft_event predicate field `token_id_range.min` should not be greater than `token_id_range.max`"
+                    ));
+                }
+            }
+            StacksPredicate::StxEvent(StacksStxEventBasedPredicate {
+                amount_greater_than: Some(min),
+                amount_less_than: Some(max),
+                ..
+            }) => {
+                if min >= max {
+                    return Err(format!(
+                        "stx_event predicate field `amount_greater_than` should be lower than `amount_less_than`"
+                    ));
+                }
+            }
+            StacksPredicate::ContractDeployment(
+                StacksContractDeploymentPredicate::ContractIdentifier(contract_identifier),
+            ) => {
+                contract_identifier.validate()?;
+            }
+            StacksPredicate::ContractDeployment(
+                StacksContractDeploymentPredicate::SourceContains(contains),
+            ) => {
+                if contains.is_empty() {
+                    return Err(format!(
+                        "contract_deployment predicate field `source_contains` should not be empty"
+                    ));
+                }
+            }
+            StacksPredicate::StxTransferRecipient(StacksStxTransferRecipientBasedPredicate {
+                memo: Some(memo),
+                ..
+            }) => {
+                memo.validate()?;
+            }
+            StacksPredicate::ContractInteractions(expected_interactions) => {
+                if expected_interactions.all_of.is_empty() {
+                    return Err(format!(
+                        "contract_interactions predicate field `all_of` should not be empty"
+                    ));
+                }
+            }
+            StacksPredicate::BlockTime(TimeRange {
+                after: None,
+                before: None,
+            }) => {
+                return Err(format!(
+                    "block_time predicate should specify `after` or `before`"
+                ));
+            }
+            StacksPredicate::BlockTime(TimeRange {
+                after: Some(after),
+                before: Some(before),
+            }) if after >= before => {
+                return Err(format!(
+                    "block_time predicate field `after` should be lower than `before`"
+                ));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Combined predicates are evaluated against the same transaction, which rules out
+    /// block scoped predicates.
+    fn validate_combined(&self) -> Result<(), String> {
+        match self {
+            StacksPredicate::BlockHeight(_) => {
+                return Err(format!(
+                    "block_height predicates can not be combined with all_of / any_of / not"
+                ));
+            }
+            StacksPredicate::BlockTime(_) => {
+                return Err(format!(
+                    "block_time predicates can not be combined with all_of / any_of / not"
+                ));
+            }
+            _ => {}
+        }
+        self.validate()
+    }
+
+    /// Human readable description of the predicate, e.g. "FT transfer of asset-id".
+    pub fn describe(&self) -> String {
+        match &self {
+            StacksPredicate::AllOf(combination) => {
+                format!("All of ({})", join_descriptions(&combination.describe()))
+            }
+            StacksPredicate::AnyOf(combination) => {
+                format!("Any of ({})", join_descriptions(&combination.describe()))
+            }
+            StacksPredicate::Not(negation) => format!("Not ({})", negation.predicate.describe()),
+            StacksPredicate::BlockHeight(rule) => format!("Block height {}", rule.describe()),
+            StacksPredicate::BlockTime(range) => match (range.after, range.before) {
+                (Some(after), Some(before)) => {
+                    format!("Block time between {} and {}", after, before)
+                }
+                (Some(after), None) => format!("Block time after {}", after),
+                (None, Some(before)) => format!("Block time before {}", before),
+                (None, None) => format!("Any block time"),
+            },
+            StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::Deployer(
+                deployer,
+            )) => match deployer.deployer().as_str() {
+                "*" => format!("Any contract deployment"),
+                deployer => format!("Contract deployment by {}", deployer),
+            },
+            StacksPredicate::ContractDeployment(
+                StacksContractDeploymentPredicate::ImplementTrait(stacks_trait),
+            ) => {
+                let stacks_trait = match stacks_trait {
+                    StacksTrait::Sip09 => "SIP-009",
+                    StacksTrait::Sip10 => "SIP-010",
+                    StacksTrait::Any => "any trait",
+                };
+                format!("Contract deployment implementing {}", stacks_trait)
+            }
+            StacksPredicate::ContractDeployment(
+                StacksContractDeploymentPredicate::ContractIdentifier(contract_identifier),
+            ) => format!("Deployment of {}", contract_identifier.describe()),
+            StacksPredicate::ContractDeployment(StacksContractDeploymentPredicate::SourceSize(
+                rule,
+            )) => match rule {
+                ValueMatchingRule::HigherThan(size) => {
+                    format!(
+                        "Contract deployment with a source larger than {} bytes",
+                        size
+                    )
+                }
+                ValueMatchingRule::LowerThan(size) => {
+                    format!(
+                        "Contract deployment with a source smaller than {} bytes",
+                        size
+                    )
+                }
+            },
+            StacksPredicate::ContractDeployment(
+                StacksContractDeploymentPredicate::SourceContains(contains),
+            ) => match contains.as_str() {
+                "*" => format!("Any contract deployment"),
+                contains => format!(
+                    "Contract deployment with a source containing \"{}\"",
+                    contains
+                ),
+            },
+            StacksPredicate::ContractCall(contract_call) => format!(
+                "Call of {} on {}",
+                contract_call.method,
+                contract_call.contract_identifier.describe()
+            ),
+            StacksPredicate::PrintEvent(print_event) => {
+                let contract_identifier = print_event.contract_identifier().describe();
+                let mut description = match print_event {
+                    StacksPrintEventBasedPredicate::Contains { contains, .. } => {
+                        match contains.as_str() {
+                            "*" => format!("Print event of {}", contract_identifier),
+                            contains => format!(
+                                "Print event of {} containing \"{}\"",
+                                contract_identifier, contains
+                            ),
+                        }
+                    }
+                    StacksPrintEventBasedPredicate::MatchesRegex { regex, .. } => format!(
+                        "Print event of {} matching /{}/",
+                        contract_identifier, regex
+                    ),
+                    StacksPrintEventBasedPredicate::EventName { event_name, .. } => {
+                        format!("Print event {} of {}", event_name, contract_identifier)
+                    }
+                };
+                if let Some(method) = print_event.emitting_method() {
+                    description.push_str(&format!(" emitted by {}", method));
+                }
+                description
+            }
+            StacksPredicate::FtEvent(ft_event) => {
+                let mut description = format!(
+                    "FT {} of {}",
+                    ft_event.actions.join("/"),
+                    ft_event.asset_identifier
+                );
+                if let Some(ref method) = ft_event.emitting_method {
+                    description.push_str(&format!(" emitted by {}", method));
+                }
+                description.push_str(&describe_principals(&ft_event.sender, &ft_event.recipient));
+                description
+            }
+            StacksPredicate::NftEvent(nft_event) => {
+                let mut description = format!(
+                    "NFT {} of {}",
+                    nft_event.actions.join("/"),
+                    nft_event.asset_identifier
+                );
+                if let Some(ref range) = nft_event.token_id_range {
+                    description.push_str(&format!(
+                        " with token id between {} and {}",
+                        range.min, range.max
+                    ));
+                }
+                description.push_str(&describe_principals(
+                    &nft_event.sender,
+                    &nft_event.recipient,
+                ));
+                description
+            }
+            StacksPredicate::StxEvent(stx_event) => {
+                let mut description = format!("STX {}", stx_event.actions.join("/"));
+                match (stx_event.amount_greater_than, stx_event.amount_less_than) {
+                    (Some(min), Some(max)) => description
+                        .push_str(&format!(" of at least {} and less than {} uSTX", min, max)),
+                    (Some(min), None) => {
+                        description.push_str(&format!(" of at least {} uSTX", min))
+                    }
+                    (None, Some(max)) => {
+                        description.push_str(&format!(" of less than {} uSTX", max))
+                    }
+                    (None, None) => {}
+                }
+                description.push_str(&describe_principals(
+                    &stx_event.sender,
+                    &stx_event.recipient,
+                ));
+                description
+            }
+            StacksPredicate::Txid(ExactMatchingRule::Equals(txid)) => {
+                format!("Transaction {}", txid)
+            }
+            StacksPredicate::Nonce(nonce) => {
+                let rule = nonce.nonce.describe();
+                match nonce.sender {
+                    Some(ref sender) => format!("Nonce {} of {}", rule, sender),
+                    None => format!("Nonce {}", rule),
+                }
+            }
+            StacksPredicate::EventRatio(ratio) => format!(
+                "More than {}% of {} events",
+                ratio.higher_than,
+                ratio.event_type.describe()
+            ),
+            StacksPredicate::EventCount(count) => {
+                format!("More than {} events", count.higher_than)
+            }
+            StacksPredicate::EventSequence(sequence) => {
+                let events = sequence
+                    .events
+                    .iter()
+                    .map(|event| {
+                        let mut description = event.event_type.describe().to_string();
+                        if let Some(ref action) = event.action {
+                            description.push_str(&format!(" {}", action));
+                        }
+                        if let Some(ref asset_identifier) = event.asset_identifier {
+                            description.push_str(&format!(" of {}", asset_identifier));
+                        }
+                        description
+                    })
+                    .collect::<Vec<_>>();
+                format!("Event sequence {}", events.join(" then "))
+            }
+            StacksPredicate::StxTransferRecipient(recipient) => {
+                let principal_type = match recipient.principal_type {
+                    StacksPrincipalType::Standard => "standard",
+                    StacksPrincipalType::Contract => "contract",
+                };
+                let mut description = format!("STX transfer to a {} principal", principal_type);
+                match recipient.memo {
+                    Some(StacksMemoMatchingRule::Contains(ref expected)) => {
+                        description.push_str(&format!(" with memo containing \"{}\"", expected))
+                    }
+                    Some(StacksMemoMatchingRule::MatchesRegex(ref expected)) => {
+                        description.push_str(&format!(" with memo matching /{}/", expected))
+                    }
+                    None => {}
+                }
+                description
+            }
+            StacksPredicate::ContractCallPostConditionAbort(contract_call) => format!(
+                "Post condition abort of call of {} on {}",
+                contract_call.method,
+                contract_call.contract_identifier.describe()
+            ),
+            StacksPredicate::Coinbase(coinbase) => match coinbase.recipient {
+                Some(ref recipient) => format!("Coinbase paid to {}", recipient),
+                None => format!("Coinbase"),
+            },
+            StacksPredicate::ContractInteractions(interactions) => format!(
+                "Interaction with all of {}",
+                join_descriptions(&interactions.all_of)
+            ),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct StacksContractCallBasedPredicate {
-    pub contract_identifier: String,
+    pub contract_identifier: ContractIdentifierRule,
     pub method: String,
+    /// Compare the whole contract identifier (address and contract name) regardless of casing.
+    /// The method name is always compared as is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_insensitive: Option<bool>,
+    /// Constraints on the arguments of the call, all of which must be satisfied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<StacksContractCallArgMatch>>,
+}
+
+/// Expected value of the argument at `index` (starting at 0) of a contract call, `*` matching
+/// any value as long as the argument is present.
+/// When the predicate decodes Clarity values, `equals` is compared with the decoded argument
+/// (e.g. `"ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM"` or `"100"`), otherwise with the raw
+/// argument.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksContractCallArgMatch {
+    pub index: usize,
+    pub equals: String,
+}
+
+impl StacksContractCallBasedPredicate {
+    pub fn matches_contract_identifier(&self, contract_identifier: &str) -> bool {
+        self.contract_identifier
+            .matches_with_casing(contract_identifier, self.case_insensitive.unwrap_or(false))
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 // #[serde(tag = "type", content = "rule")]
 pub enum StacksContractDeploymentPredicate {
-    Deployer(String),
+    Deployer(StacksDeployerRule),
     ImplementTrait(StacksTrait),
+    ContractIdentifier(ContractIdentifierRule),
+    /// Size of the deployed source code, in bytes.
+    SourceSize(ValueMatchingRule),
+    /// Substring of the deployed source code, `*` matching any deployment.
+    SourceContains(String),
+}
+
+/// Principal deploying a contract: either a plain principal (`*` matching any deployer), or
+/// `{ "equals": "SP...", "case_insensitive": true }` to compare the deployer address,
+/// i.e. the part of the contract identifier before the `.`, regardless of casing.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum StacksDeployerRule {
+    Equals(String),
+    EqualsWithOptions {
+        equals: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        case_insensitive: Option<bool>,
+    },
+}
+
+impl StacksDeployerRule {
+    pub fn deployer(&self) -> &String {
+        match self {
+            StacksDeployerRule::Equals(deployer)
+            | StacksDeployerRule::EqualsWithOptions {
+                equals: deployer, ..
+            } => deployer,
+        }
+    }
+
+    pub fn matches(&self, contract_identifier: &str) -> bool {
+        let deployer = self.deployer();
+        if deployer.eq("*") {
+            return true;
+        }
+        match self {
+            StacksDeployerRule::EqualsWithOptions {
+                case_insensitive: Some(true),
+                ..
+            } => contract_identifier
+                .to_lowercase()
+                .starts_with(&deployer.to_lowercase()),
+            _ => contract_identifier.starts_with(deployer.as_str()),
+        }
+    }
 }
+
+impl From<&str> for StacksDeployerRule {
+    fn from(deployer: &str) -> Self {
+        StacksDeployerRule::Equals(deployer.to_string())
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum StacksTrait {
@@ -802,21 +2781,99 @@ pub enum StacksTrait {
 #[serde(untagged)]
 pub enum StacksPrintEventBasedPredicate {
     Contains {
-        contract_identifier: String,
+        contract_identifier: ContractIdentifierRule,
         contains: String,
+        /// Only match events emitted by transactions calling this method.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        emitting_method: Option<String>,
     },
     MatchesRegex {
-        contract_identifier: String,
+        contract_identifier: ContractIdentifierRule,
         #[serde(rename = "matches_regex")]
         regex: String,
+        /// Only match events emitted by transactions calling this method.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        emitting_method: Option<String>,
+    },
+    EventName {
+        contract_identifier: ContractIdentifierRule,
+        event_name: String,
+        /// Tuple field holding the event name. When omitted, `event` and `event-name` are tried.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        event_name_key: Option<String>,
+        /// Only match events emitted by transactions calling this method.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        emitting_method: Option<String>,
     },
 }
 
+impl StacksPrintEventBasedPredicate {
+    pub fn contract_identifier(&self) -> &ContractIdentifierRule {
+        match self {
+            StacksPrintEventBasedPredicate::Contains {
+                contract_identifier,
+                ..
+            }
+            | StacksPrintEventBasedPredicate::MatchesRegex {
+                contract_identifier,
+                ..
+            }
+            | StacksPrintEventBasedPredicate::EventName {
+                contract_identifier,
+                ..
+            } => contract_identifier,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.contract_identifier().validate()?;
+        if let StacksPrintEventBasedPredicate::MatchesRegex { regex, .. } = self {
+            get_compiled_regex(regex)
+                .map_err(|e| format!("invalid print_event regex {}: {}", regex, e))?;
+        }
+        Ok(())
+    }
+
+    pub fn emitting_method(&self) -> Option<&String> {
+        match self {
+            StacksPrintEventBasedPredicate::Contains {
+                emitting_method, ..
+            }
+            | StacksPrintEventBasedPredicate::MatchesRegex {
+                emitting_method, ..
+            }
+            | StacksPrintEventBasedPredicate::EventName {
+                emitting_method, ..
+            } => emitting_method.as_ref(),
+        }
+    }
+}
+
+fn describe_principals(sender: &Option<String>, recipient: &Option<String>) -> String {
+    let mut description = String::new();
+    if let Some(sender) = sender.as_deref().filter(|sender| *sender != "*") {
+        description.push_str(&format!(" from {}", sender));
+    }
+    if let Some(recipient) = recipient.as_deref().filter(|recipient| *recipient != "*") {
+        description.push_str(&format!(" to {}", recipient));
+    }
+    description
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct StacksFtEventBasedPredicate {
     pub asset_identifier: String,
     pub actions: Vec<String>,
+    /// Only match events emitted by transactions calling this method.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emitting_method: Option<String>,
+    /// Only match transfers and burns of tokens from this principal, `*` matching any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    /// Only match transfers and mints of tokens to this principal, `*` matching any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
@@ -824,12 +2881,249 @@ pub struct StacksFtEventBasedPredicate {
 pub struct StacksNftEventBasedPredicate {
     pub asset_identifier: String,
     pub actions: Vec<String>,
+    /// Only match events on integer token ids within this range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_id_range: Option<NftTokenIdRange>,
+    /// Only match transfers and burns of tokens owned by this principal, `*` matching any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    /// Only match transfers and mints of tokens to this principal, `*` matching any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+}
+
+/// Inclusive range of NFT token ids.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct NftTokenIdRange {
+    pub min: u128,
+    pub max: u128,
+}
+
+impl NftTokenIdRange {
+    pub fn contains(&self, token_id: u128) -> bool {
+        self.min <= token_id && token_id <= self.max
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct StacksStxEventBasedPredicate {
     pub actions: Vec<String>,
+    /// Only match events moving at least this amount, in uSTX.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_greater_than: Option<u128>,
+    /// Only match events moving less than this amount, in uSTX.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_less_than: Option<u128>,
+    /// Only match STX transferred, burnt or locked by this principal, `*` matching any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    /// Only match STX transferred or minted to this principal, `*` matching any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksNonceBasedPredicate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    /// `between` bounds are inclusive.
+    pub nonce: BlockIdentifierIndexRule,
+}
+
+/// Match transactions where strictly more than `higher_than` percent of the
+/// events emitted are of type `event_type`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksEventRatioBasedPredicate {
+    pub event_type: StacksEventType,
+    pub higher_than: u8,
+}
+
+/// Match transactions emitting strictly more than `higher_than` events, whatever their
+/// types.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksEventCountBasedPredicate {
+    pub higher_than: u64,
+}
+
+/// Match transactions interacting with every contract of `all_of`. A transaction interacts
+/// with the contract it calls or deploys, with the contracts emitting its print and data
+/// events, and with the contracts defining the fungible / non fungible tokens it moves.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksContractInteractionsBasedPredicate {
+    pub all_of: Vec<String>,
+}
+
+/// Sub-predicates of `all_of` / `any_of`, all evaluated against the same transaction.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksPredicateCombination {
+    pub predicates: Vec<StacksPredicate>,
+}
+
+impl StacksPredicateCombination {
+    fn describe(&self) -> Vec<String> {
+        self.predicates
+            .iter()
+            .map(|predicate| predicate.describe())
+            .collect()
+    }
+}
+
+/// Match transactions not satisfying `predicate`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksNegatedPredicate {
+    pub predicate: Box<StacksPredicate>,
+}
+
+/// Match transactions emitting events satisfying each of the `events` constraints,
+/// in the given order. Unrelated events can be interleaved.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksEventSequenceBasedPredicate {
+    pub events: Vec<StacksEventConstraint>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksEventConstraint {
+    pub event_type: StacksEventType,
+    /// One of `mint`, `transfer`, `burn` or `lock`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    /// Fungible / non-fungible token asset identifier.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset_identifier: Option<String>,
+}
+
+/// Match the coinbase transaction of each block, optionally paying the block reward to
+/// `recipient`. The reward is paid to the alternate recipient of the coinbase when set, to the
+/// miner issuing the coinbase transaction otherwise.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksCoinbasePredicate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+}
+
+/// Match transactions emitting STX transfer events whose recipient is a principal
+/// of type `principal_type`, optionally constrained on the memo of the transfer.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StacksStxTransferRecipientBasedPredicate {
+    pub principal_type: StacksPrincipalType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<StacksMemoMatchingRule>,
+}
+
+/// Rule applied to the memo of a STX transfer, decoded as ASCII once its trailing zero
+/// padding is stripped. Transfers without memo, or with a non-ASCII memo, never match.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StacksMemoMatchingRule {
+    Contains(String),
+    MatchesRegex(String),
+}
+
+impl StacksMemoMatchingRule {
+    pub fn matches(&self, memo: &str) -> bool {
+        match self {
+            StacksMemoMatchingRule::Contains(expected) => memo.contains(expected.as_str()),
+            StacksMemoMatchingRule::MatchesRegex(expected) => match get_compiled_regex(expected) {
+                Ok(regex) => regex.is_match(memo),
+                Err(_) => false,
+            },
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let StacksMemoMatchingRule::MatchesRegex(expected) = self {
+            get_compiled_regex(expected)
+                .map_err(|e| format!("invalid memo regex {}: {}", expected, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StacksPrincipalType {
+    Standard,
+    Contract,
+}
+
+impl StacksPrincipalType {
+    /// Contract principals are qualified with a contract name: `<address>.<contract-name>`.
+    pub fn from_principal(principal: &str) -> StacksPrincipalType {
+        match principal.contains('.') {
+            true => StacksPrincipalType::Contract,
+            false => StacksPrincipalType::Standard,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StacksEventType {
+    FtEvent,
+    NftEvent,
+    StxEvent,
+    PrintEvent,
+    DataVarEvent,
+    DataMapEvent,
+}
+
+impl StacksEventType {
+    fn describe(&self) -> &'static str {
+        match self {
+            StacksEventType::FtEvent => "FT",
+            StacksEventType::NftEvent => "NFT",
+            StacksEventType::StxEvent => "STX",
+            StacksEventType::PrintEvent => "print",
+            StacksEventType::DataVarEvent => "data var",
+            StacksEventType::DataMapEvent => "data map",
+        }
+    }
+}
+
+fn join_descriptions<T: std::fmt::Display>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Derive a stable uuid for specifications registered without one, out of the content
+/// of their network, predicate and action. Identical specifications get identical uuids.
+pub fn derive_specification_uuid<N, P>(network: &N, predicate: &P, action: &HookAction) -> String
+where
+    N: Serialize,
+    P: Serialize,
+{
+    let content = serde_json::to_vec(&(network, predicate, action))
+        .expect("unable to serialize chainhook specification");
+    let digest = sha256::Hash::hash(&content).to_byte_array();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[0..16]);
+    // Flag the uuid as a version 8 (custom) / RFC 4122 variant uuid.
+    bytes[6] = (bytes[6] & 0x0f) | 0x80;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
 }
 
 pub fn opcode_to_hex(asm: &str) -> Option<u8> {
@@ -0,0 +1,9 @@
+use chainhook_types::BitcoinBlockData;
+
+use crate::indexer::BlockCache;
+
+pub const DEFAULT_BITCOIN_BLOCK_CACHE_CAPACITY: usize = 128;
+
+/// Standardized Bitcoin blocks, so that scans of overlapping ranges only fetch each block
+/// once from the Bitcoin node.
+pub type BitcoinBlockCache = BlockCache<BitcoinBlockData>;
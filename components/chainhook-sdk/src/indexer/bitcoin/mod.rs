@@ -1,3 +1,8 @@
+mod blocks_cache;
+
+pub use blocks_cache::{BitcoinBlockCache, DEFAULT_BITCOIN_BLOCK_CACHE_CAPACITY};
+
+use std::fmt;
 use std::time::Duration;
 
 use crate::chainhooks::types::{
@@ -62,8 +67,16 @@ impl BitcoinBlockFullBreakdown {
 #[serde(rename_all = "camelCase")]
 pub struct BitcoinTransactionFullBreakdown {
     pub txid: String,
+    /// Signed in the responses of older bitcoind versions.
+    pub version: i64,
     pub vin: Vec<BitcoinTransactionInputFullBreakdown>,
     pub vout: Vec<BitcoinTransactionOutputFullBreakdown>,
+    /// Raw transaction, provided by `getblock` with a verbosity of 2 or more.
+    #[serde(default)]
+    pub hex: Option<String>,
+    /// Virtual size, provided by `getblock` with a verbosity of 2 or more.
+    #[serde(default)]
+    pub vsize: Option<u64>,
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
@@ -105,6 +118,9 @@ pub struct BitcoinTransactionInputPrevoutFullBreakdown {
     pub height: u64,
     #[serde(with = "bitcoin::amount::serde::as_btc")]
     pub value: Amount,
+    /// Provided by bitcoind 25.0+ with `getblock` verbosity 3.
+    #[serde(default)]
+    pub script_pub_key: Option<GetRawTransactionResultVoutScriptPubKey>,
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
@@ -145,12 +161,37 @@ pub fn build_http_client() -> HttpClient {
         .expect("Unable to build http client")
 }
 
+/// Failure to retrieve a block from the Bitcoin node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitcoinRpcError {
+    /// The node could not be reached.
+    Connection(String),
+    /// The node answered with an error, or with a response that could not be parsed.
+    Response(String),
+}
+
+impl fmt::Display for BitcoinRpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitcoinRpcError::Connection(message) | BitcoinRpcError::Response(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl From<BitcoinRpcError> for String {
+    fn from(error: BitcoinRpcError) -> String {
+        error.to_string()
+    }
+}
+
 pub async fn download_and_parse_block_with_retry(
     http_client: &HttpClient,
     block_hash: &str,
     bitcoin_config: &BitcoinConfig,
     ctx: &Context,
-) -> Result<BitcoinBlockFullBreakdown, String> {
+) -> Result<BitcoinBlockFullBreakdown, BitcoinRpcError> {
     let mut errors_count = 0;
     let block = loop {
         match download_and_parse_block(http_client, block_hash, bitcoin_config, ctx).await {
@@ -177,7 +218,7 @@ pub async fn retrieve_block_hash_with_retry(
     block_height: &u64,
     bitcoin_config: &BitcoinConfig,
     ctx: &Context,
-) -> Result<String, String> {
+) -> Result<String, BitcoinRpcError> {
     let mut errors_count = 0;
     let block_hash = loop {
         match retrieve_block_hash(http_client, block_height, bitcoin_config, ctx).await {
@@ -204,7 +245,7 @@ pub async fn retrieve_block_hash(
     block_height: &u64,
     bitcoin_config: &BitcoinConfig,
     _ctx: &Context,
-) -> Result<String, String> {
+) -> Result<String, BitcoinRpcError> {
     let body = json!({
         "jsonrpc": "1.0",
         "id": "chainhook-cli",
@@ -219,12 +260,12 @@ pub async fn retrieve_block_hash(
         .json(&body)
         .send()
         .await
-        .map_err(|e| format!("unable to send request ({})", e))?
+        .map_err(|e| BitcoinRpcError::Connection(format!("unable to send request ({})", e)))?
         .json::<bitcoincore_rpc::jsonrpc::Response>()
         .await
-        .map_err(|e| format!("unable to parse response ({})", e))?
+        .map_err(|e| BitcoinRpcError::Response(format!("unable to parse response ({})", e)))?
         .result::<String>()
-        .map_err(|e| format!("unable to parse response ({})", e))?;
+        .map_err(|e| BitcoinRpcError::Response(format!("unable to parse response ({})", e)))?;
 
     Ok(block_hash)
 }
@@ -269,7 +310,7 @@ pub async fn download_block(
     block_hash: &str,
     bitcoin_config: &BitcoinConfig,
     _ctx: &Context,
-) -> Result<Vec<u8>, String> {
+) -> Result<Vec<u8>, BitcoinRpcError> {
     let body = json!({
         "jsonrpc": "1.0",
         "id": "chainhook-cli",
@@ -284,10 +325,10 @@ pub async fn download_block(
         .json(&body)
         .send()
         .await
-        .map_err(|e| format!("unable to send request ({})", e))?
+        .map_err(|e| BitcoinRpcError::Connection(format!("unable to send request ({})", e)))?
         .bytes()
         .await
-        .map_err(|e| format!("unable to get bytes ({})", e))?
+        .map_err(|e| BitcoinRpcError::Connection(format!("unable to get bytes ({})", e)))?
         .to_vec();
     Ok(block)
 }
@@ -307,14 +348,17 @@ pub async fn download_and_parse_block(
     block_hash: &str,
     bitcoin_config: &BitcoinConfig,
     _ctx: &Context,
-) -> Result<BitcoinBlockFullBreakdown, String> {
+) -> Result<BitcoinBlockFullBreakdown, BitcoinRpcError> {
     let response = download_block(http_client, block_hash, bitcoin_config, _ctx).await?;
-    parse_downloaded_block(response)
+    parse_downloaded_block(response).map_err(BitcoinRpcError::Response)
 }
 
+/// Standardizes a block, keeping the raw transactions only when `include_raw_tx` is set, i.e.
+/// when some predicate delivers them.
 pub fn standardize_bitcoin_block(
     block: BitcoinBlockFullBreakdown,
     network: &BitcoinNetwork,
+    include_raw_tx: bool,
     ctx: &Context,
 ) -> Result<BitcoinBlockData, (String, bool)> {
     let mut transactions = vec![];
@@ -385,6 +429,10 @@ pub fn standardize_bitcoin_block(
                     vout,
                     block_height: prevout.height,
                     value: prevout.value.to_sat(),
+                    script_pubkey: prevout
+                        .script_pub_key
+                        .as_ref()
+                        .map(|script_pub_key| format!("0x{}", hex::encode(&script_pub_key.hex))),
                 },
                 script_sig: format!("0x{}", script_sig.hex),
                 sequence: input.sequence,
@@ -415,12 +463,18 @@ pub fn standardize_bitcoin_block(
             },
             operations: vec![],
             metadata: BitcoinTransactionMetadata {
+                version: tx.version as u32,
                 inputs,
                 outputs,
                 stacks_operations,
                 ordinal_operations: vec![],
                 proof: None,
                 fee: sats_in.saturating_sub(sats_out),
+                raw_tx: match include_raw_tx {
+                    true => tx.hex.map(|hex| format!("0x{}", hex)),
+                    false => None,
+                },
+                vsize: tx.vsize,
             },
         };
         transactions.push(tx);
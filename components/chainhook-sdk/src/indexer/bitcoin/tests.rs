@@ -1,4 +1,13 @@
-use super::super::tests::{helpers, process_bitcoin_blocks_and_check_expectations};
+use super::super::fork_scratch_pad::ForkScratchPad;
+use super::super::tests::helpers::bitcoin_blocks;
+use super::super::tests::{
+    helpers, process_bitcoin_blocks_and_check_expectations,
+    process_bitcoin_blocks_and_check_expectations_with_max_reorg_depth,
+};
+use super::{build_http_client, retrieve_block_hash, BitcoinRpcError};
+use crate::observer::BitcoinConfig;
+use crate::utils::{AbstractBlock, Context};
+use chainhook_types::{BitcoinBlockSignaling, BitcoinNetwork};
 
 #[test]
 fn test_bitcoin_vector_001() {
@@ -204,3 +213,58 @@ fn test_bitcoin_vector_040() {
 // fn test_bitcoin_vector_041() {
 //     process_bitcoin_blocks_and_check_expectations(helpers::shapes::get_vector_041());
 // }
+
+#[test]
+fn test_bitcoin_vector_042() {
+    process_bitcoin_blocks_and_check_expectations_with_max_reorg_depth(
+        helpers::bitcoin_shapes::get_vector_042(),
+        3,
+    );
+}
+
+#[test]
+fn test_bitcoin_reorg_deeper_than_max_reorg_depth_is_rejected() {
+    let ctx = Context::empty();
+    let mut blocks_processor = ForkScratchPad::with_max_reorg_depth(3);
+    for (block, _) in helpers::bitcoin_shapes::get_vector_042() {
+        blocks_processor
+            .process_header(block.get_header(), &ctx)
+            .unwrap();
+    }
+    // B1 is now confirmed: a fork from B1 would roll back 4 blocks
+    let deep_fork = vec![
+        bitcoin_blocks::C3(Some(bitcoin_blocks::B1(None))),
+        bitcoin_blocks::D3(None),
+        bitcoin_blocks::E3(None),
+        bitcoin_blocks::F3(None),
+    ];
+    for block in deep_fork {
+        assert_eq!(
+            blocks_processor.process_header(block.get_header(), &ctx),
+            Ok(None)
+        );
+    }
+    assert!(blocks_processor
+        .process_header(bitcoin_blocks::G3(None).get_header(), &ctx)
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_unreachable_node_is_reported_as_connection_error() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let bitcoin_config = BitcoinConfig {
+        username: "devnet".into(),
+        password: "devnet".into(),
+        rpc_url: format!("http://127.0.0.1:{port}"),
+        network: BitcoinNetwork::Regtest,
+        bitcoin_block_signaling: BitcoinBlockSignaling::ZeroMQ("tcp://127.0.0.1:18543".into()),
+    };
+    let error = retrieve_block_hash(&build_http_client(), &1, &bitcoin_config, &Context::empty())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, BitcoinRpcError::Connection(_)));
+}
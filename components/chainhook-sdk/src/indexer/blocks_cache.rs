@@ -0,0 +1,97 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Keeps the most recently standardized blocks around, keyed by block height, so that scans
+/// and replays of overlapping ranges only fetch and standardize each block once. The cache is
+/// meant to be shared by the scans running concurrently in the process. Blocks are shared, so
+/// that they can be handed to other threads without being copied.
+pub struct BlockCache<B> {
+    capacity: usize,
+    entries: Mutex<BlockCacheEntries<B>>,
+}
+
+struct BlockCacheEntries<B> {
+    blocks: HashMap<u64, Arc<B>>,
+    recency: VecDeque<u64>,
+    insertions: u64,
+}
+
+impl<B> BlockCache<B> {
+    pub fn new(capacity: usize) -> BlockCache<B> {
+        BlockCache {
+            capacity,
+            entries: Mutex::new(BlockCacheEntries {
+                blocks: HashMap::new(),
+                recency: VecDeque::new(),
+                insertions: 0,
+            }),
+        }
+    }
+
+    /// Returns the block cached at `block_height`, marking it as recently used.
+    pub fn get(&self, block_height: u64) -> Option<Arc<B>> {
+        let Ok(mut entries) = self.entries.lock() else {
+            return None;
+        };
+        let block = entries.blocks.get(&block_height).cloned()?;
+        if let Some(position) = entries
+            .recency
+            .iter()
+            .position(|height| *height == block_height)
+        {
+            entries.recency.remove(position);
+        }
+        entries.recency.push_back(block_height);
+        Some(block)
+    }
+
+    /// Caches `block` at `block_height`, replacing the block previously cached at that height
+    /// and evicting the least recently used block when the cache is full. With a capacity of 0,
+    /// the block is only handed back.
+    pub fn insert(&self, block_height: u64, block: B) -> Arc<B> {
+        let block = Arc::new(block);
+        let Ok(mut entries) = self.entries.lock() else {
+            return block;
+        };
+        entries.insertions += 1;
+        if self.capacity == 0 {
+            return block;
+        }
+        if entries.blocks.insert(block_height, block.clone()).is_none() {
+            entries.recency.push_back(block_height);
+        }
+        while entries.blocks.len() > self.capacity {
+            let Some(evicted) = entries.recency.pop_front() else {
+                break;
+            };
+            entries.blocks.remove(&evicted);
+        }
+        block
+    }
+
+    pub fn contains(&self, block_height: u64) -> bool {
+        self.entries
+            .lock()
+            .map(|entries| entries.blocks.contains_key(&block_height))
+            .unwrap_or(false)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .map(|entries| entries.blocks.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of blocks inserted, i.e. fetched or standardized because they were not cached.
+    pub fn insertions(&self) -> u64 {
+        self.entries
+            .lock()
+            .map(|entries| entries.insertions)
+            .unwrap_or(0)
+    }
+}
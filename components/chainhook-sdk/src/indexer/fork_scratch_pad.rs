@@ -14,10 +14,20 @@ pub struct ForkScratchPad {
     orphans: BTreeSet<BlockIdentifier>,
     forks: BTreeMap<usize, ChainSegment>,
     headers_store: BTreeMap<BlockIdentifier, BlockHeader>,
+    max_reorg_depth: usize,
+    last_confirmed_block: Option<BlockIdentifier>,
 }
 pub const CONFIRMED_SEGMENT_MINIMUM_LENGTH: i32 = 7;
+/// Number of blocks that can be rolled back by a reorg, unless configured otherwise.
+pub const DEFAULT_MAX_REORG_DEPTH: usize = 5;
 impl ForkScratchPad {
     pub fn new() -> ForkScratchPad {
+        ForkScratchPad::with_max_reorg_depth(DEFAULT_MAX_REORG_DEPTH)
+    }
+
+    /// Keep enough blocks in memory to emit the rollbacks of reorgs up to `max_reorg_depth`
+    /// blocks deep. Deeper reorgs are reported as errors.
+    pub fn with_max_reorg_depth(max_reorg_depth: usize) -> ForkScratchPad {
         let mut forks = BTreeMap::new();
         forks.insert(0, ChainSegment::new());
         let headers_store = BTreeMap::new();
@@ -26,6 +36,8 @@ impl ForkScratchPad {
             orphans: BTreeSet::new(),
             forks,
             headers_store,
+            max_reorg_depth: max_reorg_depth.max(1),
+            last_confirmed_block: None,
         }
     }
 
@@ -112,6 +124,7 @@ impl ForkScratchPad {
                     )
                 });
                 self.orphans.insert(header.block_identifier.clone());
+                self.check_orphan_reorg_depth(&header, &previous_canonical_fork)?;
                 return Ok(None);
             }
         };
@@ -195,6 +208,35 @@ impl ForkScratchPad {
         Ok(Some(chain_event))
     }
 
+    /// An orphan higher than the canonical tip, descending from a block that was already
+    /// confirmed and pruned, means that a reorg deeper than `max_reorg_depth` occurred.
+    fn check_orphan_reorg_depth(
+        &self,
+        header: &BlockHeader,
+        canonical_fork: &ChainSegment,
+    ) -> Result<(), String> {
+        let last_confirmed_block = match self.last_confirmed_block {
+            Some(ref block) => block,
+            None => return Ok(()),
+        };
+        if canonical_fork.is_empty()
+            || header.block_identifier.index <= canonical_fork.get_tip().index
+        {
+            return Ok(());
+        }
+        let mut ancestor_identifier = &header.parent_block_identifier;
+        while let Some(ancestor) = self.headers_store.get(ancestor_identifier) {
+            ancestor_identifier = &ancestor.parent_block_identifier;
+        }
+        if ancestor_identifier.index <= last_confirmed_block.index {
+            return Err(format!(
+                "Bitcoin {} forks off {}: reorg deeper than the {} blocks supported",
+                header.block_identifier, ancestor_identifier, self.max_reorg_depth
+            ));
+        }
+        Ok(())
+    }
+
     pub fn collect_and_prune_confirmed_blocks(
         &mut self,
         chain_event: &mut BlockchainEvent,
@@ -228,11 +270,13 @@ impl ForkScratchPad {
             }
             segment
         };
-        if canonical_segment.len() < CONFIRMED_SEGMENT_MINIMUM_LENGTH as usize {
+        // The tip and `max_reorg_depth` ancestors are kept, so that a fork can
+        // still be attached to the oldest of them.
+        if canonical_segment.len() < self.max_reorg_depth + 2 {
             return;
         }
-        // Any block beyond 6th ancestor is considered as confirmed and can be pruned
-        let cut_off = &canonical_segment[5];
+        // Any block beyond that is considered as confirmed and can be pruned
+        let cut_off = &canonical_segment[self.max_reorg_depth];
 
         // Prune forks using the confirmed block
         let mut blocks_to_prune = vec![];
@@ -253,7 +297,8 @@ impl ForkScratchPad {
             }
         }
 
-        for confirmed_block in canonical_segment[6..].into_iter() {
+        self.last_confirmed_block = Some(canonical_segment[self.max_reorg_depth + 1].clone());
+        for confirmed_block in canonical_segment[self.max_reorg_depth + 1..].into_iter() {
             let block = match self.headers_store.remove(confirmed_block) {
                 None => {
                     ctx.try_log(|logger| {
@@ -1,12 +1,15 @@
 pub mod bitcoin;
+mod blocks_cache;
 pub mod fork_scratch_pad;
 pub mod stacks;
 
+pub use blocks_cache::BlockCache;
+
 use crate::utils::{AbstractBlock, Context};
 
 use chainhook_types::{
     BitcoinBlockSignaling, BitcoinNetwork, BlockHeader, BlockIdentifier, BlockchainEvent,
-    StacksChainEvent, StacksNetwork, StacksNodeConfig,
+    StacksBlockData, StacksChainEvent, StacksNetwork, StacksNodeConfig,
 };
 use hiro_system_kit::slog;
 use rocket::serde::json::Value as JsonValue;
@@ -57,6 +60,7 @@ pub struct IndexerConfig {
     pub bitcoind_rpc_username: String,
     pub bitcoind_rpc_password: String,
     pub bitcoin_block_signaling: BitcoinBlockSignaling,
+    pub bitcoin_max_reorg_depth: usize,
 }
 
 impl IndexerConfig {
@@ -79,7 +83,8 @@ pub struct Indexer {
 impl Indexer {
     pub fn new(config: IndexerConfig) -> Indexer {
         let stacks_blocks_pool = StacksBlockPool::new();
-        let bitcoin_blocks_pool = ForkScratchPad::new();
+        let bitcoin_blocks_pool =
+            ForkScratchPad::with_max_reorg_depth(config.bitcoin_max_reorg_depth);
         let stacks_context = StacksChainContext::new(&config.stacks_network);
         let bitcoin_context = BitcoinChainContext::new();
 
@@ -115,6 +120,14 @@ impl Indexer {
         self.stacks_blocks_pool.process_block(block, ctx)
     }
 
+    pub fn handle_stacks_standardized_block(
+        &mut self,
+        block: StacksBlockData,
+        ctx: &Context,
+    ) -> Result<Option<StacksChainEvent>, String> {
+        self.stacks_blocks_pool.process_block(block, ctx)
+    }
+
     pub fn handle_stacks_serialized_microblock_trail(
         &mut self,
         serialized_microblock_trail: &str,
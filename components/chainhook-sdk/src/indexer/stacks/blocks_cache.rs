@@ -0,0 +1,30 @@
+use chainhook_types::{BlockIdentifier, StacksBlockData};
+use std::sync::Arc;
+
+use crate::indexer::BlockCache;
+
+pub const DEFAULT_STACKS_BLOCK_CACHE_CAPACITY: usize = 128;
+
+/// Standardized Stacks blocks, so that replaying or scanning overlapping ranges for several
+/// predicates only pays the standardization cost once per block.
+pub type StacksBlockCache = BlockCache<StacksBlockData>;
+
+impl BlockCache<StacksBlockData> {
+    /// Returns the block cached for `block_identifier`, or runs `standardize` and caches its
+    /// output. A block cached at the same height for another fork is replaced.
+    pub fn get_or_standardize<F>(
+        &self,
+        block_identifier: &BlockIdentifier,
+        standardize: F,
+    ) -> Result<Arc<StacksBlockData>, String>
+    where
+        F: FnOnce() -> Result<StacksBlockData, String>,
+    {
+        if let Some(block) = self.get(block_identifier.index) {
+            if block.block_identifier == *block_identifier {
+                return Ok(block);
+            }
+        }
+        Ok(self.insert(block_identifier.index, standardize()?))
+    }
+}
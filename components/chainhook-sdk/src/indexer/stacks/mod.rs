@@ -1,5 +1,7 @@
+mod blocks_cache;
 mod blocks_pool;
 
+pub use blocks_cache::{StacksBlockCache, DEFAULT_STACKS_BLOCK_CACHE_CAPACITY};
 pub use blocks_pool::StacksBlockPool;
 
 use crate::chainhooks::stacks::try_decode_clarity_value;
@@ -12,6 +14,7 @@ use rocket::serde::json::Value as JsonValue;
 use rocket::serde::Deserialize;
 use stacks_rpc_client::clarity::codec::{StacksTransaction, TransactionAuth, TransactionPayload};
 use stacks_rpc_client::clarity::stacks_common::codec::StacksMessageCodec;
+use stacks_rpc_client::clarity::stacks_common::util::hash::Sha512Trunc256Sum;
 use stacks_rpc_client::clarity::vm::types::{SequenceData, Value as ClarityValue};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryInto;
@@ -432,6 +435,7 @@ pub fn standardize_stacks_block(
             pox_cycle_length: pox_cycle_length.try_into().unwrap(),
             confirm_microblock_identifier,
             stacks_block_hash: block.block_hash.clone(),
+            network: Some(indexer_config.stacks_network.clone()),
         },
         transactions,
     };
@@ -554,6 +558,7 @@ pub fn standardize_stacks_microblock_trail(
                     hash: microblock_trail.parent_index_block_hash.clone(),
                     index: 0,
                 },
+                network: Some(indexer_config.stacks_network.clone()),
             },
         })
     }
@@ -584,6 +589,17 @@ pub fn get_value_description(raw_value: &str, ctx: &Context) -> String {
     value
 }
 
+/// Compute the id of a raw, hex encoded, transaction: the SHA512/256 digest of its bytes.
+pub fn get_txid_from_raw_tx(raw_tx: &str) -> Result<String, String> {
+    let raw_tx = raw_tx.strip_prefix("0x").unwrap_or(raw_tx);
+    let tx_bytes =
+        hex::decode(raw_tx).map_err(|e| format!("unable to decode transaction {}", e))?;
+    Ok(format!(
+        "0x{}",
+        Sha512Trunc256Sum::from_data(&tx_bytes).to_hex()
+    ))
+}
+
 pub fn get_tx_description(
     raw_tx: &str,
     tx_events: &Vec<&NewEvent>,
@@ -5,11 +5,14 @@ use chainhook_types::{
     STXTransferEventData, SmartContractEventData, StacksTransactionEventPayload,
 };
 
+use crate::chainhooks::tests::fixtures::{
+    get_contract_call_transaction, get_contract_deploy_transaction,
+};
 use crate::indexer::tests::helpers::stacks_events::create_new_event_from_stacks_event;
 
 use super::{
     super::tests::{helpers, process_stacks_blocks_and_check_expectations},
-    NewEvent,
+    get_txid_from_raw_tx, NewEvent,
 };
 use test_case::test_case;
 
@@ -277,6 +280,7 @@ fn test_stacks_vector_052() {
     sender: format!(""),
     recipient: format!(""),
     amount: format!("1"),
+    memo: None,
 }); "stx_transfer")]
 #[test_case(StacksTransactionEventPayload::STXMintEvent(STXMintEventData {
     recipient: format!(""),
@@ -385,3 +389,15 @@ fn into_chainhook_event_rejects_invalid_missing_event() {
         .into_chainhook_event()
         .expect_err("expected error on missing event");
 }
+
+#[test]
+fn get_txid_from_raw_tx_hashes_transaction_bytes() {
+    for transaction in [
+        get_contract_call_transaction(),
+        get_contract_deploy_transaction(),
+    ] {
+        let txid = get_txid_from_raw_tx(&transaction.metadata.raw_tx).unwrap();
+        assert_eq!(txid, transaction.transaction_identifier.hash);
+    }
+    assert!(get_txid_from_raw_tx("0xzz").is_err());
+}
@@ -2652,3 +2652,64 @@ pub fn get_vector_040() -> Vec<(BitcoinBlockData, BlockchainEventExpectation)> {
 pub fn get_vector_041() -> Vec<(BitcoinBlockData, BlockchainEventExpectation)> {
     vec![]
 }
+
+/// Vector 042: Generate the following blocks, with a max reorg depth of 3
+///
+/// A1(1)  -  B1(2)  -  C1(3)  -  D1(4)  -  E1(5)
+///                  \  C2(6)  -  D2(7)  -  E2(8)  -  F2(9)
+///
+pub fn get_vector_042() -> Vec<(BitcoinBlockData, BlockchainEventExpectation)> {
+    vec![
+        (
+            bitcoin_blocks::A1(None),
+            expect_chain_updated_with_block(bitcoin_blocks::A1(None), vec![]),
+        ),
+        (
+            bitcoin_blocks::B1(None),
+            expect_chain_updated_with_block(bitcoin_blocks::B1(None), vec![]),
+        ),
+        (
+            bitcoin_blocks::C1(None),
+            expect_chain_updated_with_block(bitcoin_blocks::C1(None), vec![]),
+        ),
+        (
+            bitcoin_blocks::D1(None),
+            expect_chain_updated_with_block(bitcoin_blocks::D1(None), vec![]),
+        ),
+        (
+            bitcoin_blocks::E1(None),
+            expect_chain_updated_with_block(
+                bitcoin_blocks::E1(None),
+                vec![bitcoin_blocks::A1(None)],
+            ),
+        ),
+        (
+            bitcoin_blocks::C2(Some(bitcoin_blocks::B1(None))),
+            expect_no_chain_update(),
+        ),
+        (bitcoin_blocks::D2(None), expect_no_chain_update()),
+        (
+            bitcoin_blocks::E2(None),
+            expect_chain_updated_with_block_reorg(
+                vec![
+                    bitcoin_blocks::C1(None),
+                    bitcoin_blocks::D1(None),
+                    bitcoin_blocks::E1(None),
+                ],
+                vec![
+                    bitcoin_blocks::C2(Some(bitcoin_blocks::B1(None))),
+                    bitcoin_blocks::D2(None),
+                    bitcoin_blocks::E2(None),
+                ],
+                vec![],
+            ),
+        ),
+        (
+            bitcoin_blocks::F2(None),
+            expect_chain_updated_with_block(
+                bitcoin_blocks::F2(None),
+                vec![bitcoin_blocks::B1(None)],
+            ),
+        ),
+    ]
+}
@@ -72,6 +72,7 @@ pub fn generate_test_stacks_block(
             pox_cycle_length: 100,
             confirm_microblock_identifier,
             stacks_block_hash: format!(""),
+            network: None,
         },
     })
 }
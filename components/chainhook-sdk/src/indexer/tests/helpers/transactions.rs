@@ -124,12 +124,15 @@ pub fn generate_test_tx_bitcoin_p2pkh_transfer(
         },
         operations: vec![],
         metadata: BitcoinTransactionMetadata {
+            version: 2,
             inputs: vec![],
             outputs,
             ordinal_operations: vec![],
             stacks_operations: vec![],
             proof: None,
             fee: 0,
+            raw_tx: None,
+            vsize: None,
         },
     }
 }
@@ -2,7 +2,8 @@ pub mod helpers;
 use crate::utils::{AbstractBlock, Context};
 
 use self::helpers::BlockEvent;
-use super::{fork_scratch_pad::ForkScratchPad, StacksBlockPool};
+use super::fork_scratch_pad::{ForkScratchPad, DEFAULT_MAX_REORG_DEPTH};
+use super::StacksBlockPool;
 use chainhook_types::{BitcoinBlockData, BlockchainEvent, StacksChainEvent};
 
 pub type StacksChainEventExpectation = Box<dyn Fn(Option<StacksChainEvent>) -> ()>;
@@ -34,7 +35,17 @@ pub type BlockchainEventExpectation = Box<dyn Fn(Option<BlockchainEvent>) -> ()>
 pub fn process_bitcoin_blocks_and_check_expectations(
     steps: Vec<(BitcoinBlockData, BlockchainEventExpectation)>,
 ) {
-    let mut blocks_processor = ForkScratchPad::new();
+    process_bitcoin_blocks_and_check_expectations_with_max_reorg_depth(
+        steps,
+        DEFAULT_MAX_REORG_DEPTH,
+    )
+}
+
+pub fn process_bitcoin_blocks_and_check_expectations_with_max_reorg_depth(
+    steps: Vec<(BitcoinBlockData, BlockchainEventExpectation)>,
+    max_reorg_depth: usize,
+) {
+    let mut blocks_processor = ForkScratchPad::with_max_reorg_depth(max_reorg_depth);
     for (block, check_chain_event_expectations) in steps.into_iter() {
         let chain_event = blocks_processor
             .process_header(block.get_header(), &Context::empty())
@@ -298,7 +298,10 @@ pub fn handle_new_mempool_tx(
         .map(|tx_data| {
             let (tx_description, ..) = indexer::stacks::get_tx_description(&tx_data, &vec![])
                 .expect("unable to parse transaction");
+            let txid =
+                indexer::stacks::get_txid_from_raw_tx(&tx_data).expect("unable to parse txid");
             MempoolAdmissionData {
+                txid,
                 tx_data: tx_data.clone(),
                 tx_description,
             }
@@ -321,10 +324,36 @@ pub fn handle_new_mempool_tx(
     }))
 }
 
-#[post("/drop_mempool_tx", format = "application/json")]
-pub fn handle_drop_mempool_tx(ctx: &State<Context>) -> Json<JsonValue> {
+#[post("/drop_mempool_tx", format = "application/json", data = "<payload>")]
+pub fn handle_drop_mempool_tx(
+    payload: Option<Json<JsonValue>>,
+    background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
     ctx.try_log(|logger| slog::info!(logger, "POST /drop_mempool_tx"));
-    // TODO(lgalabru): use propagate mempool events
+    let dropped_txids = payload
+        .as_ref()
+        .and_then(|payload| payload["dropped_txids"].as_array())
+        .map(|txids| {
+            txids
+                .iter()
+                .filter_map(|txid| txid.as_str().map(|txid| txid.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let background_job_tx = background_job_tx.inner();
+    match background_job_tx.lock() {
+        Ok(tx) => {
+            for txid in dropped_txids.into_iter() {
+                let _ = tx.send(ObserverCommand::PropagateStacksMempoolEvent(
+                    StacksChainMempoolEvent::TransactionDropped(txid),
+                ));
+            }
+        }
+        _ => {}
+    };
+
     Json(json!({
         "status": 200,
         "result": "Ok",
@@ -4,24 +4,31 @@ mod zmq;
 
 use crate::chainhooks::bitcoin::{
     evaluate_bitcoin_chainhooks_on_chain_event, handle_bitcoin_hook_action,
-    BitcoinChainhookOccurrence, BitcoinChainhookOccurrencePayload, BitcoinTriggerChainhook,
+    serialize_bitcoin_payload_to_json, BitcoinChainhookOccurrence,
+    BitcoinChainhookOccurrencePayload, BitcoinTriggerChainhook,
 };
 use crate::chainhooks::stacks::{
-    evaluate_stacks_chainhooks_on_chain_event, handle_stacks_hook_action,
-    StacksChainhookOccurrence, StacksChainhookOccurrencePayload,
+    evaluate_stacks_chainhooks_on_chain_event_with_occurrences, handle_stacks_hook_action,
+    serialize_stacks_payload_to_json, StacksChainhookOccurrence, StacksChainhookOccurrencePayload,
+    StacksTriggerChainhook,
 };
 use crate::chainhooks::types::{
-    ChainhookConfig, ChainhookFullSpecification, ChainhookSpecification,
+    ChainhookConfig, ChainhookFullSpecification, ChainhookSpecification, HookAction, HttpHook,
 };
 
 use crate::indexer::bitcoin::{
     build_http_client, download_and_parse_block_with_retry, standardize_bitcoin_block,
     BitcoinBlockFullBreakdown,
 };
+use crate::indexer::fork_scratch_pad::DEFAULT_MAX_REORG_DEPTH;
 use crate::indexer::{Indexer, IndexerConfig};
 use crate::monitoring::{start_serving_prometheus_metrics, PrometheusMonitoring};
-use crate::utils::{send_request, Context};
+use crate::utils::{
+    send_http_occurrences_batch, send_request, unix_socket_write, AbstractStacksBlock, Context,
+    HttpOccurrenceBatches, RetryPolicy,
+};
 
+use bitcoincore_rpc::bitcoin::hashes::{sha256, Hash};
 use bitcoincore_rpc::bitcoin::{BlockHash, Txid};
 use bitcoincore_rpc::{Auth, Client, RpcApi};
 use chainhook_types::{
@@ -31,20 +38,23 @@ use chainhook_types::{
 };
 use hiro_system_kit;
 use hiro_system_kit::slog;
+use reqwest::RequestBuilder;
 use rocket::config::{self, Config, LogLevel};
 use rocket::data::{Limits, ToByteUnit};
 use rocket::serde::Deserialize;
 use rocket::Shutdown;
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::error::Error;
 use std::net::{IpAddr, Ipv4Addr};
 use std::path::PathBuf;
 use std::str;
 use std::str::FromStr;
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 pub const DEFAULT_INGESTION_PORT: u16 = 20445;
+pub const DEFAULT_DISPATCH_BUFFER_CAPACITY: usize = 1024;
 
 #[derive(Deserialize)]
 pub struct NewTransaction {
@@ -80,6 +90,8 @@ pub struct EventObserverConfig {
     pub stacks_network: StacksNetwork,
     pub data_handler_tx: Option<crossbeam_channel::Sender<DataHandlerEvent>>,
     pub prometheus_monitoring_port: Option<u16>,
+    pub bitcoin_max_reorg_depth: usize,
+    pub dispatch_buffer_capacity: usize,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -94,6 +106,8 @@ pub struct EventObserverConfigOverrides {
     pub cache_path: Option<String>,
     pub bitcoin_network: Option<String>,
     pub stacks_network: Option<String>,
+    pub bitcoin_max_reorg_depth: Option<usize>,
+    pub dispatch_buffer_capacity: Option<usize>,
 }
 
 impl EventObserverConfig {
@@ -191,6 +205,12 @@ impl EventObserverConfig {
             stacks_network,
             data_handler_tx: None,
             prometheus_monitoring_port: None,
+            bitcoin_max_reorg_depth: overrides
+                .and_then(|c| c.bitcoin_max_reorg_depth)
+                .unwrap_or(DEFAULT_MAX_REORG_DEPTH),
+            dispatch_buffer_capacity: overrides
+                .and_then(|c| c.dispatch_buffer_capacity)
+                .unwrap_or(DEFAULT_DISPATCH_BUFFER_CAPACITY),
         };
         Ok(config)
     }
@@ -216,6 +236,8 @@ pub enum ObserverCommand {
     ExpireBitcoinPredicate(HookExpirationData),
     ExpireStacksPredicate(HookExpirationData),
     NotifyBitcoinTransactionProxied,
+    PauseDispatch,
+    ResumeDispatch,
     Terminate,
 }
 
@@ -233,6 +255,7 @@ pub enum StacksChainMempoolEvent {
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct MempoolAdmissionData {
+    pub txid: String,
     pub tx_data: String,
     pub tx_description: String,
 }
@@ -311,6 +334,343 @@ pub enum ObserverEvent {
     PredicatesTriggered(usize),
     Terminate,
     StacksChainMempoolEvent(StacksChainMempoolEvent),
+    OccurrenceDeliveryUpdated((OccurrenceDelivery, OccurrenceDeliveryStatus)),
+    DispatchPaused,
+    /// Dispatching resumed, after flushing the given number of buffered occurrences.
+    DispatchResumed(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OccurrenceDeliveryStatus {
+    Pending,
+    Acked,
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OccurrenceDelivery {
+    pub predicate_uuid: String,
+    pub idempotency_key: String,
+}
+
+impl OccurrenceDelivery {
+    /// The idempotency key is derived from the predicate and the blocks carried by the
+    /// occurrence, so that a redelivery of the same occurrence is keyed identically.
+    pub fn new<'a>(
+        predicate_uuid: &str,
+        apply: impl Iterator<Item = &'a BlockIdentifier>,
+        rollback: impl Iterator<Item = &'a BlockIdentifier>,
+    ) -> OccurrenceDelivery {
+        let mut preimage = predicate_uuid.to_string();
+        for block_identifier in apply {
+            preimage.push_str(&format!(":apply:{}", block_identifier.hash));
+        }
+        for block_identifier in rollback {
+            preimage.push_str(&format!(":rollback:{}", block_identifier.hash));
+        }
+        let digest = sha256::Hash::hash(preimage.as_bytes()).to_byte_array();
+        OccurrenceDelivery {
+            predicate_uuid: predicate_uuid.to_string(),
+            idempotency_key: hex::encode(digest),
+        }
+    }
+}
+
+/// Sends an occurrence, tagging the request with its idempotency key and reporting the
+/// delivery status transitions (pending, then acked or failed) on the observer events channel.
+pub async fn send_tracked_occurrence(
+    request: RequestBuilder,
+    delivery: OccurrenceDelivery,
+    attempts_max: u16,
+    attempts_interval_sec: u16,
+    observer_events_tx: &Option<crossbeam_channel::Sender<ObserverEvent>>,
+    ctx: &Context,
+) -> Result<(), String> {
+    let notify = |status: OccurrenceDeliveryStatus| {
+        if let Some(ref tx) = observer_events_tx {
+            let _ = tx.send(ObserverEvent::OccurrenceDeliveryUpdated((
+                delivery.clone(),
+                status,
+            )));
+        }
+    };
+    notify(OccurrenceDeliveryStatus::Pending);
+    let request = request.header("Idempotency-Key", &delivery.idempotency_key);
+    let res = send_request(request, attempts_max, attempts_interval_sec, ctx).await;
+    match res {
+        Ok(_) => notify(OccurrenceDeliveryStatus::Acked),
+        Err(_) => notify(OccurrenceDeliveryStatus::Failed),
+    }
+    res
+}
+
+/// Occurrence ready to be dispatched by the observer.
+pub enum PendingOccurrence {
+    BitcoinHttp(
+        RequestBuilder,
+        OccurrenceDelivery,
+        BitcoinChainhookOccurrencePayload,
+    ),
+    BitcoinData(BitcoinChainhookOccurrencePayload),
+    StacksHttp(RequestBuilder, OccurrenceDelivery),
+    StacksData(StacksChainhookOccurrencePayload),
+    UnixSocket(String, Vec<u8>),
+    /// Occurrence of a hook batching its occurrences, to be queued in the batch of the hook.
+    Batched(HttpHook, BatchedOccurrence),
+    /// Batch of occurrences of a hook, ready to be posted.
+    HttpBatch(HttpHook, Vec<BatchedOccurrence>),
+}
+
+/// Occurrence of an HTTP hook batching its occurrences.
+pub struct BatchedOccurrence {
+    pub payload: serde_json::Value,
+    /// Reported on the observer events channel once the batch is delivered, as the payloads of
+    /// unbatched Bitcoin occurrences are.
+    pub data: Option<BitcoinChainhookOccurrencePayload>,
+}
+
+/// Queues the occurrences of hooks batching their occurrences in `batches`, and returns the
+/// occurrences ready to be dispatched, in order: the other occurrences, along with the batches
+/// filled up or whose window elapsed.
+pub fn batch_occurrences(
+    occurrences: Vec<PendingOccurrence>,
+    batches: &mut HttpOccurrenceBatches<BatchedOccurrence>,
+    now: Instant,
+) -> Vec<PendingOccurrence> {
+    let mut ready = vec![];
+    for occurrence in occurrences.into_iter() {
+        match occurrence {
+            PendingOccurrence::Batched(http, occurrence) => {
+                if let Some((http, occurrences)) = batches.push(&http, occurrence, now) {
+                    ready.push(PendingOccurrence::HttpBatch(http, occurrences));
+                }
+            }
+            occurrence => ready.push(occurrence),
+        }
+    }
+    for (http, occurrences) in batches.poll(now).into_iter() {
+        ready.push(PendingOccurrence::HttpBatch(http, occurrences));
+    }
+    ready
+}
+
+/// Holds occurrences while dispatching is paused (e.g. during a downstream maintenance window),
+/// so they can be flushed in order once dispatching resumes.
+/// The buffer is bounded: once full, the observer stops evaluating new chain events (they keep
+/// being ingested and queued) until dispatching resumes.
+pub struct DispatchBuffer {
+    paused: bool,
+    capacity: usize,
+    occurrences: VecDeque<PendingOccurrence>,
+}
+
+impl DispatchBuffer {
+    pub fn new(capacity: usize) -> DispatchBuffer {
+        DispatchBuffer {
+            paused: false,
+            capacity,
+            occurrences: VecDeque::new(),
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes dispatching and returns the occurrences held while paused, oldest first.
+    pub fn resume(&mut self) -> Vec<PendingOccurrence> {
+        self.paused = false;
+        self.occurrences.drain(..).collect()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.paused && self.occurrences.len() >= self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.occurrences.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.occurrences.is_empty()
+    }
+
+    /// Keeps the occurrence if dispatching is paused, otherwise hands it back to be dispatched.
+    /// Occurrences produced by a chain event admitted before the buffer filled up are always
+    /// kept, so the capacity can be exceeded by the occurrences of a single chain event.
+    pub fn hold(&mut self, occurrence: PendingOccurrence) -> Option<PendingOccurrence> {
+        if self.paused {
+            self.occurrences.push_back(occurrence);
+            None
+        } else {
+            Some(occurrence)
+        }
+    }
+}
+
+/// Holds the occurrences of predicates requiring confirmations (`min_confirmations`) until
+/// enough blocks were mined on top of the block they were triggered by.
+/// The occurrences are only kept in memory: the occurrences held when the observer stops are
+/// lost, and their blocks have to be rescanned to be delivered.
+#[derive(Default)]
+pub struct ConfirmationBuffer {
+    /// Occurrences keyed by the height the chain tip has to reach for them to be confirmed,
+    /// along with the uuid of their predicate and the block they were triggered by.
+    occurrences: BTreeMap<u64, Vec<(String, BlockIdentifier, PendingOccurrence)>>,
+}
+
+impl ConfirmationBuffer {
+    pub fn new() -> ConfirmationBuffer {
+        ConfirmationBuffer::default()
+    }
+
+    pub fn hold(
+        &mut self,
+        predicate_uuid: &str,
+        block_identifier: BlockIdentifier,
+        min_confirmations: u64,
+        occurrence: PendingOccurrence,
+    ) {
+        self.occurrences
+            .entry(block_identifier.index + min_confirmations)
+            .or_default()
+            .push((predicate_uuid.to_string(), block_identifier, occurrence));
+    }
+
+    /// Drops the occurrences of a deregistered predicate.
+    pub fn drop_predicate(&mut self, predicate_uuid: &str) {
+        for occurrences in self.occurrences.values_mut() {
+            occurrences.retain(|(uuid, _, _)| uuid != predicate_uuid);
+        }
+        self.occurrences
+            .retain(|_, occurrences| !occurrences.is_empty());
+    }
+
+    /// Drops the occurrences of the blocks rolled back, and returns the identifiers of the
+    /// blocks whose occurrences were dropped: these occurrences were never delivered.
+    pub fn drop_orphaned(
+        &mut self,
+        blocks_to_rollback: &[&BlockIdentifier],
+    ) -> Vec<BlockIdentifier> {
+        let mut dropped = vec![];
+        for occurrences in self.occurrences.values_mut() {
+            occurrences.retain(|(_, block_identifier, _)| {
+                if blocks_to_rollback.contains(&block_identifier) {
+                    dropped.push(block_identifier.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.occurrences
+            .retain(|_, occurrences| !occurrences.is_empty());
+        dropped
+    }
+
+    /// Returns the occurrences confirmed by a chain tip at `chain_tip_height`, oldest first.
+    pub fn release(&mut self, chain_tip_height: u64) -> Vec<PendingOccurrence> {
+        let pending = self.occurrences.split_off(&(chain_tip_height + 1));
+        let confirmed = std::mem::replace(&mut self.occurrences, pending);
+        confirmed
+            .into_values()
+            .flatten()
+            .map(|(_, _, occurrence)| occurrence)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.occurrences.values().map(|o| o.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.occurrences.is_empty()
+    }
+}
+
+pub async fn dispatch_occurrence(
+    occurrence: PendingOccurrence,
+    observer_events_tx: &Option<crossbeam_channel::Sender<ObserverEvent>>,
+    ctx: &Context,
+) {
+    match occurrence {
+        PendingOccurrence::BitcoinHttp(request, delivery, data) => {
+            if send_tracked_occurrence(request, delivery, 3, 1, observer_events_tx, ctx)
+                .await
+                .is_ok()
+            {
+                if let Some(ref tx) = observer_events_tx {
+                    let _ = tx.send(ObserverEvent::BitcoinPredicateTriggered(data));
+                }
+            }
+        }
+        PendingOccurrence::BitcoinData(payload) => {
+            if let Some(ref tx) = observer_events_tx {
+                let _ = tx.send(ObserverEvent::BitcoinPredicateTriggered(payload));
+            }
+        }
+        PendingOccurrence::StacksHttp(request, delivery) => {
+            ctx.try_log(|logger| {
+                slog::info!(
+                    logger,
+                    "Dispatching request from stacks chainhook {:?}",
+                    request
+                )
+            });
+            let _ = send_tracked_occurrence(request, delivery, 3, 1, observer_events_tx, ctx).await;
+        }
+        PendingOccurrence::StacksData(payload) => {
+            if let Some(ref tx) = observer_events_tx {
+                let _ = tx.send(ObserverEvent::StacksPredicateTriggered(payload));
+            }
+        }
+        PendingOccurrence::UnixSocket(path, bytes) => {
+            // Same attempts as the HTTP occurrences dispatched by the observer.
+            let retry_policy = RetryPolicy {
+                max_retries: 2,
+                initial_backoff_ms: 1_000,
+                backoff_multiplier: 1.0,
+            };
+            if let Err(e) = unix_socket_write(&path, &bytes, &retry_policy, ctx).await {
+                ctx.try_log(|logger| slog::error!(logger, "unable to stream occurrence {}", e));
+            }
+        }
+        PendingOccurrence::Batched(http, occurrence) => {
+            dispatch_http_batch(&http, vec![occurrence], observer_events_tx, ctx).await
+        }
+        PendingOccurrence::HttpBatch(http, occurrences) => {
+            dispatch_http_batch(&http, occurrences, observer_events_tx, ctx).await
+        }
+    }
+}
+
+async fn dispatch_http_batch(
+    http: &HttpHook,
+    occurrences: Vec<BatchedOccurrence>,
+    observer_events_tx: &Option<crossbeam_channel::Sender<ObserverEvent>>,
+    ctx: &Context,
+) {
+    let (payloads, data): (Vec<_>, Vec<_>) = occurrences
+        .into_iter()
+        .map(|occurrence| (occurrence.payload, occurrence.data))
+        .unzip();
+    match send_http_occurrences_batch(http, payloads, ctx).await {
+        Ok(_) => {
+            if let Some(ref tx) = observer_events_tx {
+                for payload in data.into_iter().flatten() {
+                    let _ = tx.send(ObserverEvent::BitcoinPredicateTriggered(payload));
+                }
+            }
+        }
+        Err(e) => {
+            ctx.try_log(|logger| slog::error!(logger, "unable to deliver batch {}", e));
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -343,10 +703,7 @@ pub struct ChainhookStore {
 impl ChainhookStore {
     pub fn new() -> Self {
         Self {
-            predicates: ChainhookConfig {
-                stacks_chainhooks: vec![],
-                bitcoin_chainhooks: vec![],
-            },
+            predicates: ChainhookConfig::new(),
         }
     }
 }
@@ -551,6 +908,7 @@ pub async fn start_stacks_event_observer(
         stacks_network: StacksNetwork::Devnet,
         bitcoin_network: BitcoinNetwork::Regtest,
         bitcoin_block_signaling: config.bitcoin_block_signaling.clone(),
+        bitcoin_max_reorg_depth: config.bitcoin_max_reorg_depth,
     };
 
     let indexer = Indexer::new(indexer_config.clone());
@@ -742,21 +1100,67 @@ pub async fn start_observer_commands_handler(
     let mut chainhooks_occurrences_tracker: HashMap<String, u64> = HashMap::new();
     let networks = (&config.bitcoin_network, &config.stacks_network);
     let mut bitcoin_block_store: HashMap<BlockIdentifier, BitcoinBlockDataCached> = HashMap::new();
+    // Unix timestamp at which each pending Stacks transaction was first seen in the mempool.
+    let mut stacks_mempool_timestamps: HashMap<TransactionIdentifier, i64> = HashMap::new();
     let http_client = build_http_client();
     let store_update_required = observer_sidecar
         .as_ref()
         .and_then(|s| s.bitcoin_blocks_mutator.as_ref())
         .is_some();
+    let mut dispatch_buffer = DispatchBuffer::new(config.dispatch_buffer_capacity);
+    let mut confirmation_buffer = ConfirmationBuffer::new();
+    let mut stacks_confirmation_buffer = ConfirmationBuffer::new();
+    let mut http_batches: HttpOccurrenceBatches<BatchedOccurrence> = HttpOccurrenceBatches::new();
+    // Chain events received while the dispatch buffer is full, evaluated once dispatching resumes.
+    let mut deferred_commands: VecDeque<ObserverCommand> = VecDeque::new();
 
     loop {
-        let command = match observer_commands_rx.recv() {
-            Ok(cmd) => cmd,
-            Err(e) => {
-                if let Some(ref tx) = observer_events_tx {
-                    let _ = tx.send(ObserverEvent::Error(format!("Channel error: {:?}", e)));
+        let deferred_command = match dispatch_buffer.is_paused() {
+            true => None,
+            false => deferred_commands.pop_front(),
+        };
+        let command = match deferred_command {
+            Some(cmd) => cmd,
+            None => {
+                // Pending batches are posted once their window elapses, even if no command
+                // is received in the meantime.
+                let received = match http_batches.next_deadline() {
+                    Some(deadline) => observer_commands_rx
+                        .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                    None => observer_commands_rx
+                        .recv()
+                        .map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match received {
+                    Ok(cmd) => cmd,
+                    Err(RecvTimeoutError::Timeout) => {
+                        for (http, occurrences) in http_batches.poll(Instant::now()).into_iter() {
+                            let batch = PendingOccurrence::HttpBatch(http, occurrences);
+                            if let Some(occurrence) = dispatch_buffer.hold(batch) {
+                                dispatch_occurrence(occurrence, &observer_events_tx, &ctx).await;
+                            }
+                        }
+                        continue;
+                    }
+                    Err(e) => {
+                        if let Some(ref tx) = observer_events_tx {
+                            let _ =
+                                tx.send(ObserverEvent::Error(format!("Channel error: {:?}", e)));
+                        }
+                        continue;
+                    }
                 }
+            }
+        };
+        let command = match command {
+            ObserverCommand::PropagateBitcoinChainEvent(_)
+            | ObserverCommand::PropagateStacksChainEvent(_)
+                if dispatch_buffer.is_full() =>
+            {
+                deferred_commands.push_back(command);
                 continue;
             }
+            command => command,
         };
         match command {
             ObserverCommand::Terminate => {
@@ -764,6 +1168,9 @@ pub async fn start_observer_commands_handler(
                 if let Some(ingestion_shutdown) = ingestion_shutdown {
                     ingestion_shutdown.notify();
                 }
+                for (http, occurrences) in http_batches.flush().into_iter() {
+                    dispatch_http_batch(&http, occurrences, &observer_events_tx, &ctx).await;
+                }
                 if let Some(ref tx) = observer_events_tx {
                     let _ = tx.send(ObserverEvent::Info("Terminating event observer".into()));
                     let _ = tx.send(ObserverEvent::Terminate);
@@ -772,10 +1179,16 @@ pub async fn start_observer_commands_handler(
             }
             ObserverCommand::ProcessBitcoinBlock(mut block_data) => {
                 let block_hash = block_data.hash.to_string();
+                let include_raw_tx = chainhook_store
+                    .predicates
+                    .bitcoin_chainhooks
+                    .iter()
+                    .any(|predicate| predicate.action.includes_raw_tx());
                 let block = loop {
                     match standardize_bitcoin_block(
                         block_data.clone(),
                         &config.bitcoin_network,
+                        include_raw_tx,
                         &ctx,
                     ) {
                         Ok(block) => break block,
@@ -1046,12 +1459,10 @@ pub async fn start_observer_commands_handler(
                     let mut total_occurrences: u64 = *chainhooks_occurrences_tracker
                         .get(&trigger.chainhook.uuid)
                         .unwrap_or(&0);
-                    // todo: this currently is only additive, and an occurrence means we match a chain event,
-                    // rather than the number of blocks. Should we instead add to the total occurrences for
-                    // every apply block, and subtract for every rollback? If we did this, we could set the
-                    // status to `Expired` when we go above `expire_after_occurrence` occurrences, rather than
-                    // deregistering
-                    total_occurrences += 1;
+                    // todo: this currently is only additive. Should we instead subtract for every rollback
+                    // of an applied block? If we did this, we could set the status to `Expired` when we go
+                    // above `expire_after_occurrence` occurrences, rather than deregistering
+                    total_occurrences += trigger.count_occurrences();
 
                     let limit = trigger.chainhook.expire_after_occurrence.unwrap_or(0);
                     if limit == 0 || total_occurrences <= limit {
@@ -1083,28 +1494,164 @@ pub async fn start_observer_commands_handler(
                         chainhooks_to_trigger.len(),
                     ));
                 }
-                for chainhook_to_trigger in chainhooks_to_trigger.into_iter() {
-                    match handle_bitcoin_hook_action(chainhook_to_trigger, &proofs) {
-                        Err(e) => {
-                            ctx.try_log(|logger| {
-                                slog::error!(logger, "unable to handle action {}", e)
-                            });
+                // Occurrences of predicates requiring confirmations are delivered block by block,
+                // once confirmed. The rollbacks of blocks whose occurrences were never delivered
+                // are not delivered either.
+                let (blocks_applied, blocks_rolled_back) = match &chain_event {
+                    BitcoinChainEvent::ChainUpdatedWithBlocks(update) => (
+                        update
+                            .new_blocks
+                            .iter()
+                            .map(|b| &b.block_identifier)
+                            .collect::<Vec<_>>(),
+                        vec![],
+                    ),
+                    BitcoinChainEvent::ChainUpdatedWithReorg(update) => (
+                        update
+                            .blocks_to_apply
+                            .iter()
+                            .map(|b| &b.block_identifier)
+                            .collect::<Vec<_>>(),
+                        update
+                            .blocks_to_rollback
+                            .iter()
+                            .map(|b| &b.block_identifier)
+                            .collect::<Vec<_>>(),
+                    ),
+                };
+                let orphaned_blocks = confirmation_buffer.drop_orphaned(&blocks_rolled_back);
+                let mut triggers = vec![];
+                for trigger in chainhooks_to_trigger.into_iter() {
+                    let min_confirmations = match trigger.chainhook.min_confirmations {
+                        Some(min_confirmations) if min_confirmations > 0 => min_confirmations,
+                        _ => {
+                            triggers.push((trigger, None));
+                            continue;
                         }
-                        Ok(BitcoinChainhookOccurrence::Http(request, data)) => {
-                            requests.push((request, data));
+                    };
+                    for (transactions, block) in trigger.apply.into_iter() {
+                        let confirmation =
+                            Some((block.block_identifier.clone(), min_confirmations));
+                        triggers.push((
+                            BitcoinTriggerChainhook {
+                                chainhook: trigger.chainhook,
+                                apply: vec![(transactions, block)],
+                                rollback: vec![],
+                            },
+                            confirmation,
+                        ));
+                    }
+                    let rollback = trigger
+                        .rollback
+                        .into_iter()
+                        .filter(|(_, block)| !orphaned_blocks.contains(&block.block_identifier))
+                        .collect::<Vec<_>>();
+                    if !rollback.is_empty() {
+                        triggers.push((
+                            BitcoinTriggerChainhook {
+                                chainhook: trigger.chainhook,
+                                apply: vec![],
+                                rollback,
+                            },
+                            None,
+                        ));
+                    }
+                }
+
+                for (chainhook_to_trigger, confirmation) in triggers.into_iter() {
+                    let delivery = OccurrenceDelivery::new(
+                        &chainhook_to_trigger.chainhook.uuid,
+                        chainhook_to_trigger
+                            .apply
+                            .iter()
+                            .map(|(_, block)| &block.block_identifier),
+                        chainhook_to_trigger
+                            .rollback
+                            .iter()
+                            .map(|(_, block)| &block.block_identifier),
+                    );
+                    let chainhook = chainhook_to_trigger.chainhook;
+                    let occurrence = match chainhook.action {
+                        HookAction::HttpPost(ref http) if http.batching.is_some() => {
+                            let payload =
+                                serialize_bitcoin_payload_to_json(&chainhook_to_trigger, &proofs);
+                            PendingOccurrence::Batched(
+                                http.clone(),
+                                BatchedOccurrence {
+                                    payload,
+                                    data: Some(BitcoinChainhookOccurrencePayload::from_trigger(
+                                        chainhook_to_trigger,
+                                    )),
+                                },
+                            )
                         }
-                        Ok(BitcoinChainhookOccurrence::File(_path, _bytes)) => {
-                            ctx.try_log(|logger| {
-                                slog::info!(logger, "Writing to disk not supported in server mode")
-                            })
+                        _ => match handle_bitcoin_hook_action(chainhook_to_trigger, &proofs) {
+                            Err(e) => {
+                                ctx.try_log(|logger| {
+                                    slog::error!(logger, "unable to handle action {}", e)
+                                });
+                                continue;
+                            }
+                            Ok(BitcoinChainhookOccurrence::Http(request, data)) => {
+                                PendingOccurrence::BitcoinHttp(request, delivery, data)
+                            }
+                            Ok(BitcoinChainhookOccurrence::File(_path, _, _bytes))
+                            | Ok(BitcoinChainhookOccurrence::ZstdFile(_path, _, _, _bytes)) => {
+                                ctx.try_log(|logger| {
+                                    slog::info!(
+                                        logger,
+                                        "Writing to disk not supported in server mode"
+                                    )
+                                });
+                                continue;
+                            }
+                            Ok(BitcoinChainhookOccurrence::Redis(_redis, _bytes)) => {
+                                ctx.try_log(|logger| {
+                                    slog::info!(
+                                        logger,
+                                        "Redis streams not supported in server mode"
+                                    )
+                                });
+                                continue;
+                            }
+                            Ok(BitcoinChainhookOccurrence::Kafka(_kafka, _key, _bytes)) => {
+                                ctx.try_log(|logger| {
+                                    slog::info!(logger, "Kafka topics not supported in server mode")
+                                });
+                                continue;
+                            }
+                            Ok(BitcoinChainhookOccurrence::UnixSocket(path, bytes)) => {
+                                PendingOccurrence::UnixSocket(path, bytes)
+                            }
+                            Ok(BitcoinChainhookOccurrence::Data(payload)) => {
+                                PendingOccurrence::BitcoinData(payload)
+                            }
+                        },
+                    };
+                    match (confirmation, occurrence) {
+                        (Some((block_identifier, min_confirmations)), occurrence) => {
+                            confirmation_buffer.hold(
+                                &chainhook.uuid,
+                                block_identifier,
+                                min_confirmations,
+                                occurrence,
+                            )
                         }
-                        Ok(BitcoinChainhookOccurrence::Data(payload)) => {
-                            if let Some(ref tx) = observer_events_tx {
-                                let _ = tx.send(ObserverEvent::BitcoinPredicateTriggered(payload));
+                        (
+                            None,
+                            occurrence @ (PendingOccurrence::BitcoinHttp(..)
+                            | PendingOccurrence::Batched(..)),
+                        ) => requests.push(occurrence),
+                        (None, occurrence) => {
+                            if let Some(occurrence) = dispatch_buffer.hold(occurrence) {
+                                dispatch_occurrence(occurrence, &observer_events_tx, &ctx).await;
                             }
                         }
                     }
                 }
+                if let Some(chain_tip_height) = blocks_applied.iter().map(|b| b.index).max() {
+                    requests.append(&mut confirmation_buffer.release(chain_tip_height));
+                }
                 ctx.try_log(|logger| {
                     slog::info!(
                         logger,
@@ -1128,11 +1675,9 @@ pub async fn start_observer_commands_handler(
                     }
                 }
 
-                for (request, data) in requests.into_iter() {
-                    if send_request(request, 3, 1, &ctx).await.is_ok() {
-                        if let Some(ref tx) = observer_events_tx {
-                            let _ = tx.send(ObserverEvent::BitcoinPredicateTriggered(data));
-                        }
+                for occurrence in batch_occurrences(requests, &mut http_batches, Instant::now()) {
+                    if let Some(occurrence) = dispatch_buffer.hold(occurrence) {
+                        dispatch_occurrence(occurrence, &observer_events_tx, &ctx).await;
                     }
                 }
 
@@ -1154,6 +1699,18 @@ pub async fn start_observer_commands_handler(
                     .iter()
                     .filter(|p| p.enabled)
                     .filter(|p| p.expired_at.is_none())
+                    // Predicates that already triggered their `expire_after_occurrence` occurrences
+                    // are not evaluated anymore
+                    .filter(|p| {
+                        let limit = p.expire_after_occurrence.unwrap_or(0);
+                        let occurrences =
+                            *chainhooks_occurrences_tracker.get(&p.uuid).unwrap_or(&0);
+                        if limit > 0 && occurrences >= limit {
+                            hooks_ids_to_deregister.push(p.uuid.clone());
+                            return false;
+                        }
+                        true
+                    })
                     .collect::<Vec<_>>();
                 ctx.try_log(|logger| {
                     slog::info!(
@@ -1197,9 +1754,10 @@ pub async fn start_observer_commands_handler(
 
                 // process hooks
                 let (predicates_triggered, predicates_evaluated, predicates_expired) =
-                    evaluate_stacks_chainhooks_on_chain_event(
+                    evaluate_stacks_chainhooks_on_chain_event_with_occurrences(
                         &chain_event,
                         stacks_chainhooks,
+                        &chainhooks_occurrences_tracker,
                         &ctx,
                     );
                 for (uuid, block_identifier) in predicates_evaluated.into_iter() {
@@ -1230,7 +1788,7 @@ pub async fn start_observer_commands_handler(
                     let mut total_occurrences: u64 = *chainhooks_occurrences_tracker
                         .get(&trigger.chainhook.uuid)
                         .unwrap_or(&0);
-                    total_occurrences += 1;
+                    total_occurrences += trigger.count_occurrences();
 
                     let limit = trigger.chainhook.expire_after_occurrence.unwrap_or(0);
                     if limit == 0 || total_occurrences <= limit {
@@ -1247,29 +1805,175 @@ pub async fn start_observer_commands_handler(
                         chainhooks_to_trigger.len(),
                     ));
                 }
-                let proofs = HashMap::new();
-                for chainhook_to_trigger in chainhooks_to_trigger.into_iter() {
-                    match handle_stacks_hook_action(chainhook_to_trigger, &proofs, &ctx) {
-                        Err(e) => {
-                            ctx.try_log(|logger| {
-                                slog::error!(logger, "unable to handle action {}", e)
-                            });
+                let (blocks_applied, blocks_rolled_back) = match &chain_event {
+                    StacksChainEvent::ChainUpdatedWithBlocks(update) => (
+                        update
+                            .new_blocks
+                            .iter()
+                            .map(|b| &b.block.block_identifier)
+                            .collect::<Vec<_>>(),
+                        vec![],
+                    ),
+                    StacksChainEvent::ChainUpdatedWithReorg(update) => (
+                        update
+                            .blocks_to_apply
+                            .iter()
+                            .map(|b| &b.block.block_identifier)
+                            .collect::<Vec<_>>(),
+                        update
+                            .blocks_to_rollback
+                            .iter()
+                            .map(|b| &b.block.block_identifier)
+                            .collect::<Vec<_>>(),
+                    ),
+                    _ => (vec![], vec![]),
+                };
+                let orphaned_blocks = stacks_confirmation_buffer.drop_orphaned(&blocks_rolled_back);
+                let mut triggers = vec![];
+                for trigger in chainhooks_to_trigger.into_iter() {
+                    let min_confirmations = match trigger.chainhook.min_confirmations {
+                        Some(min_confirmations) if min_confirmations > 0 => min_confirmations,
+                        _ => {
+                            triggers.push((trigger, None));
+                            continue;
                         }
-                        Ok(StacksChainhookOccurrence::Http(request)) => {
-                            requests.push(request);
+                    };
+                    // Microblocks are not confirmed by blocks: their transactions are
+                    // delivered once confirmed in the anchored blocks including them.
+                    if blocks_applied.is_empty() && blocks_rolled_back.is_empty() {
+                        continue;
+                    }
+                    for (transactions, block) in trigger.apply.into_iter() {
+                        let confirmation =
+                            Some((block.get_identifier().clone(), min_confirmations));
+                        triggers.push((
+                            StacksTriggerChainhook {
+                                chainhook: trigger.chainhook,
+                                apply: vec![(transactions, block)],
+                                rollback: vec![],
+                            },
+                            confirmation,
+                        ));
+                    }
+                    let rollback = trigger
+                        .rollback
+                        .into_iter()
+                        .filter(|(_, block)| !orphaned_blocks.contains(block.get_identifier()))
+                        .collect::<Vec<_>>();
+                    if !rollback.is_empty() {
+                        triggers.push((
+                            StacksTriggerChainhook {
+                                chainhook: trigger.chainhook,
+                                apply: vec![],
+                                rollback,
+                            },
+                            None,
+                        ));
+                    }
+                }
+
+                let proofs = HashMap::new();
+                for (chainhook_to_trigger, confirmation) in triggers.into_iter() {
+                    let delivery = OccurrenceDelivery::new(
+                        &chainhook_to_trigger.chainhook.uuid,
+                        chainhook_to_trigger
+                            .apply
+                            .iter()
+                            .map(|(_, block)| block.get_identifier()),
+                        chainhook_to_trigger
+                            .rollback
+                            .iter()
+                            .map(|(_, block)| block.get_identifier()),
+                    );
+                    let chainhook = chainhook_to_trigger.chainhook;
+                    let occurrence = match chainhook.action {
+                        HookAction::HttpPost(ref http) if http.batching.is_some() => {
+                            let payload = serialize_stacks_payload_to_json(
+                                chainhook_to_trigger,
+                                &proofs,
+                                &stacks_mempool_timestamps,
+                                &ctx,
+                            );
+                            PendingOccurrence::Batched(
+                                http.clone(),
+                                BatchedOccurrence {
+                                    payload,
+                                    data: None,
+                                },
+                            )
                         }
-                        Ok(StacksChainhookOccurrence::File(_path, _bytes)) => {
-                            ctx.try_log(|logger| {
-                                slog::info!(logger, "Writing to disk not supported in server mode")
-                            })
+                        _ => match handle_stacks_hook_action(
+                            chainhook_to_trigger,
+                            &proofs,
+                            &stacks_mempool_timestamps,
+                            &ctx,
+                        ) {
+                            Err(e) => {
+                                ctx.try_log(|logger| {
+                                    slog::error!(logger, "unable to handle action {}", e)
+                                });
+                                continue;
+                            }
+                            Ok(StacksChainhookOccurrence::Http(request)) => {
+                                PendingOccurrence::StacksHttp(request, delivery)
+                            }
+                            Ok(StacksChainhookOccurrence::File(_path, _, _bytes))
+                            | Ok(StacksChainhookOccurrence::ZstdFile(_path, _, _, _bytes)) => {
+                                ctx.try_log(|logger| {
+                                    slog::info!(
+                                        logger,
+                                        "Writing to disk not supported in server mode"
+                                    )
+                                });
+                                continue;
+                            }
+                            Ok(StacksChainhookOccurrence::Redis(_redis, _bytes)) => {
+                                ctx.try_log(|logger| {
+                                    slog::info!(
+                                        logger,
+                                        "Redis streams not supported in server mode"
+                                    )
+                                });
+                                continue;
+                            }
+                            Ok(StacksChainhookOccurrence::Kafka(_kafka, _key, _bytes)) => {
+                                ctx.try_log(|logger| {
+                                    slog::info!(logger, "Kafka topics not supported in server mode")
+                                });
+                                continue;
+                            }
+                            Ok(StacksChainhookOccurrence::UnixSocket(path, bytes)) => {
+                                PendingOccurrence::UnixSocket(path, bytes)
+                            }
+                            Ok(StacksChainhookOccurrence::Data(payload)) => {
+                                PendingOccurrence::StacksData(payload)
+                            }
+                        },
+                    };
+                    match (confirmation, occurrence) {
+                        (Some((block_identifier, min_confirmations)), occurrence) => {
+                            stacks_confirmation_buffer.hold(
+                                &chainhook.uuid,
+                                block_identifier,
+                                min_confirmations,
+                                occurrence,
+                            )
                         }
-                        Ok(StacksChainhookOccurrence::Data(payload)) => {
-                            if let Some(ref tx) = observer_events_tx {
-                                let _ = tx.send(ObserverEvent::StacksPredicateTriggered(payload));
+                        (
+                            None,
+                            occurrence @ (PendingOccurrence::StacksHttp(..)
+                            | PendingOccurrence::Batched(..)),
+                        ) => requests.push(occurrence),
+                        (None, occurrence) => {
+                            if let Some(occurrence) = dispatch_buffer.hold(occurrence) {
+                                dispatch_occurrence(occurrence, &observer_events_tx, &ctx).await;
                             }
                         }
                     }
                 }
+                if let Some(chain_tip_height) = blocks_applied.iter().map(|b| b.index).max() {
+                    requests.append(&mut stacks_confirmation_buffer.release(chain_tip_height));
+                }
 
                 for hook_uuid in hooks_ids_to_deregister.iter() {
                     if let Some(chainhook) = chainhook_store
@@ -1286,16 +1990,26 @@ pub async fn start_observer_commands_handler(
                     }
                 }
 
-                for request in requests.into_iter() {
-                    // todo(lgalabru): collect responses for reporting
-                    ctx.try_log(|logger| {
-                        slog::info!(
-                            logger,
-                            "Dispatching request from stacks chainhook {:?}",
-                            request
-                        )
-                    });
-                    let _ = send_request(request, 3, 1, &ctx).await;
+                for occurrence in batch_occurrences(requests, &mut http_batches, Instant::now()) {
+                    if let Some(occurrence) = dispatch_buffer.hold(occurrence) {
+                        dispatch_occurrence(occurrence, &observer_events_tx, &ctx).await;
+                    }
+                }
+
+                // Mined transactions are no longer pending
+                let blocks_applied = match &chain_event {
+                    StacksChainEvent::ChainUpdatedWithBlocks(update) => {
+                        update.new_blocks.iter().collect::<Vec<_>>()
+                    }
+                    StacksChainEvent::ChainUpdatedWithReorg(update) => {
+                        update.blocks_to_apply.iter().collect::<Vec<_>>()
+                    }
+                    _ => vec![],
+                };
+                for block_update in blocks_applied.into_iter() {
+                    for transaction in block_update.block.transactions.iter() {
+                        stacks_mempool_timestamps.remove(&transaction.transaction_identifier);
+                    }
                 }
 
                 if let Some(ref tx) = observer_events_tx {
@@ -1306,6 +2020,23 @@ pub async fn start_observer_commands_handler(
                 ctx.try_log(|logger| {
                     slog::debug!(logger, "Handling PropagateStacksMempoolEvent command")
                 });
+                match mempool_event {
+                    StacksChainMempoolEvent::TransactionsAdmitted(ref transactions) => {
+                        let now = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .expect("Could not get current time")
+                            .as_secs() as i64;
+                        for transaction in transactions.iter() {
+                            stacks_mempool_timestamps
+                                .entry(TransactionIdentifier::new(&transaction.txid))
+                                .or_insert(now);
+                        }
+                    }
+                    // Dropped transactions won't be mined, unless admitted again
+                    StacksChainMempoolEvent::TransactionDropped(ref txid) => {
+                        stacks_mempool_timestamps.remove(&TransactionIdentifier::new(txid));
+                    }
+                }
                 if let Some(ref tx) = observer_events_tx {
                     let _ = tx.send(ObserverEvent::StacksChainMempoolEvent(mempool_event));
                 }
@@ -1318,6 +2049,24 @@ pub async fn start_observer_commands_handler(
                     let _ = tx.send(ObserverEvent::NotifyBitcoinTransactionProxied);
                 }
             }
+            ObserverCommand::PauseDispatch => {
+                ctx.try_log(|logger| slog::info!(logger, "Handling PauseDispatch command"));
+                dispatch_buffer.pause();
+                if let Some(ref tx) = observer_events_tx {
+                    let _ = tx.send(ObserverEvent::DispatchPaused);
+                }
+            }
+            ObserverCommand::ResumeDispatch => {
+                ctx.try_log(|logger| slog::info!(logger, "Handling ResumeDispatch command"));
+                let occurrences = dispatch_buffer.resume();
+                let occurrences_flushed = occurrences.len();
+                for occurrence in occurrences.into_iter() {
+                    dispatch_occurrence(occurrence, &observer_events_tx, &ctx).await;
+                }
+                if let Some(ref tx) = observer_events_tx {
+                    let _ = tx.send(ObserverEvent::DispatchResumed(occurrences_flushed));
+                }
+            }
             ObserverCommand::RegisterPredicate(spec) => {
                 ctx.try_log(|logger| slog::info!(logger, "Handling RegisterPredicate command"));
 
@@ -1334,8 +2083,7 @@ pub async fn start_observer_commands_handler(
                                 e.to_string()
                             )
                         });
-                        panic!("Unable to register new chainhook spec: {}", e.to_string());
-                        //continue;
+                        continue;
                     }
                 };
 
@@ -1367,6 +2115,7 @@ pub async fn start_observer_commands_handler(
                 ctx.try_log(|logger| {
                     slog::info!(logger, "Handling DeregisterStacksPredicate command")
                 });
+                stacks_confirmation_buffer.drop_predicate(&hook_uuid);
                 let hook = chainhook_store.predicates.deregister_stacks_hook(hook_uuid);
 
                 prometheus_monitoring.stx_metrics_deregister_predicate();
@@ -1381,6 +2130,7 @@ pub async fn start_observer_commands_handler(
                 ctx.try_log(|logger| {
                     slog::info!(logger, "Handling DeregisterBitcoinPredicate command")
                 });
+                confirmation_buffer.drop_predicate(&hook_uuid);
                 let hook = chainhook_store
                     .predicates
                     .deregister_bitcoin_hook(hook_uuid);
@@ -1,33 +1,38 @@
 use crate::chainhooks::types::{
     BitcoinChainhookFullSpecification, BitcoinChainhookNetworkSpecification,
     BitcoinChainhookSpecification, BitcoinPredicateType, ChainhookConfig,
-    ChainhookFullSpecification, ChainhookSpecification, ExactMatchingRule, HookAction,
-    OrdinalOperations, OutputPredicate, StacksChainhookFullSpecification,
+    ChainhookFullSpecification, ChainhookSpecification, ExactMatchingRule, HookAction, HttpHook,
+    OccurrenceBatchingPolicy, OrdinalOperations, OutputPredicate, StacksChainhookFullSpecification,
     StacksChainhookNetworkSpecification, StacksChainhookSpecification,
     StacksContractCallBasedPredicate, StacksPredicate,
 };
-use crate::indexer::fork_scratch_pad::ForkScratchPad;
+use crate::indexer::fork_scratch_pad::{ForkScratchPad, DEFAULT_MAX_REORG_DEPTH};
 use crate::indexer::tests::helpers::transactions::generate_test_tx_bitcoin_p2pkh_transfer;
 use crate::indexer::tests::helpers::{
     accounts, bitcoin_blocks, stacks_blocks, transactions::generate_test_tx_stacks_contract_call,
 };
 use crate::monitoring::PrometheusMonitoring;
 use crate::observer::{
-    start_observer_commands_handler, ChainhookStore, EventObserverConfig, ObserverCommand,
-    ObserverSidecar,
+    batch_occurrences, send_tracked_occurrence, start_observer_commands_handler, BatchedOccurrence,
+    ChainhookStore, ConfirmationBuffer, EventObserverConfig, ObserverCommand, ObserverSidecar,
+    OccurrenceDelivery, OccurrenceDeliveryStatus, PendingOccurrence,
 };
-use crate::utils::{AbstractBlock, Context};
+use crate::utils::{AbstractBlock, Context, HttpOccurrenceBatches};
 use chainhook_types::{
-    BitcoinBlockSignaling, BitcoinChainEvent, BitcoinNetwork, BlockchainEvent,
+    BitcoinBlockSignaling, BitcoinChainEvent, BitcoinNetwork, BlockIdentifier, BlockchainEvent,
     BlockchainUpdatedWithHeaders, OrdinalInscriptionNumber, OrdinalInscriptionRevealData,
     OrdinalOperation, StacksBlockUpdate, StacksChainEvent, StacksChainUpdatedWithBlocksData,
     StacksNetwork, StacksNodeConfig,
 };
 use hiro_system_kit;
 use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::TcpListener;
 use std::sync::mpsc::{channel, Sender};
+use std::time::{Duration, Instant};
+use test_case::test_case;
 
-use super::{ObserverEvent, DEFAULT_INGESTION_PORT};
+use super::{ObserverEvent, DEFAULT_DISPATCH_BUFFER_CAPACITY, DEFAULT_INGESTION_PORT};
 
 fn generate_test_config() -> (EventObserverConfig, ChainhookStore) {
     let config: EventObserverConfig = EventObserverConfig {
@@ -46,6 +51,8 @@ fn generate_test_config() -> (EventObserverConfig, ChainhookStore) {
         stacks_network: StacksNetwork::Devnet,
         data_handler_tx: None,
         prometheus_monitoring_port: None,
+        bitcoin_max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
+        dispatch_buffer_capacity: DEFAULT_DISPATCH_BUFFER_CAPACITY,
     };
     let predicates = ChainhookConfig::new();
     let chainhook_store = ChainhookStore { predicates };
@@ -69,9 +76,18 @@ fn stacks_chainhook_contract_call(
             capture_all_events: None,
             decode_clarity_values: Some(true),
             include_contract_abi: None,
+            projection: None,
+            include_mempool_timestamps: None,
+            identifier_format: None,
+            derived_fields: None,
+            max_clarity_value_length: None,
+            trigger_on: None,
+            min_confirmations: None,
             predicate: StacksPredicate::ContractCall(StacksContractCallBasedPredicate {
-                contract_identifier: contract_identifier.to_string(),
+                contract_identifier: contract_identifier.into(),
                 method: method.to_string(),
+                case_insensitive: None,
+                args: None,
             }),
             action: HookAction::Noop,
         },
@@ -97,6 +113,7 @@ fn bitcoin_chainhook_p2pkh(
         BitcoinNetwork::Regtest,
         BitcoinChainhookNetworkSpecification {
             start_block: None,
+            start_block_offset_from_tip: None,
             end_block: None,
             blocks: None,
             expire_after_occurrence,
@@ -108,6 +125,10 @@ fn bitcoin_chainhook_p2pkh(
             include_inputs: None,
             include_outputs: None,
             include_witness: None,
+            identifier_format: None,
+            stop_on_match: None,
+            trigger_on: None,
+            min_confirmations: None,
         },
     );
 
@@ -127,6 +148,7 @@ fn bitcoin_chainhook_ordinals(id: u8) -> BitcoinChainhookFullSpecification {
         BitcoinNetwork::Regtest,
         BitcoinChainhookNetworkSpecification {
             start_block: None,
+            start_block_offset_from_tip: None,
             end_block: None,
             blocks: None,
             expire_after_occurrence: None,
@@ -136,6 +158,10 @@ fn bitcoin_chainhook_ordinals(id: u8) -> BitcoinChainhookFullSpecification {
             include_inputs: None,
             include_outputs: None,
             include_witness: None,
+            identifier_format: None,
+            stop_on_match: None,
+            trigger_on: None,
+            min_confirmations: None,
         },
     );
 
@@ -710,6 +736,105 @@ fn test_stacks_chainhook_auto_deregister() {
     handle.join().expect("unable to terminate thread");
 }
 
+fn generate_stacks_chain_event_with_increment(block_height: u64) -> StacksChainEvent {
+    let transactions = vec![generate_test_tx_stacks_contract_call(
+        block_height,
+        &accounts::wallet_1_stx_address(),
+        "counter",
+        "increment",
+        vec!["u1"],
+    )];
+    StacksChainEvent::ChainUpdatedWithBlocks(StacksChainUpdatedWithBlocksData {
+        new_blocks: vec![StacksBlockUpdate::new(
+            stacks_blocks::generate_test_stacks_block(0, block_height, transactions, None)
+                .expect_block(),
+        )],
+        confirmed_blocks: vec![],
+    })
+}
+
+fn assert_stacks_predicate_triggered_on_block(
+    observer_events_rx: &crossbeam_channel::Receiver<ObserverEvent>,
+    block_height: u64,
+) {
+    match observer_events_rx.recv() {
+        Ok(ObserverEvent::StacksPredicateTriggered(payload)) => {
+            assert_eq!(payload.apply.len(), 1);
+            assert_eq!(payload.apply[0].block_identifier.index, block_height);
+        }
+        _ => panic!("expected StacksPredicateTriggered event to occur"),
+    }
+}
+
+#[test]
+fn test_stacks_chainhook_dispatch_paused_and_resumed() {
+    let (observer_commands_tx, observer_commands_rx) = channel();
+    let (observer_events_tx, observer_events_rx) = crossbeam_channel::unbounded();
+    let prometheus_monitoring = PrometheusMonitoring::new();
+
+    let handle = std::thread::spawn(move || {
+        let (mut config, chainhook_store) = generate_test_config();
+        config.dispatch_buffer_capacity = 1;
+        let _ = hiro_system_kit::nestable_block_on(start_observer_commands_handler(
+            config,
+            chainhook_store,
+            observer_commands_rx,
+            Some(observer_events_tx),
+            None,
+            prometheus_monitoring,
+            None,
+            Context::empty(),
+        ));
+    });
+
+    generate_and_register_new_stacks_chainhook(
+        &observer_commands_tx,
+        &observer_events_rx,
+        1,
+        "counter",
+        "increment",
+    );
+    assert!(match observer_events_rx.recv() {
+        Ok(ObserverEvent::PredicateEnabled(_)) => true,
+        _ => false,
+    });
+
+    let _ = observer_commands_tx.send(ObserverCommand::PauseDispatch);
+    assert!(match observer_events_rx.recv() {
+        Ok(ObserverEvent::DispatchPaused) => true,
+        _ => false,
+    });
+
+    // Blocks keep being evaluated, but the occurrence is held in the buffer
+    let _ = observer_commands_tx.send(ObserverCommand::PropagateStacksChainEvent(
+        generate_stacks_chain_event_with_increment(1),
+    ));
+    assert_predicates_triggered_event(&observer_events_rx, 1);
+    assert_stacks_chain_event(&observer_events_rx);
+
+    // The buffer is now full: the next block is queued without being evaluated
+    let _ = observer_commands_tx.send(ObserverCommand::PropagateStacksChainEvent(
+        generate_stacks_chain_event_with_increment(2),
+    ));
+    assert!(observer_events_rx
+        .recv_timeout(std::time::Duration::from_millis(500))
+        .is_err());
+
+    // Resuming flushes the buffered occurrence, then evaluates the queued block
+    let _ = observer_commands_tx.send(ObserverCommand::ResumeDispatch);
+    assert_stacks_predicate_triggered_on_block(&observer_events_rx, 1);
+    assert!(match observer_events_rx.recv() {
+        Ok(ObserverEvent::DispatchResumed(occurrences_flushed)) => occurrences_flushed == 1,
+        _ => false,
+    });
+    assert_predicates_triggered_event(&observer_events_rx, 1);
+    assert_stacks_predicate_triggered_on_block(&observer_events_rx, 2);
+    assert_stacks_chain_event(&observer_events_rx);
+
+    let _ = observer_commands_tx.send(ObserverCommand::Terminate);
+    handle.join().expect("unable to terminate thread");
+}
+
 #[test]
 fn test_bitcoin_chainhook_register_deregister() {
     let (observer_commands_tx, observer_commands_rx) = channel();
@@ -1320,3 +1445,399 @@ fn test_bitcoin_chainhook_through_reorg() {
         .join()
         .expect("unable to terminate thread");
 }
+
+/// Returns the heights of the blocks applied by the Bitcoin occurrences delivered until the
+/// chain event is propagated.
+fn collect_bitcoin_occurrences_heights(
+    observer_events_rx: &crossbeam_channel::Receiver<ObserverEvent>,
+) -> Vec<u64> {
+    let mut heights = vec![];
+    loop {
+        match observer_events_rx.recv() {
+            Ok(ObserverEvent::BitcoinPredicateTriggered(payload)) => {
+                assert!(payload.rollback.is_empty());
+                heights.append(
+                    &mut payload
+                        .apply
+                        .iter()
+                        .map(|a| a.block.block_identifier.index)
+                        .collect(),
+                );
+            }
+            Ok(ObserverEvent::BitcoinChainEvent(_)) => break,
+            Ok(_) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+        }
+    }
+    heights
+}
+
+#[test]
+fn test_bitcoin_chainhook_min_confirmations() {
+    let (observer_commands_tx, observer_commands_rx) = channel();
+    let (observer_events_tx, observer_events_rx) = crossbeam_channel::unbounded();
+    let empty_ctx = Context::empty();
+
+    let handle = std::thread::spawn(move || {
+        let (config, chainhook_store) = generate_test_config();
+        let _ = hiro_system_kit::nestable_block_on(start_observer_commands_handler(
+            config,
+            chainhook_store,
+            observer_commands_rx,
+            Some(observer_events_tx),
+            None,
+            PrometheusMonitoring::new(),
+            None,
+            Context::empty(),
+        ));
+    });
+
+    let mut chainhook = bitcoin_chainhook_p2pkh(1, &accounts::wallet_2_btc_address(), None);
+    chainhook
+        .networks
+        .get_mut(&BitcoinNetwork::Regtest)
+        .unwrap()
+        .min_confirmations = Some(2);
+    let _ = observer_commands_tx.send(ObserverCommand::RegisterPredicate(
+        ChainhookFullSpecification::Bitcoin(chainhook.clone()),
+    ));
+    let mut chainhook = chainhook
+        .into_selected_network_specification(&BitcoinNetwork::Regtest)
+        .unwrap();
+    chainhook.enabled = true;
+    let _ = observer_commands_tx.send(ObserverCommand::EnablePredicate(
+        ChainhookSpecification::Bitcoin(chainhook),
+    ));
+    assert!(matches!(
+        observer_events_rx.recv(),
+        Ok(ObserverEvent::PredicateRegistered(_))
+    ));
+    assert!(matches!(
+        observer_events_rx.recv(),
+        Ok(ObserverEvent::PredicateEnabled(_))
+    ));
+
+    let genesis = bitcoin_blocks::generate_test_bitcoin_block(0, 1, vec![], None);
+    let mut fork_pad = ForkScratchPad::new();
+    let _ = fork_pad.process_header(genesis.get_header(), &empty_ctx);
+
+    // (fork, height, triggering the predicate, heights of the occurrences delivered)
+    let steps: Vec<(u8, u64, bool, Vec<u64>)> = vec![
+        // Triggering block, waiting for 2 confirmations
+        (0, 2, true, vec![]),
+        // Reorg orphaning the triggering block: its occurrence is dropped
+        (1, 2, false, vec![]),
+        // Triggering block on the canonical fork
+        (1, 3, true, vec![]),
+        (1, 4, false, vec![]),
+        // Second confirmation of the block #3
+        (1, 5, false, vec![3]),
+        (1, 6, false, vec![]),
+    ];
+    for (fork_id, block_height, triggering, expected_heights) in steps.into_iter() {
+        let recipient = match triggering {
+            true => accounts::wallet_2_btc_address(),
+            false => accounts::wallet_3_btc_address(),
+        };
+        let transactions = vec![generate_test_tx_bitcoin_p2pkh_transfer(
+            0,
+            &accounts::wallet_1_btc_address(),
+            &recipient,
+            3,
+        )];
+        let block =
+            bitcoin_blocks::generate_test_bitcoin_block(fork_id, block_height, transactions, None);
+        let _ = observer_commands_tx.send(ObserverCommand::CacheBitcoinBlock(block.clone()));
+        let chain_event = fork_pad
+            .process_header(block.get_header(), &empty_ctx)
+            .unwrap()
+            .unwrap();
+        let _ = observer_commands_tx.send(ObserverCommand::PropagateBitcoinChainEvent(chain_event));
+        assert_eq!(
+            collect_bitcoin_occurrences_heights(&observer_events_rx),
+            expected_heights,
+            "unexpected occurrences after block #{} of fork {}",
+            block_height,
+            fork_id
+        );
+    }
+
+    let _ = observer_commands_tx.send(ObserverCommand::Terminate);
+    handle.join().expect("unable to terminate thread");
+}
+
+#[test]
+fn test_confirmation_buffer_drops_the_occurrences_of_a_deregistered_predicate() {
+    let mut buffer = ConfirmationBuffer::new();
+    for (predicate_uuid, block_height) in [("1", 3), ("2", 3), ("1", 4)] {
+        buffer.hold(
+            predicate_uuid,
+            BlockIdentifier {
+                index: block_height,
+                hash: format!("0x{block_height}"),
+            },
+            2,
+            PendingOccurrence::UnixSocket(predicate_uuid.to_string(), vec![]),
+        );
+    }
+    assert_eq!(buffer.len(), 3);
+
+    buffer.drop_predicate("1");
+    assert_eq!(buffer.len(), 1);
+    let released = buffer.release(6);
+    assert_eq!(released.len(), 1);
+    assert!(matches!(
+        &released[0],
+        PendingOccurrence::UnixSocket(predicate_uuid, _) if predicate_uuid == "2"
+    ));
+    assert!(buffer.is_empty());
+}
+
+/// Serves a single HTTP request with the given status code, and returns the headers received.
+fn serve_single_request(status_code: u16) -> (String, std::thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("unable to bind listener");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("unable to accept connection");
+        let mut request = vec![];
+        let mut buffer = [0u8; 1024];
+        let headers = loop {
+            let read = stream.read(&mut buffer).expect("unable to read request");
+            request.extend_from_slice(&buffer[..read]);
+            let payload = String::from_utf8_lossy(&request).to_string();
+            if let Some(end) = payload.find("\r\n\r\n") {
+                let headers = payload[..end].to_string();
+                let content_length = headers
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        match name.eq_ignore_ascii_case("content-length") {
+                            true => value.trim().parse::<usize>().ok(),
+                            false => None,
+                        }
+                    })
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + content_length {
+                    break headers;
+                }
+            }
+        };
+        let response = format!(
+            "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            status_code
+        );
+        stream
+            .write_all(response.as_bytes())
+            .expect("unable to write response");
+        headers
+    });
+    (url, handle)
+}
+
+#[test_case(200, OccurrenceDeliveryStatus::Acked ; "acked by receiver")]
+#[test_case(500, OccurrenceDeliveryStatus::Failed ; "rejected by receiver")]
+#[tokio::test]
+async fn test_occurrence_delivery_status_transitions(
+    status_code: u16,
+    expected_status: OccurrenceDeliveryStatus,
+) {
+    let (url, server_handle) = serve_single_request(status_code);
+    let (observer_events_tx, observer_events_rx) = crossbeam_channel::unbounded();
+    let block_identifier = BlockIdentifier {
+        index: 1,
+        hash: "0x01".into(),
+    };
+    let delivery =
+        OccurrenceDelivery::new("1", vec![&block_identifier].into_iter(), std::iter::empty());
+    let request = reqwest::Client::new().post(url).body("{}");
+
+    let res = send_tracked_occurrence(
+        request,
+        delivery.clone(),
+        1,
+        0,
+        &Some(observer_events_tx),
+        &Context::empty(),
+    )
+    .await;
+    assert_eq!(
+        res.is_ok(),
+        expected_status == OccurrenceDeliveryStatus::Acked
+    );
+
+    let headers = server_handle.join().expect("unable to join server thread");
+    assert!(headers
+        .to_lowercase()
+        .contains(&format!("idempotency-key: {}", delivery.idempotency_key)));
+
+    let transitions = observer_events_rx
+        .try_iter()
+        .map(|event| match event {
+            ObserverEvent::OccurrenceDeliveryUpdated((event_delivery, status)) => {
+                assert_eq!(event_delivery, delivery);
+                status
+            }
+            _ => panic!("unexpected observer event"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        transitions,
+        vec![OccurrenceDeliveryStatus::Pending, expected_status]
+    );
+}
+
+#[test]
+fn test_batch_occurrences_queues_batched_hooks() {
+    let http = HttpHook {
+        url: "http://localhost:20455/chainhook".to_string(),
+        authorization_header: "Bearer token".to_string(),
+        batching: Some(OccurrenceBatchingPolicy {
+            window_secs: 10,
+            max_batch_size: 2,
+        }),
+        max_retries: None,
+        initial_backoff_ms: None,
+        backoff_multiplier: None,
+        signing_secret: None,
+        template: None,
+        gzip: None,
+        gzip_threshold_bytes: None,
+        include_raw_tx: None,
+    };
+    let batched = |block_height: u64| {
+        PendingOccurrence::Batched(
+            http.clone(),
+            BatchedOccurrence {
+                payload: serde_json::json!({ "block_height": block_height }),
+                data: None,
+            },
+        )
+    };
+    let mut batches = HttpOccurrenceBatches::new();
+    let start = Instant::now();
+
+    let ready = batch_occurrences(
+        vec![
+            batched(1),
+            PendingOccurrence::UnixSocket("/tmp/chainhook.sock".into(), vec![]),
+        ],
+        &mut batches,
+        start,
+    );
+    assert!(matches!(ready[..], [PendingOccurrence::UnixSocket(..)]));
+    assert_eq!(
+        batches.next_deadline(),
+        Some(start + Duration::from_secs(10))
+    );
+
+    // The batch is dispatched once full, the next occurrences waiting for the window to elapse.
+    let ready = batch_occurrences(vec![batched(2), batched(3)], &mut batches, start);
+    match &ready[..] {
+        [PendingOccurrence::HttpBatch(batch_http, occurrences)] => {
+            assert_eq!(batch_http, &http);
+            assert_eq!(
+                occurrences
+                    .iter()
+                    .map(|occurrence| occurrence.payload["block_height"].as_u64().unwrap())
+                    .collect::<Vec<_>>(),
+                vec![1, 2]
+            );
+        }
+        _ => panic!("expected a single batch of occurrences"),
+    }
+    assert!(!batches.is_empty());
+    let ready = batch_occurrences(vec![], &mut batches, start + Duration::from_secs(10));
+    assert!(
+        matches!(&ready[..], [PendingOccurrence::HttpBatch(_, occurrences)] if occurrences.len() == 1)
+    );
+    assert!(batches.is_empty());
+    assert_eq!(batches.next_deadline(), None);
+}
+
+#[test]
+fn test_bitcoin_batch_is_posted_once_its_window_elapses() {
+    let (url, server_handle) = serve_single_request(200);
+    let (observer_commands_tx, observer_commands_rx) = channel();
+    let (observer_events_tx, observer_events_rx) = crossbeam_channel::unbounded();
+    let empty_ctx = Context::empty();
+
+    let handle = std::thread::spawn(move || {
+        let (config, chainhook_store) = generate_test_config();
+        let _ = hiro_system_kit::nestable_block_on(start_observer_commands_handler(
+            config,
+            chainhook_store,
+            observer_commands_rx,
+            Some(observer_events_tx),
+            None,
+            PrometheusMonitoring::new(),
+            None,
+            Context::empty(),
+        ));
+    });
+
+    let mut chainhook = bitcoin_chainhook_p2pkh(1, &accounts::wallet_2_btc_address(), None);
+    chainhook
+        .networks
+        .get_mut(&BitcoinNetwork::Regtest)
+        .unwrap()
+        .action = HookAction::HttpPost(HttpHook {
+        url,
+        authorization_header: "".to_string(),
+        batching: Some(OccurrenceBatchingPolicy {
+            window_secs: 1,
+            max_batch_size: 10,
+        }),
+        max_retries: None,
+        initial_backoff_ms: None,
+        backoff_multiplier: None,
+        signing_secret: None,
+        template: None,
+        gzip: None,
+        gzip_threshold_bytes: None,
+        include_raw_tx: None,
+    });
+    let _ = observer_commands_tx.send(ObserverCommand::RegisterPredicate(
+        ChainhookFullSpecification::Bitcoin(chainhook.clone()),
+    ));
+    let mut chainhook = chainhook
+        .into_selected_network_specification(&BitcoinNetwork::Regtest)
+        .unwrap();
+    chainhook.enabled = true;
+    let _ = observer_commands_tx.send(ObserverCommand::EnablePredicate(
+        ChainhookSpecification::Bitcoin(chainhook),
+    ));
+
+    let genesis = bitcoin_blocks::generate_test_bitcoin_block(0, 1, vec![], None);
+    let mut fork_pad = ForkScratchPad::new();
+    let _ = fork_pad.process_header(genesis.get_header(), &empty_ctx);
+    let transactions = vec![generate_test_tx_bitcoin_p2pkh_transfer(
+        0,
+        &accounts::wallet_1_btc_address(),
+        &accounts::wallet_2_btc_address(),
+        3,
+    )];
+    let block = bitcoin_blocks::generate_test_bitcoin_block(0, 2, transactions, None);
+    let _ = observer_commands_tx.send(ObserverCommand::CacheBitcoinBlock(block.clone()));
+    let chain_event = fork_pad
+        .process_header(block.get_header(), &empty_ctx)
+        .unwrap()
+        .unwrap();
+    let _ = observer_commands_tx.send(ObserverCommand::PropagateBitcoinChainEvent(chain_event));
+
+    // No other command is sent: the batch is posted once its window elapses.
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        match observer_events_rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(ObserverEvent::BitcoinPredicateTriggered(payload)) => {
+                assert_eq!(payload.apply[0].block.block_identifier.index, 2);
+                break;
+            }
+            Ok(_) => {}
+            Err(e) => panic!("batch not posted: {:?}", e),
+        }
+    }
+    server_handle.join().expect("unable to join server");
+
+    let _ = observer_commands_tx.send(ObserverCommand::Terminate);
+    handle.join().expect("unable to terminate thread");
+}
@@ -54,7 +54,8 @@ pub async fn start_zeromq_runloop(
     assert!(socket.connect(&bitcoind_zmq_url).is_ok());
     ctx.try_log(|logger| slog::info!(logger, "Waiting for ZMQ messages from bitcoind"));
 
-    let mut bitcoin_blocks_pool = ForkScratchPad::new();
+    let mut bitcoin_blocks_pool =
+        ForkScratchPad::with_max_reorg_depth(config.bitcoin_max_reorg_depth);
 
     loop {
         let msg = match socket.recv_multipart(0) {
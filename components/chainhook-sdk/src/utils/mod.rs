@@ -1,16 +1,27 @@
 use std::{
-    collections::{BTreeSet, VecDeque},
+    collections::{BTreeSet, HashMap, VecDeque},
     fs::{self, OpenOptions},
     io::{Read, Write},
     path::PathBuf,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+use crate::chainhooks::types::{
+    FileRotationPolicy, HttpHook, OccurrenceBatchingPolicy, DEFAULT_HTTP_BACKOFF_MULTIPLIER,
+    DEFAULT_HTTP_INITIAL_BACKOFF_MS, DEFAULT_HTTP_MAX_RETRIES,
+};
+use bitcoincore_rpc::bitcoin::hashes::{
+    hmac::{Hmac, HmacEngine},
+    sha256, Hash, HashEngine,
+};
 use chainhook_types::{
     BitcoinBlockData, BlockHeader, BlockIdentifier, StacksBlockData, StacksMicroblockData,
-    StacksTransactionData,
+    StacksNetwork, StacksTransactionData,
 };
+use flate2::{write::GzEncoder, Compression};
 use hiro_system_kit::slog::{self, Logger};
-use reqwest::RequestBuilder;
+use reqwest::{Client, Method, RequestBuilder};
 use serde_json::Value as JsonValue;
 
 #[derive(Clone)]
@@ -41,12 +52,17 @@ impl Context {
     }
 }
 
-pub trait AbstractStacksBlock {
+pub trait AbstractStacksBlock: Sync {
     fn get_identifier(&self) -> &BlockIdentifier;
     fn get_parent_identifier(&self) -> &BlockIdentifier;
+    /// Anchor block the block builds on: the parent of an anchor block, the anchor block
+    /// a microblock is extending.
+    fn get_anchor_block_identifier(&self) -> &BlockIdentifier;
     fn get_transactions(&self) -> &Vec<StacksTransactionData>;
     fn get_timestamp(&self) -> i64;
     fn get_serialized_metadata(&self) -> JsonValue;
+    /// Network the block was produced on, when known.
+    fn get_network(&self) -> Option<&StacksNetwork>;
 }
 
 impl AbstractStacksBlock for StacksBlockData {
@@ -58,6 +74,10 @@ impl AbstractStacksBlock for StacksBlockData {
         &self.parent_block_identifier
     }
 
+    fn get_anchor_block_identifier(&self) -> &BlockIdentifier {
+        &self.parent_block_identifier
+    }
+
     fn get_transactions(&self) -> &Vec<StacksTransactionData> {
         &self.transactions
     }
@@ -69,6 +89,10 @@ impl AbstractStacksBlock for StacksBlockData {
     fn get_serialized_metadata(&self) -> JsonValue {
         json!(self.metadata)
     }
+
+    fn get_network(&self) -> Option<&StacksNetwork> {
+        self.metadata.network.as_ref()
+    }
 }
 
 impl AbstractStacksBlock for StacksMicroblockData {
@@ -80,6 +104,10 @@ impl AbstractStacksBlock for StacksMicroblockData {
         &self.parent_block_identifier
     }
 
+    fn get_anchor_block_identifier(&self) -> &BlockIdentifier {
+        &self.metadata.anchor_block_identifier
+    }
+
     fn get_transactions(&self) -> &Vec<StacksTransactionData> {
         &self.transactions
     }
@@ -91,6 +119,10 @@ impl AbstractStacksBlock for StacksMicroblockData {
     fn get_serialized_metadata(&self) -> JsonValue {
         json!(self.metadata)
     }
+
+    fn get_network(&self) -> Option<&StacksNetwork> {
+        self.metadata.network.as_ref()
+    }
 }
 
 pub trait AbstractBlock {
@@ -144,13 +176,65 @@ impl AbstractBlock for BitcoinBlockData {
     }
 }
 
+/// Upper bound of the delay between two attempts of a delivery.
+pub const MAX_RETRY_BACKOFF_MS: u64 = 60_000;
+
+/// Retries of a failed delivery, shared by every sink: up to `max_retries` retries, the delay
+/// between attempts starting at `initial_backoff_ms` and being multiplied by
+/// `backoff_multiplier` after each retry, up to `MAX_RETRY_BACKOFF_MS`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u16,
+    pub initial_backoff_ms: u64,
+    pub backoff_multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: DEFAULT_HTTP_MAX_RETRIES,
+            initial_backoff_ms: DEFAULT_HTTP_INITIAL_BACKOFF_MS,
+            backoff_multiplier: DEFAULT_HTTP_BACKOFF_MULTIPLIER,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delays to wait before each retry, in order.
+    pub fn get_backoff_delays(&self) -> impl Iterator<Item = Duration> {
+        let backoff_multiplier = self.backoff_multiplier;
+        let max_backoff_ms = MAX_RETRY_BACKOFF_MS as f64;
+        std::iter::successors(
+            Some((self.initial_backoff_ms as f64).min(max_backoff_ms)),
+            move |backoff_ms| Some((backoff_ms * backoff_multiplier).min(max_backoff_ms)),
+        )
+        .take(self.max_retries.into())
+        .map(|backoff_ms| Duration::from_millis(backoff_ms as u64))
+    }
+}
+
 pub async fn send_request(
     request_builder: RequestBuilder,
     attempts_max: u16,
     attempts_interval_sec: u16,
     ctx: &Context,
 ) -> Result<(), String> {
-    let mut retry = 0;
+    let retry_policy = RetryPolicy {
+        max_retries: attempts_max.saturating_sub(1),
+        initial_backoff_ms: u64::from(attempts_interval_sec) * 1000,
+        backoff_multiplier: 1.0,
+    };
+    send_request_with_backoff(request_builder, &retry_policy, ctx).await
+}
+
+/// Sends the request, retrying it following `retry_policy` on connection errors and non 2xx
+/// responses.
+pub async fn send_request_with_backoff(
+    request_builder: RequestBuilder,
+    retry_policy: &RetryPolicy,
+    ctx: &Context,
+) -> Result<(), String> {
+    let mut backoff_delays = retry_policy.get_backoff_delays();
     loop {
         let request_builder = match request_builder.try_clone() {
             Some(rb) => rb,
@@ -167,7 +251,6 @@ pub async fn send_request(
                     ctx.try_log(|logger| slog::info!(logger, "Trigger {} successful", res.url()));
                     return Ok(());
                 } else {
-                    retry += 1;
                     let err_msg =
                         format!("Trigger {} failed with status {}", res.url(), res.status());
                     ctx.try_log(|logger| slog::warn!(logger, "{}", err_msg));
@@ -175,25 +258,32 @@ pub async fn send_request(
                 }
             }
             Err(e) => {
-                retry += 1;
                 let err_msg = format!("unable to send request {}", e.to_string());
                 ctx.try_log(|logger| slog::warn!(logger, "{}", err_msg));
                 err_msg
             }
         };
-        if retry >= attempts_max {
+        let Some(backoff_delay) = backoff_delays.next() else {
             let msg: String = format!(
                 "unable to send request after several retries. most recent error: {}",
                 err_msg
             );
             ctx.try_log(|logger| slog::warn!(logger, "{}", msg));
             return Err(msg);
-        }
-        std::thread::sleep(std::time::Duration::from_secs(attempts_interval_sec.into()));
+        };
+        tokio::time::sleep(backoff_delay).await;
     }
 }
 
-pub fn file_append(path: String, bytes: Vec<u8>, ctx: &Context) -> Result<(), String> {
+/// Append `bytes`, followed by a newline, to the file at `path`. When the file reached one
+/// of the thresholds of `rotation`, it is first renamed with a timestamp suffix, and `bytes`
+/// are written to a new file.
+pub fn file_append(
+    path: String,
+    bytes: Vec<u8>,
+    rotation: &FileRotationPolicy,
+    ctx: &Context,
+) -> Result<(), String> {
     let mut file_path = match std::env::current_dir() {
         Err(e) => {
             let msg = format!("unable to retrieve current_dir {}", e.to_string());
@@ -203,20 +293,17 @@ pub fn file_append(path: String, bytes: Vec<u8>, ctx: &Context) -> Result<(), St
         Ok(p) => p,
     };
     file_path.push(path);
+    rotate_file_if_due(&file_path, rotation, count_file_lines, ctx)?;
     if !file_path.exists() {
-        match std::fs::File::create(&file_path) {
-            Ok(ref mut file) => {
-                let _ = file.write_all(&bytes);
-            }
-            Err(e) => {
-                let msg = format!(
-                    "unable to create file {}: {}",
-                    file_path.display(),
-                    e.to_string()
-                );
-                ctx.try_log(|logger| slog::warn!(logger, "{}", msg));
-                return Err(msg);
-            }
+        // The occurrence itself is written below, once the file exists.
+        if let Err(e) = std::fs::File::create(&file_path) {
+            let msg = format!(
+                "unable to create file {}: {}",
+                file_path.display(),
+                e.to_string()
+            );
+            ctx.try_log(|logger| slog::warn!(logger, "{}", msg));
+            return Err(msg);
         }
     }
 
@@ -224,7 +311,7 @@ pub fn file_append(path: String, bytes: Vec<u8>, ctx: &Context) -> Result<(), St
         .create(false)
         .write(true)
         .append(true)
-        .open(file_path)
+        .open(&file_path)
     {
         Err(e) => {
             let msg = format!("unable to open file {}", e.to_string());
@@ -249,10 +336,877 @@ pub fn file_append(path: String, bytes: Vec<u8>, ctx: &Context) -> Result<(), St
         eprintln!("Couldn't write to file: {}", e);
         return Err(msg);
     }
+    record_appended_lines(&file_path, count_lines(utf8.as_bytes()) + 1);
+
+    Ok(())
+}
+
+/// Number of lines of the files appended to by the process, keyed by path. The lines of a
+/// file are only counted when it is first appended to, the appended lines being added next,
+/// so that line based rotations don't read the whole file on every append.
+fn get_file_line_counts() -> &'static Mutex<HashMap<PathBuf, u64>> {
+    static LINE_COUNTS: OnceLock<Mutex<HashMap<PathBuf, u64>>> = OnceLock::new();
+    LINE_COUNTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn count_lines(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|byte| **byte == b'\n').count() as u64
+}
+
+fn count_file_lines(file_path: &PathBuf) -> std::io::Result<u64> {
+    let mut content = vec![];
+    fs::File::open(file_path)?.read_to_end(&mut content)?;
+    Ok(count_lines(&content))
+}
+
+fn count_zstd_file_lines(file_path: &PathBuf) -> std::io::Result<u64> {
+    let content = zstd::stream::decode_all(fs::File::open(file_path)?)?;
+    Ok(count_lines(&content))
+}
+
+/// Adds `lines` to the line count of the file at `file_path`, if it is tracked.
+fn record_appended_lines(file_path: &PathBuf, lines: u64) {
+    let mut line_counts = get_file_line_counts()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(line_count) = line_counts.get_mut(file_path) {
+        *line_count += lines;
+    }
+}
+
+/// Renames the file at `file_path` with a timestamp suffix when it reached one of the
+/// thresholds of `rotation`. `count_file_lines` counts the lines of a file not tracked yet.
+fn rotate_file_if_due(
+    file_path: &PathBuf,
+    rotation: &FileRotationPolicy,
+    count_file_lines: fn(&PathBuf) -> std::io::Result<u64>,
+    ctx: &Context,
+) -> Result<(), String> {
+    if !file_path.exists() {
+        get_file_line_counts()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(file_path);
+        return Ok(());
+    }
+    let rotation_due = match is_file_rotation_due(file_path, rotation, count_file_lines) {
+        Ok(rotation_due) => rotation_due,
+        Err(e) => {
+            let msg = format!(
+                "unable to inspect file {}: {}",
+                file_path.display(),
+                e.to_string()
+            );
+            ctx.try_log(|logger| slog::warn!(logger, "{}", msg));
+            return Err(msg);
+        }
+    };
+    if !rotation_due {
+        return Ok(());
+    }
+    let rotated_path = get_rotated_file_path(file_path);
+    if let Err(e) = fs::rename(file_path, &rotated_path) {
+        let msg = format!(
+            "unable to rotate file {}: {}",
+            file_path.display(),
+            e.to_string()
+        );
+        ctx.try_log(|logger| slog::warn!(logger, "{}", msg));
+        return Err(msg);
+    }
+    // The next occurrences are appended to a new file.
+    let mut line_counts = get_file_line_counts()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    match rotation.max_lines {
+        Some(_) => line_counts.insert(file_path.clone(), 0),
+        None => line_counts.remove(file_path),
+    };
+    drop(line_counts);
+    ctx.try_log(|logger| {
+        slog::info!(
+            logger,
+            "Rotated file {} to {}",
+            file_path.display(),
+            rotated_path.display()
+        )
+    });
+    Ok(())
+}
+
+/// Whether the file at `file_path` holds at least as many bytes or lines as allowed by `rotation`.
+fn is_file_rotation_due(
+    file_path: &PathBuf,
+    rotation: &FileRotationPolicy,
+    count_file_lines: fn(&PathBuf) -> std::io::Result<u64>,
+) -> std::io::Result<bool> {
+    if let Some(max_size_bytes) = rotation.max_size_bytes {
+        if fs::metadata(file_path)?.len() >= max_size_bytes {
+            return Ok(true);
+        }
+    }
+    if let Some(max_lines) = rotation.max_lines {
+        let mut line_counts = get_file_line_counts()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let lines = match line_counts.get(file_path) {
+            Some(lines) => *lines,
+            None => {
+                let lines = count_file_lines(file_path)?;
+                line_counts.insert(file_path.clone(), lines);
+                lines
+            }
+        };
+        if lines >= max_lines {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// `file_path` suffixed with the current timestamp, in milliseconds, and with an index when
+/// several files are rotated within the same millisecond.
+fn get_rotated_file_path(file_path: &PathBuf) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or(0);
+    let mut rotated_path = PathBuf::from(format!("{}.{}", file_path.display(), timestamp));
+    let mut index = 1;
+    while rotated_path.exists() {
+        rotated_path = PathBuf::from(format!("{}.{}-{}", file_path.display(), timestamp, index));
+        index += 1;
+    }
+    rotated_path
+}
+
+#[test]
+fn file_append_rotates_files() {
+    let rotations = vec![
+        FileRotationPolicy {
+            max_size_bytes: None,
+            max_lines: Some(2),
+        },
+        // Each occurrence is 14 bytes long once followed by a newline.
+        FileRotationPolicy {
+            max_size_bytes: Some(28),
+            max_lines: None,
+        },
+    ];
+    for (i, rotation) in rotations.iter().enumerate() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("chainhook-rotation-{}-{}", std::process::id(), i));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("occurrences.json");
+
+        let ctx = Context::empty();
+        for block_height in 1..=5 {
+            let occurrence = format!(r#"{{"apply":[{}]}}"#, block_height);
+            let path = path.to_str().unwrap().to_string();
+            file_append(path, occurrence.into_bytes(), rotation, &ctx).unwrap();
+        }
+
+        let mut files = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        files.sort();
+        let contents = files
+            .iter()
+            .map(|file| fs::read_to_string(file).unwrap())
+            .collect::<Vec<_>>();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0], path);
+        let rotated_prefix = format!("{}.", path.display());
+        assert!(files[1..]
+            .iter()
+            .all(|file| file.to_str().unwrap().starts_with(&rotated_prefix)));
+        assert_eq!(contents[0], "{\"apply\":[5]}\n");
+        assert_eq!(contents[1], "{\"apply\":[1]}\n{\"apply\":[2]}\n");
+        assert_eq!(contents[2], "{\"apply\":[3]}\n{\"apply\":[4]}\n");
+    }
+}
+
+/// Append `bytes`, followed by a newline, to the zstd compressed file at `path`.
+/// Each call writes a complete frame, so that the file can be decompressed with the
+/// standard zstd tooling at any time, even if the process is interrupted. The file is
+/// rotated as by `file_append`, its size being the compressed one.
+pub fn zstd_file_append(
+    path: String,
+    bytes: Vec<u8>,
+    compression_level: i32,
+    rotation: &FileRotationPolicy,
+    ctx: &Context,
+) -> Result<(), String> {
+    let mut file_path = match std::env::current_dir() {
+        Err(e) => {
+            let msg = format!("unable to retrieve current_dir {}", e.to_string());
+            ctx.try_log(|logger| slog::warn!(logger, "{}", msg));
+            return Err(msg);
+        }
+        Ok(p) => p,
+    };
+    file_path.push(path);
+    rotate_file_if_due(&file_path, rotation, count_zstd_file_lines, ctx)?;
+
+    let mut line = bytes;
+    line.push(b'\n');
+    let frame = match zstd::stream::encode_all(&line[..], compression_level) {
+        Ok(frame) => frame,
+        Err(e) => {
+            let msg = format!("unable to compress payload {}", e.to_string());
+            ctx.try_log(|logger| slog::warn!(logger, "{}", msg));
+            return Err(msg);
+        }
+    };
+
+    let mut file = match OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&file_path)
+    {
+        Err(e) => {
+            let msg = format!(
+                "unable to open file {}: {}",
+                file_path.display(),
+                e.to_string()
+            );
+            ctx.try_log(|logger| slog::warn!(logger, "{}", msg));
+            return Err(msg);
+        }
+        Ok(p) => p,
+    };
+
+    if let Err(e) = file.write_all(&frame).and_then(|_| file.flush()) {
+        let msg = format!("unable to write file {}", e.to_string());
+        ctx.try_log(|logger| slog::warn!(logger, "{}", msg));
+        return Err(msg);
+    }
+    record_appended_lines(&file_path, count_lines(&line));
+
+    Ok(())
+}
+
+#[test]
+fn zstd_file_append_round_trips_occurrences() {
+    let mut path = std::env::temp_dir();
+    path.push(format!("chainhook-occurrences-{}.zst", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let ctx = Context::empty();
+    let occurrences = vec![r#"{"apply":[1]}"#, r#"{"apply":[2]}"#];
+    for occurrence in occurrences.iter() {
+        let path = path.to_str().unwrap().to_string();
+        zstd_file_append(
+            path,
+            occurrence.as_bytes().to_vec(),
+            19,
+            &FileRotationPolicy::default(),
+            &ctx,
+        )
+        .unwrap();
+    }
 
+    let file = fs::File::open(&path).unwrap();
+    let decompressed = zstd::stream::decode_all(file).unwrap();
+    let _ = fs::remove_file(&path);
+    let lines = String::from_utf8(decompressed).unwrap();
+    assert_eq!(lines.lines().collect::<Vec<_>>(), occurrences);
+}
+
+#[test]
+fn zstd_file_append_rotates_files() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("chainhook-zstd-rotation-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("occurrences.json.zst");
+    // Lines written before the file is first appended to by the process are counted too.
+    fs::write(
+        &path,
+        zstd::stream::encode_all(&b"{\"apply\":[0]}\n"[..], 3).unwrap(),
+    )
+    .unwrap();
+
+    let rotation = FileRotationPolicy {
+        max_size_bytes: None,
+        max_lines: Some(2),
+    };
+    let ctx = Context::empty();
+    for block_height in 1..=4 {
+        let occurrence = format!(r#"{{"apply":[{}]}}"#, block_height);
+        let path = path.to_str().unwrap().to_string();
+        zstd_file_append(path, occurrence.into_bytes(), 3, &rotation, &ctx).unwrap();
+    }
+
+    let mut files = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    files.sort();
+    let contents = files
+        .iter()
+        .map(|file| {
+            let decompressed = zstd::stream::decode_all(fs::File::open(file).unwrap()).unwrap();
+            String::from_utf8(decompressed).unwrap()
+        })
+        .collect::<Vec<_>>();
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[0], path);
+    assert_eq!(contents[0], "{\"apply\":[4]}\n");
+    assert_eq!(contents[1], "{\"apply\":[0]}\n{\"apply\":[1]}\n");
+    assert_eq!(contents[2], "{\"apply\":[2]}\n{\"apply\":[3]}\n");
+}
+
+pub async fn unix_socket_write(
+    path: &str,
+    bytes: &[u8],
+    retry_policy: &RetryPolicy,
+    ctx: &Context,
+) -> Result<(), String> {
+    let mut backoff_delays = retry_policy.get_backoff_delays();
+    loop {
+        let err_msg = match unix_socket_write_line(path, bytes).await {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                let err_msg = format!("unable to write to unix socket {}: {}", path, e);
+                ctx.try_log(|logger| slog::warn!(logger, "{}", err_msg));
+                err_msg
+            }
+        };
+        let Some(backoff_delay) = backoff_delays.next() else {
+            let msg: String = format!(
+                "unable to write to unix socket after several retries. most recent error: {}",
+                err_msg
+            );
+            ctx.try_log(|logger| slog::warn!(logger, "{}", msg));
+            return Err(msg);
+        };
+        tokio::time::sleep(backoff_delay).await;
+    }
+}
+
+#[cfg(unix)]
+async fn unix_socket_write_line(path: &str, bytes: &[u8]) -> Result<(), String> {
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::UnixStream;
+
+    // A fresh connection is established for every occurrence, so that a consumer
+    // restarting (or not listening yet) is picked up on the next attempt.
+    let mut stream = UnixStream::connect(path).await.map_err(|e| e.to_string())?;
+    stream.write_all(bytes).await.map_err(|e| e.to_string())?;
+    stream.write_all(b"\n").await.map_err(|e| e.to_string())?;
+    stream.flush().await.map_err(|e| e.to_string())?;
     Ok(())
 }
 
+#[cfg(not(unix))]
+async fn unix_socket_write_line(_path: &str, _bytes: &[u8]) -> Result<(), String> {
+    Err(format!("unix sockets are not supported on this platform"))
+}
+
+#[test]
+fn retry_policy_caps_the_backoff_delays() {
+    let retry_policy = RetryPolicy {
+        max_retries: 4,
+        initial_backoff_ms: 20_000,
+        backoff_multiplier: 2.0,
+    };
+    let delays = retry_policy
+        .get_backoff_delays()
+        .map(|delay| delay.as_millis())
+        .collect::<Vec<_>>();
+    assert_eq!(delays, vec![20_000, 40_000, 60_000, 60_000]);
+}
+
+#[cfg(all(test, unix))]
+fn unix_socket_test_path(name: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!("chainhook-{}-{}.sock", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_socket_write_delivers_ndjson_lines() {
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
+
+    let path = unix_socket_test_path("write");
+    let listener = UnixListener::bind(&path).unwrap();
+    let reader = std::thread::spawn(move || {
+        let mut lines = vec![];
+        for stream in listener.incoming().take(2) {
+            let mut line = String::new();
+            BufReader::new(stream.unwrap())
+                .read_line(&mut line)
+                .unwrap();
+            lines.push(line);
+        }
+        lines
+    });
+
+    let ctx = Context::empty();
+    let socket_path = path.to_str().unwrap();
+    let retry_policy = RetryPolicy {
+        max_retries: 0,
+        ..RetryPolicy::default()
+    };
+    unix_socket_write(socket_path, br#"{"block":1}"#, &retry_policy, &ctx)
+        .await
+        .unwrap();
+    unix_socket_write(socket_path, br#"{"block":2}"#, &retry_policy, &ctx)
+        .await
+        .unwrap();
+
+    let lines = reader.join().unwrap();
+    assert_eq!(lines, vec!["{\"block\":1}\n", "{\"block\":2}\n"]);
+    let _ = fs::remove_file(&path);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_socket_write_retries_until_socket_is_available() {
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
+
+    let path = unix_socket_test_path("retry");
+    let listener_path = path.clone();
+    let reader = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(500));
+        let listener = UnixListener::bind(&listener_path).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        line
+    });
+
+    let ctx = Context::empty();
+    let retry_policy = RetryPolicy {
+        max_retries: 4,
+        initial_backoff_ms: 1000,
+        backoff_multiplier: 1.0,
+    };
+    unix_socket_write(path.to_str().unwrap(), b"{}", &retry_policy, &ctx)
+        .await
+        .unwrap();
+    assert_eq!(reader.join().unwrap(), "{}\n");
+
+    let missing = unix_socket_test_path("missing");
+    let retry_policy = RetryPolicy {
+        max_retries: 0,
+        ..RetryPolicy::default()
+    };
+    assert!(
+        unix_socket_write(missing.to_str().unwrap(), b"{}", &retry_policy, &ctx)
+            .await
+            .is_err()
+    );
+    let _ = fs::remove_file(&path);
+}
+
+pub struct OccurrenceBatcher<T = JsonValue> {
+    policy: OccurrenceBatchingPolicy,
+    window_started_at: Option<Instant>,
+    pending: Vec<T>,
+}
+
+impl<T> OccurrenceBatcher<T> {
+    pub fn new(policy: OccurrenceBatchingPolicy) -> OccurrenceBatcher<T> {
+        OccurrenceBatcher {
+            policy,
+            window_started_at: None,
+            pending: vec![],
+        }
+    }
+
+    /// Queues an occurrence, and returns the pending batch if it is full or if its window elapsed.
+    pub fn push(&mut self, occurrence: T, now: Instant) -> Option<Vec<T>> {
+        if self.pending.is_empty() {
+            self.window_started_at = Some(now);
+        }
+        self.pending.push(occurrence);
+        if self.pending.len() >= self.policy.max_batch_size {
+            return self.flush();
+        }
+        self.poll(now)
+    }
+
+    /// Returns the pending batch if its window elapsed.
+    pub fn poll(&mut self, now: Instant) -> Option<Vec<T>> {
+        let started_at = self.window_started_at?;
+        if now.duration_since(started_at) >= Duration::from_secs(self.policy.window_secs) {
+            return self.flush();
+        }
+        None
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Instant at which the window of the pending batch elapses, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.window_started_at
+            .map(|started_at| started_at + Duration::from_secs(self.policy.window_secs))
+    }
+
+    /// Returns the pending batch, regardless of its window.
+    pub fn flush(&mut self) -> Option<Vec<T>> {
+        self.window_started_at = None;
+        if self.pending.is_empty() {
+            return None;
+        }
+        Some(self.pending.drain(..).collect())
+    }
+}
+
+/// Occurrences of the HTTP hooks batching their occurrences, pending delivery. Hooks are
+/// compared on their whole configuration: the occurrences of predicates posting to the same
+/// endpoint with the same settings are delivered together.
+pub struct HttpOccurrenceBatches<T = JsonValue> {
+    batches: Vec<(HttpHook, OccurrenceBatcher<T>)>,
+}
+
+impl<T> HttpOccurrenceBatches<T> {
+    pub fn new() -> HttpOccurrenceBatches<T> {
+        HttpOccurrenceBatches { batches: vec![] }
+    }
+
+    /// Queues an occurrence of `http`, and returns the pending batch of the hook if it is full
+    /// or if its window elapsed. Occurrences of hooks not batching are returned right away.
+    pub fn push(
+        &mut self,
+        http: &HttpHook,
+        occurrence: T,
+        now: Instant,
+    ) -> Option<(HttpHook, Vec<T>)> {
+        let Some(ref policy) = http.batching else {
+            return Some((http.clone(), vec![occurrence]));
+        };
+        let index = match self
+            .batches
+            .iter()
+            .position(|(batch_http, _)| batch_http == http)
+        {
+            Some(index) => index,
+            None => {
+                self.batches
+                    .push((http.clone(), OccurrenceBatcher::new(policy.clone())));
+                self.batches.len() - 1
+            }
+        };
+        let (http, batcher) = &mut self.batches[index];
+        batcher
+            .push(occurrence, now)
+            .map(|occurrences| (http.clone(), occurrences))
+    }
+
+    /// Returns the pending batches whose window elapsed.
+    pub fn poll(&mut self, now: Instant) -> Vec<(HttpHook, Vec<T>)> {
+        self.batches
+            .iter_mut()
+            .filter_map(|(http, batcher)| {
+                batcher
+                    .poll(now)
+                    .map(|occurrences| (http.clone(), occurrences))
+            })
+            .collect()
+    }
+
+    /// Returns all the pending batches, regardless of their window.
+    pub fn flush(&mut self) -> Vec<(HttpHook, Vec<T>)> {
+        self.batches
+            .drain(..)
+            .filter_map(|(http, mut batcher)| {
+                batcher.flush().map(|occurrences| (http, occurrences))
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.batches.iter().all(|(_, batcher)| batcher.is_empty())
+    }
+
+    /// Instant at which the window of the first pending batch elapses, if any.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.batches
+            .iter()
+            .filter_map(|(_, batcher)| batcher.deadline())
+            .min()
+    }
+}
+
+pub fn build_batched_http_request(
+    http: &HttpHook,
+    payloads: Vec<JsonValue>,
+) -> Result<RequestBuilder, String> {
+    let body = serde_json::to_vec(&json!({ "occurrences": payloads }))
+        .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?;
+    build_http_request(http, body)
+}
+
+/// Posts a batch of occurrences in a single request, retried as configured by `http`.
+pub async fn send_http_occurrences_batch(
+    http: &HttpHook,
+    payloads: Vec<JsonValue>,
+    ctx: &Context,
+) -> Result<(), String> {
+    ctx.try_log(|logger| {
+        slog::info!(
+            logger,
+            "Delivering batch of {} occurrences to {}",
+            payloads.len(),
+            http.url
+        )
+    });
+    let request = build_batched_http_request(http, payloads)?;
+    send_request_with_backoff(request, &http.get_retry_policy(), ctx).await
+}
+
+/// Builds the request delivering a single occurrence to the hook: as a batch of one occurrence
+/// when the hook batches its occurrences, rendered with the hook's `template` when one is set.
+pub fn build_http_occurrence_request(
+    http: &HttpHook,
+    payload: JsonValue,
+) -> Result<RequestBuilder, String> {
+    if http.batching.is_some() {
+        return build_batched_http_request(http, vec![payload]);
+    }
+    let body = match http.template {
+        Some(ref template) => serde_json::to_vec(&HttpHook::render_template(template, &payload))
+            .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?,
+        None => serde_json::to_vec(&payload)
+            .map_err(|e| format!("unable to serialize payload {}", e.to_string()))?,
+    };
+    build_http_request(http, body)
+}
+
+pub const CHAINHOOK_SIGNATURE_HEADER: &str = "X-Chainhook-Signature";
+
+/// Builds the POST request delivering `body` to the hook, gzipped when the hook compresses
+/// bodies that large, and signed with the hook's `signing_secret` when one is set. The
+/// signature covers the bytes actually sent, compressed or not.
+pub fn build_http_request(http: &HttpHook, body: Vec<u8>) -> Result<RequestBuilder, String> {
+    let client = Client::builder()
+        .build()
+        .map_err(|e| format!("unable to build http client: {}", e.to_string()))?;
+    let mut request = client
+        .request(Method::POST, &http.url)
+        .header("Content-Type", "application/json")
+        .header("Authorization", http.authorization_header.clone());
+    let body = if http.should_gzip(body.len()) {
+        request = request.header("Content-Encoding", "gzip");
+        gzip_compress(&body)?
+    } else {
+        body
+    };
+    if let Some(ref secret) = http.signing_secret {
+        request = request.header(CHAINHOOK_SIGNATURE_HEADER, sign_payload(secret, &body));
+    }
+    Ok(request.body(body))
+}
+
+pub fn gzip_compress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder
+        .write_all(bytes)
+        .map_err(|e| format!("unable to gzip body: {}", e.to_string()))?;
+    encoder
+        .finish()
+        .map_err(|e| format!("unable to gzip body: {}", e.to_string()))
+}
+
+/// Hex encoded HMAC-SHA256 of `body`, keyed with `secret`.
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut engine = HmacEngine::<sha256::Hash>::new(secret.as_bytes());
+    engine.input(body);
+    hex::encode(Hmac::<sha256::Hash>::from_engine(engine).to_byte_array())
+}
+
+#[test]
+fn test_build_http_request_signs_body() {
+    let http = HttpHook {
+        url: "http://localhost:20455/chainhook".to_string(),
+        authorization_header: "Bearer token".to_string(),
+        batching: None,
+        max_retries: None,
+        initial_backoff_ms: None,
+        backoff_multiplier: None,
+        signing_secret: Some("Jefe".to_string()),
+        template: None,
+        gzip: None,
+        gzip_threshold_bytes: None,
+        include_raw_tx: None,
+    };
+    let body = b"what do ya want for nothing?".to_vec();
+    let request = build_http_request(&http, body.clone())
+        .unwrap()
+        .build()
+        .unwrap();
+    // RFC 4231, test case 2
+    assert_eq!(
+        request.headers()[CHAINHOOK_SIGNATURE_HEADER],
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    assert_eq!(request.body().unwrap().as_bytes(), Some(&body[..]));
+    assert!(!format!("{:?}", http).contains("Jefe"));
+
+    let unsigned = HttpHook {
+        signing_secret: None,
+        template: None,
+        ..http
+    };
+    let request = build_http_request(&unsigned, body)
+        .unwrap()
+        .build()
+        .unwrap();
+    assert!(request.headers().get(CHAINHOOK_SIGNATURE_HEADER).is_none());
+}
+
+#[test]
+fn test_build_http_request_gzips_large_bodies() {
+    let http = HttpHook {
+        url: "http://localhost:20455/chainhook".to_string(),
+        authorization_header: "Bearer token".to_string(),
+        batching: None,
+        max_retries: None,
+        initial_backoff_ms: None,
+        backoff_multiplier: None,
+        signing_secret: Some("secret".to_string()),
+        template: None,
+        gzip: Some(true),
+        gzip_threshold_bytes: Some(16),
+        include_raw_tx: None,
+    };
+    let transactions = vec!["0xfaaa"; 8];
+    let body = serde_json::to_vec(&json!({ "apply": [{ "transactions": transactions }] })).unwrap();
+    let request = build_http_request(&http, body.clone())
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(request.headers()["Content-Encoding"], "gzip");
+    // The signature covers the gzipped body, as sent.
+    let sent = request.body().unwrap().as_bytes().unwrap();
+    assert_eq!(
+        request.headers()[CHAINHOOK_SIGNATURE_HEADER],
+        sign_payload("secret", sent).as_str()
+    );
+    let mut decoder = flate2::read::GzDecoder::new(sent);
+    let mut decompressed = vec![];
+    decoder.read_to_end(&mut decompressed).unwrap();
+    assert_eq!(decompressed, body);
+
+    // Bodies up to the threshold, or of hooks not compressing, are sent as is.
+    for http in [
+        HttpHook {
+            gzip_threshold_bytes: Some(body.len()),
+            ..http.clone()
+        },
+        HttpHook { gzip: None, ..http },
+    ] {
+        let request = build_http_request(&http, body.clone())
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(request.headers().get("Content-Encoding").is_none());
+        assert_eq!(request.body().unwrap().as_bytes(), Some(&body[..]));
+    }
+}
+
+#[test]
+fn test_occurrence_batcher_delivers_window_together() {
+    let mut batcher = OccurrenceBatcher::new(OccurrenceBatchingPolicy {
+        window_secs: 10,
+        max_batch_size: 100,
+    });
+    let start = Instant::now();
+    assert_eq!(batcher.push(json!(1), start), None);
+    assert_eq!(batcher.push(json!(2), start + Duration::from_secs(4)), None);
+    assert_eq!(batcher.poll(start + Duration::from_secs(9)), None);
+    assert_eq!(
+        batcher.push(json!(3), start + Duration::from_secs(10)),
+        Some(vec![json!(1), json!(2), json!(3)])
+    );
+    assert_eq!(
+        batcher.push(json!(4), start + Duration::from_secs(11)),
+        None
+    );
+    assert_eq!(
+        batcher.poll(start + Duration::from_secs(21)),
+        Some(vec![json!(4)])
+    );
+    assert_eq!(batcher.flush(), None);
+}
+
+#[test]
+fn test_occurrence_batcher_flushes_full_batches() {
+    let mut batcher = OccurrenceBatcher::new(OccurrenceBatchingPolicy {
+        window_secs: 10,
+        max_batch_size: 2,
+    });
+    let start = Instant::now();
+    assert_eq!(batcher.push(json!(1), start), None);
+    assert_eq!(
+        batcher.push(json!(2), start),
+        Some(vec![json!(1), json!(2)])
+    );
+    assert_eq!(batcher.push(json!(3), start), None);
+    assert_eq!(batcher.flush(), Some(vec![json!(3)]));
+}
+
+#[test]
+fn test_http_occurrence_batches_group_identical_hooks() {
+    let http = HttpHook {
+        url: "http://localhost:20455/chainhook".to_string(),
+        authorization_header: "Bearer token".to_string(),
+        batching: Some(OccurrenceBatchingPolicy {
+            window_secs: 10,
+            max_batch_size: 2,
+        }),
+        max_retries: None,
+        initial_backoff_ms: None,
+        backoff_multiplier: None,
+        signing_secret: None,
+        template: None,
+        gzip: None,
+        gzip_threshold_bytes: None,
+        include_raw_tx: None,
+    };
+    // Same endpoint, but signed: delivered separately.
+    let signed_http = HttpHook {
+        signing_secret: Some("secret".to_string()),
+        ..http.clone()
+    };
+    let unbatched_http = HttpHook {
+        batching: None,
+        ..http.clone()
+    };
+    let mut batches = HttpOccurrenceBatches::new();
+    let start = Instant::now();
+    assert_eq!(batches.push(&http, json!(1), start), None);
+    assert_eq!(batches.push(&signed_http, json!(2), start), None);
+    assert_eq!(
+        batches.push(&unbatched_http, json!(3), start),
+        Some((unbatched_http, vec![json!(3)]))
+    );
+    assert_eq!(
+        batches.push(&http.clone(), json!(4), start),
+        Some((http, vec![json!(1), json!(4)]))
+    );
+    assert!(!batches.is_empty());
+    assert_eq!(
+        batches.poll(start + Duration::from_secs(10)),
+        vec![(signed_http, vec![json!(2)])]
+    );
+    assert!(batches.is_empty());
+    assert!(batches.flush().is_empty());
+}
+
 #[derive(Debug)]
 pub enum BlockHeightsError {
     ExceedsMaxEntries(u64, u64),
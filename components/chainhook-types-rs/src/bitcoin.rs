@@ -41,6 +41,9 @@ pub struct OutPoint {
     pub value: u64,
     /// The script which must be satisfied for the output to be spent.
     pub block_height: u64,
+    /// The script_pubkey of the referenced output, when provided by the node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script_pubkey: Option<String>,
 }
 
 impl TxOut {
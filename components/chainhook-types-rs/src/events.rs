@@ -3,6 +3,9 @@ pub struct STXTransferEventData {
     pub sender: String,
     pub recipient: String,
     pub amount: String,
+    /// Hex encoded memo attached to the transfer, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
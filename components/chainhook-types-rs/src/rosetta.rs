@@ -99,6 +99,9 @@ pub struct StacksMicroblockData {
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct StacksMicroblockMetadata {
     pub anchor_block_identifier: BlockIdentifier,
+    /// Network of the node the microblock was received from, unknown for microblocks indexed before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<StacksNetwork>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -114,6 +117,9 @@ pub struct StacksBlockMetadata {
     pub pox_cycle_length: u32,
     pub confirm_microblock_identifier: Option<BlockIdentifier>,
     pub stacks_block_hash: String,
+    /// Network of the node the block was received from, unknown for blocks indexed before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<StacksNetwork>,
 }
 
 /// BitcoinBlock contain an array of Transactions that occurred at a particular
@@ -307,12 +313,21 @@ pub struct BitcoinTransactionData {
 /// Extra data for Transaction
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct BitcoinTransactionMetadata {
+    /// Version of the transaction (e.g. 2 for BIP-68 relative timelocks, 3 for TRUC).
+    #[serde(default)]
+    pub version: u32,
     pub inputs: Vec<TxIn>,
     pub outputs: Vec<TxOut>,
     pub stacks_operations: Vec<StacksBaseChainOperation>,
     pub ordinal_operations: Vec<OrdinalOperation>,
     pub proof: Option<String>,
     pub fee: u64,
+    /// Hex encoded raw transaction, as found in the block retrieved from bitcoind.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_tx: Option<String>,
+    /// Virtual size of the transaction, in vbytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vsize: Option<u64>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]